@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::detect::Format;
+use crate::error::Result;
+
+/// A file to convert, named on the command line or found inside a directory
+/// argument.
+#[derive(Debug, PartialEq)]
+pub struct Input {
+    pub path: PathBuf,
+    /// Path relative to the directory argument it was found in, or just the
+    /// file name for files given directly. Used to lay out output files.
+    pub relative: PathBuf,
+}
+
+/// Expand directory arguments into the files beneath them, in sorted order.
+/// Hidden entries and files with an unrecognised extension are skipped, and
+/// symbolic links to directories are not followed; files given directly are
+/// always kept.
+pub fn collect_inputs(paths: &[PathBuf]) -> Result<Vec<Input>> {
+    let mut inputs = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk(path, path, &mut inputs)?;
+        } else {
+            let relative = path
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| path.clone());
+            inputs.push(Input {
+                path: path.clone(),
+                relative,
+            });
+        }
+    }
+    Ok(inputs)
+}
+
+fn walk(root: &Path, dir: &Path, inputs: &mut Vec<Input>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(root, &path, inputs)?;
+        } else if file_type.is_symlink() && path.is_dir() {
+            // A link back up the tree would otherwise be walked forever
+            continue;
+        } else if Format::detect(Some(&name), &[]).is_some() {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            inputs.push(Input { path, relative });
+        }
+    }
+    Ok(())
+}
+
+/// Remembers the content hash of each input in a batch so byte-identical
+/// copies can be skipped instead of converted again.
+#[derive(Debug, Default)]
+pub struct Duplicates {
+    seen: HashMap<[u8; 32], PathBuf>,
+}
+
+impl Duplicates {
+    /// Record the contents of `path`, returning the input first seen with
+    /// identical bytes if there was one.
+    pub fn check(&mut self, path: &Path, bytes: &[u8]) -> Option<&Path> {
+        let hash: [u8; 32] = Sha256::digest(bytes).into();
+        let first = self.seen.entry(hash).or_insert_with(|| path.to_path_buf());
+        (first != path).then_some(first.as_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_collect_inputs_walks_directories() {
+        let dir = std::env::temp_dir().join(format!("mq-conv-batch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        for name in ["b.csv", "a.json", "notes.unknown", "sub/c.pdf", ".git/d.json"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        let single = dir.join("notes.unknown");
+
+        let inputs = collect_inputs(&[dir.clone(), single.clone()]).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            inputs,
+            vec![
+                Input {
+                    path: dir.join("a.json"),
+                    relative: "a.json".into(),
+                },
+                Input {
+                    path: dir.join("b.csv"),
+                    relative: "b.csv".into(),
+                },
+                Input {
+                    path: dir.join("sub/c.pdf"),
+                    relative: Path::new("sub").join("c.pdf"),
+                },
+                Input {
+                    path: single,
+                    relative: "notes.unknown".into(),
+                },
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[rstest]
+    fn test_collect_inputs_skips_directory_links() {
+        let dir = std::env::temp_dir().join(format!("mq-conv-batch-loop-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/a.json"), b"x").unwrap();
+        std::fs::write(dir.join("b.csv"), b"x").unwrap();
+        std::os::unix::fs::symlink("..", dir.join("sub/back")).unwrap();
+        std::os::unix::fs::symlink("../b.csv", dir.join("sub/linked.csv")).unwrap();
+
+        let inputs = collect_inputs(std::slice::from_ref(&dir)).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let relative: Vec<_> = inputs.into_iter().map(|input| input.relative).collect();
+        assert_eq!(
+            relative,
+            vec![
+                PathBuf::from("b.csv"),
+                Path::new("sub").join("a.json"),
+                Path::new("sub").join("linked.csv"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_duplicates() {
+        let mut duplicates = Duplicates::default();
+        assert_eq!(duplicates.check(Path::new("a.pdf"), b"same"), None);
+        assert_eq!(duplicates.check(Path::new("b.pdf"), b"other"), None);
+        assert_eq!(
+            duplicates.check(Path::new("c/a.pdf"), b"same"),
+            Some(Path::new("a.pdf"))
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use scraper::{Html, Selector};
+use ureq::ResponseExt;
+use url::Url;
+
+use crate::error::{Error, Result};
+use crate::warnings;
+
+/// Default for `--max-pages`.
+pub const DEFAULT_MAX_PAGES: usize = 20;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    /// Stop after this many HTML pages have been fetched.
+    pub max_pages: usize,
+    /// Only follow links to the scheme, host and port of the start URL.
+    pub same_origin: bool,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_pages: DEFAULT_MAX_PAGES,
+            same_origin: false,
+        }
+    }
+}
+
+/// A fetched HTML page, under the URL it was served from after redirects.
+#[derive(Debug)]
+pub struct Page {
+    pub url: Url,
+    pub html: Vec<u8>,
+}
+
+impl Page {
+    /// Where to write this page in a snapshot directory: the host, then the
+    /// URL path with its extension replaced by `.md`. Directory URLs become
+    /// `index.md`, and a query string is folded into the file name.
+    pub fn file_path(&self) -> PathBuf {
+        let mut path = PathBuf::from(sanitize(self.url.host_str().unwrap_or("site")));
+        let mut segments: Vec<&str> = self
+            .url
+            .path_segments()
+            .map(|s| s.collect())
+            .unwrap_or_default();
+        let last = segments.pop().filter(|s| !s.is_empty()).unwrap_or("index");
+        for segment in segments.into_iter().filter(|s| !s.is_empty()) {
+            path.push(sanitize(segment));
+        }
+
+        let stem = match last.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem,
+            _ => last,
+        };
+        let name = match self.url.query().filter(|q| !q.is_empty()) {
+            Some(query) => format!("{}-{}", sanitize(stem), sanitize(query)),
+            None => sanitize(stem),
+        };
+        path.push(format!("{name}.md"));
+        path
+    }
+}
+
+/// Fetch `start` and the pages it links to, breadth-first, until
+/// `options.max_pages` HTML pages have been fetched. Links to other
+/// resources, such as images or PDFs, are skipped, as are pages that fail to
+/// load after the first.
+pub fn crawl(start: &str, options: &CrawlOptions) -> Result<Vec<Page>> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .build()
+        .into();
+    crawl_with(start, options, |url| fetch(&agent, url))
+}
+
+fn crawl_with(
+    start: &str,
+    options: &CrawlOptions,
+    mut fetch: impl FnMut(&Url) -> std::result::Result<Option<Page>, String>,
+) -> Result<Vec<Page>> {
+    let start = Url::parse(start)
+        .ok()
+        .filter(is_web_url)
+        .ok_or_else(|| error(format!("{start} is not an http(s) URL")))?;
+
+    let mut queue = VecDeque::from([without_fragment(start.clone())]);
+    let mut seen: HashSet<Url> = queue.iter().cloned().collect();
+    let mut pages = Vec::new();
+    while let Some(url) = queue.pop_front() {
+        if pages.len() >= options.max_pages {
+            break;
+        }
+        let page = match fetch(&url) {
+            Ok(Some(page)) => page,
+            Ok(None) if pages.is_empty() => {
+                return Err(error(format!("{url} is not an HTML page")));
+            }
+            Ok(None) => continue,
+            // The start page has to load; anything later is only skipped
+            Err(message) if pages.is_empty() => return Err(error(message)),
+            Err(message) => {
+                warnings::warn(format!("Skipped {url}: {message}"));
+                continue;
+            }
+        };
+        // A redirect may land on a page that was already fetched
+        let page_url = without_fragment(page.url.clone());
+        if page_url != url && !seen.insert(page_url) {
+            continue;
+        }
+
+        for link in links(&page) {
+            if options.same_origin && link.origin() != start.origin() {
+                continue;
+            }
+            if seen.insert(link.clone()) {
+                queue.push_back(link);
+            }
+        }
+        pages.push(page);
+    }
+    Ok(pages)
+}
+
+/// Fetch `url`, returning `None` for responses that are not HTML.
+fn fetch(agent: &ureq::Agent, url: &Url) -> std::result::Result<Option<Page>, String> {
+    let mut response = agent
+        .get(url.as_str())
+        .header("Accept", "text/html,application/xhtml+xml")
+        .call()
+        .map_err(|e| e.to_string())?;
+    let is_html = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|v| v.contains("html"));
+    if !is_html {
+        return Ok(None);
+    }
+    let final_url = Url::parse(&response.get_uri().to_string()).unwrap_or_else(|_| url.clone());
+    let html = response
+        .body_mut()
+        .read_to_vec()
+        .map_err(|e| e.to_string())?;
+    Ok(Some(Page {
+        url: final_url,
+        html,
+    }))
+}
+
+/// The http(s) targets of the page's `<a href>` links, resolved against its
+/// `<base href>` when it has one.
+fn links(page: &Page) -> Vec<Url> {
+    let doc = Html::parse_document(&String::from_utf8_lossy(&page.html));
+    let base_selector = Selector::parse("base[href]").expect("valid selector");
+    let base = doc
+        .select(&base_selector)
+        .next()
+        .and_then(|base| page.url.join(base.value().attr("href")?).ok())
+        .unwrap_or_else(|| page.url.clone());
+
+    let selector = Selector::parse("a[href]").expect("valid selector");
+    doc.select(&selector)
+        .filter_map(|a| base.join(a.value().attr("href")?.trim()).ok())
+        .filter(is_web_url)
+        .map(without_fragment)
+        .collect()
+}
+
+fn is_web_url(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+}
+
+fn without_fragment(mut url: Url) -> Url {
+    url.set_fragment(None);
+    url
+}
+
+/// Keep a URL component usable as a file name on every platform.
+fn sanitize(component: &str) -> String {
+    component
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn error(message: String) -> Error {
+    Error::Conversion {
+        format: "html",
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::collections::HashMap;
+
+    fn site() -> HashMap<&'static str, &'static str> {
+        HashMap::from([
+            (
+                "https://example.com/",
+                r##"<a href="/docs/">Docs</a> <a href="about.html#team">About</a>
+                    <a href="https://other.org/">Other</a> <a href="mailto:a@example.com">Mail</a>
+                    <a href="/logo.png">Logo</a>"##,
+            ),
+            (
+                "https://example.com/docs/",
+                r#"<a href="intro.html">Intro</a> <a href="/">Home</a>"#,
+            ),
+            ("https://example.com/about.html", "<p>About</p>"),
+            ("https://example.com/docs/intro.html", "<p>Intro</p>"),
+            ("https://other.org/", "<p>Other</p>"),
+        ])
+    }
+
+    fn crawl_site(options: CrawlOptions) -> Vec<String> {
+        let site = site();
+        let pages = crawl_with("https://example.com/", &options, |url| {
+            if url.path().ends_with(".png") {
+                return Ok(None);
+            }
+            site.get(url.as_str())
+                .map(|html| {
+                    Some(Page {
+                        url: url.clone(),
+                        html: html.as_bytes().to_vec(),
+                    })
+                })
+                .ok_or_else(|| "404 Not Found".to_string())
+        })
+        .unwrap();
+        pages.into_iter().map(|p| p.url.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case::all(
+        CrawlOptions { max_pages: 10, same_origin: false },
+        &[
+            "https://example.com/",
+            "https://example.com/docs/",
+            "https://example.com/about.html",
+            "https://other.org/",
+            "https://example.com/docs/intro.html",
+        ]
+    )]
+    #[case::same_origin(
+        CrawlOptions { max_pages: 10, same_origin: true },
+        &[
+            "https://example.com/",
+            "https://example.com/docs/",
+            "https://example.com/about.html",
+            "https://example.com/docs/intro.html",
+        ]
+    )]
+    #[case::max_pages(
+        CrawlOptions { max_pages: 2, same_origin: true },
+        &["https://example.com/", "https://example.com/docs/"]
+    )]
+    fn test_crawl(#[case] options: CrawlOptions, #[case] expected: &[&str]) {
+        assert_eq!(crawl_site(options), expected);
+    }
+
+    #[rstest]
+    fn test_start_page_must_load() {
+        let result = crawl_with("https://example.com/", &CrawlOptions::default(), |_| {
+            Err("connection refused".to_string())
+        });
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            error("connection refused".to_string()).to_string()
+        );
+    }
+
+    #[rstest]
+    #[case("https://example.com/", "example.com/index.md")]
+    #[case("https://example.com/docs/", "example.com/docs/index.md")]
+    #[case("https://example.com/docs/intro.html", "example.com/docs/intro.md")]
+    #[case("https://example.com/list?page=2", "example.com/list-page-2.md")]
+    #[case("http://localhost:8080/a%20b/c", "localhost/a-20b/c.md")]
+    fn test_file_path(#[case] url: &str, #[case] expected: &str) {
+        let page = Page {
+            url: Url::parse(url).unwrap(),
+            html: Vec::new(),
+        };
+        assert_eq!(page.file_path(), PathBuf::from(expected));
+    }
+}
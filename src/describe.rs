@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use base64::Engine;
+use serde_json::{Value, json};
+
+use crate::error::{Error, Result};
+use crate::options::DescribeOptions;
+
+/// Environment variable holding the API key sent to the vision endpoint.
+pub const API_KEY_ENV: &str = "MQ_CONV_DESCRIBE_API_KEY";
+
+/// Default model requested from the vision endpoint.
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+const PROMPT: &str = "Write concise alt text for this image in one or two sentences. \
+     Describe what it shows and transcribe any important text. Reply with the description only.";
+
+/// Ask the configured vision endpoint to describe an image, returning `None`
+/// when no endpoint is configured.
+pub fn describe_image(
+    options: &DescribeOptions,
+    image: &[u8],
+    mime: &str,
+    format: &'static str,
+) -> Result<Option<String>> {
+    let Some(endpoint) = &options.endpoint else {
+        return Ok(None);
+    };
+
+    let model = options.model.as_deref().unwrap_or(DEFAULT_MODEL);
+    let body = request_body(model, image, mime);
+    let description = send(endpoint, &body).map_err(|message| Error::Conversion {
+        format,
+        message: format!("Image description failed: {message}"),
+    })?;
+    Ok(Some(description))
+}
+
+/// An OpenAI-compatible chat completion request with the image inlined as a
+/// data URL.
+fn request_body(model: &str, image: &[u8], mime: &str) -> Value {
+    let data = base64::engine::general_purpose::STANDARD.encode(image);
+    json!({
+        "model": model,
+        "max_tokens": 300,
+        "messages": [{
+            "role": "user",
+            "content": [
+                {"type": "text", "text": PROMPT},
+                {"type": "image_url", "image_url": {"url": format!("data:{mime};base64,{data}")}},
+            ],
+        }],
+    })
+}
+
+fn send(endpoint: &str, body: &Value) -> std::result::Result<String, String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .into();
+
+    let request = agent.post(endpoint).content_type("application/json");
+    let request = match std::env::var(API_KEY_ENV) {
+        Ok(key) => request.header("Authorization", format!("Bearer {key}")),
+        Err(_) => request,
+    };
+    let mut response = request
+        .send(body.to_string().as_bytes())
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let text = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{endpoint} returned {status}: {}", text.trim()));
+    }
+
+    let json: Value = serde_json::from_str(&text).map_err(|e| format!("Invalid response: {e}"))?;
+    parse_response(&json).ok_or_else(|| "Response contained no description".to_string())
+}
+
+/// Extract `choices[0].message.content`, which may be a string or a list of
+/// text parts, collapsed onto a single line.
+fn parse_response(json: &Value) -> Option<String> {
+    let content = &json["choices"][0]["message"]["content"];
+    let text = match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p["text"].as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => return None,
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_request_body_inlines_image() {
+        let body = request_body("vision", b"abc", "image/png");
+        assert_eq!(body["model"], "vision");
+        assert_eq!(
+            body["messages"][0]["content"][1]["image_url"]["url"],
+            "data:image/png;base64,YWJj"
+        );
+    }
+
+    #[rstest]
+    #[case::string(
+        r#"{"choices": [{"message": {"content": "A red\n bicycle. "}}]}"#,
+        Some("A red bicycle.")
+    )]
+    #[case::parts(
+        r#"{"choices": [{"message": {"content": [{"type": "text", "text": "A cat"}]}}]}"#,
+        Some("A cat")
+    )]
+    #[case::empty(r#"{"choices": [{"message": {"content": "  "}}]}"#, None)]
+    #[case::missing(r#"{"error": "nope"}"#, None)]
+    fn test_parse_response(#[case] json: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            parse_response(&serde_json::from_str(json).unwrap()).as_deref(),
+            expected
+        );
+    }
+}
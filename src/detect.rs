@@ -6,6 +6,7 @@ pub enum Format {
     Pdf,
     PowerPoint,
     Word,
+    Doc,
     Image,
     Zip,
     Epub,
@@ -51,6 +52,7 @@ impl Format {
             "pdf" => Some(Self::Pdf),
             "pptx" => Some(Self::PowerPoint),
             "docx" => Some(Self::Word),
+            "doc" => Some(Self::Doc),
             "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "bmp" | "tiff" | "tif" => {
                 Some(Self::Image)
             }
@@ -59,10 +61,10 @@ impl Format {
             "mp3" | "wav" | "flac" | "ogg" | "m4a" | "aac" | "wma" => Some(Self::Audio),
             "csv" | "tsv" => Some(Self::Csv),
             "html" | "htm" => Some(Self::Html),
-            "json" => Some(Self::Json),
+            "json" | "geojson" => Some(Self::Json),
             "yaml" | "yml" => Some(Self::Yaml),
             "toml" => Some(Self::Toml),
-            "xml" => Some(Self::Xml),
+            "xml" | "xsd" | "dtd" => Some(Self::Xml),
             "sqlite" | "sqlite3" | "db" => Some(Self::Sqlite),
             "tar" => Some(Self::Tar),
             "tgz" => Some(Self::Tar),
@@ -145,6 +147,11 @@ impl Format {
             return Some(Self::Sqlite);
         }
 
+        // OLE compound file: legacy Word (.doc) or Excel (.xls)
+        if bytes.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) {
+            return Self::detect_compound_file_content(bytes);
+        }
+
         // Gzip (tar.gz): \x1F\x8B
         if bytes.starts_with(&[0x1F, 0x8B]) {
             return Some(Self::Tar);
@@ -173,6 +180,22 @@ impl Format {
         None
     }
 
+    /// Tell legacy Office files apart by their main stream, whose name is
+    /// stored in UTF-16 in the compound file's directory.
+    fn detect_compound_file_content(bytes: &[u8]) -> Option<Self> {
+        let contains = |name: &str| {
+            let needle: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            bytes.windows(needle.len()).any(|window| window == needle)
+        };
+        if contains("WordDocument") {
+            Some(Self::Doc)
+        } else if contains("Workbook") {
+            Some(Self::Excel)
+        } else {
+            None
+        }
+    }
+
     #[cfg(any(
         feature = "zip",
         feature = "word",
@@ -213,6 +236,7 @@ impl std::fmt::Display for Format {
             Self::Pdf => write!(f, "pdf"),
             Self::PowerPoint => write!(f, "powerpoint"),
             Self::Word => write!(f, "word"),
+            Self::Doc => write!(f, "doc"),
             Self::Image => write!(f, "image"),
             Self::Zip => write!(f, "zip"),
             Self::Epub => write!(f, "epub"),
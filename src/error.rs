@@ -21,4 +21,162 @@ pub enum Error {
 
     #[error("Feature not enabled: {0}. Recompile with --features {0}")]
     FeatureDisabled(String),
+
+    /// A part the format requires is missing from its container, such as a
+    /// slide listed in a presentation but absent from the zip.
+    #[error("Conversion error ({format}): Entry not found: {entry}")]
+    MissingEntry { format: &'static str, entry: String },
+
+    /// Malformed input, located by container entry and byte offset where
+    /// they are known.
+    #[error("Conversion error ({format}): {}{message}", location(.entry, .offset))]
+    Parse {
+        format: &'static str,
+        entry: Option<String>,
+        offset: Option<u64>,
+        message: String,
+    },
+}
+
+/// Prefix locating a parse error, such as `word/document.xml at byte 120: `.
+fn location(entry: &Option<String>, offset: &Option<u64>) -> String {
+    match (entry, offset) {
+        (Some(entry), Some(offset)) => format!("{entry} at byte {offset}: "),
+        (Some(entry), None) => format!("{entry}: "),
+        (None, Some(offset)) => format!("At byte {offset}: "),
+        (None, None) => String::new(),
+    }
+}
+
+impl Error {
+    /// A stable identifier for the kind of failure, for scripts to branch on
+    /// instead of matching messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "io",
+            Error::UnsupportedFormat(_) => "unsupported_format",
+            Error::DetectionFailed => "detection_failed",
+            Error::Conversion { .. } => "conversion_failed",
+            Error::FeatureDisabled(_) => "feature_disabled",
+            Error::MissingEntry { .. } => "missing_entry",
+            Error::Parse { .. } => "parse_error",
+        }
+    }
+
+    /// The converter that failed, when the error came from one.
+    pub fn format(&self) -> Option<&str> {
+        match self {
+            Error::Conversion { format, .. }
+            | Error::MissingEntry { format, .. }
+            | Error::Parse { format, .. } => Some(format),
+            _ => None,
+        }
+    }
+
+    /// The container entry (zip member) the error is about.
+    pub fn entry(&self) -> Option<&str> {
+        match self {
+            Error::MissingEntry { entry, .. } => Some(entry),
+            Error::Parse { entry, .. } => entry.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Byte offset of malformed input, within the entry when there is one.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::Parse { offset, .. } => *offset,
+            _ => None,
+        }
+    }
+
+    /// Attribute a parse error to the container entry it was read from.
+    pub fn in_entry(mut self, name: &str) -> Self {
+        if let Error::Parse { entry, .. } = &mut self
+            && entry.is_none()
+        {
+            *entry = Some(name.to_string());
+        }
+        self
+    }
+
+    /// The error as a JSON object with its code, message and whichever of
+    /// format, entry and offset are known (`null` otherwise).
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "format": self.format(),
+            "entry": self.entry(),
+            "offset": self.offset(),
+        })
+    }
+}
+
+// Lets the CLI keep the error inside a `miette::Report` and recover it for
+// `--errors json`
+impl miette::Diagnostic for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::entry_and_offset(
+        Some("ppt/slides/slide2.xml"),
+        Some(120),
+        "Conversion error (powerpoint): ppt/slides/slide2.xml at byte 120: Unexpected end"
+    )]
+    #[case::offset_only(
+        None,
+        Some(7),
+        "Conversion error (powerpoint): At byte 7: Unexpected end"
+    )]
+    #[case::neither(None, None, "Conversion error (powerpoint): Unexpected end")]
+    fn test_parse_error_message(
+        #[case] entry: Option<&str>,
+        #[case] offset: Option<u64>,
+        #[case] expected: &str,
+    ) {
+        let error = Error::Parse {
+            format: "powerpoint",
+            entry: entry.map(str::to_string),
+            offset,
+            message: "Unexpected end".to_string(),
+        };
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_to_json() {
+        let error = Error::Parse {
+            format: "word",
+            entry: None,
+            offset: Some(42),
+            message: "Bad tag".to_string(),
+        }
+        .in_entry("word/document.xml");
+        assert_eq!(
+            error.to_json(),
+            serde_json::json!({
+                "code": "parse_error",
+                "message": "Conversion error (word): word/document.xml at byte 42: Bad tag",
+                "format": "word",
+                "entry": "word/document.xml",
+                "offset": 42,
+            })
+        );
+        assert_eq!(
+            Error::FeatureDisabled("pdf".into()).to_json(),
+            serde_json::json!({
+                "code": "feature_disabled",
+                "message": "Feature not enabled: pdf. Recompile with --features pdf",
+                "format": null,
+                "entry": null,
+                "offset": null,
+            })
+        );
+    }
 }
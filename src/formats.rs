@@ -1,10 +1,19 @@
-#[cfg(any(feature = "json", feature = "toml_conv", feature = "yaml"))]
 pub mod structured;
 
+pub(crate) mod anchors;
+#[cfg(any(feature = "csv", feature = "excel", feature = "sqlite"))]
+pub(crate) mod cells;
+#[cfg(any(feature = "zip", feature = "tar"))]
+pub mod archive;
+#[cfg(any(feature = "zip", feature = "tar", feature = "pdf"))]
+mod embedded;
+
 #[cfg(feature = "audio")]
 pub mod audio;
 #[cfg(feature = "csv")]
 pub mod csv;
+#[cfg(feature = "doc")]
+pub mod doc;
 #[cfg(feature = "epub")]
 pub mod epub;
 #[cfg(feature = "excel")]
@@ -58,91 +67,127 @@ pub mod zip;
 
 use crate::converter::Converter;
 use crate::detect::Format;
+use crate::options::ConvertOptions;
+
+pub fn get_converter(
+    format: Format,
+    options: &ConvertOptions,
+) -> crate::error::Result<Box<dyn Converter>> {
+    let converter = redact(base_converter(format, options)?, options)?;
+    crate::metadata::wrap(converter, &options.metadata)
+}
+
+#[cfg(feature = "redact")]
+fn redact(
+    converter: Box<dyn Converter>,
+    options: &ConvertOptions,
+) -> crate::error::Result<Box<dyn Converter>> {
+    crate::redact::wrap(converter, &options.redact)
+}
 
-pub fn get_converter(format: Format) -> crate::error::Result<Box<dyn Converter>> {
+#[cfg(not(feature = "redact"))]
+fn redact(
+    converter: Box<dyn Converter>,
+    options: &ConvertOptions,
+) -> crate::error::Result<Box<dyn Converter>> {
+    if options.redact.is_enabled() {
+        return Err(crate::error::Error::FeatureDisabled("redact".into()));
+    }
+    Ok(converter)
+}
+
+pub(crate) fn base_converter(
+    format: Format,
+    options: &ConvertOptions,
+) -> crate::error::Result<Box<dyn Converter>> {
     match format {
         #[cfg(feature = "excel")]
-        Format::Excel => Ok(Box::new(excel::ExcelConverter)),
+        Format::Excel => Ok(Box::new(excel::ExcelConverter::new(options.clone()))),
         #[cfg(not(feature = "excel"))]
         Format::Excel => Err(crate::error::Error::FeatureDisabled("excel".into())),
 
         #[cfg(feature = "pdf")]
-        Format::Pdf => Ok(Box::new(pdf::PdfConverter)),
+        Format::Pdf => Ok(Box::new(pdf::PdfConverter::new(options.clone()))),
         #[cfg(not(feature = "pdf"))]
         Format::Pdf => Err(crate::error::Error::FeatureDisabled("pdf".into())),
 
         #[cfg(feature = "powerpoint")]
-        Format::PowerPoint => Ok(Box::new(powerpoint::PowerPointConverter)),
+        Format::PowerPoint => Ok(Box::new(powerpoint::PowerPointConverter::new(options.clone()))),
         #[cfg(not(feature = "powerpoint"))]
         Format::PowerPoint => Err(crate::error::Error::FeatureDisabled("powerpoint".into())),
 
         #[cfg(feature = "word")]
-        Format::Word => Ok(Box::new(word::WordConverter)),
+        Format::Word => Ok(Box::new(word::WordConverter::new(options.clone()))),
         #[cfg(not(feature = "word"))]
         Format::Word => Err(crate::error::Error::FeatureDisabled("word".into())),
 
+        #[cfg(feature = "doc")]
+        Format::Doc => Ok(Box::new(doc::DocConverter)),
+        #[cfg(not(feature = "doc"))]
+        Format::Doc => Err(crate::error::Error::FeatureDisabled("doc".into())),
+
         #[cfg(feature = "image")]
-        Format::Image => Ok(Box::new(image::ImageConverter)),
+        Format::Image => Ok(Box::new(image::ImageConverter::new(options.clone()))),
         #[cfg(not(feature = "image"))]
         Format::Image => Err(crate::error::Error::FeatureDisabled("image".into())),
 
         #[cfg(feature = "zip")]
-        Format::Zip => Ok(Box::new(zip::ZipConverter)),
+        Format::Zip => Ok(Box::new(zip::ZipConverter::new(options.clone()))),
         #[cfg(not(feature = "zip"))]
         Format::Zip => Err(crate::error::Error::FeatureDisabled("zip".into())),
 
         #[cfg(feature = "epub")]
-        Format::Epub => Ok(Box::new(epub::EpubConverter)),
+        Format::Epub => Ok(Box::new(epub::EpubConverter::new(options.clone()))),
         #[cfg(not(feature = "epub"))]
         Format::Epub => Err(crate::error::Error::FeatureDisabled("epub".into())),
 
         #[cfg(feature = "audio")]
-        Format::Audio => Ok(Box::new(audio::AudioConverter)),
+        Format::Audio => Ok(Box::new(audio::AudioConverter::new(options.clone()))),
         #[cfg(not(feature = "audio"))]
         Format::Audio => Err(crate::error::Error::FeatureDisabled("audio".into())),
 
         #[cfg(feature = "csv")]
-        Format::Csv => Ok(Box::new(csv::CsvConverter)),
+        Format::Csv => Ok(Box::new(csv::CsvConverter::new(options.clone()))),
         #[cfg(not(feature = "csv"))]
         Format::Csv => Err(crate::error::Error::FeatureDisabled("csv".into())),
 
         #[cfg(feature = "html")]
-        Format::Html => Ok(Box::new(html::HtmlConverter)),
+        Format::Html => Ok(Box::new(html::HtmlConverter::new(options.clone()))),
         #[cfg(not(feature = "html"))]
         Format::Html => Err(crate::error::Error::FeatureDisabled("html".into())),
 
         #[cfg(feature = "json")]
-        Format::Json => Ok(Box::new(json::JsonConverter)),
+        Format::Json => Ok(Box::new(json::JsonConverter::new(options.clone()))),
         #[cfg(not(feature = "json"))]
         Format::Json => Err(crate::error::Error::FeatureDisabled("json".into())),
 
         #[cfg(feature = "yaml")]
-        Format::Yaml => Ok(Box::new(yaml::YamlConverter)),
+        Format::Yaml => Ok(Box::new(yaml::YamlConverter::new(options.clone()))),
         #[cfg(not(feature = "yaml"))]
         Format::Yaml => Err(crate::error::Error::FeatureDisabled("yaml".into())),
 
         #[cfg(feature = "toml_conv")]
-        Format::Toml => Ok(Box::new(toml_conv::TomlConverter)),
+        Format::Toml => Ok(Box::new(toml_conv::TomlConverter::new(options.clone()))),
         #[cfg(not(feature = "toml_conv"))]
         Format::Toml => Err(crate::error::Error::FeatureDisabled("toml".into())),
 
         #[cfg(feature = "xml")]
-        Format::Xml => Ok(Box::new(xml::XmlConverter)),
+        Format::Xml => Ok(Box::new(xml::XmlConverter::new(options.clone()))),
         #[cfg(not(feature = "xml"))]
         Format::Xml => Err(crate::error::Error::FeatureDisabled("xml".into())),
 
         #[cfg(feature = "sqlite")]
-        Format::Sqlite => Ok(Box::new(sqlite::SqliteConverter)),
+        Format::Sqlite => Ok(Box::new(sqlite::SqliteConverter::new(options.clone()))),
         #[cfg(not(feature = "sqlite"))]
         Format::Sqlite => Err(crate::error::Error::FeatureDisabled("sqlite".into())),
 
         #[cfg(feature = "tar")]
-        Format::Tar => Ok(Box::new(tar::TarConverter)),
+        Format::Tar => Ok(Box::new(tar::TarConverter::new(options.clone()))),
         #[cfg(not(feature = "tar"))]
         Format::Tar => Err(crate::error::Error::FeatureDisabled("tar".into())),
 
         #[cfg(feature = "video")]
-        Format::Video => Ok(Box::new(video::VideoConverter)),
+        Format::Video => Ok(Box::new(video::VideoConverter::new(options.clone()))),
         #[cfg(not(feature = "video"))]
         Format::Video => Err(crate::error::Error::FeatureDisabled("video".into())),
 
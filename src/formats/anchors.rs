@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+/// Generates GitHub-style heading anchors, disambiguating repeated headings.
+#[derive(Default)]
+pub(crate) struct Slugger {
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub(crate) fn slug(&mut self, text: &str) -> String {
+        let base: String = text
+            .trim()
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    Some(c)
+                } else if c.is_whitespace() {
+                    Some('-')
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let count = self.seen.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 {
+            base
+        } else {
+            format!("{base}-{count}")
+        };
+        *count += 1;
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::plain("Hello World", "hello-world")]
+    #[case::punctuation("1. What's new?", "1-whats-new")]
+    fn test_slug(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(Slugger::default().slug(text), expected);
+    }
+
+    #[rstest]
+    fn test_slug_deduplicates() {
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.slug("Notes"), "notes");
+        assert_eq!(slugger.slug("Notes"), "notes-1");
+    }
+}
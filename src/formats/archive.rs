@@ -0,0 +1,340 @@
+use std::io::{Read, Write};
+
+use super::embedded;
+use crate::error::Result;
+use crate::options::{ArchiveOptions, ConvertOptions, DEFAULT_MAX_ENTRY_SIZE};
+use crate::warnings;
+
+/// Whether an archive entry passes the `--include`/`--exclude` filters.
+///
+/// An entry is kept when it matches at least one include pattern (or none
+/// are given) and no exclude pattern. Patterns without a `/` are tested
+/// against every path component, so `node_modules` or `*.log` match at any
+/// depth; patterns with a `/` are tested against the full path and its
+/// leading directories, so `src/generated` also covers everything below it.
+pub fn is_included(options: &ArchiveOptions, path: &str) -> bool {
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    (options.include.is_empty() || options.include.iter().any(|p| matches_path(p, path)))
+        && !options.exclude.iter().any(|p| matches_path(p, path))
+}
+
+fn matches_path(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    if pattern.contains('/') {
+        // The path itself or any of its parent directories
+        path.match_indices('/')
+            .map(|(i, _)| &path[..i])
+            .chain(std::iter::once(path))
+            .any(|prefix| glob_match(pattern, prefix))
+    } else {
+        path.split('/').any(|segment| glob_match(pattern, segment))
+    }
+}
+
+/// Match `text` against a glob where `*` matches within a path component,
+/// `**` matches across components and `?` matches a single character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_at(&pattern, &text)
+}
+
+fn glob_match_at(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` may also match zero directories
+            if rest.first() == Some(&'/') && glob_match_at(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| glob_match_at(rest, &text[i..]))
+        }
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match_at(&pattern[1..], &text[i..])),
+        Some('?') => {
+            text.first().is_some_and(|&c| c != '/') && glob_match_at(&pattern[1..], &text[1..])
+        }
+        Some(&c) => text.first() == Some(&c) && glob_match_at(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Read an entry for conversion, or `None` when it is over the size limit.
+/// Reads at most one byte past the limit, so a misreported size cannot make
+/// a compressed entry expand without bound.
+pub fn read_entry(options: &ArchiveOptions, reader: impl Read) -> Result<Option<Vec<u8>>> {
+    let limit = options.max_entry_size.unwrap_or(DEFAULT_MAX_ENTRY_SIZE);
+    let mut bytes = Vec::new();
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+    Ok((bytes.len() as u64 <= limit).then_some(bytes))
+}
+
+/// How many of the largest files the summary lists.
+const LARGEST_FILES: usize = 10;
+
+/// Write a summary of an archive's files, given as `(path, size)`: the
+/// largest of them, and how many files of each extension there are and
+/// how much space they take.
+pub fn write_summary(writer: &mut dyn Write, files: &[(&str, u64)]) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let mut largest: Vec<&(&str, u64)> = files.iter().collect();
+    largest.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    writeln!(writer)?;
+    writeln!(writer, "## Largest Files")?;
+    writeln!(writer)?;
+    writeln!(writer, "| # | Name | Size |")?;
+    writeln!(writer, "|---|------|------|")?;
+    for (idx, (name, size)) in largest.iter().take(LARGEST_FILES).enumerate() {
+        writeln!(writer, "| {} | {name} | {} |", idx + 1, format_size(*size))?;
+    }
+
+    let mut extensions: Vec<(String, usize, u64)> = Vec::new();
+    for (name, size) in files {
+        let extension = extension(name);
+        match extensions.iter_mut().find(|(e, _, _)| *e == extension) {
+            Some((_, count, total)) => {
+                *count += 1;
+                *total += size;
+            }
+            None => extensions.push((extension, 1, *size)),
+        }
+    }
+    extensions.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)).then(a.0.cmp(&b.0)));
+    writeln!(writer)?;
+    writeln!(writer, "## File Types")?;
+    writeln!(writer)?;
+    writeln!(writer, "| Extension | Files | Size |")?;
+    writeln!(writer, "|-----------|-------|------|")?;
+    for (extension, count, size) in &extensions {
+        writeln!(writer, "| {extension} | {count} | {} |", format_size(*size))?;
+    }
+    Ok(())
+}
+
+/// The lowercased extension of a path's file name, such as `.tar.gz` is
+/// `.gz`, or `(none)`. Dotfiles such as `.gitignore` have none.
+fn extension(path: &str) -> String {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => {
+            format!(".{}", extension.to_lowercase())
+        }
+        _ => "(none)".to_string(),
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    const GB: u64 = 1024 * MB;
+
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+/// Write a converted entry as a section below the listing. `bytes` is
+/// `None` for entries over the size limit. Nested archives are converted
+/// with one level less to descend.
+pub fn write_entry(
+    writer: &mut dyn Write,
+    name: &str,
+    bytes: Option<&[u8]>,
+    options: &ConvertOptions,
+) -> Result<()> {
+    writeln!(writer)?;
+    writeln!(writer, "## {name}")?;
+    writeln!(writer)?;
+
+    let Some(bytes) = bytes else {
+        let limit = options
+            .archive
+            .max_entry_size
+            .unwrap_or(DEFAULT_MAX_ENTRY_SIZE);
+        writeln!(writer, "*Not converted: larger than {limit} bytes*")?;
+        return Ok(());
+    };
+
+    let options = ConvertOptions {
+        archive: ArchiveOptions {
+            depth: options.archive.depth.saturating_sub(1),
+            ..options.archive.clone()
+        },
+        ..options.clone()
+    };
+    match embedded::convert(name, bytes, &options, 2) {
+        Ok(Some(markdown)) => writeln!(writer, "{markdown}")?,
+        Ok(None) => writeln!(writer, "*Not converted: unrecognised format*")?,
+        Err(e) => {
+            warnings::warn(format!("{name}: not converted: {e}"));
+            writeln!(writer, "*Not converted: {e}*")?
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::literal("README.md", "README.md", true)]
+    #[case::star("*.rs", "main.rs", true)]
+    #[case::star_stops_at_slash("src/*.rs", "src/a/main.rs", false)]
+    #[case::double_star("src/**/*.rs", "src/a/b/main.rs", true)]
+    #[case::double_star_zero_dirs("src/**/*.rs", "src/main.rs", true)]
+    #[case::question("?.txt", "a.txt", true)]
+    #[case::question_needs_char("?.txt", ".txt", false)]
+    fn test_glob_match(#[case] pattern: &str, #[case] text: &str, #[case] expected: bool) {
+        assert_eq!(glob_match(pattern, text), expected);
+    }
+
+    fn options(include: &[&str], exclude: &[&str]) -> ArchiveOptions {
+        ArchiveOptions {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::no_filters(&[], &[], "a/b.txt", true)]
+    #[case::exclude_component(&[], &["node_modules"], "app/node_modules/x/index.js", false)]
+    #[case::exclude_directory_entry(&[], &[".git"], ".git/", false)]
+    #[case::exclude_prefix(&[], &["src/generated"], "src/generated/a.rs", false)]
+    #[case::exclude_other(&[], &["node_modules"], "app/src/index.js", true)]
+    #[case::include_match(&["*.rs"], &[], "src/main.rs", true)]
+    #[case::include_miss(&["*.rs"], &[], "src/main.py", false)]
+    #[case::exclude_wins(&["*.rs"], &["target"], "target/debug/build.rs", false)]
+    fn test_is_included(
+        #[case] include: &[&str],
+        #[case] exclude: &[&str],
+        #[case] path: &str,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_included(&options(include, exclude), path), expected);
+    }
+
+    #[rstest]
+    #[case::plain("src/main.rs", ".rs")]
+    #[case::upper("docs/Scan.PDF", ".pdf")]
+    #[case::double("dist/app.tar.gz", ".gz")]
+    #[case::dotfile("app/.gitignore", "(none)")]
+    #[case::bare("Makefile", "(none)")]
+    fn test_extension(#[case] path: &str, #[case] expected: &str) {
+        assert_eq!(extension(path), expected);
+    }
+
+    #[rstest]
+    fn test_write_summary() {
+        let files: Vec<(String, u64)> = (1..=11)
+            .map(|i| (format!("logs/{i}.log"), i * 100))
+            .chain([
+                ("data/big.CSV".to_string(), 3 * 1024 * 1024),
+                ("README".to_string(), 40),
+            ])
+            .collect();
+        let files: Vec<(&str, u64)> = files.iter().map(|(n, s)| (n.as_str(), *s)).collect();
+        let mut output = Vec::new();
+        write_summary(&mut output, &files).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "
+## Largest Files
+
+| # | Name | Size |
+|---|------|------|
+| 1 | data/big.CSV | 3.0 MB |
+| 2 | logs/11.log | 1.1 KB |
+| 3 | logs/10.log | 1000 B |
+| 4 | logs/9.log | 900 B |
+| 5 | logs/8.log | 800 B |
+| 6 | logs/7.log | 700 B |
+| 7 | logs/6.log | 600 B |
+| 8 | logs/5.log | 500 B |
+| 9 | logs/4.log | 400 B |
+| 10 | logs/3.log | 300 B |
+
+## File Types
+
+| Extension | Files | Size |
+|-----------|-------|------|
+| .csv | 1 | 3.0 MB |
+| .log | 11 | 6.4 KB |
+| (none) | 1 | 40 B |
+"
+        );
+    }
+
+    #[cfg(all(feature = "zip", feature = "tar", feature = "csv"))]
+    fn nested_archive() -> Vec<u8> {
+        use std::io::{Cursor, Write};
+
+        let csv = b"name,qty\napple,3\n";
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(csv.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "data/items.csv", &csv[..])
+            .unwrap();
+        let tar_gz = tar.into_inner().unwrap().finish().unwrap();
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("inner.tar.gz", options).unwrap();
+        zip.write_all(&tar_gz).unwrap();
+        zip.start_file("big.csv", options).unwrap();
+        zip.write_all(&[b'x'; 64 * 1024]).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[cfg(all(feature = "zip", feature = "tar", feature = "csv"))]
+    #[rstest]
+    #[case::listing_only(0, &[], &["## inner.tar.gz"])]
+    #[case::one_level(1, &["## inner.tar.gz", "### Archive", "| 1 | data/items.csv |", "*Not converted: larger than 32768 bytes*"], &["#### data/items.csv"])]
+    #[case::nested(2, &["#### data/items.csv", "| apple | 3 |"], &[])]
+    fn test_nested_archives(
+        #[case] depth: usize,
+        #[case] expected: &[&str],
+        #[case] unexpected: &[&str],
+    ) {
+        use crate::converter::Converter;
+
+        let options = ConvertOptions {
+            archive: ArchiveOptions {
+                depth,
+                max_entry_size: Some(32 * 1024),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        crate::formats::zip::ZipConverter::new(options)
+            .convert(&nested_archive(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        for text in expected {
+            assert!(output.contains(text), "missing {text:?} in\n{output}");
+        }
+        for text in unexpected {
+            assert!(!output.contains(text), "unexpected {text:?} in\n{output}");
+        }
+    }
+}
@@ -1,14 +1,26 @@
+pub mod album;
+
 use std::io::{Cursor, Write};
 
-use lofty::file::TaggedFileExt;
+use lofty::file::{TaggedFile, TaggedFileExt};
 use lofty::prelude::*;
 use lofty::probe::Probe;
 use lofty::tag::ItemKey;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::options::ConvertOptions;
+
+#[derive(Default)]
+pub struct AudioConverter {
+    options: ConvertOptions,
+}
 
-pub struct AudioConverter;
+impl AudioConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for AudioConverter {
     fn format_name(&self) -> &'static str {
@@ -16,94 +28,127 @@ impl Converter for AudioConverter {
     }
 
     fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
-        let cursor = Cursor::new(input);
-        let tagged_file =
-            Probe::new(cursor)
-                .guess_file_type()
-                .map_err(|e| Error::Conversion {
-                    format: "audio",
-                    message: e.to_string(),
-                })?
-                .read()
-                .map_err(|e| Error::Conversion {
-                    format: "audio",
-                    message: e.to_string(),
-                })?;
+        let tagged_file = read(input)?;
 
         writeln!(writer, "# Audio")?;
         writeln!(writer)?;
 
         // File properties
-        let props = tagged_file.properties();
         writeln!(writer, "## File Info")?;
         writeln!(writer)?;
         writeln!(writer, "| Property | Value |")?;
         writeln!(writer, "|----------|-------|")?;
-
-        writeln!(
-            writer,
-            "| Format | {:?} |",
-            tagged_file.file_type()
-        )?;
-        writeln!(writer, "| Size | {} |", format_size(input.len() as u64))?;
-
-        let duration = props.duration();
-        if !duration.is_zero() {
-            let secs = duration.as_secs();
-            let mins = secs / 60;
-            let rem = secs % 60;
-            writeln!(writer, "| Duration | {mins}:{rem:02} |")?;
-        }
-
-        if let Some(bitrate) = props.overall_bitrate() {
-            writeln!(writer, "| Bitrate | {bitrate} kbps |")?;
-        }
-
-        if let Some(sample_rate) = props.sample_rate() {
-            writeln!(writer, "| Sample Rate | {sample_rate} Hz |")?;
-        }
-
-        if let Some(channels) = props.channels() {
-            let ch_label = match channels {
-                1 => "Mono",
-                2 => "Stereo",
-                _ => "Multi-channel",
-            };
-            writeln!(writer, "| Channels | {channels} ({ch_label}) |")?;
+        for (key, value) in file_info(&tagged_file, input.len() as u64) {
+            writeln!(writer, "| {key} | {value} |")?;
         }
 
         writeln!(writer)?;
 
         // Tags
-        if let Some(tag) = tagged_file.primary_tag().or(tagged_file.first_tag()) {
-            let items: Vec<(&str, String)> = [
-                ("Title", tag.get_string(ItemKey::TrackTitle)),
-                ("Artist", tag.get_string(ItemKey::TrackArtist)),
-                ("Album", tag.get_string(ItemKey::AlbumTitle)),
-                ("Year", tag.get_string(ItemKey::Year)),
-                ("Track", tag.get_string(ItemKey::TrackNumber)),
-                ("Genre", tag.get_string(ItemKey::Genre)),
-                ("Comment", tag.get_string(ItemKey::Comment)),
-            ]
-            .into_iter()
-            .filter_map(|(k, v)| v.map(|v| (k, v.to_string())))
-            .collect();
-
-            if !items.is_empty() {
-                writeln!(writer, "## Tags")?;
+        let mut needs_blank = false;
+        let items = tags(&tagged_file);
+        if !items.is_empty() {
+            writeln!(writer, "## Tags")?;
+            writeln!(writer)?;
+            writeln!(writer, "| Tag | Value |")?;
+            writeln!(writer, "|-----|-------|")?;
+            for (key, value) in &items {
+                writeln!(writer, "| {key} | {} |", value.replace('|', "\\|"))?;
+            }
+            needs_blank = true;
+        }
+
+        if self.options.transcribe.endpoint.is_some() {
+            if needs_blank {
                 writeln!(writer)?;
-                writeln!(writer, "| Tag | Value |")?;
-                writeln!(writer, "|-----|-------|")?;
-                for (key, value) in &items {
-                    writeln!(writer, "| {key} | {} |", value.replace('|', "\\|"))?;
-                }
             }
+            #[cfg(feature = "remote-transcribe")]
+            crate::transcribe::write_transcript(
+                &self.options.transcribe,
+                input,
+                self.options.input_name.as_deref().unwrap_or("audio.mp3"),
+                "audio",
+                writer,
+            )?;
+            #[cfg(not(feature = "remote-transcribe"))]
+            return Err(Error::FeatureDisabled("remote-transcribe".into()));
         }
 
         Ok(())
     }
 }
 
+/// Probe `input` for its audio format and read its properties and tags.
+fn read(input: &[u8]) -> Result<TaggedFile> {
+    Probe::new(Cursor::new(input))
+        .guess_file_type()
+        .map_err(|e| Error::Conversion {
+            format: "audio",
+            message: e.to_string(),
+        })?
+        .read()
+        .map_err(|e| Error::Conversion {
+            format: "audio",
+            message: e.to_string(),
+        })
+}
+
+/// The format and audio properties of a file of `size` bytes.
+fn file_info(tagged_file: &TaggedFile, size: u64) -> Vec<(&'static str, String)> {
+    let props = tagged_file.properties();
+    let mut info = vec![
+        ("Format", format!("{:?}", tagged_file.file_type())),
+        ("Size", format_size(size)),
+    ];
+
+    let duration = props.duration();
+    if !duration.is_zero() {
+        let secs = duration.as_secs();
+        let mins = secs / 60;
+        let rem = secs % 60;
+        info.push(("Duration", format!("{mins}:{rem:02}")));
+    }
+
+    if let Some(bitrate) = props.overall_bitrate() {
+        info.push(("Bitrate", format!("{bitrate} kbps")));
+    }
+
+    if let Some(sample_rate) = props.sample_rate() {
+        info.push(("Sample Rate", format!("{sample_rate} Hz")));
+    }
+
+    if let Some(channels) = props.channels() {
+        let ch_label = match channels {
+            1 => "Mono",
+            2 => "Stereo",
+            _ => "Multi-channel",
+        };
+        info.push(("Channels", format!("{channels} ({ch_label})")));
+    }
+
+    info
+}
+
+/// The tags shown for a file, in display order, from its primary tag or
+/// else the first one it has.
+fn tags(tagged_file: &TaggedFile) -> Vec<(&'static str, String)> {
+    let Some(tag) = tagged_file.primary_tag().or(tagged_file.first_tag()) else {
+        return Vec::new();
+    };
+    [
+        ("Title", tag.get_string(ItemKey::TrackTitle)),
+        ("Artist", tag.get_string(ItemKey::TrackArtist)),
+        ("Album", tag.get_string(ItemKey::AlbumTitle)),
+        ("Year", tag.get_string(ItemKey::Year)),
+        ("Track", tag.get_string(ItemKey::TrackNumber)),
+        ("Genre", tag.get_string(ItemKey::Genre)),
+        ("Comment", tag.get_string(ItemKey::Comment)),
+    ]
+    .into_iter()
+    .filter_map(|(k, v)| v.map(|v| (k, v.to_string())))
+    .collect()
+}
+
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * KB;
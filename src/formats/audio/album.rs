@@ -0,0 +1,271 @@
+use std::io::Write;
+
+use lofty::file::TaggedFileExt;
+use lofty::prelude::*;
+use lofty::tag::ItemKey;
+
+use super::{file_info, read, tags};
+use crate::error::{Error, Result};
+
+/// One track of an album, read from an audio file.
+#[derive(Debug, PartialEq)]
+pub struct AlbumTrack {
+    pub name: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// The track number, without the total of a `3/12` tag.
+    pub number: Option<u32>,
+    /// The length of the track, in whole seconds.
+    pub duration: u64,
+    /// The file properties and tags shown in the track's metadata table.
+    pub metadata: Vec<(&'static str, String)>,
+}
+
+impl AlbumTrack {
+    pub fn read(name: &str, input: &[u8]) -> Result<Self> {
+        let tagged_file = read(input).map_err(|e| Error::Conversion {
+            format: "audio",
+            message: format!("{name}: {e}"),
+        })?;
+        let tag = tagged_file.primary_tag().or(tagged_file.first_tag());
+        let get = |key| {
+            tag.and_then(|tag| tag.get_string(key))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let number = get(ItemKey::TrackNumber).and_then(|number| {
+            let number = number.split('/').next().unwrap_or_default();
+            number.trim().parse().ok()
+        });
+        let mut metadata = file_info(&tagged_file, input.len() as u64);
+        metadata.extend(tags(&tagged_file));
+        Ok(Self {
+            name: name.to_string(),
+            title: get(ItemKey::TrackTitle),
+            artist: get(ItemKey::TrackArtist),
+            album: get(ItemKey::AlbumTitle),
+            number,
+            duration: tagged_file.properties().duration().as_secs(),
+            metadata,
+        })
+    }
+
+    /// The track's title tag, or else its file name.
+    fn display_title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Write one album-style document for `tracks`: a tracklist with each
+/// track's duration and the total runtime, followed by a metadata table per
+/// track. Tracks are ordered by their track number, then by file name, and
+/// the album tag they all share titles the document in place of `title`.
+pub fn write_album_as_markdown(
+    writer: &mut dyn Write,
+    title: &str,
+    tracks: &[AlbumTrack],
+) -> Result<()> {
+    let mut tracks: Vec<&AlbumTrack> = tracks.iter().collect();
+    tracks.sort_by(|a, b| {
+        (a.number.is_none(), a.number, &a.name).cmp(&(b.number.is_none(), b.number, &b.name))
+    });
+
+    let album = shared(&tracks, |track| track.album.as_deref());
+    writeln!(writer, "# {}", album.unwrap_or(title))?;
+    writeln!(writer)?;
+    if tracks.is_empty() {
+        writeln!(writer, "*No audio files*")?;
+        return Ok(());
+    }
+
+    if let Some(artist) = shared(&tracks, |track| track.artist.as_deref()) {
+        writeln!(writer, "- **Artist**: {artist}")?;
+    }
+    let total: u64 = tracks.iter().map(|track| track.duration).sum();
+    writeln!(writer, "- **Tracks**: {}", tracks.len())?;
+    writeln!(writer, "- **Total runtime**: {}", format_runtime(total))?;
+
+    writeln!(writer)?;
+    writeln!(writer, "## Tracklist")?;
+    writeln!(writer)?;
+    writeln!(writer, "| # | Title | Artist | Duration |")?;
+    writeln!(writer, "|---|---|---|---|")?;
+    for (index, track) in tracks.iter().enumerate() {
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} |",
+            track.number.map_or(index + 1, |n| n as usize),
+            escape(track.display_title()),
+            escape(track.artist.as_deref().unwrap_or_default()),
+            format_runtime(track.duration)
+        )?;
+    }
+
+    for (index, track) in tracks.iter().enumerate() {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "## {}. {}",
+            track.number.map_or(index + 1, |n| n as usize),
+            track.display_title()
+        )?;
+        writeln!(writer)?;
+        writeln!(writer, "| Property | Value |")?;
+        writeln!(writer, "|----------|-------|")?;
+        writeln!(writer, "| File | {} |", escape(&track.name))?;
+        for (key, value) in &track.metadata {
+            writeln!(writer, "| {key} | {} |", escape(value))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The value every track has for a tag, if they all have the same one.
+fn shared<'a>(
+    tracks: &[&'a AlbumTrack],
+    tag: impl Fn(&'a AlbumTrack) -> Option<&'a str>,
+) -> Option<&'a str> {
+    let first = tag(tracks.first()?)?;
+    tracks
+        .iter()
+        .all(|track| tag(track) == Some(first))
+        .then_some(first)
+}
+
+/// A duration as `m:ss`, or `h:mm:ss` from an hour up.
+fn format_runtime(secs: u64) -> String {
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}")
+    } else {
+        format!("{mins}:{secs:02}")
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    /// A mono 8 kHz, 8-bit WAV file of `secs` seconds of silence with the
+    /// given RIFF INFO tags.
+    fn wav(secs: u32, info: &[(&[u8; 4], &str)]) -> Vec<u8> {
+        let mut list = b"INFO".to_vec();
+        for (id, value) in info {
+            let mut value = value.as_bytes().to_vec();
+            value.push(0);
+            if value.len() % 2 == 1 {
+                value.push(0);
+            }
+            list.extend_from_slice(*id);
+            list.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            list.extend_from_slice(&value);
+        }
+        let mut body = b"WAVEfmt ".to_vec();
+        body.extend_from_slice(&16u32.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&8000u32.to_le_bytes());
+        body.extend_from_slice(&8000u32.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&8u16.to_le_bytes());
+        body.extend_from_slice(b"data");
+        body.extend_from_slice(&(secs * 8000).to_le_bytes());
+        body.extend(std::iter::repeat_n(128u8, secs as usize * 8000));
+        body.extend_from_slice(b"LIST");
+        body.extend_from_slice(&(list.len() as u32).to_le_bytes());
+        body.extend_from_slice(&list);
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        file.extend_from_slice(&body);
+        file
+    }
+
+    fn album(tracks: &[(&str, Vec<u8>)]) -> String {
+        let tracks: Vec<AlbumTrack> = tracks
+            .iter()
+            .map(|(name, input)| AlbumTrack::read(name, input).unwrap())
+            .collect();
+        let mut output = Vec::new();
+        write_album_as_markdown(&mut output, "demos", &tracks).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_album() {
+        let output = album(&[
+            (
+                "b.wav",
+                wav(
+                    2,
+                    &[
+                        (b"INAM", "Second"),
+                        (b"IART", "Band"),
+                        (b"IPRD", "Live"),
+                        (b"IPRT", "2/2"),
+                    ],
+                ),
+            ),
+            (
+                "a.wav",
+                wav(
+                    1,
+                    &[
+                        (b"INAM", "First | Intro"),
+                        (b"IART", "Band"),
+                        (b"IPRD", "Live"),
+                        (b"IPRT", "1/2"),
+                    ],
+                ),
+            ),
+        ]);
+        assert!(
+            output.starts_with(
+                "# Live\n\n\
+                 - **Artist**: Band\n- **Tracks**: 2\n- **Total runtime**: 0:03\n\n\
+                 ## Tracklist\n\n| # | Title | Artist | Duration |\n|---|---|---|---|\n\
+                 | 1 | First \\| Intro | Band | 0:01 |\n| 2 | Second | Band | 0:02 |\n\n\
+                 ## 1. First | Intro\n\n| Property | Value |\n|----------|-------|\n\
+                 | File | a.wav |\n| Format | Wav |\n"
+            ),
+            "{output}"
+        );
+        assert!(
+            output.contains(
+                "## 2. Second\n\n| Property | Value |\n|----------|-------|\n| File | b.wav |\n"
+            ),
+            "{output}"
+        );
+        assert!(output.contains("| Duration | 0:02 |\n"), "{output}");
+        assert!(output.contains("| Track | 2/2 |\n"), "{output}");
+    }
+
+    #[rstest]
+    fn test_untagged_tracks() {
+        let output = album(&[("b.wav", wav(1, &[])), ("a.wav", wav(1, &[]))]);
+        assert!(
+            output.starts_with(
+                "# demos\n\n- **Tracks**: 2\n- **Total runtime**: 0:02\n\n\
+                 ## Tracklist\n\n| # | Title | Artist | Duration |\n|---|---|---|---|\n\
+                 | 1 | a.wav |  | 0:01 |\n| 2 | b.wav |  | 0:01 |\n"
+            ),
+            "{output}"
+        );
+    }
+
+    #[rstest]
+    #[case(0, "0:00")]
+    #[case(185, "3:05")]
+    #[case(4_000, "1:06:40")]
+    fn test_format_runtime(#[case] secs: u64, #[case] expected: &str) {
+        assert_eq!(format_runtime(secs), expected);
+    }
+}
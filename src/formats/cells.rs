@@ -0,0 +1,48 @@
+use std::borrow::Cow;
+
+/// Neutralize a table cell that could be misread downstream: text starting
+/// like a spreadsheet formula (`=`, `+`, `-`, `@`, a tab or a carriage
+/// return) is prefixed with `'` so spreadsheets show it as text, and `<`
+/// and `>` become entities so Markdown renderers never see HTML tags.
+/// Numbers such as `-5` or `+1.5` are left as they are.
+pub(crate) fn sanitize(cell: &str) -> Cow<'_, str> {
+    let formula = cell.starts_with(['=', '+', '-', '@', '\t', '\r'])
+        && cell.trim().parse::<f64>().is_err();
+    if !formula && !cell.contains(['<', '>']) {
+        return Cow::Borrowed(cell);
+    }
+    let mut out = String::with_capacity(cell.len() + 1);
+    if formula {
+        out.push('\'');
+    }
+    for c in cell.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::formula("=HYPERLINK(\"http://x\")", "'=HYPERLINK(\"http://x\")")]
+    #[case::plus("+cmd", "'+cmd")]
+    #[case::minus("-2+3+cmd|' /C calc'!A0", "'-2+3+cmd|' /C calc'!A0")]
+    #[case::at("@SUM(A1)", "'@SUM(A1)")]
+    #[case::tab("\t=1", "'\t=1")]
+    #[case::html("<script>alert(1)</script>", "&lt;script&gt;alert(1)&lt;/script&gt;")]
+    #[case::both("=1<2", "'=1&lt;2")]
+    #[case::negative_number("-5", "-5")]
+    #[case::signed_decimal("+1.5", "+1.5")]
+    #[case::plain("a = b - c", "a = b - c")]
+    fn test_sanitize(#[case] cell: &str, #[case] expected: &str) {
+        assert_eq!(sanitize(cell), expected);
+    }
+}
@@ -1,9 +1,24 @@
+pub mod dataset;
+
+use std::collections::HashMap;
 use std::io::Write;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::options::ConvertOptions;
+
+use super::cells;
 
-pub struct CsvConverter;
+#[derive(Default)]
+pub struct CsvConverter {
+    options: ConvertOptions,
+}
+
+impl CsvConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for CsvConverter {
     fn format_name(&self) -> &'static str {
@@ -15,10 +30,15 @@ impl Converter for CsvConverter {
             .flexible(true)
             .from_reader(input);
 
-        let headers = reader.headers().map_err(|e| Error::Conversion {
-            format: "csv",
-            message: e.to_string(),
-        })?;
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(|e| Error::Conversion {
+                format: "csv",
+                message: e.to_string(),
+            })?
+            .iter()
+            .map(str::to_string)
+            .collect();
 
         let col_count = headers.len();
         if col_count == 0 {
@@ -26,19 +46,67 @@ impl Converter for CsvConverter {
             return Ok(());
         }
 
-        // Header row
-        write!(writer, "|")?;
-        for field in headers.iter() {
-            write!(writer, " {} |", escape_pipe(field))?;
+        let max_columns = self.options.csv.max_columns;
+
+        if let Some(column) = &self.options.csv.group_by {
+            let key_idx = headers.iter().position(|h| h == column).ok_or_else(|| {
+                Error::Conversion {
+                    format: "csv",
+                    message: format!("Group-by column not found: {column}"),
+                }
+            })?;
+
+            // Groups keep the order in which their keys first appear
+            let mut groups: Vec<(String, Vec<Vec<String>>)> = Vec::new();
+            let mut group_index: HashMap<String, usize> = HashMap::new();
+            for record in read_records(&mut reader)? {
+                let key = record.get(key_idx).cloned().unwrap_or_default();
+                let row: Vec<String> = (0..col_count)
+                    .filter(|&i| i != key_idx)
+                    .map(|i| record.get(i).cloned().unwrap_or_default())
+                    .collect();
+                match group_index.get(&key) {
+                    Some(&idx) => groups[idx].1.push(row),
+                    None => {
+                        group_index.insert(key.clone(), groups.len());
+                        groups.push((key, vec![row]));
+                    }
+                }
+            }
+
+            let group_headers: Vec<String> = headers
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != key_idx)
+                .map(|(_, h)| h.clone())
+                .collect();
+
+            for (idx, (key, rows)) in groups.iter().enumerate() {
+                if idx > 0 {
+                    writeln!(writer)?;
+                }
+                let title = if key.is_empty() { "(empty)" } else { key };
+                writeln!(writer, "## {title}")?;
+                writeln!(writer)?;
+                if group_headers.is_empty() {
+                    // Grouping by the only column leaves nothing to tabulate
+                    let plural = if rows.len() == 1 { "" } else { "s" };
+                    writeln!(writer, "*{} row{plural}*", rows.len())?;
+                } else {
+                    write_records(writer, &group_headers, rows, &self.options)?;
+                }
+            }
+            return Ok(());
         }
-        writeln!(writer)?;
 
-        // Separator
-        write!(writer, "|")?;
-        for _ in 0..col_count {
-            write!(writer, "---|")?;
+        if self.options.csv.transpose {
+            let records = read_records(&mut reader)?;
+            write_records(writer, &headers, &records, &self.options)?;
+            return Ok(());
         }
-        writeln!(writer)?;
+
+        let sanitize = self.options.sanitize_cells;
+        let shown = write_header(writer, &headers, max_columns, sanitize)?;
 
         // Data rows
         for result in reader.records() {
@@ -46,18 +114,222 @@ impl Converter for CsvConverter {
                 format: "csv",
                 message: e.to_string(),
             })?;
-            write!(writer, "|")?;
-            for i in 0..col_count {
-                let cell = record.get(i).unwrap_or("");
-                write!(writer, " {} |", escape_pipe(cell))?;
-            }
-            writeln!(writer)?;
+            let row: Vec<&str> = (0..col_count).map(|i| record.get(i).unwrap_or("")).collect();
+            write_row(writer, &row, shown, sanitize)?;
         }
 
+        write_hidden_note(writer, col_count - shown)?;
+
         Ok(())
     }
 }
 
-fn escape_pipe(s: &str) -> String {
-    s.replace('|', "\\|")
+fn read_records(reader: &mut csv::Reader<&[u8]>) -> Result<Vec<Vec<String>>> {
+    reader
+        .records()
+        .map(|result| {
+            result
+                .map(|record| record.iter().map(str::to_string).collect())
+                .map_err(|e| Error::Conversion {
+                    format: "csv",
+                    message: e.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Write buffered records as a table, honoring the transpose and column limit options.
+fn write_records(
+    writer: &mut dyn Write,
+    headers: &[String],
+    records: &[Vec<String>],
+    options: &ConvertOptions,
+) -> Result<()> {
+    let max_columns = options.csv.max_columns;
+    let sanitize = options.sanitize_cells;
+
+    if options.csv.transpose {
+        // Each original column becomes a row; each record becomes a column.
+        let mut header = vec!["Column".to_string()];
+        header.extend((1..=records.len()).map(|n| n.to_string()));
+        let shown = write_header(writer, &header, max_columns, sanitize)?;
+        for (i, name) in headers.iter().enumerate() {
+            let mut row = vec![name.as_str()];
+            row.extend(records.iter().map(|r| cell(r, i)));
+            write_row(writer, &row, shown, sanitize)?;
+        }
+        write_hidden_note(writer, header.len() - shown)?;
+    } else {
+        let shown = write_header(writer, headers, max_columns, sanitize)?;
+        for record in records {
+            let row: Vec<&str> = (0..headers.len()).map(|i| cell(record, i)).collect();
+            write_row(writer, &row, shown, sanitize)?;
+        }
+        write_hidden_note(writer, headers.len() - shown)?;
+    }
+
+    Ok(())
+}
+
+fn cell(row: &[String], i: usize) -> &str {
+    row.get(i).map_or("", |s| s.as_str())
+}
+
+/// Write the header and separator rows, returning how many columns are shown.
+fn write_header(
+    writer: &mut dyn Write,
+    headers: &[String],
+    max_columns: Option<usize>,
+    sanitize: bool,
+) -> Result<usize> {
+    let shown = max_columns.map_or(headers.len(), |max| headers.len().min(max));
+
+    write!(writer, "|")?;
+    for field in &headers[..shown] {
+        write!(writer, " {} |", escape_cell(field, sanitize))?;
+    }
+    writeln!(writer)?;
+
+    write!(writer, "|")?;
+    for _ in 0..shown {
+        write!(writer, "---|")?;
+    }
+    writeln!(writer)?;
+
+    Ok(shown)
+}
+
+fn write_row(writer: &mut dyn Write, row: &[&str], shown: usize, sanitize: bool) -> Result<()> {
+    write!(writer, "|")?;
+    for i in 0..shown {
+        let cell = row.get(i).copied().unwrap_or("");
+        write!(writer, " {} |", escape_cell(cell, sanitize))?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn write_hidden_note(writer: &mut dyn Write, hidden: usize) -> Result<()> {
+    if hidden > 0 {
+        let plural = if hidden == 1 { "" } else { "s" };
+        writeln!(writer)?;
+        writeln!(writer, "*… {hidden} more column{plural}*")?;
+    }
+    Ok(())
+}
+
+fn escape_cell(s: &str, sanitize: bool) -> String {
+    if sanitize {
+        cells::sanitize(s).replace('|', "\\|")
+    } else {
+        s.replace('|', "\\|")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::CsvOptions;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn convert_with(input: &str, csv: CsvOptions) -> String {
+        let converter = CsvConverter::new(ConvertOptions {
+            csv,
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    #[case::plain(
+        CsvOptions::default(),
+        "| a | b | c |\n|---|---|---|\n| 1 | 2 | 3 |\n"
+    )]
+    #[case::max_columns(
+        CsvOptions { max_columns: Some(2), ..Default::default() },
+        "| a | b |\n|---|---|\n| 1 | 2 |\n\n*… 1 more column*\n"
+    )]
+    #[case::transpose(
+        CsvOptions { transpose: true, ..Default::default() },
+        "| Column | 1 |\n|---|---|\n| a | 1 |\n| b | 2 |\n| c | 3 |\n"
+    )]
+    fn test_table_layout(#[case] csv: CsvOptions, #[case] expected: &str) {
+        assert_eq!(convert_with("a,b,c\n1,2,3\n", csv), expected);
+    }
+
+    #[rstest]
+    fn test_transpose_with_max_columns() {
+        let csv = CsvOptions {
+            transpose: true,
+            max_columns: Some(2),
+            ..Default::default()
+        };
+        let output = convert_with("a,b\n1,2\n3,4\n5,6\n", csv);
+        assert_eq!(
+            output,
+            "| Column | 1 |\n|---|---|\n| a | 1 |\n| b | 2 |\n\n*… 2 more columns*\n"
+        );
+    }
+
+    #[rstest]
+    fn test_group_by() {
+        let csv = CsvOptions {
+            group_by: Some("team".to_string()),
+            ..Default::default()
+        };
+        let output = convert_with("name,team\nAlice,red\nBob,blue\nCarol,red\nDan,\n", csv);
+        assert_eq!(
+            output,
+            "## red\n\n| name |\n|---|\n| Alice |\n| Carol |\n\n\
+             ## blue\n\n| name |\n|---|\n| Bob |\n\n\
+             ## (empty)\n\n| name |\n|---|\n| Dan |\n"
+        );
+    }
+
+    #[rstest]
+    fn test_group_by_only_column() {
+        let csv = CsvOptions {
+            group_by: Some("team".to_string()),
+            ..Default::default()
+        };
+        let output = convert_with("team\nred\nblue\nred\n", csv);
+        assert_eq!(output, "## red\n\n*2 rows*\n\n## blue\n\n*1 row*\n");
+    }
+
+    #[rstest]
+    fn test_group_by_unknown_column() {
+        let converter = CsvConverter::new(ConvertOptions {
+            csv: CsvOptions {
+                group_by: Some("missing".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        assert!(converter.convert(b"a,b\n1,2\n", &mut output).is_err());
+    }
+
+    #[rstest]
+    fn test_pipe_escaped() {
+        let output = convert_with("a\nx|y\n", CsvOptions::default());
+        assert!(output.contains("x\\|y"));
+    }
+
+    #[rstest]
+    #[case::off(false, "| =1+1 | <b>x</b> \\| y | -5 |\n")]
+    #[case::on(true, "| '=1+1 | &lt;b&gt;x&lt;/b&gt; \\| y | -5 |\n")]
+    fn test_sanitize_cells(#[case] sanitize_cells: bool, #[case] expected_row: &str) {
+        let converter = CsvConverter::new(ConvertOptions {
+            sanitize_cells,
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter
+            .convert(b"a,b,c\n=1+1,<b>x</b> | y,-5\n", &mut output)
+            .unwrap();
+        assert!(String::from_utf8(output).unwrap().ends_with(expected_row));
+    }
 }
@@ -0,0 +1,214 @@
+use std::io::Write;
+
+use super::{escape_cell, read_records, write_header, write_row};
+use crate::error::{Error, Result};
+
+/// One CSV file of a dataset, read in full.
+#[derive(Debug, PartialEq)]
+pub struct DatasetFile {
+    pub name: String,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl DatasetFile {
+    pub fn read(name: &str, input: &[u8]) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(input);
+        let headers = reader
+            .headers()
+            .map_err(|e| Error::Conversion {
+                format: "csv",
+                message: format!("{name}: {e}"),
+            })?
+            .iter()
+            .map(str::to_string)
+            .collect();
+        Ok(Self {
+            name: name.to_string(),
+            headers,
+            rows: read_records(&mut reader)?,
+        })
+    }
+}
+
+/// Write one report describing `files` as a single dataset: the columns
+/// they share, per-file row counts and, with `preview`, a table of up to that
+/// many rows from each file restricted to the shared columns. With
+/// `sanitize`, preview cells go through the same sanitization as
+/// `--sanitize-cells` tables.
+pub fn write_dataset_as_markdown(
+    writer: &mut dyn Write,
+    title: &str,
+    files: &[DatasetFile],
+    preview: Option<usize>,
+    sanitize: bool,
+) -> Result<()> {
+    writeln!(writer, "# {title}")?;
+    writeln!(writer)?;
+    if files.is_empty() {
+        writeln!(writer, "*No CSV files*")?;
+        return Ok(());
+    }
+
+    // Every column in the order it is first seen, with the files that have it
+    let mut columns: Vec<(&str, usize)> = Vec::new();
+    for file in files {
+        for header in &file.headers {
+            match columns.iter_mut().find(|(name, _)| name == header) {
+                Some((_, count)) => *count += 1,
+                None => columns.push((header, 1)),
+            }
+        }
+    }
+    let shared: Vec<&str> = columns
+        .iter()
+        .filter(|(_, count)| *count == files.len())
+        .map(|(name, _)| *name)
+        .collect();
+
+    let total: usize = files.iter().map(|f| f.rows.len()).sum();
+    writeln!(writer, "- **Files**: {}", files.len())?;
+    writeln!(writer, "- **Rows**: {total}")?;
+    if shared.len() == columns.len() {
+        writeln!(writer, "- **Schema**: shared by all files")?;
+    } else {
+        writeln!(
+            writer,
+            "- **Schema**: {} of {} columns shared by all files",
+            shared.len(),
+            columns.len()
+        )?;
+    }
+
+    writeln!(writer)?;
+    writeln!(writer, "## Columns")?;
+    writeln!(writer)?;
+    writeln!(writer, "| Column | Files |")?;
+    writeln!(writer, "|---|---|")?;
+    for (name, count) in &columns {
+        writeln!(
+            writer,
+            "| {} | {count}/{} |",
+            escape_cell(name, false),
+            files.len()
+        )?;
+    }
+
+    writeln!(writer)?;
+    writeln!(writer, "## Files")?;
+    writeln!(writer)?;
+    writeln!(writer, "| File | Rows | Missing columns | Extra columns |")?;
+    writeln!(writer, "|---|---|---|---|")?;
+    for file in files {
+        let missing: Vec<&str> = columns
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| !shared.contains(name) && !file.headers.iter().any(|h| h == name))
+            .collect();
+        let extra: Vec<&str> = file
+            .headers
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !shared.contains(name))
+            .collect();
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} |",
+            escape_cell(&file.name, false),
+            file.rows.len(),
+            escape_cell(&missing.join(", "), false),
+            escape_cell(&extra.join(", "), false)
+        )?;
+    }
+
+    if let Some(limit) = preview {
+        writeln!(writer)?;
+        writeln!(writer, "## Preview")?;
+        writeln!(writer)?;
+        let mut headers = vec!["File".to_string()];
+        headers.extend(shared.iter().map(|name| name.to_string()));
+        let shown = write_header(writer, &headers, None, sanitize)?;
+        for file in files {
+            let indices: Vec<Option<usize>> = shared
+                .iter()
+                .map(|name| file.headers.iter().position(|h| h == name))
+                .collect();
+            for record in file.rows.iter().take(limit) {
+                let mut row = vec![file.name.as_str()];
+                row.extend(
+                    indices
+                        .iter()
+                        .map(|i| i.and_then(|i| record.get(i)).map_or("", String::as_str)),
+                );
+                write_row(writer, &row, shown, sanitize)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn report(files: &[(&str, &str)], preview: Option<usize>) -> String {
+        let files: Vec<DatasetFile> = files
+            .iter()
+            .map(|(name, csv)| DatasetFile::read(name, csv.as_bytes()).unwrap())
+            .collect();
+        let mut output = Vec::new();
+        write_dataset_as_markdown(&mut output, "sales", &files, preview, false).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_shared_schema() {
+        assert_eq!(
+            report(
+                &[("jan.csv", "id,amount\n1,10\n2,20\n"), ("feb.csv", "id,amount\n3,30\n")],
+                None
+            ),
+            "# sales\n\n\
+             - **Files**: 2\n- **Rows**: 3\n- **Schema**: shared by all files\n\n\
+             ## Columns\n\n| Column | Files |\n|---|---|\n| id | 2/2 |\n| amount | 2/2 |\n\n\
+             ## Files\n\n| File | Rows | Missing columns | Extra columns |\n|---|---|---|---|\n\
+             | jan.csv | 2 |  |  |\n| feb.csv | 1 |  |  |\n"
+        );
+    }
+
+    #[rstest]
+    fn test_differing_columns_with_preview() {
+        let output = report(
+            &[
+                ("jan.csv", "id,amount,note\n1,10,first\n2,20,\n"),
+                ("feb.csv", "amount,id,region\n30,3,EU\n"),
+            ],
+            Some(1),
+        );
+        assert!(
+            output.contains("- **Schema**: 2 of 4 columns shared by all files\n"),
+            "{output}"
+        );
+        assert!(
+            output.contains("| jan.csv | 2 | region | note |\n| feb.csv | 1 | note | region |\n"),
+            "{output}"
+        );
+        assert!(
+            output.ends_with(
+                "## Preview\n\n| File | id | amount |\n|---|---|---|\n\
+                 | jan.csv | 1 | 10 |\n| feb.csv | 3 | 30 |\n"
+            ),
+            "{output}"
+        );
+    }
+
+    #[rstest]
+    fn test_no_files() {
+        assert_eq!(report(&[], Some(5)), "# sales\n\n*No CSV files*\n");
+    }
+}
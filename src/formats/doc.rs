@@ -0,0 +1,624 @@
+use std::io::{Cursor, Read, Write};
+
+use crate::converter::Converter;
+use crate::error::{Error, Result};
+
+/// Converts Word 97-2003 binary documents (`.doc`). The text comes from
+/// the piece table in the `WordDocument` stream, and the paragraph
+/// properties (PAPX) mark headings, list items and table rows.
+pub struct DocConverter;
+
+impl Converter for DocConverter {
+    fn format_name(&self) -> &'static str {
+        "doc"
+    }
+
+    fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
+        let mut file = cfb::CompoundFile::open(Cursor::new(input))
+            .map_err(|e| conversion_error(e.to_string()))?;
+        let word = read_stream(&mut file, "/WordDocument")?;
+        let fib = Fib::parse(&word)?;
+        let table = read_stream(&mut file, fib.table_stream)?;
+        let pieces = read_pieces(&table, fib.clx)?;
+        let runs = read_paragraph_runs(&word, &table, fib.bte_papx);
+        let blocks = read_blocks(&word, &pieces, &runs, fib.ccp_text);
+        write_blocks(writer, &blocks)
+    }
+}
+
+fn conversion_error(message: impl Into<String>) -> Error {
+    Error::Conversion {
+        format: "doc",
+        message: message.into(),
+    }
+}
+
+fn read_stream(file: &mut cfb::CompoundFile<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>> {
+    let mut stream = file
+        .open_stream(name)
+        .map_err(|e| conversion_error(format!("Stream not found: {name}: {e}")))?;
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The parts of the File Information Block this converter uses.
+struct Fib {
+    table_stream: &'static str,
+    /// Length of the main document text in characters.
+    ccp_text: u32,
+    /// Offset and length in the table stream of the piece table (`Clx`)
+    /// and of the index of paragraph property pages (`PlcBtePapx`).
+    clx: (usize, usize),
+    bte_papx: (usize, usize),
+}
+
+impl Fib {
+    fn parse(word: &[u8]) -> Result<Self> {
+        let truncated = || conversion_error("Truncated file information block");
+        if u16_at(word, 0) != Some(0xA5EC) {
+            return Err(conversion_error("Not a Word document"));
+        }
+        let n_fib = u16_at(word, 2).ok_or_else(truncated)?;
+        let flags = u16_at(word, 0x0A).ok_or_else(truncated)?;
+        if flags & 0x0100 != 0 {
+            return Err(conversion_error("Encrypted documents are not supported"));
+        }
+        if n_fib < 0x00C0 {
+            return Err(conversion_error(
+                "Word 6.0 and Word 95 documents are not supported",
+            ));
+        }
+
+        // FibBase, then three arrays, each preceded by its length: 16-bit
+        // values, 32-bit values, and offset/length pairs
+        let csw = usize::from(u16_at(word, 32).ok_or_else(truncated)?);
+        let rg_lw = 34 + csw * 2 + 2;
+        let cslw = usize::from(u16_at(word, rg_lw - 2).ok_or_else(truncated)?);
+        let rg_fc_lcb = rg_lw + cslw * 4 + 2;
+        let pair = |index: usize| -> Result<(usize, usize)> {
+            let at = rg_fc_lcb + index * 8;
+            let fc = u32_at(word, at).ok_or_else(truncated)?;
+            let lcb = u32_at(word, at + 4).ok_or_else(truncated)?;
+            Ok((fc as usize, lcb as usize))
+        };
+        Ok(Self {
+            table_stream: if flags & 0x0200 != 0 {
+                "/1Table"
+            } else {
+                "/0Table"
+            },
+            ccp_text: u32_at(word, rg_lw + 12).ok_or_else(truncated)?,
+            bte_papx: pair(13)?,
+            clx: pair(33)?,
+        })
+    }
+}
+
+/// A run of text stored contiguously in the `WordDocument` stream.
+struct Piece {
+    cp_start: u32,
+    cp_end: u32,
+    /// Byte offset of the first character.
+    fc: u32,
+    /// One byte (cp1252) per character rather than two (UTF-16).
+    compressed: bool,
+}
+
+fn read_pieces(table: &[u8], (fc, lcb): (usize, usize)) -> Result<Vec<Piece>> {
+    let missing = || conversion_error("Missing piece table");
+    let clx = table.get(fc..fc + lcb).ok_or_else(missing)?;
+    let mut pos = 0;
+    // Property modifiers (`Prc`) precede the piece table (`Pcdt`)
+    while clx.get(pos) == Some(&0x01) {
+        let size = u16_at(clx, pos + 1).ok_or_else(missing)? as i16;
+        pos += 3 + usize::try_from(size).map_err(|_| missing())?;
+    }
+    if clx.get(pos) != Some(&0x02) {
+        return Err(missing());
+    }
+    let lcb = u32_at(clx, pos + 1).ok_or_else(missing)? as usize;
+    let plc = clx.get(pos + 5..pos + 5 + lcb).ok_or_else(missing)?;
+    // n + 1 character positions, then n 8-byte piece descriptors
+    let n = lcb.saturating_sub(4) / 12;
+    (0..n)
+        .map(|i| {
+            let fc = u32_at(plc, 4 * (n + 1) + i * 8 + 2).ok_or_else(missing)?;
+            let compressed = fc & 0x4000_0000 != 0;
+            let fc = fc & 0x3FFF_FFFF;
+            Ok(Piece {
+                cp_start: u32_at(plc, i * 4).ok_or_else(missing)?,
+                cp_end: u32_at(plc, (i + 1) * 4).ok_or_else(missing)?,
+                fc: if compressed { fc / 2 } else { fc },
+                compressed,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct ParagraphProps {
+    /// Style index; 1 to 9 are the built-in headings.
+    istd: u16,
+    in_table: bool,
+    /// The paragraph mark ends a table row rather than a cell.
+    row_end: bool,
+    list: bool,
+    list_level: u8,
+    /// Outline level set on the paragraph itself, 0 to 8.
+    outline: Option<u8>,
+}
+
+/// Paragraph properties of the paragraphs whose marks fall in a byte range
+/// of the `WordDocument` stream.
+struct ParagraphRun {
+    fc_start: u32,
+    fc_end: u32,
+    props: ParagraphProps,
+}
+
+/// Read every paragraph property page (`PapxFkp`). Documents without them
+/// are read as plain paragraphs.
+fn read_paragraph_runs(word: &[u8], table: &[u8], (fc, lcb): (usize, usize)) -> Vec<ParagraphRun> {
+    let Some(plc) = table.get(fc..fc + lcb) else {
+        return Vec::new();
+    };
+    let n = lcb.saturating_sub(4) / 8;
+    let mut runs = Vec::new();
+    for i in 0..n {
+        let Some(pn) = u32_at(plc, 4 * (n + 1) + i * 4) else {
+            break;
+        };
+        let start = (pn & 0x003F_FFFF) as usize * 512;
+        let Some(page) = word.get(start..start + 512) else {
+            continue;
+        };
+        let crun = usize::from(page[511]);
+        for j in 0..crun {
+            let (Some(fc_start), Some(fc_end)) = (u32_at(page, j * 4), u32_at(page, j * 4 + 4))
+            else {
+                break;
+            };
+            // Each BxPap is a word offset of the PAPX, then 12 reserved bytes
+            let offset = usize::from(page.get(4 * (crun + 1) + j * 13).copied().unwrap_or(0)) * 2;
+            runs.push(ParagraphRun {
+                fc_start,
+                fc_end,
+                props: read_papx(page, offset).unwrap_or_default(),
+            });
+        }
+    }
+    runs.sort_by_key(|run| run.fc_start);
+    runs
+}
+
+fn read_papx(page: &[u8], offset: usize) -> Option<ParagraphProps> {
+    if offset == 0 {
+        return None;
+    }
+    let cb = usize::from(*page.get(offset)?);
+    let (start, len) = if cb != 0 {
+        (offset + 1, 2 * cb - 1)
+    } else {
+        (offset + 2, 2 * usize::from(*page.get(offset + 1)?))
+    };
+    let grpprl = page.get(start..start + len)?;
+    let mut props = ParagraphProps {
+        istd: u16_at(grpprl, 0)?,
+        ..Default::default()
+    };
+    let mut pos = 2;
+    while let Some(sprm) = u16_at(grpprl, pos) {
+        pos += 2;
+        let operand = grpprl.get(pos..).unwrap_or_default();
+        // The top three bits give the operand size
+        let size = match sprm >> 13 {
+            0 | 1 => 1,
+            2 | 4 | 5 => 2,
+            3 => 4,
+            7 => 3,
+            // sprmTDefTable has a 16-bit size; the others an 8-bit one
+            _ if sprm == 0xD608 => usize::from(u16_at(operand, 0)?) + 1,
+            _ if sprm == 0xC615 && operand.first() == Some(&255) => return Some(props),
+            _ => usize::from(*operand.first()?) + 1,
+        };
+        let value = operand.first().copied().unwrap_or(0);
+        match sprm {
+            0x2416 => props.in_table = value != 0,
+            0x2417 => props.row_end = value != 0,
+            0x6649 => props.in_table |= u32_at(operand, 0).is_some_and(|itap| itap > 0),
+            0x260A => props.list_level = value,
+            0x460B => props.list = u16_at(operand, 0).is_some_and(|ilfo| ilfo != 0),
+            0x2640 if value < 9 => props.outline = Some(value),
+            _ => {}
+        }
+        pos += size;
+    }
+    Some(props)
+}
+
+fn props_at(runs: &[ParagraphRun], fc: u32) -> ParagraphProps {
+    let index = runs.partition_point(|run| run.fc_start <= fc);
+    index
+        .checked_sub(1)
+        .map(|i| &runs[i])
+        .filter(|run| fc < run.fc_end)
+        .map(|run| run.props)
+        .unwrap_or_default()
+}
+
+/// The characters of the main document text with the stream offset of
+/// each, which locates its paragraph properties.
+fn read_text(word: &[u8], pieces: &[Piece], ccp_text: u32) -> Vec<(char, u32)> {
+    let mut chars = Vec::new();
+    for piece in pieces {
+        let end = piece.cp_end.min(ccp_text);
+        if piece.cp_start >= end {
+            continue;
+        }
+        let count = (end - piece.cp_start) as usize;
+        if piece.compressed {
+            for i in 0..count {
+                let fc = piece.fc + i as u32;
+                let Some(&byte) = word.get(fc as usize) else {
+                    break;
+                };
+                chars.push((cp1252(byte), fc));
+            }
+        } else {
+            let units: Vec<u16> = (0..count)
+                .map_while(|i| u16_at(word, piece.fc as usize + i * 2))
+                .collect();
+            let mut fc = piece.fc;
+            for c in char::decode_utf16(units.iter().copied()) {
+                let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+                chars.push((c, fc));
+                fc += 2 * c.len_utf16() as u32;
+            }
+        }
+    }
+    chars
+}
+
+/// Decode a byte of Windows-1252, which differs from Latin-1 in 0x80-0x9F.
+fn cp1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž',
+        '\u{8F}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Block {
+    Heading(u8, String),
+    Paragraph(String),
+    ListItem(u8, String),
+    Table(Vec<Vec<String>>),
+}
+
+/// A field (`0x13` instruction `0x14` result `0x15`) being read.
+struct Field {
+    instr: String,
+    in_result: bool,
+    /// Where the result starts in the paragraph text, while it is in the
+    /// paragraph the field began in.
+    start: Option<usize>,
+}
+
+fn read_blocks(word: &[u8], pieces: &[Piece], runs: &[ParagraphRun], ccp_text: u32) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut cell = String::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut fields: Vec<Field> = Vec::new();
+
+    for (c, fc) in read_text(word, pieces, ccp_text) {
+        if let Some(field) = fields.last_mut()
+            && !field.in_result
+            && !matches!(c, '\u{13}' | '\u{14}' | '\u{15}')
+        {
+            field.instr.push(c);
+            continue;
+        }
+        match c {
+            '\u{13}' => fields.push(Field {
+                instr: String::new(),
+                in_result: false,
+                start: None,
+            }),
+            '\u{14}' => {
+                if let Some(field) = fields.last_mut() {
+                    field.in_result = true;
+                    field.start = Some(text.len());
+                }
+            }
+            '\u{15}' => {
+                if let Some(field) = fields.pop()
+                    && let Some(start) = field.start
+                    && let Some(url) = hyperlink_target(&field.instr)
+                    && start < text.len()
+                {
+                    let label = text.split_off(start);
+                    text.push_str(&format!("[{}]({url})", label.trim()));
+                }
+            }
+            // Paragraph, cell and page marks
+            '\r' | '\u{07}' | '\u{0C}' => {
+                for field in &mut fields {
+                    field.start = None;
+                }
+                let props = props_at(runs, fc);
+                let paragraph = std::mem::take(&mut text).trim().to_string();
+                if props.in_table {
+                    if props.row_end {
+                        if !row.is_empty() {
+                            rows.push(std::mem::take(&mut row));
+                        }
+                    } else if c == '\u{07}' {
+                        cell.push_str(&paragraph);
+                        row.push(std::mem::take(&mut cell));
+                    } else if !paragraph.is_empty() {
+                        cell.push_str(&paragraph);
+                        cell.push('\n');
+                    }
+                    continue;
+                }
+                if !rows.is_empty() {
+                    blocks.push(Block::Table(std::mem::take(&mut rows)));
+                }
+                if paragraph.is_empty() {
+                    continue;
+                }
+                let heading = match (props.istd, props.outline) {
+                    (1..=9, _) => Some(props.istd.min(6) as u8),
+                    (_, Some(level)) => Some((level + 1).min(6)),
+                    _ => None,
+                };
+                blocks.push(match heading {
+                    Some(level) => Block::Heading(level, paragraph),
+                    None if props.list => Block::ListItem(props.list_level, paragraph),
+                    None => Block::Paragraph(paragraph),
+                });
+            }
+            '\u{0B}' => text.push('\n'),
+            '\u{1E}' => text.push('-'),
+            '\t' => text.push('\t'),
+            // Pictures, note references and other anchors have no text
+            c if c < ' ' => {}
+            c => text.push(c),
+        }
+    }
+    if !row.is_empty() {
+        rows.push(row);
+    }
+    if !rows.is_empty() {
+        blocks.push(Block::Table(rows));
+    }
+    let paragraph = text.trim();
+    if !paragraph.is_empty() {
+        blocks.push(Block::Paragraph(paragraph.to_string()));
+    }
+    blocks
+}
+
+/// The target of a `HYPERLINK "url"` field instruction.
+fn hyperlink_target(instr: &str) -> Option<&str> {
+    let rest = instr.trim().strip_prefix("HYPERLINK")?;
+    let start = rest.find('"')? + 1;
+    let end = start + rest[start..].find('"')?;
+    Some(&rest[start..end])
+}
+
+fn write_blocks(writer: &mut dyn Write, blocks: &[Block]) -> Result<()> {
+    for (idx, block) in blocks.iter().enumerate() {
+        let continued_list = idx > 0
+            && matches!(block, Block::ListItem(..))
+            && matches!(blocks[idx - 1], Block::ListItem(..));
+        if idx > 0 && !continued_list {
+            writeln!(writer)?;
+        }
+        match block {
+            Block::Heading(level, text) => {
+                writeln!(writer, "{} {text}", "#".repeat(usize::from(*level)))?;
+            }
+            Block::Paragraph(text) => writeln!(writer, "{text}")?,
+            Block::ListItem(level, text) => {
+                writeln!(writer, "{}- {text}", "  ".repeat(usize::from(*level)))?;
+            }
+            Block::Table(rows) => write_table(writer, rows)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_table(writer: &mut dyn Write, rows: &[Vec<String>]) -> Result<()> {
+    let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if col_count == 0 {
+        return Ok(());
+    }
+    for (idx, row) in rows.iter().enumerate() {
+        write!(writer, "|")?;
+        for i in 0..col_count {
+            let cell = row.get(i).map(|s| s.trim_end()).unwrap_or("");
+            write!(
+                writer,
+                " {} |",
+                cell.replace('|', "\\|").replace('\n', "<br>")
+            )?;
+        }
+        writeln!(writer)?;
+        if idx == 0 {
+            writeln!(writer, "|{}", "---|".repeat(col_count))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    const TEXT_FC: usize = 1024;
+    const FKP_PAGE: usize = 4;
+
+    /// A `.doc` holding `paragraphs`, each text ending in its mark and the
+    /// PAPX (style index, then sprms) of that mark.
+    fn make_doc(paragraphs: &[(&str, &[u8])], compressed: bool) -> Vec<u8> {
+        let text: String = paragraphs.iter().map(|(text, _)| *text).collect();
+        let width = if compressed { 1 } else { 2 };
+        let ccp = text.chars().count() as u32;
+
+        let mut word = vec![0u8; (FKP_PAGE + 1) * 512];
+        word[0..2].copy_from_slice(&0xA5ECu16.to_le_bytes());
+        word[2..4].copy_from_slice(&0x00C1u16.to_le_bytes());
+        word[0x0A..0x0C].copy_from_slice(&0x0200u16.to_le_bytes());
+        word[32..34].copy_from_slice(&14u16.to_le_bytes());
+        word[62..64].copy_from_slice(&22u16.to_le_bytes());
+        word[76..80].copy_from_slice(&ccp.to_le_bytes());
+        word[152..154].copy_from_slice(&93u16.to_le_bytes());
+        for (i, c) in text.chars().enumerate() {
+            if compressed {
+                word[TEXT_FC + i] = c as u8;
+            } else {
+                word[TEXT_FC + i * 2..TEXT_FC + i * 2 + 2]
+                    .copy_from_slice(&(c as u16).to_le_bytes());
+            }
+        }
+
+        // One property page with a run per paragraph, PAPXs from the end
+        let page = &mut word[FKP_PAGE * 512..];
+        let crun = paragraphs.len();
+        page[511] = crun as u8;
+        let mut fc = TEXT_FC as u32;
+        let mut papx_end = 511;
+        for (j, (text, grpprl)) in paragraphs.iter().enumerate() {
+            page[j * 4..j * 4 + 4].copy_from_slice(&fc.to_le_bytes());
+            fc += (text.chars().count() * width) as u32;
+            let papx = if grpprl.len() % 2 == 1 {
+                [&[grpprl.len().div_ceil(2) as u8], *grpprl].concat()
+            } else {
+                [&[0, (grpprl.len() / 2) as u8], *grpprl].concat()
+            };
+            let offset = (papx_end - papx.len()) & !1;
+            page[offset..offset + papx.len()].copy_from_slice(&papx);
+            page[4 * (crun + 1) + j * 13] = (offset / 2) as u8;
+            papx_end = offset;
+        }
+        page[crun * 4..crun * 4 + 4].copy_from_slice(&fc.to_le_bytes());
+
+        let piece_fc = if compressed {
+            (TEXT_FC as u32 * 2) | 0x4000_0000
+        } else {
+            TEXT_FC as u32
+        };
+        let mut plc_pcd = [0u32.to_le_bytes(), ccp.to_le_bytes()].concat();
+        plc_pcd.extend([0, 0]);
+        plc_pcd.extend(piece_fc.to_le_bytes());
+        plc_pcd.extend([0, 0]);
+        let mut table = vec![0x02];
+        table.extend((plc_pcd.len() as u32).to_le_bytes());
+        table.extend(&plc_pcd);
+        let bte_fc = table.len() as u32;
+        table.extend((TEXT_FC as u32).to_le_bytes());
+        table.extend(fc.to_le_bytes());
+        table.extend((FKP_PAGE as u32).to_le_bytes());
+        let pair = |index: usize, fc: u32, lcb: u32, word: &mut Vec<u8>| {
+            let at = 154 + index * 8;
+            word[at..at + 4].copy_from_slice(&fc.to_le_bytes());
+            word[at + 4..at + 8].copy_from_slice(&lcb.to_le_bytes());
+        };
+        pair(33, 0, bte_fc, &mut word);
+        pair(13, bte_fc, 12, &mut word);
+
+        let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        file.create_stream("/WordDocument")
+            .unwrap()
+            .write_all(&word)
+            .unwrap();
+        file.create_stream("/1Table")
+            .unwrap()
+            .write_all(&table)
+            .unwrap();
+        file.flush().unwrap();
+        file.into_inner().into_inner()
+    }
+
+    const NORMAL: &[u8] = &[0, 0];
+    const HEADING_1: &[u8] = &[1, 0];
+    const CELL: &[u8] = &[0, 0, 0x16, 0x24, 1];
+    const ROW_END: &[u8] = &[0, 0, 0x16, 0x24, 1, 0x17, 0x24, 1];
+    const LIST_0: &[u8] = &[0, 0, 0x0B, 0x46, 1, 0, 0x0A, 0x26, 0];
+    const LIST_1: &[u8] = &[0, 0, 0x0B, 0x46, 1, 0, 0x0A, 0x26, 1];
+
+    fn convert(input: &[u8]) -> String {
+        let mut output = Vec::new();
+        DocConverter.convert(input, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    #[case::utf16(false)]
+    #[case::compressed(true)]
+    fn test_document(#[case] compressed: bool) {
+        let doc = make_doc(
+            &[
+                ("Title\r", HEADING_1),
+                (
+                    "See \u{13} HYPERLINK \"https://example.com\" \u{14}site\u{15} now.\r",
+                    NORMAL,
+                ),
+                ("First\r", LIST_0),
+                ("Second\r", LIST_1),
+                ("A\u{07}", CELL),
+                ("B\u{07}", CELL),
+                ("\u{07}", ROW_END),
+                ("1\u{07}", CELL),
+                ("2\u{07}", CELL),
+                ("\u{07}", ROW_END),
+                ("End\r", NORMAL),
+            ],
+            compressed,
+        );
+        assert_eq!(
+            convert(&doc),
+            "# Title\n\nSee [site](https://example.com) now.\n\n- First\n  - Second\n\n\
+             | A | B |\n|---|---|\n| 1 | 2 |\n\nEnd\n"
+        );
+    }
+
+    #[rstest]
+    fn test_cp1252() {
+        assert_eq!(
+            [0x93, b'a', 0x94, 0x80]
+                .map(cp1252)
+                .iter()
+                .collect::<String>(),
+            "“a”€"
+        );
+    }
+
+    #[rstest]
+    fn test_not_a_compound_file() {
+        assert!(
+            DocConverter
+                .convert(b"plain text", &mut Vec::new())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::detect::Format;
+use crate::error::Result;
+use crate::options::ConvertOptions;
+
+/// Convert a file found inside another document, such as an archive entry
+/// or a PDF attachment, pushing its headings down `levels` so they nest
+/// under the section that introduces it. Returns `None` when the format is
+/// not recognised. Markdown sources are included as they are.
+pub(crate) fn convert(
+    name: &str,
+    bytes: &[u8],
+    options: &ConvertOptions,
+    levels: usize,
+) -> Result<Option<String>> {
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    let Some(format) = Format::detect(Some(file_name), bytes) else {
+        return Ok(None);
+    };
+    convert_as(format, name, bytes, options, levels).map(Some)
+}
+
+/// Convert an embedded file whose format is already known.
+pub(crate) fn convert_as(
+    format: Format,
+    name: &str,
+    bytes: &[u8],
+    options: &ConvertOptions,
+    levels: usize,
+) -> Result<String> {
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    let options = ConvertOptions {
+        input_name: Some(file_name.to_string()),
+        ..options.clone()
+    };
+    let converter = super::base_converter(format, &options)?;
+    let mut output = Vec::new();
+    // Only Markdown sources convert to other formats; keep them as they are
+    if converter.output_extension() != "md" {
+        output.extend_from_slice(bytes);
+    } else {
+        converter.convert(bytes, &mut output)?;
+    }
+    let markdown = String::from_utf8_lossy(&output);
+    Ok(demote_headings(markdown.trim_end(), levels))
+}
+
+/// Push ATX headings down by `levels` so converted entries nest under their
+/// section, leaving fenced code alone. Headings stop at level 6.
+fn demote_headings(markdown: &str, levels: usize) -> String {
+    let mut in_code = false;
+    let mut out = String::with_capacity(markdown.len());
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        }
+        let depth = line.chars().take_while(|&c| c == '#').count();
+        let is_heading = (1..=6).contains(&depth)
+            && matches!(
+                line[depth..].chars().next(),
+                None | Some(' ' | '\t' | '\r' | '\n')
+            );
+        if !in_code && is_heading {
+            out.push_str(&"#".repeat((depth + levels).min(6) - depth));
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_demote_headings() {
+        let markdown = "# Archive\n\ntext\n\n```\n# comment\n```\n\n##### Deep\n#hashtag\n";
+        assert_eq!(
+            demote_headings(markdown, 2),
+            "### Archive\n\ntext\n\n```\n# comment\n```\n\n###### Deep\n#hashtag\n"
+        );
+    }
+}
@@ -1,12 +1,34 @@
+use std::collections::{HashMap, HashSet};
 use std::io::{Cursor, Read, Write};
+use std::ops::Range;
 
 use quick_xml::Reader;
 use quick_xml::events::Event;
 
+use super::anchors::Slugger;
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::media::{data_uri, input_stem, write_media};
+use crate::metadata::Counts;
+use crate::options::ConvertOptions;
+use crate::warnings;
 
-pub struct EpubConverter;
+mod images;
+mod toc;
+
+/// Average silent reading speed used for reading-time estimates.
+const WORDS_PER_MINUTE: usize = 238;
+
+#[derive(Default)]
+pub struct EpubConverter {
+    options: ConvertOptions,
+}
+
+impl EpubConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for EpubConverter {
     fn format_name(&self) -> &'static str {
@@ -25,7 +47,12 @@ impl Converter for EpubConverter {
 
         // Parse the OPF for metadata and spine order
         let opf_content = read_entry(&mut archive, &opf_path)?;
-        let (metadata, spine_items) = parse_opf(&opf_content)?;
+        let Opf {
+            metadata,
+            spine_items,
+            toc: toc_href,
+            images,
+        } = parse_opf(&opf_content).map_err(|e| e.in_entry(&opf_path))?;
 
         // Resolve the base directory of the OPF file
         let opf_dir = if let Some(pos) = opf_path.rfind('/') {
@@ -34,6 +61,109 @@ impl Converter for EpubConverter {
             ""
         };
 
+        // Load spine items (chapters)
+        let mut chapters: Vec<(String, Result<String>)> = Vec::new();
+        for item_path in &spine_items {
+            let full_path = if let Some(stripped) = item_path.strip_prefix('/') {
+                stripped.to_string()
+            } else {
+                format!("{opf_dir}{item_path}")
+            };
+
+            // Unreadable chapters are left out, or noted with --best-effort
+            match read_entry(&mut archive, &full_path) {
+                Ok(html_content) => chapters.push((full_path, Ok(html_content))),
+                Err(e) if self.options.best_effort => chapters.push((full_path, Err(e))),
+                Err(_) => {}
+            }
+        }
+
+        // Images are written to the media directory, or inlined, and the
+        // chapters point at them there
+        let images: HashSet<String> = images
+            .iter()
+            .map(|href| normalize_path(opf_dir, &percent_decode(href)))
+            .collect();
+        for (path, html) in &mut chapters {
+            if let Ok(html) = html {
+                *html = images::rewrite_images(html, path, |image| {
+                    self.image_link(&mut archive, &images, image)
+                })?;
+            }
+        }
+
+        // The table of contents titles the chapters it lists, at the level
+        // of their nesting, and its entries for places within a chapter set
+        // the level of the headings there
+        let toc_entries = toc_href
+            .map(|href| normalize_path(opf_dir, &percent_decode(&href)))
+            .and_then(|path| Some(toc::entries(&read_entry(&mut archive, &path).ok()?, &path)))
+            .unwrap_or_default();
+        let mut toc_titles: HashMap<&str, (&str, usize)> = HashMap::new();
+        for entry in &toc_entries {
+            let level = heading_level(entry.depth);
+            if toc_titles.contains_key(entry.path.as_str()) {
+                if let (Some(fragment), Some((_, Ok(html)))) = (
+                    &entry.fragment,
+                    chapters.iter_mut().find(|(path, _)| *path == entry.path),
+                ) {
+                    *html = relevel_heading(html, fragment, level);
+                }
+            } else {
+                toc_titles.insert(&entry.path, (&entry.label, level));
+            }
+        }
+        let titles: HashMap<&str, (String, usize)> = chapters
+            .iter()
+            .filter_map(|(path, html)| {
+                let html = html.as_ref().ok()?;
+                let title = match toc_titles.get(path.as_str()) {
+                    Some((label, level)) => (label.to_string(), *level),
+                    None => (chapter_title(html, metadata.title.as_deref())?, 2),
+                };
+                Some((path.as_str(), title))
+            })
+            .collect();
+
+        // Map chapter files and element ids to the heading anchors they become
+        // in the combined document, so cross-chapter links keep working.
+        let mut slugger = Slugger::default();
+        slugger.slug(metadata.title.as_deref().unwrap_or("EPUB"));
+        let anchors: HashMap<&str, ChapterAnchors> = chapters
+            .iter()
+            .filter_map(|(path, html)| Some((path.as_str(), html.as_ref().ok()?)))
+            .map(|(path, html)| {
+                let title = titles.get(path).map(|(title, _)| title.as_str());
+                (path, scan_anchors(html, title, &mut slugger))
+            })
+            .collect();
+
+        // Convert the chapters up front so the header can report their
+        // length. Skipped chapters have no word count.
+        let mut texts: Vec<(String, Option<usize>)> = Vec::new();
+        for (path, html_content) in &chapters {
+            let html_content = match html_content {
+                Ok(html_content) => rewrite_links(html_content, path, &anchors),
+                Err(e) => {
+                    let note = warnings::skipped_section(&format!("Chapter {path}"), e);
+                    texts.push((note, None));
+                    continue;
+                }
+            };
+            let text = html_to_markdown(&html_content).trim().to_string();
+            let text = match titles.get(path.as_str()) {
+                Some((title, level)) => {
+                    with_chapter_heading(&text, title, *level, opens_with_h1(&html_content))
+                }
+                None => text,
+            };
+            if !text.is_empty() {
+                let words = count_words(&text);
+                texts.push((text, Some(words)));
+            }
+        }
+        let total_words: usize = texts.iter().filter_map(|(_, words)| *words).sum();
+
         // Write metadata
         if let Some(title) = &metadata.title {
             writeln!(writer, "# {title}")?;
@@ -42,8 +172,17 @@ impl Converter for EpubConverter {
         }
         writeln!(writer)?;
 
-        if let Some(author) = &metadata.author {
-            writeln!(writer, "**Author**: {author}")?;
+        if !metadata.authors.is_empty() {
+            writeln!(writer, "**Author**: {}", metadata.authors.join(", "))?;
+        }
+        if !metadata.contributors.is_empty() {
+            writeln!(writer, "**Contributors**: {}", metadata.contributors.join(", "))?;
+        }
+        if let Some(series) = &metadata.series {
+            match &metadata.series_index {
+                Some(index) => writeln!(writer, "**Series**: {series} (#{index})")?,
+                None => writeln!(writer, "**Series**: {series}")?,
+            }
         }
         if let Some(language) = &metadata.language {
             writeln!(writer, "**Language**: {language}")?;
@@ -54,6 +193,16 @@ impl Converter for EpubConverter {
         if let Some(date) = &metadata.date {
             writeln!(writer, "**Date**: {date}")?;
         }
+        for identifier in &metadata.identifiers {
+            writeln!(writer, "**{}**: {}", identifier.label(), identifier.display_value())?;
+        }
+        if !metadata.subjects.is_empty() {
+            writeln!(writer, "**Subjects**: {}", metadata.subjects.join(", "))?;
+        }
+        if total_words > 0 {
+            writeln!(writer, "**Words**: {total_words}")?;
+            writeln!(writer, "**Reading time**: {}", reading_time(total_words))?;
+        }
         if let Some(description) = &metadata.description {
             writeln!(writer)?;
             writeln!(writer, "> {description}")?;
@@ -62,28 +211,17 @@ impl Converter for EpubConverter {
         writeln!(writer)?;
         writeln!(writer, "---")?;
 
-        // Process spine items (chapters)
-        let mut chapter_num = 0;
-        for item_path in &spine_items {
-            let full_path = if let Some(stripped) = item_path.strip_prefix('/') {
-                stripped.to_string()
-            } else {
-                format!("{opf_dir}{item_path}")
-            };
-
-            if let Ok(html_content) = read_entry(&mut archive, &full_path) {
-                let text = html_to_markdown(&html_content);
-                let text = text.trim();
-                if !text.is_empty() {
-                    chapter_num += 1;
-
-                    if chapter_num > 1 {
-                        writeln!(writer)?;
-                        writeln!(writer, "---")?;
-                    }
-                    writeln!(writer)?;
-                    writeln!(writer, "{text}")?;
+        for (i, (text, words)) in texts.iter().enumerate() {
+            if i > 0 {
+                writeln!(writer)?;
+                writeln!(writer, "---")?;
+            }
+            writeln!(writer)?;
+            match words {
+                Some(words) if self.options.epub.chapter_stats => {
+                    writeln!(writer, "{}", with_chapter_stats(text, *words))?
                 }
+                _ => writeln!(writer, "{text}")?,
             }
         }
 
@@ -91,14 +229,93 @@ impl Converter for EpubConverter {
     }
 }
 
+impl EpubConverter {
+    /// The reference to use for the image at archive path `path`: a file
+    /// written to the media directory or a `data:` URI. Paths the manifest
+    /// does not list as images have none, and neither does any image when
+    /// media is neither extracted nor embedded.
+    fn image_link(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        images: &HashSet<String>,
+        path: &str,
+    ) -> Result<Option<String>> {
+        let media = &self.options.media;
+        if !images.contains(path) || (media.extract_dir.is_none() && !media.embed) {
+            return Ok(None);
+        }
+        let mut bytes = Vec::new();
+        let read = archive
+            .by_name(path)
+            .map(|mut file| file.read_to_end(&mut bytes));
+        if !matches!(read, Ok(Ok(_))) {
+            warnings::warn(format!("Image {path} is missing from the book"));
+            return Ok(None);
+        }
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        match &self.options.media.extract_dir {
+            Some(dir) => {
+                let stem = input_stem(self.options.input_name.as_deref(), "book");
+                write_media(
+                    dir,
+                    &format!("{stem}-{file_name}"),
+                    &bytes,
+                    self.options.media.link_base.as_deref(),
+                )
+                .map(Some)
+            }
+            None => Ok(Some(data_uri(file_name, &bytes))),
+        }
+    }
+}
+
 #[derive(Default)]
 struct EpubMetadata {
     title: Option<String>,
-    author: Option<String>,
+    authors: Vec<String>,
+    contributors: Vec<String>,
     language: Option<String>,
     publisher: Option<String>,
     description: Option<String>,
     date: Option<String>,
+    identifiers: Vec<Identifier>,
+    subjects: Vec<String>,
+    series: Option<String>,
+    series_index: Option<String>,
+}
+
+struct Identifier {
+    scheme: Option<String>,
+    value: String,
+}
+
+impl Identifier {
+    fn is_isbn(&self) -> bool {
+        self.scheme
+            .as_deref()
+            .is_some_and(|s| s.eq_ignore_ascii_case("isbn"))
+            || self.value.to_ascii_lowercase().starts_with("urn:isbn:")
+    }
+
+    fn label(&self) -> &str {
+        if self.is_isbn() {
+            "ISBN"
+        } else {
+            "Identifier"
+        }
+    }
+
+    fn display_value(&self) -> &str {
+        if self.is_isbn() {
+            let prefix_len = "urn:isbn:".len();
+            if self.value.len() > prefix_len
+                && self.value[..prefix_len].eq_ignore_ascii_case("urn:isbn:")
+            {
+                return &self.value[prefix_len..];
+            }
+        }
+        &self.value
+    }
 }
 
 fn find_opf_path(archive: &mut zip::ZipArchive<Cursor<&[u8]>>) -> Result<String> {
@@ -116,9 +333,11 @@ fn find_opf_path(archive: &mut zip::ZipArchive<Cursor<&[u8]>>) -> Result<String>
             }
             Ok(Event::Eof) => break,
             Err(e) => {
-                return Err(Error::Conversion {
+                return Err(Error::Parse {
                     format: "epub",
-                    message: format!("Failed to parse container.xml: {e}"),
+                    entry: Some("META-INF/container.xml".into()),
+                    offset: Some(reader.error_position()),
+                    message: e.to_string(),
                 });
             }
             _ => {}
@@ -131,14 +350,31 @@ fn find_opf_path(archive: &mut zip::ZipArchive<Cursor<&[u8]>>) -> Result<String>
     })
 }
 
-fn parse_opf(content: &str) -> Result<(EpubMetadata, Vec<String>)> {
+/// What the converter reads from the package document.
+struct Opf {
+    metadata: EpubMetadata,
+    /// The hrefs of the chapters, in reading order.
+    spine_items: Vec<String>,
+    /// The href of the table of contents: the EPUB3 navigation document, or
+    /// else the EPUB2 NCX.
+    toc: Option<String>,
+    /// The hrefs of the images in the manifest.
+    images: Vec<String>,
+}
+
+fn parse_opf(content: &str) -> Result<Opf> {
     let mut metadata = EpubMetadata::default();
-    let mut manifest: Vec<(String, String)> = Vec::new(); // (id, href)
+    let mut manifest: Vec<ManifestItem> = Vec::new();
     let mut spine_ids: Vec<String> = Vec::new();
+    // id of the NCX, named by the spine's `toc` attribute
+    let mut ncx_id: Option<String> = None;
 
     let mut reader = Reader::from_str(content);
     let mut current_tag = String::new();
     let mut in_metadata = false;
+    let mut identifier_scheme: Option<String> = None;
+    // id of the EPUB3 `belongs-to-collection` meta, referenced by `group-position`
+    let mut collection_id: Option<String> = None;
 
     loop {
         match reader.read_event() {
@@ -146,64 +382,105 @@ fn parse_opf(content: &str) -> Result<(EpubMetadata, Vec<String>)> {
                 let local = local_name(e.name().as_ref());
                 match local.as_str() {
                     "metadata" => in_metadata = true,
-                    "title" | "creator" | "language" | "publisher" | "description" | "date"
+                    "title" | "creator" | "contributor" | "language" | "publisher"
+                    | "description" | "date" | "subject"
                         if in_metadata =>
                     {
                         current_tag = local.clone();
                     }
-                    "item" => {
-                        let mut id = String::new();
-                        let mut href = String::new();
-                        for attr in e.attributes().flatten() {
-                            match attr.key.as_ref() {
-                                b"id" => id = String::from_utf8_lossy(&attr.value).to_string(),
-                                b"href" => href = String::from_utf8_lossy(&attr.value).to_string(),
-                                _ => {}
+                    "identifier" if in_metadata => {
+                        identifier_scheme = e
+                            .attributes()
+                            .flatten()
+                            .find(|a| local_name(a.key.as_ref()) == "scheme")
+                            .map(|a| String::from_utf8_lossy(&a.value).to_string());
+                        current_tag = local.clone();
+                    }
+                    "meta" if in_metadata => {
+                        let attr = |name: &str| {
+                            e.attributes()
+                                .flatten()
+                                .find(|a| a.key.as_ref() == name.as_bytes())
+                                .map(|a| String::from_utf8_lossy(&a.value).to_string())
+                        };
+                        match attr("property").as_deref() {
+                            Some("belongs-to-collection") => {
+                                collection_id = attr("id").map(|id| format!("#{id}"));
+                                current_tag = "collection".to_string();
                             }
-                        }
-                        if !id.is_empty() && !href.is_empty() {
-                            manifest.push((id, href));
+                            Some("group-position")
+                                if collection_id.is_some() && attr("refines") == collection_id =>
+                            {
+                                current_tag = "group-position".to_string();
+                            }
+                            _ => {}
                         }
                     }
+                    "item" => manifest.extend(ManifestItem::read(&e)),
+                    "spine" => {
+                        ncx_id = e
+                            .attributes()
+                            .flatten()
+                            .find(|a| a.key.as_ref() == b"toc")
+                            .map(|a| String::from_utf8_lossy(&a.value).to_string());
+                    }
                     _ => {}
                 }
             }
             Ok(Event::Empty(e)) => {
                 let local = local_name(e.name().as_ref());
                 match local.as_str() {
-                    "item" => {
-                        let mut id = String::new();
-                        let mut href = String::new();
+                    "item" => manifest.extend(ManifestItem::read(&e)),
+                    "itemref" => {
                         for attr in e.attributes().flatten() {
-                            match attr.key.as_ref() {
-                                b"id" => id = String::from_utf8_lossy(&attr.value).to_string(),
-                                b"href" => href = String::from_utf8_lossy(&attr.value).to_string(),
-                                _ => {}
+                            if attr.key.as_ref() == b"idref" {
+                                spine_ids.push(String::from_utf8_lossy(&attr.value).to_string());
                             }
                         }
-                        if !id.is_empty() && !href.is_empty() {
-                            manifest.push((id, href));
-                        }
                     }
-                    "itemref" => {
+                    // Calibre stores series information as EPUB2-style name/content pairs
+                    "meta" if in_metadata => {
+                        let mut name = String::new();
+                        let mut content = String::new();
                         for attr in e.attributes().flatten() {
-                            if attr.key.as_ref() == b"idref" {
-                                spine_ids.push(String::from_utf8_lossy(&attr.value).to_string());
+                            match attr.key.as_ref() {
+                                b"name" => name = String::from_utf8_lossy(&attr.value).to_string(),
+                                b"content" => {
+                                    content = String::from_utf8_lossy(&attr.value).to_string()
+                                }
+                                _ => {}
                             }
                         }
+                        match name.as_str() {
+                            "calibre:series" if metadata.series.is_none() => {
+                                metadata.series = Some(content)
+                            }
+                            "calibre:series_index" if metadata.series_index.is_none() => {
+                                metadata.series_index = Some(content)
+                            }
+                            _ => {}
+                        }
                     }
                     _ => {}
                 }
             }
             Ok(Event::Text(e)) if !current_tag.is_empty() => {
-                let text = e.decode().unwrap_or_default().to_string();
+                let text = e.decode().unwrap_or_default().trim().to_string();
                 match current_tag.as_str() {
                     "title" => metadata.title = Some(text),
-                    "creator" => metadata.author = Some(text),
+                    "creator" => metadata.authors.push(text),
+                    "contributor" => metadata.contributors.push(text),
                     "language" => metadata.language = Some(text),
                     "publisher" => metadata.publisher = Some(text),
                     "description" => metadata.description = Some(text),
                     "date" => metadata.date = Some(text),
+                    "subject" => metadata.subjects.push(text),
+                    "identifier" => metadata.identifiers.push(Identifier {
+                        scheme: identifier_scheme.take(),
+                        value: text,
+                    }),
+                    "collection" => metadata.series = Some(text),
+                    "group-position" => metadata.series_index = Some(text),
                     _ => {}
                 }
             }
@@ -220,9 +497,11 @@ fn parse_opf(content: &str) -> Result<(EpubMetadata, Vec<String>)> {
             }
             Ok(Event::Eof) => break,
             Err(e) => {
-                return Err(Error::Conversion {
+                return Err(Error::Parse {
                     format: "epub",
-                    message: format!("Failed to parse OPF: {e}"),
+                    entry: None,
+                    offset: Some(reader.error_position()),
+                    message: e.to_string(),
                 });
             }
             _ => {}
@@ -235,31 +514,500 @@ fn parse_opf(content: &str) -> Result<(EpubMetadata, Vec<String>)> {
         .filter_map(|id| {
             manifest
                 .iter()
-                .find(|(mid, _)| mid == id)
-                .map(|(_, href)| href.clone())
+                .find(|item| item.id == *id)
+                .map(|item| item.href.clone())
+        })
+        .collect();
+
+    let nav = manifest
+        .iter()
+        .find(|item| item.properties.split_whitespace().any(|p| p == "nav"));
+    let ncx = || {
+        manifest.iter().find(|item| {
+            Some(&item.id) == ncx_id.as_ref() || item.media_type == "application/x-dtbncx+xml"
         })
+    };
+    let toc = nav.or_else(ncx).map(|item| item.href.clone());
+    let images = manifest
+        .iter()
+        .filter(|item| item.media_type.starts_with("image/"))
+        .map(|item| item.href.clone())
         .collect();
 
-    Ok((metadata, spine_items))
+    Ok(Opf {
+        metadata,
+        spine_items,
+        toc,
+        images,
+    })
+}
+
+/// An `<item>` of the package manifest.
+struct ManifestItem {
+    id: String,
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+impl ManifestItem {
+    fn read(e: &quick_xml::events::BytesStart) -> Option<Self> {
+        let mut item = Self {
+            id: String::new(),
+            href: String::new(),
+            media_type: String::new(),
+            properties: String::new(),
+        };
+        for attr in e.attributes().flatten() {
+            let value = String::from_utf8_lossy(&attr.value).to_string();
+            match attr.key.as_ref() {
+                b"id" => item.id = value,
+                b"href" => item.href = value,
+                b"media-type" => item.media_type = value,
+                b"properties" => item.properties = value,
+                _ => {}
+            }
+        }
+        (!item.id.is_empty() && !item.href.is_empty()).then_some(item)
+    }
 }
 
 fn read_entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
-    let mut file = archive.by_name(name).map_err(|e| Error::Conversion {
+    let mut file = archive.by_name(name).map_err(|_| Error::MissingEntry {
         format: "epub",
-        message: format!("Entry not found: {name}: {e}"),
+        entry: name.to_string(),
     })?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
     Ok(content)
 }
 
+/// Heading anchors of a single chapter file.
+#[derive(Default)]
+struct ChapterAnchors {
+    /// Anchor of the first heading, used for links to the chapter itself.
+    first: Option<String>,
+    /// Element id → anchor of the heading it belongs to.
+    ids: HashMap<String, String>,
+}
+
+/// Collect heading anchors from chapter HTML in document order, starting
+/// with the chapter heading written for `title`, which takes the place of
+/// an `<h1>` the chapter opens with.
+///
+/// Ids on headings map to that heading; ids on other elements (section
+/// wrappers, `<a id>` markers) map to the next heading, falling back to the
+/// previous one when nothing follows.
+fn scan_anchors(html: &str, title: Option<&str>, slugger: &mut Slugger) -> ChapterAnchors {
+    let mut anchors = ChapterAnchors::default();
+    let mut pending_ids: Vec<String> = Vec::new();
+    let mut last_slug: Option<String> = None;
+    let mut pos = 0;
+
+    let mut replaced_h1 = false;
+    if let Some(title) = title {
+        let slug = slugger.slug(title);
+        anchors.first = Some(slug.clone());
+        last_slug = Some(slug);
+        replaced_h1 = opens_with_h1(html);
+    }
+
+    while let Some((start, end)) = next_tag(html, pos) {
+        pos = end;
+        let tag = &html[start + 1..end - 1];
+        if tag.starts_with('/') || tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let id = attribute_value(tag, "id");
+
+        let heading_text = match name.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let close = format!("</{name}");
+                let inner_end = find_ignore_case(html, &close, end).unwrap_or(html.len());
+                let text = strip_tags(&html[end..inner_end]);
+                pos = inner_end;
+                Some(text)
+            }
+            _ => None,
+        };
+
+        match heading_text {
+            // An empty heading is dropped by the HTML converter
+            Some(text) if !text.trim().is_empty() => {
+                let slug = match last_slug.clone().filter(|_| replaced_h1) {
+                    Some(slug) => {
+                        replaced_h1 = false;
+                        slug
+                    }
+                    None => slugger.slug(&text),
+                };
+                if anchors.first.is_none() {
+                    anchors.first = Some(slug.clone());
+                }
+                for pending in pending_ids.drain(..) {
+                    anchors.ids.insert(pending, slug.clone());
+                }
+                if let Some(id) = id {
+                    anchors.ids.insert(id, slug.clone());
+                }
+                last_slug = Some(slug);
+            }
+            _ => {
+                if let Some(id) = id {
+                    pending_ids.push(id);
+                }
+            }
+        }
+    }
+
+    if let Some(slug) = last_slug {
+        for pending in pending_ids {
+            anchors.ids.insert(pending, slug.clone());
+        }
+    }
+
+    anchors
+}
+
+/// The title of a chapter the table of contents does not list: its first
+/// `<h1>`, else its `<title>` unless that only repeats the book's title.
+fn chapter_title(html: &str, book_title: Option<&str>) -> Option<String> {
+    element_text(html, "h1")
+        .or_else(|| element_text(html, "title").filter(|title| Some(title.as_str()) != book_title))
+}
+
+/// The level of the heading for a table of contents entry at `depth`,
+/// below the book's `#` title and within the six levels of Markdown.
+fn heading_level(depth: usize) -> usize {
+    (depth + 2).min(6)
+}
+
+/// The text of the first non-empty `name` element of chapter HTML.
+fn element_text(html: &str, name: &str) -> Option<String> {
+    elements(html, |tag| tag.eq_ignore_ascii_case(name))
+        .map(|(_, text)| text)
+        .next()
+}
+
+/// Whether the first heading of chapter HTML is an `<h1>`.
+fn opens_with_h1(html: &str) -> bool {
+    let heading = |tag: &str| matches!(tag.as_bytes(), [b'h' | b'H', b'1'..=b'6']);
+    elements(html, heading)
+        .next()
+        .is_some_and(|(tag, _)| tag.eq_ignore_ascii_case("h1"))
+}
+
+/// Set the level of the heading at the element with id `fragment`, or of
+/// the first heading after it, such as the heading of a `<section>`.
+fn relevel_heading(html: &str, fragment: &str, level: usize) -> String {
+    let mut pos = 0;
+    let mut found = false;
+    while let Some((start, end)) = next_tag(html, pos) {
+        pos = end;
+        let tag = &html[start + 1..end - 1];
+        found = found || attribute_value(tag, "id").is_some_and(|id| id == fragment);
+        let heading = matches!(
+            tag.as_bytes(),
+            [b'h' | b'H', b'1'..=b'6', rest @ ..] if rest.first().is_none_or(|c| !c.is_ascii_alphanumeric())
+        );
+        if !found || !heading {
+            continue;
+        }
+        let name = &tag[..2];
+        let close = format!("</{name}");
+        let Some(inner_end) = find_ignore_case(html, &close, end) else {
+            break;
+        };
+        return format!(
+            "{}<h{level}{}</h{level}{}",
+            &html[..start],
+            &html[start + 3..inner_end],
+            &html[inner_end + 4..]
+        );
+    }
+    html.to_string()
+}
+
+/// The tag names and text of the non-empty elements whose tag names pass
+/// `filter`, in document order.
+fn elements<'a>(
+    html: &'a str,
+    filter: impl Fn(&str) -> bool + 'a,
+) -> impl Iterator<Item = (&'a str, String)> + 'a {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        while let Some((start, end)) = next_tag(html, pos) {
+            pos = end;
+            let tag = &html[start + 1..end - 1];
+            let name = tag
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or("");
+            if name.is_empty() || !filter(name) {
+                continue;
+            }
+            let close = format!("</{name}");
+            let inner_end = find_ignore_case(html, &close, end).unwrap_or(html.len());
+            let text = strip_tags(&html[end..inner_end]);
+            pos = inner_end;
+            if !text.is_empty() {
+                return Some((name, text));
+            }
+        }
+        None
+    })
+}
+
+/// Put a heading of `level` for the chapter's title at its top. A chapter
+/// that opens with an `<h1>` has that heading replaced instead, so the
+/// title is not repeated.
+fn with_chapter_heading(text: &str, title: &str, level: usize, opens_with_h1: bool) -> String {
+    let heading = format!("{} {title}", "#".repeat(level));
+    if opens_with_h1 && text.starts_with("# ") {
+        let rest = text.find('\n').map_or("", |pos| &text[pos..]);
+        return format!("{heading}{rest}");
+    }
+    match text.is_empty() {
+        true => heading,
+        false => format!("{heading}\n\n{text}"),
+    }
+}
+
+/// Rewrite `href`s that point at other spine items into in-document anchors.
+fn rewrite_links(html: &str, chapter_path: &str, anchors: &HashMap<&str, ChapterAnchors>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(pos) = find_ignore_case(rest, "href=", 0) {
+        let value_start = pos + "href=".len();
+        let Some(quote) = rest[value_start..]
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+        else {
+            out.push_str(&rest[..value_start]);
+            rest = &rest[value_start..];
+            continue;
+        };
+        let Some(len) = rest[value_start + 1..].find(quote) else {
+            break;
+        };
+        let href = &rest[value_start + 1..value_start + 1 + len];
+        out.push_str(&rest[..value_start + 1]);
+        out.push_str(&resolve_internal_link(href, chapter_path, anchors).unwrap_or_else(|| href.to_string()));
+        rest = &rest[value_start + 1 + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn resolve_internal_link(
+    href: &str,
+    chapter_path: &str,
+    anchors: &HashMap<&str, ChapterAnchors>,
+) -> Option<String> {
+    if href.contains("://") || href.starts_with("mailto:") {
+        return None;
+    }
+    let (file, fragment) = match href.split_once('#') {
+        Some((file, fragment)) => (file, Some(fragment)),
+        None => (href, None),
+    };
+    if file.is_empty() {
+        // A fragment within the same chapter only resolves to a known id;
+        // falling back to the chapter's first heading would jump elsewhere
+        let anchor = anchors.get(chapter_path)?.ids.get(fragment?)?;
+        return Some(format!("#{anchor}"));
+    }
+    let base_dir = chapter_path
+        .rfind('/')
+        .map_or("", |pos| &chapter_path[..=pos]);
+    let target = anchors.get(normalize_path(base_dir, &percent_decode(file)).as_str())?;
+    let anchor = fragment
+        .and_then(|f| target.ids.get(f))
+        .or(target.first.as_ref())?;
+    Some(format!("#{anchor}"))
+}
+
+/// Join a relative href onto a directory inside the archive, resolving `.` and `..`.
+fn normalize_path(base_dir: &str, href: &str) -> String {
+    let joined = match href.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("{base_dir}{href}"),
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && let Some(b) = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(b);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Find the next `<...>` tag at or after `from`, skipping quoted `>` characters.
+fn next_tag(html: &str, from: usize) -> Option<(usize, usize)> {
+    let start = from + html.get(from..)?.find('<')?;
+    let mut quote: Option<char> = None;
+    for (offset, c) in html[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some((start, start + offset + 1)),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn attribute_value(tag: &str, name: &str) -> Option<String> {
+    attribute_span(tag, name).map(|span| tag[span].to_string())
+}
+
+/// Where the value of attribute `name` is within `tag`, without its quotes.
+fn attribute_span(tag: &str, name: &str) -> Option<Range<usize>> {
+    let mut search = 0;
+    while let Some(pos) = find_ignore_case(tag, name, search) {
+        search = pos + name.len();
+        let preceded_by_space = tag[..pos].ends_with(|c: char| c.is_whitespace());
+        let rest = tag[pos + name.len()..].trim_start();
+        if !preceded_by_space || !rest.starts_with('=') {
+            continue;
+        }
+        let rest = rest[1..].trim_start();
+        let offset = tag.len() - rest.len();
+        let quote = rest.chars().next()?;
+        if quote == '"' || quote == '\'' {
+            let end = rest[1..].find(quote)?;
+            return Some(offset + 1..offset + 1 + end);
+        }
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(rest.len());
+        return Some(offset..offset + end);
+    }
+    None
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn find_ignore_case(haystack: &str, needle: &str, from: usize) -> Option<usize> {
+    let hay = haystack.as_bytes();
+    let needle = needle.as_bytes();
+    if needle.is_empty() || from >= hay.len() {
+        return None;
+    }
+    (from..=hay.len().checked_sub(needle.len())?)
+        .find(|&i| hay[i..i + needle.len()].eq_ignore_ascii_case(needle))
+}
+
+/// Words of prose in converted Markdown, counted as `--counts` does but
+/// without the front matter.
+fn count_words(markdown: &str) -> usize {
+    let body = front_matter_end(markdown).map_or(markdown, |end| &markdown[end..]);
+    Counts::of(body).words
+}
+
+/// Byte offset just past a leading YAML front matter block.
+fn front_matter_end(markdown: &str) -> Option<usize> {
+    let rest = markdown.strip_prefix("---\n")?;
+    let end = rest.find("\n---")?;
+    let after = &rest[end + 4..];
+    let newline = usize::from(after.starts_with('\n'));
+    Some(markdown.len() - after.len() + newline)
+}
+
+/// Rounded up to the minute, so even a short chapter reads as "1 min".
+fn reading_time(words: usize) -> String {
+    let minutes = words.div_ceil(WORDS_PER_MINUTE);
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m} min"),
+        (h, 0) => format!("{h} h"),
+        (h, m) => format!("{h} h {m} min"),
+    }
+}
+
+/// Put a chapter's word count and reading time on the line after its first
+/// heading, or at its top when it has none.
+fn with_chapter_stats(text: &str, words: usize) -> String {
+    let stats = format!("*{words} words, {} read*", reading_time(words));
+    let start = front_matter_end(text).unwrap_or(0);
+    let heading_end = text[start..]
+        .split_inclusive('\n')
+        .scan(start, |offset, line| {
+            *offset += line.len();
+            Some((line, *offset))
+        })
+        .find(|(line, _)| line.starts_with('#'))
+        .map(|(_, end)| end);
+    let text = match heading_end {
+        Some(end) => format!(
+            "{}\n\n{stats}\n\n{}",
+            text[..end].trim_end(),
+            text[end..].trim_start()
+        ),
+        None => format!("{}{stats}\n\n{}", &text[..start], &text[start..]),
+    };
+    text.trim_end().to_string()
+}
+
 fn html_to_markdown(html: &str) -> String {
     mq_markdown::convert_html_to_markdown(
         html,
         mq_markdown::ConversionOptions {
             extract_scripts_as_code_blocks: true,
-            generate_front_matter: true,
-            use_title_as_h1: true,
+            // Chapters are titled by the heading `with_chapter_heading` adds
+            generate_front_matter: false,
+            use_title_as_h1: false,
         },
     )
     .unwrap_or_default()
@@ -273,3 +1021,381 @@ fn local_name(name: &[u8]) -> String {
         s.to_string()
     }
 }
+
+pub(super) fn attr(e: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == name)
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::io::Write;
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+
+    fn make_epub(metadata: &str, chapters: &[(&str, &str)]) -> Vec<u8> {
+        make_epub_with_nav(metadata, chapters, None)
+    }
+
+    /// Like `make_epub`, with a navigation document at `OEBPS/nav.xhtml`.
+    /// Chapters that start with `<html` are used as the whole document, and
+    /// `.png` entries are added to the manifest as images, outside the spine.
+    fn make_epub_with_nav(metadata: &str, chapters: &[(&str, &str)], nav: Option<&str>) -> Vec<u8> {
+        let mut manifest: String = chapters
+            .iter()
+            .enumerate()
+            .map(|(i, (href, _))| {
+                let media_type = match href.ends_with(".png") {
+                    true => "image/png",
+                    false => "application/xhtml+xml",
+                };
+                format!(r#"<item id="c{i}" href="{href}" media-type="{media_type}"/>"#)
+            })
+            .collect();
+        if nav.is_some() {
+            manifest.push_str(
+                r#"<item id="nav" href="nav.xhtml" properties="nav" media-type="application/xhtml+xml"/>"#,
+            );
+        }
+        let spine: String = (0..chapters.len())
+            .filter(|i| !chapters[*i].0.ends_with(".png"))
+            .map(|i| format!(r#"<itemref idref="c{i}"/>"#))
+            .collect();
+        let opf = format!(
+            r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata>{metadata}</metadata>
+  <manifest>{manifest}</manifest>
+  <spine>{spine}</spine>
+</package>"#
+        );
+
+        let cursor = Cursor::new(Vec::new());
+        let mut zip = zip::ZipWriter::new(cursor);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        let mut entries = vec![
+            ("mimetype".to_string(), "application/epub+zip".to_string()),
+            ("META-INF/container.xml".to_string(), CONTAINER.to_string()),
+            ("OEBPS/content.opf".to_string(), opf),
+        ];
+        for (href, body) in chapters {
+            let html = match body.starts_with("<html") || href.ends_with(".png") {
+                true => body.to_string(),
+                false => format!("<html><head><title></title></head><body>{body}</body></html>"),
+            };
+            entries.push((format!("OEBPS/{href}"), html));
+        }
+        if let Some(nav) = nav {
+            entries.push(("OEBPS/nav.xhtml".to_string(), nav.to_string()));
+        }
+        for (name, content) in entries {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn convert(epub: &[u8]) -> String {
+        convert_with(epub, ConvertOptions::default())
+    }
+
+    fn convert_with(epub: &[u8], options: ConvertOptions) -> String {
+        let mut output = Vec::new();
+        EpubConverter::new(options)
+            .convert(epub, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_basic_metadata() {
+        let epub = make_epub(
+            "<dc:title>My Book</dc:title><dc:creator>Jane Doe</dc:creator><dc:language>en</dc:language>",
+            &[("ch1.xhtml", "<p>Hello</p>")],
+        );
+        let output = convert(&epub);
+        assert!(output.starts_with("# My Book\n"), "{output}");
+        assert!(output.contains("**Author**: Jane Doe"), "{output}");
+        assert!(output.contains("**Language**: en"), "{output}");
+        assert!(output.contains("Hello"), "{output}");
+    }
+
+    #[rstest]
+    #[case::epub2_scheme(r#"<dc:identifier opf:scheme="ISBN">9780000000001</dc:identifier>"#)]
+    #[case::urn(r#"<dc:identifier id="pub-id">urn:isbn:9780000000001</dc:identifier>"#)]
+    fn test_isbn_identifier(#[case] identifier: &str) {
+        let epub = make_epub(identifier, &[]);
+        let output = convert(&epub);
+        assert!(output.contains("**ISBN**: 9780000000001"), "{output}");
+    }
+
+    #[rstest]
+    fn test_subjects_and_contributors() {
+        let epub = make_epub(
+            "<dc:creator>A</dc:creator><dc:creator>B</dc:creator>\
+             <dc:contributor>Editor E</dc:contributor>\
+             <dc:subject>Fiction</dc:subject><dc:subject>Mystery</dc:subject>",
+            &[],
+        );
+        let output = convert(&epub);
+        assert!(output.contains("**Author**: A, B"), "{output}");
+        assert!(output.contains("**Contributors**: Editor E"), "{output}");
+        assert!(output.contains("**Subjects**: Fiction, Mystery"), "{output}");
+    }
+
+    #[rstest]
+    #[case::calibre(r#"<meta name="calibre:series" content="Saga"/><meta name="calibre:series_index" content="2"/>"#)]
+    #[case::epub3(
+        r##"<meta property="belongs-to-collection" id="c01">Saga</meta><meta refines="#c01" property="group-position">2</meta>"##
+    )]
+    fn test_series(#[case] meta: &str) {
+        let epub = make_epub(meta, &[]);
+        let output = convert(&epub);
+        assert!(output.contains("**Series**: Saga (#2)"), "{output}");
+    }
+
+    #[rstest]
+    fn test_cross_chapter_links_rewritten_to_anchors() {
+        let epub = make_epub(
+            "<dc:title>Book</dc:title>",
+            &[
+                (
+                    "text/ch1.xhtml",
+                    r##"<h1>Intro</h1><p>See <a href="ch2.xhtml#sec1">details</a> and <a href="../text/ch2.xhtml">chapter two</a>.</p>"##,
+                ),
+                (
+                    "text/ch2.xhtml",
+                    r##"<h1>Second Chapter</h1><section id="sec1"><h2>Fine Details</h2><p>Body, <a href="#sec1">again</a> and <a href="#note1">a note</a></p></section>"##,
+                ),
+            ],
+        );
+        let output = convert(&epub);
+        assert!(output.contains("[details](#fine-details)"), "{output}");
+        assert!(output.contains("[chapter two](#second-chapter)"), "{output}");
+        assert!(output.contains("[again](#fine-details)"), "{output}");
+        assert!(output.contains("[a note](#note1)"), "{output}");
+    }
+
+    #[rstest]
+    fn test_chapter_headings() {
+        let epub = make_epub_with_nav(
+            "<dc:title>Book</dc:title>",
+            &[
+                ("ch1.xhtml", "<h1>Opening</h1><p>First</p>"),
+                (
+                    "ch2.xhtml",
+                    r#"<p>Second</p><section id="aside"><h2>Aside</h2></section>"#,
+                ),
+                (
+                    "ch3.xhtml",
+                    "<html><head><title>Third</title></head><body><p>Third</p></body></html>",
+                ),
+                (
+                    "ch4.xhtml",
+                    r##"<html><head><title>Book</title></head><body><p>Back to <a href="ch2.xhtml">the second</a> and <a href="ch2.xhtml#aside">its aside</a></p></body></html>"##,
+                ),
+                ("ch5.xhtml", "<h1>Epilogue</h1><p>End</p>"),
+            ],
+            Some(
+                r##"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops"><body><nav epub:type="toc"><ol><li><a href="ch1.xhtml">Chapter 1</a></li><li><a href="ch2.xhtml">Chapter 2</a><ol><li><a href="ch2.xhtml#aside">An Aside</a></li><li><a href="ch3.xhtml">Part 2.1</a></li></ol></li></ol></nav></body></html>"##,
+            ),
+        );
+        let output = convert(&epub);
+        let chapters = output.split_once("\n---\n\n").unwrap().1;
+        assert_eq!(
+            chapters,
+            "## Chapter 1\n\nFirst\n\n---\n\n\
+             ## Chapter 2\n\nSecond\n\n### Aside\n\n---\n\n\
+             ### Part 2.1\n\nThird\n\n---\n\n\
+             Back to [the second](#chapter-2) and [its aside](#aside)\n\n---\n\n\
+             ## Epilogue\n\nEnd\n"
+        );
+    }
+
+    #[rstest]
+    #[case(r#"<p>a</p><h2 id="s">S</h2>"#, "s", 4, r#"<p>a</p><h4 id="s">S</h4>"#)]
+    #[case(
+        r#"<section id="s"><H2>S</H2></section>"#,
+        "s",
+        3,
+        r#"<section id="s"><h3>S</h3></section>"#
+    )]
+    #[case(
+        r#"<h2>T</h2><div id="s"><p>x</p></div>"#,
+        "s",
+        3,
+        r#"<h2>T</h2><div id="s"><p>x</p></div>"#
+    )]
+    #[case(r#"<h2 id="t">T</h2>"#, "s", 3, r#"<h2 id="t">T</h2>"#)]
+    fn test_relevel_heading(
+        #[case] html: &str,
+        #[case] fragment: &str,
+        #[case] level: usize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(relevel_heading(html, fragment, level), expected);
+    }
+
+    #[rstest]
+    #[case::extracted(false, "![Map](DIR/Atlas-map.png)")]
+    #[case::embedded(true, "![Map](data:image/png;base64,UE5HREFUQQ==)")]
+    fn test_images(#[case] embed: bool, #[case] expected: &str) {
+        let dir = std::env::temp_dir().join(format!("mq-conv-epub-images-{}", std::process::id()));
+        let epub = make_epub(
+            "<dc:title>Atlas</dc:title>",
+            &[
+                (
+                    "text/ch1.xhtml",
+                    r#"<h1>Maps</h1><p><img alt="Map" src="../images/map.png"/></p><p><img alt="Gone" src="../images/gone.png"/></p>"#,
+                ),
+                ("images/map.png", "PNGDATA"),
+            ],
+        );
+        let output = convert_with(
+            &epub,
+            ConvertOptions {
+                input_name: Some("Atlas.epub".to_string()),
+                media: crate::options::MediaOptions {
+                    extract_dir: (!embed).then(|| dir.clone()),
+                    embed,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let expected = expected.replace("DIR", &dir.to_string_lossy().replace('\\', "/"));
+        assert!(output.contains(&expected), "{output}");
+        assert!(output.contains("*[Image: Gone]*"), "{output}");
+        if !embed {
+            assert_eq!(
+                std::fs::read(dir.join("Atlas-map.png")).unwrap(),
+                b"PNGDATA"
+            );
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest]
+    fn test_images_without_media_dir() {
+        let epub = make_epub(
+            "<dc:title>Atlas</dc:title>",
+            &[
+                (
+                    "text/ch1.xhtml",
+                    r#"<h1>Maps</h1><p><img alt="Map" src="../images/map.png"/></p>"#,
+                ),
+                ("images/map.png", "PNGDATA"),
+            ],
+        );
+        let output = convert_with(&epub, ConvertOptions::default());
+        assert!(output.contains("*[Image: Map]*"), "{output}");
+        assert!(!output.contains("../images/map.png"), "{output}");
+    }
+
+    #[rstest]
+    fn test_external_links_untouched() {
+        let epub = make_epub(
+            "",
+            &[("ch1.xhtml", r#"<p><a href="https://example.com/a.xhtml">site</a></p>"#)],
+        );
+        let output = convert(&epub);
+        assert!(output.contains("[site](https://example.com/a.xhtml)"), "{output}");
+    }
+
+    #[rstest]
+    fn test_word_count_and_reading_time() {
+        let long = "word ".repeat(300);
+        let epub = make_epub(
+            "<dc:title>Stats</dc:title>",
+            &[
+                ("ch1.xhtml", "<h1>Intro</h1><p>One two three</p>"),
+                ("ch2.xhtml", &format!("<h1>Long</h1><p>{long}</p>")),
+            ],
+        );
+        let mut options = ConvertOptions::default();
+        let output = convert_with(&epub, options.clone());
+        assert!(output.contains("**Words**: 305\n"), "{output}");
+        assert!(output.contains("**Reading time**: 2 min\n"), "{output}");
+        assert!(!output.contains("words,"), "{output}");
+
+        options.epub.chapter_stats = true;
+        let output = convert_with(&epub, options);
+        assert!(
+            output.contains("# Intro\n\n*4 words, 1 min read*\n\nOne two three"),
+            "{output}"
+        );
+        assert!(
+            output.contains("# Long\n\n*301 words, 2 min read*\n\nword word"),
+            "{output}"
+        );
+    }
+
+    #[rstest]
+    #[case::heading("# Title\n\nSee [the docs](https://example.com/a b) - ok", 5)]
+    #[case::front_matter("---\ntitle: Chapter One\n---\n\nBody text", 2)]
+    #[case::cjk("## 章\n\n吾輩は猫", 5)]
+    fn test_count_words(#[case] markdown: &str, #[case] expected: usize) {
+        assert_eq!(count_words(markdown), expected);
+    }
+
+    #[rstest]
+    #[case(1, "1 min")]
+    #[case(238, "1 min")]
+    #[case(239, "2 min")]
+    #[case(238 * 60, "1 h")]
+    #[case(238 * 75, "1 h 15 min")]
+    fn test_reading_time(#[case] words: usize, #[case] expected: &str) {
+        assert_eq!(reading_time(words), expected);
+    }
+
+    #[rstest]
+    fn test_best_effort_skips_corrupt_chapter() {
+        let mut epub = make_epub(
+            "<dc:title>Book</dc:title>",
+            &[
+                ("ch1.xhtml", "<h1>One</h1><p>First</p>"),
+                ("ch2.xhtml", "<h1>Two</h1><p>Broken</p>"),
+                ("ch3.xhtml", "<h1>Three</h1><p>Last</p>"),
+            ],
+        );
+        // Damage the stored chapter so its checksum no longer matches
+        let pos = epub.windows(6).position(|w| w == b"Broken").unwrap();
+        epub[pos] = b'b';
+
+        let mut options = ConvertOptions {
+            best_effort: true,
+            ..Default::default()
+        };
+        options.epub.chapter_stats = true;
+        let output = convert_with(&epub, options);
+        assert!(output.contains("**Words**: 4\n"), "{output}");
+        assert!(
+            output.contains(
+                "---\n\n> **Conversion warning**: Chapter OEBPS/ch2.xhtml could not be converted: "
+            ),
+            "{output}"
+        );
+        assert!(
+            output.ends_with("# Three\n\n*2 words, 1 min read*\n\nLast\n"),
+            "{output}"
+        );
+
+        // Without it the damaged chapter is left out as before
+        assert!(!convert(&epub).contains("Conversion warning"));
+    }
+
+    #[rstest]
+    #[case::no_heading("Just text", "*2 words, 1 min read*\n\nJust text")]
+    #[case::heading_last("# Only", "# Only\n\n*2 words, 1 min read*")]
+    fn test_chapter_stats_placement(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(with_chapter_stats(text, 2), expected);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::error::Result;
+
+use super::{attribute_span, next_tag, normalize_path, percent_decode};
+
+/// Point the `<img>` and SVG `<image>` elements of the chapter at
+/// `chapter_path` to the references `link` returns for the archive paths of
+/// their images. Images `link` returns `None` for would point into the book,
+/// so an `<img>` becomes a placeholder naming it and an SVG `<image>` is
+/// dropped. External and `data:` images keep their reference.
+pub(super) fn rewrite_images(
+    html: &str,
+    chapter_path: &str,
+    mut link: impl FnMut(&str) -> Result<Option<String>>,
+) -> Result<String> {
+    let base_dir = chapter_path
+        .rfind('/')
+        .map_or("", |pos| &chapter_path[..=pos]);
+    let mut out = String::with_capacity(html.len());
+    let mut copied = 0;
+    let mut pos = 0;
+    while let Some((start, end)) = next_tag(html, pos) {
+        pos = end;
+        let tag = &html[start + 1..end - 1];
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let attributes: &[&str] = match name.as_str() {
+            "img" => &["src"],
+            "image" | "svg:image" => &["href", "xlink:href"],
+            _ => continue,
+        };
+        let Some(span) = attributes.iter().find_map(|name| attribute_span(tag, name)) else {
+            continue;
+        };
+        let src = &tag[span.clone()];
+        if src.contains(':') {
+            continue;
+        }
+        let file = src.split('#').next().unwrap_or_default();
+        match link(&normalize_path(base_dir, &percent_decode(file)))? {
+            Some(target) => {
+                out.push_str(&html[copied..start + 1 + span.start]);
+                out.push_str(&target.replace('&', "&amp;").replace('"', "&quot;"));
+                copied = start + 1 + span.end;
+            }
+            None => {
+                out.push_str(&html[copied..start]);
+                if name == "img" {
+                    match attribute_span(tag, "alt").map(|alt| tag[alt].trim()) {
+                        Some(alt) if !alt.is_empty() => {
+                            out.push_str(&format!("<em>[Image: {alt}]</em>"));
+                        }
+                        _ => out.push_str("<em>[Image]</em>"),
+                    }
+                }
+                copied = end;
+            }
+        }
+    }
+    out.push_str(&html[copied..]);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_rewrite_images() {
+        let html = r#"<p><img alt="Map" src="../images/map%201.png"/><img src='cover.jpg'>
+<svg><image width="10" xlink:href="../images/map%201.png"/></svg>
+<img src="https://example.com/a.png"/><img alt="Lost" src="../images/missing.png"/><img src="../images/missing.png"/>
+<svg><image href="../images/missing.png"/></svg></p>"#;
+        let mut seen = Vec::new();
+        let output = rewrite_images(html, "OEBPS/text/ch1.xhtml", |path| {
+            seen.push(path.to_string());
+            Ok((!path.ends_with("missing.png"))
+                .then(|| format!("media/{}", path.replace(' ', "%20"))))
+        })
+        .unwrap();
+        assert_eq!(
+            output,
+            r#"<p><img alt="Map" src="media/OEBPS/images/map%201.png"/><img src='media/OEBPS/text/cover.jpg'>
+<svg><image width="10" xlink:href="media/OEBPS/images/map%201.png"/></svg>
+<img src="https://example.com/a.png"/><em>[Image: Lost]</em><em>[Image]</em>
+<svg></svg></p>"#
+        );
+        assert_eq!(
+            seen,
+            [
+                "OEBPS/images/map 1.png",
+                "OEBPS/text/cover.jpg",
+                "OEBPS/images/map 1.png",
+                "OEBPS/images/missing.png",
+                "OEBPS/images/missing.png",
+                "OEBPS/images/missing.png"
+            ]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+
+use super::{attr, local_name, normalize_path, percent_decode};
+
+/// An entry of a book's table of contents.
+#[derive(Debug, PartialEq)]
+pub(super) struct Entry {
+    /// The path in the archive of the file the entry points to.
+    pub path: String,
+    /// The id of the element the entry points to within its file.
+    pub fragment: Option<String>,
+    pub label: String,
+    /// How deeply the entry is nested, from 0 for top-level entries.
+    pub depth: usize,
+}
+
+/// The entries of a book's table of contents, in reading order. `xml` is
+/// either an EPUB3 navigation document or an EPUB2 NCX at `toc_path`.
+pub(super) fn entries(xml: &str, toc_path: &str) -> Vec<Entry> {
+    let base_dir = toc_path.rfind('/').map_or("", |pos| &toc_path[..=pos]);
+    let mut entries = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    // Inside the `<nav epub:type="toc">` of a navigation document
+    let mut in_toc = false;
+    // Open nav `<ol>` or NCX `<navPoint>` elements
+    let mut depth: usize = 0;
+    // The label being read, from a nav `<a>` or an NCX `<navLabel>`
+    let mut label: Option<String> = None;
+    let mut href: Option<String> = None;
+    let mut insert = |href: Option<String>, label: Option<String>, depth: usize| {
+        let (Some(href), Some(label)) = (href, label) else {
+            return;
+        };
+        let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+        let (file, fragment) = match href.split_once('#') {
+            Some((file, fragment)) => (file, Some(percent_decode(fragment))),
+            None => (href.as_str(), None),
+        };
+        if !label.is_empty() && !file.is_empty() {
+            entries.push(Entry {
+                path: normalize_path(base_dir, &percent_decode(file)),
+                fragment: fragment.filter(|fragment| !fragment.is_empty()),
+                label,
+                depth: depth.saturating_sub(1),
+            });
+        }
+    };
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match local_name(e.name().as_ref()).as_str() {
+                "nav" => in_toc = attr(&e, "type").is_some_and(|t| t.contains("toc")),
+                "ol" if in_toc => depth += 1,
+                "navPoint" => depth += 1,
+                "a" if in_toc => {
+                    href = attr(&e, "href");
+                    label = Some(String::new());
+                }
+                "navLabel" => label = Some(String::new()),
+                "content" => insert(attr(&e, "src"), label.take(), depth),
+                _ => {}
+            },
+            // NCX entries name their file after their label
+            Ok(Event::Empty(e)) if local_name(e.name().as_ref()) == "content" => {
+                insert(attr(&e, "src"), label.take(), depth);
+            }
+            Ok(Event::Text(e)) => {
+                if let Some(label) = label.as_mut() {
+                    label.push_str(&e.decode().unwrap_or_default());
+                }
+            }
+            Ok(Event::GeneralRef(e)) => {
+                if let Some(label) = label.as_mut() {
+                    match e.resolve_char_ref() {
+                        Ok(Some(c)) => label.push(c),
+                        _ => {
+                            let name = e.decode().unwrap_or_default();
+                            label.push_str(resolve_predefined_entity(&name).unwrap_or_default());
+                        }
+                    }
+                }
+            }
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()).as_str() {
+                "nav" => in_toc = false,
+                "ol" if in_toc => depth = depth.saturating_sub(1),
+                "navPoint" => depth = depth.saturating_sub(1),
+                "a" if in_toc => insert(href.take(), label.take(), depth),
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::nav(
+        "OEBPS/nav.xhtml",
+        r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops"><body>
+<nav epub:type="toc"><ol>
+  <li><a href="text/ch1.xhtml">Down the
+    <em>Rabbit</em>-Hole</a></li>
+  <li><a href="text/ch2.xhtml#start">Tea &amp; Cakes</a>
+    <ol><li><a href="text/ch2.xhtml#later">Later</a></li></ol></li>
+</ol></nav>
+<nav epub:type="landmarks"><ol><li><a href="text/cover.xhtml">Cover</a></li></ol></nav>
+</body></html>"#
+    )]
+    #[case::ncx(
+        "OEBPS/toc.ncx",
+        r#"<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/"><navMap>
+<navPoint id="p1"><navLabel><text>Down the Rabbit-Hole</text></navLabel><content src="text/ch1.xhtml"/></navPoint>
+<navPoint id="p2"><navLabel><text>Tea &amp; Cakes</text></navLabel><content src="text/ch2.xhtml#start"/>
+  <navPoint id="p3"><navLabel><text>Later</text></navLabel><content src="text/ch2.xhtml#later"/></navPoint>
+</navPoint>
+</navMap></ncx>"#
+    )]
+    fn test_entries(#[case] path: &str, #[case] xml: &str) {
+        let entry = |path: &str, fragment: Option<&str>, label: &str, depth| Entry {
+            path: path.to_string(),
+            fragment: fragment.map(str::to_string),
+            label: label.to_string(),
+            depth,
+        };
+        assert_eq!(
+            entries(xml, path),
+            vec![
+                entry("OEBPS/text/ch1.xhtml", None, "Down the Rabbit-Hole", 0),
+                entry("OEBPS/text/ch2.xhtml", Some("start"), "Tea & Cakes", 0),
+                entry("OEBPS/text/ch2.xhtml", Some("later"), "Later", 1),
+            ]
+        );
+    }
+}
@@ -1,11 +1,49 @@
-use std::io::{Cursor, Write};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, Write};
 
-use calamine::{Data, Reader, open_workbook_auto_from_rs};
+use calamine::{
+    Data, Dimensions, Range, Reader, Sheet, SheetVisible, Sheets, Xlsx, open_workbook_auto_from_rs,
+};
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::options::{ConvertOptions, Formulas, MergedCells};
+use crate::warnings;
 
-pub struct ExcelConverter;
+use super::cells;
+
+use self::chart::{Chart, chart};
+use self::comments::{Comment, comments};
+use self::dependencies::Dependencies;
+use self::hidden::Hidden;
+use self::hyperlink::{hyperlinks, is_safe, link};
+use self::number_format::{
+    NumberFormats, StyleCursor, cell_styles, format_datetime, format_number, general,
+};
+use self::package::Package;
+use self::table::{DefinedTable, defined_table};
+use self::validation::{DataValidation, data_validations};
+
+mod chart;
+mod comments;
+mod dependencies;
+mod hidden;
+mod hyperlink;
+mod number_format;
+mod package;
+mod table;
+mod validation;
+
+#[derive(Default)]
+pub struct ExcelConverter {
+    options: ConvertOptions,
+}
+
+impl ExcelConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for ExcelConverter {
     fn format_name(&self) -> &'static str {
@@ -21,56 +59,430 @@ impl Converter for ExcelConverter {
             })?;
 
         let sheet_names: Vec<String> = workbook.sheet_names().to_vec();
+        let include_hidden = self.options.excel.include_hidden;
+        let mut shown = if include_hidden {
+            sheet_names.clone()
+        } else {
+            visible_sheets(workbook.sheets_metadata())
+        };
+        let patterns = &self.options.excel.sheets;
+        if !patterns.is_empty() {
+            for pattern in patterns {
+                if !shown.iter().any(|name| sheet_match(pattern, name)) {
+                    warnings::warn(format!("No sheet matches {pattern}"));
+                }
+            }
+            shown.retain(|name| patterns.iter().any(|pattern| sheet_match(pattern, name)));
+        }
+        // Validation rules, number formats and hidden rows and columns are
+        // only stored in .xlsx packages; calamine reads dates on its own
+        let raw_values = self.options.excel.raw_values;
+        let mut package = Package::open(input);
+        let number_formats = match &mut package {
+            Some(package) if !raw_values => package
+                .part("xl/styles.xml")
+                .map(|xml| NumberFormats::parse(&xml)),
+            _ => None,
+        };
+
+        let max_rows = self.options.excel.max_rows;
+        let merged_cells = self.options.excel.merged_cells;
+        // Inline and tabled formulas and repeated merges need the whole sheet
+        let stream = self.options.excel.stream
+            && self.options.excel.formulas == Formulas::Values
+            && merged_cells != MergedCells::Repeat;
+
+        for (idx, name) in shown.iter().enumerate() {
+            if stream && matches!(workbook, Sheets::Xlsx(_)) {
+                if idx > 0 {
+                    writeln!(writer)?;
+                }
+                writeln!(writer, "# {name}")?;
+                let merges = match merged_cells {
+                    MergedCells::Annotate => merged_regions(&mut workbook, name),
+                    _ => Vec::new(),
+                };
+                // Everything but the cells, whose styles are read alongside them
+                let sheet_xml = package
+                    .as_mut()
+                    .and_then(|p| p.sheet_xml_without_cells(name));
+                let formatter = CellFormatter::new(
+                    &self.options,
+                    package.as_mut(),
+                    name,
+                    sheet_xml.as_deref(),
+                    number_formats.as_ref(),
+                );
+                let hidden = sheet_xml
+                    .as_deref()
+                    .filter(|_| !include_hidden)
+                    .map(Hidden::parse);
+                let streamed = match &mut workbook {
+                    Sheets::Xlsx(xlsx) => package
+                        .as_mut()
+                        .filter(|_| number_formats.is_some())
+                        .and_then(|package| {
+                            package.with_sheet_reader(name, |xml| {
+                                let mut styles = StyleCursor::new(xml);
+                                let sheet = StreamedSheet {
+                                    formatter: &formatter,
+                                    styles: Some(&mut styles),
+                                    hidden: hidden.as_ref(),
+                                    max_rows,
+                                };
+                                stream_sheet(writer, xlsx, name, sheet)
+                            })
+                        })
+                        .unwrap_or_else(|| {
+                            let sheet = StreamedSheet {
+                                formatter: &formatter,
+                                styles: None,
+                                hidden: hidden.as_ref(),
+                                max_rows,
+                            };
+                            stream_sheet(writer, xlsx, name, sheet)
+                        }),
+                    _ => Ok(()),
+                };
+                match streamed {
+                    Ok(()) => {}
+                    Err(e) if self.options.best_effort => {
+                        let section = format!("Sheet {name}");
+                        writeln!(writer)?;
+                        writeln!(writer, "{}", warnings::skipped_section(&section, &e))?;
+                    }
+                    Err(e) => return Err(e),
+                }
+                write_merges(writer, &merges)?;
+                if let Some(xml) = &sheet_xml
+                    && self.options.excel.data_validation
+                {
+                    write_validations(writer, &data_validations(xml))?;
+                }
+                if let Some(package) = package.as_mut().filter(|_| self.options.excel.comments) {
+                    let xml = package.sheet_comments(name).unwrap_or_default();
+                    write_comments(writer, &comments(&xml), self.options.sanitize_cells)?;
+                }
+                if let Some(package) = package.as_mut() {
+                    let charts: Vec<Chart> = package
+                        .sheet_charts(name)
+                        .iter()
+                        .map(|xml| chart(xml))
+                        .collect();
+                    write_charts(writer, &charts, &self.options)?;
+                }
+                continue;
+            }
 
-        for (idx, name) in sheet_names.iter().enumerate() {
             let range = workbook
                 .worksheet_range(name)
                 .map_err(|e| Error::Conversion {
                     format: "excel",
                     message: e.to_string(),
-                })?;
+                });
 
             if idx > 0 {
                 writeln!(writer)?;
             }
+            let range = match range {
+                Ok(range) => range,
+                Err(e) if self.options.best_effort => {
+                    writeln!(writer, "# {name}")?;
+                    writeln!(writer)?;
+                    let section = format!("Sheet {name}");
+                    writeln!(writer, "{}", warnings::skipped_section(&section, &e))?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             writeln!(writer, "# {name}")?;
 
-            let rows: Vec<Vec<String>> = range
-                .rows()
-                .map(|row| row.iter().map(format_cell).collect())
-                .collect();
+            let formulas = match self.options.excel.formulas {
+                Formulas::Values => None,
+                Formulas::Inline | Formulas::Table => match workbook.worksheet_formula(name) {
+                    Ok(formulas) => Some(formulas),
+                    Err(e) => {
+                        warnings::warn(format!("Formulas of sheet {name} skipped: {e}"));
+                        None
+                    }
+                },
+            };
+            let sheet_xml = package.as_mut().and_then(|p| p.sheet_xml(name));
+            let formatter = CellFormatter::new(
+                &self.options,
+                package.as_mut(),
+                name,
+                sheet_xml.as_deref(),
+                number_formats.as_ref(),
+            );
+            let (origin, mut rows) = match &formulas {
+                Some(formulas) if self.options.excel.formulas == Formulas::Inline => {
+                    inline_formulas(&range, formulas, &formatter)
+                }
+                _ => {
+                    let (top, left) = range.start().unwrap_or_default();
+                    let rows = (top..)
+                        .zip(range.rows())
+                        .map(|(row, cells)| {
+                            (left..)
+                                .zip(cells)
+                                .map(|(col, cell)| formatter.format(cell, (row, col)))
+                                .collect()
+                        })
+                        .collect();
+                    ((top, left), rows)
+                }
+            };
 
-            if rows.is_empty() {
-                writeln!(writer)?;
-                writeln!(writer, "*Empty sheet*")?;
-                continue;
+            let merges = match merged_cells {
+                MergedCells::Blank => Vec::new(),
+                _ => merged_regions(&mut workbook, name),
+            };
+            if merged_cells == MergedCells::Repeat {
+                repeat_merged(&mut rows, origin, &merges);
+            }
+            let hidden = sheet_xml
+                .as_deref()
+                .filter(|_| !include_hidden)
+                .map(Hidden::parse);
+            // Defined tables are written under their own headings, after the
+            // rest of the sheet
+            let table_parts = match &mut package {
+                Some(package) => package.sheet_parts(name, "table"),
+                None => Vec::new(),
+            };
+            let tables: Vec<(String, Vec<Vec<String>>)> = table_parts
+                .iter()
+                .filter_map(|xml| defined_table(xml))
+                .map(|table| {
+                    let cells = take_table(&mut rows, origin, &table, hidden.as_ref());
+                    (table.name, cells)
+                })
+                .collect();
+            if let Some(hidden) = &hidden {
+                hidden.remove(&mut rows, origin);
             }
 
             let blocks = split_into_blocks(rows);
-            if blocks.is_empty() {
+            if blocks.is_empty() && tables.is_empty() {
                 writeln!(writer)?;
                 writeln!(writer, "*Empty sheet*")?;
-                continue;
             }
 
             for block in blocks {
                 writeln!(writer)?;
                 match classify_block(block) {
-                    Block::Table(rows) => write_table(writer, &rows)?,
+                    Block::Table(rows) => write_table(writer, &rows, max_rows)?,
                     Block::Text(lines) => write_text(writer, &lines)?,
                 }
             }
+
+            for (table_name, cells) in &tables {
+                writeln!(writer)?;
+                writeln!(writer, "## {table_name}")?;
+                writeln!(writer)?;
+                write_table(writer, cells, max_rows)?;
+            }
+
+            if merged_cells == MergedCells::Annotate {
+                write_merges(writer, &merges)?;
+            }
+
+            if let Some(formulas) = &formulas
+                && self.options.excel.formulas == Formulas::Table
+            {
+                write_formulas(writer, &range, formulas, &formatter)?;
+            }
+
+            if let Some(xml) = &sheet_xml
+                && self.options.excel.data_validation
+            {
+                write_validations(writer, &data_validations(xml))?;
+            }
+
+            if let Some(package) = package.as_mut().filter(|_| self.options.excel.comments) {
+                let xml = package.sheet_comments(name).unwrap_or_default();
+                write_comments(writer, &comments(&xml), self.options.sanitize_cells)?;
+            }
+
+            if let Some(package) = package.as_mut() {
+                let charts: Vec<Chart> = package
+                    .sheet_charts(name)
+                    .iter()
+                    .map(|xml| chart(xml))
+                    .collect();
+                write_charts(writer, &charts, &self.options)?;
+            }
+        }
+
+        write_named_ranges(writer, workbook.defined_names())?;
+
+        if self.options.excel.formula_graph {
+            dependencies(&mut workbook, &sheet_names).write(writer)?;
         }
 
         Ok(())
     }
 }
 
+/// Map the sheets and tables each sheet's formulas read from. Tables are
+/// only known for `.xlsx` workbooks; sheets whose formulas cannot be read
+/// are left out with a warning.
+fn dependencies<RS: std::io::Read + std::io::Seek>(
+    workbook: &mut Sheets<RS>,
+    sheet_names: &[String],
+) -> Dependencies {
+    let mut tables = Vec::new();
+    if let Sheets::Xlsx(xlsx) = workbook
+        && xlsx.load_tables().is_ok()
+    {
+        for (idx, name) in sheet_names.iter().enumerate() {
+            tables.extend(
+                xlsx.table_names_in_sheet(name)
+                    .into_iter()
+                    .map(|table| (table.clone(), idx)),
+            );
+        }
+    }
+    let names = workbook.defined_names().to_vec();
+    let mut deps = Dependencies::new(sheet_names.to_vec(), tables, &names);
+    for (idx, name) in sheet_names.iter().enumerate() {
+        match workbook.worksheet_formula(name) {
+            Ok(formulas) => {
+                for formula in formulas.used_cells().map(|(_, _, f)| f) {
+                    if !formula.is_empty() {
+                        deps.add_formula(idx, formula);
+                    }
+                }
+            }
+            Err(e) => warnings::warn(format!("Formulas of sheet {name} skipped: {e}")),
+        }
+    }
+    deps
+}
+
+/// The names of the sheets not hidden, in workbook order.
+fn visible_sheets(sheets: &[Sheet]) -> Vec<String> {
+    sheets
+        .iter()
+        .filter(|sheet| sheet.visible == SheetVisible::Visible)
+        .map(|sheet| sheet.name.clone())
+        .collect()
+}
+
+/// Match a sheet name against a pattern where `*` matches any run of
+/// characters, ignoring case as Excel does when it compares sheet names.
+fn sheet_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.to_lowercase(), name.to_lowercase());
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// The cell ranges merged in a sheet. Merges are only stored in `.xlsx`
+/// and `.xls` workbooks; a sheet whose merges cannot be read is treated as
+/// having none, with a warning.
+fn merged_regions<RS: std::io::Read + std::io::Seek>(
+    workbook: &mut Sheets<RS>,
+    name: &str,
+) -> Vec<Dimensions> {
+    let merges = match workbook {
+        Sheets::Xlsx(xlsx) => xlsx
+            .merge_cells_by_sheet_name(name)
+            .map_err(|e| e.to_string()),
+        Sheets::Xls(xls) => xls
+            .merge_cells_by_sheet_name(name)
+            .map_err(|e| e.to_string()),
+        Sheets::Xlsb(_) | Sheets::Ods(_) => Ok(Vec::new()),
+    };
+    let mut merges = merges.unwrap_or_else(|e| {
+        warnings::warn(format!("Merged cells of sheet {name} skipped: {e}"));
+        Vec::new()
+    });
+    merges.sort_by_key(|m| m.start);
+    merges
+}
+
+/// Copy the value of each merged range's top-left cell into the cells it
+/// covers, for rows whose first cell is at `origin` on the sheet.
+fn repeat_merged(rows: &mut [Vec<String>], origin: (u32, u32), merges: &[Dimensions]) {
+    for merge in merges {
+        let (Some(top), Some(left)) = (
+            merge.start.0.checked_sub(origin.0),
+            merge.start.1.checked_sub(origin.1),
+        ) else {
+            continue;
+        };
+        let (top, left) = (top as usize, left as usize);
+        let Some(text) = rows.get(top).and_then(|r| r.get(left)).cloned() else {
+            continue;
+        };
+        let height = (merge.end.0 - merge.start.0) as usize + 1;
+        let width = (merge.end.1 - merge.start.1) as usize + 1;
+        for row in rows.iter_mut().skip(top).take(height) {
+            for cell in row.iter_mut().skip(left).take(width) {
+                cell.clone_from(&text);
+            }
+        }
+    }
+}
+
 enum Block {
     Table(Vec<Vec<String>>),
     Text(Vec<String>),
 }
 
+/// Cut a defined table out of a sheet's rows, whose first cell is at
+/// `origin`, blanking its cells so the rest of the sheet is split into
+/// blocks without it. The table's rows start with its column names.
+fn take_table(
+    rows: &mut [Vec<String>],
+    origin: (u32, u32),
+    table: &DefinedTable,
+    hidden: Option<&Hidden>,
+) -> Vec<Vec<String>> {
+    // Rows past the sheet's last are empty, however far the table reaches
+    let last = table
+        .end
+        .0
+        .min((origin.0 + rows.len() as u32).saturating_sub(1));
+    let mut cells: Vec<Vec<String>> = Vec::new();
+    for row in table.start.0..=last {
+        let mut sheet_row = row
+            .checked_sub(origin.0)
+            .and_then(|r| rows.get_mut(r as usize));
+        let row_cells = (table.start.1..=table.end.1).map(|col| {
+            let c = col.checked_sub(origin.1)?;
+            sheet_row.as_mut()?.get_mut(c as usize).map(std::mem::take)
+        });
+        cells.push(row_cells.map(Option::unwrap_or_default).collect());
+    }
+    if let Some(hidden) = hidden {
+        hidden.remove(&mut cells, table.start);
+    }
+    if !table.has_header {
+        let mut names: Vec<String> = table.columns.iter().map(|name| escape_pipe(name)).collect();
+        if let Some(hidden) = hidden {
+            hidden.remove_columns(&mut names, table.start.1);
+        }
+        cells.insert(0, names);
+    }
+    cells
+}
+
 fn split_into_blocks(rows: Vec<Vec<String>>) -> Vec<Vec<Vec<String>>> {
     let mut blocks = Vec::new();
     let mut current: Vec<Vec<String>> = Vec::new();
@@ -118,20 +530,18 @@ fn classify_block(block: Vec<Vec<String>>) -> Block {
     Block::Text(lines)
 }
 
-fn write_table(writer: &mut dyn Write, rows: &[Vec<String>]) -> Result<()> {
+fn write_table(
+    writer: &mut dyn Write,
+    rows: &[Vec<String>],
+    max_rows: Option<usize>,
+) -> Result<()> {
     let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
     if col_count == 0 {
         return Ok(());
     }
 
     // Header row
-    let header = &rows[0];
-    write!(writer, "|")?;
-    for i in 0..col_count {
-        let cell = header.get(i).map(|s| s.as_str()).unwrap_or("");
-        write!(writer, " {cell} |")?;
-    }
-    writeln!(writer)?;
+    write_row(writer, &rows[0], col_count)?;
 
     // Separator
     write!(writer, "|")?;
@@ -141,18 +551,163 @@ fn write_table(writer: &mut dyn Write, rows: &[Vec<String>]) -> Result<()> {
     writeln!(writer)?;
 
     // Data rows
-    for row in rows.iter().skip(1) {
-        write!(writer, "|")?;
-        for i in 0..col_count {
-            let cell = row.get(i).map(|s| s.as_str()).unwrap_or("");
-            write!(writer, " {cell} |")?;
-        }
-        writeln!(writer)?;
+    let count = rows.len() - 1;
+    let limit = max_rows.unwrap_or(count);
+    for row in rows.iter().skip(1).take(limit) {
+        write_row(writer, row, col_count)?;
     }
+    write_showing(writer, limit, count)
+}
 
+fn write_row(writer: &mut dyn Write, row: &[String], col_count: usize) -> Result<()> {
+    write!(writer, "|")?;
+    for i in 0..col_count {
+        let cell = row.get(i).map(|s| s.as_str()).unwrap_or("");
+        write!(writer, " {cell} |")?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Note how many rows a table left out.
+fn write_showing(writer: &mut dyn Write, limit: usize, count: usize) -> Result<()> {
+    if count > limit {
+        writeln!(writer)?;
+        writeln!(writer, "*Showing {limit} of {count} rows*")?;
+    }
     Ok(())
 }
 
+/// How the cells of a streamed sheet are turned into rows.
+struct StreamedSheet<'a, 'r> {
+    formatter: &'a CellFormatter<'a>,
+    /// The cells' styles, read alongside them; without it the formatter
+    /// has none.
+    styles: Option<&'a mut StyleCursor<'r>>,
+    hidden: Option<&'a Hidden>,
+    max_rows: Option<usize>,
+}
+
+/// Write a sheet of an `.xlsx` workbook as one table while its cells are
+/// read, holding a single row in memory besides the sheet's hidden rows and
+/// columns and hyperlinks: the first row that is not blank is the header
+/// and blank rows are skipped. With `max_rows`, the rows past the limit are
+/// still read to count them.
+fn stream_sheet<RS: Read + Seek>(
+    writer: &mut dyn Write,
+    xlsx: &mut Xlsx<RS>,
+    name: &str,
+    sheet: StreamedSheet,
+) -> Result<()> {
+    let StreamedSheet {
+        formatter,
+        mut styles,
+        hidden,
+        max_rows,
+    } = sheet;
+    let to_error = |e: calamine::XlsxError| Error::Conversion {
+        format: "excel",
+        message: e.to_string(),
+    };
+    let mut reader = xlsx.worksheet_cells_reader(name).map_err(to_error)?;
+    let dimensions = reader.dimensions();
+    let left = dimensions.start.1;
+    // The declared range, less hidden columns, is as wide as the table
+    // `write_table` would make from the loaded rows
+    let mut declared = vec![String::new(); (dimensions.end.1 - left + 1) as usize];
+    if let Some(hidden) = hidden {
+        hidden.remove_columns(&mut declared, left);
+    }
+    let mut table = StreamedTable {
+        width: declared.len(),
+        columns: None,
+        rows: 0,
+        max_rows,
+    };
+    let mut current: Option<(u32, Vec<String>)> = None;
+    while let Some(cell) = reader.next_cell().map_err(to_error)? {
+        let (row, col) = cell.get_position();
+        if let Some((r, cells)) = current.take_if(|(r, _)| *r != row) {
+            table.push(writer, cells, (r, left), hidden)?;
+        }
+        let (_, cells) = current.get_or_insert_with(|| (row, Vec::new()));
+        let Some(i) = col.checked_sub(left).map(|i| i as usize) else {
+            continue;
+        };
+        if cells.len() <= i {
+            cells.resize(i + 1, String::new());
+        }
+        let data = Data::from(cell.get_value().clone());
+        cells[i] = match styles.as_mut() {
+            Some(styles) => formatter.format_styled(&data, (row, col), styles.style((row, col))),
+            None => formatter.format(&data, (row, col)),
+        };
+    }
+    if let Some((r, cells)) = current {
+        table.push(writer, cells, (r, left), hidden)?;
+    }
+    table.finish(writer)
+}
+
+/// The state of a table written row by row.
+struct StreamedTable {
+    /// The width of the sheet's declared range.
+    width: usize,
+    /// The width of the header, once it is written.
+    columns: Option<usize>,
+    /// The data rows seen so far, written or not.
+    rows: usize,
+    max_rows: Option<usize>,
+}
+
+impl StreamedTable {
+    fn push(
+        &mut self,
+        writer: &mut dyn Write,
+        cells: Vec<String>,
+        origin: (u32, u32),
+        hidden: Option<&Hidden>,
+    ) -> Result<()> {
+        let mut rows = vec![cells];
+        if let Some(hidden) = hidden {
+            hidden.remove(&mut rows, origin);
+        }
+        let Some(cells) = rows.pop().filter(|cells| !is_blank_row(cells)) else {
+            return Ok(());
+        };
+        match self.columns {
+            None => {
+                let columns = cells.len().max(self.width);
+                writeln!(writer)?;
+                write_row(writer, &cells, columns)?;
+                write!(writer, "|")?;
+                for _ in 0..columns {
+                    write!(writer, "---|")?;
+                }
+                writeln!(writer)?;
+                self.columns = Some(columns);
+            }
+            Some(columns) => {
+                self.rows += 1;
+                // A sheet without a declared range can still have wider
+                // rows; their cells are kept rather than dropped
+                if self.max_rows.is_none_or(|max| self.rows <= max) {
+                    write_row(writer, &cells, columns.max(cells.len()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self, writer: &mut dyn Write) -> Result<()> {
+        if self.columns.is_none() {
+            writeln!(writer)?;
+            writeln!(writer, "*Empty sheet*")?;
+        }
+        write_showing(writer, self.max_rows.unwrap_or(self.rows), self.rows)
+    }
+}
+
 fn write_text(writer: &mut dyn Write, lines: &[String]) -> Result<()> {
     let mut first = true;
     for line in lines {
@@ -168,27 +723,360 @@ fn write_text(writer: &mut dyn Write, lines: &[String]) -> Result<()> {
     Ok(())
 }
 
+/// Note the ranges of a sheet's merged cells below it.
+fn write_merges(writer: &mut dyn Write, merges: &[Dimensions]) -> Result<()> {
+    if merges.is_empty() {
+        return Ok(());
+    }
+    let ranges: Vec<String> = merges.iter().map(range_name).collect();
+    writeln!(writer)?;
+    writeln!(writer, "*Merged cells: {}*", ranges.join(", "))?;
+    Ok(())
+}
+
+/// List the workbook's named ranges with the references they stand for.
+/// Names Excel defines itself, such as print areas, are left out.
+fn write_named_ranges(writer: &mut dyn Write, names: &[(String, String)]) -> Result<()> {
+    let names: Vec<&(String, String)> = names
+        .iter()
+        .filter(|(name, _)| !name.starts_with("_xlnm."))
+        .collect();
+    if names.is_empty() {
+        return Ok(());
+    }
+    let rows: Vec<Vec<String>> = std::iter::once(vec!["Name".to_string(), "Refers to".to_string()])
+        .chain(
+            names
+                .into_iter()
+                .map(|(name, formula)| vec![escape_pipe(name), format_formula(formula)]),
+        )
+        .collect();
+    writeln!(writer)?;
+    writeln!(writer, "# Named Ranges")?;
+    writeln!(writer)?;
+    write_table(writer, &rows, None)
+}
+
+/// Document a sheet's data-validation rules, e.g. the allowed values of
+/// dropdown cells.
+fn write_validations(writer: &mut dyn Write, rules: &[DataValidation]) -> Result<()> {
+    if rules.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer)?;
+    writeln!(writer, "## Data Validation")?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "| Cells | Type | Rule | Input message | Error message |"
+    )?;
+    writeln!(writer, "| --- | --- | --- | --- | --- |")?;
+    for rule in rules {
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} | {} |",
+            escape_pipe(&rule.cells.split_whitespace().collect::<Vec<_>>().join(", ")),
+            rule.kind_label(),
+            escape_pipe(&rule.rule()),
+            escape_pipe(&rule.prompt.replace('\n', " ")),
+            escape_pipe(&rule.error.replace('\n', " ")),
+        )?;
+    }
+    Ok(())
+}
+
+/// List a sheet's cell comments with the cell and author of each.
+fn write_comments(writer: &mut dyn Write, comments: &[Comment], sanitize: bool) -> Result<()> {
+    if comments.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer)?;
+    writeln!(writer, "## Comments")?;
+    writeln!(writer)?;
+    writeln!(writer, "| Cell | Author | Comment |")?;
+    writeln!(writer, "| --- | --- | --- |")?;
+    for comment in comments {
+        let text = comment.text.replace('\n', " ");
+        let text = match sanitize {
+            true => cells::sanitize(&text).into_owned(),
+            false => text,
+        };
+        writeln!(
+            writer,
+            "| {} | {} | {} |",
+            comment.cell,
+            escape_pipe(&comment.author),
+            escape_pipe(&text),
+        )?;
+    }
+    Ok(())
+}
+
+/// Write a sheet's charts with the data Excel caches for them: one table
+/// per chart with a row per category and a column per series, and values
+/// in their number format unless `--raw-values` is set.
+fn write_charts(writer: &mut dyn Write, charts: &[Chart], options: &ConvertOptions) -> Result<()> {
+    if charts.is_empty() {
+        return Ok(());
+    }
+    let cell = |text: &str| match options.sanitize_cells {
+        true => escape_pipe(&cells::sanitize(text)),
+        false => escape_pipe(text),
+    };
+    writeln!(writer)?;
+    writeln!(writer, "## Charts")?;
+    for (idx, chart) in charts.iter().enumerate() {
+        writeln!(writer)?;
+        match &chart.title {
+            Some(title) => writeln!(writer, "### {title}")?,
+            None => writeln!(writer, "### Chart {}", idx + 1)?,
+        }
+        if let Some((first, rest)) = chart.kinds.split_first() {
+            let mut chars = first.chars();
+            let mut kinds: String = chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+                .collect();
+            for kind in rest {
+                kinds.push_str(" and ");
+                kinds.push_str(kind);
+            }
+            writeln!(writer)?;
+            writeln!(writer, "*{kinds} chart*")?;
+        }
+        if chart.series.is_empty() {
+            continue;
+        }
+
+        // Series of a chart share their categories; the first that has
+        // them labels the rows
+        let categories = chart
+            .series
+            .iter()
+            .map(|series| &series.categories)
+            .find(|categories| !categories.is_empty());
+        let points = chart
+            .series
+            .iter()
+            .map(|series| series.values.len().max(series.categories.len()))
+            .max()
+            .unwrap_or_default();
+        writeln!(writer)?;
+        write!(writer, "| Category |")?;
+        for (idx, series) in chart.series.iter().enumerate() {
+            match &series.name {
+                Some(name) => write!(writer, " {} |", cell(name))?,
+                None => write!(writer, " Series {} |", idx + 1)?,
+            }
+        }
+        writeln!(writer)?;
+        writeln!(writer, "|{}", " --- |".repeat(chart.series.len() + 1))?;
+        for point in 0..points {
+            match categories.and_then(|categories| categories.get(point)) {
+                Some(category) => write!(writer, "| {} |", cell(category))?,
+                None => write!(writer, "| {} |", point + 1)?,
+            }
+            for series in &chart.series {
+                let value = series.values.get(point).map_or("", String::as_str);
+                let value = match (value.parse::<f64>(), &series.format_code) {
+                    _ if options.excel.raw_values => value.to_string(),
+                    (Ok(number), Some(code)) if code != "General" => {
+                        format_number(number, code).unwrap_or_else(|| general(number))
+                    }
+                    (Ok(number), _) => general(number),
+                    (Err(_), _) => value.to_string(),
+                };
+                write!(writer, " {} |", cell(&value))?;
+            }
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// The rows of a sheet with each formula cell showing its formula instead
+/// of its cached value, and the sheet position of their first cell. Formula
+/// cells without a cached value, outside the value range, are included.
+fn inline_formulas(
+    values: &Range<Data>,
+    formulas: &Range<String>,
+    formatter: &CellFormatter,
+) -> ((u32, u32), Vec<Vec<String>>) {
+    let mut spans = [
+        values.start().zip(values.end()),
+        formulas.start().zip(formulas.end()),
+    ]
+    .into_iter()
+    .flatten();
+    let Some((mut start, mut end)) = spans.next() else {
+        return ((0, 0), Vec::new());
+    };
+    for (first, last) in spans {
+        start = (start.0.min(first.0), start.1.min(first.1));
+        end = (end.0.max(last.0), end.1.max(last.1));
+    }
+    let rows = (start.0..=end.0)
+        .map(|row| {
+            (start.1..=end.1)
+                .map(|col| match formulas.get_value((row, col)) {
+                    Some(formula) if !formula.is_empty() => format_formula(formula),
+                    _ => values
+                        .get_value((row, col))
+                        .map(|cell| formatter.format(cell, (row, col)))
+                        .unwrap_or_default(),
+                })
+                .collect()
+        })
+        .collect();
+    (start, rows)
+}
+
+/// List a sheet's formulas with their cells and cached values.
+fn write_formulas(
+    writer: &mut dyn Write,
+    values: &Range<Data>,
+    formulas: &Range<String>,
+    formatter: &CellFormatter,
+) -> Result<()> {
+    let Some((top, left)) = formulas.start() else {
+        return Ok(());
+    };
+    let mut cells = formulas
+        .used_cells()
+        .filter(|(_, _, formula)| !formula.is_empty())
+        .peekable();
+    if cells.peek().is_none() {
+        return Ok(());
+    }
+    writeln!(writer)?;
+    writeln!(writer, "## Formulas")?;
+    writeln!(writer)?;
+    writeln!(writer, "| Cell | Formula | Value |")?;
+    writeln!(writer, "| --- | --- | --- |")?;
+    for (row, col, formula) in cells {
+        let (row, col) = (top + row as u32, left + col as u32);
+        let value = values
+            .get_value((row, col))
+            .map(|cell| formatter.format(cell, (row, col)))
+            .unwrap_or_default();
+        writeln!(
+            writer,
+            "| {} | {} | {value} |",
+            cell_name(row, col),
+            format_formula(formula)
+        )?;
+    }
+    Ok(())
+}
+
+/// A formula as a code span, so operators such as `*` are not read as
+/// Markdown.
+fn format_formula(formula: &str) -> String {
+    format!("`={}`", escape_pipe(formula.trim_start_matches('=')))
+}
+
+/// The A1-style name of a zero-based cell position.
+fn cell_name(row: u32, col: u32) -> String {
+    let mut letters = Vec::new();
+    let mut n = col + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push(char::from(b'A' + (n % 26) as u8));
+        n /= 26;
+    }
+    format!("{}{}", letters.iter().rev().collect::<String>(), row + 1)
+}
+
+/// A cell range in A1 notation, such as `A1:B2`.
+fn range_name(range: &Dimensions) -> String {
+    format!(
+        "{}:{}",
+        cell_name(range.start.0, range.start.1),
+        cell_name(range.end.0, range.end.1)
+    )
+}
+
 fn is_blank_row(row: &[String]) -> bool {
     row.iter().all(|c| c.is_empty())
 }
 
-fn format_cell(data: &Data) -> String {
-    match data {
-        Data::Empty => String::new(),
-        Data::String(s) => escape_pipe(s),
-        Data::Int(n) => n.to_string(),
-        Data::Float(f) => {
-            if *f == f.trunc() {
-                format!("{f:.0}")
-            } else {
-                f.to_string()
+/// Turns cell values into table text.
+struct CellFormatter<'a> {
+    sanitize: bool,
+    /// Show numbers and dates as stored rather than as their formats
+    /// display them.
+    raw: bool,
+    number_formats: Option<&'a NumberFormats>,
+    /// Style index of each styled cell of the sheet, by position.
+    styles: HashMap<(u32, u32), usize>,
+    /// Link target of each hyperlinked cell of the sheet, by position.
+    links: HashMap<(u32, u32), String>,
+}
+
+impl<'a> CellFormatter<'a> {
+    /// A formatter for the cells of the sheet called `name`, with its
+    /// worksheet XML when the workbook is an `.xlsx` package.
+    fn new(
+        options: &ConvertOptions,
+        package: Option<&mut Package>,
+        name: &str,
+        sheet_xml: Option<&str>,
+        number_formats: Option<&'a NumberFormats>,
+    ) -> Self {
+        let raw = options.excel.raw_values;
+        Self {
+            sanitize: options.sanitize_cells,
+            raw,
+            number_formats,
+            styles: match (number_formats, sheet_xml) {
+                (Some(_), Some(xml)) => cell_styles(xml),
+                _ => HashMap::new(),
+            },
+            links: match (package, sheet_xml) {
+                (Some(package), Some(xml)) => hyperlinks(xml, &package.sheet_rels(name)),
+                _ => HashMap::new(),
+            },
+        }
+    }
+
+    fn format(&self, data: &Data, position: (u32, u32)) -> String {
+        self.format_styled(data, position, self.styles.get(&position).copied())
+    }
+
+    /// Format the cell at `position` with the given style index rather than
+    /// the one looked up in the sheet.
+    fn format_styled(&self, data: &Data, position: (u32, u32), style: Option<usize>) -> String {
+        let number = |value: f64| {
+            let code = self.number_formats?.code(style?)?;
+            format_number(value, code).map(|s| escape_pipe(&s))
+        };
+        let text = match data {
+            Data::Empty => String::new(),
+            Data::String(s) if self.sanitize => escape_pipe(&cells::sanitize(s)),
+            Data::String(s) => escape_pipe(s),
+            Data::Int(n) if self.raw => n.to_string(),
+            Data::Int(n) => number(*n as f64).unwrap_or_else(|| n.to_string()),
+            Data::Float(f) if self.raw => {
+                if *f == f.trunc() {
+                    format!("{f:.0}")
+                } else {
+                    f.to_string()
+                }
             }
+            Data::Float(f) => number(*f).unwrap_or_else(|| general(*f)),
+            Data::Bool(b) => b.to_string(),
+            Data::DateTime(dt) if self.raw => escape_pipe(&dt.to_string()),
+            Data::DateTime(dt) => format_datetime(dt),
+            Data::DateTimeIso(s) => escape_pipe(s),
+            Data::DurationIso(s) => escape_pipe(s),
+            Data::Error(e) => format!("#{e:?}"),
+        };
+        match self.links.get(&position) {
+            Some(url) if !text.is_empty() && (!self.sanitize || is_safe(url)) => link(&text, url),
+            _ => text,
         }
-        Data::Bool(b) => b.to_string(),
-        Data::DateTime(dt) => escape_pipe(&dt.to_string()),
-        Data::DateTimeIso(s) => escape_pipe(s),
-        Data::DurationIso(s) => escape_pipe(s),
-        Data::Error(e) => format!("#{e:?}"),
     }
 }
 
@@ -214,6 +1102,46 @@ mod tests {
         assert_eq!(is_blank_row(&row), expected);
     }
 
+    #[rstest]
+    fn test_visible_sheets() {
+        let sheet = |name: &str, visible| Sheet {
+            name: name.to_string(),
+            typ: calamine::SheetType::WorkSheet,
+            visible,
+        };
+        let sheets = [
+            sheet("Summary", SheetVisible::Visible),
+            sheet("Scratch", SheetVisible::Hidden),
+            sheet("Lookup", SheetVisible::VeryHidden),
+            sheet("Data", SheetVisible::Visible),
+        ];
+        assert_eq!(visible_sheets(&sheets), vec!["Summary", "Data"]);
+    }
+
+    #[rstest]
+    #[case::exact("Summary", "Summary", true)]
+    #[case::case_insensitive("summary", "SUMMARY", true)]
+    #[case::prefix("2024-*", "2024-Q1", true)]
+    #[case::prefix_mismatch("2024-*", "2023-Q1", false)]
+    #[case::suffix("* Data", "Sales Data", true)]
+    #[case::infix("Q*-*", "Q1-East", true)]
+    #[case::overlap("a*a", "a", false)]
+    #[case::partial("Sum", "Summary", false)]
+    #[case::any("*", "Anything", true)]
+    fn test_sheet_match(#[case] pattern: &str, #[case] name: &str, #[case] expected: bool) {
+        assert_eq!(sheet_match(pattern, name), expected);
+    }
+
+    #[rstest]
+    #[case(0, 0, "A1")]
+    #[case(9, 25, "Z10")]
+    #[case(0, 26, "AA1")]
+    #[case(41, 701, "ZZ42")]
+    #[case(0, 702, "AAA1")]
+    fn test_cell_name(#[case] row: u32, #[case] col: u32, #[case] expected: &str) {
+        assert_eq!(cell_name(row, col), expected);
+    }
+
     fn s(v: &[&str]) -> Vec<String> {
         v.iter().map(|s| s.to_string()).collect()
     }
@@ -303,6 +1231,11 @@ mod tests {
         /// Empty rows in `rows` (empty slices `&[]`) become gaps in row numbering
         /// so calamine produces blank rows in the Range.
         fn make_xlsx(sheet_name: &str, rows: &[&[&str]]) -> Vec<u8> {
+            make_xlsx_with(sheet_name, rows, "")
+        }
+
+        /// Like `make_xlsx`, with `extra` worksheet XML after `sheetData`.
+        fn make_xlsx_with(sheet_name: &str, rows: &[&[&str]], extra: &str) -> Vec<u8> {
             fn col_letter(i: usize) -> char {
                 (b'A' + i as u8) as char
             }
@@ -355,7 +1288,7 @@ mod tests {
             let worksheet = format!(
                 r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
-  <sheetData>{sheet_data}</sheetData>
+  <sheetData>{sheet_data}</sheetData>{extra}
 </worksheet>"#
             );
 
@@ -381,10 +1314,699 @@ mod tests {
 
         fn convert(data: &[u8]) -> String {
             let mut out = Vec::new();
-            ExcelConverter.convert(data, &mut out).unwrap();
+            ExcelConverter::default().convert(data, &mut out).unwrap();
             String::from_utf8(out).unwrap()
         }
 
+        #[test]
+        fn test_data_validation() {
+            let xlsx = make_xlsx_with(
+                "Form",
+                &[&["Status", "Quantity"], &["Open", "3"]],
+                r#"<dataValidations count="3">
+    <dataValidation type="list" allowBlank="1" showInputMessage="1" promptTitle="Status" prompt="Pick a status" sqref="A2:A100"><formula1>"Open,Closed"</formula1></dataValidation>
+    <dataValidation type="whole" operator="between" showErrorMessage="1" error="1 to 10 only" sqref="B2:B100 D2"><formula1>1</formula1><formula2>10</formula2></dataValidation>
+    <dataValidation type="custom" sqref="C2"><formula1>LEN(C2)&lt;=5</formula1></dataValidation>
+  </dataValidations>
+  <extLst><ext uri="{CCE6A557-97BC-4b89-ADB6-D9C93CAAB3DF}" xmlns:x14="http://schemas.microsoft.com/office/spreadsheetml/2009/9/main">
+    <x14:dataValidations xmlns:xm="http://schemas.microsoft.com/office/excel/2006/main" count="1">
+      <x14:dataValidation type="list"><x14:formula1><xm:f>Lists!$A$1:$A$3</xm:f></x14:formula1><xm:sqref>E2:E50</xm:sqref></x14:dataValidation>
+    </x14:dataValidations>
+  </ext></extLst>"#,
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    data_validation: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(out.contains(
+                "## Data Validation\n\n| Cells | Type | Rule | Input message | Error message |\n"
+            ));
+            assert!(out.contains(
+                "| A2:A100 | List | One of: Open, Closed (blank allowed) | Status: Pick a status |  |"
+            ));
+            assert!(
+                out.contains("| B2:B100, D2 | Whole number | Between 1 and 10 |  | 1 to 10 only |")
+            );
+            assert!(out.contains("| C2 | Custom | Formula `LEN(C2)<=5` is true |  |  |"));
+            assert!(
+                out.contains("| E2:E50 | List | One of the values in `Lists!$A$1:$A$3` |  |  |")
+            );
+
+            // Off by default
+            assert!(!convert(&xlsx).contains("Data Validation"));
+        }
+
+        /// Build an xlsx with one worksheet per `(name, sheetData)` and the
+        /// given `(sheet index, name, range)` tables.
+        fn make_workbook(sheets: &[(&str, &str)], tables: &[(usize, &str, &str)]) -> Vec<u8> {
+            make_workbook_with_rels(sheets, tables, &[], &[])
+        }
+
+        /// Like `make_workbook`, with extra `Relationship` elements for
+        /// sheets by index and extra `(path, content)` parts, which replace
+        /// generated parts at the same path. Sheet data that has its own
+        /// `sheetData` is used as the whole worksheet body.
+        fn make_workbook_with_rels(
+            sheets: &[(&str, &str)],
+            tables: &[(usize, &str, &str)],
+            sheet_rels: &[(usize, &str)],
+            extra_parts: &[(&str, &str)],
+        ) -> Vec<u8> {
+            let ns = r#"xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships""#;
+            let rel = |id: String, kind: &str, target: String| {
+                format!(
+                    r#"<Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{kind}" Target="{target}"/>"#
+                )
+            };
+            let rels = |body: String| {
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{body}</Relationships>"#
+                )
+            };
+
+            let mut parts = vec![
+                (
+                    "_rels/.rels".to_string(),
+                    rels(rel(
+                        "rId1".into(),
+                        "officeDocument",
+                        "xl/workbook.xml".into(),
+                    )),
+                ),
+                (
+                    "xl/workbook.xml".to_string(),
+                    format!(
+                        "<workbook {ns}><sheets>{}</sheets></workbook>",
+                        sheets
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (name, _))| format!(
+                                r#"<sheet name="{name}" sheetId="{}" r:id="rId{}"/>"#,
+                                i + 1,
+                                i + 1
+                            ))
+                            .collect::<String>()
+                    ),
+                ),
+                (
+                    "xl/_rels/workbook.xml.rels".to_string(),
+                    rels(
+                        (1..=sheets.len())
+                            .map(|i| {
+                                rel(
+                                    format!("rId{i}"),
+                                    "worksheet",
+                                    format!("worksheets/sheet{i}.xml"),
+                                )
+                            })
+                            .collect(),
+                    ),
+                ),
+            ];
+            for (i, (_, data)) in sheets.iter().enumerate() {
+                let body = match data.contains("<sheetData") {
+                    true => data.to_string(),
+                    false => format!("<sheetData>{data}</sheetData>"),
+                };
+                parts.push((
+                    format!("xl/worksheets/sheet{}.xml", i + 1),
+                    format!("<worksheet {ns}>{body}</worksheet>"),
+                ));
+                let mut sheet_tables: String = tables
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (sheet, _, _))| *sheet == i)
+                    .map(|(t, _)| {
+                        rel(
+                            format!("rId{}", t + 1),
+                            "table",
+                            format!("../tables/table{}.xml", t + 1),
+                        )
+                    })
+                    .collect();
+                for (_, rel) in sheet_rels.iter().filter(|(sheet, _)| *sheet == i) {
+                    sheet_tables.push_str(rel);
+                }
+                parts.push((
+                    format!("xl/worksheets/_rels/sheet{}.xml.rels", i + 1),
+                    rels(sheet_tables),
+                ));
+            }
+            for (t, (_, name, range)) in tables.iter().enumerate() {
+                parts.push((
+                    format!("xl/tables/table{}.xml", t + 1),
+                    format!(
+                        r#"<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" id="{}" name="{name}" displayName="{name}" ref="{range}"><tableColumns count="1"><tableColumn id="1" name="Amount"/></tableColumns></table>"#,
+                        t + 1
+                    ),
+                ));
+            }
+            for (path, content) in extra_parts {
+                match parts.iter_mut().find(|(p, _)| p == path) {
+                    Some(part) => part.1 = content.to_string(),
+                    None => parts.push((path.to_string(), content.to_string())),
+                }
+            }
+
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            let opts = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            for (name, content) in parts {
+                zip.start_file(name, opts).unwrap();
+                zip.write_all(content.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap().into_inner()
+        }
+
+        #[rstest]
+        #[case::formatted(false, false, "| 2024-05-01 | 25.60% | $1,234.50 | 0.3 |")]
+        #[case::raw(true, false, "| 45413 | 0.256 | 1234.5 | 0.30000000000000004 |")]
+        #[case::streamed(false, true, "| 2024-05-01 | 25.60% | $1,234.50 | 0.3 |")]
+        fn test_number_formats(
+            #[case] raw_values: bool,
+            #[case] stream: bool,
+            #[case] expected_row: &str,
+        ) {
+            let xlsx = make_workbook(
+                &[(
+                    "Sales",
+                    r#"<row r="1"><c r="A1" t="inlineStr"><is><t>Date</t></is></c><c r="B1" t="inlineStr"><is><t>Share</t></is></c><c r="C1" t="inlineStr"><is><t>Price</t></is></c><c r="D1" t="inlineStr"><is><t>Ratio</t></is></c></row><row r="2"><c r="A2" s="1"><v>45413</v></c><c r="B2" s="2"><v>0.256</v></c><c r="C2" s="3"><v>1234.5</v></c><c r="D2"><v>0.30000000000000004</v></c></row>"#,
+                )],
+                &[],
+            );
+            let styles = r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><numFmts count="1"><numFmt numFmtId="164" formatCode="&quot;$&quot;#,##0.00"/></numFmts><cellXfs count="4"><xf numFmtId="0"/><xf numFmtId="14" applyNumberFormat="1"/><xf numFmtId="10" applyNumberFormat="1"/><xf numFmtId="164" applyNumberFormat="1"/></cellXfs></styleSheet>"#;
+            let mut zip = zip::ZipWriter::new_append(std::io::Cursor::new(xlsx)).unwrap();
+            zip.start_file("xl/styles.xml", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(styles.as_bytes()).unwrap();
+            let xlsx = zip.finish().unwrap().into_inner();
+
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    raw_values,
+                    stream,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(out.contains(expected_row), "{out}");
+        }
+
+        #[rstest]
+        #[case::hidden_left_out(false, "| Item | Qty |\n|---|---|\n| Pens | 2 |\n")]
+        #[case::include_hidden(
+            true,
+            "| Item | Qty |\n|---|---|\n| Pens | 2 |\n| Old stock | 9 |\n"
+        )]
+        fn test_hidden_rows(#[case] include_hidden: bool, #[case] expected: &str) {
+            let xlsx = make_workbook(
+                &[(
+                    "Stock",
+                    r#"<row r="1"><c r="A1" t="inlineStr"><is><t>Item</t></is></c><c r="B1" t="inlineStr"><is><t>Qty</t></is></c></row><row r="2"><c r="A2" t="inlineStr"><is><t>Pens</t></is></c><c r="B2"><v>2</v></c></row><row r="3" hidden="1"><c r="A3" t="inlineStr"><is><t>Old stock</t></is></c><c r="B3"><v>9</v></c></row>"#,
+                )],
+                &[],
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    include_hidden,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                format!("# Stock\n\n{expected}")
+            );
+        }
+
+        #[rstest]
+        #[case::none(&[], "# Summary\n\ntotal\n\n# 2024-Q1\n\nq1\n\n# 2024-Q2\n\nq2\n")]
+        #[case::name(&["summary"], "# Summary\n\ntotal\n")]
+        #[case::glob(&["2024-*"], "# 2024-Q1\n\nq1\n\n# 2024-Q2\n\nq2\n")]
+        #[case::workbook_order(&["2024-Q2", "Summary"], "# Summary\n\ntotal\n\n# 2024-Q2\n\nq2\n")]
+        #[case::no_match(&["Missing"], "")]
+        fn test_sheets(#[case] sheets: &[&str], #[case] expected: &str) {
+            let cell = |text: &str| {
+                format!(r#"<row r="1"><c r="A1" t="inlineStr"><is><t>{text}</t></is></c></row>"#)
+            };
+            let xlsx = make_workbook(
+                &[
+                    ("Summary", &cell("total")),
+                    ("2024-Q1", &cell("q1")),
+                    ("2024-Q2", &cell("q2")),
+                ],
+                &[],
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    sheets: sheets.iter().map(|s| s.to_string()).collect(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+
+        fn sheet_rows(rows: &[&[&str]]) -> String {
+            let col = |i: usize| char::from(b'A' + i as u8);
+            rows.iter()
+                .enumerate()
+                .map(|(r, cells)| {
+                    let cells: String = cells
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, text)| !text.is_empty())
+                        .map(|(c, text)| {
+                            format!(
+                                r#"<c r="{}{}" t="inlineStr"><is><t>{text}</t></is></c>"#,
+                                col(c),
+                                r + 1
+                            )
+                        })
+                        .collect();
+                    let hidden = if cells.contains("hidden") {
+                        r#" hidden="1""#
+                    } else {
+                        ""
+                    };
+                    format!(r#"<row r="{}"{hidden}>{cells}</row>"#, r + 1)
+                })
+                .collect()
+        }
+
+        #[rstest]
+        #[case::loaded(
+            false,
+            None,
+            "# Stock\n\n| Item | Qty |\n|---|---|\n| Pens | 2 |\n| Ink | 5 |\n| Pads | 1 |\n\nTotal  8\n"
+        )]
+        #[case::loaded_limit(
+            false,
+            Some(2),
+            "# Stock\n\n| Item | Qty |\n|---|---|\n| Pens | 2 |\n| Ink | 5 |\n\n*Showing 2 of 3 rows*\n\nTotal  8\n"
+        )]
+        #[case::streamed(
+            true,
+            None,
+            "# Stock\n\n| Item | Qty |\n|---|---|\n| Pens | 2 |\n| Ink | 5 |\n| Pads | 1 |\n| Total | 8 |\n"
+        )]
+        #[case::streamed_limit(
+            true,
+            Some(2),
+            "# Stock\n\n| Item | Qty |\n|---|---|\n| Pens | 2 |\n| Ink | 5 |\n\n*Showing 2 of 4 rows*\n"
+        )]
+        fn test_max_rows(
+            #[case] stream: bool,
+            #[case] max_rows: Option<usize>,
+            #[case] expected: &str,
+        ) {
+            let rows = sheet_rows(&[
+                &["Item", "Qty"],
+                &["Pens", "2"],
+                &["Ink", "5"],
+                &["hidden", "9"],
+                &["Pads", "1"],
+                &[],
+                &["Total", "8"],
+            ]);
+            let xlsx = make_workbook(&[("Stock", &rows)], &[]);
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    max_rows,
+                    stream,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+
+        #[rstest]
+        #[case::loaded(false)]
+        #[case::streamed(true)]
+        fn test_rows_wider_than_header(#[case] stream: bool) {
+            let data = format!(
+                r#"<dimension ref="A1:D2"/><sheetData>{}</sheetData>"#,
+                sheet_rows(&[&["A", "B"], &["1", "2", "3", "4"]])
+            );
+            let xlsx = make_workbook(&[("Wide", &data)], &[]);
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    stream,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                "# Wide\n\n| A | B |  |  |\n|---|---|---|---|\n| 1 | 2 | 3 | 4 |\n"
+            );
+        }
+
+        #[rstest]
+        #[case::linked(
+            false,
+            false,
+            "| [Docs](https://example.com/docs%28v2%29) | [Totals](#summary) | [Run](javascript:run%28%29) |"
+        )]
+        #[case::sanitized(
+            true,
+            false,
+            "| [Docs](https://example.com/docs%28v2%29) | [Totals](#summary) | Run |"
+        )]
+        #[case::streamed(
+            false,
+            true,
+            "| [Docs](https://example.com/docs%28v2%29) | [Totals](#summary) | [Run](javascript:run%28%29) |"
+        )]
+        fn test_hyperlinks(
+            #[case] sanitize_cells: bool,
+            #[case] stream: bool,
+            #[case] expected_row: &str,
+        ) {
+            let data = format!(
+                "<sheetData>{}</sheetData><hyperlinks>\
+                 <hyperlink ref=\"A2\" r:id=\"rIdLink1\"/>\
+                 <hyperlink ref=\"B2\" location=\"Summary!A1\"/>\
+                 <hyperlink ref=\"C1:C2\" r:id=\"rIdLink2\"/>\
+                 </hyperlinks>",
+                sheet_rows(&[&["Link", "Jump", "Script"], &["Docs", "Totals", "Run"]])
+            );
+            let external = |id: &str, target: &str| {
+                format!(
+                    r#"<Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="{target}" TargetMode="External"/>"#
+                )
+            };
+            let xlsx = make_workbook_with_rels(
+                &[("Links", &data)],
+                &[],
+                &[
+                    (0, &external("rIdLink1", "https://example.com/docs(v2)")),
+                    (0, &external("rIdLink2", "javascript:run()")),
+                ],
+                &[],
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                sanitize_cells,
+                excel: crate::options::ExcelOptions {
+                    stream,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(out.contains(expected_row), "{out}");
+        }
+
+        #[rstest]
+        #[case::listed(
+            true,
+            false,
+            "\n## Comments\n\n| Cell | Author | Comment |\n| --- | --- | --- |\n\
+             | B2 | Ana | Check against the ledger |\n\
+             | A3 | Ana | =ROUND(B3, 2) \\| rounded |\n"
+        )]
+        #[case::sanitized(
+            true,
+            true,
+            "\n## Comments\n\n| Cell | Author | Comment |\n| --- | --- | --- |\n\
+             | B2 | Ana | Check against the ledger |\n\
+             | A3 | Ana | '=ROUND(B3, 2) \\| rounded |\n"
+        )]
+        #[case::off(false, false, "")]
+        fn test_comments(
+            #[case] comments: bool,
+            #[case] sanitize_cells: bool,
+            #[case] expected: &str,
+        ) {
+            let rows = sheet_rows(&[&["Item", "Cost"], &["Ink", "5"], &["Pads", "1"]]);
+            let xlsx = make_workbook_with_rels(
+                &[("Costs", &rows)],
+                &[],
+                &[(
+                    0,
+                    r#"<Relationship Id="rIdC1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments" Target="../comments1.xml"/>"#,
+                )],
+                &[(
+                    "xl/comments1.xml",
+                    r#"<comments xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><authors><author>Ana</author></authors><commentList><comment ref="B2" authorId="0"><text><r><t>Ana:</t></r><r><t xml:space="preserve">
+Check against the ledger</t></r></text></comment><comment ref="A3" authorId="0"><text><t>=ROUND(B3, 2) | rounded</t></text></comment></commentList></comments>"#,
+                )],
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                sanitize_cells,
+                excel: crate::options::ExcelOptions {
+                    comments,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            let table = "| Item | Cost |\n|---|---|\n| Ink | 5 |\n| Pads | 1 |\n";
+            assert_eq!(out, format!("# Costs\n\n{table}{expected}"));
+        }
+
+        #[rstest]
+        #[case::formatted(false, "| Q1 | 1,250 | 0.8 |\n| Q2 | 980 |  |\n")]
+        #[case::raw(true, "| Q1 | 1250 | 0.80000000000000004 |\n| Q2 | 980 |  |\n")]
+        fn test_charts(#[case] raw_values: bool, #[case] expected_rows: &str) {
+            let rows = sheet_rows(&[&["Quarter", "Sales"], &["Q1", "1250"], &["Q2", "980"]]);
+            let xlsx = make_workbook_with_rels(
+                &[("Sales", &rows)],
+                &[],
+                &[(
+                    0,
+                    r#"<Relationship Id="rIdD1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/drawing" Target="../drawings/drawing1.xml"/>"#,
+                )],
+                &[
+                    ("xl/drawings/drawing1.xml", "<xdr:wsDr/>"),
+                    (
+                        "xl/drawings/_rels/drawing1.xml.rels",
+                        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/chart" Target="../charts/chart1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/chart" Target="../charts/chart2.xml"/></Relationships>"#,
+                    ),
+                    (
+                        "xl/charts/chart1.xml",
+                        r#"<c:chartSpace xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><c:chart><c:title><c:tx><c:rich><a:p><a:r><a:t>Sales by Quarter</a:t></a:r></a:p></c:rich></c:tx></c:title><c:plotArea><c:barChart><c:ser><c:tx><c:strRef><c:f>Sales!$B$1</c:f><c:strCache><c:pt idx="0"><c:v>Sales</c:v></c:pt></c:strCache></c:strRef></c:tx><c:cat><c:strRef><c:f>Sales!$A$2:$A$3</c:f><c:strCache><c:pt idx="0"><c:v>Q1</c:v></c:pt><c:pt idx="1"><c:v>Q2</c:v></c:pt></c:strCache></c:strRef></c:cat><c:val><c:numRef><c:f>Sales!$B$2:$B$3</c:f><c:numCache><c:formatCode>#,##0</c:formatCode><c:pt idx="0"><c:v>1250</c:v></c:pt><c:pt idx="1"><c:v>980</c:v></c:pt></c:numCache></c:numRef></c:val></c:ser></c:barChart><c:lineChart><c:ser><c:val><c:numLit><c:formatCode>General</c:formatCode><c:pt idx="0"><c:v>0.80000000000000004</c:v></c:pt></c:numLit></c:val></c:ser></c:lineChart></c:plotArea></c:chart></c:chartSpace>"#,
+                    ),
+                    (
+                        "xl/charts/chart2.xml",
+                        r#"<c:chartSpace xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart"><c:chart><c:plotArea><c:pieChart/></c:plotArea></c:chart></c:chartSpace>"#,
+                    ),
+                ],
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    raw_values,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                format!(
+                    "# Sales\n\n| Quarter | Sales |\n|---|---|\n| Q1 | 1250 |\n| Q2 | 980 |\n\n\
+                     ## Charts\n\n### Sales by Quarter\n\n*Bar and line chart*\n\n\
+                     | Category | Sales | Series 2 |\n| --- | --- | --- |\n{expected_rows}\n\
+                     ### Chart 2\n\n*Pie chart*\n"
+                )
+            );
+        }
+
+        #[rstest]
+        fn test_defined_tables_and_names() {
+            let rows = sheet_rows(&[
+                &["Orders 2024", "", "", "", "0.2"],
+                &["", "", "", "", "0.1"],
+                &["Item", "Qty", "Price"],
+                &["Pens", "2", "1.5"],
+                &["hidden", "9", "9"],
+                &["Total", "2"],
+            ]);
+            let table = |id: &str, target: &str| {
+                format!(
+                    r#"<Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/table" Target="{target}"/>"#
+                )
+            };
+            let xlsx = make_workbook_with_rels(
+                &[("Orders", &rows)],
+                &[],
+                &[
+                    (0, &table("rIdT1", "../tables/table1.xml")),
+                    (0, &table("rIdT2", "../tables/table2.xml")),
+                ],
+                &[
+                    (
+                        "xl/workbook.xml",
+                        r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Orders" sheetId="1" r:id="rId1"/></sheets><definedNames><definedName name="_xlnm.Print_Area" localSheetId="0">Orders!$A$1:$C$6</definedName><definedName name="TaxRate">Orders!$E$1</definedName></definedNames></workbook>"#,
+                    ),
+                    (
+                        "xl/tables/table1.xml",
+                        r#"<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" id="1" name="Table1" displayName="OrderLines" ref="A3:C6" totalsRowCount="1"><tableColumns count="3"><tableColumn id="1" name="Item"/><tableColumn id="2" name="Qty"/><tableColumn id="3" name="Price"/></tableColumns></table>"#,
+                    ),
+                    (
+                        "xl/tables/table2.xml",
+                        r#"<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" id="2" name="Rates" displayName="Rates" ref="E1:E2" headerRowCount="0"><tableColumns count="1"><tableColumn id="1" name="Rate"/></tableColumns></table>"#,
+                    ),
+                ],
+            );
+            assert_eq!(
+                convert(&xlsx),
+                "# Orders\n\nOrders 2024\n\n\
+                 ## OrderLines\n\n| Item | Qty | Price |\n|---|---|---|\n| Pens | 2 | 1.5 |\n| Total | 2 |  |\n\n\
+                 ## Rates\n\n| Rate |\n|---|\n| 0.2 |\n| 0.1 |\n\n\
+                 # Named Ranges\n\n| Name | Refers to |\n|---|---|\n| TaxRate | `=Orders!$E$1` |\n"
+            );
+        }
+
+        #[test]
+        fn test_formula_graph() {
+            let xlsx = make_workbook(
+                &[
+                    ("Rates", r#"<row r="1"><c r="A1"><v>0.2</v></c></row>"#),
+                    (
+                        "Data",
+                        r#"<row r="1"><c r="A1" t="inlineStr"><is><t>Amount</t></is></c></row><row r="2"><c r="A2"><v>5</v></c></row>"#,
+                    ),
+                    (
+                        "Summary",
+                        r#"<row r="1"><c r="A1"><f>SUM(Sales[Amount])*Rates!A1</f><v>1</v></c><c r="B1"><f>Rates!A1*2</f><v>0.4</v></c></row>"#,
+                    ),
+                ],
+                &[(1, "Sales", "A1:A2")],
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    formula_graph: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(
+                out.ends_with(
+                    r#"
+# Formula Dependencies
+
+```mermaid
+flowchart LR
+    s0["Rates"]
+    subgraph s1["Data"]
+        t0[("Sales")]
+    end
+    s2["Summary"]
+    s0 -->|"2 formulas"| s2
+    t0 -->|"1 formula"| s2
+```
+"#
+                ),
+                "{out}"
+            );
+
+            // Off by default
+            assert!(!convert(&xlsx).contains("Formula Dependencies"));
+        }
+
+        #[rstest]
+        #[case::values(
+            Formulas::Values,
+            "| Item | Total |\n|---|---|\n| Pens | 6 |\n| Ink | 12 |\n"
+        )]
+        #[case::inline(
+            Formulas::Inline,
+            "| Item | Total |\n|---|---|\n| Pens | `=2*3` |\n| Ink | `=SUM(B2,B2)` |\n"
+        )]
+        #[case::table(
+            Formulas::Table,
+            "| Item | Total |\n|---|---|\n| Pens | 6 |\n| Ink | 12 |\n\n\
+             ## Formulas\n\n\
+             | Cell | Formula | Value |\n\
+             | --- | --- | --- |\n\
+             | B2 | `=2*3` | 6 |\n\
+             | B3 | `=SUM(B2,B2)` | 12 |\n"
+        )]
+        fn test_formulas(#[case] formulas: Formulas, #[case] expected: &str) {
+            let xlsx = make_workbook(
+                &[(
+                    "Orders",
+                    r#"<row r="1"><c r="A1" t="inlineStr"><is><t>Item</t></is></c><c r="B1" t="inlineStr"><is><t>Total</t></is></c></row><row r="2"><c r="A2" t="inlineStr"><is><t>Pens</t></is></c><c r="B2"><f>2*3</f><v>6</v></c></row><row r="3"><c r="A3" t="inlineStr"><is><t>Ink</t></is></c><c r="B3"><f>SUM(B2,B2)</f><v>12</v></c></row>"#,
+                )],
+                &[],
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    formulas,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                format!("# Orders\n\n{expected}")
+            );
+        }
+
+        #[rstest]
+        #[case::blank(
+            MergedCells::Blank,
+            "| Quarter |  | Total |\n|---|---|---|\n| Jan | Feb |  |\n| 1 | 2 | 3 |\n"
+        )]
+        #[case::repeat(
+            MergedCells::Repeat,
+            "| Quarter | Quarter | Total |\n|---|---|---|\n| Jan | Feb | Total |\n| 1 | 2 | 3 |\n"
+        )]
+        #[case::annotate(
+            MergedCells::Annotate,
+            "| Quarter |  | Total |\n|---|---|---|\n| Jan | Feb |  |\n| 1 | 2 | 3 |\n\n\
+             *Merged cells: A1:B1, C1:C2*\n"
+        )]
+        fn test_merged_cells(#[case] merged_cells: MergedCells, #[case] expected: &str) {
+            let xlsx = make_xlsx_with(
+                "Sales",
+                &[&["Quarter", "", "Total"], &["Jan", "Feb"], &["1", "2", "3"]],
+                r#"<mergeCells count="2"><mergeCell ref="C1:C2"/><mergeCell ref="A1:B1"/></mergeCells>"#,
+            );
+            let converter = ExcelConverter::new(ConvertOptions {
+                excel: crate::options::ExcelOptions {
+                    merged_cells,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            let mut out = Vec::new();
+            converter.convert(&xlsx, &mut out).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                format!("# Sales\n\n{expected}")
+            );
+        }
+
         #[test]
         fn test_pure_table() {
             let xlsx = make_xlsx(
@@ -459,6 +2081,26 @@ mod tests {
             assert!(out.contains("a\\|b"), "pipe not escaped");
         }
 
+        #[test]
+        fn test_sanitize_cells() {
+            let xlsx = make_xlsx(
+                "S",
+                &[&["Name", "Note"], &["@SUM(A1)", "&lt;i&gt;x&lt;/i&gt;"]],
+            );
+            let mut out = Vec::new();
+            ExcelConverter::new(ConvertOptions {
+                sanitize_cells: true,
+                ..Default::default()
+            })
+            .convert(&xlsx, &mut out)
+            .unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(
+                out.contains("| '@SUM(A1) | &lt;i&gt;x&lt;/i&gt; |"),
+                "{out}"
+            );
+        }
+
         #[test]
         fn test_sheet_name_as_heading() {
             let xlsx = make_xlsx("MySheet", &[&["a", "b"], &["1", "2"]]);
@@ -0,0 +1,210 @@
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+
+use super::package::{attr, local_name};
+
+/// Points beyond this index of a series' cached data are dropped, which
+/// keeps a bogus `idx` from allocating without bound.
+const MAX_POINTS: usize = 10_000;
+
+/// A chart from a `chart*.xml` part, with the data Excel caches for its
+/// series.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Chart {
+    pub title: Option<String>,
+    /// The plot types of the chart, such as `bar` or `line`; combination
+    /// charts have more than one.
+    pub kinds: Vec<String>,
+    pub series: Vec<Series>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Series {
+    pub name: Option<String>,
+    /// The category, or X, value of each point.
+    pub categories: Vec<String>,
+    /// The value, or Y, of each point as stored, unformatted.
+    pub values: Vec<String>,
+    /// The number format of the values, such as `0.0%`.
+    pub format_code: Option<String>,
+}
+
+/// The part of a series the text being read belongs to.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Categories,
+    Values,
+}
+
+/// Read a chart part: its title, plot types and the cached categories and
+/// values of each series.
+pub(super) fn chart(xml: &str) -> Chart {
+    let mut chart = Chart::default();
+    let mut reader = Reader::from_str(xml);
+    // Local names of the open elements
+    let mut stack: Vec<String> = Vec::new();
+    let mut title: Option<String> = None;
+    let mut series: Option<Series> = None;
+    let mut field: Option<Field> = None;
+    let mut point: Option<usize> = None;
+    // The level of multi-level categories being read; only the first, the
+    // one next to the axis, is kept
+    let mut level = 0;
+    let mut text = String::new();
+    loop {
+        let event = reader.read_event();
+        let (start, empty) = match &event {
+            Ok(Event::Start(e)) => (Some(e), false),
+            Ok(Event::Empty(e)) => (Some(e), true),
+            _ => (None, false),
+        };
+        if let Some(e) = start {
+            let name = local_name(e.name().as_ref());
+            let parent = stack.last().map(String::as_str);
+            match name.as_str() {
+                "title" if parent == Some("chart") => title = Some(String::new()),
+                kind if parent == Some("plotArea") && kind.ends_with("Chart") => {
+                    let kind = kind.trim_end_matches("Chart").to_string();
+                    if !kind.is_empty() && !chart.kinds.contains(&kind) {
+                        chart.kinds.push(kind);
+                    }
+                }
+                "ser" => series = Some(Series::default()),
+                "tx" if series.is_some() && parent == Some("ser") => field = Some(Field::Name),
+                "cat" | "xVal" if series.is_some() => field = Some(Field::Categories),
+                "val" | "yVal" if series.is_some() => field = Some(Field::Values),
+                "lvl" => level += 1,
+                "pt" => point = attr(e, "idx").and_then(|idx| idx.parse().ok()),
+                "formatCode" | "v" | "t" => text.clear(),
+                _ => {}
+            }
+            if !empty {
+                stack.push(name);
+            }
+            continue;
+        }
+        match event {
+            Ok(Event::Text(e)) => text.push_str(&e.decode().unwrap_or_default()),
+            Ok(Event::GeneralRef(e)) => match e.resolve_char_ref() {
+                Ok(Some(c)) => text.push(c),
+                _ => {
+                    let name = e.decode().unwrap_or_default();
+                    text.push_str(resolve_predefined_entity(&name).unwrap_or_default());
+                }
+            },
+            Ok(Event::End(_)) => {
+                let name = stack.pop().unwrap_or_default();
+                let parent = stack.last().map(String::as_str);
+                match name.as_str() {
+                    "title" if parent == Some("chart") => {
+                        chart.title = title.take().map(|t| collapse(&t)).filter(|t| !t.is_empty());
+                    }
+                    "v" | "t" | "p" if title.is_some() => {
+                        if let Some(title) = title.as_mut() {
+                            match name.as_str() {
+                                // Paragraphs of a title go on one line
+                                "p" => title.push(' '),
+                                _ => title.push_str(&text),
+                            }
+                        }
+                    }
+                    "v" => {
+                        let Some(series) = series.as_mut() else {
+                            continue;
+                        };
+                        match field {
+                            Some(Field::Name) => {
+                                series.name.get_or_insert_default().push_str(&text);
+                            }
+                            Some(Field::Categories) if level <= 1 => {
+                                set_point(&mut series.categories, point, &text);
+                            }
+                            Some(Field::Values) => set_point(&mut series.values, point, &text),
+                            _ => {}
+                        }
+                    }
+                    "formatCode" if field == Some(Field::Values) => {
+                        if let Some(series) = series.as_mut() {
+                            series.format_code = Some(text.clone());
+                        }
+                    }
+                    "pt" => point = None,
+                    "tx" | "cat" | "xVal" | "val" | "yVal" if parent == Some("ser") => {
+                        field = None;
+                        level = 0;
+                    }
+                    "ser" => chart.series.extend(series.take()),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    chart
+}
+
+fn set_point(points: &mut Vec<String>, index: Option<usize>, value: &str) {
+    let Some(index) = index.filter(|&index| index < MAX_POINTS) else {
+        return;
+    };
+    if points.len() <= index {
+        points.resize(index + 1, String::new());
+    }
+    points[index] = value.to_string();
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_chart() {
+        let xml = r#"<c:chartSpace xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><c:chart>
+<c:title><c:tx><c:rich><a:p><a:r><a:t>Sales &amp;</a:t></a:r><a:r><a:t> Costs</a:t></a:r></a:p><a:p><a:r><a:t>2024</a:t></a:r></a:p></c:rich></c:tx></c:title>
+<c:plotArea>
+<c:barChart><c:barDir val="col"/>
+<c:ser><c:idx val="0"/><c:tx><c:strRef><c:f>Data!$B$1</c:f><c:strCache><c:ptCount val="1"/><c:pt idx="0"><c:v>Revenue</c:v></c:pt></c:strCache></c:strRef></c:tx>
+<c:cat><c:multiLvlStrRef><c:f>Data!$A$2:$A$3</c:f><c:multiLvlStrCache><c:ptCount val="2"/>
+<c:lvl><c:pt idx="0"><c:v>Q1</c:v></c:pt><c:pt idx="1"><c:v>Q2</c:v></c:pt></c:lvl>
+<c:lvl><c:pt idx="0"><c:v>H1</c:v></c:pt></c:lvl></c:multiLvlStrCache></c:multiLvlStrRef></c:cat>
+<c:val><c:numRef><c:f>Data!$B$2:$B$3</c:f><c:numCache><c:formatCode>#,##0</c:formatCode><c:ptCount val="2"/><c:pt idx="1"><c:v>1200</c:v></c:pt><c:pt idx="0"><c:v>950</c:v></c:pt></c:numCache></c:numRef></c:val>
+</c:ser>
+<c:axId val="1"/><c:axId val="2"/></c:barChart>
+<c:lineChart>
+<c:ser><c:idx val="1"/><c:tx><c:v>Target</c:v></c:tx>
+<c:val><c:numLit><c:ptCount val="2"/><c:pt idx="0"><c:v>1000</c:v></c:pt></c:numLit></c:val></c:ser>
+</c:lineChart>
+<c:valAx><c:axId val="2"/><c:title><c:tx><c:rich><a:p><a:r><a:t>Euros</a:t></a:r></a:p></c:rich></c:tx></c:title></c:valAx>
+</c:plotArea></c:chart></c:chartSpace>"#;
+        assert_eq!(
+            chart(xml),
+            Chart {
+                title: Some("Sales & Costs 2024".into()),
+                kinds: vec!["bar".into(), "line".into()],
+                series: vec![
+                    Series {
+                        name: Some("Revenue".into()),
+                        categories: vec!["Q1".into(), "Q2".into()],
+                        values: vec!["950".into(), "1200".into()],
+                        format_code: Some("#,##0".into()),
+                    },
+                    Series {
+                        name: Some("Target".into()),
+                        categories: Vec::new(),
+                        values: vec!["1000".into()],
+                        format_code: None,
+                    },
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+
+use super::package::{attr, local_name};
+
+/// A cell comment, or note, from a sheet's `comments*.xml` part.
+#[derive(Debug, PartialEq)]
+pub(super) struct Comment {
+    /// The cell the comment is attached to, e.g. `B2`.
+    pub cell: String,
+    pub author: String,
+    pub text: String,
+}
+
+/// Read every comment of a comments part, in document order.
+pub(super) fn comments(xml: &str) -> Vec<Comment> {
+    let mut authors = Vec::new();
+    let mut comments = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    // The comment being read, with the index of its author
+    let mut current: Option<(Comment, Option<usize>)> = None;
+    let mut author: Option<String> = None;
+    // Phonetic runs repeat the text they annotate
+    let mut phonetic = false;
+    let mut in_text = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match local_name(e.name().as_ref()).as_str() {
+                "author" => author = Some(String::new()),
+                "comment" => {
+                    let comment = Comment {
+                        cell: attr(&e, "ref").unwrap_or_default(),
+                        author: String::new(),
+                        text: String::new(),
+                    };
+                    let author_id = attr(&e, "authorId").and_then(|id| id.parse().ok());
+                    current = Some((comment, author_id));
+                }
+                "rPh" => phonetic = true,
+                "t" => in_text = true,
+                _ => {}
+            },
+            Ok(event @ (Event::Text(_) | Event::GeneralRef(_))) => {
+                let text = match event {
+                    Event::Text(e) => e.decode().unwrap_or_default().into_owned(),
+                    Event::GeneralRef(e) => match e.resolve_char_ref() {
+                        Ok(Some(c)) => c.to_string(),
+                        _ => {
+                            let name = e.decode().unwrap_or_default();
+                            resolve_predefined_entity(&name)
+                                .unwrap_or_default()
+                                .to_string()
+                        }
+                    },
+                    _ => continue,
+                };
+                if let Some(author) = author.as_mut() {
+                    author.push_str(&text);
+                } else if let Some((comment, _)) = current.as_mut()
+                    && in_text
+                    && !phonetic
+                {
+                    comment.text.push_str(&text);
+                }
+            }
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()).as_str() {
+                "author" => authors.extend(author.take()),
+                "comment" => {
+                    if let Some((mut comment, author_id)) = current.take() {
+                        comment.author = author_id
+                            .and_then(|id| authors.get(id))
+                            .cloned()
+                            .unwrap_or_default();
+                        comment.text = body(&comment.text, &comment.author);
+                        // Threaded comments name their author by an id
+                        if comment.author.starts_with("tc=") {
+                            comment.author.clear();
+                        }
+                        comments.push(comment);
+                    }
+                }
+                "rPh" => phonetic = false,
+                "t" => in_text = false,
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    comments
+}
+
+/// The text of a comment without the boilerplate Excel adds: the `Author:`
+/// line it starts notes with, and the notice older versions show in place
+/// of a threaded comment.
+fn body(text: &str, author: &str) -> String {
+    let text = match text.split_once("\nComment:\n") {
+        Some((notice, comment)) if notice.starts_with("[Threaded comment]") => comment,
+        _ => text,
+    };
+    let text = match text.strip_prefix(author).and_then(|t| t.strip_prefix(':')) {
+        Some(rest) if !author.is_empty() => rest,
+        _ => text,
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_comments() {
+        let xml = r#"<comments xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<authors><author>Jane Doe</author><author>tc={6A1F}</author></authors>
+<commentList>
+<comment ref="B2" authorId="0"><text><r><rPr><b/></rPr><t>Jane Doe:</t></r><r><t xml:space="preserve">
+Check the Q3 &amp; Q4 totals</t></r></text></comment>
+<comment ref="C5" authorId="1"><text><t>[Threaded comment]
+
+Your version of Excel allows you to read this threaded comment; however, any edits to it will get removed if the file is opened in a newer version of Excel. Learn more: https://go.microsoft.com/fwlink/?linkid=870924
+
+Comment:
+    Source is the 2023 audit</t></text></comment>
+<comment ref="D1" authorId="9"><text><t>単価</t><rPh sb="0" eb="2"><t>タンカ</t></rPh></text></comment>
+</commentList>
+</comments>"#;
+        assert_eq!(
+            comments(xml),
+            vec![
+                Comment {
+                    cell: "B2".into(),
+                    author: "Jane Doe".into(),
+                    text: "Check the Q3 & Q4 totals".into(),
+                },
+                Comment {
+                    cell: "C5".into(),
+                    author: String::new(),
+                    text: "Source is the 2023 audit".into(),
+                },
+                Comment {
+                    cell: "D1".into(),
+                    author: String::new(),
+                    text: "単価".into(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use crate::error::Result;
+
+/// A sheet or table that formulas read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Source {
+    Sheet(usize),
+    Table(usize),
+}
+
+/// Which sheets and tables the formulas of each sheet read from, for
+/// documenting how a workbook computes its results.
+#[derive(Debug)]
+pub(super) struct Dependencies {
+    sheets: Vec<String>,
+    /// Table names with the index of the sheet holding them.
+    tables: Vec<(String, usize)>,
+    /// Sheets each defined name refers to.
+    names: Vec<(String, Vec<usize>)>,
+    /// Number of formulas on a sheet reading from a source, by
+    /// (source, sheet).
+    edges: BTreeMap<(Source, usize), usize>,
+}
+
+impl Dependencies {
+    pub(super) fn new(
+        sheets: Vec<String>,
+        tables: Vec<(String, usize)>,
+        defined_names: &[(String, String)],
+    ) -> Self {
+        let mut deps = Self {
+            sheets,
+            tables,
+            names: Vec::new(),
+            edges: BTreeMap::new(),
+        };
+        // Names are resolved to the sheets their own formula refers to
+        let names = defined_names
+            .iter()
+            .map(|(name, formula)| {
+                let sheets = references(formula)
+                    .iter()
+                    .flat_map(|r| deps.sheets_of(r))
+                    .collect();
+                (name.clone(), sheets)
+            })
+            .collect();
+        deps.names = names;
+        deps
+    }
+
+    /// Record the sources `formula`, found on sheet `sheet`, reads from.
+    pub(super) fn add_formula(&mut self, sheet: usize, formula: &str) {
+        let mut sources = BTreeSet::new();
+        for reference in references(formula) {
+            match &reference {
+                Reference::Table(name) => {
+                    if let Some(i) = self.find_table(name) {
+                        sources.insert(Source::Table(i));
+                    }
+                }
+                Reference::Name(name) => {
+                    if let Some((_, sheets)) = self
+                        .names
+                        .iter()
+                        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                    {
+                        sources.extend(sheets.iter().map(|&s| Source::Sheet(s)));
+                    }
+                }
+                _ => sources.extend(self.sheets_of(&reference).into_iter().map(Source::Sheet)),
+            }
+        }
+        for source in sources {
+            let own = match source {
+                Source::Sheet(s) => s == sheet,
+                Source::Table(t) => self.tables[t].1 == sheet,
+            };
+            if !own {
+                *self.edges.entry((source, sheet)).or_default() += 1;
+            }
+        }
+    }
+
+    /// Sheets named by a sheet or 3-D reference.
+    fn sheets_of(&self, reference: &Reference) -> Vec<usize> {
+        match reference {
+            Reference::Sheet(name) => self.find_sheet(name).into_iter().collect(),
+            Reference::Span(first, last) => match (self.find_sheet(first), self.find_sheet(last)) {
+                (Some(a), Some(b)) => (a.min(b)..=a.max(b)).collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    fn find_sheet(&self, name: &str) -> Option<usize> {
+        self.sheets
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case(name))
+    }
+
+    fn find_table(&self, name: &str) -> Option<usize> {
+        self.tables
+            .iter()
+            .position(|(t, _)| t.eq_ignore_ascii_case(name))
+    }
+
+    /// Write a `Formula Dependencies` section with a Mermaid flowchart whose
+    /// arrows point from the sheet or table read to the sheet reading it,
+    /// labelled with the number of formulas. Tables are drawn inside the
+    /// sheet holding them.
+    pub(super) fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        writeln!(writer)?;
+        writeln!(writer, "# Formula Dependencies")?;
+        writeln!(writer)?;
+        if self.edges.is_empty() {
+            writeln!(writer, "*No formulas refer to other sheets or tables.*")?;
+            return Ok(());
+        }
+
+        let mut sheets = BTreeSet::new();
+        let mut tables = BTreeSet::new();
+        for &(source, sheet) in self.edges.keys() {
+            sheets.insert(sheet);
+            match source {
+                Source::Sheet(s) => {
+                    sheets.insert(s);
+                }
+                Source::Table(t) => {
+                    tables.insert(t);
+                    sheets.insert(self.tables[t].1);
+                }
+            }
+        }
+
+        writeln!(writer, "```mermaid")?;
+        writeln!(writer, "flowchart LR")?;
+        for &sheet in &sheets {
+            let label = mermaid_label(&self.sheets[sheet]);
+            let held: Vec<usize> = tables
+                .iter()
+                .copied()
+                .filter(|&t| self.tables[t].1 == sheet)
+                .collect();
+            if held.is_empty() {
+                writeln!(writer, "    s{sheet}[\"{label}\"]")?;
+                continue;
+            }
+            writeln!(writer, "    subgraph s{sheet}[\"{label}\"]")?;
+            for table in held {
+                let label = mermaid_label(&self.tables[table].0);
+                writeln!(writer, "        t{table}[(\"{label}\")]")?;
+            }
+            writeln!(writer, "    end")?;
+        }
+        for (&(source, sheet), &count) in &self.edges {
+            let source = match source {
+                Source::Sheet(s) => format!("s{s}"),
+                Source::Table(t) => format!("t{t}"),
+            };
+            let formulas = if count == 1 { "formula" } else { "formulas" };
+            writeln!(writer, "    {source} -->|\"{count} {formulas}\"| s{sheet}")?;
+        }
+        writeln!(writer, "```")?;
+        Ok(())
+    }
+}
+
+fn mermaid_label(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+/// Something a formula refers to by name.
+#[derive(Debug, PartialEq)]
+enum Reference {
+    /// `Sheet1!A1` or `'My Sheet'!A1`.
+    Sheet(String),
+    /// A 3-D reference such as `Jan:Dec!B2`, covering every sheet between.
+    Span(String, String),
+    /// A structured reference such as `Sales[Amount]`.
+    Table(String),
+    /// Any other identifier: a defined name, a cell reference or a
+    /// constant such as `TRUE`.
+    Name(String),
+}
+
+/// The references in a formula, skipping string literals and references
+/// to other workbooks (`[1]Sheet1!A1`).
+fn references(formula: &str) -> Vec<Reference> {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut refs = Vec::new();
+    let mut i = 0;
+    // Set after a `[n]` external workbook prefix
+    let mut external = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' {
+            i = skip_quoted(&chars, i, '"').1;
+            continue;
+        }
+        if c == '[' {
+            // An external workbook index or the column part of a structured
+            // reference without its table name, e.g. `[@Amount]`
+            i = skip_brackets(&chars, i);
+            external = true;
+            continue;
+        }
+        if c == '\'' {
+            let (name, end) = skip_quoted(&chars, i, '\'');
+            i = end;
+            let external = std::mem::take(&mut external) || name.starts_with('[');
+            if chars.get(i) == Some(&'!') {
+                i = skip_address(&chars, i + 1);
+                if !external {
+                    refs.push(sheet_reference(&name));
+                }
+            }
+            continue;
+        }
+        if is_name_start(c) {
+            let start = i;
+            i = skip_address(&chars, i);
+            let name: String = chars[start..i].iter().collect();
+            let external = std::mem::take(&mut external);
+            match chars.get(i) {
+                Some('!') => {
+                    i = skip_address(&chars, i + 1);
+                    if !external {
+                        refs.push(sheet_reference(&name));
+                    }
+                }
+                Some('[') => {
+                    refs.push(Reference::Table(name));
+                    i = skip_brackets(&chars, i);
+                }
+                Some('(') => {}
+                _ => refs.push(Reference::Name(name)),
+            }
+            continue;
+        }
+        external = false;
+        i += 1;
+    }
+    refs
+}
+
+fn sheet_reference(name: &str) -> Reference {
+    match name.split_once(':') {
+        Some((first, last)) => Reference::Span(first.to_string(), last.to_string()),
+        None => Reference::Sheet(name.to_string()),
+    }
+}
+
+/// The text between the quote at `start` and its closing quote, with
+/// doubled quotes unescaped, and the index after the closing quote.
+fn skip_quoted(chars: &[char], start: usize, quote: char) -> (String, usize) {
+    let mut text = String::new();
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                text.push(quote);
+                i += 2;
+                continue;
+            }
+            return (text, i + 1);
+        }
+        text.push(chars[i]);
+        i += 1;
+    }
+    (text, i)
+}
+
+/// The index after the name or cell address starting at `start`.
+fn skip_address(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+    while i < chars.len() && is_name_char(chars[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// The index after the `]` matching the `[` at `start`.
+fn skip_brackets(chars: &[char], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    chars.len()
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '\\' || c == '$'
+}
+
+/// Characters of sheet names, defined names and cell references; `:` joins
+/// the two ends of a 3-D reference.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '\\' | '$' | ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn sheet(name: &str) -> Reference {
+        Reference::Sheet(name.to_string())
+    }
+
+    fn name(name: &str) -> Reference {
+        Reference::Name(name.to_string())
+    }
+
+    #[rstest]
+    #[case::unquoted("SUM(Inputs!A1:A3)*2", vec![sheet("Inputs")])]
+    #[case::quoted("'Q1 ''24'!B2+Rates!C1", vec![sheet("Q1 '24"), sheet("Rates")])]
+    #[case::span("SUM(Jan:Mar!B2)", vec![Reference::Span("Jan".into(), "Mar".into())])]
+    #[case::table("SUM(Sales[Amount])+Sales[[#Totals],[Tax]]", vec![Reference::Table("Sales".into()), Reference::Table("Sales".into())])]
+    #[case::names_and_cells("TaxRate*$B$2", vec![name("TaxRate"), name("$B$2")])]
+    #[case::string_literal(r#"IF(A1="Other!x","Inputs!A1",0)"#, vec![name("A1")])]
+    #[case::external("[1]Prices!A1+'[2]Old data'!B1", vec![])]
+    fn test_references(#[case] formula: &str, #[case] expected: Vec<Reference>) {
+        assert_eq!(references(formula), expected);
+    }
+
+    #[rstest]
+    fn test_write_graph() {
+        let sheets = ["Inputs", "Data", "Summary", "Notes \"draft\""]
+            .map(String::from)
+            .to_vec();
+        let mut deps = Dependencies::new(
+            sheets,
+            vec![("Sales".to_string(), 1)],
+            &[("TaxRate".to_string(), "Inputs!$B$1".to_string())],
+        );
+        deps.add_formula(2, "SUM(Sales[Amount])*TaxRate");
+        deps.add_formula(2, "Inputs!A1+Inputs!A2");
+        deps.add_formula(1, "Data!A1*2");
+        deps.add_formula(3, "Summary!A1");
+
+        let mut output = Vec::new();
+        deps.write(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"
+# Formula Dependencies
+
+```mermaid
+flowchart LR
+    s0["Inputs"]
+    subgraph s1["Data"]
+        t0[("Sales")]
+    end
+    s2["Summary"]
+    s3["Notes #quot;draft#quot;"]
+    s0 -->|"2 formulas"| s2
+    s2 -->|"1 formula"| s3
+    t0 -->|"1 formula"| s2
+```
+"#
+        );
+    }
+
+    #[rstest]
+    fn test_write_without_dependencies() {
+        let mut deps = Dependencies::new(vec!["Only".to_string()], Vec::new(), &[]);
+        deps.add_formula(0, "A1+Only!B1");
+        let mut output = Vec::new();
+        deps.write(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\n# Formula Dependencies\n\n*No formulas refer to other sheets or tables.*\n"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use super::package::{attr, local_name};
+
+/// The rows and columns a worksheet hides, zero-based.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Hidden {
+    rows: HashSet<u32>,
+    columns: HashSet<u32>,
+}
+
+impl Hidden {
+    /// Read the `hidden` flags of a worksheet's `row` and `col` elements.
+    pub(super) fn parse(xml: &str) -> Self {
+        let mut hidden = Self::default();
+        let mut reader = Reader::from_str(xml);
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e) | Event::Empty(e))
+                    if attr(&e, "hidden").is_some_and(|v| v == "1" || v == "true") =>
+                {
+                    let number = |name: &str| attr(&e, name).and_then(|v| v.parse::<u32>().ok());
+                    match local_name(e.name().as_ref()).as_str() {
+                        "row" => hidden
+                            .rows
+                            .extend(number("r").and_then(|r| r.checked_sub(1))),
+                        "col" => {
+                            if let (Some(min), Some(max)) = (number("min"), number("max")) {
+                                hidden.columns.extend(min.saturating_sub(1)..max);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+        hidden
+    }
+
+    /// Remove the hidden rows and columns from `rows`, whose first cell is
+    /// at `origin` on the sheet.
+    pub(super) fn remove(&self, rows: &mut Vec<Vec<String>>, origin: (u32, u32)) {
+        let mut row = origin.0;
+        rows.retain(|_| {
+            row += 1;
+            !self.rows.contains(&(row - 1))
+        });
+        for cells in rows {
+            self.remove_columns(cells, origin.1);
+        }
+    }
+
+    /// Remove the cells of hidden columns from a row whose first cell is in
+    /// column `first`.
+    pub(super) fn remove_columns(&self, cells: &mut Vec<String>, first: u32) {
+        if self.columns.is_empty() {
+            return;
+        }
+        let mut col = first;
+        cells.retain(|_| {
+            col += 1;
+            !self.columns.contains(&(col - 1))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_hidden_rows_and_columns() {
+        let xml = r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<cols><col min="1" max="1" width="9"/><col min="2" max="3" hidden="1"/></cols>
+<sheetData><row r="1"/><row r="2" hidden="1"/><row r="3" hidden="true"><c r="A3"/></row></sheetData>
+</worksheet>"#;
+        let hidden = Hidden::parse(xml);
+        assert_eq!(
+            hidden,
+            Hidden {
+                rows: HashSet::from([1, 2]),
+                columns: HashSet::from([1, 2]),
+            }
+        );
+
+        // The rows start at B2 on the sheet
+        let mut rows = vec![
+            vec!["B2".to_string(), "C2".to_string(), "D2".to_string()],
+            vec!["B3".to_string(), "C3".to_string(), "D3".to_string()],
+            vec!["B4".to_string(), "C4".to_string(), "D4".to_string()],
+        ];
+        hidden.remove(&mut rows, (1, 1));
+        assert_eq!(rows, vec![vec!["D4".to_string()]]);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use super::number_format::cell_range;
+use super::package::{attr, local_name};
+use crate::formats::anchors::Slugger;
+
+/// Ranges larger than this link only their top-left cell, so a link over
+/// whole columns does not map a million cells.
+const MAX_LINKED_CELLS: u64 = 65_536;
+
+/// The link target of each hyperlinked cell of a worksheet, by zero-based
+/// position. `rels` are the worksheet's relationship targets by id, which
+/// hold the URLs of external links; links to a place in the workbook point
+/// to the heading of the sheet they name.
+pub(super) fn hyperlinks(xml: &str, rels: &HashMap<String, String>) -> HashMap<(u32, u32), String> {
+    let mut links = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e))
+                if local_name(e.name().as_ref()) == "hyperlink" =>
+            {
+                let target = attr(&e, "id").and_then(|id| rels.get(&id).cloned());
+                let location = attr(&e, "location").filter(|l| !l.is_empty());
+                let url = match (target, location) {
+                    (Some(target), Some(location)) => format!("{target}#{location}"),
+                    (Some(target), None) => target,
+                    (None, Some(location)) => format!("#{}", sheet_anchor(&location)),
+                    (None, None) => continue,
+                };
+                let Some((start, end)) = attr(&e, "ref").as_deref().and_then(cell_range) else {
+                    continue;
+                };
+                let cells = u64::from(end.0 - start.0 + 1) * u64::from(end.1 - start.1 + 1);
+                if cells > MAX_LINKED_CELLS {
+                    links.insert(start, url);
+                    continue;
+                }
+                for row in start.0..=end.0 {
+                    for col in start.1..=end.1 {
+                        links.insert((row, col), url.clone());
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    links
+}
+
+/// The anchor of the sheet heading a location such as `'Q1 Sales'!A1`
+/// refers to. Locations without a sheet, such as defined names, are
+/// slugged as they are.
+fn sheet_anchor(location: &str) -> String {
+    let sheet = match location.rsplit_once('!') {
+        Some((sheet, _)) => sheet,
+        None => location,
+    };
+    let sheet = match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        Some(quoted) => quoted.replace("''", "'"),
+        None => sheet.to_string(),
+    };
+    Slugger::default().slug(&sheet)
+}
+
+/// A cell's text as a Markdown link to `url`. The text is expected to be
+/// escaped for a table cell already; characters that would end the link
+/// are escaped or percent-encoded.
+pub(super) fn link(text: &str, url: &str) -> String {
+    let text = text.replace('[', "\\[").replace(']', "\\]");
+    let mut destination = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            ' ' => destination.push_str("%20"),
+            '(' => destination.push_str("%28"),
+            ')' => destination.push_str("%29"),
+            '|' => destination.push_str("%7C"),
+            '<' => destination.push_str("%3C"),
+            '>' => destination.push_str("%3E"),
+            c => destination.push(c),
+        }
+    }
+    format!("[{text}]({destination})")
+}
+
+/// Whether `url` is safe to link to when cells are sanitized: web and mail
+/// links and places in the document, but not `javascript:` or `data:`.
+pub(super) fn is_safe(url: &str) -> bool {
+    let scheme = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| !scheme.contains(['/', '#', '?']));
+    match scheme {
+        Some(scheme) => ["http", "https", "mailto"]
+            .iter()
+            .any(|safe| scheme.eq_ignore_ascii_case(safe)),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_hyperlinks() {
+        let xml = r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<sheetData/>
+<hyperlinks>
+<hyperlink ref="A2" r:id="rId1"/>
+<hyperlink ref="B2:B3" r:id="rId2" location="intro"/>
+<hyperlink ref="C2" location="'Q1 Sales'!A1" display="Q1"/>
+<hyperlink ref="D2" r:id="rId9"/>
+<hyperlink ref="A1:XFD1048576" location="Totals"/>
+</hyperlinks>
+</worksheet>"#;
+        let rels = HashMap::from([
+            ("rId1".to_string(), "https://example.com/".to_string()),
+            ("rId2".to_string(), "https://example.com/guide".to_string()),
+        ]);
+        assert_eq!(
+            hyperlinks(xml, &rels),
+            HashMap::from([
+                ((1, 0), "https://example.com/".to_string()),
+                ((1, 1), "https://example.com/guide#intro".to_string()),
+                ((2, 1), "https://example.com/guide#intro".to_string()),
+                ((1, 2), "#q1-sales".to_string()),
+                ((0, 0), "#totals".to_string()),
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::plain("Docs", "https://example.com/a", "[Docs](https://example.com/a)")]
+    #[case::brackets(
+        "[draft]",
+        "https://example.com/",
+        "[\\[draft\\]](https://example.com/)"
+    )]
+    #[case::encoded(
+        "Wiki",
+        "https://en.wikipedia.org/wiki/Rust_(language) x|y",
+        "[Wiki](https://en.wikipedia.org/wiki/Rust_%28language%29%20x%7Cy)"
+    )]
+    fn test_link(#[case] text: &str, #[case] url: &str, #[case] expected: &str) {
+        assert_eq!(link(text, url), expected);
+    }
+
+    #[rstest]
+    #[case::https("https://example.com", true)]
+    #[case::mail("MAILTO:team@example.com", true)]
+    #[case::anchor("#summary", true)]
+    #[case::relative("docs/report.pdf", true)]
+    #[case::javascript("javascript:alert(1)", false)]
+    #[case::data("data:text/html,<b>x</b>", false)]
+    #[case::file("file:///etc/passwd", false)]
+    fn test_is_safe(#[case] url: &str, #[case] expected: bool) {
+        assert_eq!(is_safe(url), expected);
+    }
+}
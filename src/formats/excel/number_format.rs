@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use calamine::ExcelDateTime;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use super::package::{attr, local_name};
+
+/// The number format code of each cell style in a workbook's
+/// `styles.xml`, by style index (the `s` attribute of a cell).
+#[derive(Debug, Default)]
+pub(super) struct NumberFormats {
+    styles: Vec<String>,
+}
+
+impl NumberFormats {
+    pub(super) fn parse(xml: &str) -> Self {
+        let mut custom = HashMap::new();
+        let mut ids = Vec::new();
+        let mut in_cell_xfs = false;
+        let mut reader = Reader::from_str(xml);
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e) | Event::Empty(e)) => {
+                    match local_name(e.name().as_ref()).as_str() {
+                        "numFmt" => {
+                            if let (Some(id), Some(code)) =
+                                (attr(&e, "numFmtId"), attr(&e, "formatCode"))
+                            {
+                                custom.insert(id, code);
+                            }
+                        }
+                        "cellXfs" => in_cell_xfs = true,
+                        "xf" if in_cell_xfs => ids.push(attr(&e, "numFmtId").unwrap_or_default()),
+                        _ => {}
+                    }
+                }
+                Ok(Event::End(e)) if local_name(e.name().as_ref()) == "cellXfs" => {
+                    in_cell_xfs = false;
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+        let styles = ids
+            .into_iter()
+            .map(|id| {
+                custom
+                    .get(&id)
+                    .cloned()
+                    .or_else(|| builtin(&id).map(String::from))
+                    .unwrap_or_default()
+            })
+            .collect();
+        Self { styles }
+    }
+
+    /// The format code of cell style `index`, unless it is `General`.
+    pub(super) fn code(&self, index: usize) -> Option<&str> {
+        self.styles
+            .get(index)
+            .map(String::as_str)
+            .filter(|code| !code.is_empty() && !code.eq_ignore_ascii_case("general"))
+    }
+}
+
+/// The built-in number formats that are not dates, in their `en-US` form.
+fn builtin(id: &str) -> Option<&'static str> {
+    Some(match id {
+        "1" => "0",
+        "2" => "0.00",
+        "3" => "#,##0",
+        "4" => "#,##0.00",
+        "5" => "$#,##0;($#,##0)",
+        "6" => "$#,##0;[Red]($#,##0)",
+        "7" => "$#,##0.00;($#,##0.00)",
+        "8" => "$#,##0.00;[Red]($#,##0.00)",
+        "9" => "0%",
+        "10" => "0.00%",
+        "37" => "#,##0;(#,##0)",
+        "38" => "#,##0;[Red](#,##0)",
+        "39" => "#,##0.00;(#,##0.00)",
+        "40" => "#,##0.00;[Red](#,##0.00)",
+        _ => return None,
+    })
+}
+
+/// The style index of each styled cell of a worksheet, by zero-based
+/// `(row, column)`.
+pub(super) fn cell_styles(xml: &str) -> HashMap<(u32, u32), usize> {
+    let mut styles = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) if local_name(e.name().as_ref()) == "c" => {
+                let position = attr(&e, "r").and_then(|r| cell_position(&r));
+                let style = attr(&e, "s").and_then(|s| s.parse().ok());
+                if let (Some(position), Some(style)) = (position, style) {
+                    styles.insert(position, style);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    styles
+}
+
+/// The style index of each styled cell of a worksheet, read from the
+/// worksheet XML alongside its cells so that it is never held in memory.
+/// Cells have to be asked for in the order they are stored, row by row.
+pub(super) struct StyleCursor<'r> {
+    reader: Reader<&'r mut dyn BufRead>,
+    buf: Vec<u8>,
+    /// The styled cell read ahead of the last position asked for.
+    next: Option<((u32, u32), usize)>,
+    done: bool,
+}
+
+impl<'r> StyleCursor<'r> {
+    pub(super) fn new(xml: &'r mut dyn BufRead) -> Self {
+        Self {
+            reader: Reader::from_reader(xml),
+            buf: Vec::new(),
+            next: None,
+            done: false,
+        }
+    }
+
+    /// The style index of the cell at `position`, skipping the styled cells
+    /// before it.
+    pub(super) fn style(&mut self, position: (u32, u32)) -> Option<usize> {
+        loop {
+            if let Some((next, style)) = self.next {
+                if next > position {
+                    return None;
+                }
+                self.next = None;
+                if next == position {
+                    return Some(style);
+                }
+            }
+            if self.done {
+                return None;
+            }
+            self.next = self.read_next();
+            self.done = self.next.is_none();
+        }
+    }
+
+    fn read_next(&mut self) -> Option<((u32, u32), usize)> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf) {
+                Ok(Event::Start(e) | Event::Empty(e)) if local_name(e.name().as_ref()) == "c" => {
+                    let position = attr(&e, "r").and_then(|r| cell_position(&r));
+                    let style = attr(&e, "s").and_then(|s| s.parse().ok());
+                    if let (Some(position), Some(style)) = (position, style) {
+                        return Some((position, style));
+                    }
+                }
+                Ok(Event::Eof) | Err(_) => return None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Parse an A1-style reference such as `AB12` into zero-based
+/// `(row, column)`.
+pub(super) fn cell_position(reference: &str) -> Option<(u32, u32)> {
+    let digits = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, row) = reference.split_at(digits);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let col = letters
+        .bytes()
+        .fold(0u32, |col, b| col * 26 + u32::from(b - b'A') + 1);
+    Some((row.parse::<u32>().ok()?.checked_sub(1)?, col - 1))
+}
+
+/// A cell reference or range such as `B2` or `A1:C3`, as its top-left and
+/// bottom-right positions.
+pub(super) fn cell_range(reference: &str) -> Option<((u32, u32), (u32, u32))> {
+    let (start, end) = match reference.split_once(':') {
+        Some((start, end)) => (cell_position(start)?, cell_position(end)?),
+        None => (cell_position(reference)?, cell_position(reference)?),
+    };
+    Some((
+        (start.0.min(end.0), start.1.min(end.1)),
+        (start.0.max(end.0), start.1.max(end.1)),
+    ))
+}
+
+/// Format `value` the way Excel displays it with the number format
+/// `code`: fixed decimals, thousands separators, percentages and the
+/// currency symbols and other text around the number. `None` for codes
+/// this does not cover, such as scientific, fraction and text formats.
+pub(super) fn format_number(value: f64, code: &str) -> Option<String> {
+    let sections = split_sections(code);
+    // The second section, when there is one, formats negative numbers and
+    // carries its own sign, such as the parentheses of accounting formats
+    let (section, value, signed) = match sections.get(1) {
+        Some(negative) if value < 0.0 => (*negative, -value, false),
+        _ => (sections[0], value, true),
+    };
+
+    let (mut prefix, mut suffix, mut pattern) = (String::new(), String::new(), String::new());
+    let mut percent = false;
+    let mut chars = section.chars();
+    while let Some(c) = chars.next() {
+        let literal = match c {
+            '0' | '#' | '?' | ',' | '.' if suffix.is_empty() => {
+                pattern.push(c);
+                continue;
+            }
+            '0' | '#' | '?' => return None,
+            '"' => chars.by_ref().take_while(|&c| c != '"').collect(),
+            '\\' => chars.next()?.to_string(),
+            // Padding to the width of a character, and fill characters
+            '_' | '*' => {
+                chars.next();
+                continue;
+            }
+            '[' => {
+                let bracket: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                // `[$€-407]` is a currency symbol with its locale; colors
+                // and conditions are left out
+                match bracket.strip_prefix('$') {
+                    Some(symbol) => symbol.split('-').next().unwrap_or_default().to_string(),
+                    None => continue,
+                }
+            }
+            '%' => {
+                percent = true;
+                c.to_string()
+            }
+            // Dates, times, exponents and text placeholders
+            c if c.is_alphabetic() || c == '@' || c == '/' => return None,
+            c => c.to_string(),
+        };
+        if pattern.is_empty() {
+            prefix.push_str(&literal);
+        } else {
+            suffix.push_str(&literal);
+        }
+    }
+    if !pattern.contains(['0', '#', '?']) {
+        return None;
+    }
+
+    // Commas right of the last digit placeholder scale by a thousand each
+    let digits = pattern.trim_end_matches(',');
+    let mut value = value / 1000f64.powi((pattern.len() - digits.len()) as i32);
+    if percent {
+        value *= 100.0;
+    }
+    let (int_pattern, frac_pattern) = digits.split_once('.').unwrap_or((digits, ""));
+    let decimals = frac_pattern.chars().filter(|c| *c != ',').count();
+    let required = frac_pattern.chars().filter(|c| *c == '0').count();
+
+    let number = format!("{:.decimals$}", value.abs());
+    let (int_digits, mut frac_digits) = number.split_once('.').unwrap_or((&number, ""));
+    while frac_digits.len() > required && frac_digits.ends_with('0') {
+        frac_digits = &frac_digits[..frac_digits.len() - 1];
+    }
+    let int_digits = if int_digits == "0" && !int_pattern.contains('0') {
+        ""
+    } else {
+        int_digits
+    };
+    let mut out = String::new();
+    if signed && value < 0.0 && number.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+        out.push('-');
+    }
+    out.push_str(&prefix);
+    if int_pattern.contains(',') {
+        out.push_str(&group_thousands(int_digits));
+    } else {
+        out.push_str(int_digits);
+    }
+    if !frac_digits.is_empty() {
+        out.push('.');
+        out.push_str(frac_digits);
+    }
+    out.push_str(&suffix);
+    Some(out)
+}
+
+/// Split a format code into its `;`-separated sections, leaving quoted
+/// text and brackets alone.
+fn split_sections(code: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let (mut start, mut quoted, mut bracket, mut escaped) = (0, false, false, false);
+    for (i, c) in code.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if !quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '[' if !quoted => bracket = true,
+            ']' if !quoted => bracket = false,
+            ';' if !quoted && !bracket => {
+                sections.push(&code[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    sections.push(&code[start..]);
+    sections
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// A number in the General format: whole numbers without decimals and
+/// others to 15 significant digits, which drops the noise of binary
+/// floating point such as `0.30000000000000004`.
+pub(super) fn general(value: f64) -> String {
+    if value == value.trunc() {
+        return format!("{value:.0}");
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    let decimals = (14 - magnitude).clamp(0, 30) as usize;
+    let number = format!("{value:.decimals$}");
+    number
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// A date as ISO 8601: `2024-05-01` for whole days, `2024-05-01T13:30:00`
+/// with a time and `13:30:00` for a time of day alone. Durations are
+/// written as hours, minutes and seconds, such as `36:15:00`.
+pub(super) fn format_datetime(datetime: &ExcelDateTime) -> String {
+    let value = datetime.as_f64();
+    if datetime.is_duration() {
+        let seconds = (value * 86_400.0).round() as i64;
+        let sign = if seconds < 0 { "-" } else { "" };
+        let seconds = seconds.abs();
+        return format!(
+            "{sign}{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+    }
+    let (year, month, day, hour, minute, second, _) = datetime.to_ymd_hms_milli();
+    let date = format!("{year:04}-{month:02}-{day:02}");
+    let time = format!("{hour:02}:{minute:02}:{second:02}");
+    if value.fract() == 0.0 {
+        date
+    } else if (0.0..1.0).contains(&value) {
+        time
+    } else {
+        format!("{date}T{time}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::ExcelDateTimeType;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::fixed(1234.5, "0.00", Some("1234.50"))]
+    #[case::thousands(1234567.891, "#,##0.00", Some("1,234,567.89"))]
+    #[case::percent(0.256, "0.0%", Some("25.6%"))]
+    #[case::whole_percent(0.5, "0%", Some("50%"))]
+    #[case::dollars(-1234.5, "$#,##0.00", Some("-$1,234.50"))]
+    #[case::negative_section(-1234.5, "$#,##0.00;($#,##0.00)", Some("($1,234.50)"))]
+    #[case::euro(99.9, "#,##0.00 [$€-407]", Some("99.90 €"))]
+    #[case::quoted(42.0, "0\" units\"", Some("42 units"))]
+    #[case::accounting(
+        12.0,
+        "_($* #,##0.00_);_($* (#,##0.00);_($* \"-\"??_);_(@_)",
+        Some("$12.00")
+    )]
+    #[case::optional_decimals(2.5, "0.##", Some("2.5"))]
+    #[case::no_leading_zero(0.5, "#.00", Some(".50"))]
+    #[case::thousands_scaled(1500000.0, "#,##0.0,,\"M\"", Some("1.5M"))]
+    #[case::colored(-3.0, "0;[Red]-0", Some("-3"))]
+    #[case::rounded_to_zero(-0.001, "0.00", Some("0.00"))]
+    #[case::scientific(1234.5, "0.00E+00", None)]
+    #[case::text(1.0, "@", None)]
+    fn test_format_number(#[case] value: f64, #[case] code: &str, #[case] expected: Option<&str>) {
+        assert_eq!(format_number(value, code).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case::whole(42.0, "42")]
+    #[case::float_noise(0.1 + 0.2, "0.3")]
+    #[case::decimals(1234.5678, "1234.5678")]
+    #[case::negative(-2.5, "-2.5")]
+    fn test_general(#[case] value: f64, #[case] expected: &str) {
+        assert_eq!(general(value), expected);
+    }
+
+    #[rstest]
+    #[case::date(45413.0, ExcelDateTimeType::DateTime, "2024-05-01")]
+    #[case::date_time(45413.5625, ExcelDateTimeType::DateTime, "2024-05-01T13:30:00")]
+    #[case::time(0.75, ExcelDateTimeType::DateTime, "18:00:00")]
+    #[case::duration(1.5104166666666667, ExcelDateTimeType::TimeDelta, "36:15:00")]
+    fn test_format_datetime(
+        #[case] value: f64,
+        #[case] kind: ExcelDateTimeType,
+        #[case] expected: &str,
+    ) {
+        let datetime = ExcelDateTime::new(value, kind, false);
+        assert_eq!(format_datetime(&datetime), expected);
+    }
+
+    #[rstest]
+    fn test_styles() {
+        let styles = r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<numFmts count="1"><numFmt numFmtId="164" formatCode="&quot;€&quot;#,##0.00"/></numFmts>
+<cellXfs count="3"><xf numFmtId="0"/><xf numFmtId="164" applyNumberFormat="1"/><xf numFmtId="10"/></cellXfs>
+</styleSheet>"#;
+        let formats = NumberFormats::parse(styles);
+        assert_eq!(formats.code(0), None);
+        assert_eq!(formats.code(1), Some("\"€\"#,##0.00"));
+        assert_eq!(formats.code(2), Some("0.00%"));
+
+        let sheet = r#"<worksheet><sheetData><row r="2"><c r="B2" s="1"><v>1</v></c><c r="AA2"><v>2</v></c></row></sheetData></worksheet>"#;
+        assert_eq!(cell_styles(sheet), HashMap::from([((1, 1), 1)]));
+    }
+
+    #[rstest]
+    fn test_style_cursor() {
+        let sheet = r#"<worksheet><sheetData><row r="1"><c r="A1" s="2"/><c r="C1" s="3"/></row><row r="3"><c r="B3" s="1"/></row></sheetData></worksheet>"#;
+        let mut xml = sheet.as_bytes();
+        let mut styles = StyleCursor::new(&mut xml);
+        assert_eq!(styles.style((0, 0)), Some(2));
+        assert_eq!(styles.style((0, 1)), None);
+        assert_eq!(styles.style((0, 2)), Some(3));
+        assert_eq!(styles.style((1, 0)), None);
+        assert_eq!(styles.style((2, 1)), Some(1));
+        assert_eq!(styles.style((2, 2)), None);
+    }
+
+    #[rstest]
+    #[case("A1", Some((0, 0)))]
+    #[case("AB12", Some((11, 27)))]
+    #[case("12", None)]
+    #[case("A0", None)]
+    fn test_cell_position(#[case] reference: &str, #[case] expected: Option<(u32, u32)>) {
+        assert_eq!(cell_position(reference), expected);
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Cursor, Read};
+
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+
+/// The parts of an `.xlsx` package that calamine does not expose, read
+/// straight from the zip. Other workbook formats have no package.
+pub(super) struct Package<'a> {
+    archive: zip::ZipArchive<Cursor<&'a [u8]>>,
+    /// Worksheet part path by sheet name.
+    sheets: HashMap<String, String>,
+}
+
+impl<'a> Package<'a> {
+    /// Open `input` as an `.xlsx` package, or `None` for `.xls`, `.ods` and
+    /// other formats.
+    pub(super) fn open(input: &'a [u8]) -> Option<Self> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(input)).ok()?;
+        let workbook = read_part(&mut archive, "xl/workbook.xml")?;
+        let rels = read_part(&mut archive, "xl/_rels/workbook.xml.rels")
+            .map(|xml| relationships(&xml))
+            .unwrap_or_default();
+        let sheets = sheet_ids(&workbook)
+            .into_iter()
+            .filter_map(|(name, id)| Some((name, part_path("xl/", rels.get(&id)?))))
+            .collect();
+        Some(Self { archive, sheets })
+    }
+
+    /// The part at `path`, such as `xl/styles.xml`.
+    pub(super) fn part(&mut self, path: &str) -> Option<String> {
+        read_part(&mut self.archive, path)
+    }
+
+    /// The worksheet XML of the sheet called `name`.
+    pub(super) fn sheet_xml(&mut self, name: &str) -> Option<String> {
+        let path = self.sheets.get(name)?.clone();
+        read_part(&mut self.archive, &path)
+    }
+
+    /// Run `f` on the worksheet XML of the sheet called `name`, decompressed
+    /// as it is read rather than held in memory.
+    pub(super) fn with_sheet_reader<T>(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut dyn BufRead) -> T,
+    ) -> Option<T> {
+        let path = self.sheets.get(name)?;
+        let file = self.archive.by_name(path).ok()?;
+        Some(f(&mut BufReader::new(file)))
+    }
+
+    /// The worksheet XML of the sheet called `name` with the rows of its
+    /// `sheetData` left out, except for empty copies of the hidden ones.
+    /// The part is read as it is decompressed, so only what surrounds the
+    /// cells, such as column widths, hyperlinks and validation rules, is
+    /// ever held in memory.
+    pub(super) fn sheet_xml_without_cells(&mut self, name: &str) -> Option<String> {
+        self.with_sheet_reader(name, |input| {
+            let mut reader = Reader::from_reader(input);
+            let mut writer = Writer::new(Vec::new());
+            let mut buf = Vec::new();
+            let mut in_data = false;
+            loop {
+                buf.clear();
+                let event = match reader.read_event_into(&mut buf) {
+                    Ok(Event::Eof) | Err(_) => break,
+                    Ok(event) => event,
+                };
+                match &event {
+                    Event::Start(e) if local_name(e.name().as_ref()) == "sheetData" => {
+                        in_data = true;
+                    }
+                    Event::End(e) if local_name(e.name().as_ref()) == "sheetData" => {
+                        in_data = false;
+                    }
+                    Event::Start(e) | Event::Empty(e) if in_data => {
+                        if local_name(e.name().as_ref()) == "row"
+                            && attr(e, "hidden").is_some_and(|v| v == "1" || v == "true")
+                        {
+                            writer.write_event(Event::Empty(e.borrow())).ok()?;
+                        }
+                        continue;
+                    }
+                    _ if in_data => continue,
+                    _ => {}
+                }
+                writer.write_event(event).ok()?;
+            }
+            String::from_utf8(writer.into_inner()).ok()
+        })
+        .flatten()
+    }
+
+    /// The relationship targets by id of the sheet called `name`, such as
+    /// the URLs of its hyperlinks.
+    pub(super) fn sheet_rels(&mut self, name: &str) -> HashMap<String, String> {
+        let Some((dir, file)) = self.sheets.get(name).and_then(|path| path.rsplit_once('/')) else {
+            return HashMap::new();
+        };
+        read_part(&mut self.archive, &format!("{dir}/_rels/{file}.rels"))
+            .map(|xml| relationships(&xml))
+            .unwrap_or_default()
+    }
+
+    /// The comments part of the sheet called `name`, which holds the notes
+    /// attached to its cells.
+    pub(super) fn sheet_comments(&mut self, name: &str) -> Option<String> {
+        self.sheet_parts(name, "comments").into_iter().next()
+    }
+
+    /// The parts the sheet called `name` relates to as `kind`, such as its
+    /// `table` parts, in relationship order.
+    pub(super) fn sheet_parts(&mut self, name: &str, kind: &str) -> Vec<String> {
+        let Some(path) = self.sheets.get(name).cloned() else {
+            return Vec::new();
+        };
+        self.related(&path, kind)
+            .iter()
+            .filter_map(|path| read_part(&mut self.archive, path))
+            .collect()
+    }
+
+    /// The chart parts of the sheet called `name`, which it holds through
+    /// its drawings.
+    pub(super) fn sheet_charts(&mut self, name: &str) -> Vec<String> {
+        let Some(path) = self.sheets.get(name).cloned() else {
+            return Vec::new();
+        };
+        let mut charts = Vec::new();
+        for drawing in self.related(&path, "drawing") {
+            for chart in self.related(&drawing, "chart") {
+                charts.extend(read_part(&mut self.archive, &chart));
+            }
+        }
+        charts
+    }
+
+    /// The paths of the parts the part at `path` relates to as `kind`.
+    fn related(&mut self, path: &str, kind: &str) -> Vec<String> {
+        let Some((dir, file)) = path.rsplit_once('/') else {
+            return Vec::new();
+        };
+        let dir = format!("{dir}/");
+        let Some(rels) = read_part(&mut self.archive, &format!("{dir}_rels/{file}.rels")) else {
+            return Vec::new();
+        };
+        relationships_of_type(&rels, kind)
+            .iter()
+            .map(|target| part_path(&dir, target))
+            .collect()
+    }
+}
+
+fn read_part(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// Sheet names and relationship ids from `workbook.xml`.
+fn sheet_ids(xml: &str) -> Vec<(String, String)> {
+    let mut sheets = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e)) if local_name(e.name().as_ref()) == "sheet" => {
+                if let (Some(name), Some(id)) = (attr(&e, "name"), attr(&e, "id")) {
+                    sheets.push((name, id));
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    sheets
+}
+
+/// Relationship targets by id.
+fn relationships(xml: &str) -> HashMap<String, String> {
+    let mut rels = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e))
+                if local_name(e.name().as_ref()) == "Relationship" =>
+            {
+                if let (Some(id), Some(target)) = (attr(&e, "Id"), attr(&e, "Target")) {
+                    rels.insert(id, target);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    rels
+}
+
+/// The targets of the relationships whose type ends in `/{kind}`.
+fn relationships_of_type(xml: &str, kind: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e))
+                if local_name(e.name().as_ref()) == "Relationship"
+                    && attr(&e, "Type").is_some_and(|t| t.rsplit('/').next() == Some(kind)) =>
+            {
+                targets.extend(attr(&e, "Target"));
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    targets
+}
+
+/// Resolve a relationship target against the directory of its source part,
+/// such as `../comments1.xml` from `xl/worksheets/`.
+fn part_path(dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments: Vec<&str> = dir.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// The value of the attribute with local name `name`, unescaped.
+pub(super) fn attr(e: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == name)
+        .and_then(|a| {
+            a.normalized_value(quick_xml::XmlVersion::Implicit1_0)
+                .ok()
+                .map(|v| v.into_owned())
+        })
+}
+
+pub(super) fn local_name(name: &[u8]) -> String {
+    let s = String::from_utf8_lossy(name);
+    match s.rfind(':') {
+        Some(i) => s[i + 1..].to_string(),
+        None => s.to_string(),
+    }
+}
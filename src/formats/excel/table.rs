@@ -0,0 +1,84 @@
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use super::number_format::cell_range;
+use super::package::{attr, local_name};
+
+/// A table defined on a worksheet, from an `xl/tables/table*.xml` part.
+#[derive(Debug, PartialEq)]
+pub(super) struct DefinedTable {
+    pub name: String,
+    /// Zero-based positions of its top-left and bottom-right cells,
+    /// including its header and totals rows.
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+    /// Whether its first row holds the column names. Tables without one
+    /// only name their columns in the part.
+    pub has_header: bool,
+    pub columns: Vec<String>,
+}
+
+/// Read a table part, or `None` when it has no name or range.
+pub(super) fn defined_table(xml: &str) -> Option<DefinedTable> {
+    let mut table: Option<DefinedTable> = None;
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) => match local_name(e.name().as_ref()).as_str() {
+                "table" => {
+                    let name = attr(&e, "displayName").or_else(|| attr(&e, "name"))?;
+                    let (start, end) = cell_range(&attr(&e, "ref")?)?;
+                    table = Some(DefinedTable {
+                        name,
+                        start,
+                        end,
+                        has_header: attr(&e, "headerRowCount").as_deref() != Some("0"),
+                        columns: Vec::new(),
+                    });
+                }
+                "tableColumn" => {
+                    if let Some(table) = table.as_mut() {
+                        table.columns.push(attr(&e, "name").unwrap_or_default());
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::header(
+        r#"<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" id="1" name="Table1" displayName="Orders" ref="B2:C10" totalsRowCount="1"><autoFilter ref="B2:C9"/><tableColumns count="2"><tableColumn id="1" name="Item"/><tableColumn id="2" name="Q1 &amp; Q2"/></tableColumns></table>"#,
+        Some(DefinedTable {
+            name: "Orders".into(),
+            start: (1, 1),
+            end: (9, 2),
+            has_header: true,
+            columns: vec!["Item".into(), "Q1 & Q2".into()],
+        })
+    )]
+    #[case::no_header(
+        r#"<table id="2" name="Rates" ref="A1:A3" headerRowCount="0"><tableColumns count="1"><tableColumn id="1" name="Rate"/></tableColumns></table>"#,
+        Some(DefinedTable {
+            name: "Rates".into(),
+            start: (0, 0),
+            end: (2, 0),
+            has_header: false,
+            columns: vec!["Rate".into()],
+        })
+    )]
+    #[case::no_range(r#"<table id="3" name="Broken"/>"#, None)]
+    fn test_defined_table(#[case] xml: &str, #[case] expected: Option<DefinedTable>) {
+        assert_eq!(defined_table(xml), expected);
+    }
+}
@@ -0,0 +1,168 @@
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+
+use super::package::{attr, local_name};
+
+/// A data-validation rule from a worksheet's `dataValidations`, or from
+/// the `x14` extension Excel uses for lists that refer to other sheets.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct DataValidation {
+    /// Space-separated cell ranges the rule applies to, e.g. `B2:B10 D2`.
+    pub cells: String,
+    kind: String,
+    operator: String,
+    formula1: String,
+    formula2: String,
+    allow_blank: bool,
+    pub prompt: String,
+    pub error: String,
+}
+
+/// Read every data-validation rule in a worksheet, in document order.
+pub(super) fn data_validations(xml: &str) -> Vec<DataValidation> {
+    let mut rules = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<DataValidation> = None;
+    // Local name of the element whose text is being read
+    let mut element = String::new();
+    // Which formula `xm:f` text belongs to, set by the enclosing element
+    let mut formula = 0;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let local = local_name(e.name().as_ref());
+                match local.as_str() {
+                    "dataValidation" => current = Some(read_rule(&e)),
+                    "formula1" => formula = 1,
+                    "formula2" => formula = 2,
+                    _ => {}
+                }
+                element = local;
+            }
+            Ok(Event::Empty(e)) if local_name(e.name().as_ref()) == "dataValidation" => {
+                rules.push(read_rule(&e));
+            }
+            Ok(event @ (Event::Text(_) | Event::GeneralRef(_))) => {
+                let Some(rule) = current.as_mut() else {
+                    continue;
+                };
+                // Formulas often hold `&lt;`, `&amp;` and `&quot;`
+                let text = match event {
+                    Event::Text(e) => e.decode().unwrap_or_default().into_owned(),
+                    Event::GeneralRef(e) => match e.resolve_char_ref() {
+                        Ok(Some(c)) => c.to_string(),
+                        _ => {
+                            let name = e.decode().unwrap_or_default();
+                            resolve_predefined_entity(&name)
+                                .unwrap_or_default()
+                                .to_string()
+                        }
+                    },
+                    _ => continue,
+                };
+                // x14 rules wrap formulas in `xm:f` and move sqref to an element
+                match (element.as_str(), formula) {
+                    ("formula1" | "f", 1) => rule.formula1.push_str(&text),
+                    ("formula2" | "f", 2) => rule.formula2.push_str(&text),
+                    ("sqref", _) => rule.cells.push_str(&text),
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                match local_name(e.name().as_ref()).as_str() {
+                    "dataValidation" => {
+                        if let Some(rule) = current.take() {
+                            rules.push(rule);
+                        }
+                    }
+                    "formula1" | "formula2" => formula = 0,
+                    _ => {}
+                }
+                element.clear();
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    rules
+}
+
+fn read_rule(e: &quick_xml::events::BytesStart) -> DataValidation {
+    let text = |name: &str| attr(e, name).unwrap_or_default();
+    let message = |title: String, body: String| match (title.is_empty(), body.is_empty()) {
+        (false, false) => format!("{title}: {body}"),
+        (true, _) => body,
+        (false, true) => title,
+    };
+    DataValidation {
+        cells: text("sqref"),
+        kind: attr(e, "type").unwrap_or_else(|| "any".to_string()),
+        operator: attr(e, "operator").unwrap_or_else(|| "between".to_string()),
+        allow_blank: matches!(text("allowBlank").as_str(), "1" | "true"),
+        prompt: message(text("promptTitle"), text("prompt")),
+        error: message(text("errorTitle"), text("error")),
+        ..Default::default()
+    }
+}
+
+impl DataValidation {
+    /// The kind of value the cells accept.
+    pub(super) fn kind_label(&self) -> &str {
+        match self.kind.as_str() {
+            "list" => "List",
+            "whole" => "Whole number",
+            "decimal" => "Decimal",
+            "date" => "Date",
+            "time" => "Time",
+            "textLength" => "Text length",
+            "custom" => "Custom",
+            "any" => "Any value",
+            other => other,
+        }
+    }
+
+    /// The rule in words, e.g. `One of: Yes, No` or `Between 1 and 10`.
+    pub(super) fn rule(&self) -> String {
+        let f1 = self.formula1.trim();
+        let f2 = self.formula2.trim();
+        let rule = match self.kind.as_str() {
+            "any" => String::new(),
+            "list" => match f1.strip_prefix('"').and_then(|l| l.strip_suffix('"')) {
+                // An inline list; otherwise the values come from a range
+                Some(values) => {
+                    let values: Vec<&str> = values.split(',').map(str::trim).collect();
+                    format!("One of: {}", values.join(", "))
+                }
+                None => format!("One of the values in `{f1}`"),
+            },
+            "custom" => format!("Formula `{f1}` is true"),
+            _ => {
+                let (f1, f2) = (literal(f1), literal(f2));
+                match self.operator.as_str() {
+                    "notBetween" => format!("Not between {f1} and {f2}"),
+                    "equal" => format!("Equal to {f1}"),
+                    "notEqual" => format!("Not equal to {f1}"),
+                    "greaterThan" => format!("Greater than {f1}"),
+                    "lessThan" => format!("Less than {f1}"),
+                    "greaterThanOrEqual" => format!("At least {f1}"),
+                    "lessThanOrEqual" => format!("At most {f1}"),
+                    _ => format!("Between {f1} and {f2}"),
+                }
+            }
+        };
+        match (rule.is_empty(), self.allow_blank) {
+            (false, true) => format!("{rule} (blank allowed)"),
+            _ => rule,
+        }
+    }
+}
+
+/// Numbers as they are; cell references and formulas in code spans.
+fn literal(formula: &str) -> String {
+    if formula.parse::<f64>().is_ok() {
+        formula.to_string()
+    } else {
+        format!("`{formula}`")
+    }
+}
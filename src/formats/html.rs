@@ -1,9 +1,26 @@
+use std::borrow::Cow;
 use std::io::Write;
 
+use scraper::node::Text;
+use scraper::{ElementRef, Html, Node, Selector};
+
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::options::{ConvertOptions, TableSpans};
+
+/// Upper bound for `colspan`/`rowspan`, as browsers apply.
+const MAX_SPAN: usize = 1000;
 
-pub struct HtmlConverter;
+#[derive(Default)]
+pub struct HtmlConverter {
+    options: ConvertOptions,
+}
+
+impl HtmlConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for HtmlConverter {
     fn format_name(&self) -> &'static str {
@@ -11,22 +28,24 @@ impl Converter for HtmlConverter {
     }
 
     fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
-        let text = mq_markdown::convert_html_to_markdown(
-            std::str::from_utf8(input).map_err(|e| Error::Conversion {
-                format: "html",
-                message: e.to_string(),
-            })?,
-            mq_markdown::ConversionOptions {
-                extract_scripts_as_code_blocks: true,
-                generate_front_matter: true,
-                use_title_as_h1: true,
-            },
-        )
-        .map_err(|e| Error::Conversion {
+        let html = std::str::from_utf8(input).map_err(|e| Error::Conversion {
             format: "html",
             message: e.to_string(),
         })?;
 
+        let (html, tables) = match self.options.html.table_spans {
+            TableSpans::Flatten => (Cow::Borrowed(html), Vec::new()),
+            spans => {
+                let (html, tables) = replace_spanned_tables(html, spans)?;
+                (Cow::Owned(html), tables)
+            }
+        };
+
+        let mut text = to_markdown(&html, true)?;
+        for (i, table) in tables.iter().enumerate() {
+            text = text.replace(&placeholder(i), table);
+        }
+
         let trimmed = text.trim();
         if trimmed.is_empty() {
             writeln!(writer, "*Empty HTML document*")?;
@@ -37,3 +56,210 @@ impl Converter for HtmlConverter {
         Ok(())
     }
 }
+
+fn to_markdown(html: &str, document: bool) -> Result<String> {
+    mq_markdown::convert_html_to_markdown(
+        html,
+        mq_markdown::ConversionOptions {
+            extract_scripts_as_code_blocks: true,
+            generate_front_matter: document,
+            use_title_as_h1: document,
+        },
+    )
+    .map_err(|e| Error::Conversion {
+        format: "html",
+        message: e.to_string(),
+    })
+}
+
+fn placeholder(index: usize) -> String {
+    format!("MQCONVTABLE{index}END")
+}
+
+/// Swap each table with spanned cells for a placeholder, returning the
+/// rewritten document and the Markdown that replaces each placeholder.
+fn replace_spanned_tables(html: &str, spans: TableSpans) -> Result<(String, Vec<String>)> {
+    let mut doc = Html::parse_document(html);
+    let selector = Selector::parse("table").expect("valid selector");
+
+    let mut replaced = Vec::new();
+    let mut tables = Vec::new();
+    for table in doc.select(&selector) {
+        // Tables nested in a replaced table go along with it
+        if table.ancestors().any(|node| replaced.contains(&node.id())) {
+            continue;
+        }
+        let rows = table_rows(table);
+        let spanned = rows
+            .iter()
+            .flat_map(|row| cells(*row))
+            .any(|cell| span(cell, "colspan") > 1 || span(cell, "rowspan") > 1);
+        if !spanned {
+            continue;
+        }
+        let markdown = match spans {
+            TableSpans::Html => table.html(),
+            _ => to_markdown(&expand(table, &rows), false)?.trim().to_string(),
+        };
+        replaced.push(table.id());
+        tables.push(markdown);
+    }
+
+    for (i, id) in replaced.into_iter().enumerate() {
+        if let Some(mut node) = doc.tree.get_mut(id) {
+            node.insert_before(Node::Text(Text {
+                text: placeholder(i).into(),
+            }));
+            node.detach();
+        }
+    }
+    Ok((doc.html(), tables))
+}
+
+/// Rows belonging to `table` itself, not to tables nested in its cells.
+fn table_rows(table: ElementRef<'_>) -> Vec<ElementRef<'_>> {
+    table
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|el| el.value().name() == "tr")
+        .filter(|row| {
+            row.ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|el| el.value().name() == "table")
+                .is_some_and(|owner| owner.id() == table.id())
+        })
+        .collect()
+}
+
+fn cells(row: ElementRef<'_>) -> impl Iterator<Item = ElementRef<'_>> {
+    row.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|el| matches!(el.value().name(), "td" | "th"))
+}
+
+fn span(cell: ElementRef, attr: &str) -> usize {
+    cell.attr(attr)
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_SPAN)
+}
+
+/// Rebuild `table` as a plain grid with spanned cells repeated, using the
+/// first row as the header.
+fn expand(table: ElementRef, rows: &[ElementRef]) -> String {
+    let mut grid: Vec<Vec<Option<String>>> = vec![Vec::new(); rows.len()];
+    for (r, row) in rows.iter().enumerate() {
+        let mut col = 0;
+        for cell in cells(*row) {
+            // Skip columns still covered by a rowspan from above
+            while grid[r].get(col).is_some_and(Option::is_some) {
+                col += 1;
+            }
+            let colspan = span(cell, "colspan");
+            let rowspan = span(cell, "rowspan").min(rows.len() - r);
+            let content = cell.inner_html();
+            for covered in &mut grid[r..r + rowspan] {
+                if covered.len() < col + colspan {
+                    covered.resize(col + colspan, None);
+                }
+                for slot in &mut covered[col..col + colspan] {
+                    *slot = Some(content.clone());
+                }
+            }
+            col += colspan;
+        }
+    }
+
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    let mut out = String::from("<table>");
+    if let Some(caption) = table
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|el| el.value().name() == "caption")
+    {
+        out.push_str(&caption.html());
+    }
+    for (r, row) in grid.iter().enumerate() {
+        let (tag, section) = if r == 0 { ("th", "thead") } else { ("td", "tbody") };
+        if r <= 1 {
+            out.push_str(&format!("<{section}>"));
+        }
+        out.push_str("<tr>");
+        for c in 0..width {
+            let content = row.get(c).cloned().flatten().unwrap_or_default();
+            out.push_str(&format!("<{tag}>{content}</{tag}>"));
+        }
+        out.push_str("</tr>");
+        if r == 0 || r + 1 == grid.len() {
+            out.push_str(&format!("</{section}>"));
+        }
+    }
+    out.push_str("</table>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::HtmlOptions;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    const SPANNED: &str = r#"<html><body>
+<p>Before</p>
+<table>
+  <tr><th rowspan="2">Region</th><th colspan="2">Sales</th></tr>
+  <tr><th>Q1</th><th>Q2</th></tr>
+  <tr><td>North</td><td>10</td><td>12</td></tr>
+</table>
+<p>After</p>
+</body></html>"#;
+
+    fn convert(html: &str, table_spans: TableSpans) -> String {
+        let options = ConvertOptions {
+            html: HtmlOptions { table_spans },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        HtmlConverter::new(options)
+            .convert(html.as_bytes(), &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_expand_spans() {
+        let output = convert(SPANNED, TableSpans::Expand);
+        assert!(
+            output.contains(
+                "| Region | Sales | Sales |\n|---|---|---|\n| Region | Q1 | Q2 |\n| North | 10 | 12 |"
+            ),
+            "{output}"
+        );
+        assert!(output.starts_with("Before"), "{output}");
+        assert!(output.trim_end().ends_with("After"), "{output}");
+    }
+
+    #[rstest]
+    fn test_keep_spanned_table_as_html() {
+        let output = convert(SPANNED, TableSpans::Html);
+        assert!(output.contains(r#"<th colspan="2">Sales</th>"#), "{output}");
+        assert!(!output.contains(&placeholder(0)), "{output}");
+    }
+
+    #[rstest]
+    #[case::expand(TableSpans::Expand)]
+    #[case::html(TableSpans::Html)]
+    fn test_tables_without_spans_unchanged(#[case] spans: TableSpans) {
+        let html = "<table><tr><th>A</th><th>B</th></tr><tr><td>1</td><td>2</td></tr></table>";
+        assert_eq!(convert(html, spans), convert(html, TableSpans::Flatten));
+    }
+
+    #[rstest]
+    fn test_expand_ragged_rows() {
+        let html = "<table><tr><th>A</th><th>B</th><th>C</th></tr>\
+            <tr><td colspan=\"2\">wide</td></tr></table>";
+        let output = convert(html, TableSpans::Expand);
+        assert!(output.contains("| wide | wide |  |"), "{output}");
+    }
+}
@@ -1,9 +1,22 @@
+pub mod contact_sheet;
+
 use std::io::{Cursor, Write};
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::media::{input_stem, write_media};
+use crate::options::{ConvertOptions, DEFAULT_THUMBNAIL_SIZE};
+
+#[derive(Default)]
+pub struct ImageConverter {
+    options: ConvertOptions,
+}
 
-pub struct ImageConverter;
+impl ImageConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for ImageConverter {
     fn format_name(&self) -> &'static str {
@@ -14,6 +27,17 @@ impl Converter for ImageConverter {
         if is_svg(input) {
             writeln!(writer, "# Image")?;
             writeln!(writer)?;
+            if let Some(dir) = &self.options.media.extract_dir {
+                let stem = input_stem(self.options.input_name.as_deref(), "image");
+                let link = write_media(
+                    dir,
+                    &format!("{stem}.svg"),
+                    input,
+                    self.options.media.link_base.as_deref(),
+                )?;
+                writeln!(writer, "![{stem}]({link})")?;
+                writeln!(writer)?;
+            }
             writeln!(writer, "| Property | Value |")?;
             writeln!(writer, "|----------|-------|")?;
             writeln!(writer, "| Format | SVG |")?;
@@ -30,13 +54,33 @@ impl Converter for ImageConverter {
             })?;
 
         let format = reader.format();
-        let img = reader.decode().map_err(|e| Error::Conversion {
+        let mut img = reader.decode().map_err(|e| Error::Conversion {
             format: "image",
             message: e.to_string(),
         })?;
 
+        // Turn the pixels upright, as viewers do, so dimensions, thumbnails
+        // and descriptions match what people see
+        let exif = read_exif(input);
+        let orientation = exif.as_ref().and_then(orientation).filter(|&o| o != 1);
+        let stored = (img.width(), img.height());
+        if let Some(o) = orientation.and_then(image::metadata::Orientation::from_exif) {
+            img.apply_orientation(o);
+        }
+
+        let description = if orientation.is_some() && self.options.describe.endpoint.is_some() {
+            self.describe(&encode_png(&img)?, Some(image::ImageFormat::Png))?
+        } else {
+            self.describe(input, format)?
+        };
+
         writeln!(writer, "# Image")?;
         writeln!(writer)?;
+
+        if self.options.media.extract_dir.is_some() {
+            self.write_preview(input, &img, format, description.as_deref(), writer)?;
+        }
+
         writeln!(writer, "| Property | Value |")?;
         writeln!(writer, "|----------|-------|")?;
 
@@ -51,27 +95,195 @@ impl Converter for ImageConverter {
             img.width(),
             img.height()
         )?;
+        if let Some(o) = orientation {
+            writeln!(
+                writer,
+                "| Orientation | {} (stored as {}x{}) |",
+                describe_orientation(o),
+                stored.0,
+                stored.1
+            )?;
+        }
         writeln!(writer, "| Color Type | {:?} |", img.color())?;
+        if let Some(thumbnail) = exif.as_ref().and_then(exif_thumbnail) {
+            let size = format_size(thumbnail.len() as u64);
+            match image::load_from_memory(thumbnail) {
+                Ok(thumb) => {
+                    let (w, h) = match orientation {
+                        Some(5..=8) => (thumb.height(), thumb.width()),
+                        _ => (thumb.width(), thumb.height()),
+                    };
+                    writeln!(writer, "| EXIF Thumbnail | {w}x{h}, {size} |")?;
+                }
+                Err(_) => writeln!(writer, "| EXIF Thumbnail | {size} |")?,
+            }
+        }
+
+        if let Some(exif) = &exif {
+            write_exif(exif, writer)?;
+        }
+
+        if let Some(description) = description {
+            writeln!(writer)?;
+            writeln!(writer, "## Description")?;
+            writeln!(writer)?;
+            writeln!(writer, "{description}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ImageConverter {
+    /// Describe the image with the configured vision endpoint, if any.
+    fn describe(
+        &self,
+        input: &[u8],
+        format: Option<image::ImageFormat>,
+    ) -> Result<Option<String>> {
+        if self.options.describe.endpoint.is_none() {
+            return Ok(None);
+        }
+        #[cfg(feature = "describe-images")]
+        {
+            let mime = format.map_or("image/png", |f| f.to_mime_type());
+            crate::describe::describe_image(&self.options.describe, input, mime, "image")
+        }
+        #[cfg(not(feature = "describe-images"))]
+        {
+            let _ = (input, format);
+            Err(Error::FeatureDisabled("describe-images".into()))
+        }
+    }
+
+    /// Copy the image to the media directory and, when it is larger than the
+    /// thumbnail size, write a downscaled copy next to it. The Markdown shows
+    /// the thumbnail linked to the full-size image.
+    fn write_preview(
+        &self,
+        input: &[u8],
+        img: &image::DynamicImage,
+        format: Option<image::ImageFormat>,
+        description: Option<&str>,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let Some(dir) = &self.options.media.extract_dir else {
+            return Ok(());
+        };
+        let stem = input_stem(self.options.input_name.as_deref(), "image");
+        let alt = description
+            .map(|d| d.replace('[', "\\[").replace(']', "\\]"))
+            .unwrap_or_else(|| stem.clone());
+        let ext = self
+            .options
+            .input_name
+            .as_deref()
+            .and_then(|name| std::path::Path::new(name).extension())
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .or_else(|| format.and_then(|f| f.extensions_str().first()).map(|e| e.to_string()))
+            .unwrap_or_else(|| "img".to_string());
+        let link_base = self.options.media.link_base.as_deref();
+        let original = write_media(dir, &format!("{stem}.{ext}"), input, link_base)?;
+
+        let max = self
+            .options
+            .media
+            .thumbnail_size
+            .unwrap_or(DEFAULT_THUMBNAIL_SIZE)
+            .max(1);
+        if img.width() <= max && img.height() <= max {
+            writeln!(writer, "![{alt}]({original})")?;
+            writeln!(writer)?;
+            return Ok(());
+        }
+
+        // Keep the source format when it can be encoded, otherwise fall back to PNG
+        let thumb = img.thumbnail(max, max);
+        let mut bytes = Vec::new();
+        let source_format = format.filter(|f| f.writing_enabled());
+        let thumb_format = match source_format {
+            Some(f) if thumb.write_to(&mut Cursor::new(&mut bytes), f).is_ok() => f,
+            _ => {
+                bytes.clear();
+                thumb
+                    .to_rgba8()
+                    .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+                    .map_err(|e| Error::Conversion {
+                        format: "image",
+                        message: format!("Failed to write thumbnail: {e}"),
+                    })?;
+                image::ImageFormat::Png
+            }
+        };
+        let thumb_ext = thumb_format.extensions_str().first().copied().unwrap_or("png");
+        let thumbnail = write_media(
+            dir,
+            &format!("{stem}.thumb.{thumb_ext}"),
+            &bytes,
+            link_base,
+        )?;
 
-        write_exif(input, writer)?;
+        writeln!(writer, "[![{alt}]({thumbnail})]({original})")?;
+        writeln!(writer)?;
 
         Ok(())
     }
 }
 
-fn write_exif(input: &[u8], writer: &mut dyn Write) -> Result<()> {
-    let exif_reader = exif::Reader::new();
-    let mut cursor = Cursor::new(input);
-    let exif_data: exif::Exif = match exif_reader.read_from_container(&mut cursor) {
-        Ok(exif) => exif,
-        Err(_) => return Ok(()),
-    };
+fn read_exif(input: &[u8]) -> Option<exif::Exif> {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(input))
+        .ok()
+}
+
+/// The EXIF `Orientation` tag, 1 (upright) to 8.
+fn orientation(exif: &exif::Exif) -> Option<u8> {
+    let field = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
+    field.value.get_uint(0)?.try_into().ok()
+}
 
+fn describe_orientation(orientation: u8) -> String {
+    match orientation {
+        1 => "Normal",
+        2 => "Mirrored horizontally",
+        3 => "Rotated 180°",
+        4 => "Mirrored vertically",
+        5 => "Mirrored horizontally and rotated 270° CW",
+        6 => "Rotated 90° CW",
+        7 => "Mirrored horizontally and rotated 90° CW",
+        8 => "Rotated 270° CW",
+        other => return format!("Unknown ({other})"),
+    }
+    .to_string()
+}
+
+/// The JPEG thumbnail cameras embed in the EXIF data, if any.
+fn exif_thumbnail(exif: &exif::Exif) -> Option<&[u8]> {
+    let uint = |tag| exif.get_field(tag, exif::In::THUMBNAIL)?.value.get_uint(0);
+    let offset = uint(exif::Tag::JPEGInterchangeFormat)? as usize;
+    let len = uint(exif::Tag::JPEGInterchangeFormatLength)? as usize;
+    exif.buf()
+        .get(offset..offset.checked_add(len)?)
+        .filter(|bytes| !bytes.is_empty())
+}
+
+fn encode_png(img: &image::DynamicImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    img.to_rgba8()
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|e| Error::Conversion {
+            format: "image",
+            message: e.to_string(),
+        })?;
+    Ok(bytes)
+}
+
+fn write_exif(exif_data: &exif::Exif, writer: &mut dyn Write) -> Result<()> {
     let fields: Vec<(String, String)> = exif_data
         .fields()
         .filter_map(|f| {
             let tag_name = f.tag.to_string();
-            let value = f.display_value().with_unit(&exif_data).to_string();
+            let value = f.display_value().with_unit(exif_data).to_string();
             if value.is_empty() || value == "unknown" {
                 return None;
             }
@@ -113,3 +325,139 @@ fn format_size(bytes: u64) -> String {
         format!("{bytes} B")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::MediaOptions;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn make_png(width: u32, height: u32) -> Vec<u8> {
+        let img = image::RgbImage::from_pixel(width, height, image::Rgb([200, 100, 50]));
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn make_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let img = image::RgbImage::from_pixel(width, height, image::Rgb([200, 100, 50]));
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Jpeg)
+            .unwrap();
+        bytes
+    }
+
+    /// A JPEG whose EXIF data records `orientation` and embeds `thumbnail`.
+    fn make_jpeg_with_exif(width: u32, height: u32, orientation: u16, thumbnail: &[u8]) -> Vec<u8> {
+        let entry = |tag: u16, kind: u16, value: u32| {
+            let mut entry = tag.to_le_bytes().to_vec();
+            entry.extend(kind.to_le_bytes());
+            entry.extend(1u32.to_le_bytes());
+            entry.extend(value.to_le_bytes());
+            entry
+        };
+        // Header, IFD0 with Orientation, IFD1 with the thumbnail's offset and length
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend(8u32.to_le_bytes());
+        tiff.extend(1u16.to_le_bytes());
+        tiff.extend(entry(0x0112, 3, orientation.into()));
+        tiff.extend(26u32.to_le_bytes());
+        tiff.extend(2u16.to_le_bytes());
+        tiff.extend(entry(0x0201, 4, 56));
+        tiff.extend(entry(0x0202, 4, thumbnail.len() as u32));
+        tiff.extend(0u32.to_le_bytes());
+        tiff.extend_from_slice(thumbnail);
+
+        let jpeg = make_jpeg(width, height);
+        let mut bytes = jpeg[..2].to_vec();
+        bytes.extend([0xFF, 0xE1]);
+        bytes.extend(((tiff.len() + 8) as u16).to_be_bytes());
+        bytes.extend(b"Exif\0\0");
+        bytes.extend(tiff);
+        bytes.extend_from_slice(&jpeg[2..]);
+        bytes
+    }
+
+    fn convert_with_media(
+        input: &[u8],
+        name: &str,
+        thumbnail_size: Option<u32>,
+    ) -> (String, std::path::PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("mq-conv-media-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let converter = ImageConverter::new(ConvertOptions {
+            input_name: Some(name.to_string()),
+            media: MediaOptions {
+                extract_dir: Some(dir.clone()),
+                thumbnail_size,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(input, &mut output).unwrap();
+        (String::from_utf8(output).unwrap(), dir)
+    }
+
+    #[rstest]
+    fn test_without_media_dir_writes_no_links() {
+        let mut output = Vec::new();
+        ImageConverter::default()
+            .convert(&make_png(4, 4), &mut output)
+            .unwrap();
+        assert!(!String::from_utf8(output).unwrap().contains("]("));
+    }
+
+    #[rstest]
+    fn test_thumbnail_written_and_linked() {
+        let (output, dir) = convert_with_media(&make_png(600, 300), "photo.png", Some(100));
+        let original = dir.join("photo.png");
+        let thumbnail = dir.join("photo.thumb.png");
+        assert!(output.contains(&format!(
+            "[![photo]({})]({})",
+            thumbnail.display(),
+            original.display()
+        )));
+        let thumb = image::open(&thumbnail).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (100, 50));
+        assert!(original.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest]
+    fn test_small_image_links_original_only() {
+        let (output, dir) = convert_with_media(&make_png(20, 10), "icon.png", None);
+        assert!(output.contains(&format!("![icon]({})", dir.join("icon.png").display())));
+        assert!(!dir.join("icon.thumb.png").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest]
+    fn test_exif_orientation_and_thumbnail() {
+        let thumbnail = make_jpeg(8, 4);
+        let input = make_jpeg_with_exif(40, 20, 6, &thumbnail);
+        let mut output = Vec::new();
+        ImageConverter::default()
+            .convert(&input, &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("| Dimensions | 20x40 |"), "{output}");
+        assert!(output.contains("| Orientation | Rotated 90° CW (stored as 40x20) |"));
+        assert!(output.contains(&format!(
+            "| EXIF Thumbnail | 4x8, {} |",
+            format_size(thumbnail.len() as u64)
+        )));
+    }
+
+    #[rstest]
+    fn test_thumbnail_follows_orientation() {
+        let input = make_jpeg_with_exif(600, 300, 8, &[]);
+        let (_, dir) = convert_with_media(&input, "portrait.jpg", Some(100));
+        let thumb = image::open(dir.join("portrait.thumb.jpg")).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (50, 100));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,353 @@
+use std::io::{Cursor, Write};
+
+use super::{format_size, is_svg, orientation, read_exif};
+use crate::error::{Error, Result};
+use crate::media::{data_uri, input_stem, write_media};
+use crate::options::{DEFAULT_THUMBNAIL_SIZE, MediaOptions};
+
+/// One image of a contact sheet, read from an image file.
+#[derive(Debug, PartialEq)]
+pub struct SheetImage {
+    pub name: String,
+    /// The Markdown image showing the thumbnail: a link to a file written to
+    /// the media directory, or a `data:` URI without one.
+    pub thumbnail: String,
+    /// The upright width and height; SVG images have none.
+    pub dimensions: Option<(u32, u32)>,
+    /// The file size in bytes.
+    pub size: u64,
+    /// When the photo was taken, from its EXIF `DateTimeOriginal` tag or
+    /// else `DateTime`.
+    pub captured: Option<String>,
+}
+
+impl SheetImage {
+    /// Read the image and make its thumbnail, no larger than the configured
+    /// thumbnail size. With a media directory the image and its thumbnail
+    /// are written there and the thumbnail links to the full-size image.
+    pub fn read(name: &str, input: &[u8], media: &MediaOptions) -> Result<Self> {
+        let file_name = std::path::Path::new(name)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned());
+        let stem = input_stem(file_name.as_deref(), "image");
+        let alt = stem.replace('[', "\\[").replace(']', "\\]");
+
+        if is_svg(input) {
+            let file = format!("{stem}.svg");
+            let link = match &media.extract_dir {
+                Some(dir) => write_media(dir, &file, input, media.link_base.as_deref())?,
+                None => data_uri(&file, input),
+            };
+            return Ok(Self {
+                name: name.to_string(),
+                thumbnail: format!("![{alt}]({link})"),
+                dimensions: None,
+                size: input.len() as u64,
+                captured: None,
+            });
+        }
+
+        let error = |e: image::ImageError| Error::Conversion {
+            format: "image",
+            message: format!("{name}: {e}"),
+        };
+        let reader = image::ImageReader::new(Cursor::new(input))
+            .with_guessed_format()
+            .map_err(|e| error(e.into()))?;
+        let format = reader.format();
+        let mut img = reader.decode().map_err(error)?;
+        let exif = read_exif(input);
+        if let Some(o) = exif
+            .as_ref()
+            .and_then(orientation)
+            .and_then(image::metadata::Orientation::from_exif)
+        {
+            img.apply_orientation(o);
+        }
+
+        let ext = file_name
+            .as_deref()
+            .and_then(|name| std::path::Path::new(name).extension())
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .or_else(|| {
+                format
+                    .and_then(|f| f.extensions_str().first())
+                    .map(|e| e.to_string())
+            })
+            .unwrap_or_else(|| "img".to_string());
+        let max = media
+            .thumbnail_size
+            .unwrap_or(DEFAULT_THUMBNAIL_SIZE)
+            .max(1);
+        // Re-encode even small images, so the stored pixels are upright
+        let (thumb_format, bytes) = encode_thumbnail(&img.thumbnail(max, max), format)?;
+        let thumb_ext = thumb_format
+            .extensions_str()
+            .first()
+            .copied()
+            .unwrap_or("png");
+        let thumb_file = format!("{stem}.thumb.{thumb_ext}");
+        let thumbnail = match &media.extract_dir {
+            Some(dir) => {
+                let link_base = media.link_base.as_deref();
+                let original = write_media(dir, &format!("{stem}.{ext}"), input, link_base)?;
+                let thumbnail = write_media(dir, &thumb_file, &bytes, link_base)?;
+                format!("[![{alt}]({thumbnail})]({original})")
+            }
+            None => format!("![{alt}]({})", data_uri(&thumb_file, &bytes)),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            thumbnail,
+            dimensions: Some((img.width(), img.height())),
+            size: input.len() as u64,
+            captured: exif.as_ref().and_then(captured),
+        })
+    }
+}
+
+/// Write one gallery document for `images`: a summary followed by a table
+/// with each image's thumbnail, file name, dimensions, size and capture
+/// date, in the order given.
+pub fn write_contact_sheet_as_markdown(
+    writer: &mut dyn Write,
+    title: &str,
+    images: &[SheetImage],
+) -> Result<()> {
+    writeln!(writer, "# {title}")?;
+    writeln!(writer)?;
+    if images.is_empty() {
+        writeln!(writer, "*No images*")?;
+        return Ok(());
+    }
+
+    let total: u64 = images.iter().map(|image| image.size).sum();
+    writeln!(writer, "- **Images**: {}", images.len())?;
+    writeln!(writer, "- **Total size**: {}", format_size(total))?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "| Thumbnail | File | Dimensions | Size | Captured |"
+    )?;
+    writeln!(writer, "|---|---|---|---|---|")?;
+    for image in images {
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} | {} |",
+            escape(&image.thumbnail),
+            escape(&image.name),
+            image
+                .dimensions
+                .map(|(w, h)| format!("{w}x{h}"))
+                .unwrap_or_default(),
+            format_size(image.size),
+            image.captured.as_deref().unwrap_or_default()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Encode a thumbnail in the source format when it can be written,
+/// otherwise as PNG.
+fn encode_thumbnail(
+    thumb: &image::DynamicImage,
+    format: Option<image::ImageFormat>,
+) -> Result<(image::ImageFormat, Vec<u8>)> {
+    let mut bytes = Vec::new();
+    if let Some(f) = format.filter(|f| f.writing_enabled())
+        && thumb.write_to(&mut Cursor::new(&mut bytes), f).is_ok()
+    {
+        return Ok((f, bytes));
+    }
+    bytes.clear();
+    thumb
+        .to_rgba8()
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|e| Error::Conversion {
+            format: "image",
+            message: format!("Failed to write thumbnail: {e}"),
+        })?;
+    Ok((image::ImageFormat::Png, bytes))
+}
+
+/// The EXIF capture date, as `2024-05-01 13:30:00`.
+fn captured(exif: &exif::Exif) -> Option<String> {
+    [exif::Tag::DateTimeOriginal, exif::Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| exif.get_field(tag, exif::In::PRIMARY))
+        .map(|field| field.display_value().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn escape(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn make_image(width: u32, height: u32, format: image::ImageFormat) -> Vec<u8> {
+        let img = image::RgbImage::from_pixel(width, height, image::Rgb([200, 100, 50]));
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+        bytes
+    }
+
+    /// A JPEG whose EXIF data records `DateTimeOriginal` and `orientation`.
+    fn make_photo(width: u32, height: u32, orientation: u16, taken: &str) -> Vec<u8> {
+        let entry = |tiff: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32| {
+            tiff.extend(tag.to_le_bytes());
+            tiff.extend(kind.to_le_bytes());
+            tiff.extend(count.to_le_bytes());
+            tiff.extend(value.to_le_bytes());
+        };
+        // Header, IFD0 with Orientation and the Exif IFD pointer (offsets 8..38),
+        // the Exif IFD with DateTimeOriginal (38..56), then the date string
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend(8u32.to_le_bytes());
+        tiff.extend(2u16.to_le_bytes());
+        entry(&mut tiff, 0x0112, 3, 1, orientation.into());
+        entry(&mut tiff, 0x8769, 4, 1, 38);
+        tiff.extend(0u32.to_le_bytes());
+        tiff.extend(1u16.to_le_bytes());
+        entry(&mut tiff, 0x9003, 2, taken.len() as u32 + 1, 56);
+        tiff.extend(0u32.to_le_bytes());
+        tiff.extend(taken.as_bytes());
+        tiff.push(0);
+
+        let jpeg = make_image(width, height, image::ImageFormat::Jpeg);
+        let mut bytes = jpeg[..2].to_vec();
+        bytes.extend([0xFF, 0xE1]);
+        bytes.extend(((tiff.len() + 8) as u16).to_be_bytes());
+        bytes.extend(b"Exif\0\0");
+        bytes.extend(tiff);
+        bytes.extend_from_slice(&jpeg[2..]);
+        bytes
+    }
+
+    #[rstest]
+    fn test_contact_sheet() {
+        let media = MediaOptions {
+            thumbnail_size: Some(50),
+            ..Default::default()
+        };
+        let photo = make_photo(200, 100, 6, "2024:05:01 13:30:00");
+        let images = vec![
+            SheetImage::read("trip/beach.jpg", &photo, &media).unwrap(),
+            SheetImage::read(
+                "icon|1.png",
+                &make_image(20, 10, image::ImageFormat::Png),
+                &media,
+            )
+            .unwrap(),
+            SheetImage::read(
+                "logo.svg",
+                b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>",
+                &media,
+            )
+            .unwrap(),
+        ];
+        assert_eq!(images[0].dimensions, Some((100, 200)));
+        assert_eq!(images[0].captured.as_deref(), Some("2024-05-01 13:30:00"));
+        assert!(
+            images[0]
+                .thumbnail
+                .starts_with("![beach](data:image/jpeg;base64,"),
+            "{}",
+            images[0].thumbnail
+        );
+        assert!(
+            images[1]
+                .thumbnail
+                .starts_with("![icon|1](data:image/png;base64,")
+        );
+        assert!(
+            images[2]
+                .thumbnail
+                .starts_with("![logo](data:image/svg+xml;base64,")
+        );
+
+        let mut output = Vec::new();
+        write_contact_sheet_as_markdown(&mut output, "Photos", &images).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let rows: Vec<String> = output
+            .lines()
+            .map(|line| {
+                line.split(" | ")
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        if i == 0 && cell.starts_with("| ![") {
+                            "| T"
+                        } else {
+                            cell
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            })
+            .collect();
+        let total = format_size(images.iter().map(|image| image.size).sum());
+        assert_eq!(
+            rows,
+            [
+                "# Photos".to_string(),
+                String::new(),
+                "- **Images**: 3".into(),
+                format!("- **Total size**: {total}"),
+                String::new(),
+                "| Thumbnail | File | Dimensions | Size | Captured |".into(),
+                "|---|---|---|---|---|".into(),
+                format!(
+                    "| T | trip/beach.jpg | 100x200 | {} | 2024-05-01 13:30:00 |",
+                    format_size(photo.len() as u64)
+                ),
+                format!(
+                    "| T | icon\\|1.png | 20x10 | {} |  |",
+                    format_size(images[1].size)
+                ),
+                "| T | logo.svg |  | 41 B |  |".into(),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_thumbnails_written_to_media_dir() {
+        let dir =
+            std::env::temp_dir().join(format!("mq-conv-contact-sheet-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let media = MediaOptions {
+            extract_dir: Some(dir.clone()),
+            thumbnail_size: Some(100),
+            ..Default::default()
+        };
+        let input = make_image(600, 300, image::ImageFormat::Png);
+        let image = SheetImage::read("photo.png", &input, &media).unwrap();
+        assert_eq!(
+            image.thumbnail,
+            format!(
+                "[![photo]({})]({})",
+                dir.join("photo.thumb.png").display(),
+                dir.join("photo.png").display()
+            )
+        );
+        let thumb = image::open(dir.join("photo.thumb.png")).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (100, 50));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest]
+    fn test_unreadable_image() {
+        let error = SheetImage::read(
+            "broken.png",
+            b"\x89PNG\r\n\x1a\nnope",
+            &MediaOptions::default(),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("broken.png"), "{error}");
+    }
+}
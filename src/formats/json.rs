@@ -2,9 +2,21 @@ use std::io::Write;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
-use crate::formats::structured;
+use crate::formats::structured::{self, schema};
+use crate::options::ConvertOptions;
 
-pub struct JsonConverter;
+mod geojson;
+
+#[derive(Default)]
+pub struct JsonConverter {
+    options: ConvertOptions,
+}
+
+impl JsonConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for JsonConverter {
     fn format_name(&self) -> &'static str {
@@ -12,17 +24,48 @@ impl Converter for JsonConverter {
     }
 
     fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
-        let value: serde_json::Value =
-            serde_json::from_slice(input).map_err(|e| Error::Conversion {
-                format: "json",
-                message: e.to_string(),
-            })?;
+        let value = parse(input)?;
+        let options = &self.options.structured;
+        let errors = options.schema.as_ref().map(|s| s.validate(&value));
+        // With a schema the report follows the document, so hold it back
+        let mut document = Vec::new();
+        let out: &mut dyn Write = match errors {
+            Some(_) => &mut document,
+            None => writer,
+        };
+        if !options.generic && geojson::is_feature_collection(&value) {
+            geojson::write(out, &value)?;
+        } else {
+            structured::write_document(out, value, options)?;
+        }
+        match errors {
+            Some(errors) => schema::write_report(writer, &document, &errors),
+            None => Ok(()),
+        }
+    }
+}
 
-        let structured_value = structured::Value::from(value);
-        structured::write_value_as_markdown(writer, &structured_value)?;
+pub(crate) fn parse(input: &[u8]) -> Result<structured::Value> {
+    let value: serde_json::Value = serde_json::from_slice(input).map_err(|e| Error::Parse {
+        format: "json",
+        entry: None,
+        offset: byte_offset(input, e.line(), e.column()),
+        message: e.to_string(),
+    })?;
+    Ok(structured::Value::from(value))
+}
 
-        Ok(())
+/// Byte offset of a 1-based line and column, as serde_json reports them.
+fn byte_offset(input: &[u8], line: usize, column: usize) -> Option<u64> {
+    if line == 0 {
+        return None;
     }
+    let line_start: usize = input
+        .split_inclusive(|&b| b == b'\n')
+        .take(line - 1)
+        .map(<[u8]>::len)
+        .sum();
+    Some((line_start + column.saturating_sub(1)) as u64)
 }
 
 #[cfg(test)]
@@ -33,7 +76,7 @@ mod tests {
     use rstest::rstest;
 
     fn convert(input: &str) -> String {
-        let converter = JsonConverter;
+        let converter = JsonConverter::default();
         let mut output = Vec::new();
         converter.convert(input.as_bytes(), &mut output).unwrap();
         String::from_utf8(output).unwrap()
@@ -97,6 +140,25 @@ mod tests {
         assert!(output.contains("deep"));
     }
 
+    #[rstest]
+    fn test_sort_keys() {
+        let converter = JsonConverter::new(ConvertOptions {
+            structured: crate::options::StructuredOptions {
+                sort_keys: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter
+            .convert(br#"{"b":1,"a":{"d":2,"c":3}}"#, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# a\n\n| Key | Value |\n|---|---|\n| c | 3 |\n| d | 2 |\n\n| Key | Value |\n|---|---|\n| b | 1 |\n\n"
+        );
+    }
+
     #[rstest]
     fn test_mixed_array() {
         let output = convert(r#"[1,{"key":"val"}]"#);
@@ -104,4 +166,54 @@ mod tests {
         assert!(output.contains("| Key | Value |"));
         assert!(output.contains("| key | val |"));
     }
+
+    #[rstest]
+    fn test_geojson() {
+        let input = r#"{
+  "type": "FeatureCollection",
+  "features": [
+    {"type": "Feature", "id": "tokyo", "geometry": {"type": "Point", "coordinates": [139.6917, 35.6895]},
+     "properties": {"name": "Tokyo", "population": 13960000}},
+    {"type": "Feature", "id": "park", "geometry": {"type": "Polygon", "coordinates": [[[139.0, 35.0], [140.0, 35.0], [140.0, 36.0], [139.0, 36.0], [139.0, 35.0]]]},
+     "properties": {"name": "Park|Garden", "tags": ["green", "public"]}},
+    {"type": "Feature", "geometry": null, "properties": null}
+  ]
+}"#;
+        assert_eq!(
+            convert(input),
+            "# Features\n\n\
+             - **Features:** 3\n\
+             - **Bounds:** west 139, south 35, east 140, north 36\n\n\
+             | id | Geometry | Centroid | name | population | tags |\n|---|---|---|---|---|---|\n\
+             | tokyo | Point | 139.6917, 35.6895 | Tokyo | 13960000 |  |\n\
+             | park | Polygon | 139.5, 35.5 | Park\\|Garden |  | green, public |\n\
+             |  |  |  |  |  |  |\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_geojson_generic() {
+        let converter = JsonConverter::new(ConvertOptions {
+            structured: crate::options::StructuredOptions {
+                generic: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter
+            .convert(
+                br#"{"type":"FeatureCollection","bbox":[0,0,1,1],"features":[]}"#,
+                &mut output,
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "| Key | Value |\n|---|---|\n| type | FeatureCollection |\n\n# bbox\n\n- 0\n- 0\n- 1\n- 1\n\n# features\n\n*empty*\n"
+        );
+        assert_eq!(
+            convert(r#"{"type":"FeatureCollection","bbox":[0,0,1,1],"features":[]}"#),
+            "# Features\n\n- **Features:** 0\n- **Bounds:** west 0, south 0, east 1, north 1\n\n"
+        );
+    }
 }
@@ -0,0 +1,291 @@
+use std::io::Write;
+
+use crate::error::Result;
+use crate::formats::structured::{self, Value};
+
+/// Whether `value` is a GeoJSON `FeatureCollection`.
+pub(super) fn is_feature_collection(value: &Value) -> bool {
+    matches!(value.get("type"), Some(Value::String(t)) if t == "FeatureCollection")
+        && matches!(value.get("features"), Some(Value::Array(_)))
+}
+
+/// Write a feature collection as a summary of its size and bounds followed
+/// by a table of its features: their id, geometry type, centroid and
+/// properties.
+pub(super) fn write(writer: &mut dyn Write, value: &Value) -> Result<()> {
+    let features: &[Value] = match value.get("features") {
+        Some(Value::Array(features)) => features,
+        _ => &[],
+    };
+    let name = value.get("name").map(Value::inline);
+    writeln!(writer, "# {}", name.as_deref().unwrap_or("Features"))?;
+    writeln!(writer)?;
+
+    writeln!(writer, "- **Features:** {}", features.len())?;
+    let bounds = bbox(value.get("bbox")).or_else(|| {
+        let mut points = Vec::new();
+        for feature in features {
+            if let Some(geometry) = feature.get("geometry") {
+                positions(geometry, &mut points);
+            }
+        }
+        Bounds::of(&points)
+    });
+    if let Some(b) = bounds {
+        writeln!(
+            writer,
+            "- **Bounds:** west {}, south {}, east {}, north {}",
+            number(b.west),
+            number(b.south),
+            number(b.east),
+            number(b.north)
+        )?;
+    }
+    writeln!(writer)?;
+
+    if features.is_empty() {
+        return Ok(());
+    }
+    let with_id = features.iter().any(|f| f.get("id").is_some());
+    let mut properties: Vec<&str> = Vec::new();
+    for feature in features {
+        if let Some(Value::Object(entries)) = feature.get("properties") {
+            for (key, _) in entries {
+                if !properties.contains(&key.as_str()) {
+                    properties.push(key);
+                }
+            }
+        }
+    }
+
+    let headers: Vec<String> = with_id
+        .then_some("id")
+        .into_iter()
+        .chain(["Geometry", "Centroid"])
+        .chain(properties.iter().copied())
+        .map(String::from)
+        .collect();
+    let rows: Vec<Vec<String>> = features
+        .iter()
+        .map(|feature| {
+            let geometry = feature.get("geometry").filter(|g| **g != Value::Null);
+            let mut row = Vec::with_capacity(headers.len());
+            if with_id {
+                row.push(feature.get("id").map(Value::inline).unwrap_or_default());
+            }
+            row.push(
+                geometry
+                    .and_then(|g| g.get("type"))
+                    .map(Value::inline)
+                    .unwrap_or_default(),
+            );
+            row.push(
+                geometry
+                    .and_then(centroid)
+                    .map(|(x, y)| format!("{}, {}", number(x), number(y)))
+                    .unwrap_or_default(),
+            );
+            let values = feature.get("properties");
+            row.extend(properties.iter().map(|key| {
+                values
+                    .and_then(|v| v.get(key))
+                    .map(Value::inline)
+                    .unwrap_or_default()
+            }));
+            row
+        })
+        .collect();
+    structured::write_markdown_table(writer, &headers, &rows)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+struct Bounds {
+    west: f64,
+    south: f64,
+    east: f64,
+    north: f64,
+}
+
+impl Bounds {
+    fn of(points: &[(f64, f64)]) -> Option<Self> {
+        let (&(x, y), rest) = points.split_first()?;
+        let mut bounds = Self {
+            west: x,
+            south: y,
+            east: x,
+            north: y,
+        };
+        for &(x, y) in rest {
+            bounds.west = bounds.west.min(x);
+            bounds.south = bounds.south.min(y);
+            bounds.east = bounds.east.max(x);
+            bounds.north = bounds.north.max(y);
+        }
+        Some(bounds)
+    }
+}
+
+/// A `bbox` member, in either its two or three dimensional form.
+fn bbox(value: Option<&Value>) -> Option<Bounds> {
+    let Some(Value::Array(items)) = value else {
+        return None;
+    };
+    let n: Vec<f64> = items.iter().filter_map(coordinate).collect();
+    match n[..] {
+        [west, south, east, north] | [west, south, _, east, north, _] => Some(Bounds {
+            west,
+            south,
+            east,
+            north,
+        }),
+        _ => None,
+    }
+}
+
+fn coordinate(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+/// A `[x, y, ...]` position, ignoring any altitude.
+fn position(value: &Value) -> Option<(f64, f64)> {
+    match value {
+        Value::Array(items) => Some((coordinate(items.first()?)?, coordinate(items.get(1)?)?)),
+        _ => None,
+    }
+}
+
+/// Collect every position of a geometry, at any nesting of its coordinates
+/// and of geometry collections.
+fn positions(geometry: &Value, points: &mut Vec<(f64, f64)>) {
+    fn walk(value: &Value, points: &mut Vec<(f64, f64)>) {
+        if let Some(point) = position(value) {
+            points.push(point);
+        } else if let Value::Array(items) = value {
+            items.iter().for_each(|item| walk(item, points));
+        }
+    }
+    if let Some(Value::Array(geometries)) = geometry.get("geometries") {
+        geometries.iter().for_each(|g| positions(g, points));
+    } else if let Some(coordinates) = geometry.get("coordinates") {
+        walk(coordinates, points);
+    }
+}
+
+/// The centroid of a geometry: the area-weighted centroid of the outer
+/// rings of polygons, and the mean of the positions of anything else.
+fn centroid(geometry: &Value) -> Option<(f64, f64)> {
+    let polygons: Vec<&Value> = match (geometry.get("type"), geometry.get("coordinates")) {
+        (Some(Value::String(t)), Some(polygon)) if t == "Polygon" => vec![polygon],
+        (Some(Value::String(t)), Some(Value::Array(polygons))) if t == "MultiPolygon" => {
+            polygons.iter().collect()
+        }
+        _ => Vec::new(),
+    };
+    let (mut area, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    for polygon in polygons {
+        let Value::Array(rings) = polygon else {
+            continue;
+        };
+        let ring: Vec<(f64, f64)> = match rings.first() {
+            Some(Value::Array(ring)) => ring.iter().filter_map(position).collect(),
+            _ => continue,
+        };
+        for pair in ring.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            let cross = x0 * y1 - x1 * y0;
+            area += cross;
+            cx += (x0 + x1) * cross;
+            cy += (y0 + y1) * cross;
+        }
+    }
+    if area.abs() > f64::EPSILON {
+        return Some((cx / (3.0 * area), cy / (3.0 * area)));
+    }
+
+    // Points, lines and degenerate polygons
+    let mut points = Vec::new();
+    positions(geometry, &mut points);
+    if points.is_empty() {
+        return None;
+    }
+    let n = points.len() as f64;
+    let (x, y) = points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), (px, py)| (x + px, y + py));
+    Some((x / n, y / n))
+}
+
+/// A coordinate to six decimal places, about ten centimetres, without
+/// trailing zeros.
+fn number(n: f64) -> String {
+    let text = format!("{n:.6}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn value(json: &str) -> Value {
+        Value::from(serde_json::from_str::<serde_json::Value>(json).unwrap())
+    }
+
+    #[rstest]
+    #[case::point(r#"{"type":"Point","coordinates":[139.7,35.7,40]}"#, Some((139.7, 35.7)))]
+    #[case::line(
+        r#"{"type":"LineString","coordinates":[[0,0],[2,4]]}"#,
+        Some((1.0, 2.0))
+    )]
+    #[case::square(
+        r#"{"type":"Polygon","coordinates":[[[0,0],[4,0],[4,2],[0,2],[0,0]]]}"#,
+        Some((2.0, 1.0))
+    )]
+    #[case::multi_polygon(
+        r#"{"type":"MultiPolygon","coordinates":[[[[0,0],[2,0],[2,2],[0,2],[0,0]]],[[[4,0],[6,0],[6,2],[4,2],[4,0]]]]}"#,
+        Some((3.0, 1.0))
+    )]
+    #[case::collection(
+        r#"{"type":"GeometryCollection","geometries":[{"type":"Point","coordinates":[0,0]},{"type":"Point","coordinates":[2,2]}]}"#,
+        Some((1.0, 1.0))
+    )]
+    #[case::empty(r#"{"type":"Point","coordinates":[]}"#, None)]
+    fn test_centroid(#[case] geometry: &str, #[case] expected: Option<(f64, f64)>) {
+        assert_eq!(centroid(&value(geometry)), expected);
+    }
+
+    #[rstest]
+    #[case::two_d("[1, 2, 3, 4]", Some((1.0, 2.0, 3.0, 4.0)))]
+    #[case::three_d("[1, 2, 0, 3, 4, 100]", Some((1.0, 2.0, 3.0, 4.0)))]
+    #[case::malformed("[1, 2]", None)]
+    fn test_bbox(#[case] json: &str, #[case] expected: Option<(f64, f64, f64, f64)>) {
+        assert_eq!(
+            bbox(Some(&value(json))),
+            expected.map(|(west, south, east, north)| Bounds {
+                west,
+                south,
+                east,
+                north
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::integer(139.0, "139")]
+    #[case::rounded(35.689_487_3, "35.689487")]
+    #[case::negative_zero(-0.000_000_1, "0")]
+    fn test_number(#[case] n: f64, #[case] expected: &str) {
+        assert_eq!(number(n), expected);
+    }
+}
@@ -1,14 +1,86 @@
+mod images;
+mod script;
+
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 
 use pdf_extract::{
-    ColorSpace, Document, MediaBox, Object, OutputDev, OutputError, Path, PathOp, Transform,
-    output_doc,
+    ColorSpace, Dictionary, Document, LoadOptions, MediaBox, Object, ObjectId, OutputDev,
+    OutputError, Path, PathOp, Transform, output_doc,
 };
 
+use super::embedded;
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::media::{input_stem, write_media};
+use crate::options::ConvertOptions;
+
+#[derive(Default)]
+pub struct PdfConverter {
+    options: ConvertOptions,
+}
 
-pub struct PdfConverter;
+impl PdfConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+
+    /// Write the form fields and attachments that follow the page content,
+    /// separating each section with a rule after pages or a blank line after
+    /// the no-text notice.
+    fn write_appendices(&self, doc: &Document, writer: &mut dyn Write, rule: bool) -> Result<()> {
+        let separate = |writer: &mut dyn Write| -> Result<()> {
+            if rule {
+                writeln!(writer, "---")?;
+            }
+            writeln!(writer)?;
+            Ok(())
+        };
+        let fields = read_form_fields(doc);
+        if !fields.is_empty() {
+            separate(writer)?;
+            write_form_fields(writer, &fields)?;
+        }
+        if fields.iter().any(|field| field.signature.is_some()) {
+            separate(writer)?;
+            write_signatures(writer, &fields)?;
+        }
+        let attachments = read_attachments(doc);
+        if !attachments.is_empty() {
+            separate(writer)?;
+            write_attachments(writer, &attachments, &self.options)?;
+        }
+        Ok(())
+    }
+
+    /// Write a page's images to the media directory, if one is set, ordered
+    /// top to bottom with undrawn ones last.
+    fn extract_figures(&self, images: &[images::PageImage], page: usize) -> Result<Vec<Figure>> {
+        let Some(dir) = &self.options.media.extract_dir else {
+            return Ok(Vec::new());
+        };
+        let stem = input_stem(self.options.input_name.as_deref(), "pdf");
+        let mut figures = Vec::new();
+        for (n, image) in images.iter().enumerate() {
+            let name = format!("{stem}-page{page}-{}.{}", n + 1, image.extension);
+            let link = write_media(
+                dir,
+                &name,
+                &image.bytes,
+                self.options.media.link_base.as_deref(),
+            )?;
+            figures.push(Figure {
+                top: image.top,
+                markdown: format!("![Image {} on page {page}]({link})", n + 1),
+            });
+        }
+        figures.sort_by(|a, b| match (a.top, b.top) {
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        Ok(figures)
+    }
+}
 
 impl Converter for PdfConverter {
     fn format_name(&self) -> &'static str {
@@ -16,12 +88,10 @@ impl Converter for PdfConverter {
     }
 
     fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
-        let doc = Document::load_mem(input).map_err(|e| Error::Conversion {
-            format: "pdf",
-            message: e.to_string(),
-        })?;
+        let doc = load_document(input, self.options.pdf.password.as_deref())?;
 
         write_metadata(&doc, writer)?;
+        let bookmarks = read_outline(&doc);
 
         let mut collector = PageCollector::new();
         output_doc(&doc, &mut collector).map_err(|e| Error::Conversion {
@@ -34,18 +104,42 @@ impl Converter for PdfConverter {
                 writer,
                 "*PDF contains no extractable text (may be scanned/image-based)*"
             )?;
-            return Ok(());
+            return self.write_appendices(&doc, writer, false);
         }
 
+        let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
         let total_pages = collector.pages.len();
         for (i, page) in collector.pages.into_iter().enumerate() {
-            writeln!(writer, "## Page {}", i + 1)?;
+            // With an outline the bookmarks provide the structure, so page
+            // boundaries are only marked
+            if bookmarks.is_empty() {
+                writeln!(writer, "## Page {}", i + 1)?;
+            } else {
+                writeln!(writer, "<!-- Page {} -->", i + 1)?;
+            }
             writeln!(writer)?;
 
+            let page_bookmarks: Vec<&Bookmark> = bookmarks.iter().filter(|b| b.page == i).collect();
+            let links = page_ids
+                .get(i)
+                .map(|id| page_links(&doc, *id))
+                .unwrap_or_default();
+            let images = match page_ids.get(i) {
+                Some(id) if page.glyphs.is_empty() || self.options.media.extract_dir.is_some() => {
+                    images::page_images(&doc, *id)
+                }
+                _ => Vec::new(),
+            };
+            let figures = self.extract_figures(&images, i + 1)?;
             if page.glyphs.is_empty() {
-                writeln!(writer, "*Empty page*")?;
+                for bookmark in &page_bookmarks {
+                    write_bookmark(writer, bookmark)?;
+                }
+                write_figures(writer, &figures)?;
+                write_textless_page(&images, writer)?;
+                write_links(writer, links.iter())?;
             } else {
-                write_page_content(writer, page)?;
+                write_page_content(writer, page, &page_bookmarks, &links, &figures)?;
             }
 
             if i + 1 < total_pages {
@@ -55,10 +149,94 @@ impl Converter for PdfConverter {
             }
         }
 
+        self.write_appendices(&doc, writer, true)
+    }
+}
+
+/// An extracted image linked from the page.
+struct Figure {
+    top: Option<f64>,
+    markdown: String,
+}
+
+/// Load a document, decrypting it with `password` if it is protected. PDFs
+/// with an empty user password open without one.
+fn load_document(input: &[u8], password: Option<&str>) -> Result<Document> {
+    let options = password.map(LoadOptions::with_password).unwrap_or_default();
+    let doc = Document::load_mem_with_options(input, options).map_err(|e| Error::Conversion {
+        format: "pdf",
+        message: match e {
+            pdf_extract::Error::InvalidPassword => {
+                "Incorrect password for encrypted PDF".to_string()
+            }
+            e => e.to_string(),
+        },
+    })?;
+    // Without a usable password the reader leaves the document encrypted
+    if doc.is_encrypted() {
+        return Err(Error::Conversion {
+            format: "pdf",
+            message: "PDF is encrypted; provide its password with --password".to_string(),
+        });
+    }
+    Ok(doc)
+}
+
+/// Render a page without a text layer. Scanned pages are images, which are
+/// run through OCR when the `pdf-ocr` feature is enabled.
+fn write_textless_page(images: &[images::PageImage], writer: &mut dyn Write) -> Result<()> {
+    if images.is_empty() {
+        writeln!(writer, "*Empty page*")?;
+        return Ok(());
+    }
+
+    #[cfg(feature = "pdf-ocr")]
+    {
+        let text = recognise_text(images)?;
+        if text.trim().is_empty() {
+            writeln!(writer, "*No text recognised in page images*")?;
+            return Ok(());
+        }
+        writeln!(writer, "*Text recognised with OCR*")?;
+        writeln!(writer)?;
+        for line in text.trim().lines() {
+            writeln!(writer, "{}", line.trim())?;
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "pdf-ocr"))]
+    {
+        writeln!(
+            writer,
+            "*Page contains only images; build with the `pdf-ocr` feature to recognise their text*"
+        )?;
         Ok(())
     }
 }
 
+/// OCR each image, skipping ones Tesseract cannot load.
+#[cfg(feature = "pdf-ocr")]
+fn recognise_text(images: &[images::PageImage]) -> Result<String> {
+    let mut lt = leptess::LepTess::new(None, "eng").map_err(|e| Error::Conversion {
+        format: "pdf",
+        message: format!("Failed to initialize Tesseract (is tesseract installed?): {e}"),
+    })?;
+
+    let mut text = String::new();
+    for image in images {
+        if lt.set_image_from_mem(&image.bytes).is_err() {
+            continue;
+        }
+        let recognised = lt.get_utf8_text().map_err(|e| Error::Conversion {
+            format: "pdf",
+            message: format!("OCR extraction failed: {e}"),
+        })?;
+        text.push_str(&recognised);
+        text.push('\n');
+    }
+    Ok(text)
+}
+
 // ---------------------------------------------------------------------------
 // Positional data structures
 // ---------------------------------------------------------------------------
@@ -68,6 +246,8 @@ struct Glyph {
     y: f64,
     advance: f64,
     ch: String,
+    /// Index of the page link covering this glyph.
+    link: Option<usize>,
 }
 
 struct PageData {
@@ -144,6 +324,7 @@ impl OutputDev for PageCollector {
             y,
             advance,
             ch: char.to_string(),
+            link: None,
         });
         Ok(())
     }
@@ -187,8 +368,11 @@ impl OutputDev for PageCollector {
 
 struct Word {
     x: f64,
+    /// Right edge of the last glyph.
+    end: f64,
     y: f64,
     text: String,
+    link: Option<usize>,
 }
 
 struct TextLine {
@@ -213,18 +397,22 @@ fn build_words(mut glyphs: Vec<Glyph>) -> Vec<Word> {
     let mut wy = glyphs[0].y;
     let mut prev_x_end = glyphs[0].x + glyphs[0].advance.max(1.0);
     let mut prev_y = glyphs[0].y;
+    let mut wlink = glyphs[0].link;
 
     for glyph in &glyphs {
         let y_diff = (glyph.y - prev_y).abs();
         let x_gap = glyph.x - prev_x_end;
-        // New line (>3pt y diff) or significant horizontal gap = word boundary
-        let new_word = y_diff > 3.0 || x_gap > 4.0;
+        // New line (>3pt y diff), significant horizontal gap or the edge of
+        // a link = word boundary
+        let new_word = y_diff > 3.0 || x_gap > 4.0 || glyph.link != wlink;
 
         if new_word && !buf.trim().is_empty() {
             words.push(Word {
                 x: wx,
+                end: prev_x_end,
                 y: wy,
                 text: buf.trim().to_string(),
+                link: wlink,
             });
             buf.clear();
             wx = glyph.x;
@@ -238,6 +426,7 @@ fn build_words(mut glyphs: Vec<Glyph>) -> Vec<Word> {
         if buf.is_empty() {
             wx = glyph.x;
             wy = glyph.y;
+            wlink = glyph.link;
         }
 
         buf.push_str(&glyph.ch);
@@ -248,8 +437,10 @@ fn build_words(mut glyphs: Vec<Glyph>) -> Vec<Word> {
     if !buf.trim().is_empty() {
         words.push(Word {
             x: wx,
+            end: prev_x_end,
             y: wy,
             text: buf.trim().to_string(),
+            link: wlink,
         });
     }
 
@@ -290,60 +481,67 @@ fn build_lines(mut words: Vec<Word>) -> Vec<TextLine> {
 // Table detection
 // ---------------------------------------------------------------------------
 
-/// Cluster a list of x-positions into column boundaries (within `tol` points).
-fn cluster_columns(positions: &[f64], tol: f64) -> Vec<f64> {
-    let mut sorted = positions.to_vec();
-    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-    sorted.dedup_by(|a, b| (*a - *b).abs() < tol);
-    sorted
-}
-
-/// Assign a word to the nearest column index.
-fn nearest_col(x: f64, cols: &[f64]) -> usize {
-    cols.iter()
-        .enumerate()
-        .min_by(|&(_, a), &(_, b)| {
-            (x - a)
-                .abs()
-                .partial_cmp(&(x - b).abs())
-                .unwrap_or(std::cmp::Ordering::Equal)
-        })
-        .map(|(i, _)| i)
+/// Find column extents by projecting every word onto the x-axis and merging
+/// spans that overlap. Gutters between columns are where no line has text,
+/// so left-, right- and centre-aligned cells all land in one column.
+fn column_spans(lines: &[&TextLine]) -> Vec<(f64, f64)> {
+    let mut spans: Vec<(f64, f64)> = lines
+        .iter()
+        .flat_map(|l| l.words.iter().map(|w| (w.x, w.end.max(w.x))))
+        .collect();
+    spans.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in spans {
+        match columns.last_mut() {
+            Some(last) if start <= last.1 + 1.0 => last.1 = last.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+    columns
+}
+
+/// Index of the column containing a word's left edge.
+fn column_of(x: f64, columns: &[(f64, f64)]) -> usize {
+    columns
+        .iter()
+        .rposition(|&(start, _)| start <= x + 0.5)
         .unwrap_or(0)
 }
 
 /// Try to interpret a slice of consecutive lines as a table.
 /// Returns Some(rows) if the lines look like a table, None otherwise.
-fn try_as_table(lines: &[&TextLine]) -> Option<Vec<Vec<String>>> {
+///
+/// `bordered` relaxes the alignment requirement for pages that draw cell
+/// borders, where wrapped cell text often leaves single-column lines.
+fn try_as_table(lines: &[&TextLine], bordered: bool) -> Option<Vec<Vec<String>>> {
     if lines.len() < 2 {
         return None;
     }
 
-    // Collect all word x-start positions
-    let all_x: Vec<f64> = lines
-        .iter()
-        .flat_map(|l| l.words.iter().map(|w| w.x))
-        .collect();
-
-    let cols = cluster_columns(&all_x, 8.0);
+    let cols = column_spans(lines);
     if cols.len() < 2 {
         return None;
     }
 
-    // Count how many lines have words aligned to ≥2 distinct columns
+    // Count how many lines have words in ≥2 distinct columns
     let aligned = lines
         .iter()
         .filter(|line| {
-            let mut used_cols = std::collections::HashSet::new();
-            for w in &line.words {
-                used_cols.insert(nearest_col(w.x, &cols));
-            }
-            used_cols.len() >= 2
+            let used: std::collections::HashSet<usize> =
+                line.words.iter().map(|w| column_of(w.x, &cols)).collect();
+            used.len() >= 2
         })
         .count();
 
-    // Require ≥ 2/3 of lines to be multi-column, and at least 2 such lines
-    if aligned < 2 || aligned * 3 < lines.len() * 2 {
+    // Require ≥ 2/3 of lines (half when bordered) to be multi-column, and at
+    // least 2 such lines
+    let enough = if bordered {
+        aligned * 2 >= lines.len()
+    } else {
+        aligned * 3 >= lines.len() * 2
+    };
+    if aligned < 2 || !enough {
         return None;
     }
 
@@ -353,13 +551,16 @@ fn try_as_table(lines: &[&TextLine]) -> Option<Vec<Vec<String>>> {
         .map(|line| {
             let mut cells: Vec<String> = vec![String::new(); cols.len()];
             for word in &line.words {
-                let ci = nearest_col(word.x, &cols);
+                let ci = column_of(word.x, &cols);
                 if !cells[ci].is_empty() {
                     cells[ci].push(' ');
                 }
                 cells[ci].push_str(&word.text);
             }
             cells
+                .iter()
+                .map(|cell| script::normalize_line(cell))
+                .collect()
         })
         .collect();
 
@@ -426,11 +627,13 @@ fn typical_line_spacing(lines: &[TextLine]) -> f64 {
 }
 
 fn line_to_string(line: &TextLine) -> String {
-    line.words
+    let text = line
+        .words
         .iter()
         .map(|w| w.text.as_str())
         .collect::<Vec<_>>()
-        .join(" ")
+        .join(" ");
+    script::normalize_line(&text)
 }
 
 fn is_bullet_line(s: &str) -> bool {
@@ -442,13 +645,40 @@ fn is_bullet_line(s: &str) -> bool {
         || s.starts_with("* ")
 }
 
-fn write_page_content(writer: &mut dyn Write, page: PageData) -> Result<()> {
+fn write_page_content(
+    writer: &mut dyn Write,
+    mut page: PageData,
+    bookmarks: &[&Bookmark],
+    links: &[Link],
+    figures: &[Figure],
+) -> Result<()> {
     let has_table_rects = rects_suggest_table(&page.rects);
+    for glyph in &mut page.glyphs {
+        // Test a point inside the glyph rather than its baseline origin
+        let (x, y) = (glyph.x + glyph.advance / 2.0, glyph.y + 2.0);
+        glyph.link = links.iter().position(|link| link.contains(x, y));
+    }
     let words = build_words(page.glyphs);
-    let lines = build_lines(words);
+    let mut lines = build_lines(words);
+    let linked = apply_links(&mut lines, links);
+
+    // A bookmark is due once the text reaches its destination; ones without
+    // a position go at the top of the page
+    let due =
+        |bookmark: &Bookmark, line: &TextLine| bookmark.top.is_none_or(|top| top + 2.0 >= line.y);
+    let mut next_bookmark = 0;
+    // Images go before the first line below their top edge
+    let figure_due = |figure: &Figure, line: &TextLine| figure.top.is_some_and(|top| top >= line.y);
+    let mut next_figure = 0;
+    // Bookmarked titles usually also appear as a line of text; skip it
+    let mut titles: Vec<String> = Vec::new();
 
     if lines.is_empty() {
-        return Ok(());
+        for bookmark in bookmarks {
+            write_bookmark(writer, bookmark)?;
+        }
+        write_figures(writer, figures)?;
+        return write_links(writer, links.iter());
     }
 
     let spacing = typical_line_spacing(&lines);
@@ -458,24 +688,52 @@ fn write_page_content(writer: &mut dyn Write, page: PageData) -> Result<()> {
 
     let mut i = 0;
     while i < lines.len() {
-        // --- Table detection: try to grow a table region from i ---
-        let mut table_end = i + 1;
-        while table_end <= lines.len() {
-            let slice: Vec<&TextLine> = lines[i..table_end].iter().collect();
-            if try_as_table(&slice).is_none() && !(has_table_rects && table_end - i >= 2) {
+        while next_bookmark < bookmarks.len() && due(bookmarks[next_bookmark], &lines[i]) {
+            write_bookmark(writer, bookmarks[next_bookmark])?;
+            titles.push(title_key(&bookmarks[next_bookmark].title));
+            next_bookmark += 1;
+        }
+        let placed = figures[next_figure..]
+            .iter()
+            .take_while(|f| figure_due(f, &lines[i]))
+            .count();
+        write_figures(writer, &figures[next_figure..next_figure + placed])?;
+        next_figure += placed;
+        if let Some(pos) = titles
+            .iter()
+            .position(|t| *t == title_key(&line_to_string(&lines[i])))
+        {
+            titles.remove(pos);
+            i += 1;
+            continue;
+        }
+
+        // --- Table detection: grow a table region from i while it still
+        // reads as one, stopping at paragraph gaps and bookmarks ---
+        let mut table: Option<(usize, Vec<Vec<String>>)> = None;
+        let mut end = i + 2;
+        while end <= lines.len() {
+            let last = &lines[end - 1];
+            if (lines[end - 2].y - last.y).abs() > para_gap
+                || bookmarks.get(next_bookmark).is_some_and(|b| due(b, last))
+                || figures
+                    .get(next_figure)
+                    .is_some_and(|f| figure_due(f, last))
+            {
                 break;
             }
-            table_end += 1;
+            let slice: Vec<&TextLine> = lines[i..end].iter().collect();
+            match try_as_table(&slice, has_table_rects) {
+                Some(rows) => table = Some((end, rows)),
+                None => break,
+            }
+            end += 1;
         }
-        table_end -= 1;
 
-        if table_end > i + 1 {
-            let slice: Vec<&TextLine> = lines[i..table_end].iter().collect();
-            if let Some(rows) = try_as_table(&slice) {
-                render_table(writer, &rows)?;
-                i = table_end;
-                continue;
-            }
+        if let Some((table_end, rows)) = table {
+            render_table(writer, &rows)?;
+            i = table_end;
+            continue;
         }
 
         // --- Special single-line elements (bullets, numbered lists) ---
@@ -506,8 +764,15 @@ fn write_page_content(writer: &mut dyn Write, page: PageData) -> Result<()> {
         while j < lines.len() {
             let y_gap = (lines[j - 1].y - lines[j].y).abs();
 
-            // Large vertical gap → paragraph break
-            if y_gap > para_gap {
+            // Large vertical gap, a bookmark destination or an image → paragraph break
+            if y_gap > para_gap
+                || bookmarks
+                    .get(next_bookmark)
+                    .is_some_and(|b| due(b, &lines[j]))
+                || figures
+                    .get(next_figure)
+                    .is_some_and(|f| figure_due(f, &lines[j]))
+            {
                 break;
             }
 
@@ -520,7 +785,7 @@ fn write_page_content(writer: &mut dyn Write, page: PageData) -> Result<()> {
             }
             if j + 1 < lines.len() {
                 let two: Vec<&TextLine> = lines[j..j + 2].iter().collect();
-                if try_as_table(&two).is_some() {
+                if try_as_table(&two, has_table_rects).is_some() {
                     break;
                 }
             }
@@ -533,9 +798,44 @@ fn write_page_content(writer: &mut dyn Write, page: PageData) -> Result<()> {
         i = j;
     }
 
+    for bookmark in &bookmarks[next_bookmark..] {
+        write_bookmark(writer, bookmark)?;
+    }
+    write_figures(writer, &figures[next_figure..])?;
+
+    // Links whose area holds no text, e.g. over images, are listed instead
+    let unplaced = links.iter().zip(&linked).filter(|(_, used)| !**used);
+    write_links(writer, unplaced.map(|(link, _)| link))
+}
+
+fn write_figures(writer: &mut dyn Write, figures: &[Figure]) -> Result<()> {
+    for figure in figures {
+        writeln!(writer, "{}", figure.markdown)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+fn write_bookmark(writer: &mut dyn Write, bookmark: &Bookmark) -> Result<()> {
+    writeln!(
+        writer,
+        "{} {}",
+        "#".repeat((bookmark.depth + 2).min(6)),
+        bookmark.title
+    )?;
+    writeln!(writer)?;
     Ok(())
 }
 
+/// Compare titles ignoring case and whitespace, which text extraction
+/// does not preserve reliably.
+fn title_key(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
 /// Join a group of consecutive lines into a single paragraph and write it.
 fn write_paragraph(writer: &mut dyn Write, lines: &[&TextLine]) -> Result<()> {
     let mut para = String::new();
@@ -569,7 +869,8 @@ fn write_paragraph(writer: &mut dyn Write, lines: &[&TextLine]) -> Result<()> {
         para.push_str(t);
     }
 
-    let para = para.trim().to_string();
+    // Lines of Chinese and Japanese text wrap without a space
+    let para = script::join_cjk(para.trim());
     if para.is_empty() {
         return Ok(());
     }
@@ -586,6 +887,753 @@ fn write_paragraph(writer: &mut dyn Write, lines: &[&TextLine]) -> Result<()> {
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Link annotations
+// ---------------------------------------------------------------------------
+
+/// A URI link annotation and the page area it covers.
+struct Link {
+    /// `(x1, y1, x2, y2)` with `x1 <= x2` and `y1 <= y2`.
+    rect: (f64, f64, f64, f64),
+    uri: String,
+}
+
+impl Link {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let (x1, y1, x2, y2) = self.rect;
+        (x1..=x2).contains(&x) && (y1..=y2).contains(&y)
+    }
+
+    /// The URI as a Markdown link destination.
+    fn destination(&self) -> String {
+        self.uri
+            .replace(' ', "%20")
+            .replace('(', "%28")
+            .replace(')', "%29")
+    }
+}
+
+/// Read the page's `/Link` annotations with `/URI` actions. Internal
+/// `/GoTo` links are left out since their targets are not in the output.
+fn page_links(doc: &Document, page_id: ObjectId) -> Vec<Link> {
+    let deref = |obj: &Object| doc.dereference(obj).ok().map(|(_, obj)| obj.clone());
+    let annotations = doc.get_page_annotations(page_id).unwrap_or_default();
+    annotations
+        .into_iter()
+        .filter_map(|annot| {
+            if annot.get(b"Subtype").and_then(Object::as_name).ok()? != b"Link" {
+                return None;
+            }
+            let action = deref(annot.get(b"A").ok()?)?;
+            let action = action.as_dict().ok()?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"URI" {
+                return None;
+            }
+            let Object::String(uri, _) = deref(action.get(b"URI").ok()?)? else {
+                return None;
+            };
+            let uri = String::from_utf8_lossy(&uri).trim().to_string();
+            let rect = deref(annot.get(b"Rect").ok()?)?;
+            let coords: Vec<f64> = rect
+                .as_array()
+                .ok()?
+                .iter()
+                .filter_map(|v| v.as_float().ok().map(f64::from))
+                .collect();
+            let [x1, y1, x2, y2] = coords[..] else {
+                return None;
+            };
+            (!uri.is_empty()).then(|| Link {
+                rect: (x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)),
+                uri,
+            })
+        })
+        .collect()
+}
+
+/// Wrap each run of words inside one link in Markdown link syntax. Returns,
+/// per link, whether any text was found under it.
+fn apply_links(lines: &mut [TextLine], links: &[Link]) -> Vec<bool> {
+    let mut linked = vec![false; links.len()];
+    for line in lines {
+        let mut start = 0;
+        while start < line.words.len() {
+            let Some(link) = line.words[start].link else {
+                start += 1;
+                continue;
+            };
+            let mut end = start;
+            while line
+                .words
+                .get(end + 1)
+                .is_some_and(|w| w.link == Some(link))
+            {
+                end += 1;
+            }
+            for word in &mut line.words[start..=end] {
+                word.text = word.text.replace('[', "\\[").replace(']', "\\]");
+            }
+            line.words[start].text.insert(0, '[');
+            let destination = links[link].destination();
+            line.words[end].text.push_str(&format!("]({destination})"));
+            linked[link] = true;
+            start = end + 1;
+        }
+    }
+    linked
+}
+
+fn write_links<'a>(writer: &mut dyn Write, links: impl Iterator<Item = &'a Link>) -> Result<()> {
+    let mut links = links.peekable();
+    if links.peek().is_none() {
+        return Ok(());
+    }
+    writeln!(writer, "**Links**:")?;
+    writeln!(writer)?;
+    for link in links {
+        writeln!(writer, "- [{}]({})", link.uri, link.destination())?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Form fields (AcroForm)
+// ---------------------------------------------------------------------------
+
+/// A terminal form field with its fully qualified name.
+#[derive(Debug, PartialEq)]
+struct FormField {
+    name: String,
+    kind: &'static str,
+    value: String,
+    /// The signature dictionary's details, for signed signature fields.
+    signature: Option<Signature>,
+}
+
+/// Who signed a signature field, and when and why, as the signing software
+/// recorded it. The certificate itself is not inspected.
+#[derive(Debug, PartialEq)]
+struct Signature {
+    signer: String,
+    time: String,
+    reason: String,
+    location: String,
+}
+
+impl Signature {
+    fn read(dict: &Dictionary) -> Self {
+        let get = |key: &[u8]| dict.get(key).map(pdf_object_to_string).unwrap_or_default();
+        Self {
+            signer: get(b"Name"),
+            time: format_pdf_date(&get(b"M")),
+            reason: get(b"Reason"),
+            location: get(b"Location"),
+        }
+    }
+}
+
+/// Field attributes that kids inherit from their parent field.
+#[derive(Clone, Default)]
+struct Inherited {
+    name: String,
+    field_type: Option<Vec<u8>>,
+    value: Option<Object>,
+    flags: i64,
+}
+
+/// Read the interactive form's fields in document order.
+fn read_form_fields(doc: &Document) -> Vec<FormField> {
+    let fields = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|form| doc.dereference(form).ok())
+        .and_then(|(_, form)| form.as_dict().ok())
+        .and_then(|form| form.get(b"Fields").ok())
+        .and_then(|fields| doc.dereference(fields).ok())
+        .and_then(|(_, fields)| fields.as_array().ok());
+
+    let mut out = Vec::new();
+    let mut visited = HashSet::new();
+    for field in fields.into_iter().flatten() {
+        collect_fields(doc, field, &Inherited::default(), &mut visited, &mut out);
+    }
+    out
+}
+
+fn collect_fields(
+    doc: &Document,
+    field: &Object,
+    parent: &Inherited,
+    visited: &mut HashSet<ObjectId>,
+    out: &mut Vec<FormField>,
+) {
+    if let Ok(id) = field.as_reference()
+        && !visited.insert(id)
+    {
+        return;
+    }
+    let Some(dict) = doc
+        .dereference(field)
+        .ok()
+        .and_then(|(_, field)| field.as_dict().ok())
+    else {
+        return;
+    };
+
+    let mut inherited = parent.clone();
+    if let Ok(partial) = dict.get(b"T") {
+        let partial = pdf_object_to_string(partial);
+        inherited.name = if parent.name.is_empty() {
+            partial
+        } else {
+            format!("{}.{partial}", parent.name)
+        };
+    }
+    if let Ok(field_type) = dict.get(b"FT").and_then(Object::as_name) {
+        inherited.field_type = Some(field_type.to_vec());
+    }
+    if let Ok(value) = dict.get(b"V") {
+        inherited.value = doc.dereference(value).ok().map(|(_, v)| v.clone());
+    }
+    if let Ok(flags) = dict.get(b"Ff").and_then(Object::as_i64) {
+        inherited.flags = flags;
+    }
+
+    // Kids without a name are the field's widgets rather than child fields
+    let kids: Vec<&Object> = dict
+        .get(b"Kids")
+        .ok()
+        .and_then(|kids| doc.dereference(kids).ok())
+        .and_then(|(_, kids)| kids.as_array().ok())
+        .into_iter()
+        .flatten()
+        .filter(|kid| {
+            doc.dereference(kid)
+                .ok()
+                .and_then(|(_, kid)| kid.as_dict().ok())
+                .is_some_and(|kid| kid.has(b"T"))
+        })
+        .collect();
+    if !kids.is_empty() {
+        for kid in kids {
+            collect_fields(doc, kid, &inherited, visited, out);
+        }
+        return;
+    }
+
+    if let Some(field_type) = &inherited.field_type {
+        let (kind, value) = describe_field(field_type, inherited.flags, inherited.value.as_ref());
+        let signature = inherited
+            .value
+            .as_ref()
+            .filter(|_| field_type == b"Sig")
+            .and_then(|value| value.as_dict().ok())
+            .map(Signature::read);
+        out.push(FormField {
+            name: inherited.name,
+            kind,
+            value,
+            signature,
+        });
+    }
+}
+
+/// The field's type and its current value as display text.
+fn describe_field(field_type: &[u8], flags: i64, value: Option<&Object>) -> (&'static str, String) {
+    const RADIO: i64 = 1 << 15;
+    const PUSH_BUTTON: i64 = 1 << 16;
+    const COMBO: i64 = 1 << 17;
+
+    let name = match value {
+        Some(Object::Name(name)) if name.as_slice() != b"Off" => {
+            Some(String::from_utf8_lossy(name).to_string())
+        }
+        _ => None,
+    };
+    match field_type {
+        b"Btn" if flags & PUSH_BUTTON != 0 => ("Button", String::new()),
+        b"Btn" if flags & RADIO != 0 => ("Radio", name.unwrap_or_default()),
+        b"Btn" => {
+            let checked = if name.is_some() {
+                "Checked"
+            } else {
+                "Unchecked"
+            };
+            ("Checkbox", checked.to_string())
+        }
+        b"Ch" => {
+            let kind = if flags & COMBO != 0 {
+                "Combo box"
+            } else {
+                "List box"
+            };
+            let value = match value {
+                Some(Object::Array(items)) => items
+                    .iter()
+                    .map(pdf_object_to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Some(value) => pdf_object_to_string(value),
+                None => String::new(),
+            };
+            (kind, value)
+        }
+        b"Sig" => {
+            let signed = if value.is_some_and(|v| v.as_dict().is_ok()) {
+                "Signed"
+            } else {
+                "Unsigned"
+            };
+            ("Signature", signed.to_string())
+        }
+        _ => ("Text", value.map(pdf_object_to_string).unwrap_or_default()),
+    }
+}
+
+fn write_form_fields(writer: &mut dyn Write, fields: &[FormField]) -> Result<()> {
+    writeln!(writer, "## Form Fields")?;
+    writeln!(writer)?;
+    let mut rows = vec![vec![
+        "Field".to_string(),
+        "Type".to_string(),
+        "Value".to_string(),
+    ]];
+    rows.extend(fields.iter().map(|field| {
+        let value = field
+            .value
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .trim()
+            .replace('\n', "<br>");
+        vec![field.name.clone(), field.kind.to_string(), value]
+    }));
+    render_table(writer, &rows)
+}
+
+fn write_signatures(writer: &mut dyn Write, fields: &[FormField]) -> Result<()> {
+    writeln!(writer, "## Signatures")?;
+    writeln!(writer)?;
+    let mut rows = vec![vec![
+        "Field".to_string(),
+        "Signer".to_string(),
+        "Signed".to_string(),
+        "Reason".to_string(),
+        "Location".to_string(),
+    ]];
+    rows.extend(fields.iter().filter_map(|field| {
+        let signature = field.signature.as_ref()?;
+        Some(vec![
+            field.name.clone(),
+            signature.signer.clone(),
+            signature.time.clone(),
+            signature.reason.clone(),
+            signature.location.clone(),
+        ])
+    }));
+    render_table(writer, &rows)
+}
+
+/// `D:20240301120000+01'00'` -> `2024-03-01 12:00:00 +01:00`. Dates that
+/// do not follow the PDF date format are returned unchanged.
+fn format_pdf_date(date: &str) -> String {
+    let raw = date.strip_prefix("D:").unwrap_or(date);
+    let digits = raw.bytes().take_while(u8::is_ascii_digit).count();
+    if digits < 8 {
+        return date.to_string();
+    }
+    let part = |range: std::ops::Range<usize>| raw.get(range).filter(|p| p.len() == 2);
+    let mut out = format!("{}-{}-{}", &raw[..4], &raw[4..6], &raw[6..8]);
+    if let (Some(hour), Some(minute)) = (part(8..10), part(10..12)) {
+        out.push_str(&format!(" {hour}:{minute}"));
+        if let Some(second) = part(12..14) {
+            out.push_str(&format!(":{second}"));
+        }
+    }
+    let zone = raw[digits..].replace('\'', "");
+    match zone.as_str() {
+        "" => {}
+        "Z" => out.push_str(" UTC"),
+        _ if zone.len() == 5 => out.push_str(&format!(" {}:{}", &zone[..3], &zone[3..])),
+        _ => out.push_str(&format!(" {zone}")),
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Attachments (embedded files)
+// ---------------------------------------------------------------------------
+
+/// A file in the document's `/EmbeddedFiles` name tree.
+struct Attachment {
+    name: String,
+    size: Option<u64>,
+    mime: Option<String>,
+    bytes: Option<Vec<u8>>,
+}
+
+fn read_attachments(doc: &Document) -> Vec<Attachment> {
+    let Some(tree) = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| doc.dereference(names).ok())
+        .and_then(|(_, names)| names.as_dict().ok())
+        .and_then(|names| names.get(b"EmbeddedFiles").ok())
+        .and_then(|tree| doc.dereference(tree).ok())
+        .map(|(_, tree)| tree)
+    else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    name_tree_entries(doc, tree, 0, &mut entries);
+    entries
+        .into_iter()
+        .filter_map(|(key, spec)| {
+            let spec = doc.dereference(spec).ok()?.1.as_dict().ok()?;
+            let name = [b"UF".as_slice(), b"F"]
+                .iter()
+                .filter_map(|k| spec.get(k).ok())
+                .map(pdf_object_to_string)
+                .find(|name| !name.is_empty())
+                .unwrap_or_else(|| String::from_utf8_lossy(key).into_owned());
+            let stream = spec
+                .get(b"EF")
+                .and_then(Object::as_dict)
+                .ok()
+                .and_then(|ef| ef.get(b"UF").or_else(|_| ef.get(b"F")).ok())
+                .and_then(|file| doc.dereference(file).ok())
+                .and_then(|(_, file)| file.as_stream().ok());
+            let bytes = stream.and_then(|s| {
+                if s.dict.has(b"Filter") {
+                    s.decompressed_content().ok()
+                } else {
+                    Some(s.content.clone())
+                }
+            });
+            let size = stream
+                .and_then(|s| s.dict.get(b"Params").and_then(Object::as_dict).ok())
+                .and_then(|params| params.get(b"Size").and_then(Object::as_i64).ok())
+                .and_then(|size| u64::try_from(size).ok())
+                .or_else(|| bytes.as_ref().map(|b| b.len() as u64));
+            let mime = stream
+                .and_then(|s| s.dict.get(b"Subtype").and_then(Object::as_name).ok())
+                .map(|mime| String::from_utf8_lossy(mime).into_owned());
+            Some(Attachment {
+                name,
+                size,
+                mime,
+                bytes,
+            })
+        })
+        .collect()
+}
+
+/// Collect the key/value pairs of a name tree in key order.
+fn name_tree_entries<'a>(
+    doc: &'a Document,
+    node: &'a Object,
+    depth: usize,
+    out: &mut Vec<(&'a [u8], &'a Object)>,
+) {
+    if depth > 16 {
+        return;
+    }
+    let Ok(node) = node.as_dict() else {
+        return;
+    };
+    if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+        for pair in names.chunks(2) {
+            if let (Ok(key), Some(value)) = (pair[0].as_str(), pair.get(1)) {
+                out.push((key, value));
+            }
+        }
+    }
+    if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+        for (_, kid) in kids.iter().filter_map(|kid| doc.dereference(kid).ok()) {
+            name_tree_entries(doc, kid, depth + 1, out);
+        }
+    }
+}
+
+/// List the attachments and, with `--convert-attachments`, convert the ones in a
+/// recognised format below the list.
+fn write_attachments(
+    writer: &mut dyn Write,
+    attachments: &[Attachment],
+    options: &ConvertOptions,
+) -> Result<()> {
+    writeln!(writer, "## Attachments")?;
+    writeln!(writer)?;
+    let mut rows = vec![vec![
+        "Name".to_string(),
+        "Size".to_string(),
+        "Type".to_string(),
+    ]];
+    let stem = input_stem(options.input_name.as_deref(), "pdf");
+    for attachment in attachments {
+        // With a media directory, the attachment is written out and linked
+        let name = match (&options.media.extract_dir, &attachment.bytes) {
+            (Some(dir), Some(bytes)) => {
+                let file_name = attachment
+                    .name
+                    .rsplit(['/', '\\'])
+                    .next()
+                    .unwrap_or_default();
+                let link = write_media(
+                    dir,
+                    &format!("{stem}-{file_name}"),
+                    bytes,
+                    options.media.link_base.as_deref(),
+                )?;
+                format!("[{}]({link})", attachment.name)
+            }
+            _ => attachment.name.clone(),
+        };
+        rows.push(vec![
+            name,
+            attachment.size.map(format_size).unwrap_or_default(),
+            attachment.mime.clone().unwrap_or_default(),
+        ]);
+    }
+    render_table(writer, &rows)?;
+
+    if !options.pdf.convert_attachments {
+        return Ok(());
+    }
+    for attachment in attachments {
+        let Some(bytes) = &attachment.bytes else {
+            continue;
+        };
+        match embedded::convert(&attachment.name, bytes, options, 3) {
+            Ok(Some(markdown)) => {
+                writeln!(writer, "### {}", attachment.name)?;
+                writeln!(writer)?;
+                writeln!(writer, "{markdown}")?;
+                writeln!(writer)?;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                writeln!(writer, "### {}", attachment.name)?;
+                writeln!(writer)?;
+                writeln!(writer, "*Not converted: {e}*")?;
+                writeln!(writer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    const GB: u64 = 1024 * MB;
+
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Outline (bookmarks)
+// ---------------------------------------------------------------------------
+
+/// An outline entry placed at its destination.
+#[derive(Debug, PartialEq)]
+struct Bookmark {
+    title: String,
+    /// Nesting depth, 0 for top-level entries.
+    depth: usize,
+    /// 0-based page index.
+    page: usize,
+    /// Destination y-coordinate in page space, when the link gives one.
+    top: Option<f64>,
+}
+
+/// Read the document outline in order. Entries without a usable destination
+/// are placed with the entry that follows them.
+fn read_outline(doc: &Document) -> Vec<Bookmark> {
+    let pages: HashMap<ObjectId, usize> = doc
+        .get_pages()
+        .into_iter()
+        .map(|(number, id)| (id, number as usize - 1))
+        .collect();
+    let first = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Outlines").ok())
+        .and_then(|outlines| doc.dereference(outlines).ok())
+        .and_then(|(_, outlines)| outlines.as_dict().ok())
+        .and_then(|outlines| outlines.get(b"First").ok())
+        .and_then(|first| first.as_reference().ok());
+
+    let mut entries = Vec::new();
+    collect_bookmarks(doc, first, 0, &pages, &mut HashSet::new(), &mut entries);
+
+    let mut bookmarks = Vec::new();
+    let mut next = None;
+    for (title, depth, target) in entries.into_iter().rev() {
+        if target.is_some() {
+            next = target;
+        }
+        if let Some((page, top)) = target.or(next) {
+            bookmarks.push(Bookmark {
+                title,
+                depth,
+                page,
+                top,
+            });
+        }
+    }
+    bookmarks.reverse();
+    bookmarks
+}
+
+type Target = Option<(usize, Option<f64>)>;
+
+fn collect_bookmarks(
+    doc: &Document,
+    mut id: Option<ObjectId>,
+    depth: usize,
+    pages: &HashMap<ObjectId, usize>,
+    seen: &mut HashSet<ObjectId>,
+    out: &mut Vec<(String, usize, Target)>,
+) {
+    while let Some(current) = id {
+        if !seen.insert(current) {
+            break;
+        }
+        let Ok(item) = doc.get_dictionary(current) else {
+            break;
+        };
+
+        let title = item
+            .get(b"Title")
+            .ok()
+            .and_then(|t| doc.dereference(t).ok())
+            .map(|(_, t)| pdf_object_to_string(t))
+            .unwrap_or_default();
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !title.is_empty() {
+            out.push((title, depth, bookmark_target(doc, item, pages)));
+        }
+
+        let child = item.get(b"First").and_then(Object::as_reference).ok();
+        collect_bookmarks(doc, child, depth + 1, pages, seen, out);
+        id = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+}
+
+/// Resolve an outline item's `/Dest` or `/A` GoTo action.
+fn bookmark_target(
+    doc: &Document,
+    item: &pdf_extract::Dictionary,
+    pages: &HashMap<ObjectId, usize>,
+) -> Target {
+    let dest = match item.get(b"Dest") {
+        Ok(dest) => dest,
+        Err(_) => {
+            let (_, action) = doc.dereference(item.get(b"A").ok()?).ok()?;
+            let action = action.as_dict().ok()?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+                return None;
+            }
+            action.get(b"D").ok()?
+        }
+    };
+    resolve_destination(doc, dest, pages, 0)
+}
+
+fn resolve_destination(
+    doc: &Document,
+    dest: &Object,
+    pages: &HashMap<ObjectId, usize>,
+    depth: usize,
+) -> Target {
+    if depth > 8 {
+        return None;
+    }
+    let (_, dest) = doc.dereference(dest).ok()?;
+    match dest {
+        // [page /XYZ left top zoom], [page /FitH top], [page /Fit], ...
+        Object::Array(array) => {
+            let page = match array.first()? {
+                Object::Integer(n) => usize::try_from(*n).ok()?,
+                page => *pages.get(&page.as_reference().ok()?)?,
+            };
+            let top = match array.get(1).and_then(|k| k.as_name().ok()) {
+                Some(b"XYZ") => array.get(3),
+                Some(b"FitH" | b"FitBH") => array.get(2),
+                _ => None,
+            };
+            Some((page, top.and_then(|t| t.as_float().ok()).map(f64::from)))
+        }
+        Object::Dictionary(dict) => {
+            resolve_destination(doc, dict.get(b"D").ok()?, pages, depth + 1)
+        }
+        Object::Name(name) | Object::String(name, _) => {
+            let target = named_destination(doc, name)?;
+            resolve_destination(doc, target, pages, depth + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Look a named destination up in the catalog's `/Dests` dictionary or the
+/// `/Names` `/Dests` name tree.
+fn named_destination<'a>(doc: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = doc.catalog().ok()?;
+    if let Some(dest) = catalog
+        .get(b"Dests")
+        .ok()
+        .and_then(|d| doc.dereference(d).ok())
+        .and_then(|(_, d)| d.as_dict().ok())
+        .and_then(|d| d.get(name).ok())
+    {
+        return Some(dest);
+    }
+    let names = doc
+        .dereference(catalog.get(b"Names").ok()?)
+        .ok()?
+        .1
+        .as_dict()
+        .ok()?;
+    let tree = doc.dereference(names.get(b"Dests").ok()?).ok()?.1;
+    name_tree_lookup(doc, tree, name, 0)
+}
+
+fn name_tree_lookup<'a>(
+    doc: &'a Document,
+    node: &'a Object,
+    name: &[u8],
+    depth: usize,
+) -> Option<&'a Object> {
+    if depth > 16 {
+        return None;
+    }
+    let node = node.as_dict().ok()?;
+    if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+        return names
+            .chunks(2)
+            .find(|pair| pair[0].as_str().is_ok_and(|key| key == name))
+            .and_then(|pair| pair.get(1));
+    }
+    node.get(b"Kids")
+        .and_then(Object::as_array)
+        .ok()?
+        .iter()
+        .filter_map(|kid| doc.dereference(kid).ok())
+        .find_map(|(_, kid)| name_tree_lookup(doc, kid, name, depth + 1))
+}
+
 // ---------------------------------------------------------------------------
 // Metadata
 // ---------------------------------------------------------------------------
@@ -719,3 +1767,678 @@ fn strip_numbered_prefix(line: &str) -> Option<&str> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::PdfOptions;
+    use pdf_extract::{Dictionary, Stream, StringFormat, dictionary};
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    /// Build a PDF with one page per entry, each a list of `(y, text)` lines
+    /// set in Helvetica at the left margin. `configure` can add catalog
+    /// entries once the page ids are known.
+    fn make_pdf(
+        pages: &[&[(f64, &str)]],
+        configure: impl FnOnce(&mut Document, &[ObjectId], &mut Dictionary),
+    ) -> Vec<u8> {
+        let pages: Vec<Vec<(f64, f64, &str)>> = pages
+            .iter()
+            .map(|lines| lines.iter().map(|&(y, text)| (72.0, y, text)).collect())
+            .collect();
+        let pages: Vec<&[(f64, f64, &str)]> = pages.iter().map(Vec::as_slice).collect();
+        make_pdf_at(&pages, configure)
+    }
+
+    /// Like [`make_pdf`], with `(x, y, text)` runs.
+    fn make_pdf_at(
+        pages: &[&[(f64, f64, &str)]],
+        configure: impl FnOnce(&mut Document, &[ObjectId], &mut Dictionary),
+    ) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let mut page_ids = Vec::new();
+        for lines in pages {
+            let content: String = lines
+                .iter()
+                .map(|(x, y, text)| format!("BT /F1 12 Tf {x} {y} Td ({text}) Tj ET\n"))
+                .collect();
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+            page_ids.push(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }));
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.iter().map(|id| Object::Reference(*id)).collect::<Vec<_>>(),
+                "Count" => page_ids.len() as i64,
+                "Resources" => resources_id,
+            }),
+        );
+
+        let mut catalog = dictionary! { "Type" => "Catalog", "Pages" => pages_id };
+        configure(&mut doc, &page_ids, &mut catalog);
+        let catalog_id = doc.add_object(catalog);
+        doc.trailer.set("Root", catalog_id);
+
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn text(s: &str) -> Object {
+        Object::String(s.as_bytes().to_vec(), StringFormat::Literal)
+    }
+
+    /// Add an outline from `(title, depth, destination)` entries in order.
+    fn add_outline(
+        doc: &mut Document,
+        catalog: &mut Dictionary,
+        entries: Vec<(&str, usize, Object)>,
+    ) {
+        let outlines_id = doc.new_object_id();
+        let ids: Vec<ObjectId> = entries.iter().map(|_| doc.new_object_id()).collect();
+        let mut outlines = dictionary! { "Type" => "Outlines" };
+        let mut items: Vec<Dictionary> = Vec::new();
+        // Index of each open ancestor, None for the outline root
+        let mut ancestors: Vec<Option<usize>> = vec![None];
+        let mut last_child: HashMap<Option<usize>, usize> = HashMap::new();
+
+        for (i, (title, depth, dest)) in entries.into_iter().enumerate() {
+            ancestors.truncate(depth + 1);
+            let parent = ancestors[depth];
+            let item = dictionary! {
+                "Title" => text(title),
+                "Dest" => dest,
+                "Parent" => parent.map_or(outlines_id, |p| ids[p]),
+            };
+            match (last_child.insert(parent, i), parent) {
+                (Some(prev), _) => items[prev].set("Next", ids[i]),
+                (None, Some(p)) => items[p].set("First", ids[i]),
+                (None, None) => outlines.set("First", ids[i]),
+            }
+            items.push(item);
+            ancestors.push(Some(i));
+        }
+        for (id, item) in ids.into_iter().zip(items) {
+            doc.objects.insert(id, Object::Dictionary(item));
+        }
+        doc.objects
+            .insert(outlines_id, Object::Dictionary(outlines));
+        catalog.set("Outlines", outlines_id);
+    }
+
+    fn xyz(page: ObjectId, top: f64) -> Object {
+        Object::Array(vec![
+            page.into(),
+            "XYZ".into(),
+            0.into(),
+            Object::Real(top as f32),
+            Object::Null,
+        ])
+    }
+
+    fn convert(pdf: &[u8]) -> String {
+        let mut output = Vec::new();
+        PdfConverter::default().convert(pdf, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_aligned_columns_become_table() {
+        let pdf = make_pdf_at(
+            &[&[
+                (72.0, 700.0, "Quarterly results are summarised below."),
+                (72.0, 670.0, "Region"),
+                (200.0, 670.0, "Q1"),
+                (300.0, 670.0, "Q2"),
+                (72.0, 655.0, "North"),
+                (200.0, 655.0, "1,200"),
+                (300.0, 655.0, "1,350"),
+                // Right-aligned figures start at different x positions
+                (72.0, 640.0, "South East"),
+                (210.0, 640.0, "980"),
+                (300.0, 640.0, "1,010"),
+                (72.0, 625.0, "West"),
+                (200.0, 625.0, "N/A"),
+                (310.0, 625.0, "700"),
+                (72.0, 595.0, "Totals rose in every region this year."),
+            ]],
+            |_, _, _| {},
+        );
+        assert_eq!(
+            convert(&pdf),
+            "## Page 1\n\nQuarterly results are summarised below.\n\n\
+             | Region | Q1 | Q2 |\n| --- | --- | --- |\n| North | 1,200 | 1,350 |\n\
+             | South East | 980 | 1,010 |\n| West | N/A | 700 |\n\n\
+             Totals rose in every region this year.\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_column_spans_merge_overlaps() {
+        let word = |x: f64, end: f64| Word {
+            x,
+            end,
+            y: 0.0,
+            text: "w".into(),
+            link: None,
+        };
+        let a = TextLine {
+            y: 0.0,
+            words: vec![word(10.0, 40.0), word(100.0, 120.0)],
+        };
+        let b = TextLine {
+            y: 0.0,
+            words: vec![word(30.0, 60.0), word(110.0, 130.0)],
+        };
+        assert_eq!(column_spans(&[&a, &b]), vec![(10.0, 60.0), (100.0, 130.0)]);
+    }
+
+    #[cfg(not(feature = "pdf-ocr"))]
+    #[rstest]
+    fn test_image_only_page() {
+        let pdf = make_pdf(&[&[]], |doc, pages, _| {
+            let image = doc.add_object(Stream::new(
+                dictionary! {
+                    "Type" => "XObject",
+                    "Subtype" => "Image",
+                    "Filter" => "DCTDecode",
+                    "Width" => 1,
+                    "Height" => 1,
+                },
+                b"\xff\xd8".to_vec(),
+            ));
+            let page = doc.get_dictionary_mut(pages[0]).unwrap();
+            page.set(
+                "Resources",
+                dictionary! { "XObject" => dictionary! { "Im1" => image } },
+            );
+        });
+        assert_eq!(
+            convert(&pdf),
+            "## Page 1\n\n*Page contains only images; build with the `pdf-ocr` feature to recognise their text*\n"
+        );
+    }
+
+    /// Add a URI link annotation covering `rect` to the page.
+    fn add_link(doc: &mut Document, page: ObjectId, rect: [i64; 4], uri: &str) {
+        let link = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => rect.iter().map(|&v| Object::Integer(v)).collect::<Vec<_>>(),
+            "A" => dictionary! { "S" => "URI", "URI" => text(uri) },
+        });
+        let page = doc.get_dictionary_mut(page).unwrap();
+        let mut annots = page
+            .get(b"Annots")
+            .and_then(Object::as_array)
+            .cloned()
+            .unwrap_or_default();
+        annots.push(link.into());
+        page.set("Annots", annots);
+    }
+
+    #[rstest]
+    fn test_link_inlined_over_text() {
+        // "example site" spans x 116.7-184.1 in 12pt Helvetica
+        let pdf = make_pdf(
+            &[&[(700.0, "See the example site for details.")]],
+            |doc, pages, _| {
+                add_link(
+                    doc,
+                    pages[0],
+                    [116, 695, 184, 712],
+                    "https://example.com/a b",
+                )
+            },
+        );
+        assert_eq!(
+            convert(&pdf),
+            "## Page 1\n\nSee the [example site](https://example.com/a%20b) for details.\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_links_without_text_listed() {
+        let pdf = make_pdf(&[&[(700.0, "Body text here.")]], |doc, pages, _| {
+            add_link(
+                doc,
+                pages[0],
+                [72, 100, 200, 200],
+                "https://example.com/logo",
+            );
+            add_link(
+                doc,
+                pages[0],
+                [72, 300, 200, 400],
+                "mailto:team@example.com",
+            );
+        });
+        assert_eq!(
+            convert(&pdf),
+            "## Page 1\n\nBody text here.\n\n**Links**:\n\n\
+             - [https://example.com/logo](https://example.com/logo)\n\
+             - [mailto:team@example.com](mailto:team@example.com)\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_pages_without_outline() {
+        let pdf = make_pdf(
+            &[
+                &[(700.0, "First page text.")],
+                &[(700.0, "Second page text.")],
+            ],
+            |_, _, _| {},
+        );
+        assert_eq!(
+            convert(&pdf),
+            "## Page 1\n\nFirst page text.\n\n\n---\n\n## Page 2\n\nSecond page text.\n\n"
+        );
+    }
+
+    /// Encrypt `pdf` with RC4 so that `user_password` is needed to open it.
+    fn encrypt(pdf: &[u8], user_password: &str) -> Vec<u8> {
+        use pdf_extract::{EncryptionState, EncryptionVersion, Permissions};
+
+        let mut doc = Document::load_mem(pdf).unwrap();
+        let id = Object::String(b"0123456789abcdef".to_vec(), StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+        let state = EncryptionState::try_from(EncryptionVersion::V2 {
+            document: &doc,
+            owner_password: "owner",
+            user_password,
+            key_length: 128,
+            permissions: Permissions::all(),
+        })
+        .unwrap();
+        doc.encrypt(&state).unwrap();
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn convert_with_password(pdf: &[u8], password: Option<&str>) -> Result<String> {
+        let options = ConvertOptions {
+            pdf: PdfOptions {
+                password: password.map(str::to_string),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PdfConverter::new(options).convert(pdf, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[rstest]
+    fn test_encrypted_with_password() {
+        let pdf = encrypt(
+            &make_pdf(&[&[(700.0, "Top secret text.")]], |_, _, _| {}),
+            "secret",
+        );
+        let output = convert_with_password(&pdf, Some("secret")).unwrap();
+        assert_eq!(output, "## Page 1\n\nTop secret text.\n\n");
+    }
+
+    #[rstest]
+    #[case::missing(None, "provide its password")]
+    #[case::wrong(Some("guess"), "Incorrect password")]
+    fn test_encrypted_without_password(#[case] password: Option<&str>, #[case] expected: &str) {
+        let pdf = encrypt(
+            &make_pdf(&[&[(700.0, "Top secret text.")]], |_, _, _| {}),
+            "secret",
+        );
+        let err = convert_with_password(&pdf, password).unwrap_err();
+        assert!(err.to_string().contains(expected), "{err}");
+    }
+
+    #[rstest]
+    fn test_encrypted_with_empty_user_password() {
+        let pdf = encrypt(&make_pdf(&[&[(700.0, "Open text.")]], |_, _, _| {}), "");
+        let output = convert_with_password(&pdf, None).unwrap();
+        assert_eq!(output, "## Page 1\n\nOpen text.\n\n");
+    }
+
+    #[rstest]
+    fn test_images_extracted_in_place() {
+        let pdf = make_pdf(
+            &[&[(700.0, "Above the chart."), (500.0, "Below the chart.")]],
+            |doc, pages, _| {
+                let image = doc.add_object(Stream::new(
+                    dictionary! {
+                        "Type" => "XObject",
+                        "Subtype" => "Image",
+                        "Width" => 2,
+                        "Height" => 2,
+                        "BitsPerComponent" => 8,
+                        "ColorSpace" => "DeviceGray",
+                    },
+                    vec![0, 255, 255, 0],
+                ));
+                let draw = doc.add_object(Stream::new(
+                    dictionary! {},
+                    b"q 200 0 0 100 72 560 cm /Im1 Do Q\n".to_vec(),
+                ));
+                let page = doc.get_dictionary_mut(pages[0]).unwrap();
+                let text = page.get(b"Contents").unwrap().clone();
+                page.set("Contents", vec![text, draw.into()]);
+                page.set(
+                    "Resources",
+                    dictionary! {
+                        "Font" => dictionary! {
+                            "F1" => dictionary! {
+                                "Type" => "Font",
+                                "Subtype" => "Type1",
+                                "BaseFont" => "Helvetica",
+                            },
+                        },
+                        "XObject" => dictionary! { "Im1" => image },
+                    },
+                );
+            },
+        );
+
+        let dir = std::env::temp_dir().join(format!("mq-conv-pdf-images-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let options = ConvertOptions {
+            input_name: Some("report.pdf".to_string()),
+            media: crate::options::MediaOptions {
+                extract_dir: Some(dir.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PdfConverter::new(options)
+            .convert(&pdf, &mut output)
+            .unwrap();
+        let written = std::fs::read(dir.join("report-page1-1.png"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let link = format!("{}/report-page1-1.png", dir.display());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "## Page 1\n\nAbove the chart.\n\n![Image 1 on page 1]({link})\n\nBelow the chart.\n\n"
+            )
+        );
+        assert!(written.unwrap().starts_with(b"\x89PNG"));
+    }
+
+    fn with_attachment() -> Vec<u8> {
+        make_pdf(
+            &[&[(700.0, "See the attached data.")]],
+            |doc, _, catalog| {
+                let file = doc.add_object(Stream::new(
+                    dictionary! {
+                        "Type" => "EmbeddedFile",
+                        "Subtype" => "text/csv",
+                        "Params" => dictionary! { "Size" => 18 },
+                    },
+                    b"name,qty\napple,3\n".to_vec(),
+                ));
+                let spec = doc.add_object(dictionary! {
+                    "Type" => "Filespec",
+                    "F" => text("data.csv"),
+                    "EF" => dictionary! { "F" => file },
+                });
+                let names = doc.add_object(dictionary! {
+                    "EmbeddedFiles" => dictionary! {
+                        "Names" => vec![text("data.csv"), spec.into()],
+                    },
+                });
+                catalog.set("Names", names);
+            },
+        )
+    }
+
+    #[rstest]
+    fn test_attachments_listed() {
+        assert_eq!(
+            convert(&with_attachment()),
+            "## Page 1\n\nSee the attached data.\n\n---\n\n## Attachments\n\n\
+             | Name | Size | Type |\n| --- | --- | --- |\n| data.csv | 18 B | text/csv |\n\n"
+        );
+    }
+
+    #[cfg(feature = "csv")]
+    #[rstest]
+    fn test_attachments_converted() {
+        let options = ConvertOptions {
+            pdf: PdfOptions {
+                convert_attachments: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PdfConverter::new(options)
+            .convert(&with_attachment(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.ends_with(
+                "| data.csv | 18 B | text/csv |\n\n\
+                 ### data.csv\n\n| name | qty |\n|---|---|\n| apple | 3 |\n\n"
+            ),
+            "{output}"
+        );
+    }
+
+    #[rstest]
+    fn test_attachments_extracted_to_media_dir() {
+        let dir =
+            std::env::temp_dir().join(format!("mq-conv-pdf-attachments-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let options = ConvertOptions {
+            input_name: Some("invoice.pdf".to_string()),
+            media: crate::options::MediaOptions {
+                extract_dir: Some(dir.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PdfConverter::new(options)
+            .convert(&with_attachment(), &mut output)
+            .unwrap();
+        let path = dir.join("invoice-data.csv");
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.contains(&format!(
+                "| [data.csv]({}) | 18 B | text/csv |",
+                path.to_string_lossy().replace('\\', "/")
+            )),
+            "{output}"
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"name,qty\napple,3\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest]
+    fn test_signatures() {
+        let pdf = make_pdf(&[&[(700.0, "Signed contract.")]], |doc, _, catalog| {
+            let signature = doc.add_object(dictionary! {
+                "Type" => "Sig",
+                "Filter" => "Adobe.PPKLite",
+                "Name" => text("Ada Lovelace"),
+                "M" => text("D:20240301120000+01'00'"),
+                "Reason" => text("Approved"),
+            });
+            let fields: Vec<Object> = [
+                dictionary! { "T" => text("approver"), "FT" => "Sig", "V" => signature },
+                dictionary! { "T" => text("witness"), "FT" => "Sig" },
+            ]
+            .into_iter()
+            .map(|field| doc.add_object(field).into())
+            .collect();
+            let form = doc.add_object(dictionary! { "Fields" => fields });
+            catalog.set("AcroForm", form);
+        });
+        let output = convert(&pdf);
+        assert!(
+            output.ends_with(
+                "| approver | Signature | Signed |\n| witness | Signature | Unsigned |\n\n\
+                 ---\n\n## Signatures\n\n\
+                 | Field | Signer | Signed | Reason | Location |\n\
+                 | --- | --- | --- | --- | --- |\n\
+                 | approver | Ada Lovelace | 2024-03-01 12:00:00 +01:00 | Approved |  |\n\n"
+            ),
+            "{output}"
+        );
+    }
+
+    #[rstest]
+    #[case::full("D:20240301120000+01'00'", "2024-03-01 12:00:00 +01:00")]
+    #[case::utc("D:20240301120000Z", "2024-03-01 12:00:00 UTC")]
+    #[case::date_only("D:20240301", "2024-03-01")]
+    #[case::not_a_date("yesterday", "yesterday")]
+    fn test_format_pdf_date(#[case] date: &str, #[case] expected: &str) {
+        assert_eq!(format_pdf_date(date), expected);
+    }
+
+    #[rstest]
+    fn test_form_fields() {
+        let pdf = make_pdf(
+            &[&[(700.0, "Fill in the form below.")]],
+            |doc, _, catalog| {
+                let city = doc.add_object(dictionary! {
+                    "T" => text("city"),
+                    "V" => text("Paris"),
+                });
+                let address = doc.add_object(dictionary! {
+                    "T" => text("address"),
+                    "FT" => "Tx",
+                    "Kids" => vec![city.into()],
+                });
+                let widget = doc.add_object(dictionary! { "Subtype" => "Widget" });
+                let fields: Vec<Object> = [
+                dictionary! { "T" => text("name"), "FT" => "Tx", "V" => text("Ada | L\nLovelace") },
+                dictionary! {
+                    "T" => text("agree"),
+                    "FT" => "Btn",
+                    "V" => "Yes",
+                    "Kids" => vec![widget.into()],
+                },
+                dictionary! { "T" => text("size"), "FT" => "Btn", "Ff" => 1 << 15, "V" => "Off" },
+                dictionary! { "T" => text("colour"), "FT" => "Ch", "Ff" => 1 << 17, "V" => text("Red") },
+                dictionary! { "T" => text("signature"), "FT" => "Sig" },
+            ]
+            .into_iter()
+            .map(|field| doc.add_object(field).into())
+            .chain([address.into()])
+            .collect();
+                let form = doc.add_object(dictionary! { "Fields" => fields });
+                catalog.set("AcroForm", form);
+            },
+        );
+        assert_eq!(
+            convert(&pdf),
+            "## Page 1\n\nFill in the form below.\n\n---\n\n## Form Fields\n\n\
+             | Field | Type | Value |\n| --- | --- | --- |\n\
+             | name | Text | Ada \\| L<br>Lovelace |\n\
+             | agree | Checkbox | Checked |\n\
+             | size | Radio |  |\n\
+             | colour | Combo box | Red |\n\
+             | signature | Signature | Unsigned |\n\
+             | address.city | Text | Paris |\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_outline_becomes_headings() {
+        let pdf = make_pdf(
+            &[
+                &[
+                    (700.0, "Introduction"),
+                    (680.0, "Opening words here."),
+                    (600.0, "Background"),
+                    (580.0, "Some history."),
+                ],
+                &[
+                    (700.0, "Later notes continue."),
+                    (500.0, "Results"),
+                    (480.0, "Numbers went up."),
+                ],
+            ],
+            |doc, pages, catalog| {
+                let (p1, p2) = (pages[0], pages[1]);
+                doc.objects.insert(
+                    (90, 0),
+                    Object::Dictionary(dictionary! {
+                        "Names" => vec![text("results"), xyz(p2, 512.0)],
+                    }),
+                );
+                let names = doc.add_object(dictionary! { "Dests" => (90, 0) });
+                catalog.set("Names", names);
+                add_outline(
+                    doc,
+                    catalog,
+                    vec![
+                        ("Introduction", 0, xyz(p1, 712.0)),
+                        ("Background", 1, xyz(p1, 612.0)),
+                        ("Results", 0, text("results")),
+                    ],
+                );
+            },
+        );
+        assert_eq!(
+            convert(&pdf),
+            "<!-- Page 1 -->\n\n## Introduction\n\nOpening words here.\n\n### Background\n\nSome history.\n\n\n---\n\n\
+             <!-- Page 2 -->\n\nLater notes continue.\n\n## Results\n\nNumbers went up.\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_read_outline_fills_missing_destinations() {
+        let pdf = make_pdf(
+            &[&[(700.0, "A")], &[(700.0, "B")]],
+            |doc, pages, catalog| {
+                let p2 = pages[1];
+                add_outline(
+                    doc,
+                    catalog,
+                    vec![
+                        ("Part One", 0, Object::Null),
+                        ("Chapter 1", 1, Object::Array(vec![p2.into(), "Fit".into()])),
+                    ],
+                );
+            },
+        );
+        let doc = Document::load_mem(&pdf).unwrap();
+        assert_eq!(
+            read_outline(&doc),
+            vec![
+                Bookmark {
+                    title: "Part One".into(),
+                    depth: 0,
+                    page: 1,
+                    top: None,
+                },
+                Bookmark {
+                    title: "Chapter 1".into(),
+                    depth: 1,
+                    page: 1,
+                    top: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use flate2::Crc;
+use flate2::write::ZlibEncoder;
+use pdf_extract::{Dictionary, Document, Object, ObjectId, Stream};
+
+/// An image XObject drawn on a page, as a standalone file.
+pub(super) struct PageImage {
+    pub bytes: Vec<u8>,
+    pub extension: &'static str,
+    /// Top edge of the area the image is first drawn in, in page space.
+    /// `None` when the page content never draws it directly.
+    pub top: Option<f64>,
+}
+
+/// Images in a page's resources (including inherited ones), in resource
+/// order. Images in encodings that cannot be turned into a file are skipped.
+pub(super) fn page_images(doc: &Document, page_id: ObjectId) -> Vec<PageImage> {
+    let Ok((own, inherited)) = doc.get_page_resources(page_id) else {
+        return Vec::new();
+    };
+    let tops = image_tops(doc, page_id);
+    let resources = own.into_iter().chain(
+        inherited
+            .into_iter()
+            .filter_map(|id| doc.get_dictionary(id).ok()),
+    );
+
+    let mut seen = Vec::new();
+    let mut images = Vec::new();
+    for resources in resources {
+        let Some(xobjects) = resources
+            .get(b"XObject")
+            .ok()
+            .and_then(|x| doc.dereference(x).ok())
+            .and_then(|(_, x)| x.as_dict().ok())
+        else {
+            continue;
+        };
+        for (name, xobject) in xobjects.iter() {
+            let Ok((id, Object::Stream(stream))) = doc.dereference(xobject) else {
+                continue;
+            };
+            if id.is_some_and(|id| seen.contains(&id)) {
+                continue;
+            }
+            seen.extend(id);
+            if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image")
+                && let Some(mut image) = encode(doc, stream)
+            {
+                image.top = tops.get(name.as_slice()).copied();
+                images.push(image);
+            }
+        }
+    }
+    images
+}
+
+/// The top edge of each XObject the page content draws with `Do`, keyed by
+/// resource name, from the first time it is drawn.
+fn image_tops(doc: &Document, page_id: ObjectId) -> HashMap<Vec<u8>, f64> {
+    let mut tops = HashMap::new();
+    let Ok(content) = doc.get_and_decode_page_content(page_id) else {
+        return tops;
+    };
+
+    let mut ctm = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+    let mut stack = Vec::new();
+    for op in &content.operations {
+        match op.operator.as_str() {
+            "q" => stack.push(ctm),
+            "Q" => ctm = stack.pop().unwrap_or(ctm),
+            "cm" => {
+                let m: Vec<f64> = op
+                    .operands
+                    .iter()
+                    .filter_map(|v| v.as_float().ok().map(f64::from))
+                    .collect();
+                if let [a, b, c, d, e, f] = m[..] {
+                    ctm = [
+                        a * ctm[0] + b * ctm[2],
+                        a * ctm[1] + b * ctm[3],
+                        c * ctm[0] + d * ctm[2],
+                        c * ctm[1] + d * ctm[3],
+                        e * ctm[0] + f * ctm[2] + ctm[4],
+                        e * ctm[1] + f * ctm[3] + ctm[5],
+                    ];
+                }
+            }
+            "Do" => {
+                if let Some(Ok(name)) = op.operands.first().map(Object::as_name) {
+                    // Images fill the unit square, so the top is its highest corner
+                    let top = [0.0, ctm[1], ctm[3], ctm[1] + ctm[3]]
+                        .into_iter()
+                        .fold(f64::MIN, f64::max)
+                        + ctm[5];
+                    tops.entry(name.to_vec()).or_insert(top);
+                }
+            }
+            _ => {}
+        }
+    }
+    tops
+}
+
+fn encode(doc: &Document, stream: &Stream) -> Option<PageImage> {
+    let dict = &stream.dict;
+    let filters: Vec<&[u8]> = match dict.get(b"Filter") {
+        Ok(Object::Name(name)) => vec![name.as_slice()],
+        Ok(Object::Array(array)) => array.iter().filter_map(|f| f.as_name().ok()).collect(),
+        _ => Vec::new(),
+    };
+
+    match filters.last().copied() {
+        Some(b"DCTDecode") => Some(PageImage {
+            bytes: predecode(stream, filters.len() - 1)?,
+            extension: "jpg",
+            top: None,
+        }),
+        Some(b"JPXDecode") => Some(PageImage {
+            bytes: predecode(stream, filters.len() - 1)?,
+            extension: "jp2",
+            top: None,
+        }),
+        Some(b"CCITTFaxDecode") => {
+            let data = predecode(stream, filters.len() - 1)?;
+            Some(PageImage {
+                bytes: ccitt_to_tiff(dict, &data)?,
+                extension: "tiff",
+                top: None,
+            })
+        }
+        _ => {
+            let pixels = stream.decompressed_content().ok()?;
+            Some(PageImage {
+                bytes: raw_to_png(doc, dict, &pixels)?,
+                extension: "png",
+                top: None,
+            })
+        }
+    }
+}
+
+/// Undo the general-purpose filters applied before the image codec.
+fn predecode(stream: &Stream, count: usize) -> Option<Vec<u8>> {
+    if count == 0 {
+        return Some(stream.content.clone());
+    }
+    let filters = match stream.dict.get(b"Filter").ok()? {
+        Object::Array(array) => array[..count].to_vec(),
+        _ => return None,
+    };
+    let mut outer = stream.clone();
+    outer.dict.set("Filter", Object::Array(filters));
+    outer.dict.remove(b"DecodeParms");
+    outer.decompressed_content().ok()
+}
+
+fn int(dict: &Dictionary, key: &[u8]) -> Option<i64> {
+    dict.get(key).and_then(Object::as_i64).ok()
+}
+
+/// Wrap CCITT fax data in a single-strip TIFF, which is how scanned pages
+/// are usually stored.
+fn ccitt_to_tiff(dict: &Dictionary, data: &[u8]) -> Option<Vec<u8>> {
+    let params = dict.get(b"DecodeParms").and_then(Object::as_dict).ok();
+    let param = |key: &[u8]| params.and_then(|p| int(p, key));
+    let width = param(b"Columns").unwrap_or(1728) as u32;
+    let height = param(b"Rows").or_else(|| int(dict, b"Height"))? as u32;
+    let k = param(b"K").unwrap_or(0);
+    // Group 4 for K < 0, otherwise Group 3 (2D when K > 0)
+    let (compression, t4_options) = match k {
+        k if k < 0 => (4, None),
+        0 => (3, Some(0)),
+        _ => (3, Some(1)),
+    };
+    let inverted = dict
+        .get(b"Decode")
+        .and_then(Object::as_array)
+        .is_ok_and(|d| d.first().and_then(|v| v.as_i64().ok()) == Some(1));
+
+    let mut entries: Vec<(u16, u16, u32)> = vec![
+        (256, 4, width),               // ImageWidth
+        (257, 4, height),              // ImageLength
+        (258, 3, 1),                   // BitsPerSample
+        (259, 3, compression),         // Compression
+        (262, 3, u32::from(inverted)), // PhotometricInterpretation
+        (273, 4, 0),                   // StripOffsets, patched below
+        (277, 3, 1),                   // SamplesPerPixel
+        (278, 4, height),              // RowsPerStrip
+        (279, 4, data.len() as u32),   // StripByteCounts
+    ];
+    if let Some(options) = t4_options {
+        entries.push((292, 4, options)); // T4Options
+    }
+
+    let ifd_len = 2 + entries.len() * 12 + 4;
+    let data_offset = (8 + ifd_len) as u32;
+    let mut tiff = Vec::with_capacity(data_offset as usize + data.len());
+    tiff.extend_from_slice(b"II*\0");
+    tiff.extend_from_slice(&8u32.to_le_bytes());
+    tiff.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for (tag, kind, value) in entries {
+        let value = if tag == 273 { data_offset } else { value };
+        tiff.extend_from_slice(&tag.to_le_bytes());
+        tiff.extend_from_slice(&kind.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        if kind == 3 {
+            tiff.extend_from_slice(&(value as u16).to_le_bytes());
+            tiff.extend_from_slice(&[0, 0]);
+        } else {
+            tiff.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    tiff.extend_from_slice(&0u32.to_le_bytes());
+    tiff.extend_from_slice(data);
+    Some(tiff)
+}
+
+/// Encode uncompressed samples as a PNG. Supports gray, RGB, CMYK and
+/// indexed colour at the bit depths PNG shares with PDF.
+fn raw_to_png(doc: &Document, dict: &Dictionary, pixels: &[u8]) -> Option<Vec<u8>> {
+    let width = u32::try_from(int(dict, b"Width")?).ok()?;
+    let height = u32::try_from(int(dict, b"Height")?).ok()?;
+    let image_mask = dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false);
+    let bits = if image_mask {
+        1
+    } else {
+        int(dict, b"BitsPerComponent").unwrap_or(8) as u8
+    };
+
+    let color_space = match dict.get(b"ColorSpace") {
+        Ok(cs) => Some(doc.dereference(cs).ok()?.1),
+        Err(_) => None,
+    };
+    let (kind, palette) = if image_mask {
+        (ColorKind::Gray, None)
+    } else {
+        color_kind(doc, color_space?)?
+    };
+
+    let (color_type, channels, samples) = match kind {
+        ColorKind::Gray => (0, 1, pixels.to_vec()),
+        ColorKind::Rgb => (2, 3, pixels.to_vec()),
+        ColorKind::Cmyk if bits == 8 => (2, 3, cmyk_to_rgb(pixels)),
+        ColorKind::Indexed => (3, 1, pixels.to_vec()),
+        ColorKind::Cmyk => return None,
+    };
+    let valid_depth = match color_type {
+        0 | 3 => matches!(bits, 1 | 2 | 4 | 8),
+        _ => bits == 8,
+    };
+    let row_len = (width as usize * channels * bits as usize).div_ceil(8);
+    if !valid_depth || samples.len() < row_len * height as usize {
+        return None;
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[bits, color_type, 0, 0, 0]);
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    for row in samples.chunks(row_len).take(height as usize) {
+        encoder.write_all(&[0]).ok()?;
+        encoder.write_all(row).ok()?;
+    }
+    let idat = encoder.finish().ok()?;
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut png, b"IHDR", &ihdr);
+    if let Some(palette) = palette {
+        png_chunk(&mut png, b"PLTE", &palette);
+    }
+    png_chunk(&mut png, b"IDAT", &idat);
+    png_chunk(&mut png, b"IEND", &[]);
+    Some(png)
+}
+
+enum ColorKind {
+    Gray,
+    Rgb,
+    Cmyk,
+    Indexed,
+}
+
+/// Classify a colour space, returning the RGB palette for indexed ones.
+fn color_kind(doc: &Document, space: &Object) -> Option<(ColorKind, Option<Vec<u8>>)> {
+    match space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" | b"CalGray" | b"G" => Some((ColorKind::Gray, None)),
+            b"DeviceRGB" | b"CalRGB" | b"RGB" => Some((ColorKind::Rgb, None)),
+            b"DeviceCMYK" | b"CMYK" => Some((ColorKind::Cmyk, None)),
+            _ => None,
+        },
+        Object::Array(array) => match array.first()?.as_name().ok()? {
+            b"ICCBased" => {
+                let (_, profile) = doc.dereference(array.get(1)?).ok()?;
+                match int(&profile.as_stream().ok()?.dict, b"N")? {
+                    1 => Some((ColorKind::Gray, None)),
+                    3 => Some((ColorKind::Rgb, None)),
+                    4 => Some((ColorKind::Cmyk, None)),
+                    _ => None,
+                }
+            }
+            b"CalGray" => Some((ColorKind::Gray, None)),
+            b"CalRGB" => Some((ColorKind::Rgb, None)),
+            b"Indexed" | b"I" => {
+                let (base, _) = color_kind(doc, doc.dereference(array.get(1)?).ok()?.1)?;
+                let (_, lookup) = doc.dereference(array.get(3)?).ok()?;
+                let lookup = match lookup {
+                    Object::String(bytes, _) => bytes.clone(),
+                    Object::Stream(stream) => stream.decompressed_content().ok()?,
+                    _ => return None,
+                };
+                let palette = match base {
+                    ColorKind::Rgb => lookup,
+                    ColorKind::Gray => lookup.iter().flat_map(|&v| [v, v, v]).collect(),
+                    ColorKind::Cmyk => cmyk_to_rgb(&lookup),
+                    ColorKind::Indexed => return None,
+                };
+                Some((ColorKind::Indexed, Some(palette)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn cmyk_to_rgb(cmyk: &[u8]) -> Vec<u8> {
+    cmyk.chunks_exact(4)
+        .flat_map(|p| {
+            let k = 255 - u16::from(p[3]);
+            [0, 1, 2].map(|i| ((255 - u16::from(p[i])) * k / 255) as u8)
+        })
+        .collect()
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_extract::{Stream, dictionary};
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn image_stream(dict: Dictionary, content: &[u8]) -> Stream {
+        let mut dict = dict;
+        dict.set("Type", "XObject");
+        dict.set("Subtype", "Image");
+        Stream::new(dict, content.to_vec())
+    }
+
+    #[rstest]
+    fn test_jpeg_passes_through() {
+        let doc = Document::with_version("1.5");
+        let stream = image_stream(dictionary! { "Filter" => "DCTDecode" }, b"\xff\xd8jpeg");
+        let image = encode(&doc, &stream).unwrap();
+        assert_eq!(image.extension, "jpg");
+        assert_eq!(image.bytes, b"\xff\xd8jpeg");
+    }
+
+    #[rstest]
+    fn test_raw_gray_becomes_png() {
+        let doc = Document::with_version("1.5");
+        let stream = image_stream(
+            dictionary! {
+                "Width" => 2,
+                "Height" => 2,
+                "BitsPerComponent" => 8,
+                "ColorSpace" => "DeviceGray",
+            },
+            &[0, 255, 255, 0],
+        );
+        let image = encode(&doc, &stream).unwrap();
+        assert_eq!(image.extension, "png");
+        assert!(
+            image
+                .bytes
+                .starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x02\0\0\0\x02\x08\x00")
+        );
+        assert!(image.bytes.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[rstest]
+    fn test_indexed_palette() {
+        let doc = Document::with_version("1.5");
+        let space = Object::Array(vec![
+            "Indexed".into(),
+            "DeviceRGB".into(),
+            1.into(),
+            Object::String(
+                vec![255, 0, 0, 0, 0, 255],
+                pdf_extract::StringFormat::Hexadecimal,
+            ),
+        ]);
+        let (_, palette) = color_kind(&doc, &space).unwrap();
+        assert_eq!(palette, Some(vec![255, 0, 0, 0, 0, 255]));
+    }
+
+    #[rstest]
+    fn test_ccitt_tiff_header() {
+        let dict = dictionary! {
+            "Height" => 100,
+            "DecodeParms" => dictionary! { "K" => -1, "Columns" => 2480 },
+        };
+        let tiff = ccitt_to_tiff(&dict, b"G4DATA").unwrap();
+        assert_eq!(&tiff[..8], b"II*\0\x08\0\0\0");
+        // Compression (tag 259) is Group 4
+        let entry = tiff
+            .windows(4)
+            .position(|w| w == [0x03, 0x01, 0x03, 0x00])
+            .unwrap();
+        assert_eq!(tiff[entry + 8], 4);
+        assert!(tiff.ends_with(b"G4DATA"));
+    }
+
+    #[rstest]
+    fn test_cmyk_to_rgb() {
+        assert_eq!(
+            cmyk_to_rgb(&[0, 0, 0, 0, 255, 0, 0, 0]),
+            vec![255, 255, 255, 0, 255, 255]
+        );
+    }
+}
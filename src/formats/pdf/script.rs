@@ -0,0 +1,104 @@
+use unicode_bidi::{BidiClass, Level, ParagraphBidiInfo, bidi_class};
+
+/// Tidy a line of extracted text for the scripts it is written in: drop
+/// the spaces glyph positioning leaves between Chinese and Japanese
+/// characters, and put right-to-left text, which PDFs store in the order
+/// it is drawn, back into reading order.
+pub(super) fn normalize_line(text: &str) -> String {
+    visual_to_logical(&join_cjk(text))
+}
+
+/// Remove spaces between two CJK characters, which are written without
+/// them. Korean, which separates words with spaces, is left alone.
+pub(super) fn join_cjk(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' && out.chars().next_back().is_some_and(is_cjk) {
+            let mut spaces = 1;
+            while chars.next_if_eq(&' ').is_some() {
+                spaces += 1;
+            }
+            if !chars.peek().is_some_and(|&next| is_cjk(next)) {
+                out.extend(std::iter::repeat_n(' ', spaces));
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Reorder a line laid out left to right on the page into logical order.
+/// Reordering swaps the right-to-left runs back while numbers and
+/// left-to-right words inside them keep their direction; the line reads
+/// right to left when most of its letters do.
+fn visual_to_logical(text: &str) -> String {
+    let (mut rtl, mut ltr) = (0, 0);
+    for c in text.chars() {
+        match bidi_class(c) {
+            BidiClass::R | BidiClass::AL => rtl += 1,
+            BidiClass::L => ltr += 1,
+            _ => {}
+        }
+    }
+    if rtl == 0 {
+        return text.to_string();
+    }
+    let level = if rtl > ltr {
+        Level::rtl()
+    } else {
+        Level::ltr()
+    };
+    let info = ParagraphBidiInfo::new(text, Some(level));
+    info.reorder_line(0..text.len()).into_owned()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{2E80}'..='\u{2FDF}'     // CJK radicals
+        | '\u{3001}'..='\u{303F}'   // CJK punctuation (not the ideographic space)
+        | '\u{3040}'..='\u{30FF}'   // Hiragana and Katakana
+        | '\u{3100}'..='\u{312F}'   // Bopomofo
+        | '\u{31F0}'..='\u{31FF}'   // Katakana extensions
+        | '\u{3400}'..='\u{4DBF}'   // CJK extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK unified ideographs
+        | '\u{F900}'..='\u{FAFF}'   // CJK compatibility ideographs
+        | '\u{FF01}'..='\u{FF60}'   // Fullwidth forms
+        | '\u{FF61}'..='\u{FF9F}'   // Halfwidth Katakana
+        | '\u{20000}'..='\u{2FA1F}' // CJK extensions B and later
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::japanese("日 本 語 の テ キ ス ト 。", "日本語のテキスト。")]
+    #[case::chinese("中 文  文 本", "中文文本")]
+    #[case::mixed("PDF を 変 換 する 2 ページ", "PDF を変換する 2 ページ")]
+    #[case::korean("한국어 텍스트", "한국어 텍스트")]
+    #[case::latin("plain  text", "plain  text")]
+    fn test_join_cjk(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(join_cjk(text), expected);
+    }
+
+    #[rstest]
+    // "مرحبا بالعالم" drawn right to left
+    #[case::arabic("ملاعلاب ابحرم", "مرحبا بالعالم")]
+    // "שלום 2024 עולם", whose number is drawn left to right
+    #[case::hebrew_with_number("םלוע 2024 םולש", "שלום 2024 עולם")]
+    #[case::english_with_hebrew_word("Say םולש now", "Say שלום now")]
+    #[case::ltr_only("Hello world", "Hello world")]
+    fn test_visual_to_logical(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(visual_to_logical(text), expected);
+    }
+
+    #[rstest]
+    fn test_normalize_line() {
+        assert_eq!(normalize_line("第 1 章 概 要"), "第 1 章概要");
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::{Cursor, Read, Write};
 
 use quick_xml::Reader;
@@ -5,8 +6,250 @@ use quick_xml::events::Event;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::media::{data_uri, image_markdown, input_stem, write_media};
+use crate::options::{ConvertOptions, Notes, NotesStyle};
+use crate::warnings;
 
-pub struct PowerPointConverter;
+use self::animations::Timeline;
+use self::comments::Comment;
+use self::layout::{GroupTransform, Placeholder, Point};
+use super::anchors::Slugger;
+
+mod animations;
+mod comments;
+mod diagrams;
+mod layout;
+
+#[derive(Default)]
+pub struct PowerPointConverter {
+    options: ConvertOptions,
+}
+
+impl PowerPointConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+
+    /// The link target for the picture behind relationship `id`: the
+    /// written file with a media directory, a data URI with `--embed-media`,
+    /// or the URL of a linked picture. Empty when none applies.
+    fn image_link(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        rels: &HashMap<String, Relationship>,
+        id: &str,
+    ) -> Result<String> {
+        let Some(rel) = rels.get(id) else {
+            return Ok(String::new());
+        };
+        if rel.external {
+            return Ok(rel.target.clone());
+        }
+        let media = &self.options.media;
+        if media.extract_dir.is_none() && !media.embed {
+            return Ok(String::new());
+        }
+
+        let path = resolve_target("ppt/slides", &rel.target);
+        let Ok(bytes) = read_bytes(archive, &path) else {
+            warnings::warn(format!("Image {path} is missing from the presentation"));
+            return Ok(String::new());
+        };
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+        match &media.extract_dir {
+            Some(dir) => {
+                let stem = input_stem(self.options.input_name.as_deref(), "presentation");
+                write_media(
+                    dir,
+                    &format!("{stem}-{file_name}"),
+                    &bytes,
+                    media.link_base.as_deref(),
+                )
+            }
+            None => Ok(data_uri(file_name, &bytes)),
+        }
+    }
+}
+
+impl PowerPointConverter {
+    /// Write the text of the SmartArt diagram behind relationship `id` as a
+    /// nested list.
+    fn write_diagram(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        rels: &HashMap<String, Relationship>,
+        id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let Some(rel) = rels.get(id).filter(|rel| !rel.external) else {
+            return Ok(());
+        };
+        let path = resolve_target("ppt/slides", &rel.target);
+        let Ok(xml) = read_entry(archive, &path) else {
+            warnings::warn(format!("Diagram {path} is missing from the presentation"));
+            return Ok(());
+        };
+        let items = diagrams::outline(&xml).map_err(|e| e.in_entry(&path))?;
+        if items.is_empty() {
+            return Ok(());
+        }
+        let paragraphs: Vec<Paragraph> = items
+            .into_iter()
+            .map(|(level, text)| Paragraph {
+                runs: vec![TextRun {
+                    text,
+                    ..Default::default()
+                }],
+                level,
+                auto_number: None,
+            })
+            .collect();
+        write_list(writer, &paragraphs)
+    }
+
+    /// Write a slide's content after its title: shapes, tables and, when
+    /// enabled, its animation timeline.
+    fn write_slide_body(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        rels: &HashMap<String, Relationship>,
+        content: &SlideContent,
+        xml: &str,
+        title_written: bool,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let start = if title_written { 1 } else { 0 };
+        let content_shapes: Vec<_> = content.shapes[start..]
+            .iter()
+            .filter(|s| !s.paragraphs.is_empty() || s.picture.is_some() || s.diagram.is_some())
+            .collect();
+
+        if content_shapes.is_empty() && content.tables.is_empty() && !title_written {
+            writeln!(writer, "*Empty slide*")?;
+        }
+
+        for shape in &content_shapes {
+            if let Some(rel) = &shape.diagram {
+                self.write_diagram(archive, rels, rel, writer)?;
+            } else if let Some(picture) = &shape.picture {
+                let link = self.image_link(archive, rels, &picture.rel)?;
+                writeln!(writer, "{}", image_markdown(&picture.alt, &link))?;
+                writeln!(writer)?;
+            } else if shape.is_subtitle {
+                let text = join_paragraphs_inline(&shape.paragraphs);
+                if !text.is_empty() {
+                    writeln!(writer, "## {text}")?;
+                    writeln!(writer)?;
+                }
+            } else if shape.has_bullets {
+                write_list(writer, &shape.paragraphs)?;
+            } else {
+                for para in &shape.paragraphs {
+                    let text = render_paragraph(para);
+                    let text = text.trim();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    writeln!(writer, "{text}")?;
+                    writeln!(writer)?;
+                }
+            }
+        }
+
+        // Write tables
+        for table in &content.tables {
+            write_table(writer, table)?;
+            writeln!(writer)?;
+        }
+
+        if self.options.powerpoint.animations {
+            write_timeline(writer, &Timeline::parse(xml))?;
+        }
+        Ok(())
+    }
+
+    /// The text of the speaker notes of `slide_name`, one paragraph per
+    /// line, or `None` when it has none.
+    fn read_notes(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        slide_name: &str,
+        links: Links,
+    ) -> Result<Option<String>> {
+        let notes_name = slide_name.replace("ppt/slides/slide", "ppt/notesSlides/notesSlide");
+        let Ok(notes_xml) = read_entry(archive, &notes_name) else {
+            return Ok(None);
+        };
+        let mut notes_content =
+            extract_slide_content(&notes_xml).map_err(|e| e.in_entry(&notes_name))?;
+        let notes_rels = read_rels(archive, &notes_name);
+        Links {
+            rels: &notes_rels,
+            base: "ppt/notesSlides",
+            ..links
+        }
+        .resolve(&mut notes_content.shapes);
+        let notes_text: String = notes_content
+            .shapes
+            .iter()
+            .flat_map(|s| &s.paragraphs)
+            .map(render_paragraph)
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty() && !s.chars().all(|c| c.is_ascii_digit()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok((!notes_text.is_empty()).then_some(notes_text))
+    }
+
+    /// Positions of the placeholders a slide inherits, from its layout and,
+    /// for those the layout does not place, the slide master.
+    fn layout_positions(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        rels: &HashMap<String, Relationship>,
+    ) -> HashMap<String, Point> {
+        let mut positions = HashMap::new();
+        let Some(layout) = related_part(rels, "ppt/slides", "ppt/slideLayouts/") else {
+            return positions;
+        };
+        let layout_rels = read_rels(archive, &layout);
+        if let Some(master) = related_part(&layout_rels, "ppt/slideLayouts", "ppt/slideMasters/")
+            && let Ok(xml) = read_entry(archive, &master)
+        {
+            positions = layout::placeholder_positions(&xml);
+        }
+        if let Ok(xml) = read_entry(archive, &layout) {
+            positions.extend(layout::placeholder_positions(&xml));
+        }
+        positions
+    }
+
+    /// The reviewer comments on a slide, from the comment parts its
+    /// relationships point to.
+    fn read_comments(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        rels: &HashMap<String, Relationship>,
+        authors: &HashMap<String, String>,
+    ) -> Result<Vec<Comment>> {
+        let mut parts: Vec<String> = rels
+            .values()
+            .filter(|rel| !rel.external)
+            .map(|rel| resolve_target("ppt/slides", &rel.target))
+            .filter(|path| path.starts_with("ppt/comments/"))
+            .collect();
+        parts.sort();
+        let mut slide_comments = Vec::new();
+        for path in parts {
+            let Ok(xml) = read_entry(archive, &path) else {
+                warnings::warn(format!("Comments {path} are missing from the presentation"));
+                continue;
+            };
+            slide_comments.extend(comments::parse(&xml, authors).map_err(|e| e.in_entry(&path))?);
+        }
+        Ok(slide_comments)
+    }
+}
 
 impl Converter for PowerPointConverter {
     fn format_name(&self) -> &'static str {
@@ -37,16 +280,105 @@ impl Converter for PowerPointConverter {
                 .unwrap_or(0)
         });
 
+        let selection = self.options.powerpoint.slides.as_ref();
+        if let Some(selection) = selection
+            && selection.first() > slide_names.len()
+        {
+            warnings::warn(format!(
+                "No slides selected: the presentation has {} slides",
+                slide_names.len()
+            ));
+        }
+
+        // Each selected slide with its index in the deck
+        let mut slides = Vec::new();
         for (idx, slide_name) in slide_names.iter().enumerate() {
-            let xml = read_entry(&mut archive, slide_name)?;
-            let content = extract_slide_content(&xml)?;
+            if selection.is_some_and(|s| !s.contains(idx + 1)) {
+                continue;
+            }
+            let slide = read_entry(&mut archive, slide_name).and_then(|xml| {
+                let content = extract_slide_content(&xml).map_err(|e| e.in_entry(slide_name))?;
+                Ok((xml, content))
+            });
+            match slide {
+                Err(e) if !self.options.best_effort => return Err(e),
+                slide => slides.push((idx, slide)),
+            }
+        }
+
+        // Heading anchors of each converted slide, for links that jump
+        // between slides
+        let mut slugger = Slugger::default();
+        let anchors: Vec<String> = slides
+            .iter()
+            .map(|(idx, slide)| {
+                let Ok((_, content)) = slide else {
+                    return slugger.slug(&format!("Slide {}", idx + 1));
+                };
+                let anchor = match content.shapes.first() {
+                    Some(first) if first.is_title => slugger.slug(&plain_text(&first.paragraphs)),
+                    _ => slugger.slug(&format!("Slide {}", idx + 1)),
+                };
+                // Subtitles are headings too, so they take part in deduplication
+                for shape in content.shapes.iter().filter(|s| s.is_subtitle) {
+                    slugger.slug(&plain_text(&shape.paragraphs));
+                }
+                anchor
+            })
+            .collect();
+        let slide_anchors: HashMap<&str, &str> = slides
+            .iter()
+            .map(|(idx, _)| slide_names[*idx].as_str())
+            .zip(anchors.iter().map(String::as_str))
+            .collect();
+
+        // Footnote-style notes are written after the last slide
+        let mut footnotes: Vec<(usize, String)> = Vec::new();
 
-            if idx > 0 {
+        // Legacy and modern comments keep their authors in separate parts
+        let mut authors = HashMap::new();
+        if self.options.powerpoint.comments {
+            for name in ["ppt/commentAuthors.xml", "ppt/authors.xml"] {
+                if let Ok(xml) = read_entry(&mut archive, name) {
+                    authors.extend(comments::parse_authors(&xml));
+                }
+            }
+        }
+
+        for (written, (idx, slide)) in slides.into_iter().enumerate() {
+            let slide_name = &slide_names[idx];
+            if written > 0 {
                 writeln!(writer)?;
                 writeln!(writer, "---")?;
                 writeln!(writer)?;
             }
 
+            let (xml, mut content) = match slide {
+                Ok(slide) => slide,
+                Err(e) => {
+                    let section = format!("Slide {}", idx + 1);
+                    writeln!(writer, "# {section}")?;
+                    writeln!(writer)?;
+                    writeln!(writer, "{}", warnings::skipped_section(&section, &e))?;
+                    writeln!(writer)?;
+                    continue;
+                }
+            };
+            let rels = read_rels(&mut archive, slide_name);
+            let links = Links {
+                rels: &rels,
+                base: "ppt/slides",
+                slides: &slide_names,
+                anchors: &slide_anchors,
+                current: idx,
+            };
+            links.resolve(&mut content.shapes);
+            if self.options.powerpoint.reading_order {
+                let positions = self.layout_positions(&mut archive, &rels);
+                let shapes = std::mem::take(&mut content.shapes);
+                content.shapes = layout::reading_order(shapes, &positions);
+            }
+
             // Use first shape as slide title if it looks like a title
             let mut title_written = false;
             if let Some(first) = content.shapes.first()
@@ -62,71 +394,85 @@ impl Converter for PowerPointConverter {
                 writeln!(writer)?;
             }
 
-            let start = if title_written { 1 } else { 0 };
-            let content_shapes: Vec<_> = content.shapes[start..]
-                .iter()
-                .filter(|s| !s.paragraphs.is_empty())
-                .collect();
-
-            if content_shapes.is_empty() && content.tables.is_empty() && !title_written {
-                writeln!(writer, "*Empty slide*")?;
+            let notes_only = self.options.powerpoint.notes == Notes::Only;
+            if !notes_only {
+                self.write_slide_body(&mut archive, &rels, &content, &xml, title_written, writer)?;
             }
 
-            for shape in &content_shapes {
-                if shape.is_subtitle {
-                    let text = join_paragraphs_inline(&shape.paragraphs);
-                    if !text.is_empty() {
-                        writeln!(writer, "## {text}")?;
-                        writeln!(writer)?;
-                    }
-                } else {
-                    for para in &shape.paragraphs {
-                        let text = render_paragraph(para);
-                        let text = text.trim();
-                        if text.is_empty() {
-                            continue;
-                        }
-
-                        if shape.has_bullets {
-                            writeln!(writer, "- {text}")?;
-                        } else {
-                            writeln!(writer, "{text}")?;
+            // Speaker notes
+            let notes = match self.options.powerpoint.notes {
+                Notes::Exclude => Ok(None),
+                Notes::Include | Notes::Only => self.read_notes(&mut archive, slide_name, links),
+            };
+            let notes_text = match notes {
+                Ok(notes) => notes,
+                Err(e) if self.options.best_effort => {
+                    let section = format!("Speaker notes of slide {}", idx + 1);
+                    writeln!(writer, "{}", warnings::skipped_section(&section, &e))?;
+                    writeln!(writer)?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if notes_only {
+                match notes_text {
+                    Some(text) => {
+                        for line in text.lines() {
+                            writeln!(writer, "{line}")?;
                             writeln!(writer)?;
                         }
                     }
-                    if shape.has_bullets {
+                    None => {
+                        writeln!(writer, "*No speaker notes*")?;
                         writeln!(writer)?;
                     }
                 }
+                continue;
             }
-
-            // Write tables
-            for table in &content.tables {
-                write_table(writer, table)?;
+            if let Some(notes_text) = notes_text {
+                match self.options.powerpoint.notes_style {
+                    NotesStyle::Blockquote => {
+                        writeln!(writer, "> **Notes**: {notes_text}")?;
+                    }
+                    NotesStyle::Details => {
+                        writeln!(writer, "<details>")?;
+                        writeln!(writer, "<summary>Notes</summary>")?;
+                        writeln!(writer)?;
+                        writeln!(writer, "{notes_text}")?;
+                        writeln!(writer)?;
+                        writeln!(writer, "</details>")?;
+                    }
+                    NotesStyle::Footnote => {
+                        writeln!(writer, "*Speaker notes*[^slide-{}]", idx + 1)?;
+                        footnotes.push((idx + 1, notes_text));
+                    }
+                }
                 writeln!(writer)?;
             }
 
-            // Speaker notes
-            let notes_name =
-                slide_name.replace("ppt/slides/slide", "ppt/notesSlides/notesSlide");
-            if let Ok(notes_xml) = read_entry(&mut archive, &notes_name) {
-                let notes_content = extract_slide_content(&notes_xml)?;
-                let notes_text: String = notes_content
-                    .shapes
-                    .iter()
-                    .flat_map(|s| &s.paragraphs)
-                    .map(render_paragraph)
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty() && !s.chars().all(|c| c.is_ascii_digit()))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                if !notes_text.is_empty() {
-                    writeln!(writer, "> **Notes**: {notes_text}")?;
-                    writeln!(writer)?;
+            if self.options.powerpoint.comments {
+                match self.read_comments(&mut archive, &rels, &authors) {
+                    Ok(comments) => write_comments(writer, &comments)?,
+                    Err(e) if self.options.best_effort => {
+                        let section = format!("Comments on slide {}", idx + 1);
+                        writeln!(writer, "{}", warnings::skipped_section(&section, &e))?;
+                        writeln!(writer)?;
+                    }
+                    Err(e) => return Err(e),
                 }
             }
         }
 
+        if !footnotes.is_empty() {
+            writeln!(writer)?;
+            writeln!(writer, "---")?;
+            writeln!(writer)?;
+            for (slide, text) in &footnotes {
+                // Continuation lines are indented to stay in the footnote
+                writeln!(writer, "[^slide-{slide}]: {}", text.replace('\n', "\n    "))?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -141,23 +487,132 @@ struct SlideShape {
     is_title: bool,
     is_subtitle: bool,
     has_bullets: bool,
+    picture: Option<Picture>,
+    /// Relationship id of a SmartArt diagram's data part.
+    diagram: Option<String>,
+    /// Top-left corner on the slide, unless inherited from the layout.
+    position: Option<Point>,
+    placeholder: Option<Placeholder>,
+}
+
+/// A `p:pic` shape, with the relationship id of its image.
+#[derive(Default)]
+struct Picture {
+    alt: String,
+    rel: String,
 }
 
+#[derive(Default)]
 struct Paragraph {
     runs: Vec<TextRun>,
+    /// Indentation level from `a:pPr lvl`, 0 for the outermost.
+    level: usize,
+    auto_number: Option<AutoNumber>,
 }
 
+/// An `a:buAutoNum` bullet. Markdown only has Arabic numerals, so lettered
+/// and Roman schemes are numbered too.
+struct AutoNumber {
+    start: u32,
+    /// `1)` rather than `1.`
+    paren: bool,
+}
+
+#[derive(Default)]
 struct TextRun {
     text: String,
     bold: bool,
     italic: bool,
+    link: Option<Hyperlink>,
+    /// Where `link` points once resolved: a URL, or `#anchor` of a slide.
+    href: Option<String>,
+}
+
+/// An `a:hlinkClick` on a run, before its relationship is resolved.
+#[derive(Default)]
+struct Hyperlink {
+    rel: String,
+    /// Such as `ppaction://hlinksldjump` for a jump to another slide.
+    action: String,
+}
+
+/// What run hyperlinks on one slide (or its notes) resolve against.
+#[derive(Clone, Copy)]
+struct Links<'a> {
+    rels: &'a HashMap<String, Relationship>,
+    /// Package directory of the part the relationships belong to.
+    base: &'a str,
+    slides: &'a [String],
+    anchors: &'a HashMap<&'a str, &'a str>,
+    /// Index of the slide being written, for next/previous slide jumps.
+    current: usize,
+}
+
+impl Links<'_> {
+    fn resolve(&self, shapes: &mut [SlideShape]) {
+        for run in shapes
+            .iter_mut()
+            .flat_map(|s| &mut s.paragraphs)
+            .flat_map(|p| &mut p.runs)
+        {
+            run.href = run.link.as_ref().and_then(|link| self.href(link));
+        }
+    }
+
+    /// The link target of `link`, or `None` for links with nowhere to go in
+    /// Markdown (such as starting a program or ending the show).
+    fn href(&self, link: &Hyperlink) -> Option<String> {
+        let slide = if let Some(jump) = link.action.strip_prefix("ppaction://hlinkshowjump?jump=") {
+            let last = self.slides.len().checked_sub(1)?;
+            match jump {
+                "firstslide" => 0,
+                "lastslide" => last,
+                "nextslide" if self.current < last => self.current + 1,
+                "previousslide" => self.current.checked_sub(1)?,
+                _ => return None,
+            }
+        } else {
+            let rel = self.rels.get(&link.rel)?;
+            if link.action.starts_with("ppaction://hlinksldjump") {
+                let target = resolve_target(self.base, &rel.target);
+                self.slides.iter().position(|name| *name == target)?
+            } else if rel.external || link.action.is_empty() {
+                return Some(rel.target.clone());
+            } else {
+                return None;
+            }
+        };
+        let anchor = self.anchors.get(self.slides[slide].as_str())?;
+        Some(format!("#{anchor}"))
+    }
 }
 
 fn render_paragraph(para: &Paragraph) -> String {
-    para.runs
+    let mut out = String::new();
+    let mut runs = para.runs.iter().peekable();
+    while let Some(run) = runs.next() {
+        let text = format_run_text(&run.text, run.bold, run.italic);
+        let Some(href) = &run.href else {
+            out.push_str(&text);
+            continue;
+        };
+        // Formatting changes split a link into several runs
+        let mut text = text;
+        while let Some(next) = runs.next_if(|next| next.href.as_ref() == Some(href)) {
+            text.push_str(&format_run_text(&next.text, next.bold, next.italic));
+        }
+        out.push_str(&format!("[{text}]({href})"));
+    }
+    out
+}
+
+/// Paragraph text without formatting or links, as heading anchors see it.
+fn plain_text(paragraphs: &[Paragraph]) -> String {
+    paragraphs
         .iter()
-        .map(|run| format_run_text(&run.text, run.bold, run.italic))
-        .collect::<String>()
+        .map(|p| p.runs.iter().map(|r| r.text.as_str()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 fn join_paragraphs_inline(paragraphs: &[Paragraph]) -> String {
@@ -196,15 +651,20 @@ fn extract_slide_content(xml: &str) -> Result<SlideContent> {
     let mut in_table_row = false;
     let mut in_table_cell = false;
 
-    let mut current_run = TextRun {
-        text: String::new(),
-        bold: false,
-        italic: false,
-    };
-    let mut current_paragraph = Paragraph { runs: Vec::new() };
+    let mut current_run = TextRun::default();
+    let mut current_paragraph = Paragraph::default();
     let mut paragraphs: Vec<Paragraph> = Vec::new();
     let mut shape_type = String::new();
     let mut has_bullets = false;
+    let mut picture: Option<Picture> = None;
+    let mut placeholder: Option<Placeholder> = None;
+    let mut position: Option<Point> = None;
+
+    // Transforms of the groups being read, outermost first, and whether
+    // an `a:xfrm` belongs to the innermost group rather than a shape
+    let mut groups: Vec<GroupTransform> = Vec::new();
+    let mut in_group_props = false;
+    let mut frame_position: Option<Point> = None;
 
     let mut table_rows: Vec<Vec<String>> = Vec::new();
     let mut table_row: Vec<String> = Vec::new();
@@ -215,25 +675,33 @@ fn extract_slide_content(xml: &str) -> Result<SlideContent> {
             Ok(Event::Start(e)) => {
                 let local = local_name(e.name().as_ref());
                 match local.as_str() {
+                    // Shapes inside `grpSp` groups, however deeply nested,
+                    // are read like top-level ones, their positions mapped
+                    // onto the slide through the groups' transforms
                     "sp" | "pic" if !in_table => {
                         in_shape = true;
                         paragraphs.clear();
                         shape_type.clear();
                         has_bullets = false;
+                        picture = (local == "pic").then(Picture::default);
+                        placeholder = None;
+                        position = None;
                     }
+                    "grpSp" if !in_table => groups.push(GroupTransform::default()),
+                    "grpSpPr" => in_group_props = true,
+                    "graphicFrame" => frame_position = None,
                     "txBody" => in_text_body = true,
                     "p" if in_text_body => {
                         in_paragraph = true;
-                        current_paragraph = Paragraph { runs: Vec::new() };
+                        current_paragraph = Paragraph::default();
+                    }
+                    "pPr" if in_paragraph => {
+                        in_ppr = true;
+                        read_level(&e, &mut current_paragraph, &mut has_bullets);
                     }
-                    "pPr" if in_paragraph => in_ppr = true,
                     "r" if in_paragraph => {
                         in_run = true;
-                        current_run = TextRun {
-                            text: String::new(),
-                            bold: false,
-                            italic: false,
-                        };
+                        current_run = TextRun::default();
                     }
                     "rPr" if in_run => {
                         in_rpr = true;
@@ -253,6 +721,8 @@ fn extract_slide_content(xml: &str) -> Result<SlideContent> {
                         }
                     }
                     "t" if in_run => in_text = true,
+                    "hlinkClick" if in_rpr => current_run.link = Some(read_hyperlink(&e)),
+                    "cNvPr" | "blip" => read_picture_attrs(&e, picture.as_mut()),
                     "tbl" => {
                         in_table = true;
                         table_rows.clear();
@@ -281,8 +751,55 @@ fn extract_slide_content(xml: &str) -> Result<SlideContent> {
                         if shape_type.is_empty() {
                             shape_type = "body".to_string();
                         }
+                        placeholder = Some(layout::read_placeholder(&e));
+                    }
+                    "off" | "ext" | "chOff" | "chExt" if in_group_props => {
+                        if let Some(group) = groups.last_mut() {
+                            group.read(&e);
+                        }
                     }
-                    "buChar" | "buAutoNum" | "buFont" if in_ppr => {
+                    "off" if in_shape => {
+                        position = position.or_else(|| layout::offset(&e, &groups));
+                    }
+                    "off" => frame_position = layout::offset(&e, &groups),
+                    "cNvPr" | "blip" => read_picture_attrs(&e, picture.as_mut()),
+                    "hlinkClick" if in_rpr => current_run.link = Some(read_hyperlink(&e)),
+                    // SmartArt, referenced from a graphic frame
+                    "relIds" => {
+                        if let Some(rel) = e
+                            .attributes()
+                            .flatten()
+                            .find(|a| local_name(a.key.as_ref()) == "dm")
+                        {
+                            shapes.push(SlideShape {
+                                paragraphs: Vec::new(),
+                                is_title: false,
+                                is_subtitle: false,
+                                has_bullets: false,
+                                picture: None,
+                                diagram: Some(String::from_utf8_lossy(&rel.value).to_string()),
+                                position: frame_position,
+                                placeholder: None,
+                            });
+                        }
+                    }
+                    "pPr" if in_paragraph => {
+                        read_level(&e, &mut current_paragraph, &mut has_bullets);
+                    }
+                    "buAutoNum" if in_ppr => {
+                        has_bullets = true;
+                        let attr = |name: &[u8]| {
+                            e.attributes()
+                                .flatten()
+                                .find(|a| a.key.as_ref() == name)
+                                .map(|a| String::from_utf8_lossy(&a.value).to_string())
+                        };
+                        current_paragraph.auto_number = Some(AutoNumber {
+                            start: attr(b"startAt").and_then(|v| v.parse().ok()).unwrap_or(1),
+                            paren: attr(b"type").is_some_and(|t| t.contains("Paren")),
+                        });
+                    }
+                    "buChar" | "buFont" if in_ppr => {
                         has_bullets = true;
                     }
                     "rPr" if in_run => {
@@ -316,7 +833,9 @@ fn extract_slide_content(xml: &str) -> Result<SlideContent> {
                 let local = local_name(e.name().as_ref());
                 match local.as_str() {
                     "sp" | "pic" if !in_table => {
-                        if in_shape && !paragraphs.is_empty() {
+                        // Pictures without an image (such as placeholders) are dropped
+                        let picture = picture.take().filter(|p| !p.rel.is_empty());
+                        if in_shape && (!paragraphs.is_empty() || picture.is_some()) {
                             let is_title = matches!(
                                 shape_type.as_str(),
                                 "title" | "ctrTitle"
@@ -330,31 +849,31 @@ fn extract_slide_content(xml: &str) -> Result<SlideContent> {
                                 is_title,
                                 is_subtitle,
                                 has_bullets,
+                                picture,
+                                diagram: None,
+                                position,
+                                placeholder: placeholder.take(),
                             });
                         }
                         in_shape = false;
                     }
+                    "grpSp" if !in_table => {
+                        groups.pop();
+                    }
+                    "grpSpPr" => in_group_props = false,
                     "txBody" => in_text_body = false,
                     "p" if in_text_body && !in_table_cell => {
                         if in_paragraph && !current_paragraph.runs.is_empty() {
-                            paragraphs.push(std::mem::replace(
-                                &mut current_paragraph,
-                                Paragraph { runs: Vec::new() },
-                            ));
+                            paragraphs.push(std::mem::take(&mut current_paragraph));
                         }
                         in_paragraph = false;
                     }
                     "pPr" => in_ppr = false,
                     "r" if !in_table_cell => {
                         if in_run && !current_run.text.is_empty() {
-                            current_paragraph.runs.push(std::mem::replace(
-                                &mut current_run,
-                                TextRun {
-                                    text: String::new(),
-                                    bold: false,
-                                    italic: false,
-                                },
-                            ));
+                            current_paragraph
+                                .runs
+                                .push(std::mem::take(&mut current_run));
                         }
                         in_run = false;
                         in_rpr = false;
@@ -383,21 +902,262 @@ fn extract_slide_content(xml: &str) -> Result<SlideContent> {
             }
             Ok(Event::Eof) => break,
             Err(e) => {
-                return Err(Error::Conversion {
+                return Err(Error::Parse {
                     format: "powerpoint",
-                    message: format!("Failed to parse slide XML: {e}"),
+                    entry: None,
+                    offset: Some(reader.error_position()),
+                    message: e.to_string(),
                 });
             }
             _ => {}
         }
     }
 
-    // Suppress unused variable warnings
-    let _ = in_rpr;
-
     Ok(SlideContent { shapes, tables })
 }
 
+/// Read a paragraph's indentation level. Indented paragraphs are nested
+/// bullets, even when the bullet itself is inherited from the slide layout.
+fn read_level(
+    e: &quick_xml::events::BytesStart,
+    paragraph: &mut Paragraph,
+    has_bullets: &mut bool,
+) {
+    let level = e
+        .attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == b"lvl")
+        .and_then(|a| String::from_utf8_lossy(&a.value).parse().ok())
+        .unwrap_or(0);
+    if level > 0 {
+        paragraph.level = level;
+        *has_bullets = true;
+    }
+}
+
+/// Write a bulleted shape as a Markdown list, nesting paragraphs by level
+/// and numbering auto-numbered ones.
+fn write_list(writer: &mut dyn Write, paragraphs: &[Paragraph]) -> Result<()> {
+    // Column where the text of the latest item at each level starts, which
+    // is where its nested items have to be indented to
+    let mut columns: Vec<usize> = Vec::new();
+    // Next number at each level, while a numbered list is running there
+    let mut numbers: Vec<Option<u32>> = Vec::new();
+    for para in paragraphs {
+        let text = render_paragraph(para);
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        // A level can only be one deeper than the item before it
+        let level = para.level.min(columns.len());
+        columns.truncate(level);
+        numbers.resize(level + 1, None);
+        let indent = columns.last().copied().unwrap_or(0);
+        let marker = match &para.auto_number {
+            Some(auto) => {
+                let n = numbers[level].unwrap_or(auto.start);
+                numbers[level] = Some(n + 1);
+                format!("{n}{}", if auto.paren { ')' } else { '.' })
+            }
+            None => {
+                numbers[level] = None;
+                "-".to_string()
+            }
+        };
+        writeln!(writer, "{:indent$}{marker} {text}", "")?;
+        columns.push(indent + marker.len() + 1);
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Pick up a picture's alt text from its `p:cNvPr`, preferring the
+/// description over the title, and its image from `a:blip` (`r:embed`, or
+/// `r:link` for linked pictures).
+fn read_picture_attrs(e: &quick_xml::events::BytesStart, picture: Option<&mut Picture>) {
+    let Some(picture) = picture else {
+        return;
+    };
+    let mut descr = None;
+    let mut title = None;
+    for attr in e.attributes().flatten() {
+        let value = attr
+            .normalized_value(quick_xml::XmlVersion::Implicit1_0)
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default();
+        if value.is_empty() {
+            continue;
+        }
+        match local_name(attr.key.as_ref()).as_str() {
+            "descr" => descr = Some(value),
+            "title" => title = Some(value),
+            "embed" | "link" if picture.rel.is_empty() => picture.rel = value,
+            _ => {}
+        }
+    }
+    if let Some(text) = descr.or(title)
+        && picture.alt.is_empty()
+    {
+        picture.alt = text;
+    }
+}
+
+/// Read an `a:hlinkClick`'s relationship id and action.
+fn read_hyperlink(e: &quick_xml::events::BytesStart) -> Hyperlink {
+    let mut link = Hyperlink::default();
+    for attr in e.attributes().flatten() {
+        let value = String::from_utf8_lossy(&attr.value).to_string();
+        match local_name(attr.key.as_ref()).as_str() {
+            "id" => link.rel = value,
+            "action" => link.action = value,
+            _ => {}
+        }
+    }
+    link
+}
+
+struct Relationship {
+    target: String,
+    external: bool,
+}
+
+fn parse_relationships(xml: &str) -> HashMap<String, Relationship> {
+    let mut rels = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e))
+                if local_name(e.name().as_ref()) == "Relationship" =>
+            {
+                let mut id = None;
+                let mut target = None;
+                let mut external = false;
+                for attr in e.attributes().flatten() {
+                    let value = String::from_utf8_lossy(&attr.value).to_string();
+                    match attr.key.as_ref() {
+                        b"Id" => id = Some(value),
+                        b"Target" => target = Some(value),
+                        b"TargetMode" => external = value == "External",
+                        _ => {}
+                    }
+                }
+                if let (Some(id), Some(target)) = (id, target) {
+                    rels.insert(id, Relationship { target, external });
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    rels
+}
+
+/// The relationships of a slide or notes part, empty when it has none.
+fn read_rels(
+    archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+    part: &str,
+) -> HashMap<String, Relationship> {
+    let (dir, file) = part.rsplit_once('/').unwrap_or(("", part));
+    read_entry(archive, &format!("{dir}/_rels/{file}.rels"))
+        .map(|xml| parse_relationships(&xml))
+        .unwrap_or_default()
+}
+
+/// The first part under `dir` that a part in `base` is related to.
+fn related_part(rels: &HashMap<String, Relationship>, base: &str, dir: &str) -> Option<String> {
+    rels.values()
+        .filter(|rel| !rel.external)
+        .map(|rel| resolve_target(base, &rel.target))
+        .filter(|path| path.starts_with(dir))
+        .min()
+}
+
+/// Resolve a relationship target against the package directory of the
+/// part it belongs to, such as `../media/image1.png` from `ppt/slides`.
+fn resolve_target(base: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = base.split('/').filter(|p| !p.is_empty()).collect();
+    for part in target.split('/') {
+        match part {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// List a slide's comments with their authors and dates, replies nested
+/// below the comment they answer.
+fn write_comments(writer: &mut dyn Write, comments: &[Comment]) -> Result<()> {
+    if comments.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "**Comments**")?;
+    writeln!(writer)?;
+    for comment in comments {
+        writeln!(writer, "- {}", describe_comment(comment))?;
+        for reply in &comment.replies {
+            writeln!(writer, "  - {}", describe_comment(reply))?;
+        }
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn describe_comment(comment: &Comment) -> String {
+    let mut item = match &comment.author {
+        Some(author) => format!("**{author}**"),
+        None => String::new(),
+    };
+    if let Some(date) = &comment.date {
+        item.push_str(&format!(" ({date})"));
+    }
+    let item = item.trim_start();
+    if item.is_empty() {
+        comment.text.clone()
+    } else {
+        format!("{item}: {}", comment.text)
+    }
+}
+
+/// Summarize a slide's transition and numbered animation sequence.
+fn write_timeline(writer: &mut dyn Write, timeline: &Timeline) -> Result<()> {
+    if let Some(transition) = &timeline.transition {
+        writeln!(writer, "**Transition:** {}", transition.describe())?;
+        writeln!(writer)?;
+    }
+    let steps = timeline.steps();
+    if steps.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "**Animations:**")?;
+    writeln!(writer)?;
+    let mut rows = vec![vec![
+        "#".to_string(),
+        "Start".to_string(),
+        "Effect".to_string(),
+        "Shape".to_string(),
+    ]];
+    rows.extend(steps.into_iter().map(|(step, trigger, effect, shape)| {
+        vec![
+            step.to_string(),
+            trigger.to_string(),
+            effect,
+            shape.to_string(),
+        ]
+    }));
+    write_table(writer, &rows)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
 fn write_table(writer: &mut dyn Write, rows: &[Vec<String>]) -> Result<()> {
     if rows.is_empty() {
         return Ok(());
@@ -438,15 +1198,25 @@ fn write_table(writer: &mut dyn Write, rows: &[Vec<String>]) -> Result<()> {
 }
 
 fn read_entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
-    let mut file = archive.by_name(name).map_err(|e| Error::Conversion {
+    let mut file = archive.by_name(name).map_err(|_| Error::MissingEntry {
         format: "powerpoint",
-        message: format!("Entry not found: {name}: {e}"),
+        entry: name.to_string(),
     })?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
     Ok(content)
 }
 
+fn read_bytes(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>> {
+    let mut file = archive.by_name(name).map_err(|_| Error::MissingEntry {
+        format: "powerpoint",
+        entry: name.to_string(),
+    })?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
 fn local_name(name: &[u8]) -> String {
     let s = std::str::from_utf8(name).unwrap_or("");
     if let Some(pos) = s.rfind(':') {
@@ -456,10 +1226,18 @@ fn local_name(name: &[u8]) -> String {
     }
 }
 
+pub(super) fn attr(e: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == name)
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::converter::Converter;
+    use crate::options::{MediaOptions, PowerPointOptions, SlideRanges};
     use rstest::rstest;
     use std::io::Write;
 
@@ -556,12 +1334,334 @@ mod tests {
     }
 
     fn convert(pptx_bytes: &[u8]) -> String {
-        let converter = PowerPointConverter;
+        convert_with(pptx_bytes, NotesStyle::default())
+    }
+
+    fn convert_with(pptx_bytes: &[u8], notes_style: NotesStyle) -> String {
+        let mut options = ConvertOptions::default();
+        options.powerpoint.notes_style = notes_style;
+        let converter = PowerPointConverter::new(options);
         let mut output = Vec::new();
         converter.convert(pptx_bytes, &mut output).unwrap();
         String::from_utf8(output).unwrap()
     }
 
+    fn notes_xml(lines: &[&str]) -> String {
+        let paras: String = lines
+            .iter()
+            .map(|t| format!("<a:p><a:r><a:t>{t}</a:t></a:r></a:p>"))
+            .collect();
+        slide_xml(&format!(
+            r#"<p:sp><p:nvSpPr><p:nvPr><p:ph type="body"/></p:nvPr></p:nvSpPr>
+<p:txBody>{paras}</p:txBody></p:sp>"#
+        ))
+    }
+
+    #[rstest]
+    #[case::blockquote(
+        NotesStyle::Blockquote,
+        "# Intro\n\n> **Notes**: Say hello\nThen wave\n\n\n---\n\n# Outro\n\n"
+    )]
+    #[case::details(
+        NotesStyle::Details,
+        "# Intro\n\n<details>\n<summary>Notes</summary>\n\nSay hello\nThen wave\n\n</details>\n\n\n---\n\n# Outro\n\n"
+    )]
+    #[case::footnote(
+        NotesStyle::Footnote,
+        "# Intro\n\n*Speaker notes*[^slide-1]\n\n\n---\n\n# Outro\n\n\n---\n\n\
+         [^slide-1]: Say hello\n    Then wave\n"
+    )]
+    fn test_notes_style(#[case] style: NotesStyle, #[case] expected: &str) {
+        let s1 = slide_xml(&title_shape("Intro"));
+        let s2 = slide_xml(&title_shape("Outro"));
+        let notes = notes_xml(&["Say hello", "Then wave", "1"]);
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &s1),
+            ("ppt/slides/slide2.xml", &s2),
+            ("ppt/notesSlides/notesSlide1.xml", &notes),
+        ]);
+        assert_eq!(convert_with(&pptx, style), expected);
+    }
+
+    #[rstest]
+    #[case::exclude(Notes::Exclude, "# Intro\n\nWelcome\n\n\n---\n\n# Outro\n\n")]
+    #[case::only(
+        Notes::Only,
+        "# Intro\n\nSay hello\n\nThen wave\n\n\n---\n\n# Outro\n\n*No speaker notes*\n\n"
+    )]
+    fn test_notes_selection(#[case] notes: Notes, #[case] expected: &str) {
+        let s1 = slide_xml(&format!(
+            "{}{}",
+            title_shape("Intro"),
+            body_shape("Welcome")
+        ));
+        let s2 = slide_xml(&title_shape("Outro"));
+        let notes_part = notes_xml(&["Say hello", "Then wave", "1"]);
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &s1),
+            ("ppt/slides/slide2.xml", &s2),
+            ("ppt/notesSlides/notesSlide1.xml", &notes_part),
+        ]);
+        let mut options = ConvertOptions::default();
+        options.powerpoint.notes = notes;
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    /// An effect node in a slide's main animation sequence.
+    fn effect(id: u32, class: &str, preset: u32, trigger: &str, spid: u32) -> String {
+        format!(
+            r#"<p:par><p:cTn id="{id}" presetID="{preset}" presetClass="{class}" nodeType="{trigger}"><p:childTnLst><p:set><p:cBhvr><p:tgtEl><p:spTgt spid="{spid}"/></p:tgtEl></p:cBhvr></p:set></p:childTnLst></p:cTn></p:par>"#
+        )
+    }
+
+    #[rstest]
+    fn test_animations() {
+        let shapes = r#"<p:sp><p:nvSpPr><p:cNvPr id="2" name="Title 1"/><p:nvPr><p:ph type="title"/></p:nvPr></p:nvSpPr>
+<p:txBody><a:p><a:r><a:t>Agenda</a:t></a:r></a:p></p:txBody></p:sp>
+<p:pic><p:nvPicPr><p:cNvPr id="3" name="Logo"/></p:nvPicPr></p:pic>"#;
+        let timing = format!(
+            r#"<p:transition spd="med"><p:wipe dir="d"/></p:transition><p:timing><p:tnLst><p:par><p:cTn id="1" nodeType="tmRoot"><p:childTnLst><p:seq><p:cTn id="2" nodeType="mainSeq"><p:childTnLst>{}{}{}</p:childTnLst></p:cTn></p:seq><p:seq><p:cTn id="9" nodeType="interactiveSeq"><p:childTnLst>{}</p:childTnLst></p:cTn></p:seq></p:childTnLst></p:cTn></p:par></p:tnLst></p:timing>"#,
+            effect(3, "entr", 2, "clickEffect", 2),
+            effect(4, "emph", 8, "withEffect", 3),
+            effect(5, "exit", 10, "clickEffect", 3),
+            effect(10, "entr", 10, "clickEffect", 2),
+        );
+        let xml = slide_xml(shapes).replace("</p:cSld>", &format!("</p:cSld>{timing}"));
+        let pptx = make_pptx(&[("ppt/slides/slide1.xml", &xml)]);
+
+        let mut options = ConvertOptions::default();
+        options.powerpoint.animations = true;
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Agenda\n\n\
+             **Transition:** Wipe (down, medium)\n\n\
+             **Animations:**\n\n\
+             | # | Start | Effect | Shape |\n\
+             |---|---|---|---|\n\
+             | 1 | On click | Entrance: Fly In | Agenda |\n\
+             | 1 | With previous | Emphasis: Spin | Logo |\n\
+             | 2 | On click | Exit: Fade | Logo |\n\n"
+        );
+        assert!(!convert(&pptx).contains("Animations"));
+    }
+
+    #[rstest]
+    fn test_comments() {
+        let rels = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments" Target="../comments/comment1.xml"/>
+</Relationships>"#;
+        let authors = r#"<p:cmAuthorLst xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"><p:cmAuthor id="1" name="Alice" initials="A" lastIdx="2" clrIdx="0"/></p:cmAuthorLst>"#;
+        let comments = r#"<p:cmLst xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"><p:cm authorId="1" dt="2024-05-01T10:15:00.000" idx="1"><p:pos x="10" y="10"/><p:text>Check this figure</p:text></p:cm><p:cm authorId="1" idx="2"><p:pos x="10" y="10"/><p:text>Typo in the title</p:text></p:cm></p:cmLst>"#;
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &slide_xml(&title_shape("Revenue"))),
+            ("ppt/slides/_rels/slide1.xml.rels", rels),
+            ("ppt/slides/slide2.xml", &slide_xml(&title_shape("Costs"))),
+            ("ppt/comments/comment1.xml", comments),
+            ("ppt/commentAuthors.xml", authors),
+        ]);
+
+        let mut options = ConvertOptions::default();
+        options.powerpoint.comments = true;
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Revenue\n\n\
+             **Comments**\n\n\
+             - **Alice** (2024-05-01): Check this figure\n\
+             - **Alice**: Typo in the title\n\n\
+             \n---\n\n\
+             # Costs\n\n"
+        );
+        assert!(!convert(&pptx).contains("Comments"));
+    }
+
+    const SLIDE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="../media/image1.png"/>
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="https://example.com/logo.png" TargetMode="External"/>
+</Relationships>"#;
+
+    fn picture_shape(descr: &str, blip: &str) -> String {
+        format!(
+            r#"<p:pic><p:nvPicPr><p:cNvPr id="4" name="Picture 3" descr="{descr}"/><p:cNvPicPr/><p:nvPr/></p:nvPicPr><p:blipFill><a:blip {blip}/></p:blipFill></p:pic>"#
+        )
+    }
+
+    fn convert_pictures(shapes: &str, options: ConvertOptions) -> String {
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &slide_xml(shapes)),
+            ("ppt/slides/_rels/slide1.xml.rels", SLIDE_RELS),
+            ("ppt/media/image1.png", "png bytes"),
+        ]);
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_pictures_extracted_to_media_dir() {
+        let dir = std::env::temp_dir().join(format!("mq-conv-pptx-media-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let options = ConvertOptions {
+            input_name: Some("deck.pptx".to_string()),
+            media: MediaOptions {
+                extract_dir: Some(dir.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let shapes = format!(
+            "{}{}{}",
+            title_shape("Results"),
+            picture_shape("Revenue chart", r#"r:embed="rId2""#),
+            body_shape("Up 10%")
+        );
+        let path = dir.join("deck-image1.png");
+        assert_eq!(
+            convert_pictures(&shapes, options),
+            format!(
+                "# Results\n\n![Revenue chart]({})\n\nUp 10%\n\n",
+                path.to_string_lossy().replace('\\', "/")
+            )
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"png bytes");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest]
+    fn test_pictures_embedded_as_data_uri() {
+        let options = ConvertOptions {
+            media: MediaOptions {
+                embed: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            convert_pictures(&picture_shape("Chart", r#"r:embed="rId2""#), options),
+            "# Slide 1\n\n![Chart](data:image/png;base64,cG5nIGJ5dGVz)\n\n"
+        );
+    }
+
+    #[rstest]
+    #[case::linked(r#"r:link="rId3""#, "![Logo](https://example.com/logo.png)\n\n")]
+    #[case::other_prefix(
+        r#"xmlns:rel="http://schemas.openxmlformats.org/officeDocument/2006/relationships" rel:link="rId3""#,
+        "![Logo](https://example.com/logo.png)\n\n"
+    )]
+    #[case::not_extracted(r#"r:embed="rId2""#, "*[Image: Logo]*\n\n")]
+    #[case::unknown_relationship(r#"r:embed="rId9""#, "*[Image: Logo]*\n\n")]
+    fn test_picture_targets_without_media_dir(#[case] blip: &str, #[case] expected: &str) {
+        assert_eq!(
+            convert_pictures(&picture_shape("Logo", blip), ConvertOptions::default()),
+            format!("# Slide 1\n\n{expected}")
+        );
+    }
+
+    #[rstest]
+    #[case("../media/image1.png", "ppt/media/image1.png")]
+    #[case("/ppt/media/image2.jpeg", "ppt/media/image2.jpeg")]
+    #[case("./image3.gif", "ppt/slides/image3.gif")]
+    fn test_resolve_target(#[case] target: &str, #[case] expected: &str) {
+        assert_eq!(resolve_target("ppt/slides", target), expected);
+    }
+
+    const LINK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/docs" TargetMode="External"/>
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide" Target="slide2.xml"/>
+</Relationships>"#;
+
+    fn link_shape(runs: &str) -> String {
+        format!(
+            r#"<p:sp><p:nvSpPr><p:cNvPr id="3" name="Body"/><p:cNvSpPr/><p:nvPr/></p:nvSpPr>
+<p:txBody><a:p>{runs}</a:p></p:txBody></p:sp>"#
+        )
+    }
+
+    fn link_run(text: &str, rpr: &str, link: &str) -> String {
+        format!(
+            r#"<a:r><a:rPr lang="en-US" {rpr}><a:hlinkClick {link}/></a:rPr><a:t>{text}</a:t></a:r>"#
+        )
+    }
+
+    #[rstest]
+    #[case::external(
+        &link_run("the docs", "", r#"r:id="rId2""#),
+        "See [the docs](https://example.com/docs)"
+    )]
+    #[case::other_prefix(
+        &link_run(
+            "the docs",
+            "",
+            r#"xmlns:rel="http://schemas.openxmlformats.org/officeDocument/2006/relationships" rel:id="rId2""#
+        ),
+        "See [the docs](https://example.com/docs)"
+    )]
+    #[case::split_by_formatting(
+        &format!("{}{}", link_run("the ", "", r#"r:id="rId2""#), link_run("docs", r#"b="1""#, r#"r:id="rId2""#)),
+        "See [the **docs**](https://example.com/docs)"
+    )]
+    #[case::slide_jump(
+        &link_run("results", "", r#"r:id="rId3" action="ppaction://hlinksldjump""#),
+        "See [results](#results)"
+    )]
+    #[case::show_jump(
+        &link_run("next", "", r#"r:id="" action="ppaction://hlinkshowjump?jump=nextslide""#),
+        "See [next](#results)"
+    )]
+    #[case::end_show(
+        &link_run("the end", "", r#"r:id="" action="ppaction://hlinkshowjump?jump=endshow""#),
+        "See the end"
+    )]
+    #[case::unknown_relationship(&link_run("docs", "", r#"r:id="rId9""#), "See docs")]
+    fn test_hyperlinks(#[case] runs: &str, #[case] expected: &str) {
+        let body = link_shape(&format!("<a:r><a:t>See </a:t></a:r>{runs}"));
+        let pptx = make_pptx(&[
+            (
+                "ppt/slides/slide1.xml",
+                &slide_xml(&format!("{}{body}", title_shape("Agenda"))),
+            ),
+            ("ppt/slides/_rels/slide1.xml.rels", LINK_RELS),
+            ("ppt/slides/slide2.xml", &slide_xml(&title_shape("Results"))),
+        ]);
+        assert_eq!(
+            convert(&pptx),
+            format!("# Agenda\n\n{expected}\n\n\n---\n\n# Results\n\n")
+        );
+    }
+
+    #[rstest]
+    fn test_hyperlink_to_untitled_slide() {
+        let body = link_shape(&link_run(
+            "back",
+            "",
+            r#"r:id="" action="ppaction://hlinkshowjump?jump=previousslide""#,
+        ));
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &slide_xml(&body_shape("Intro"))),
+            ("ppt/slides/slide2.xml", &slide_xml(&body)),
+        ]);
+        assert_eq!(
+            convert(&pptx),
+            "# Slide 1\n\nIntro\n\n\n---\n\n# Slide 2\n\n[back](#slide-1)\n\n"
+        );
+    }
+
     #[rstest]
     #[case::title("title", "# Hello")]
     #[case::plain("plain", "Some content")]
@@ -597,6 +1697,59 @@ mod tests {
         assert!(output.contains("- Item C"));
     }
 
+    fn list_shape(items: &[(&str, &str)]) -> String {
+        let paras: String = items
+            .iter()
+            .map(|(ppr, t)| format!("<a:p>{ppr}<a:r><a:t>{t}</a:t></a:r></a:p>"))
+            .collect();
+        format!(
+            r#"<p:sp><p:nvSpPr><p:nvPr><p:ph type="body" idx="1"/></p:nvPr></p:nvSpPr>
+<p:txBody>{paras}</p:txBody></p:sp>"#
+        )
+    }
+
+    #[rstest]
+    #[case::nested_bullets(
+        &[
+            (r#"<a:pPr><a:buChar char="•"/></a:pPr>"#, "Fruit"),
+            (r#"<a:pPr lvl="1"/>"#, "Apple"),
+            (r#"<a:pPr lvl="2"/>"#, "Fuji"),
+            (r#"<a:pPr lvl="1"/>"#, "Pear"),
+            (r#"<a:pPr><a:buChar char="•"/></a:pPr>"#, "Vegetables"),
+        ],
+        "- Fruit\n  - Apple\n    - Fuji\n  - Pear\n- Vegetables\n"
+    )]
+    #[case::numbered(
+        &[
+            (r#"<a:pPr><a:buAutoNum type="arabicPeriod"/></a:pPr>"#, "Plan"),
+            (r#"<a:pPr lvl="1"><a:buChar char="•"/></a:pPr>"#, "Scope"),
+            (r#"<a:pPr><a:buAutoNum type="arabicPeriod"/></a:pPr>"#, "Build"),
+            (r#"<a:pPr lvl="1"><a:buAutoNum type="alphaLcParenR"/></a:pPr>"#, "Code"),
+            (r#"<a:pPr lvl="1"><a:buAutoNum type="alphaLcParenR"/></a:pPr>"#, "Test"),
+            (r#"<a:pPr><a:buAutoNum type="arabicPeriod"/></a:pPr>"#, "Ship"),
+        ],
+        "1. Plan\n   - Scope\n2. Build\n   1) Code\n   2) Test\n3. Ship\n"
+    )]
+    #[case::start_at(
+        &[
+            (r#"<a:pPr><a:buAutoNum type="arabicPeriod" startAt="9"/></a:pPr>"#, "Nine"),
+            (r#"<a:pPr><a:buAutoNum type="arabicPeriod" startAt="9"/></a:pPr>"#, "Ten"),
+            (r#"<a:pPr lvl="1"><a:buAutoNum type="arabicPeriod"/></a:pPr>"#, "Sub"),
+        ],
+        "9. Nine\n10. Ten\n    1. Sub\n"
+    )]
+    #[case::skipped_level(
+        &[
+            (r#"<a:pPr><a:buChar char="•"/></a:pPr>"#, "Top"),
+            (r#"<a:pPr lvl="3"/>"#, "Deep"),
+        ],
+        "- Top\n  - Deep\n"
+    )]
+    fn test_list_levels(#[case] items: &[(&str, &str)], #[case] expected: &str) {
+        let pptx = make_pptx(&[("ppt/slides/slide1.xml", &slide_xml(&list_shape(items)))]);
+        assert_eq!(convert(&pptx), format!("# Slide 1\n\n{expected}\n"));
+    }
+
     #[rstest]
     fn test_table() {
         let tbl = table_xml(&[&["Name", "Age"], &["Alice", "30"], &["Bob", "25"]]);
@@ -631,6 +1784,211 @@ mod tests {
         assert!(output.contains("*Empty slide*"));
     }
 
+    #[rstest]
+    fn test_best_effort_skips_corrupt_slide() {
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &slide_xml(&title_shape("Intro"))),
+            ("ppt/slides/slide2.xml", "<p:sld><p:cSld></p:sld>"),
+            ("ppt/slides/slide3.xml", &slide_xml(&title_shape("Outro"))),
+        ]);
+        let mut output = Vec::new();
+        let error = PowerPointConverter::default()
+            .convert(&pptx, &mut output)
+            .unwrap_err();
+        assert_eq!(error.entry(), Some("ppt/slides/slide2.xml"));
+
+        let options = ConvertOptions {
+            best_effort: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "# Intro\n\n\n---\n\n# Slide 2\n\n> **Conversion warning**: Slide 2 could not be converted: {error}\n\n\n---\n\n# Outro\n\n"
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::list_and_range("2,4-", "# Slide 2\n\nDetails\n\n\n---\n\n# Outro\n\n")]
+    #[case::single("1", "# Intro\n\n")]
+    #[case::beyond_the_deck("9-12", "")]
+    fn test_slide_selection(#[case] slides: &str, #[case] expected: &str) {
+        // Slide 3 is corrupt, but is never parsed when not selected
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &slide_xml(&title_shape("Intro"))),
+            ("ppt/slides/slide2.xml", &slide_xml(&body_shape("Details"))),
+            ("ppt/slides/slide3.xml", "<p:sld><p:cSld></p:sld>"),
+            ("ppt/slides/slide4.xml", &slide_xml(&title_shape("Outro"))),
+        ]);
+        let options = ConvertOptions {
+            powerpoint: PowerPointOptions {
+                slides: Some(slides.parse().unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::zero("0-3")]
+    #[case::reversed("5-2")]
+    #[case::not_a_number("1,x")]
+    #[case::empty("")]
+    fn test_invalid_slide_ranges(#[case] slides: &str) {
+        assert!(slides.parse::<SlideRanges>().is_err());
+    }
+
+    #[rstest]
+    #[case::default_prefix(r#"r:dm="rId2""#)]
+    #[case::other_prefix(
+        r#"xmlns:rel="http://schemas.openxmlformats.org/officeDocument/2006/relationships" rel:dm="rId2""#
+    )]
+    fn test_smartart(#[case] data_rel: &str) {
+        let frame = format!(
+            r#"<p:graphicFrame><p:nvGraphicFramePr><p:cNvPr id="4" name="Diagram 3"/><p:cNvGraphicFramePr/><p:nvPr/></p:nvGraphicFramePr>
+<a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/diagram"><dgm:relIds xmlns:dgm="http://schemas.openxmlformats.org/drawingml/2006/diagram" {data_rel} r:lo="rId3" r:qs="rId4" r:cs="rId5"/></a:graphicData></a:graphic></p:graphicFrame>"#
+        );
+        let rels = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/diagramData" Target="../diagrams/data1.xml"/>
+</Relationships>"#;
+        let data = r#"<dgm:dataModel xmlns:dgm="http://schemas.openxmlformats.org/drawingml/2006/diagram" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main">
+<dgm:ptLst>
+<dgm:pt modelId="0" type="doc"/>
+<dgm:pt modelId="1"><dgm:t><a:p><a:r><a:t>Discover</a:t></a:r></a:p></dgm:t></dgm:pt>
+<dgm:pt modelId="2"><dgm:t><a:p><a:r><a:t>Interviews</a:t></a:r></a:p></dgm:t></dgm:pt>
+<dgm:pt modelId="3"><dgm:t><a:p><a:r><a:t>Deliver</a:t></a:r></a:p></dgm:t></dgm:pt>
+</dgm:ptLst>
+<dgm:cxnLst>
+<dgm:cxn modelId="4" srcId="0" destId="1" srcOrd="0" destOrd="0"/>
+<dgm:cxn modelId="5" srcId="1" destId="2" srcOrd="0" destOrd="0"/>
+<dgm:cxn modelId="6" srcId="0" destId="3" srcOrd="1" destOrd="0"/>
+</dgm:cxnLst>
+</dgm:dataModel>"#;
+        let pptx = make_pptx(&[
+            (
+                "ppt/slides/slide1.xml",
+                &slide_xml(&format!("{}{frame}", title_shape("Process"))),
+            ),
+            ("ppt/slides/_rels/slide1.xml.rels", rels),
+            ("ppt/diagrams/data1.xml", data),
+        ]);
+        assert_eq!(
+            convert(&pptx),
+            "# Process\n\n- Discover\n  - Interviews\n- Deliver\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_grouped_shapes() {
+        let group = format!(
+            r#"<p:grpSp><p:nvGrpSpPr><p:cNvPr id="5" name="Group 4"/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr/>{}<p:grpSp><p:nvGrpSpPr><p:cNvPr id="7" name="Group 6"/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr/>{}</p:grpSp></p:grpSp>"#,
+            body_shape("Outer"),
+            body_shape("Inner")
+        );
+        let pptx = make_pptx(&[(
+            "ppt/slides/slide1.xml",
+            &slide_xml(&format!("{}{group}", title_shape("Team"))),
+        )]);
+        assert_eq!(convert(&pptx), "# Team\n\nOuter\n\nInner\n\n");
+    }
+
+    fn placed_shape(text: &str, x: i64, y: i64) -> String {
+        format!(
+            r#"<p:sp><p:nvSpPr><p:cNvPr id="9" name="TextBox"/><p:cNvSpPr txBox="1"/><p:nvPr/></p:nvSpPr><p:spPr><a:xfrm><a:off x="{x}" y="{y}"/><a:ext cx="100" cy="100"/></a:xfrm></p:spPr>
+<p:txBody><a:p><a:r><a:t>{text}</a:t></a:r></a:p></p:txBody></p:sp>"#
+        )
+    }
+
+    #[rstest]
+    fn test_reading_order() {
+        // The group's children are laid out at 10x scale from (0, 0)
+        let group = format!(
+            r#"<p:grpSp><p:nvGrpSpPr><p:cNvPr id="5" name="Group 4"/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr><a:xfrm><a:off x="0" y="3000000"/><a:ext cx="5000000" cy="1000000"/><a:chOff x="0" y="0"/><a:chExt cx="500000" cy="100000"/></a:xfrm></p:grpSpPr>{}{}</p:grpSp>"#,
+            placed_shape("Caption right", 300000, 0),
+            placed_shape("Caption left", 0, 0),
+        );
+        let shapes = [
+            placed_shape("Footnote", 0, 6000000),
+            group,
+            placed_shape("Right column", 4000000, 1000000),
+            body_shape("Left column"),
+            title_shape("Overview"),
+        ]
+        .concat();
+        let rels = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout2.xml"/>
+</Relationships>"#;
+        let layout = slide_xml(
+            r#"<p:sp><p:nvSpPr><p:cNvPr id="3" name="Content"/><p:cNvSpPr/><p:nvPr><p:ph type="body"/></p:nvPr></p:nvSpPr><p:spPr><a:xfrm><a:off x="500000" y="1100000"/><a:ext cx="3000000" cy="1000000"/></a:xfrm></p:spPr></p:sp>"#,
+        );
+        let pptx = make_pptx(&[
+            ("ppt/slides/slide1.xml", &slide_xml(&shapes)),
+            ("ppt/slides/_rels/slide1.xml.rels", rels),
+            ("ppt/slideLayouts/slideLayout2.xml", &layout),
+        ]);
+
+        let mut options = ConvertOptions::default();
+        options.powerpoint.reading_order = true;
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Overview\n\nLeft column\n\nRight column\n\nCaption left\n\nCaption right\n\nFootnote\n\n"
+        );
+        assert_eq!(
+            convert(&pptx),
+            "# Slide 1\n\nFootnote\n\nCaption right\n\nCaption left\n\nRight column\n\nLeft column\n\nOverview\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_reading_order_nested_groups() {
+        // The outer group doubles its children's coordinates; the inner one,
+        // placed in the outer group's space, shifts its children right
+        let group = |xfrm: &str, children: &str| {
+            format!(
+                r#"<p:grpSp><p:nvGrpSpPr><p:cNvPr id="5" name="Group"/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr><a:xfrm>{xfrm}</a:xfrm></p:grpSpPr>{children}</p:grpSp>"#
+            )
+        };
+        let inner = group(
+            r#"<a:off x="500000" y="0"/><a:ext cx="100" cy="100"/><a:chOff x="0" y="0"/><a:chExt cx="100" cy="100"/>"#,
+            &placed_shape("Inner", 0, 0),
+        );
+        let outer = group(
+            r#"<a:off x="0" y="2000000"/><a:ext cx="2000000" cy="2000000"/><a:chOff x="0" y="0"/><a:chExt cx="1000000" cy="1000000"/>"#,
+            &format!("{inner}{}", placed_shape("Outer", 0, 0)),
+        );
+        let pptx = make_pptx(&[(
+            "ppt/slides/slide1.xml",
+            &slide_xml(&format!("{outer}{}", placed_shape("Top", 3000000, 0))),
+        )]);
+
+        let mut options = ConvertOptions::default();
+        options.powerpoint.reading_order = true;
+        let mut output = Vec::new();
+        PowerPointConverter::new(options)
+            .convert(&pptx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Slide 1\n\nTop\n\nOuter\n\nInner\n\n"
+        );
+    }
+
     #[rstest]
     fn test_slide_ordering() {
         let s1 = slide_xml(&title_shape("First"));
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use super::{attr, local_name};
+
+/// A slide's transition and the effects of its main animation sequence,
+/// in play order.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Timeline {
+    pub transition: Option<Transition>,
+    effects: Vec<Effect>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Transition {
+    /// Local name of the effect element, e.g. `fade` or `push`.
+    kind: Option<String>,
+    direction: Option<String>,
+    speed: Option<String>,
+    duration_ms: Option<u32>,
+    advance_on_click: bool,
+    advance_after_ms: Option<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Effect {
+    /// `entr`, `exit`, `emph` or `path`.
+    class: String,
+    preset: u32,
+    /// `clickEffect`, `withEffect` or `afterEffect`.
+    trigger: String,
+    /// The animated shape's name or text.
+    shape: String,
+}
+
+impl Timeline {
+    /// Read the transition and animations of a slide.
+    pub(super) fn parse(xml: &str) -> Self {
+        let shapes = shape_labels(xml);
+        let mut timeline = Self::default();
+        let mut reader = Reader::from_str(xml);
+        let mut in_transition = false;
+        let mut in_fallback = false;
+        // `nodeType` of each open time node, to skip interactive sequences
+        let mut nodes: Vec<String> = Vec::new();
+        loop {
+            let (e, empty) = match reader.read_event() {
+                Ok(Event::Start(e)) => (e, false),
+                Ok(Event::Empty(e)) => (e, true),
+                Ok(Event::End(e)) => {
+                    match local_name(e.name().as_ref()).as_str() {
+                        "transition" => in_transition = false,
+                        "Fallback" => in_fallback = false,
+                        "cTn" => {
+                            nodes.pop();
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => continue,
+            };
+            match local_name(e.name().as_ref()).as_str() {
+                // Markup-compatibility fallbacks repeat the preferred transition
+                "Fallback" => in_fallback = !empty,
+                "transition" if !in_fallback && timeline.transition.is_none() => {
+                    timeline.transition = Some(Transition::read(&e));
+                    in_transition = !empty;
+                }
+                "sndAc" | "stSnd" | "snd" | "endSnd" | "extLst" => {}
+                _ if in_transition => {
+                    if let Some(transition) = timeline.transition.as_mut()
+                        && transition.kind.is_none()
+                    {
+                        let local = local_name(e.name().as_ref());
+                        // PowerPoint 2013 presets name themselves in `prst`
+                        transition.kind = attr(&e, "prst").or(Some(local));
+                        transition.direction = attr(&e, "dir");
+                    }
+                }
+                "cTn" => {
+                    let node_type = attr(&e, "nodeType").unwrap_or_default();
+                    let interactive = nodes.iter().any(|n| n == "interactiveSeq");
+                    if let Some(class) = attr(&e, "presetClass")
+                        && !interactive
+                    {
+                        timeline.effects.push(Effect {
+                            class,
+                            preset: attr(&e, "presetID")
+                                .and_then(|id| id.parse().ok())
+                                .unwrap_or(0),
+                            trigger: node_type.clone(),
+                            shape: String::new(),
+                        });
+                    }
+                    if !empty {
+                        nodes.push(node_type);
+                    }
+                }
+                "spTgt" => {
+                    if let Some(effect) = timeline.effects.last_mut()
+                        && effect.shape.is_empty()
+                    {
+                        let id = attr(&e, "spid").unwrap_or_default();
+                        effect.shape = shapes
+                            .get(&id)
+                            .cloned()
+                            .unwrap_or_else(|| format!("Shape {id}"));
+                    }
+                }
+                _ => {}
+            }
+        }
+        timeline
+    }
+
+    /// The effects as `(step, trigger, effect, shape)` rows. Steps count
+    /// clicks, so effects that start with or after another share its step;
+    /// step 0 plays without a click when the slide appears.
+    pub(super) fn steps(&self) -> Vec<(usize, &'static str, String, &str)> {
+        let mut step = 0;
+        self.effects
+            .iter()
+            .map(|effect| {
+                let trigger = match effect.trigger.as_str() {
+                    "clickEffect" => {
+                        step += 1;
+                        "On click"
+                    }
+                    "withEffect" => "With previous",
+                    _ => "After previous",
+                };
+                (step, trigger, effect.name(), effect.shape.as_str())
+            })
+            .collect()
+    }
+}
+
+impl Transition {
+    fn read(e: &BytesStart) -> Self {
+        Self {
+            speed: attr(e, "spd"),
+            duration_ms: attr(e, "dur").and_then(|ms| ms.parse().ok()),
+            advance_on_click: attr(e, "advClick").is_none_or(|click| click != "0"),
+            advance_after_ms: attr(e, "advTm").and_then(|ms| ms.parse().ok()),
+            ..Default::default()
+        }
+    }
+
+    /// `Push (left, 1.5 s); advances after 3 s`
+    pub(super) fn describe(&self) -> String {
+        let mut details = Vec::new();
+        if let Some(direction) = &self.direction {
+            details.push(match direction.as_str() {
+                "l" => "left".to_string(),
+                "r" => "right".to_string(),
+                "u" => "up".to_string(),
+                "d" => "down".to_string(),
+                "horz" => "horizontal".to_string(),
+                "vert" => "vertical".to_string(),
+                other => words(other),
+            });
+        }
+        match (self.duration_ms, self.speed.as_deref()) {
+            (Some(ms), _) => details.push(seconds(ms)),
+            (None, Some("slow")) => details.push("slow".to_string()),
+            (None, Some("med")) => details.push("medium".to_string()),
+            (None, Some("fast")) => details.push("fast".to_string()),
+            _ => {}
+        }
+        let mut text = self
+            .kind
+            .as_deref()
+            .map(|kind| capitalize(&words(kind)))
+            .unwrap_or_else(|| "None".to_string());
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        match (self.advance_after_ms, self.advance_on_click) {
+            (Some(ms), true) => {
+                text.push_str(&format!("; advances on click or after {}", seconds(ms)))
+            }
+            (Some(ms), false) => text.push_str(&format!("; advances after {}", seconds(ms))),
+            (None, false) => text.push_str("; does not advance on click"),
+            (None, true) => {}
+        }
+        text
+    }
+}
+
+impl Effect {
+    /// `Entrance: Fly In`, or the preset number when it has no known name.
+    fn name(&self) -> String {
+        let (class, name) = match self.class.as_str() {
+            "entr" => ("Entrance", entrance_name(self.preset)),
+            "exit" => ("Exit", exit_name(self.preset)),
+            "emph" => ("Emphasis", emphasis_name(self.preset)),
+            "path" => return "Motion path".to_string(),
+            "mediacall" => return "Media".to_string(),
+            other => (other, None),
+        };
+        match name {
+            Some(name) => format!("{class}: {name}"),
+            None => format!("{class}: preset {}", self.preset),
+        }
+    }
+}
+
+fn entrance_name(preset: u32) -> Option<&'static str> {
+    Some(match preset {
+        1 => "Appear",
+        2 => "Fly In",
+        9 => "Dissolve In",
+        12 => "Peek In",
+        other => shared_name(other)?,
+    })
+}
+
+fn exit_name(preset: u32) -> Option<&'static str> {
+    Some(match preset {
+        1 => "Disappear",
+        2 => "Fly Out",
+        9 => "Dissolve Out",
+        12 => "Peek Out",
+        other => shared_name(other)?,
+    })
+}
+
+/// Entrance and exit effects with the same name either way.
+fn shared_name(preset: u32) -> Option<&'static str> {
+    Some(match preset {
+        3 => "Blinds",
+        4 => "Box",
+        5 => "Checkerboard",
+        6 => "Circle",
+        8 => "Diamond",
+        10 => "Fade",
+        13 => "Plus",
+        14 => "Random Bars",
+        16 => "Split",
+        18 => "Strips",
+        21 => "Wheel",
+        22 => "Wipe",
+        23 => "Zoom",
+        26 => "Bounce",
+        _ => return None,
+    })
+}
+
+fn emphasis_name(preset: u32) -> Option<&'static str> {
+    Some(match preset {
+        6 => "Grow/Shrink",
+        8 => "Spin",
+        9 => "Transparency",
+        _ => return None,
+    })
+}
+
+/// Label each shape id with the first line of its text, or its name.
+fn shape_labels(xml: &str) -> HashMap<String, String> {
+    let mut labels: HashMap<String, (String, String)> = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<String> = None;
+    let mut in_text = false;
+    // Only the shape's first paragraph is used
+    let mut first_paragraph_done = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) if local_name(e.name().as_ref()) == "cNvPr" => {
+                if let Some(id) = attr(&e, "id") {
+                    labels.insert(
+                        id.clone(),
+                        (attr(&e, "name").unwrap_or_default(), String::new()),
+                    );
+                    current = Some(id);
+                    first_paragraph_done = false;
+                }
+            }
+            Ok(Event::Start(e)) if local_name(e.name().as_ref()) == "t" => in_text = true,
+            Ok(Event::Text(e)) if in_text && !first_paragraph_done => {
+                if let Some((_, text)) = current.as_ref().and_then(|id| labels.get_mut(id)) {
+                    text.push_str(&e.decode().unwrap_or_default());
+                }
+            }
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()).as_str() {
+                "t" => in_text = false,
+                "p" => {
+                    first_paragraph_done |= current
+                        .as_ref()
+                        .and_then(|id| labels.get(id))
+                        .is_some_and(|(_, text)| !text.trim().is_empty());
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    labels
+        .into_iter()
+        .map(|(id, (name, text))| {
+            let text = text.trim();
+            (
+                id,
+                if text.is_empty() {
+                    name
+                } else {
+                    text.to_string()
+                },
+            )
+        })
+        .collect()
+}
+
+/// `randomBar` -> `random bar`.
+fn words(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_uppercase() && !out.is_empty() {
+            out.push(' ');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// `1500` -> `1.5 s`.
+fn seconds(ms: u32) -> String {
+    format!("{} s", f64::from(ms) / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::preset(r#"<p:transition spd="slow"><p:fade/></p:transition>"#, "Fade (slow)")]
+    #[case::direction_and_timing(
+        r#"<p:transition spd="fast" advClick="0" advTm="3000"><p:push dir="l"/></p:transition>"#,
+        "Push (left, fast); advances after 3 s"
+    )]
+    #[case::alternate_content(
+        r#"<mc:AlternateContent><mc:Choice Requires="p14"><p:transition spd="slow" p14:dur="1500"><p14:vortex dir="r"/></p:transition></mc:Choice><mc:Fallback><p:transition spd="slow"><p:fade/></p:transition></mc:Fallback></mc:AlternateContent>"#,
+        "Vortex (right, 1.5 s)"
+    )]
+    #[case::no_effect(
+        r#"<p:transition advTm="2000"/>"#,
+        "None; advances on click or after 2 s"
+    )]
+    fn test_transition(#[case] xml: &str, #[case] expected: &str) {
+        let timeline = Timeline::parse(&format!("<p:sld>{xml}</p:sld>"));
+        assert_eq!(timeline.transition.unwrap().describe(), expected);
+    }
+}
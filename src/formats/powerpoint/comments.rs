@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use super::{attr, local_name};
+use crate::error::{Error, Result};
+
+/// A reviewer comment on a slide, or a reply to one.
+#[derive(Debug, PartialEq)]
+pub(super) struct Comment {
+    pub(super) author: Option<String>,
+    /// The day it was written, `YYYY-MM-DD`.
+    pub(super) date: Option<String>,
+    pub(super) text: String,
+    pub(super) replies: Vec<Comment>,
+}
+
+/// Read comment authors by id, from either `ppt/commentAuthors.xml`
+/// (`p:cmAuthor`) or the `ppt/authors.xml` of modern comments
+/// (`p188:author`).
+pub(super) fn parse_authors(xml: &str) -> HashMap<String, String> {
+    let mut authors = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e))
+                if matches!(
+                    local_name(e.name().as_ref()).as_str(),
+                    "cmAuthor" | "author"
+                ) =>
+            {
+                if let (Some(id), Some(name)) = (attr(&e, "id"), attr(&e, "name")) {
+                    authors.insert(id, name);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    authors
+}
+
+/// Read the comments of one slide in the order they were made. Both the
+/// legacy `ppt/comments/commentN.xml` parts, whose text is a plain
+/// `p:text`, and the modern threaded ones, whose text is a text body with
+/// replies nested below it, are understood.
+pub(super) fn parse(xml: &str, authors: &HashMap<String, String>) -> Result<Vec<Comment>> {
+    let mut comments: Vec<Comment> = Vec::new();
+    // The comment being read and, inside its reply list, the reply
+    let mut current: Option<Comment> = None;
+    let mut reply: Option<Comment> = None;
+    let mut in_text = false;
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match local_name(e.name().as_ref()).as_str() {
+                "cm" => current = Some(read_comment(&e, authors)),
+                "reply" if current.is_some() => reply = Some(read_comment(&e, authors)),
+                // `p:text` of legacy comments, `a:t` runs of modern ones
+                "text" | "t" => in_text = true,
+                _ => {}
+            },
+            Ok(Event::Text(e)) if in_text => {
+                if let Some(comment) = reply.as_mut().or(current.as_mut()) {
+                    comment.text.push_str(&e.decode().unwrap_or_default());
+                }
+            }
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()).as_str() {
+                "text" | "t" => in_text = false,
+                // Paragraphs of one comment are joined into a single line
+                "p" => {
+                    if let Some(comment) = reply.as_mut().or(current.as_mut())
+                        && !comment.text.ends_with(' ')
+                    {
+                        comment.text.push(' ');
+                    }
+                }
+                "reply" => {
+                    if let (Some(comment), Some(reply)) = (current.as_mut(), reply.take()) {
+                        comment.replies.push(finish(reply));
+                    }
+                }
+                "cm" => comments.extend(current.take().map(finish)),
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(Error::Parse {
+                    format: "powerpoint",
+                    entry: None,
+                    offset: Some(reader.error_position()),
+                    message: e.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(comments)
+}
+
+fn read_comment(e: &BytesStart, authors: &HashMap<String, String>) -> Comment {
+    // Legacy comments date themselves with `dt`, modern ones with `created`
+    let date = attr(e, "dt")
+        .or_else(|| attr(e, "created"))
+        .map(|dt| dt.split('T').next().unwrap_or_default().to_string())
+        .filter(|date| !date.is_empty());
+    Comment {
+        author: attr(e, "authorId").and_then(|id| authors.get(&id).cloned()),
+        date,
+        text: String::new(),
+        replies: Vec::new(),
+    }
+}
+
+fn finish(mut comment: Comment) -> Comment {
+    comment.text = comment
+        .text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    comment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn authors() -> HashMap<String, String> {
+        HashMap::from([
+            ("0".to_string(), "Alice".to_string()),
+            ("{B0B}".to_string(), "Bob".to_string()),
+        ])
+    }
+
+    fn comment(author: Option<&str>, date: Option<&str>, text: &str) -> Comment {
+        Comment {
+            author: author.map(String::from),
+            date: date.map(String::from),
+            text: text.to_string(),
+            replies: Vec::new(),
+        }
+    }
+
+    #[rstest]
+    fn test_parse_authors() {
+        let legacy = r#"<p:cmAuthorLst xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"><p:cmAuthor id="0" name="Alice" initials="A" lastIdx="2" clrIdx="0"/></p:cmAuthorLst>"#;
+        let modern = r#"<p188:authorLst xmlns:p188="http://schemas.microsoft.com/office/powerpoint/2018/8/main"><p188:author id="{B0B}" name="Bob" initials="B" userId="bob@example.com" providerId="AD"/></p188:authorLst>"#;
+        assert_eq!(parse_authors(legacy)["0"], "Alice");
+        assert_eq!(parse_authors(modern)["{B0B}"], "Bob");
+    }
+
+    #[rstest]
+    fn test_parse_legacy_comments() {
+        let xml = r#"<p:cmLst xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
+<p:cm authorId="0" dt="2024-05-01T10:15:00.000" idx="1"><p:pos x="10" y="10"/><p:text>Check   this figure</p:text></p:cm>
+<p:cm authorId="7" idx="2"><p:pos x="10" y="10"/><p:text>Unknown author</p:text></p:cm>
+</p:cmLst>"#;
+        assert_eq!(
+            parse(xml, &authors()).unwrap(),
+            vec![
+                comment(Some("Alice"), Some("2024-05-01"), "Check this figure"),
+                comment(None, None, "Unknown author"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_parse_modern_comments_with_replies() {
+        let xml = r#"<p188:cmLst xmlns:p188="http://schemas.microsoft.com/office/powerpoint/2018/8/main" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main">
+<p188:cm id="{C1}" authorId="{B0B}" created="2024-06-02T08:00:00.000">
+<pc:sldMkLst xmlns:pc="http://schemas.microsoft.com/office/powerpoint/2013/main/command"/>
+<p188:replyLst><p188:reply id="{R1}" authorId="0" created="2024-06-03T09:30:00.000"><p188:txBody><a:bodyPr/><a:p><a:r><a:t>Done</a:t></a:r></a:p></p188:txBody></p188:reply></p188:replyLst>
+<p188:txBody><a:bodyPr/><a:p><a:r><a:t>Update the </a:t></a:r><a:r><a:t>numbers</a:t></a:r></a:p><a:p><a:r><a:t>for Q2</a:t></a:r></a:p></p188:txBody>
+</p188:cm>
+</p188:cmLst>"#;
+        let mut expected = comment(Some("Bob"), Some("2024-06-02"), "Update the numbers for Q2");
+        expected
+            .replies
+            .push(comment(Some("Alice"), Some("2024-06-03"), "Done"));
+        assert_eq!(parse(xml, &authors()).unwrap(), vec![expected]);
+    }
+
+    #[rstest]
+    fn test_parse_malformed() {
+        assert!(matches!(
+            parse("<p:cmLst><p:cm></p:cmLst>", &authors()),
+            Err(Error::Parse { .. })
+        ));
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use super::{attr, local_name};
+use crate::error::{Error, Result};
+
+/// A point of a SmartArt data model.
+#[derive(Default)]
+struct Point {
+    /// `doc` for the diagram itself, `node` (the default) or `asst` for
+    /// boxes with text, and transition or presentation points otherwise.
+    kind: String,
+    text: String,
+}
+
+/// Read the text of a SmartArt diagram from its data part
+/// (`ppt/diagrams/dataN.xml`) as an outline: each node's text with its
+/// depth below the diagram, in the order the diagram shows them.
+pub(super) fn outline(xml: &str) -> Result<Vec<(usize, String)>> {
+    let mut points: Vec<(String, Point)> = Vec::new();
+    // Parent id → (order, child id), from `parOf` connections
+    let mut children: HashMap<String, Vec<(u32, String)>> = HashMap::new();
+
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<(String, Point)> = None;
+    // Inside a point's `dgm:t` text body, and inside one of its `a:t` runs
+    let mut in_body = false;
+    let mut in_text = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match local_name(e.name().as_ref()).as_str() {
+                "pt" => current = Some(read_point(&e)),
+                "t" if current.is_some() => {
+                    if in_body {
+                        in_text = true;
+                    } else {
+                        in_body = true;
+                    }
+                }
+                "cxn" => read_connection(&e, &mut children),
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => match local_name(e.name().as_ref()).as_str() {
+                "pt" => points.push(read_point(&e)),
+                "cxn" => read_connection(&e, &mut children),
+                _ => {}
+            },
+            Ok(Event::Text(e)) if in_text => {
+                if let Some((_, point)) = current.as_mut() {
+                    point.text.push_str(&e.decode().unwrap_or_default());
+                }
+            }
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()).as_str() {
+                "pt" => {
+                    points.extend(current.take());
+                    in_body = false;
+                }
+                "t" if in_text => in_text = false,
+                "t" => in_body = false,
+                // Paragraphs of one box are joined into a single item
+                "p" if in_body => {
+                    if let Some((_, point)) = current.as_mut()
+                        && !point.text.ends_with(' ')
+                    {
+                        point.text.push(' ');
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(Error::Parse {
+                    format: "powerpoint",
+                    entry: None,
+                    offset: Some(reader.error_position()),
+                    message: e.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|(order, _)| *order);
+    }
+    let by_id: HashMap<&str, &Point> = points.iter().map(|(id, p)| (id.as_str(), p)).collect();
+    let mut items = Vec::new();
+    let mut visited = HashSet::new();
+    let roots: Vec<&str> = points
+        .iter()
+        .filter(|(_, p)| p.kind == "doc")
+        .map(|(id, _)| id.as_str())
+        .collect();
+    if roots.is_empty() {
+        // Without the document point there is no hierarchy to follow
+        for (_, point) in &points {
+            if is_text_node(point) {
+                items.push((0, point.text.trim().to_string()));
+            }
+        }
+    }
+    for root in roots {
+        walk(root, 0, &by_id, &children, &mut visited, &mut items);
+    }
+    items.retain(|(_, text)| !text.is_empty());
+    Ok(items)
+}
+
+/// Add the children of `id` at `depth`, each followed by its own children.
+fn walk<'a>(
+    id: &'a str,
+    depth: usize,
+    points: &HashMap<&str, &Point>,
+    children: &'a HashMap<String, Vec<(u32, String)>>,
+    visited: &mut HashSet<&'a str>,
+    items: &mut Vec<(usize, String)>,
+) {
+    if !visited.insert(id) {
+        return;
+    }
+    for (_, child) in children.get(id).into_iter().flatten() {
+        let Some(point) = points.get(child.as_str()) else {
+            continue;
+        };
+        if is_text_node(point) {
+            items.push((depth, point.text.trim().to_string()));
+            walk(child, depth + 1, points, children, visited, items);
+        }
+    }
+}
+
+fn is_text_node(point: &Point) -> bool {
+    matches!(point.kind.as_str(), "node" | "asst")
+}
+
+fn read_point(e: &BytesStart) -> (String, Point) {
+    let point = Point {
+        kind: attr(e, "type").unwrap_or_else(|| "node".to_string()),
+        text: String::new(),
+    };
+    (attr(e, "modelId").unwrap_or_default(), point)
+}
+
+/// Record a `parOf` connection (the default type), which makes `destId` a
+/// child of `srcId` at position `srcOrd`.
+fn read_connection(e: &BytesStart, children: &mut HashMap<String, Vec<(u32, String)>>) {
+    if attr(e, "type").is_some_and(|t| t != "parOf") {
+        return;
+    }
+    let (Some(src), Some(dest)) = (attr(e, "srcId"), attr(e, "destId")) else {
+        return;
+    };
+    let order = attr(e, "srcOrd").and_then(|o| o.parse().ok()).unwrap_or(0);
+    children.entry(src).or_default().push((order, dest));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn data_xml(points: &[(&str, Option<&str>, &str)], cxns: &[(&str, &str, u32)]) -> String {
+        let points: String = points
+            .iter()
+            .map(|(id, kind, text)| {
+                let kind = kind.map(|k| format!(r#" type="{k}""#)).unwrap_or_default();
+                let body = if text.is_empty() {
+                    String::new()
+                } else {
+                    let paragraphs: String = text
+                        .split('\n')
+                        .map(|p| {
+                            format!("<a:p><a:r><a:rPr lang=\"en-US\"/><a:t>{p}</a:t></a:r></a:p>")
+                        })
+                        .collect();
+                    format!("<dgm:t><a:bodyPr/><a:lstStyle/>{paragraphs}</dgm:t>")
+                };
+                format!(r#"<dgm:pt modelId="{id}"{kind}><dgm:prSet/><dgm:spPr/>{body}</dgm:pt>"#)
+            })
+            .collect();
+        let cxns: String = cxns
+            .iter()
+            .map(|(src, dest, ord)| {
+                format!(r#"<dgm:cxn modelId="c-{dest}" srcId="{src}" destId="{dest}" srcOrd="{ord}" destOrd="0"/>"#)
+            })
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<dgm:dataModel xmlns:dgm="http://schemas.openxmlformats.org/drawingml/2006/diagram" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main">
+<dgm:ptLst>{points}</dgm:ptLst>
+<dgm:cxnLst>{cxns}<dgm:cxn modelId="p1" type="presOf" srcId="1" destId="pres1" srcOrd="0" destOrd="0"/></dgm:cxnLst>
+</dgm:dataModel>"#
+        )
+    }
+
+    #[rstest]
+    fn test_outline_follows_hierarchy_and_order() {
+        let xml = data_xml(
+            &[
+                ("0", Some("doc"), ""),
+                ("1", None, "Plan"),
+                ("2", None, "Build"),
+                ("3", None, "Test\nand ship"),
+                ("4", Some("asst"), "Review"),
+                ("5", Some("parTrans"), ""),
+                ("pres1", Some("pres"), "Ignored"),
+            ],
+            &[("0", "2", 1), ("0", "1", 0), ("2", "4", 1), ("2", "3", 0)],
+        );
+        assert_eq!(
+            outline(&xml).unwrap(),
+            vec![
+                (0, "Plan".to_string()),
+                (0, "Build".to_string()),
+                (1, "Test and ship".to_string()),
+                (1, "Review".to_string()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::no_document_point(&[("1", None, "A"), ("2", None, "B")], &[(0, "A"), (0, "B")])]
+    #[case::empty_nodes(&[("0", Some("doc"), ""), ("1", None, "")], &[])]
+    fn test_outline_edge_cases(
+        #[case] points: &[(&str, Option<&str>, &str)],
+        #[case] expected: &[(usize, &str)],
+    ) {
+        let xml = data_xml(points, &[("0", "1", 0)]);
+        let expected: Vec<(usize, String)> =
+            expected.iter().map(|(d, t)| (*d, t.to_string())).collect();
+        assert_eq!(outline(&xml).unwrap(), expected);
+    }
+
+    #[rstest]
+    fn test_outline_malformed() {
+        assert!(matches!(
+            outline("<dgm:dataModel><dgm:ptLst></dgm:dataModel>"),
+            Err(Error::Parse { .. })
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use super::{SlideShape, local_name};
+
+/// Shapes whose tops are this close, in EMU (a quarter of an inch), are
+/// on the same row and read left to right.
+const ROW_TOLERANCE: i64 = 228_600;
+
+/// Where a slide position is, in EMU from the slide's top-left corner.
+pub(super) type Point = (i64, i64);
+
+/// A placeholder shape: its `p:ph` type (`body` when unset) and index,
+/// which tie it to the placeholder of the slide layout it inherits from.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Placeholder {
+    pub kind: String,
+    pub idx: Option<String>,
+}
+
+impl Placeholder {
+    /// Keys to look the placeholder up by in a layout, best match first.
+    fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        self.idx
+            .iter()
+            .map(|idx| format!("idx:{idx}"))
+            .chain(std::iter::once(format!("type:{}", self.kind)))
+    }
+}
+
+/// The transform of a `p:grpSp` group, which places its children in a
+/// coordinate space of its own that maps onto the group's frame.
+#[derive(Debug, Default)]
+pub(super) struct GroupTransform {
+    off: Point,
+    ext: Point,
+    ch_off: Point,
+    ch_ext: Point,
+}
+
+impl GroupTransform {
+    /// Record one of the `a:off`, `a:ext`, `a:chOff` or `a:chExt` elements
+    /// of the group's `a:xfrm`.
+    pub(super) fn read(&mut self, e: &BytesStart) {
+        let name = local_name(e.name().as_ref());
+        let (field, x, y) = match name.as_str() {
+            "off" => (&mut self.off, "x", "y"),
+            "ext" => (&mut self.ext, "cx", "cy"),
+            "chOff" => (&mut self.ch_off, "x", "y"),
+            "chExt" => (&mut self.ch_ext, "cx", "cy"),
+            _ => return,
+        };
+        if let Some(point) = point(e, x, y) {
+            *field = point;
+        }
+    }
+
+    fn apply(&self, (x, y): Point) -> Point {
+        let scale = |v: i64, ch_off: i64, off: i64, ext: i64, ch_ext: i64| {
+            if ch_ext == 0 {
+                off + v - ch_off
+            } else {
+                off + (v - ch_off) * ext / ch_ext
+            }
+        };
+        (
+            scale(x, self.ch_off.0, self.off.0, self.ext.0, self.ch_ext.0),
+            scale(y, self.ch_off.1, self.off.1, self.ext.1, self.ch_ext.1),
+        )
+    }
+}
+
+/// Map the `a:off` of a shape inside `groups`, outermost first, onto the
+/// slide.
+pub(super) fn offset(e: &BytesStart, groups: &[GroupTransform]) -> Option<Point> {
+    let point = point(e, "x", "y")?;
+    Some(groups.iter().rev().fold(point, |p, group| group.apply(p)))
+}
+
+/// Read the positions of the placeholders of a slide layout or master,
+/// keyed by `idx:N` and `type:T`.
+pub(super) fn placeholder_positions(xml: &str) -> HashMap<String, Point> {
+    let mut positions = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut placeholder: Option<Placeholder> = None;
+    let mut position: Option<Point> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if local_name(e.name().as_ref()) == "sp" => {
+                placeholder = None;
+                position = None;
+            }
+            Ok(Event::Empty(e)) => match local_name(e.name().as_ref()).as_str() {
+                "ph" => placeholder = Some(read_placeholder(&e)),
+                "off" if position.is_none() => position = point(&e, "x", "y"),
+                _ => {}
+            },
+            Ok(Event::End(e)) if local_name(e.name().as_ref()) == "sp" => {
+                if let (Some(placeholder), Some(position)) = (placeholder.take(), position) {
+                    // Several placeholders may share a type; the first wins
+                    for key in placeholder.keys() {
+                        positions.entry(key).or_insert(position);
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    positions
+}
+
+pub(super) fn read_placeholder(e: &BytesStart) -> Placeholder {
+    let mut placeholder = Placeholder {
+        kind: "body".to_string(),
+        idx: None,
+    };
+    for attr in e.attributes().flatten() {
+        let value = String::from_utf8_lossy(&attr.value).to_string();
+        match attr.key.as_ref() {
+            b"type" => placeholder.kind = value,
+            b"idx" => placeholder.idx = Some(value),
+            _ => {}
+        }
+    }
+    placeholder
+}
+
+/// Reorder shapes the way a reader scans the slide: the title first, then
+/// row by row from the top, each row left to right. Placeholders without a
+/// position of their own take the one of their layout placeholder, and
+/// shapes without any stay after the shape before them.
+pub(super) fn reading_order(
+    shapes: Vec<SlideShape>,
+    layout: &HashMap<String, Point>,
+) -> Vec<SlideShape> {
+    let mut last = (0, 0);
+    let mut positioned: Vec<(Point, SlideShape)> = shapes
+        .into_iter()
+        .map(|shape| {
+            let position = shape
+                .position
+                .or_else(|| {
+                    let placeholder = shape.placeholder.as_ref()?;
+                    placeholder.keys().find_map(|key| layout.get(&key).copied())
+                })
+                .unwrap_or(last);
+            last = position;
+            (position, shape)
+        })
+        .collect();
+    positioned.sort_by_key(|((x, y), shape)| (!shape.is_title, *y, *x));
+
+    let mut ordered = Vec::with_capacity(positioned.len());
+    let mut row: Vec<(Point, SlideShape)> = Vec::new();
+    for (position, shape) in positioned {
+        if let Some(((_, top), first)) = row.first()
+            && (shape.is_title != first.is_title || position.1 - top > ROW_TOLERANCE)
+        {
+            row.sort_by_key(|((x, _), _)| *x);
+            ordered.extend(row.drain(..).map(|(_, shape)| shape));
+        }
+        row.push((position, shape));
+    }
+    row.sort_by_key(|((x, _), _)| *x);
+    ordered.extend(row.into_iter().map(|(_, shape)| shape));
+    ordered
+}
+
+fn point(e: &BytesStart, x: &str, y: &str) -> Option<Point> {
+    let value = |name: &str| {
+        e.attributes()
+            .flatten()
+            .find(|a| a.key.as_ref() == name.as_bytes())
+            .and_then(|a| String::from_utf8_lossy(&a.value).parse::<i64>().ok())
+    };
+    Some((value(x)?, value(y)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::formats::powerpoint::{Paragraph, TextRun};
+
+    fn shape(text: &str, position: Option<Point>) -> SlideShape {
+        SlideShape {
+            paragraphs: vec![Paragraph {
+                runs: vec![TextRun {
+                    text: text.to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            is_title: false,
+            is_subtitle: false,
+            has_bullets: false,
+            picture: None,
+            diagram: None,
+            position,
+            placeholder: None,
+        }
+    }
+
+    fn texts(shapes: &[SlideShape]) -> Vec<&str> {
+        shapes
+            .iter()
+            .map(|s| s.paragraphs[0].runs[0].text.as_str())
+            .collect()
+    }
+
+    #[rstest]
+    fn test_reading_order() {
+        let mut title = shape("Title", Some((0, 5_000_000)));
+        title.is_title = true;
+        let mut body = shape("Body", None);
+        body.placeholder = Some(Placeholder {
+            kind: "body".to_string(),
+            idx: Some("1".to_string()),
+        });
+        let shapes = vec![
+            shape("Footer", Some((100, 6_000_000))),
+            shape("Right", Some((4_000_000, 1_000_100))),
+            body,
+            shape("After body", None),
+            title,
+            // Slightly lower than "Right", but on the same row
+            shape("Left", Some((100, 1_000_000 + ROW_TOLERANCE))),
+        ];
+        let layout = HashMap::from([("idx:1".to_string(), (100, 2_000_000))]);
+        assert_eq!(
+            texts(&reading_order(shapes, &layout)),
+            vec!["Title", "Left", "Right", "Body", "After body", "Footer"]
+        );
+    }
+
+    #[rstest]
+    #[case::identity(r#"<a:off x="0" y="0"/><a:ext cx="10" cy="10"/><a:chOff x="0" y="0"/><a:chExt cx="10" cy="10"/>"#, (5, 7))]
+    #[case::moved(r#"<a:off x="100" y="200"/><a:ext cx="10" cy="10"/><a:chOff x="0" y="0"/><a:chExt cx="10" cy="10"/>"#, (105, 207))]
+    #[case::scaled(r#"<a:off x="100" y="0"/><a:ext cx="20" cy="20"/><a:chOff x="5" y="5"/><a:chExt cx="10" cy="10"/>"#, (100, 4))]
+    fn test_group_transform(#[case] xfrm: &str, #[case] expected: Point) {
+        let xml = format!(r#"<a:xfrm xmlns:a="a">{xfrm}</a:xfrm>"#);
+        let mut group = GroupTransform::default();
+        let mut reader = Reader::from_str(&xml);
+        while let Ok(event) = reader.read_event() {
+            match event {
+                Event::Empty(e) => group.read(&e),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        assert_eq!(group.apply((5, 7)), expected);
+    }
+
+    #[rstest]
+    fn test_placeholder_positions() {
+        let xml = r#"<p:sldLayout xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree>
+<p:sp><p:nvSpPr><p:nvPr><p:ph type="title"/></p:nvPr></p:nvSpPr><p:spPr><a:xfrm><a:off x="1" y="2"/><a:ext cx="3" cy="4"/></a:xfrm></p:spPr></p:sp>
+<p:sp><p:nvSpPr><p:nvPr><p:ph idx="1"/></p:nvPr></p:nvSpPr><p:spPr><a:xfrm><a:off x="5" y="6"/></a:xfrm></p:spPr></p:sp>
+<p:sp><p:nvSpPr><p:nvPr><p:ph idx="2"/></p:nvPr></p:nvSpPr><p:spPr/></p:sp>
+</p:spTree></p:cSld></p:sldLayout>"#;
+        let positions = placeholder_positions(xml);
+        assert_eq!(positions.get("type:title"), Some(&(1, 2)));
+        assert_eq!(positions.get("idx:1"), Some(&(5, 6)));
+        assert_eq!(positions.get("type:body"), Some(&(5, 6)));
+        assert_eq!(positions.get("idx:2"), None);
+    }
+}
@@ -1,9 +1,31 @@
 use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::options::ConvertOptions;
 
-pub struct SqliteConverter;
+use super::cells;
+
+pub mod diff;
+
+/// Column statistics are computed over at most this many rows of a table.
+const STATS_SAMPLE_ROWS: i64 = 100_000;
+
+/// Columns with more distinct values than this are reported as having
+/// "more than" this many, so counting stops early on unique columns.
+const STATS_DISTINCT_LIMIT: i64 = 20;
+
+#[derive(Default)]
+pub struct SqliteConverter {
+    options: ConvertOptions,
+}
+
+impl SqliteConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for SqliteConverter {
     fn format_name(&self) -> &'static str {
@@ -12,10 +34,15 @@ impl Converter for SqliteConverter {
 
     fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
         // Write input to a temporary file since rusqlite needs a file path
-        let tmp = std::env::temp_dir().join(format!("mq-conv-{}.db", std::process::id()));
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let tmp = std::env::temp_dir().join(format!(
+            "mq-conv-{}-{}.db",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
         std::fs::write(&tmp, input)?;
 
-        let result = convert_db(&tmp, writer);
+        let result = convert_db(&tmp, &self.options, writer);
 
         let _ = std::fs::remove_file(&tmp);
 
@@ -23,7 +50,17 @@ impl Converter for SqliteConverter {
     }
 }
 
-fn convert_db(path: &std::path::Path, writer: &mut dyn Write) -> Result<()> {
+struct Column {
+    name: String,
+    dtype: String,
+    pk: bool,
+    not_null: bool,
+    default: Option<String>,
+    /// Expression and storage of a generated column, e.g. `` `a + b` (stored) ``.
+    generated: Option<String>,
+}
+
+fn convert_db(path: &std::path::Path, options: &ConvertOptions, writer: &mut dyn Write) -> Result<()> {
     let conn = rusqlite::Connection::open_with_flags(
         path,
         rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
@@ -62,20 +99,50 @@ fn convert_db(path: &std::path::Path, writer: &mut dyn Write) -> Result<()> {
         writeln!(writer, "## {table}")?;
         writeln!(writer)?;
 
-        // Get column info
+        // Get column info; table_xinfo also lists generated columns
         let mut col_stmt = conn
-            .prepare(&format!("PRAGMA table_info(\"{}\")", table.replace('"', "\"\"")))
+            .prepare(&format!("PRAGMA table_xinfo(\"{}\")", table.replace('"', "\"\"")))
             .map_err(|e| Error::Conversion {
                 format: "sqlite",
                 message: e.to_string(),
             })?;
 
-        let columns: Vec<(String, String, bool)> = col_stmt
+        let create_sql: String = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type='table' AND name=?1",
+                [table],
+                |row| row.get(0),
+            )
+            .unwrap_or_default();
+        let expressions = generated_expressions(&create_sql);
+
+        let columns: Vec<Column> = col_stmt
             .query_map([], |row| {
+                let name = row.get::<_, String>(1)?;
+                // hidden: 1 = hidden virtual-table column, 2 = virtual, 3 = stored
+                let hidden = row.get::<_, i64>(6)?;
+                let generated = match hidden {
+                    2 | 3 => {
+                        let storage = if hidden == 3 { "stored" } else { "virtual" };
+                        let expr = expressions
+                            .iter()
+                            .find(|(col, _)| col.eq_ignore_ascii_case(&name))
+                            .map(|(_, expr)| format!("`{expr}` "))
+                            .unwrap_or_default();
+                        Some(format!("{expr}({storage})"))
+                    }
+                    _ => None,
+                };
                 Ok((
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, bool>(5)?,
+                    hidden,
+                    Column {
+                        name,
+                        dtype: row.get::<_, String>(2)?,
+                        not_null: row.get::<_, bool>(3)?,
+                        default: row.get::<_, Option<String>>(4)?,
+                        pk: row.get::<_, bool>(5)?,
+                        generated,
+                    },
                 ))
             })
             .map_err(|e| Error::Conversion {
@@ -83,16 +150,40 @@ fn convert_db(path: &std::path::Path, writer: &mut dyn Write) -> Result<()> {
                 message: e.to_string(),
             })?
             .filter_map(|r| r.ok())
+            .filter(|(hidden, _)| *hidden != 1)
+            .map(|(_, column)| column)
             .collect();
 
         // Schema
-        writeln!(writer, "| Column | Type | PK |")?;
-        writeln!(writer, "|--------|------|----|")?;
-        for (name, dtype, pk) in &columns {
-            let pk_mark = if *pk { "yes" } else { "" };
-            writeln!(writer, "| {name} | {dtype} | {pk_mark} |")?;
+        if !options.sqlite.data_only {
+            let has_generated = columns.iter().any(|c| c.generated.is_some());
+            if has_generated {
+                writeln!(writer, "| Column | Type | PK | Not Null | Default | Generated |")?;
+                writeln!(writer, "|--------|------|----|----------|---------|-----------|")?;
+            } else {
+                writeln!(writer, "| Column | Type | PK | Not Null | Default |")?;
+                writeln!(writer, "|--------|------|----|----------|---------|")?;
+            }
+            for column in &columns {
+                let pk_mark = if column.pk { "yes" } else { "" };
+                let not_null_mark = if column.not_null { "yes" } else { "" };
+                let default = column.default.as_deref().unwrap_or_default().replace('|', "\\|");
+                write!(
+                    writer,
+                    "| {} | {} | {pk_mark} | {not_null_mark} | {default} |",
+                    column.name, column.dtype
+                )?;
+                if has_generated {
+                    let generated = column.generated.as_deref().unwrap_or_default();
+                    write!(writer, " {} |", generated.replace('|', "\\|"))?;
+                }
+                writeln!(writer)?;
+            }
+            if options.sqlite.schema_only {
+                continue;
+            }
+            writeln!(writer)?;
         }
-        writeln!(writer)?;
 
         // Row count
         let count: i64 = conn
@@ -105,11 +196,12 @@ fn convert_db(path: &std::path::Path, writer: &mut dyn Write) -> Result<()> {
 
         writeln!(writer, "**Rows**: {count}")?;
 
-        // Preview first 10 rows
+        // Preview first 10 rows, or every row when exporting data
+        let limit = if options.sqlite.data_only { count } else { 10 };
         if count > 0 && !columns.is_empty() {
             writeln!(writer)?;
 
-            let col_names: Vec<&str> = columns.iter().map(|(n, _, _)| n.as_str()).collect();
+            let col_names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
 
             // Header
             write!(writer, "|")?;
@@ -125,9 +217,9 @@ fn convert_db(path: &std::path::Path, writer: &mut dyn Write) -> Result<()> {
             }
             writeln!(writer)?;
 
-            // Data (limit to 10 rows)
+            // Data
             let query = format!(
-                "SELECT * FROM \"{}\" LIMIT 10",
+                "SELECT * FROM \"{}\" LIMIT {limit}",
                 table.replace('"', "\"\"")
             );
             let mut data_stmt = conn.prepare(&query).map_err(|e| Error::Conversion {
@@ -149,25 +241,308 @@ fn convert_db(path: &std::path::Path, writer: &mut dyn Write) -> Result<()> {
                 for i in 0..col_count {
                     let val: String = row
                         .get::<_, rusqlite::types::Value>(i)
-                        .map(|v| match v {
-                            rusqlite::types::Value::Null => "NULL".to_string(),
-                            rusqlite::types::Value::Integer(n) => n.to_string(),
-                            rusqlite::types::Value::Real(f) => f.to_string(),
-                            rusqlite::types::Value::Text(s) => s.replace('|', "\\|"),
-                            rusqlite::types::Value::Blob(b) => format!("[BLOB {} bytes]", b.len()),
-                        })
+                        .map(|v| format_value(v, options.sanitize_cells))
                         .unwrap_or_default();
                     write!(writer, " {val} |")?;
                 }
                 writeln!(writer)?;
             }
 
-            if count > 10 {
+            if count > limit {
                 writeln!(writer)?;
-                writeln!(writer, "*Showing 10 of {count} rows*")?;
+                writeln!(writer, "*Showing {limit} of {count} rows*")?;
             }
         }
+
+        if options.sqlite.column_stats && count > 0 && !columns.is_empty() {
+            write_column_stats(&conn, table, &columns, count, options, writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn format_value(value: rusqlite::types::Value, sanitize: bool) -> String {
+    match value {
+        rusqlite::types::Value::Null => "NULL".to_string(),
+        rusqlite::types::Value::Integer(n) => n.to_string(),
+        rusqlite::types::Value::Real(f) => f.to_string(),
+        rusqlite::types::Value::Text(s) if sanitize => cells::sanitize(&s).replace('|', "\\|"),
+        rusqlite::types::Value::Text(s) => s.replace('|', "\\|"),
+        rusqlite::types::Value::Blob(b) => format!("[BLOB {} bytes]", b.len()),
+    }
+}
+
+/// Profile each column of a table: its null count, smallest and largest
+/// value and, for columns with few of them, the number of distinct values.
+/// Large tables are profiled from their first `STATS_SAMPLE_ROWS` rows.
+fn write_column_stats(
+    conn: &rusqlite::Connection,
+    table: &str,
+    columns: &[Column],
+    count: i64,
+    options: &ConvertOptions,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    writeln!(writer)?;
+    writeln!(writer, "### Column Statistics")?;
+    writeln!(writer)?;
+    writeln!(writer, "| Column | Nulls | Distinct | Min | Max |")?;
+    writeln!(writer, "|---|---|---|---|---|")?;
+
+    let sample = format!(
+        "SELECT * FROM \"{}\" LIMIT {STATS_SAMPLE_ROWS}",
+        table.replace('"', "\"\"")
+    );
+    for column in columns {
+        let name = format!("\"{}\"", column.name.replace('"', "\"\""));
+        let stats = conn.query_row(
+            &format!("SELECT SUM({name} IS NULL), MIN({name}), MAX({name}) FROM ({sample})"),
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, Option<i64>>(0)?.unwrap_or_default(),
+                    row.get::<_, rusqlite::types::Value>(1)?,
+                    row.get::<_, rusqlite::types::Value>(2)?,
+                ))
+            },
+        );
+        let distinct = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM (SELECT DISTINCT {name} FROM ({sample}) \
+                 WHERE {name} IS NOT NULL LIMIT {})",
+                STATS_DISTINCT_LIMIT + 1
+            ),
+            [],
+            |row| row.get::<_, i64>(0),
+        );
+        let (nulls, min, max) = stats.map_err(|e| Error::Conversion {
+            format: "sqlite",
+            message: e.to_string(),
+        })?;
+        let distinct = match distinct {
+            Ok(n) if n > STATS_DISTINCT_LIMIT => format!("> {STATS_DISTINCT_LIMIT}"),
+            Ok(n) => n.to_string(),
+            Err(_) => String::new(),
+        };
+        // MIN and MAX skip NULLs, so a column of only NULLs has neither
+        let bound = |value| match value {
+            rusqlite::types::Value::Null => String::new(),
+            value => format_value(value, options.sanitize_cells),
+        };
+        writeln!(
+            writer,
+            "| {} | {nulls} | {distinct} | {} | {} |",
+            column.name,
+            bound(min),
+            bound(max)
+        )?;
     }
 
+    if count > STATS_SAMPLE_ROWS {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "*Statistics of the first {STATS_SAMPLE_ROWS} of {count} rows*"
+        )?;
+    }
     Ok(())
 }
+
+/// Extract `(column, expression)` pairs for generated columns from a
+/// `CREATE TABLE` statement, since PRAGMA output does not include them.
+fn generated_expressions(sql: &str) -> Vec<(String, String)> {
+    let Some(body) = sql
+        .find('(')
+        .zip(sql.rfind(')'))
+        .filter(|(start, end)| start < end)
+        .map(|(start, end)| &sql[start + 1..end])
+    else {
+        return Vec::new();
+    };
+
+    split_top_level(body)
+        .into_iter()
+        .filter_map(|def| {
+            let def = def.trim();
+            let name = def
+                .split_whitespace()
+                .next()?
+                .trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'));
+            let upper = def.to_ascii_uppercase();
+            // `[GENERATED ALWAYS] AS (expr)`: find an `AS` keyword followed by `(`
+            let open = upper.match_indices("AS").find_map(|(pos, _)| {
+                let before = upper[..pos].chars().next_back();
+                let after = upper[pos + 2..].trim_start();
+                (before.is_some_and(char::is_whitespace) && after.starts_with('('))
+                    .then(|| def.len() - after.len())
+            })?;
+            let mut depth = 0;
+            for (i, c) in def[open..].char_indices() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            let expr = def[open + 1..open + i].trim().to_string();
+                            return Some((name.to_string(), expr));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            None
+        })
+        .collect()
+}
+
+/// Split a column list on commas that are not nested in parentheses or quotes.
+fn split_top_level(body: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&body[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::SqliteOptions;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn make_db(name: &str, statements: &str) -> Vec<u8> {
+        let path = std::env::temp_dir().join(format!(
+            "mq-conv-test-{}-{name}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(statements).unwrap();
+        drop(conn);
+        let bytes = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        bytes
+    }
+
+    fn convert(input: &[u8], sqlite: SqliteOptions) -> String {
+        let converter = SqliteConverter::new(ConvertOptions {
+            sqlite,
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(input, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    const USERS: &str = "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+        INSERT INTO users (name) VALUES ('a'), ('b'), ('c'), ('d'), ('e'), ('f'),
+            ('g'), ('h'), ('i'), ('j'), ('k'), ('l');";
+
+    #[rstest]
+    fn test_default_includes_schema_and_preview() {
+        let output = convert(&make_db("default", USERS), SqliteOptions::default());
+        assert!(output.contains("| Column | Type | PK | Not Null | Default |"));
+        assert!(output.contains("**Rows**: 12"));
+        assert!(output.contains("*Showing 10 of 12 rows*"));
+    }
+
+    #[rstest]
+    fn test_schema_only() {
+        let output = convert(
+            &make_db("schema", USERS),
+            SqliteOptions {
+                schema_only: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            output,
+            "# Database\n\n**Tables**: 1\n\n## users\n\n\
+             | Column | Type | PK | Not Null | Default |\n|--------|------|----|----------|---------|\n\
+             | id | INTEGER | yes |  |  |\n| name | TEXT |  |  |  |\n"
+        );
+    }
+
+    #[rstest]
+    fn test_data_only_exports_every_row() {
+        let output = convert(
+            &make_db("data", USERS),
+            SqliteOptions {
+                data_only: true,
+                ..Default::default()
+            },
+        );
+        assert!(!output.contains("| Column | Type | PK |"));
+        assert!(output.contains("| 12 | l |"));
+        assert!(!output.contains("*Showing"));
+    }
+
+    #[rstest]
+    fn test_column_stats() {
+        let db = make_db(
+            "stats",
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, status TEXT, note TEXT, total REAL);
+            WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 30)
+            INSERT INTO orders (status, note, total)
+                SELECT CASE i % 3 WHEN 0 THEN 'paid' WHEN 1 THEN 'open' ELSE 'void' END,
+                    CASE i % 10 WHEN 0 THEN 'a|b' WHEN 5 THEN 'c' END,
+                    i * 1.5
+                FROM n;",
+        );
+        let output = convert(
+            &db,
+            SqliteOptions {
+                column_stats: true,
+                ..Default::default()
+            },
+        );
+        let stats = output.split("### Column Statistics\n\n").nth(1).unwrap();
+        assert_eq!(
+            stats,
+            "| Column | Nulls | Distinct | Min | Max |\n|---|---|---|---|---|\n\
+             | id | 0 | > 20 | 1 | 30 |\n\
+             | status | 0 | 3 | open | void |\n\
+             | note | 24 | 2 | a\\|b | c |\n\
+             | total | 0 | > 20 | 1.5 | 45 |\n"
+        );
+    }
+
+    #[rstest]
+    fn test_column_constraints_and_generated() {
+        let db = make_db(
+            "generated",
+            "CREATE TABLE items (
+                price REAL NOT NULL DEFAULT 0,
+                qty INTEGER DEFAULT 1,
+                total REAL GENERATED ALWAYS AS (price * qty) STORED,
+                label TEXT AS (upper(substr('a,b', 1, 1))),
+                created TEXT DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO items (price, qty) VALUES (2.5, 4);",
+        );
+        let output = convert(&db, SqliteOptions::default());
+        assert!(output.contains("| Column | Type | PK | Not Null | Default | Generated |"));
+        assert!(output.contains("| price | REAL |  | yes | 0 |  |"));
+        assert!(output.contains("| total | REAL |  |  |  | `price * qty` (stored) |"));
+        assert!(output.contains("| label | TEXT |  |  |  | `upper(substr('a,b', 1, 1))` (virtual) |"));
+        assert!(output.contains("| created | TEXT |  |  | CURRENT_TIMESTAMP |  |"));
+        assert!(output.contains("| 2.5 | 4 | 10 | A | "));
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::warnings;
+
+/// The tables of a database, with their columns and row counts.
+#[derive(Debug, Default, PartialEq)]
+pub struct Schema {
+    tables: BTreeMap<String, Table>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Table {
+    /// Column names and declared types, in table order.
+    columns: Vec<(String, String)>,
+    /// `None` when the table could not be counted.
+    rows: Option<i64>,
+}
+
+/// One difference between two databases.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    TableAdded {
+        table: String,
+        columns: usize,
+        rows: Option<i64>,
+    },
+    TableRemoved {
+        table: String,
+        columns: usize,
+        rows: Option<i64>,
+    },
+    ColumnAdded {
+        table: String,
+        column: String,
+        dtype: String,
+    },
+    ColumnRemoved {
+        table: String,
+        column: String,
+        dtype: String,
+    },
+    ColumnRetyped {
+        table: String,
+        column: String,
+        old: String,
+        new: String,
+    },
+    /// Reported for every table in both databases, changed or not.
+    RowCount {
+        table: String,
+        old: Option<i64>,
+        new: Option<i64>,
+    },
+}
+
+impl Schema {
+    /// Read the schema and row counts of the database at `path`. SQLite's
+    /// internal tables, such as `sqlite_sequence`, and the hidden columns of
+    /// virtual tables are left out; generated columns are kept.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn =
+            rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(error)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT name FROM sqlite_master WHERE type='table' \
+                 AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' ORDER BY name",
+            )
+            .map_err(error)?;
+        let names: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(error)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut tables = BTreeMap::new();
+        for name in names {
+            let quoted = name.replace('"', "\"\"");
+            // table_info leaves out generated columns
+            let mut col_stmt = conn
+                .prepare(&format!("PRAGMA table_xinfo(\"{quoted}\")"))
+                .map_err(error)?;
+            let columns = col_stmt
+                .query_map([], |row| {
+                    Ok((row.get(1)?, row.get(2)?, row.get::<_, i64>(6)?))
+                })
+                .map_err(error)?
+                .filter_map(|r| r.ok())
+                .filter(|(_, _, hidden)| *hidden != 1)
+                .map(|(name, dtype, _)| (name, dtype))
+                .collect();
+            let rows = conn
+                .query_row(&format!("SELECT COUNT(*) FROM \"{quoted}\""), [], |row| {
+                    row.get(0)
+                })
+                .inspect_err(|e| warnings::warn(format!("Rows of table {name} not counted: {e}")))
+                .ok();
+            tables.insert(name, Table { columns, rows });
+        }
+        Ok(Self { tables })
+    }
+}
+
+fn error(e: rusqlite::Error) -> Error {
+    Error::Conversion {
+        format: "sqlite",
+        message: e.to_string(),
+    }
+}
+
+/// Compare two databases. Tables and columns are matched by name, so a
+/// rename reads as one removed and one added.
+pub fn diff(old: &Schema, new: &Schema) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (name, table) in &old.tables {
+        let Some(new_table) = new.tables.get(name) else {
+            changes.push(Change::TableRemoved {
+                table: name.clone(),
+                columns: table.columns.len(),
+                rows: table.rows,
+            });
+            continue;
+        };
+        let find = |columns: &[(String, String)], column: &str| {
+            columns
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(column))
+                .map(|(_, dtype)| dtype.clone())
+        };
+        for (column, dtype) in &table.columns {
+            match find(&new_table.columns, column) {
+                None => changes.push(Change::ColumnRemoved {
+                    table: name.clone(),
+                    column: column.clone(),
+                    dtype: dtype.clone(),
+                }),
+                Some(new_dtype) if !new_dtype.eq_ignore_ascii_case(dtype) => {
+                    changes.push(Change::ColumnRetyped {
+                        table: name.clone(),
+                        column: column.clone(),
+                        old: dtype.clone(),
+                        new: new_dtype,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (column, dtype) in &new_table.columns {
+            if find(&table.columns, column).is_none() {
+                changes.push(Change::ColumnAdded {
+                    table: name.clone(),
+                    column: column.clone(),
+                    dtype: dtype.clone(),
+                });
+            }
+        }
+        changes.push(Change::RowCount {
+            table: name.clone(),
+            old: table.rows,
+            new: new_table.rows,
+        });
+    }
+    for (name, table) in &new.tables {
+        if !old.tables.contains_key(name) {
+            changes.push(Change::TableAdded {
+                table: name.clone(),
+                columns: table.columns.len(),
+                rows: table.rows,
+            });
+        }
+    }
+    changes
+}
+
+/// Write a Markdown report of `changes` between the databases named `old`
+/// and `new`: the schema changes, then the row count of every table the
+/// two have in common.
+pub fn write_diff_as_markdown(
+    writer: &mut dyn Write,
+    old: &str,
+    new: &str,
+    changes: &[Change],
+) -> Result<()> {
+    writeln!(writer, "# Changes from `{old}` to `{new}`")?;
+    writeln!(writer)?;
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut column_changes = Vec::new();
+    let mut row_counts = Vec::new();
+    let mut rows_changed = 0;
+    for change in changes {
+        match change {
+            Change::TableAdded {
+                table,
+                columns,
+                rows,
+            } => added.push([code(table), columns.to_string(), row_count(*rows)]),
+            Change::TableRemoved {
+                table,
+                columns,
+                rows,
+            } => removed.push([code(table), columns.to_string(), row_count(*rows)]),
+            Change::ColumnAdded {
+                table,
+                column,
+                dtype,
+            } => column_changes.push([
+                code(table),
+                code(column),
+                "Added".to_string(),
+                String::new(),
+                dtype.clone(),
+            ]),
+            Change::ColumnRemoved {
+                table,
+                column,
+                dtype,
+            } => column_changes.push([
+                code(table),
+                code(column),
+                "Removed".to_string(),
+                dtype.clone(),
+                String::new(),
+            ]),
+            Change::ColumnRetyped {
+                table,
+                column,
+                old,
+                new,
+            } => column_changes.push([
+                code(table),
+                code(column),
+                "Type changed".to_string(),
+                old.clone(),
+                new.clone(),
+            ]),
+            Change::RowCount { table, old, new } => {
+                let delta = old.zip(*new).map(|(old, new)| new - old);
+                if delta != Some(0) {
+                    rows_changed += 1;
+                }
+                row_counts.push([
+                    code(table),
+                    row_count(*old),
+                    row_count(*new),
+                    delta.map(|delta| format!("{delta:+}")).unwrap_or_default(),
+                ]);
+            }
+        }
+    }
+
+    if added.is_empty() && removed.is_empty() && column_changes.is_empty() && rows_changed == 0 {
+        writeln!(writer, "*No differences*")?;
+        return Ok(());
+    }
+    writeln!(
+        writer,
+        "{} added, {} removed, {}, {} changed",
+        plural(added.len(), "table", "tables"),
+        removed.len(),
+        plural(column_changes.len(), "column change", "column changes"),
+        plural(rows_changed, "row count", "row counts")
+    )?;
+
+    write_section(
+        writer,
+        "Added Tables",
+        &["Table", "Columns", "Rows"],
+        &added,
+    )?;
+    write_section(
+        writer,
+        "Removed Tables",
+        &["Table", "Columns", "Rows"],
+        &removed,
+    )?;
+    write_section(
+        writer,
+        "Column Changes",
+        &["Table", "Column", "Change", "Before", "After"],
+        &column_changes,
+    )?;
+    write_section(
+        writer,
+        "Row Counts",
+        &["Table", "Before", "After", "Delta"],
+        &row_counts,
+    )?;
+    Ok(())
+}
+
+/// A row count, or `?` for a table that could not be counted.
+fn row_count(rows: Option<i64>) -> String {
+    rows.map_or_else(|| "?".to_string(), |rows| rows.to_string())
+}
+
+fn plural(n: usize, one: &str, many: &str) -> String {
+    format!("{n} {}", if n == 1 { one } else { many })
+}
+
+fn code(name: &str) -> String {
+    format!("`{}`", name.replace('|', "\\|"))
+}
+
+fn write_section<const N: usize>(
+    writer: &mut dyn Write,
+    title: &str,
+    headers: &[&str; N],
+    rows: &[[String; N]],
+) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer)?;
+    writeln!(writer, "## {title}")?;
+    writeln!(writer)?;
+    writeln!(writer, "| {} |", headers.join(" | "))?;
+    writeln!(writer, "|{}", "---|".repeat(N))?;
+    for row in rows {
+        writeln!(writer, "| {} |", row.join(" | "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn open_db(name: &str, statements: &str) -> Schema {
+        let path =
+            std::env::temp_dir().join(format!("mq-conv-diff-{}-{name}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(statements).unwrap();
+        drop(conn);
+        let schema = Schema::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        schema
+    }
+
+    fn report(old: &Schema, new: &Schema) -> String {
+        let mut output = Vec::new();
+        write_diff_as_markdown(&mut output, "a.db", "b.db", &diff(old, new)).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_schema_and_row_count_changes() {
+        let old = open_db(
+            "old",
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT, age TEXT);
+             CREATE TABLE legacy (id INTEGER);
+             INSERT INTO users (name) VALUES ('a'), ('b');",
+        );
+        let new = open_db(
+            "new",
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT, age INTEGER, email TEXT,
+                 tag TEXT GENERATED ALWAYS AS (upper(name)) VIRTUAL);
+             CREATE TABLE orders (id INTEGER, user_id INTEGER);
+             INSERT INTO users (name) VALUES ('a'), ('b'), ('c');",
+        );
+        assert_eq!(
+            report(&old, &new),
+            "# Changes from `a.db` to `b.db`\n\n\
+             1 table added, 1 removed, 3 column changes, 1 row count changed\n\n\
+             ## Added Tables\n\n| Table | Columns | Rows |\n|---|---|---|\n| `orders` | 2 | 0 |\n\n\
+             ## Removed Tables\n\n| Table | Columns | Rows |\n|---|---|---|\n| `legacy` | 1 | 0 |\n\n\
+             ## Column Changes\n\n| Table | Column | Change | Before | After |\n|---|---|---|---|---|\n\
+             | `users` | `age` | Type changed | TEXT | INTEGER |\n\
+             | `users` | `email` | Added |  | TEXT |\n\
+             | `users` | `tag` | Added |  | TEXT |\n\n\
+             ## Row Counts\n\n| Table | Before | After | Delta |\n|---|---|---|---|\n\
+             | `users` | 2 | 3 | +1 |\n"
+        );
+    }
+
+    #[rstest]
+    fn test_identical_databases() {
+        let schema = "CREATE TABLE t (id INTEGER); INSERT INTO t VALUES (1);";
+        assert_eq!(
+            report(&open_db("same-a", schema), &open_db("same-b", schema)),
+            "# Changes from `a.db` to `b.db`\n\n*No differences*\n"
+        );
+    }
+}
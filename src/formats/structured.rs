@@ -1,10 +1,17 @@
+pub mod diff;
+pub mod schema;
+
 use std::io::Write;
 
-use crate::error::Result;
+use crate::detect::Format;
+use crate::error::{Error, Result};
+use crate::metadata::yaml_scalar;
+use crate::options::StructuredOptions;
 
 /// A format-agnostic value representation for structured data.
 /// Each format converter converts its native value type into this enum,
 /// then uses `write_value_as_markdown` to produce structured markdown output.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Null,
     Bool(bool),
@@ -17,6 +24,27 @@ pub enum Value {
 }
 
 impl Value {
+    /// Recursively sort object keys alphabetically, so output does not depend
+    /// on the key order of the source document.
+    pub fn sort_keys(&mut self) {
+        match self {
+            Value::Array(items) => items.iter_mut().for_each(Value::sort_keys),
+            Value::Object(entries) => {
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                entries.iter_mut().for_each(|(_, v)| v.sort_keys());
+            }
+            _ => {}
+        }
+    }
+
+    /// The value of `key` when this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
     fn is_primitive(&self) -> bool {
         matches!(
             self,
@@ -34,6 +62,113 @@ impl Value {
             Value::Array(_) | Value::Object(_) => String::new(),
         }
     }
+
+    /// The value on one line: scalars as they are, arrays joined with commas
+    /// and objects as `key: value` pairs.
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    pub(crate) fn inline(&self) -> String {
+        match self {
+            Value::Array(items) => items
+                .iter()
+                .map(Value::inline)
+                .collect::<Vec<_>>()
+                .join(", "),
+            Value::Object(entries) => entries
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::Null => key.clone(),
+                    value => format!("{key}: {}", value.inline()),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => self.display_primitive(),
+        }
+    }
+}
+
+/// Parse a JSON, YAML or TOML document.
+pub fn parse(format: Format, input: &[u8]) -> Result<Value> {
+    #[cfg(not(any(feature = "json", feature = "yaml", feature = "toml_conv")))]
+    let _ = input;
+    match format {
+        #[cfg(feature = "json")]
+        Format::Json => super::json::parse(input),
+        #[cfg(not(feature = "json"))]
+        Format::Json => Err(Error::FeatureDisabled("json".into())),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => super::yaml::parse(input),
+        #[cfg(not(feature = "yaml"))]
+        Format::Yaml => Err(Error::FeatureDisabled("yaml".into())),
+        #[cfg(feature = "toml_conv")]
+        Format::Toml => super::toml_conv::parse(input),
+        #[cfg(not(feature = "toml_conv"))]
+        Format::Toml => Err(Error::FeatureDisabled("toml".into())),
+        other => Err(Error::UnsupportedFormat(format!(
+            "{other} (expected JSON, YAML or TOML)"
+        ))),
+    }
+}
+
+/// Write a parsed document as Markdown, applying the structured options.
+/// With `front_matter`, top-level primitive entries of an object become YAML
+/// front matter and only the nested entries are rendered as the body.
+pub fn write_document(
+    writer: &mut dyn Write,
+    mut value: Value,
+    options: &StructuredOptions,
+) -> Result<()> {
+    if options.sort_keys {
+        value.sort_keys();
+    }
+    match value {
+        Value::Object(entries) if options.front_matter => {
+            let nested = write_front_matter(writer, entries)?;
+            write_object(writer, &nested, 1)
+        }
+        value => write_value_as_markdown(writer, &value),
+    }
+}
+
+/// Write the primitive entries of an object as YAML front matter, returning
+/// the nested entries left for the body.
+pub(crate) fn write_front_matter(
+    writer: &mut dyn Write,
+    entries: Vec<(String, Value)>,
+) -> Result<Vec<(String, Value)>> {
+    let (scalars, nested): (Vec<_>, Vec<_>) =
+        entries.into_iter().partition(|(_, v)| v.is_primitive());
+    if !scalars.is_empty() {
+        writeln!(writer, "---")?;
+        for (key, value) in &scalars {
+            writeln!(writer, "{}: {}", yaml_scalar(key), front_matter_value(value))?;
+        }
+        writeln!(writer, "---")?;
+        if !nested.is_empty() {
+            writeln!(writer)?;
+        }
+    }
+    Ok(nested)
+}
+
+/// A primitive as a YAML scalar of the same type, quoting strings that YAML
+/// would otherwise read as a number, boolean or null.
+fn front_matter_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::String(s) => {
+            let lower = s.to_ascii_lowercase();
+            let ambiguous = matches!(
+                lower.as_str(),
+                "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~"
+            ) || s.parse::<f64>().is_ok();
+            if ambiguous {
+                format!("\"{s}\"")
+            } else {
+                yaml_scalar(s)
+            }
+        }
+        other => other.display_primitive(),
+    }
 }
 
 /// Write a structured value as markdown to the given writer.
@@ -204,7 +339,7 @@ fn try_as_table(items: &[Value]) -> Option<TableData> {
     Some(TableData { headers, rows })
 }
 
-fn write_markdown_table(
+pub(crate) fn write_markdown_table(
     writer: &mut dyn Write,
     headers: &[String],
     rows: &[Vec<String>],
@@ -243,7 +378,6 @@ fn escape_pipe(s: &str) -> String {
 
 // --- Conversions from format-specific value types ---
 
-#[cfg(feature = "json")]
 impl From<serde_json::Value> for Value {
     fn from(v: serde_json::Value) -> Self {
         match v {
@@ -460,6 +594,45 @@ mod tests {
         assert!(!output.contains("#######"));
     }
 
+    #[rstest]
+    fn test_sort_keys_recursive() {
+        let mut value = Value::Object(vec![
+            ("b".into(), Value::Integer(2)),
+            (
+                "a".into(),
+                Value::Array(vec![Value::Object(vec![
+                    ("z".into(), Value::Integer(1)),
+                    ("y".into(), Value::Integer(2)),
+                ])]),
+            ),
+        ]);
+        value.sort_keys();
+        let output = render(value);
+        assert_eq!(output, "# a\n\n| y | z |\n|---|---|\n| 2 | 1 |\n\n| Key | Value |\n|---|---|\n| b | 2 |\n\n");
+    }
+
+    #[rstest]
+    fn test_front_matter() {
+        let value = Value::Object(vec![
+            ("title".into(), Value::String("My site: home".into())),
+            ("version".into(), Value::String("1.0".into())),
+            ("draft".into(), Value::Bool(false)),
+            ("menu".into(), Value::Array(vec![Value::String("about".into())])),
+            ("weight".into(), Value::Integer(3)),
+        ]);
+        let options = StructuredOptions {
+            front_matter: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        write_document(&mut output, value, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "---\ntitle: \"My site: home\"\nversion: \"1.0\"\ndraft: false\nweight: 3\n---\n\n\
+             # menu\n\n- about\n\n"
+        );
+    }
+
     #[rstest]
     fn test_mixed_array_rendering() {
         let value = Value::Array(vec![
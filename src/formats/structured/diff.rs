@@ -0,0 +1,282 @@
+use std::io::Write;
+
+use super::{Value, escape_pipe};
+use crate::error::Result;
+
+/// One difference between two documents, reported at a leaf: a primitive
+/// value or an empty array or object.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Added { path: String, value: Value },
+    Removed { path: String, value: Value },
+    Changed { path: String, old: Value, new: Value },
+}
+
+/// Compare two documents. Object entries are matched by key regardless of
+/// order; array items are compared by position.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    compare(old, new, String::new(), &mut changes);
+    changes
+}
+
+fn compare(old: &Value, new: &Value, path: String, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old_entries), Value::Object(new_entries)) => {
+            for (key, old_value) in old_entries {
+                let child = key_path(&path, key);
+                match new_entries.iter().find(|(k, _)| k == key) {
+                    Some((_, new_value)) => compare(old_value, new_value, child, changes),
+                    None => removed(old_value, child, changes),
+                }
+            }
+            for (key, new_value) in new_entries {
+                if !old_entries.iter().any(|(k, _)| k == key) {
+                    added(new_value, key_path(&path, key), changes);
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for i in 0..old_items.len().max(new_items.len()) {
+                let child = format!("{path}[{i}]");
+                match (old_items.get(i), new_items.get(i)) {
+                    (Some(old_item), Some(new_item)) => compare(old_item, new_item, child, changes),
+                    (Some(old_item), None) => removed(old_item, child, changes),
+                    (None, Some(new_item)) => added(new_item, child, changes),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old == new => {}
+        _ if is_leaf(old) && is_leaf(new) => changes.push(Change::Changed {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        // A value changed shape, e.g. from a string to an object
+        _ => {
+            removed(old, path.clone(), changes);
+            added(new, path, changes);
+        }
+    }
+}
+
+fn added(value: &Value, path: String, changes: &mut Vec<Change>) {
+    for (path, value) in leaves(value, path) {
+        changes.push(Change::Added { path, value });
+    }
+}
+
+fn removed(value: &Value, path: String, changes: &mut Vec<Change>) {
+    for (path, value) in leaves(value, path) {
+        changes.push(Change::Removed { path, value });
+    }
+}
+
+fn is_leaf(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.is_empty(),
+        Value::Object(entries) => entries.is_empty(),
+        _ => true,
+    }
+}
+
+fn leaves(value: &Value, path: String) -> Vec<(String, Value)> {
+    match value {
+        Value::Array(items) if !items.is_empty() => items
+            .iter()
+            .enumerate()
+            .flat_map(|(i, item)| leaves(item, format!("{path}[{i}]")))
+            .collect(),
+        Value::Object(entries) if !entries.is_empty() => entries
+            .iter()
+            .flat_map(|(key, item)| leaves(item, key_path(&path, key)))
+            .collect(),
+        _ => vec![(path, value.clone())],
+    }
+}
+
+/// Append `key` to a dotted path, quoting keys that are not plain
+/// identifiers.
+pub(super) fn key_path(path: &str, key: &str) -> String {
+    let plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    match (path.is_empty(), plain) {
+        (true, true) => key.to_string(),
+        (false, true) => format!("{path}.{key}"),
+        (_, false) => format!("{path}[{}]", literal(&Value::String(key.to_string()))),
+    }
+}
+
+/// A value as it would be written in JSON, so strings and numbers stay
+/// distinguishable.
+pub(super) fn literal(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::String(s) => serde_json::to_string(s).unwrap_or_else(|_| s.clone()),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+        other => other.display_primitive(),
+    }
+}
+
+/// Write a Markdown report of `changes` between the documents named `old`
+/// and `new`.
+pub fn write_diff_as_markdown(
+    writer: &mut dyn Write,
+    old: &str,
+    new: &str,
+    changes: &[Change],
+) -> Result<()> {
+    writeln!(writer, "# Changes from `{old}` to `{new}`")?;
+    writeln!(writer)?;
+    if changes.is_empty() {
+        writeln!(writer, "*No differences*")?;
+        return Ok(());
+    }
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for change in changes {
+        match change {
+            Change::Added { path, value } => added.push([display_path(path), literal(value)]),
+            Change::Removed { path, value } => removed.push([display_path(path), literal(value)]),
+            Change::Changed { path, old, new } => {
+                changed.push([display_path(path), literal(old), literal(new)])
+            }
+        }
+    }
+    writeln!(
+        writer,
+        "{} added, {} removed, {} changed",
+        added.len(),
+        removed.len(),
+        changed.len()
+    )?;
+
+    write_section(writer, "Added", &["Path", "Value"], &added)?;
+    write_section(writer, "Removed", &["Path", "Value"], &removed)?;
+    write_section(writer, "Changed", &["Path", "Before", "After"], &changed)?;
+    Ok(())
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        "*(root)*".to_string()
+    } else {
+        format!("`{path}`")
+    }
+}
+
+fn write_section<const N: usize>(
+    writer: &mut dyn Write,
+    title: &str,
+    headers: &[&str; N],
+    rows: &[[String; N]],
+) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer)?;
+    writeln!(writer, "## {title}")?;
+    writeln!(writer)?;
+    writeln!(writer, "| {} |", headers.join(" | "))?;
+    writeln!(writer, "|{}", "---|".repeat(N))?;
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| escape_pipe(cell)).collect();
+        writeln!(writer, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn object(entries: &[(&str, Value)]) -> Value {
+        Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+
+    #[rstest]
+    fn test_diff() {
+        let old = object(&[
+            ("name", Value::String("app".into())),
+            ("version", Value::Integer(1)),
+            ("tags", Value::Array(vec![Value::String("a".into())])),
+            ("build", object(&[("debug", Value::Bool(true))])),
+        ]);
+        let new = object(&[
+            ("version", Value::String("1".into())),
+            ("name", Value::String("app".into())),
+            (
+                "tags",
+                Value::Array(vec![Value::String("a".into()), Value::String("b".into())]),
+            ),
+            ("build", Value::Null),
+            ("log level", Value::Object(Vec::new())),
+        ]);
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Change::Changed {
+                    path: "version".into(),
+                    old: Value::Integer(1),
+                    new: Value::String("1".into()),
+                },
+                Change::Added {
+                    path: "tags[1]".into(),
+                    value: Value::String("b".into()),
+                },
+                Change::Removed {
+                    path: "build.debug".into(),
+                    value: Value::Bool(true),
+                },
+                Change::Added {
+                    path: "build".into(),
+                    value: Value::Null,
+                },
+                Change::Added {
+                    path: "[\"log level\"]".into(),
+                    value: Value::Object(Vec::new()),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_write_diff() {
+        let old = object(&[("a", Value::Integer(1)), ("b", Value::String("x|y".into()))]);
+        let new = object(&[("a", Value::Integer(2)), ("c", Value::Float(1.5))]);
+        let mut output = Vec::new();
+        write_diff_as_markdown(&mut output, "old.json", "new.yaml", &diff(&old, &new)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Changes from `old.json` to `new.yaml`\n\n\
+             1 added, 1 removed, 1 changed\n\n\
+             ## Added\n\n| Path | Value |\n|---|---|\n| `c` | 1.5 |\n\n\
+             ## Removed\n\n| Path | Value |\n|---|---|\n| `b` | \"x\\|y\" |\n\n\
+             ## Changed\n\n| Path | Before | After |\n|---|---|---|\n| `a` | 1 | 2 |\n"
+        );
+    }
+
+    #[rstest]
+    fn test_no_differences() {
+        let value = object(&[("a", Value::Integer(1))]);
+        let mut output = Vec::new();
+        write_diff_as_markdown(&mut output, "a", "b", &diff(&value, &value)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Changes from `a` to `b`\n\n*No differences*\n"
+        );
+    }
+}
@@ -0,0 +1,628 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use regex::Regex;
+
+use super::diff::{key_path, literal};
+use super::{Value, escape_pipe};
+use crate::error::Result;
+use crate::warnings;
+
+/// `$ref` chains longer than this are taken to be cycles.
+const MAX_REF_DEPTH: usize = 64;
+
+/// A JSON Schema to check JSON, YAML and TOML documents against.
+///
+/// The validation keywords of drafts 7 through 2020-12 are supported for
+/// types, enums, numbers, strings, arrays and objects, together with
+/// `allOf`, `anyOf`, `oneOf`, `not`, `if`/`then`/`else` and `$ref`s within
+/// the schema. Annotations such as `format` and `default` are ignored.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    root: Value,
+    /// The compiled `pattern` and `patternProperties` expressions.
+    patterns: HashMap<String, Regex>,
+}
+
+/// A place where a document does not match its schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// The dotted path of the value, empty for the document itself.
+    pub path: String,
+    pub message: String,
+}
+
+impl Schema {
+    /// Parse a schema, compiling its regular expressions.
+    pub fn from_json(json: &str) -> std::result::Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let root = Value::from(value);
+        let mut patterns = HashMap::new();
+        collect_patterns(&root, &mut patterns)?;
+        Ok(Self { root, patterns })
+    }
+
+    /// Check `value` against the schema, returning every mismatch found.
+    pub fn validate(&self, value: &Value) -> Vec<SchemaError> {
+        let mut errors = Vec::new();
+        self.check(&self.root, value, "", 0, &mut errors);
+        errors
+    }
+
+    fn check(
+        &self,
+        schema: &Value,
+        value: &Value,
+        path: &str,
+        refs: usize,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        let mut error = |message: String| {
+            errors.push(SchemaError {
+                path: path.to_string(),
+                message,
+            })
+        };
+        let keywords = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return error("not allowed by the schema".to_string()),
+            Value::Object(keywords) => keywords,
+            _ => return,
+        };
+
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            match self.resolve(reference) {
+                _ if refs >= MAX_REF_DEPTH => error(format!("`$ref` {reference} does not end")),
+                Some(target) => self.check(target, value, path, refs + 1, errors),
+                None => error(format!("`$ref` {reference} cannot be resolved")),
+            }
+        }
+
+        for (keyword, argument) in keywords {
+            self.check_keyword(schema, keyword, argument, value, path, refs, errors);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_keyword(
+        &self,
+        schema: &Value,
+        keyword: &str,
+        argument: &Value,
+        value: &Value,
+        path: &str,
+        refs: usize,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        let mut error = |message: String| {
+            errors.push(SchemaError {
+                path: path.to_string(),
+                message,
+            })
+        };
+        match (keyword, argument, value) {
+            ("type", types, value) => {
+                let allowed: Vec<&str> = match types {
+                    Value::String(t) => vec![t.as_str()],
+                    Value::Array(types) => types
+                        .iter()
+                        .filter_map(|t| match t {
+                            Value::String(t) => Some(t.as_str()),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => return,
+                };
+                if !allowed.iter().any(|t| has_type(value, t)) {
+                    error(format!(
+                        "expected {}, found {}",
+                        allowed.join(" or "),
+                        type_name(value)
+                    ));
+                }
+            }
+            ("enum", Value::Array(options), value)
+                if !options.iter().any(|option| equal(option, value)) =>
+            {
+                let options: Vec<String> = options.iter().map(literal).collect();
+                error(format!("expected one of {}", options.join(", ")));
+            }
+            ("const", expected, value) if !equal(expected, value) => {
+                error(format!("expected {}", literal(expected)));
+            }
+
+            ("minimum", limit, value) => compare(limit, value, |n, l| n >= l, "at least", error),
+            ("maximum", limit, value) => compare(limit, value, |n, l| n <= l, "at most", error),
+            ("exclusiveMinimum", limit, value) => {
+                compare(limit, value, |n, l| n > l, "greater than", error)
+            }
+            ("exclusiveMaximum", limit, value) => {
+                compare(limit, value, |n, l| n < l, "less than", error)
+            }
+            ("multipleOf", divisor, value) => {
+                if let (Some(n), Some(d)) = (number(value), number(divisor))
+                    && d > 0.0
+                    && ((n / d).round() * d - n).abs() > f64::EPSILON * n.abs().max(1.0)
+                {
+                    error(format!("must be a multiple of {}", literal(divisor)));
+                }
+            }
+
+            ("minLength", Value::Integer(min), Value::String(s))
+                if (s.chars().count() as i64) < *min =>
+            {
+                error(format!("must be at least {min} characters long"));
+            }
+            ("maxLength", Value::Integer(max), Value::String(s))
+                if s.chars().count() as i64 > *max =>
+            {
+                error(format!("must be at most {max} characters long"));
+            }
+            ("pattern", Value::String(pattern), Value::String(s))
+                if self.patterns.get(pattern).is_some_and(|re| !re.is_match(s)) =>
+            {
+                error(format!("does not match `{pattern}`"));
+            }
+
+            ("minItems", Value::Integer(min), Value::Array(items))
+                if (items.len() as i64) < *min =>
+            {
+                error(format!("must have at least {min} items"));
+            }
+            ("maxItems", Value::Integer(max), Value::Array(items)) if items.len() as i64 > *max => {
+                error(format!("must have at most {max} items"));
+            }
+            ("uniqueItems", Value::Bool(true), Value::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    if let Some(j) = items[..i].iter().position(|other| equal(other, item)) {
+                        error(format!("items {j} and {i} are equal"));
+                        break;
+                    }
+                }
+            }
+            ("prefixItems", Value::Array(schemas), Value::Array(items)) => {
+                for (i, (schema, item)) in schemas.iter().zip(items).enumerate() {
+                    self.check(schema, item, &format!("{path}[{i}]"), refs, errors);
+                }
+            }
+            ("items", Value::Array(schemas), Value::Array(items)) => {
+                // The tuple form of drafts before 2020-12
+                for (i, (schema, item)) in schemas.iter().zip(items).enumerate() {
+                    self.check(schema, item, &format!("{path}[{i}]"), refs, errors);
+                }
+            }
+            ("items", item_schema, Value::Array(items)) => {
+                let skip = match schema.get("prefixItems") {
+                    Some(Value::Array(prefix)) => prefix.len(),
+                    _ => 0,
+                };
+                for (i, item) in items.iter().enumerate().skip(skip) {
+                    self.check(item_schema, item, &format!("{path}[{i}]"), refs, errors);
+                }
+            }
+            ("contains", contains, Value::Array(items))
+                if !items.iter().any(|item| self.matches(contains, item, refs)) =>
+            {
+                error("must contain a matching item".to_string());
+            }
+
+            ("required", Value::Array(required), Value::Object(entries)) => {
+                for key in required {
+                    if let Value::String(key) = key
+                        && !entries.iter().any(|(k, _)| k == key)
+                    {
+                        error(format!("missing required property `{key}`"));
+                    }
+                }
+            }
+            ("minProperties", Value::Integer(min), Value::Object(entries))
+                if (entries.len() as i64) < *min =>
+            {
+                error(format!("must have at least {min} properties"));
+            }
+            ("maxProperties", Value::Integer(max), Value::Object(entries))
+                if entries.len() as i64 > *max =>
+            {
+                error(format!("must have at most {max} properties"));
+            }
+            ("properties", Value::Object(properties), Value::Object(entries)) => {
+                for (key, item) in entries {
+                    if let Some((_, schema)) = properties.iter().find(|(k, _)| k == key) {
+                        self.check(schema, item, &key_path(path, key), refs, errors);
+                    }
+                }
+            }
+            ("patternProperties", Value::Object(patterns), Value::Object(entries)) => {
+                for (key, item) in entries {
+                    for (pattern, schema) in patterns {
+                        if self
+                            .patterns
+                            .get(pattern)
+                            .is_some_and(|re| re.is_match(key))
+                        {
+                            self.check(schema, item, &key_path(path, key), refs, errors);
+                        }
+                    }
+                }
+            }
+            ("additionalProperties", additional, Value::Object(entries)) => {
+                for (key, item) in entries {
+                    if !self.is_declared(schema, key) {
+                        let path = key_path(path, key);
+                        match additional {
+                            Value::Bool(false) => errors.push(SchemaError {
+                                path,
+                                message: "unexpected property".to_string(),
+                            }),
+                            additional => self.check(additional, item, &path, refs, errors),
+                        }
+                    }
+                }
+            }
+            ("propertyNames", names, Value::Object(entries)) => {
+                for (key, _) in entries {
+                    if !self.matches(names, &Value::String(key.clone()), refs) {
+                        error(format!("property name `{key}` is not allowed"));
+                    }
+                }
+            }
+
+            ("allOf", Value::Array(schemas), value) => {
+                for schema in schemas {
+                    self.check(schema, value, path, refs, errors);
+                }
+            }
+            ("anyOf", Value::Array(schemas), value)
+                if !schemas
+                    .iter()
+                    .any(|schema| self.matches(schema, value, refs)) =>
+            {
+                error("does not match any of the allowed schemas".to_string());
+            }
+            ("oneOf", Value::Array(schemas), value) => {
+                let matched = schemas
+                    .iter()
+                    .filter(|schema| self.matches(schema, value, refs))
+                    .count();
+                match matched {
+                    1 => {}
+                    0 => error("does not match any of the allowed schemas".to_string()),
+                    n => error(format!("matches {n} of the schemas instead of exactly one")),
+                }
+            }
+            ("not", not, value) if self.matches(not, value, refs) => {
+                error("matches a schema it must not match".to_string());
+            }
+            ("if", condition, value) => {
+                let branch = match self.matches(condition, value, refs) {
+                    true => schema.get("then"),
+                    false => schema.get("else"),
+                };
+                if let Some(branch) = branch {
+                    self.check(branch, value, path, refs, errors);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn matches(&self, schema: &Value, value: &Value, refs: usize) -> bool {
+        let mut errors = Vec::new();
+        self.check(schema, value, "", refs, &mut errors);
+        errors.is_empty()
+    }
+
+    /// Whether `key` is covered by `properties` or `patternProperties`, and
+    /// so is not an additional property.
+    fn is_declared(&self, schema: &Value, key: &str) -> bool {
+        schema.get("properties").and_then(|p| p.get(key)).is_some()
+            || matches!(schema.get("patternProperties"), Some(Value::Object(patterns))
+            if patterns.iter().any(|(pattern, _)| {
+                self.patterns.get(pattern).is_some_and(|re| re.is_match(key))
+            }))
+    }
+
+    /// Resolve a `$ref` within the schema: `#` or a JSON pointer such as
+    /// `#/$defs/port`.
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let pointer = reference.strip_prefix('#')?;
+        pointer
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .try_fold(&self.root, |value, token| match value {
+                Value::Object(_) => value.get(&token),
+                Value::Array(items) => items.get(token.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+}
+
+/// Compile the `pattern`s and `patternProperties` keys of a schema and its
+/// subschemas, leaving out values that are data rather than schemas.
+fn collect_patterns(
+    schema: &Value,
+    patterns: &mut HashMap<String, Regex>,
+) -> std::result::Result<(), String> {
+    match schema {
+        Value::Object(keywords) => {
+            for (keyword, argument) in keywords {
+                match (keyword.as_str(), argument) {
+                    ("enum" | "const" | "default" | "examples", _) => {}
+                    ("pattern", Value::String(pattern)) => compile(pattern, patterns)?,
+                    ("patternProperties", Value::Object(entries)) => {
+                        for (pattern, _) in entries {
+                            compile(pattern, patterns)?;
+                        }
+                        collect_patterns(argument, patterns)?;
+                    }
+                    _ => collect_patterns(argument, patterns)?,
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_patterns(item, patterns)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn compile(
+    pattern: &str,
+    patterns: &mut HashMap<String, Regex>,
+) -> std::result::Result<(), String> {
+    if !patterns.contains_key(pattern) {
+        let re = Regex::new(pattern).map_err(|e| format!("pattern `{pattern}`: {e}"))?;
+        patterns.insert(pattern.to_string(), re);
+    }
+    Ok(())
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn compare(
+    limit: &Value,
+    value: &Value,
+    within: fn(f64, f64) -> bool,
+    bound: &str,
+    mut error: impl FnMut(String),
+) {
+    if let (Some(n), Some(l)) = (number(value), number(limit))
+        && !within(n, l)
+    {
+        error(format!("must be {bound} {}", literal(limit)));
+    }
+}
+
+/// Equality as JSON Schema defines it, where `1` and `1.0` are the same
+/// number.
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, a)| {
+                    b.iter()
+                        .find(|(k, _)| k == key)
+                        .is_some_and(|(_, b)| equal(a, b))
+                })
+        }
+        _ => match (number(a), number(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a == b,
+        },
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("null", Value::Null)
+        | ("boolean", Value::Bool(_))
+        | ("string", Value::String(_))
+        | ("array", Value::Array(_))
+        | ("object", Value::Object(_))
+        | ("number" | "integer", Value::Integer(_))
+        | ("number", Value::Float(_)) => true,
+        ("integer", Value::Float(f)) => f.fract() == 0.0,
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Integer(_) => "integer",
+        Value::Float(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Write the converted `document` followed by the outcome of its validation
+/// as a Markdown section, one blank line apart, warning when the document
+/// does not match.
+pub fn write_report(writer: &mut dyn Write, document: &[u8], errors: &[SchemaError]) -> Result<()> {
+    let end = document
+        .iter()
+        .rposition(|b| *b != b'\n')
+        .map_or(0, |i| i + 1);
+    if end > 0 {
+        writer.write_all(&document[..end])?;
+        writeln!(writer)?;
+        writeln!(writer)?;
+    }
+    writeln!(writer, "# Schema Validation")?;
+    writeln!(writer)?;
+    if errors.is_empty() {
+        writeln!(writer, "**Passed:** the document matches the schema.")?;
+        return Ok(());
+    }
+    let count = match errors.len() {
+        1 => "1 error".to_string(),
+        n => format!("{n} errors"),
+    };
+    warnings::warn(format!("Document does not match the schema: {count}"));
+    writeln!(writer, "**Failed:** {count}.")?;
+    writeln!(writer)?;
+    writeln!(writer, "| Path | Error |")?;
+    writeln!(writer, "|---|---|")?;
+    for error in errors {
+        let path = match error.path.as_str() {
+            "" => "*(document)*".to_string(),
+            path => format!("`{}`", escape_pipe(path)),
+        };
+        writeln!(writer, "| {path} | {} |", escape_pipe(&error.message))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn errors(schema: &str, document: &str) -> Vec<(String, String)> {
+        let schema = Schema::from_json(schema).unwrap();
+        let document = Value::from(serde_json::from_str::<serde_json::Value>(document).unwrap());
+        schema
+            .validate(&document)
+            .into_iter()
+            .map(|e| (e.path, e.message))
+            .collect()
+    }
+
+    fn e(path: &str, message: &str) -> (String, String) {
+        (path.to_string(), message.to_string())
+    }
+
+    #[rstest]
+    #[case::valid(
+        r#"{"type":"object","properties":{"port":{"type":"integer"}}}"#,
+        r#"{"port":8080}"#,
+        vec![]
+    )]
+    #[case::wrong_type(
+        r#"{"properties":{"port":{"type":"integer"}}}"#,
+        r#"{"port":"8080"}"#,
+        vec![e("port", "expected integer, found string")]
+    )]
+    #[case::integral_float(r#"{"type":"integer"}"#, "3.0", vec![])]
+    #[case::type_list(
+        r#"{"type":["string","null"]}"#,
+        "1",
+        vec![e("", "expected string or null, found integer")]
+    )]
+    #[case::required(
+        r#"{"required":["name","port"]}"#,
+        r#"{"name":"web"}"#,
+        vec![e("", "missing required property `port`")]
+    )]
+    #[case::additional_false(
+        r#"{"properties":{"a":{}},"patternProperties":{"^x-":{}},"additionalProperties":false}"#,
+        r#"{"a":1,"x-note":2,"b":3}"#,
+        vec![e("b", "unexpected property")]
+    )]
+    #[case::additional_schema(
+        r#"{"additionalProperties":{"type":"string"}}"#,
+        r#"{"a":"x","b":2}"#,
+        vec![e("b", "expected string, found integer")]
+    )]
+    #[case::enum_(
+        r#"{"enum":["debug","info"]}"#,
+        r#""trace""#,
+        vec![e("", r#"expected one of "debug", "info""#)]
+    )]
+    #[case::const_number(r#"{"const":1}"#, "1.0", vec![])]
+    #[case::range(
+        r#"{"items":{"minimum":1,"exclusiveMaximum":10}}"#,
+        "[0,5,10]",
+        vec![e("[0]", "must be at least 1"), e("[2]", "must be less than 10")]
+    )]
+    #[case::multiple_of(r#"{"multipleOf":0.5}"#, "1.25", vec![e("", "must be a multiple of 0.5")])]
+    #[case::string(
+        r#"{"minLength":2,"maxLength":3,"pattern":"^[a-z]+$"}"#,
+        r#""ABCD""#,
+        vec![e("", "must be at most 3 characters long"), e("", "does not match `^[a-z]+$`")]
+    )]
+    #[case::array(
+        r#"{"minItems":4,"uniqueItems":true}"#,
+        "[1,2,1]",
+        vec![e("", "must have at least 4 items"), e("", "items 0 and 2 are equal")]
+    )]
+    #[case::prefix_items(
+        r#"{"prefixItems":[{"type":"string"}],"items":{"type":"integer"}}"#,
+        r#"["a",1,"b"]"#,
+        vec![e("[2]", "expected integer, found string")]
+    )]
+    #[case::nested_path(
+        r#"{"properties":{"services":{"additionalProperties":{"required":["image"]}}}}"#,
+        r#"{"services":{"web":{"ports":[]}}}"#,
+        vec![e("services.web", "missing required property `image`")]
+    )]
+    #[case::ref_(
+        r##"{"$defs":{"port":{"type":"integer","maximum":65535}},"properties":{"port":{"$ref":"#/$defs/port"}}}"##,
+        r#"{"port":70000}"#,
+        vec![e("port", "must be at most 65535")]
+    )]
+    #[case::recursive_ref(
+        r##"{"type":"object","properties":{"child":{"$ref":"#"}},"additionalProperties":false}"##,
+        r#"{"child":{"child":{"other":1}}}"#,
+        vec![e("child.child.other", "unexpected property")]
+    )]
+    #[case::unresolved_ref(
+        r##"{"$ref":"#/definitions/missing"}"##,
+        "1",
+        vec![e("", "`$ref` #/definitions/missing cannot be resolved")]
+    )]
+    #[case::any_of(
+        r#"{"anyOf":[{"type":"string"},{"type":"integer"}]}"#,
+        "true",
+        vec![e("", "does not match any of the allowed schemas")]
+    )]
+    #[case::one_of(
+        r#"{"oneOf":[{"type":"integer"},{"minimum":0}]}"#,
+        "1",
+        vec![e("", "matches 2 of the schemas instead of exactly one")]
+    )]
+    #[case::not(r#"{"not":{"type":"null"}}"#, "null", vec![e("", "matches a schema it must not match")])]
+    #[case::if_then_else(
+        r#"{"if":{"properties":{"tls":{"const":true}}},"then":{"required":["cert"]},"else":{"required":["port"]}}"#,
+        r#"{"tls":true}"#,
+        vec![e("", "missing required property `cert`")]
+    )]
+    #[case::false_schema(
+        r#"{"properties":{"legacy":false}}"#,
+        r#"{"legacy":1}"#,
+        vec![e("legacy", "not allowed by the schema")]
+    )]
+    #[case::pattern_in_enum_ignored(r#"{"enum":[{"pattern":"("}]}"#, r#"{"pattern":"("}"#, vec![])]
+    fn test_validate(
+        #[case] schema: &str,
+        #[case] document: &str,
+        #[case] expected: Vec<(String, String)>,
+    ) {
+        assert_eq!(errors(schema, document), expected);
+    }
+
+    #[rstest]
+    #[case::invalid_json("{", true)]
+    #[case::invalid_pattern(r#"{"pattern":"("}"#, true)]
+    #[case::valid(r#"{"patternProperties":{"^x-":{"pattern":"^a"}}}"#, false)]
+    fn test_from_json(#[case] json: &str, #[case] fails: bool) {
+        assert_eq!(Schema::from_json(json).is_err(), fails);
+    }
+}
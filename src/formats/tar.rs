@@ -2,8 +2,19 @@ use std::io::{Cursor, Read, Write};
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::formats::archive::{format_size, is_included, read_entry, write_entry, write_summary};
+use crate::options::ConvertOptions;
 
-pub struct TarConverter;
+#[derive(Default)]
+pub struct TarConverter {
+    options: ConvertOptions,
+}
+
+impl TarConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for TarConverter {
     fn format_name(&self) -> &'static str {
@@ -15,9 +26,9 @@ impl Converter for TarConverter {
         if is_gzip(input) {
             let decoder =
                 flate2::read::GzDecoder::new(Cursor::new(input));
-            convert_tar(decoder, writer)
+            convert_tar(decoder, &self.options, writer)
         } else {
-            convert_tar(Cursor::new(input), writer)
+            convert_tar(Cursor::new(input), &self.options, writer)
         }
     }
 }
@@ -26,7 +37,7 @@ fn is_gzip(bytes: &[u8]) -> bool {
     bytes.len() >= 2 && bytes[0] == 0x1F && bytes[1] == 0x8B
 }
 
-fn convert_tar<R: Read>(reader: R, writer: &mut dyn Write) -> Result<()> {
+fn convert_tar<R: Read>(reader: R, options: &ConvertOptions, writer: &mut dyn Write) -> Result<()> {
     let mut archive = tar::Archive::new(reader);
     let entries = archive.entries().map_err(|e| Error::Conversion {
         format: "tar",
@@ -35,6 +46,10 @@ fn convert_tar<R: Read>(reader: R, writer: &mut dyn Write) -> Result<()> {
 
     let mut items: Vec<(String, u64, char)> = Vec::new();
     let mut total_size: u64 = 0;
+    let mut skipped = 0;
+    // Entries can only be read while iterating, so contents to convert are
+    // kept until the listing is written
+    let mut contents: Vec<(String, Option<Vec<u8>>)> = Vec::new();
 
     for entry in entries {
         let entry = entry.map_err(|e| Error::Conversion {
@@ -46,6 +61,10 @@ fn convert_tar<R: Read>(reader: R, writer: &mut dyn Write) -> Result<()> {
             .path()
             .map(|p| p.to_string_lossy().to_string())
             .unwrap_or_else(|_| "???".to_string());
+        if !is_included(&options.archive, &path) {
+            skipped += 1;
+            continue;
+        }
 
         let size = entry.size();
         let kind = match entry.header().entry_type() {
@@ -57,6 +76,9 @@ fn convert_tar<R: Read>(reader: R, writer: &mut dyn Write) -> Result<()> {
         };
 
         total_size += size;
+        if options.archive.depth > 0 && kind == 'f' {
+            contents.push((path.clone(), read_entry(&options.archive, entry)?));
+        }
         items.push((path, size, kind));
     }
 
@@ -89,23 +111,24 @@ fn convert_tar<R: Read>(reader: R, writer: &mut dyn Write) -> Result<()> {
     }
 
     writeln!(writer)?;
+    if skipped > 0 {
+        writeln!(writer, "*{skipped} entries excluded by filters*")?;
+        writeln!(writer)?;
+    }
     writeln!(writer, "**Total size**: {}", format_size(total_size))?;
 
-    Ok(())
-}
+    if options.archive.summary {
+        let files: Vec<(&str, u64)> = items
+            .iter()
+            .filter(|(_, _, kind)| *kind == 'f')
+            .map(|(name, size, _)| (name.as_str(), *size))
+            .collect();
+        write_summary(writer, &files)?;
+    }
 
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-
-    if bytes >= GB {
-        format!("{:.1} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{bytes} B")
+    for (name, bytes) in &contents {
+        write_entry(writer, name, bytes.as_deref(), options)?;
     }
+
+    Ok(())
 }
@@ -2,9 +2,19 @@ use std::io::Write;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
-use crate::formats::structured;
+use crate::formats::structured::{self, schema};
+use crate::options::ConvertOptions;
 
-pub struct TomlConverter;
+#[derive(Default)]
+pub struct TomlConverter {
+    options: ConvertOptions,
+}
+
+impl TomlConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for TomlConverter {
     fn format_name(&self) -> &'static str {
@@ -12,21 +22,36 @@ impl Converter for TomlConverter {
     }
 
     fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
-        let text = std::str::from_utf8(input).map_err(|e| Error::Conversion {
-            format: "toml",
-            message: e.to_string(),
-        })?;
-
-        let value: toml::Value = toml::from_str(text).map_err(|e| Error::Conversion {
-            format: "toml",
-            message: e.to_string(),
-        })?;
+        let value = parse(input)?;
+        let options = &self.options.structured;
+        let errors = options.schema.as_ref().map(|s| s.validate(&value));
+        // With a schema the report follows the document, so hold it back
+        let mut document = Vec::new();
+        let out: &mut dyn Write = match errors {
+            Some(_) => &mut document,
+            None => writer,
+        };
+        structured::write_document(out, value, options)?;
+        match errors {
+            Some(errors) => schema::write_report(writer, &document, &errors),
+            None => Ok(()),
+        }
+    }
+}
 
-        let structured_value = structured::Value::from(value);
-        structured::write_value_as_markdown(writer, &structured_value)?;
+pub(crate) fn parse(input: &[u8]) -> Result<structured::Value> {
+    let text = std::str::from_utf8(input).map_err(|e| Error::Conversion {
+        format: "toml",
+        message: e.to_string(),
+    })?;
 
-        Ok(())
-    }
+    let value: toml::Value = toml::from_str(text).map_err(|e| Error::Parse {
+        format: "toml",
+        entry: None,
+        offset: e.span().map(|span| span.start as u64),
+        message: e.to_string(),
+    })?;
+    Ok(structured::Value::from(value))
 }
 
 #[cfg(test)]
@@ -37,7 +62,7 @@ mod tests {
     use rstest::rstest;
 
     fn convert(input: &str) -> String {
-        let converter = TomlConverter;
+        let converter = TomlConverter::default();
         let mut output = Vec::new();
         converter.convert(input.as_bytes(), &mut output).unwrap();
         String::from_utf8(output).unwrap()
@@ -84,4 +109,31 @@ mod tests {
         assert!(output.contains("dep"));
         assert!(output.contains("version"));
     }
+
+    #[rstest]
+    #[case::passed(
+        "name = \"web\"\nport = 8080",
+        "| Key | Value |\n|---|---|\n| name | web |\n| port | 8080 |\n\n\
+         # Schema Validation\n\n**Passed:** the document matches the schema.\n"
+    )]
+    #[case::failed(
+        "port = \"80|81\"",
+        "| Key | Value |\n|---|---|\n| port | 80\\|81 |\n\n\
+         # Schema Validation\n\n**Failed:** 2 errors.\n\n| Path | Error |\n|---|---|\n\
+         | *(document)* | missing required property `name` |\n\
+         | `port` | expected integer, found string |\n"
+    )]
+    fn test_schema(#[case] input: &str, #[case] expected: &str) {
+        let schema = r#"{"required": ["name"], "properties": {"port": {"type": "integer"}}}"#;
+        let converter = TomlConverter::new(ConvertOptions {
+            structured: crate::options::StructuredOptions {
+                schema: Some(structured::schema::Schema::from_json(schema).unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
 }
@@ -7,8 +7,21 @@ use lofty::tag::ItemKey;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::options::ConvertOptions;
 
-pub struct VideoConverter;
+mod color;
+mod subtitles;
+
+#[derive(Default)]
+pub struct VideoConverter {
+    options: ConvertOptions,
+}
+
+impl VideoConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for VideoConverter {
     fn format_name(&self) -> &'static str {
@@ -80,7 +93,20 @@ impl Converter for VideoConverter {
 
         writeln!(writer)?;
 
+        // Color, read from the container since lofty does not expose it
+        if let Some(color) = color::ColorInfo::read(input) {
+            writeln!(writer, "## Color")?;
+            writeln!(writer)?;
+            writeln!(writer, "| Property | Value |")?;
+            writeln!(writer, "|----------|-------|")?;
+            for (property, value) in color.rows() {
+                writeln!(writer, "| {property} | {value} |")?;
+            }
+            writeln!(writer)?;
+        }
+
         // Tags
+        let mut needs_blank = false;
         if let Some(tag) = tagged_file.primary_tag().or(tagged_file.first_tag()) {
             let items: Vec<(&str, String)> = [
                 ("Title", tag.get_string(ItemKey::TrackTitle)),
@@ -102,13 +128,197 @@ impl Converter for VideoConverter {
                 for (key, value) in &items {
                     writeln!(writer, "| {key} | {} |", value.replace('|', "\\|"))?;
                 }
+                needs_blank = true;
             }
         }
 
+        if self.options.video.keyframes > 0
+            && let Some(dir) = &self.options.media.extract_dir
+        {
+            if needs_blank {
+                writeln!(writer)?;
+            }
+            #[cfg(feature = "ffmpeg")]
+            {
+                keyframes::write_keyframes(
+                    input,
+                    duration,
+                    self.options.video.keyframes,
+                    dir,
+                    self.options.media.link_base.as_deref(),
+                    &crate::media::input_stem(self.options.input_name.as_deref(), "video"),
+                    writer,
+                )?;
+                needs_blank = true;
+            }
+            #[cfg(not(feature = "ffmpeg"))]
+            {
+                let _ = dir;
+                return Err(Error::FeatureDisabled("ffmpeg".into()));
+            }
+        }
+
+        if let Some(text) = &self.options.video.subtitles {
+            if needs_blank {
+                writeln!(writer)?;
+            }
+            subtitles::write_subtitles(writer, &subtitles::cues(text))?;
+            needs_blank = false;
+        }
+
+        if self.options.transcribe.endpoint.is_some() {
+            if needs_blank {
+                writeln!(writer)?;
+            }
+            #[cfg(feature = "remote-transcribe")]
+            crate::transcribe::write_transcript(
+                &self.options.transcribe,
+                input,
+                self.options.input_name.as_deref().unwrap_or("video.mp4"),
+                "video",
+                writer,
+            )?;
+            #[cfg(not(feature = "remote-transcribe"))]
+            return Err(Error::FeatureDisabled("remote-transcribe".into()));
+        }
+
         Ok(())
     }
 }
 
+/// Keyframe extraction by running the `ffmpeg` binary.
+#[cfg(feature = "ffmpeg")]
+mod keyframes {
+    use std::io::Write;
+    use std::path::Path;
+    use std::process::Command;
+    use std::time::Duration;
+
+    use crate::error::{Error, Result};
+    use crate::media::write_media;
+
+    /// Longest side of extracted frames, in pixels.
+    const FRAME_WIDTH: u32 = 640;
+
+    pub fn write_keyframes(
+        input: &[u8],
+        duration: Duration,
+        count: usize,
+        dir: &Path,
+        link_base: Option<&Path>,
+        stem: &str,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        // ffmpeg needs a seekable input for most containers
+        let tmp =
+            std::env::temp_dir().join(format!("mq-conv-{}-{stem}.video", std::process::id()));
+        std::fs::write(&tmp, input)?;
+        let frames = timestamps(duration, count)
+            .into_iter()
+            .enumerate()
+            .map(|(i, at)| {
+                let bytes = extract_frame(&tmp, at)?;
+                let link = write_media(
+                    dir,
+                    &format!("{stem}.frame-{}.jpg", i + 1),
+                    &bytes,
+                    link_base,
+                )?;
+                Ok((at, link))
+            })
+            .collect::<Result<Vec<_>>>();
+        let _ = std::fs::remove_file(&tmp);
+        let frames = frames?;
+
+        writeln!(writer, "## Keyframes")?;
+        writeln!(writer)?;
+        writeln!(writer, "| Time | Frame |")?;
+        writeln!(writer, "|------|-------|")?;
+        for (at, link) in &frames {
+            let time = format_timestamp(*at);
+            writeln!(writer, "| {time} | ![Frame at {time}]({link}) |")?;
+        }
+
+        Ok(())
+    }
+
+    /// `count` timestamps at the middle of equal slices of the video, so the
+    /// first and last frames (often black) are avoided.
+    fn timestamps(duration: Duration, count: usize) -> Vec<Duration> {
+        (0..count)
+            .map(|i| duration.mul_f64((i as f64 + 0.5) / count as f64))
+            .collect()
+    }
+
+    fn extract_frame(path: &Path, at: Duration) -> Result<Vec<u8>> {
+        // `-ss` before `-i` seeks to the nearest keyframe, which is fast
+        let output = Command::new("ffmpeg")
+            .args(["-v", "error", "-ss"])
+            .arg(format!("{:.3}", at.as_secs_f64()))
+            .arg("-i")
+            .arg(path)
+            .args(["-frames:v", "1", "-vf"])
+            .arg(format!("scale='min({FRAME_WIDTH},iw)':-2"))
+            .args(["-c:v", "mjpeg", "-f", "image2pipe", "pipe:1"])
+            .output()
+            .map_err(|e| Error::Conversion {
+                format: "video",
+                message: format!("Failed to run ffmpeg: {e}"),
+            })?;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(Error::Conversion {
+                format: "video",
+                message: format!(
+                    "ffmpeg could not extract a frame at {}: {}",
+                    format_timestamp(at),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            });
+        }
+        Ok(output.stdout)
+    }
+
+    fn format_timestamp(at: Duration) -> String {
+        let total_secs = at.as_secs();
+        let hours = total_secs / 3600;
+        let mins = (total_secs % 3600) / 60;
+        let secs = total_secs % 60;
+        if hours > 0 {
+            format!("{hours}:{mins:02}:{secs:02}")
+        } else {
+            format!("{mins}:{secs:02}")
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use pretty_assertions::assert_eq;
+        use rstest::rstest;
+
+        #[rstest]
+        fn test_timestamps_evenly_spaced() {
+            assert_eq!(
+                timestamps(Duration::from_secs(40), 4),
+                vec![
+                    Duration::from_secs(5),
+                    Duration::from_secs(15),
+                    Duration::from_secs(25),
+                    Duration::from_secs(35),
+                ]
+            );
+        }
+
+        #[rstest]
+        #[case::minutes(Duration::from_secs(75), "1:15")]
+        #[case::hours(Duration::from_secs(3725), "1:02:05")]
+        fn test_format_timestamp(#[case] at: Duration, #[case] expected: &str) {
+            assert_eq!(format_timestamp(at), expected);
+        }
+    }
+}
+
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * KB;
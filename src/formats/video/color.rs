@@ -0,0 +1,462 @@
+//! Color and HDR metadata of a video's first video track, read from the
+//! MP4 sample description (`colr`, `hvcC`, `av1C`, `vpcC`, `clli`, `mdcv`)
+//! or the Matroska `Colour` element.
+
+/// Code points are those of ISO/IEC 23091-2 (H.273), which both
+/// containers use.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct ColorInfo {
+    primaries: Option<u64>,
+    transfer: Option<u64>,
+    matrix: Option<u64>,
+    full_range: Option<bool>,
+    bit_depth: Option<u64>,
+    /// Maximum content and frame-average light levels, in nits.
+    max_cll: Option<u64>,
+    max_fall: Option<u64>,
+    /// Mastering display metadata is present.
+    mastering: bool,
+    dolby_vision: bool,
+}
+
+impl ColorInfo {
+    /// Read from an MP4/MOV or Matroska/WebM file. `None` when the file
+    /// has no video track or records nothing about its color.
+    pub(super) fn read(input: &[u8]) -> Option<Self> {
+        let info = if input.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            mkv::read(input)
+        } else {
+            mp4::read(input)
+        }?;
+        (info != Self::default()).then_some(info)
+    }
+
+    /// `(property, value)` rows for the known fields.
+    pub(super) fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![("Dynamic Range", self.dynamic_range().to_string())];
+        if let Some(primaries) = self.primaries {
+            rows.push(("Color Primaries", describe(primaries, primaries_name)));
+        }
+        if let Some(transfer) = self.transfer {
+            rows.push((
+                "Transfer Characteristics",
+                describe(transfer, transfer_name),
+            ));
+        }
+        if let Some(matrix) = self.matrix {
+            rows.push(("Matrix Coefficients", describe(matrix, matrix_name)));
+        }
+        if let Some(full) = self.full_range {
+            let range = if full { "Full" } else { "Limited" };
+            rows.push(("Color Range", range.to_string()));
+        }
+        if let Some(depth) = self.bit_depth {
+            rows.push(("Bit Depth", format!("{depth}-bit")));
+        }
+        if let Some(max_cll) = self.max_cll {
+            rows.push(("MaxCLL", format!("{max_cll} nits")));
+        }
+        if let Some(max_fall) = self.max_fall {
+            rows.push(("MaxFALL", format!("{max_fall} nits")));
+        }
+        rows
+    }
+
+    fn dynamic_range(&self) -> &'static str {
+        match self.transfer {
+            _ if self.dolby_vision => "Dolby Vision",
+            Some(16) if self.mastering || self.max_cll.is_some() => "HDR10",
+            Some(16) => "HDR (PQ)",
+            Some(18) => "HLG",
+            _ => "SDR",
+        }
+    }
+}
+
+fn describe(code: u64, name: fn(u64) -> Option<&'static str>) -> String {
+    match name(code) {
+        Some(name) => name.to_string(),
+        None => format!("Unknown ({code})"),
+    }
+}
+
+fn primaries_name(code: u64) -> Option<&'static str> {
+    Some(match code {
+        1 => "BT.709",
+        2 => "Unspecified",
+        4 => "BT.470 System M",
+        5 => "BT.601 (625 lines)",
+        6 => "BT.601 (525 lines)",
+        7 => "SMPTE 240M",
+        8 => "Generic film",
+        9 => "BT.2020",
+        10 => "SMPTE ST 428 (XYZ)",
+        11 => "DCI-P3",
+        12 => "Display P3",
+        22 => "EBU Tech 3213",
+        _ => return None,
+    })
+}
+
+fn transfer_name(code: u64) -> Option<&'static str> {
+    Some(match code {
+        1 => "BT.709",
+        2 => "Unspecified",
+        4 => "Gamma 2.2",
+        5 => "Gamma 2.8",
+        6 => "BT.601",
+        7 => "SMPTE 240M",
+        8 => "Linear",
+        11 => "IEC 61966-2-4",
+        13 => "sRGB",
+        14 => "BT.2020 (10-bit)",
+        15 => "BT.2020 (12-bit)",
+        16 => "SMPTE ST 2084 (PQ)",
+        17 => "SMPTE ST 428",
+        18 => "ARIB STD-B67 (HLG)",
+        _ => return None,
+    })
+}
+
+fn matrix_name(code: u64) -> Option<&'static str> {
+    Some(match code {
+        0 => "Identity (RGB)",
+        1 => "BT.709",
+        2 => "Unspecified",
+        5 => "BT.601 (625 lines)",
+        6 => "BT.601 (525 lines)",
+        7 => "SMPTE 240M",
+        9 => "BT.2020 non-constant luminance",
+        10 => "BT.2020 constant luminance",
+        14 => "ICtCp",
+        _ => return None,
+    })
+}
+
+mod mp4 {
+    use super::ColorInfo;
+
+    pub(super) fn read(input: &[u8]) -> Option<ColorInfo> {
+        find_video_track(input)
+    }
+
+    /// The first `trak` in `moov` whose handler is `vide`.
+    fn find_video_track(data: &[u8]) -> Option<ColorInfo> {
+        for (kind, body) in boxes(data) {
+            if &kind == b"trak" {
+                if handler(body) == Some(*b"vide")
+                    && let Some(info) = sample_entry(body)
+                {
+                    return Some(info);
+                }
+            } else if &kind == b"moov"
+                && let Some(info) = find_video_track(body)
+            {
+                return Some(info);
+            }
+        }
+        None
+    }
+
+    fn handler(trak: &[u8]) -> Option<[u8; 4]> {
+        let mdia = child(trak, b"mdia")?;
+        let hdlr = child(mdia, b"hdlr")?;
+        // Version and flags, then pre_defined
+        hdlr.get(8..12)?.try_into().ok()
+    }
+
+    /// Color fields of the track's first sample description.
+    fn sample_entry(trak: &[u8]) -> Option<ColorInfo> {
+        let stsd = [b"mdia", b"minf", b"stbl", b"stsd"]
+            .iter()
+            .try_fold(trak, |data, kind| child(data, kind))?;
+        // Version, flags and entry count precede the entries
+        let (kind, entry) = boxes(stsd.get(8..)?).next()?;
+        // The visual sample entry's fixed fields take 78 bytes
+        let mut info = ColorInfo {
+            dolby_vision: matches!(&kind, b"dvh1" | b"dvhe" | b"dva1" | b"dvav"),
+            ..Default::default()
+        };
+        for (kind, body) in boxes(entry.get(78..)?) {
+            match &kind {
+                b"colr" if body.starts_with(b"nclx") && body.len() >= 11 => {
+                    info.primaries = Some(u16_at(body, 4)?.into());
+                    info.transfer = Some(u16_at(body, 6)?.into());
+                    info.matrix = Some(u16_at(body, 8)?.into());
+                    info.full_range = Some(body[10] & 0x80 != 0);
+                }
+                b"hvcC" => info.bit_depth = Some(u64::from(body.get(17)? & 0x07) + 8),
+                b"av1C" => {
+                    let flags = body.get(2)?;
+                    info.bit_depth = Some(match (flags & 0x40 != 0, flags & 0x20 != 0) {
+                        (true, true) => 12,
+                        (true, false) => 10,
+                        _ => 8,
+                    });
+                }
+                // A full box: version and flags, profile, level, then the
+                // bit depth, chroma subsampling and range in one byte
+                b"vpcC" => {
+                    let packed = body.get(6)?;
+                    info.bit_depth = Some(u64::from(packed >> 4));
+                    // `colr` takes precedence when both are present
+                    info.full_range.get_or_insert(packed & 0x01 != 0);
+                    info.primaries.get_or_insert((*body.get(7)?).into());
+                    info.transfer.get_or_insert((*body.get(8)?).into());
+                    info.matrix.get_or_insert((*body.get(9)?).into());
+                }
+                b"clli" => {
+                    info.max_cll = Some(u16_at(body, 0)?.into());
+                    info.max_fall = Some(u16_at(body, 2)?.into());
+                }
+                b"mdcv" | b"SmDm" => info.mastering = true,
+                b"dvcC" | b"dvvC" | b"dvwC" => info.dolby_vision = true,
+                _ => {}
+            }
+        }
+        Some(info)
+    }
+
+    fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+        boxes(data).find(|(k, _)| k == kind).map(|(_, body)| body)
+    }
+
+    fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+        Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+    }
+
+    /// The `(type, body)` of each box in `data`, stopping at the first
+    /// malformed one.
+    fn boxes(mut data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+        std::iter::from_fn(move || {
+            let size = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+            let kind: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+            let (header, size) = match size {
+                // Extends to the end of the file
+                0 => (8, data.len()),
+                1 => (
+                    16,
+                    u64::from_be_bytes(data.get(8..16)?.try_into().ok()?) as usize,
+                ),
+                size => (8, size),
+            };
+            let body = data.get(header..size)?;
+            data = &data[size..];
+            Some((kind, body))
+        })
+    }
+}
+
+mod mkv {
+    use super::ColorInfo;
+
+    const SEGMENT: u64 = 0x1853_8067;
+    const TRACKS: u64 = 0x1654_AE6B;
+    const TRACK_ENTRY: u64 = 0xAE;
+    const TRACK_TYPE: u64 = 0x83;
+    const VIDEO: u64 = 0xE0;
+    const COLOUR: u64 = 0x55B0;
+    const CLUSTER: u64 = 0x1F43_B675;
+
+    pub(super) fn read(input: &[u8]) -> Option<ColorInfo> {
+        let segment = elements(input).find(|(id, _)| *id == SEGMENT)?.1;
+        let tracks = elements(segment)
+            .take_while(|(id, _)| *id != CLUSTER)
+            .find(|(id, _)| *id == TRACKS)?
+            .1;
+        elements(tracks)
+            .filter(|(id, _)| *id == TRACK_ENTRY)
+            .find_map(|(_, entry)| video_colour(entry))
+    }
+
+    /// The colour of a video track entry (track type 1).
+    fn video_colour(entry: &[u8]) -> Option<ColorInfo> {
+        let mut is_video = false;
+        let mut info = None;
+        for (id, body) in elements(entry) {
+            match id {
+                TRACK_TYPE => is_video = uint(body) == 1,
+                VIDEO => {
+                    let mut colour = ColorInfo::default();
+                    for (id, body) in elements(body) {
+                        if id == COLOUR {
+                            read_colour(body, &mut colour);
+                        }
+                    }
+                    info = Some(colour);
+                }
+                _ => {}
+            }
+        }
+        info.filter(|_| is_video)
+    }
+
+    fn read_colour(colour: &[u8], info: &mut ColorInfo) {
+        for (id, body) in elements(colour) {
+            let value = uint(body);
+            match id {
+                0x55B1 => info.matrix = Some(value),
+                0x55B2 => info.bit_depth = Some(value).filter(|&bits| bits > 0),
+                // 1 is broadcast range, 2 full range; 0 and 3 say nothing
+                0x55B9 if matches!(value, 1 | 2) => info.full_range = Some(value == 2),
+                0x55BA => info.transfer = Some(value),
+                0x55BB => info.primaries = Some(value),
+                0x55BC => info.max_cll = Some(value),
+                0x55BD => info.max_fall = Some(value),
+                0x55D0 => info.mastering = true,
+                _ => {}
+            }
+        }
+    }
+
+    fn uint(data: &[u8]) -> u64 {
+        data.iter().take(8).fold(0, |n, &b| (n << 8) | u64::from(b))
+    }
+
+    /// The `(id, body)` of each EBML element in `data`. An element of
+    /// unknown size runs to the end of `data`.
+    fn elements(mut data: &[u8]) -> impl Iterator<Item = (u64, &[u8])> {
+        std::iter::from_fn(move || {
+            let (id, id_len) = vint(data, true)?;
+            let (size, size_len) = vint(data.get(id_len..)?, false)?;
+            let start = id_len + size_len;
+            let unknown = size == (1 << (7 * size_len)) - 1;
+            let end = if unknown {
+                data.len()
+            } else {
+                start.checked_add(usize::try_from(size).ok()?)?
+            };
+            let body = data.get(start..end)?;
+            data = &data[end..];
+            Some((id, body))
+        })
+    }
+
+    /// A variable-length integer and its length. IDs keep their length
+    /// marker bit; sizes drop it.
+    fn vint(data: &[u8], keep_marker: bool) -> Option<(u64, usize)> {
+        let first = *data.first()?;
+        let len = first.leading_zeros() as usize + 1;
+        if len > 8 {
+            return None;
+        }
+        let bytes = data.get(..len)?;
+        let mut value = if keep_marker {
+            u64::from(first)
+        } else {
+            u64::from(first) & ((1 << (8 - len)) - 1)
+        };
+        for &b in &bytes[1..] {
+            value = (value << 8) | u64::from(b);
+        }
+        Some((value, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// An MP4 with one HEVC video track described by `children` boxes.
+    fn mp4(entry: &[u8; 4], children: &[Vec<u8>]) -> Vec<u8> {
+        let mut sample_entry = vec![0u8; 78];
+        sample_entry.extend(children.concat());
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend(mp4_box(entry, &sample_entry));
+        let mut hdlr = vec![0u8; 8];
+        hdlr.extend_from_slice(b"vide");
+        hdlr.extend([0u8; 12]);
+        let stbl = mp4_box(b"stbl", &mp4_box(b"stsd", &stsd));
+        let minf = mp4_box(b"minf", &stbl);
+        let mdia = mp4_box(b"mdia", &[mp4_box(b"hdlr", &hdlr), minf].concat());
+        let moov = mp4_box(b"moov", &mp4_box(b"trak", &mdia));
+        [mp4_box(b"ftyp", b"isom\0\0\0\0"), moov].concat()
+    }
+
+    #[test]
+    fn test_mp4_hdr10() {
+        let mut hvcc = vec![0u8; 23];
+        hvcc[17] = 0xF8 | 2;
+        let input = mp4(
+            b"hvc1",
+            &[
+                mp4_box(
+                    b"colr",
+                    &[b"nclx".as_slice(), &[0, 9, 0, 16, 0, 9, 0]].concat(),
+                ),
+                mp4_box(b"hvcC", &hvcc),
+                mp4_box(b"mdcv", &[0u8; 24]),
+                mp4_box(b"clli", &[0x03, 0xE8, 0x01, 0x90]),
+            ],
+        );
+        assert_eq!(
+            ColorInfo::read(&input).unwrap().rows(),
+            vec![
+                ("Dynamic Range", "HDR10".to_string()),
+                ("Color Primaries", "BT.2020".to_string()),
+                ("Transfer Characteristics", "SMPTE ST 2084 (PQ)".to_string()),
+                (
+                    "Matrix Coefficients",
+                    "BT.2020 non-constant luminance".to_string()
+                ),
+                ("Color Range", "Limited".to_string()),
+                ("Bit Depth", "10-bit".to_string()),
+                ("MaxCLL", "1000 nits".to_string()),
+                ("MaxFALL", "400 nits".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mp4_without_color() {
+        assert_eq!(ColorInfo::read(&mp4(b"avc1", &[])), None);
+    }
+
+    fn ebml(id: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.push(0x80 | body.len() as u8);
+        out.extend_from_slice(body);
+        out
+    }
+
+    #[test]
+    fn test_mkv_hlg() {
+        let colour = [
+            ebml(&[0x55, 0xB1], &[9]),
+            ebml(&[0x55, 0xB2], &[10]),
+            ebml(&[0x55, 0xB9], &[2]),
+            ebml(&[0x55, 0xBA], &[18]),
+            ebml(&[0x55, 0xBB], &[9]),
+        ]
+        .concat();
+        let video = ebml(&[0xE0], &ebml(&[0x55, 0xB0], &colour));
+        let entry = ebml(&[0xAE], &[ebml(&[0x83], &[1]), video].concat());
+        let segment = ebml(
+            &[0x18, 0x53, 0x80, 0x67],
+            &ebml(&[0x16, 0x54, 0xAE, 0x6B], &entry),
+        );
+        let input = [ebml(&[0x1A, 0x45, 0xDF, 0xA3], &[]), segment].concat();
+        assert_eq!(
+            ColorInfo::read(&input).unwrap().rows(),
+            vec![
+                ("Dynamic Range", "HLG".to_string()),
+                ("Color Primaries", "BT.2020".to_string()),
+                ("Transfer Characteristics", "ARIB STD-B67 (HLG)".to_string()),
+                (
+                    "Matrix Coefficients",
+                    "BT.2020 non-constant luminance".to_string()
+                ),
+                ("Color Range", "Full".to_string()),
+                ("Bit Depth", "10-bit".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::io::Write;
+
+use crate::error::Result;
+
+/// One subtitle cue, with its times in milliseconds.
+#[derive(Debug, PartialEq)]
+pub(super) struct Cue {
+    pub start: u64,
+    pub end: u64,
+    pub text: String,
+}
+
+/// Read the cues of an SRT or WebVTT file. Blocks without a timing line,
+/// such as the `WEBVTT` header and `NOTE` or `STYLE` blocks, are skipped,
+/// and formatting tags such as `<i>` or `{\an8}` are removed from the text.
+pub(super) fn cues(text: &str) -> Vec<Cue> {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    for block in text.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else {
+            continue;
+        };
+        let Some((start, end)) = timing.split_once("-->") else {
+            continue;
+        };
+        // WebVTT cue settings follow the end time
+        let end = end.split_whitespace().next().unwrap_or_default();
+        let (Some(start), Some(end)) = (timestamp(start.trim()), timestamp(end)) else {
+            continue;
+        };
+        let text = lines
+            .map(strip_tags)
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !text.is_empty() {
+            cues.push(Cue { start, end, text });
+        }
+    }
+    cues
+}
+
+/// Write `cues` as a timestamped transcript under a `## Subtitles` heading.
+pub(super) fn write_subtitles(writer: &mut dyn Write, cues: &[Cue]) -> Result<()> {
+    writeln!(writer, "## Subtitles")?;
+    writeln!(writer)?;
+    if cues.is_empty() {
+        writeln!(writer, "*No subtitles*")?;
+        return Ok(());
+    }
+    for cue in cues {
+        writeln!(
+            writer,
+            "**[{} – {}]** {}",
+            format_time(cue.start),
+            format_time(cue.end),
+            cue.text
+        )?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Milliseconds from an SRT `00:01:02,500` or WebVTT `01:02.500`
+/// timestamp; WebVTT leaves out the hours below an hour.
+fn timestamp(s: &str) -> Option<u64> {
+    let (time, millis) = s.split_once([',', '.'])?;
+    let mut secs: u64 = 0;
+    for part in time.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(secs * 1000 + millis.parse::<u64>().ok()?)
+}
+
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                chars.by_ref().find(|&c| c == '>');
+            }
+            '{' if chars.as_str().starts_with('\\') => {
+                chars.by_ref().find(|&c| c == '}');
+            }
+            c => text.push(c),
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn format_time(millis: u64) -> String {
+    let total_secs = millis / 1000;
+    let hours = total_secs / 3600;
+    let mins = (total_secs % 3600) / 60;
+    let secs = total_secs % 60;
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}")
+    } else {
+        format!("{mins}:{secs:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::srt(
+        "\u{feff}1\r\n00:00:01,000 --> 00:00:04,250\r\n<i>Hello</i> there,\r\nfriend.\r\n\r\n\
+         2\r\n01:02:03,000 --> 01:02:05,000\r\n{\\an8}Fish &amp; chips\r\n"
+    )]
+    #[case::vtt(
+        "WEBVTT - Demo\n\nNOTE written by hand\n\nSTYLE\n::cue { color: lime }\n\n\
+         intro\n00:01.000 --> 00:04.250 align:start\n<v Ann>Hello</v> there,\nfriend.\n\n\
+         01:02:03.000 --> 01:02:05.000\nFish &amp; chips\n"
+    )]
+    fn test_cues(#[case] text: &str) {
+        assert_eq!(
+            cues(text),
+            vec![
+                Cue {
+                    start: 1_000,
+                    end: 4_250,
+                    text: "Hello there, friend.".into(),
+                },
+                Cue {
+                    start: 3_723_000,
+                    end: 3_725_000,
+                    text: "Fish & chips".into(),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_write_subtitles() {
+        let mut output = Vec::new();
+        write_subtitles(
+            &mut output,
+            &cues("1\n00:00:01,000 --> 00:00:04,250\nHello\n"),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "## Subtitles\n\n**[0:01 – 0:04]** Hello\n\n"
+        );
+    }
+}
@@ -1,30 +1,84 @@
+use std::collections::{HashMap, HashSet};
 use std::io::{Cursor, Read, Write};
 
 use quick_xml::Reader;
 use quick_xml::events::Event;
 
+mod citations;
+
+use self::citations::{Bibliography, FieldResult};
+use super::anchors::Slugger;
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::media::{data_uri, image_markdown, input_stem, write_media};
+use crate::options::{ConvertOptions, MergedCells, Revisions, StyleMap, StyleRole};
+use crate::warnings;
 
-pub struct WordConverter;
+#[derive(Default)]
+pub struct WordConverter {
+    options: ConvertOptions,
+}
 
-impl Converter for WordConverter {
-    fn format_name(&self) -> &'static str {
-        "word"
+impl WordConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
     }
 
-    fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
-        let cursor = Cursor::new(input);
-        let mut archive = zip::ZipArchive::new(cursor).map_err(|e| Error::Conversion {
-            format: "word",
-            message: e.to_string(),
-        })?;
+    /// The link target for the image behind relationship `id`: the written
+    /// file with a media directory, a data URI with `--embed-media`, or the
+    /// URL of a linked (external) image. Empty when none applies.
+    fn image_link(
+        &self,
+        archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+        rels: &HashMap<String, Relationship>,
+        id: &str,
+    ) -> Result<String> {
+        let Some(rel) = rels.get(id) else {
+            return Ok(String::new());
+        };
+        if rel.external {
+            return Ok(rel.target.clone());
+        }
+        let media = &self.options.media;
+        if media.extract_dir.is_none() && !media.embed {
+            return Ok(String::new());
+        }
 
-        let document_xml = read_entry(&mut archive, "word/document.xml")?;
-        let paragraphs = parse_document(&document_xml)?;
+        // Targets are relative to word/, or absolute within the package
+        let path = match rel.target.strip_prefix('/') {
+            Some(absolute) => absolute.to_string(),
+            None => format!("word/{}", rel.target),
+        };
+        let Ok(bytes) = read_bytes(archive, &path) else {
+            warnings::warn(format!("Image {path} is missing from the document"));
+            return Ok(String::new());
+        };
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+        match &media.extract_dir {
+            Some(dir) => {
+                let stem = input_stem(self.options.input_name.as_deref(), "document");
+                write_media(
+                    dir,
+                    &format!("{stem}-{file_name}"),
+                    &bytes,
+                    media.link_base.as_deref(),
+                )
+            }
+            None => Ok(data_uri(file_name, &bytes)),
+        }
+    }
 
+    /// Render parsed paragraphs as Markdown blocks separated by blank lines.
+    fn write_paragraphs(
+        &self,
+        writer: &mut dyn Write,
+        paragraphs: &[Paragraph],
+        numbering: &Numbering,
+        final_section: Option<&SectionProps>,
+    ) -> Result<()> {
         let mut first = true;
-        for para in &paragraphs {
+        let mut lists = ListState::default();
+        for (idx, para) in paragraphs.iter().enumerate() {
             match para {
                 Paragraph::Heading(level, text) => {
                     if !first {
@@ -41,8 +95,22 @@ impl Converter for WordConverter {
                         writeln!(writer, "{text}")?;
                     }
                 }
-                Paragraph::ListItem(text) => {
-                    writeln!(writer, "- {text}")?;
+                Paragraph::ListItem {
+                    text,
+                    level,
+                    num_id,
+                } => {
+                    let continued =
+                        idx > 0 && matches!(paragraphs[idx - 1], Paragraph::ListItem { .. });
+                    if !continued {
+                        if !first {
+                            writeln!(writer)?;
+                        }
+                        // Numbering carries on across interruptions, as in Word
+                        lists.content_columns.clear();
+                    }
+                    let (indent, marker) = lists.next(numbering, num_id.as_deref(), *level);
+                    writeln!(writer, "{}{marker} {text}", " ".repeat(indent))?;
                 }
                 Paragraph::BlockQuote(text) => {
                     if !first {
@@ -50,15 +118,217 @@ impl Converter for WordConverter {
                     }
                     writeln!(writer, "> {text}")?;
                 }
-                Paragraph::Table(rows) => {
+                Paragraph::Code(text) => {
+                    // Consecutive code paragraphs share one fenced block
+                    let continued = idx > 0 && matches!(paragraphs[idx - 1], Paragraph::Code(_));
+                    if !continued {
+                        if !first {
+                            writeln!(writer)?;
+                        }
+                        writeln!(writer, "```")?;
+                    }
+                    // Blank lines Word leaves after a listing stay out of it
+                    let trailing_blank = continued
+                        && paragraphs[idx..]
+                            .iter()
+                            .take_while(|p| matches!(p, Paragraph::Code(_)))
+                            .all(|p| matches!(p, Paragraph::Code(text) if text.is_empty()));
+                    if !trailing_blank {
+                        writeln!(writer, "{text}")?;
+                    }
+                    if !matches!(paragraphs.get(idx + 1), Some(Paragraph::Code(_))) {
+                        writeln!(writer, "```")?;
+                    }
+                }
+                Paragraph::Table { rows, merges } => {
                     if !first {
                         writeln!(writer)?;
                     }
-                    write_table(writer, rows)?;
+                    match self.options.word.merged_cells {
+                        MergedCells::Repeat => write_table(writer, &repeat_merged(rows, merges))?,
+                        _ => write_table(writer, rows)?,
+                    }
+                    if self.options.word.merged_cells == MergedCells::Annotate && !merges.is_empty()
+                    {
+                        let ranges: Vec<String> = merges.iter().map(Merge::range).collect();
+                        writeln!(writer)?;
+                        writeln!(writer, "*Merged cells: {}*", ranges.join(", "))?;
+                    }
+                }
+                Paragraph::Figure { alt, target } => {
+                    if !first {
+                        writeln!(writer)?;
+                    }
+                    writeln!(writer, "{}", image_markdown(alt, target))?;
+                }
+                Paragraph::Caption(text) => {
+                    if !first {
+                        writeln!(writer)?;
+                    }
+                    writeln!(writer, "*{text}*")?;
+                }
+                Paragraph::SectionBreak(_) => {
+                    // A break at the very end only closes the final section.
+                    if idx + 1 == paragraphs.len() {
+                        continue;
+                    }
+                    if !first {
+                        writeln!(writer)?;
+                    }
+                    writeln!(writer, "---")?;
+                    if self.options.word.section_notes {
+                        // Section properties describe the section they close, so the
+                        // layout of the next section comes from the following break.
+                        let next = paragraphs[idx + 1..]
+                            .iter()
+                            .find_map(|p| match p {
+                                Paragraph::SectionBreak(props) => Some(props),
+                                _ => None,
+                            })
+                            .or(final_section);
+                        if let Some(note) = next.and_then(SectionProps::describe) {
+                            writeln!(writer)?;
+                            writeln!(writer, "*{note}*")?;
+                        }
+                    }
                 }
             }
             first = false;
         }
+        Ok(())
+    }
+}
+
+impl Converter for WordConverter {
+    fn format_name(&self) -> &'static str {
+        "word"
+    }
+
+    fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
+        let cursor = Cursor::new(input);
+        let mut archive = zip::ZipArchive::new(cursor).map_err(|e| Error::Conversion {
+            format: "word",
+            message: e.to_string(),
+        })?;
+
+        let document_xml = read_entry(&mut archive, "word/document.xml")?;
+        // Style maps may use display names, and custom or localized heading
+        // styles only declare their outline level, in styles.xml
+        let (names, headings, code) = read_entry(&mut archive, "word/styles.xml")
+            .map(|xml| parse_styles(&xml))
+            .unwrap_or_default();
+        let styles = Styles {
+            map: &self.options.word.style_map,
+            names,
+            headings,
+            code,
+        };
+        let rels = read_entry(&mut archive, "word/_rels/document.xml.rels")
+            .map(|xml| parse_relationships(&xml))
+            .unwrap_or_default();
+        let mut notes = Notes {
+            footnotes: read_entry(&mut archive, "word/footnotes.xml")
+                .map(|xml| parse_notes(&xml))
+                .unwrap_or_default(),
+            endnotes: read_entry(&mut archive, "word/endnotes.xml")
+                .map(|xml| parse_notes(&xml))
+                .unwrap_or_default(),
+            referenced: Vec::new(),
+            comments: Comments {
+                comments: if self.options.word.comments {
+                    read_entry(&mut archive, "word/comments.xml")
+                        .map(|xml| parse_annotations(&xml))
+                        .unwrap_or_default()
+                } else {
+                    HashMap::new()
+                },
+                ..Default::default()
+            },
+        };
+        let numbering = read_entry(&mut archive, "word/numbering.xml")
+            .map(|xml| parse_numbering(&xml))
+            .unwrap_or_default();
+        // Word keeps the document's sources in a custom XML part
+        let source_parts: Vec<String> = archive
+            .file_names()
+            .filter(|name| name.starts_with("customXml/item") && name.ends_with(".xml"))
+            .map(str::to_string)
+            .collect();
+        let bibliography = source_parts
+            .iter()
+            .filter_map(|name| read_entry(&mut archive, name).ok())
+            .find_map(|xml| Bibliography::parse(&xml))
+            .unwrap_or_default();
+        let mut link_image = |id: &str| self.image_link(&mut archive, &rels, id);
+        let context = Context {
+            styles: &styles,
+            rels: &rels,
+            bibliography: &bibliography,
+            revisions: self.options.word.revisions,
+        };
+        let (mut paragraphs, final_section) =
+            parse_document(&document_xml, &context, &mut notes, &mut link_image)
+                .map_err(|e| e.in_entry("word/document.xml"))?;
+        attach_captions(&mut paragraphs);
+        paragraphs.extend(notes.comments.section());
+
+        let (headers, footers) = if self.options.word.headers_footers {
+            let mut render = |kind: &str| -> Result<Vec<String>> {
+                let mut rendered: Vec<String> = Vec::new();
+                for part in part_names(&archive, kind) {
+                    let xml = read_entry(&mut archive, &part)?;
+                    let rels = read_entry(&mut archive, &part_rels_name(&part))
+                        .map(|xml| parse_relationships(&xml))
+                        .unwrap_or_default();
+                    let context = Context {
+                        rels: &rels,
+                        ..context
+                    };
+                    let mut link_image = |id: &str| self.image_link(&mut archive, &rels, id);
+                    let (paragraphs, _) =
+                        parse_document(&xml, &context, &mut notes, &mut link_image)
+                            .map_err(|e| e.in_entry(&part))?;
+                    let mut output = Vec::new();
+                    self.write_paragraphs(&mut output, &paragraphs, &numbering, None)?;
+                    let text = String::from_utf8_lossy(&output).trim().to_string();
+                    // Sections often repeat the same header or footer
+                    if !text.is_empty() && !rendered.contains(&text) {
+                        rendered.push(text);
+                    }
+                }
+                Ok(rendered)
+            };
+            (render("header")?, render("footer")?)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        for header in &headers {
+            writeln!(writer, "{header}")?;
+            writeln!(writer)?;
+        }
+        if !headers.is_empty() {
+            writeln!(writer, "---")?;
+            writeln!(writer)?;
+        }
+
+        self.write_paragraphs(writer, &paragraphs, &numbering, final_section.as_ref())?;
+
+        if !footers.is_empty() {
+            writeln!(writer)?;
+            writeln!(writer, "---")?;
+        }
+        for footer in &footers {
+            writeln!(writer)?;
+            writeln!(writer, "{footer}")?;
+        }
+
+        if !notes.referenced.is_empty() {
+            writeln!(writer)?;
+            for (i, (_, text)) in notes.referenced.iter().enumerate() {
+                writeln!(writer, "[^{}]: {text}", i + 1)?;
+            }
+        }
 
         Ok(())
     }
@@ -67,12 +337,639 @@ impl Converter for WordConverter {
 enum Paragraph {
     Heading(u8, String),
     Text(String),
-    ListItem(String),
+    ListItem {
+        text: String,
+        /// Nesting depth from `w:ilvl`, 0 for top-level items.
+        level: usize,
+        /// The `w:numId` numbering instance, which says whether the list is
+        /// ordered. `None` for items made lists by their style alone.
+        num_id: Option<String>,
+    },
     BlockQuote(String),
-    Table(Vec<Vec<String>>),
+    /// One line of a code block, without inline formatting.
+    Code(String),
+    Table {
+        /// Cells laid out on the table grid; cells covered by a merged cell
+        /// are empty.
+        rows: Vec<Vec<String>>,
+        /// Cells spanning more than one grid column or row.
+        merges: Vec<Merge>,
+    },
+    /// A paragraph holding nothing but a single picture.
+    Figure {
+        alt: String,
+        /// Link target, empty when the image is not extracted.
+        target: String,
+    },
+    Caption(String),
+    /// End of a document section; carries the properties of the section it closes.
+    SectionBreak(SectionProps),
+}
+
+#[derive(Default)]
+struct SectionProps {
+    /// Page width in twentieths of a point.
+    width: Option<u32>,
+    /// Page height in twentieths of a point.
+    height: Option<u32>,
+    landscape: bool,
+}
+
+impl SectionProps {
+    fn describe(&self) -> Option<String> {
+        let (Some(w), Some(h)) = (self.width, self.height) else {
+            return None;
+        };
+        let orientation = if self.landscape || w > h {
+            "landscape"
+        } else {
+            "portrait"
+        };
+        let size = paper_name(w.min(h), w.max(h)).map(str::to_string).unwrap_or_else(|| {
+            format!(
+                "{:.1} × {:.1} in",
+                f64::from(w) / 1440.0,
+                f64::from(h) / 1440.0
+            )
+        });
+        Some(format!("Page: {size}, {orientation}"))
+    }
+}
+
+/// Match common paper sizes (short edge, long edge in twips) with some tolerance.
+fn paper_name(short: u32, long: u32) -> Option<&'static str> {
+    const SIZES: [(&str, u32, u32); 6] = [
+        ("Letter", 12240, 15840),
+        ("Legal", 12240, 20160),
+        ("Tabloid", 15840, 24480),
+        ("A5", 8391, 11906),
+        ("A4", 11906, 16838),
+        ("A3", 16838, 23811),
+    ];
+    SIZES
+        .iter()
+        .find(|(_, s, l)| short.abs_diff(*s) <= 20 && long.abs_diff(*l) <= 20)
+        .map(|(name, _, _)| *name)
+}
+
+/// Use caption text as alt text for adjacent figures that have none, so that
+/// references like "Figure 3" still point at something meaningful.
+fn attach_captions(paragraphs: &mut [Paragraph]) {
+    for i in 0..paragraphs.len() {
+        let Paragraph::Caption(caption) = &paragraphs[i] else {
+            continue;
+        };
+        let caption = caption.clone();
+        // Captions usually sit below the figure, but some templates put them above.
+        let candidates = [i.checked_sub(1), Some(i + 1)];
+        for j in candidates.into_iter().flatten() {
+            if let Some(Paragraph::Figure { alt, .. }) = paragraphs.get_mut(j)
+                && alt.is_empty()
+            {
+                *alt = strip_emphasis(&caption);
+                break;
+            }
+        }
+    }
+}
+
+/// A package relationship from `document.xml.rels`.
+struct Relationship {
+    target: String,
+    /// Points outside the package, e.g. a linked rather than embedded image.
+    external: bool,
+}
+
+fn parse_relationships(xml: &str) -> HashMap<String, Relationship> {
+    let mut rels = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e))
+                if local_name(e.name().as_ref()) == "Relationship" =>
+            {
+                let mut id = None;
+                let mut target = None;
+                let mut external = false;
+                for attr in e.attributes().flatten() {
+                    let value = String::from_utf8_lossy(&attr.value).to_string();
+                    match attr.key.as_ref() {
+                        b"Id" => id = Some(value),
+                        b"Target" => target = Some(value),
+                        b"TargetMode" => external = value == "External",
+                        _ => {}
+                    }
+                }
+                if let (Some(id), Some(target)) = (id, target) {
+                    rels.insert(id, Relationship { target, external });
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    rels
+}
+
+fn strip_emphasis(text: &str) -> String {
+    text.replace("***", "").replace("**", "").replace('*', "")
+}
+
+fn is_caption(style: &str) -> bool {
+    // French templates use the accent-stripped style id "Lgende"
+    let lower = style.to_ascii_lowercase();
+    lower == "caption" || lower == "legende" || lower == "lgende"
+}
+
+/// Resolves paragraph style ids against the user's style map, which may
+/// name either the id or the display name declared in `styles.xml`, and
+/// against the heading levels `styles.xml` declares.
+struct Styles<'a> {
+    map: &'a StyleMap,
+    /// Display name by style id.
+    names: HashMap<String, String>,
+    /// Heading level by style id, for styles with an outline level.
+    headings: HashMap<String, u8>,
+    /// Ids of styles set in a monospace font.
+    code: HashSet<String>,
+}
+
+impl Styles<'_> {
+    fn role(&self, id: &str) -> Option<StyleRole> {
+        self.map
+            .get(id)
+            .or_else(|| self.names.get(id).and_then(|name| self.map.get(name)))
+    }
+
+    /// The heading level of a style, from its outline level or, for styles
+    /// `styles.xml` does not declare, a `HeadingN`-style id.
+    fn heading(&self, id: &str) -> Option<u8> {
+        match self.headings.get(id) {
+            Some(&level) => Some(level),
+            None if self.names.contains_key(id) => None,
+            None => heading_level(id),
+        }
+    }
+
+    /// Whether a paragraph or character style is for code, by its name or
+    /// its font.
+    fn is_code(&self, id: &str) -> bool {
+        self.code.contains(id)
+            || is_code_style(id)
+            || self.names.get(id).is_some_and(|name| is_code_style(name))
+    }
+}
+
+/// Parse `styles.xml` into each style's display name, the heading level of
+/// styles that are headings, and the styles set in a monospace font. A
+/// style is a heading when it or a style it is based on has an outline
+/// level, or is named like a built-in heading: Word keeps the English
+/// `heading 1` name even in localized documents, but other editors and
+/// older templates write the localized name.
+fn parse_styles(
+    xml: &str,
+) -> (
+    HashMap<String, String>,
+    HashMap<String, u8>,
+    HashSet<String>,
+) {
+    let mut names = HashMap::new();
+    let mut based_on: HashMap<String, String> = HashMap::new();
+    // Outline level 0-8 by style id; 9 marks body text
+    let mut outline: HashMap<String, u8> = HashMap::new();
+    let mut monospace: HashMap<String, bool> = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut current_id: Option<String> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if local_name(e.name().as_ref()) == "style" => {
+                current_id = attr_value(&e, "styleId");
+            }
+            Ok(Event::End(e)) if local_name(e.name().as_ref()) == "style" => current_id = None,
+            Ok(Event::Empty(e)) => {
+                let Some(id) = current_id.clone() else {
+                    continue;
+                };
+                if local_name(e.name().as_ref()) == "rFonts" {
+                    if let Some(font) = attr_value(&e, "ascii").or_else(|| attr_value(&e, "hAnsi"))
+                    {
+                        monospace.insert(id, is_monospace_font(&font));
+                    }
+                    continue;
+                }
+                let Some(value) = attr_value(&e, "val") else {
+                    continue;
+                };
+                match local_name(e.name().as_ref()).as_str() {
+                    "name" => {
+                        names.insert(id, value);
+                    }
+                    "basedOn" => {
+                        based_on.insert(id, value);
+                    }
+                    "outlineLvl" => {
+                        if let Ok(level) = value.parse() {
+                            outline.insert(id, level);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    let mut code = HashSet::new();
+    for id in names.keys() {
+        let mut current = id.as_str();
+        // The nearest font set along the basedOn chain applies
+        for _ in 0..16 {
+            if let Some(&mono) = monospace.get(current) {
+                if mono {
+                    code.insert(id.clone());
+                }
+                break;
+            }
+            match based_on.get(current) {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+    }
+
+    let mut headings = HashMap::new();
+    for id in names.keys().chain(outline.keys()) {
+        let mut current = id.as_str();
+        // Follow basedOn links, guarding against cycles
+        for _ in 0..16 {
+            if let Some(&level) = outline.get(current) {
+                if level < 9 {
+                    headings.insert(id.clone(), (level + 1).min(6));
+                }
+                break;
+            }
+            if let Some(level) = names.get(current).and_then(|name| heading_level(name)) {
+                headings.insert(id.clone(), level);
+                break;
+            }
+            match based_on.get(current) {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+    }
+    (names, headings, code)
+}
+
+/// Whether each level of a numbering instance is ordered, and the number
+/// it starts at, keyed by `w:numId` and level.
+type Numbering = HashMap<String, HashMap<usize, ListLevel>>;
+
+#[derive(Clone, Copy)]
+struct ListLevel {
+    ordered: bool,
+    start: usize,
+}
+
+/// Parse `numbering.xml`, resolving each `w:num` instance to the level
+/// formats of its abstract numbering definition.
+fn parse_numbering(xml: &str) -> Numbering {
+    let mut abstract_levels: HashMap<String, HashMap<usize, ListLevel>> = HashMap::new();
+    let mut instances: Vec<(String, String)> = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    let mut abstract_id: Option<String> = None;
+    let mut num_id: Option<String> = None;
+    let mut level: Option<(usize, ListLevel)> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match local_name(e.name().as_ref()).as_str() {
+                "abstractNum" => abstract_id = attr_value(&e, "abstractNumId"),
+                "num" => num_id = attr_value(&e, "numId"),
+                "lvl" => {
+                    level = attr_value(&e, "ilvl")
+                        .and_then(|ilvl| ilvl.parse().ok())
+                        .map(|ilvl| {
+                            (
+                                ilvl,
+                                ListLevel {
+                                    ordered: false,
+                                    start: 1,
+                                },
+                            )
+                        });
+                }
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => match local_name(e.name().as_ref()).as_str() {
+                "numFmt" => {
+                    if let Some((_, lvl)) = level.as_mut() {
+                        lvl.ordered = attr_value(&e, "val")
+                            .is_some_and(|format| format != "bullet" && format != "none");
+                    }
+                }
+                "start" => {
+                    if let Some((_, lvl)) = level.as_mut()
+                        && let Some(start) = attr_value(&e, "val").and_then(|v| v.parse().ok())
+                    {
+                        lvl.start = start;
+                    }
+                }
+                "abstractNumId" => {
+                    if let (Some(num), Some(id)) = (num_id.take(), attr_value(&e, "val")) {
+                        instances.push((num, id));
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()).as_str() {
+                "lvl" => {
+                    if let (Some(id), Some((ilvl, lvl))) = (&abstract_id, level.take()) {
+                        abstract_levels
+                            .entry(id.clone())
+                            .or_default()
+                            .insert(ilvl, lvl);
+                    }
+                }
+                "abstractNum" => abstract_id = None,
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    instances
+        .into_iter()
+        .filter_map(|(num, id)| Some((num, abstract_levels.get(&id)?.clone())))
+        .collect()
+}
+
+/// Numbering and indentation of the list being written.
+#[derive(Default)]
+struct ListState {
+    /// Next number per numbering instance and level.
+    counters: HashMap<(String, usize), usize>,
+    /// Column where the text of the latest item at each level starts, which
+    /// is where its nested items are indented to.
+    content_columns: Vec<usize>,
+}
+
+impl ListState {
+    /// The indentation and marker (`-` or `3.`) of the next item.
+    fn next(
+        &mut self,
+        numbering: &Numbering,
+        num_id: Option<&str>,
+        level: usize,
+    ) -> (usize, String) {
+        let format = num_id.and_then(|id| numbering.get(id)?.get(&level).copied());
+        let marker = match (num_id, format) {
+            (Some(id), Some(format)) if format.ordered => {
+                // A new item at this level restarts the numbering below it
+                self.counters
+                    .retain(|(other, other_level), _| other != id || *other_level <= level);
+                let counter = self
+                    .counters
+                    .entry((id.to_string(), level))
+                    .or_insert(format.start);
+                let number = *counter;
+                *counter += 1;
+                format!("{number}.")
+            }
+            _ => "-".to_string(),
+        };
+        let level = level.min(self.content_columns.len());
+        let indent = match level {
+            0 => 0,
+            _ => self.content_columns[level - 1],
+        };
+        self.content_columns.truncate(level);
+        self.content_columns.push(indent + marker.len() + 1);
+        (indent, marker)
+    }
 }
 
-fn parse_document(xml: &str) -> Result<Vec<Paragraph>> {
+/// Footnote and endnote texts by id, and the notes the body references in
+/// the order it first references them.
+struct Notes {
+    footnotes: HashMap<String, String>,
+    endnotes: HashMap<String, String>,
+    /// `(kind, id)` and text of each referenced note; the position plus one
+    /// is its Markdown footnote label.
+    referenced: Vec<((&'static str, String), String)>,
+    comments: Comments,
+}
+
+/// Comments by id, with the text each is anchored to.
+#[derive(Default)]
+struct Comments {
+    /// Author and text by comment id, from `comments.xml`.
+    comments: HashMap<String, (Option<String>, String)>,
+    /// Ids and anchor text of the comments found in the document, in order.
+    anchored: Vec<(String, String)>,
+    /// Indexes into `anchored` of the comment ranges still open.
+    open: Vec<usize>,
+}
+
+impl Comments {
+    /// A `commentRangeStart`, or a `commentReference` without a range.
+    fn start(&mut self, id: String) -> Option<usize> {
+        if !self.comments.contains_key(&id) {
+            return None;
+        }
+        match self
+            .anchored
+            .iter()
+            .position(|(anchored, _)| *anchored == id)
+        {
+            Some(index) => Some(index),
+            None => {
+                self.anchored.push((id, String::new()));
+                Some(self.anchored.len() - 1)
+            }
+        }
+    }
+
+    fn end(&mut self, id: &str) {
+        self.open.retain(|&i| self.anchored[i].0 != id);
+    }
+
+    /// Add document text to every open comment range.
+    fn push_text(&mut self, text: &str) {
+        for &i in &self.open {
+            self.anchored[i].1.push_str(text);
+        }
+    }
+
+    /// A "Comments" section listing each comment with its author and the
+    /// text it is anchored to; empty when there are none.
+    fn section(&self) -> Vec<Paragraph> {
+        let items: Vec<Paragraph> = self
+            .anchored
+            .iter()
+            .filter_map(|(id, anchor)| {
+                let (author, text) = self.comments.get(id)?;
+                let anchor = anchor.split_whitespace().collect::<Vec<_>>().join(" ");
+                let mut item = match author {
+                    Some(author) => format!("**{author}**"),
+                    None => String::new(),
+                };
+                if !anchor.is_empty() {
+                    item.push_str(&format!(" on \"{anchor}\""));
+                }
+                let item = item.trim_start();
+                let text = if item.is_empty() {
+                    text.clone()
+                } else {
+                    format!("{item}: {text}")
+                };
+                Some(Paragraph::ListItem {
+                    text,
+                    level: 0,
+                    num_id: None,
+                })
+            })
+            .collect();
+        if items.is_empty() {
+            return items;
+        }
+        let mut section = vec![Paragraph::Heading(2, "Comments".to_string())];
+        section.extend(items);
+        section
+    }
+}
+
+impl Notes {
+    /// The Markdown reference for a `footnoteReference` or
+    /// `endnoteReference`, or `None` when the note does not exist.
+    fn reference(&mut self, kind: &'static str, id: String) -> Option<String> {
+        let key = (kind, id);
+        let index = match self.referenced.iter().position(|(k, _)| *k == key) {
+            Some(index) => index,
+            None => {
+                let notes = if kind == "footnote" {
+                    &self.footnotes
+                } else {
+                    &self.endnotes
+                };
+                let text = notes.get(&key.1)?.clone();
+                self.referenced.push((key, text));
+                self.referenced.len() - 1
+            }
+        };
+        Some(format!("[^{}]", index + 1))
+    }
+}
+
+/// Parse `footnotes.xml` or `endnotes.xml` into the text of each note, its
+/// paragraphs joined by spaces. The separator lines Word stores alongside
+/// the notes are skipped.
+fn parse_notes(xml: &str) -> HashMap<String, String> {
+    parse_annotations(xml)
+        .into_iter()
+        .map(|(id, (_, text))| (id, text))
+        .collect()
+}
+
+/// Parse notes or `comments.xml` into the author and text of each note or
+/// comment by id.
+fn parse_annotations(xml: &str) -> HashMap<String, (Option<String>, String)> {
+    let mut notes = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    // Id, author and text of the note being read
+    let mut current: Option<(String, Option<String>, Vec<String>)> = None;
+    let mut in_text = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match local_name(e.name().as_ref()).as_str() {
+                "footnote" | "endnote" | "comment" => {
+                    let mut id = None;
+                    let mut separator = false;
+                    for attr in e.attributes().flatten() {
+                        match local_name(attr.key.as_ref()).as_str() {
+                            "id" => id = Some(String::from_utf8_lossy(&attr.value).to_string()),
+                            "type" => separator = attr.value.as_ref() != b"normal",
+                            _ => {}
+                        }
+                    }
+                    let author = attr_value(&e, "author").filter(|a| !a.is_empty());
+                    current = id.filter(|_| !separator).map(|id| (id, author, Vec::new()));
+                }
+                "p" => {
+                    if let Some((_, _, paragraphs)) = current.as_mut() {
+                        paragraphs.push(String::new());
+                    }
+                }
+                "t" => in_text = true,
+                _ => {}
+            },
+            Ok(Event::Text(e)) if in_text => {
+                if let Some(paragraph) = current.as_mut().and_then(|(_, _, p)| p.last_mut()) {
+                    paragraph.push_str(&e.decode().unwrap_or_default());
+                }
+            }
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()).as_str() {
+                "footnote" | "endnote" | "comment" => {
+                    if let Some((id, author, paragraphs)) = current.take() {
+                        let text: Vec<&str> = paragraphs
+                            .iter()
+                            .map(|p| p.trim())
+                            .filter(|p| !p.is_empty())
+                            .collect();
+                        notes.insert(id, (author, text.join(" ")));
+                    }
+                }
+                "t" => in_text = false,
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    notes
+}
+
+/// Document-wide parts the body refers to.
+struct Context<'a> {
+    styles: &'a Styles<'a>,
+    /// Resolves hyperlink targets.
+    rels: &'a HashMap<String, Relationship>,
+    /// Sources for citation and bibliography fields.
+    bibliography: &'a Bibliography,
+    /// How tracked insertions and deletions are rendered.
+    revisions: Revisions,
+}
+
+/// A field being parsed, either complex (`w:fldChar` runs) or `w:fldSimple`.
+#[derive(Default)]
+struct Field {
+    instr: String,
+    /// Set once the instruction is complete and the stored result follows.
+    result: Option<Option<FieldResult>>,
+}
+
+impl Field {
+    /// Whether the result Word stored is being replaced.
+    fn replaces_result(&self) -> bool {
+        matches!(self.result, Some(Some(_)))
+    }
+}
+
+/// Parse the document body. `notes` resolves footnote and endnote
+/// references, and `link_image` a picture's relationship id to the target
+/// its Markdown image links to.
+fn parse_document(
+    xml: &str,
+    context: &Context,
+    notes: &mut Notes,
+    link_image: &mut dyn FnMut(&str) -> Result<String>,
+) -> Result<(Vec<Paragraph>, Option<SectionProps>)> {
+    let Context {
+        styles,
+        rels,
+        bibliography,
+        revisions,
+    } = *context;
     let mut paragraphs = Vec::new();
     let mut reader = Reader::from_str(xml);
 
@@ -82,31 +979,91 @@ fn parse_document(xml: &str) -> Result<Vec<Paragraph>> {
     let mut in_table_row = false;
     let mut in_table_cell = false;
     let mut current_text = String::new();
+    // The same text without inline formatting, for code paragraphs
+    let mut plain_text = String::new();
     let mut current_style: Option<String> = None;
     let mut is_bold = false;
     let mut is_italic = false;
     let mut is_list_item = false;
+    let mut list_level = 0;
+    let mut num_id: Option<String> = None;
     let mut table_rows: Vec<Vec<String>> = Vec::new();
     let mut table_row: Vec<String> = Vec::new();
     let mut cell_text = String::new();
+    let mut merges: Vec<Merge> = Vec::new();
+    // Grid columns the current cell spans, and its `w:vMerge`, if any
+    let mut cell_span = 1;
+    let mut cell_merge: Option<VMerge> = None;
+    let mut section: Option<SectionProps> = None;
+    let mut pending_break: Option<SectionProps> = None;
+    let mut final_section: Option<SectionProps> = None;
+    // Alt text and link target of each picture in the current paragraph
+    let mut images: Vec<(String, String)> = Vec::new();
+    let mut image_alt: Option<String> = None;
+    let mut image_rel: Option<String> = None;
+    let mut has_text = false;
+    let mut in_fallback = false;
+    // Target of the open hyperlink and where its text starts
+    let mut link: Option<(String, usize)> = None;
+    // Bookmarks in the current paragraph, and those that mark a heading
+    let mut paragraph_bookmarks: Vec<String> = Vec::new();
+    let mut heading_bookmarks: Vec<(String, usize)> = Vec::new();
+    // Open fields, innermost last, and the replacement of one just closed
+    let mut fields: Vec<Field> = Vec::new();
+    let mut in_instr = false;
+    let mut finished: Option<FieldResult> = None;
+    let mut bibliography_due = false;
+    let mut has_bibliography = false;
+    let mut cited = false;
+    // Inside tracked changes (`w:ins`/`w:moveTo` and `w:del`/`w:moveFrom`),
+    // and inside the previous formatting a change recorded
+    let mut in_insertion = false;
+    let mut in_deletion = false;
+    let mut in_prop_change = false;
+    // Whether the open run is in a monospace font, whether all text of the
+    // paragraph so far is, and whether its paragraph mark is
+    let mut run_mono = false;
+    let mut all_mono = true;
+    let mut mark_mono = false;
+    let mut in_paragraph_props = false;
 
     loop {
         match reader.read_event() {
+            Ok(Event::Start(e)) if is_prop_change(e.name().as_ref()) => in_prop_change = true,
+            Ok(Event::Start(_) | Event::Empty(_)) if in_prop_change => {}
+            Ok(Event::End(e)) if in_prop_change => {
+                in_prop_change = !is_prop_change(e.name().as_ref());
+            }
             Ok(Event::Start(e)) => {
                 let local = local_name(e.name().as_ref());
                 match local.as_str() {
+                    "ins" | "moveTo" => in_insertion = true,
+                    "del" | "moveFrom" => in_deletion = true,
                     "p" => {
                         in_paragraph = true;
                         current_text.clear();
+                        plain_text.clear();
                         current_style = None;
                         is_bold = false;
                         is_italic = false;
                         is_list_item = false;
+                        list_level = 0;
+                        num_id = None;
+                        images.clear();
+                        has_text = false;
+                        paragraph_bookmarks.clear();
+                        all_mono = true;
+                        mark_mono = false;
+                    }
+                    "pPr" => in_paragraph_props = true,
+                    "r" => {
+                        in_run = true;
+                        run_mono = false;
                     }
-                    "r" => in_run = true,
                     "tbl" => {
                         in_table = true;
                         table_rows.clear();
+                        merges.clear();
                     }
                     "tr" => {
                         in_table_row = true;
@@ -115,6 +1072,34 @@ fn parse_document(xml: &str) -> Result<Vec<Paragraph>> {
                     "tc" => {
                         in_table_cell = true;
                         cell_text.clear();
+                        cell_span = 1;
+                        cell_merge = None;
+                    }
+                    "sectPr" => section = Some(SectionProps::default()),
+                    // Markup-compatibility fallbacks repeat the preferred content
+                    "Fallback" => in_fallback = true,
+                    "drawing" | "pict" if !in_fallback => {
+                        image_alt = Some(String::new());
+                        image_rel = None;
+                    }
+                    "docPr" | "shape" | "imagedata" => read_alt_text(&e, &mut image_alt),
+                    "blip" => read_image_rel(&e, &mut image_rel),
+                    "hyperlink" => {
+                        let start = if in_table_cell {
+                            cell_text.len()
+                        } else {
+                            current_text.len()
+                        };
+                        link = hyperlink_target(&e, rels).map(|target| (target, start));
+                    }
+                    "bookmarkStart" if in_paragraph => read_bookmark(&e, &mut paragraph_bookmarks),
+                    "instrText" => in_instr = true,
+                    "fldSimple" => {
+                        let instr = attr_value(&e, "instr").unwrap_or_default();
+                        fields.push(Field {
+                            result: Some(bibliography.field_result(&instr)),
+                            instr,
+                        });
                     }
                     _ => {}
                 }
@@ -133,56 +1118,307 @@ fn parse_document(xml: &str) -> Result<Vec<Paragraph>> {
                     }
                     "b" => is_bold = true,
                     "i" => is_italic = true,
-                    "numPr" | "ilvl" => is_list_item = true,
+                    "rStyle" if in_run => {
+                        run_mono = attr_value(&e, "val").is_some_and(|id| styles.is_code(&id));
+                    }
+                    "rFonts" if in_run || in_paragraph_props => {
+                        if let Some(font) =
+                            attr_value(&e, "ascii").or_else(|| attr_value(&e, "hAnsi"))
+                        {
+                            if in_run {
+                                run_mono = is_monospace_font(&font);
+                            } else {
+                                mark_mono = is_monospace_font(&font);
+                            }
+                        }
+                    }
+                    // Indentation and line breaks matter only inside code
+                    "tab" if in_run => plain_text.push('\t'),
+                    "br" if in_run
+                        && attr_value(&e, "type").is_none_or(|kind| kind == "textWrapping") =>
+                    {
+                        plain_text.push('\n');
+                    }
+                    "numPr" => is_list_item = true,
+                    "ilvl" => {
+                        is_list_item = true;
+                        list_level = attr_value(&e, "val")
+                            .and_then(|level| level.parse().ok())
+                            .unwrap_or(0);
+                    }
+                    // numId 0 removes numbering a style would otherwise apply
+                    "numId" => match attr_value(&e, "val") {
+                        Some(id) if id == "0" => {
+                            is_list_item = false;
+                            num_id = None;
+                        }
+                        id => {
+                            is_list_item = true;
+                            num_id = id;
+                        }
+                    },
+                    "gridSpan" if in_table_cell => {
+                        cell_span = attr_value(&e, "val")
+                            .and_then(|span| span.parse().ok())
+                            .unwrap_or(1)
+                            .max(1);
+                    }
+                    "vMerge" if in_table_cell => {
+                        cell_merge = Some(match attr_value(&e, "val").as_deref() {
+                            Some("restart") => VMerge::Restart,
+                            _ => VMerge::Continue,
+                        });
+                    }
+                    // Grid columns skipped before the first cell of a row
+                    "gridBefore" if in_table_row && !in_table_cell => {
+                        let skipped: usize = attr_value(&e, "val")
+                            .and_then(|n| n.parse().ok())
+                            .unwrap_or(0);
+                        table_row.extend(std::iter::repeat_n(String::new(), skipped));
+                    }
+                    "pgSz" => {
+                        if let Some(props) = section.as_mut() {
+                            for attr in e.attributes().flatten() {
+                                let value = String::from_utf8_lossy(&attr.value);
+                                match local_name(attr.key.as_ref()).as_str() {
+                                    "w" => props.width = value.parse().ok(),
+                                    "h" => props.height = value.parse().ok(),
+                                    "orient" => props.landscape = value == "landscape",
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+                    "docPr" | "shape" => read_alt_text(&e, &mut image_alt),
+                    "imagedata" => {
+                        read_alt_text(&e, &mut image_alt);
+                        read_image_rel(&e, &mut image_rel);
+                    }
+                    "blip" => read_image_rel(&e, &mut image_rel),
+                    "bookmarkStart" if in_paragraph => read_bookmark(&e, &mut paragraph_bookmarks),
+                    "fldChar" => match attr_value(&e, "fldCharType").as_deref() {
+                        Some("begin") => fields.push(Field::default()),
+                        Some("separate") => {
+                            if let Some(field) = fields.last_mut()
+                                && field.result.is_none()
+                            {
+                                field.result = Some(bibliography.field_result(&field.instr));
+                            }
+                        }
+                        Some("end") => {
+                            if let Some(field) = fields.pop() {
+                                finished = field
+                                    .result
+                                    .unwrap_or_else(|| bibliography.field_result(&field.instr));
+                            }
+                        }
+                        _ => {}
+                    },
+                    "fldSimple" => {
+                        let instr = attr_value(&e, "instr").unwrap_or_default();
+                        finished = bibliography.field_result(&instr);
+                    }
+                    "commentRangeStart" => {
+                        if let Some(index) =
+                            attr_value(&e, "id").and_then(|id| notes.comments.start(id))
+                        {
+                            notes.comments.open.push(index);
+                        }
+                    }
+                    "commentRangeEnd" => {
+                        if let Some(id) = attr_value(&e, "id") {
+                            notes.comments.end(&id);
+                        }
+                    }
+                    "commentReference" => {
+                        if let Some(id) = attr_value(&e, "id") {
+                            notes.comments.start(id);
+                        }
+                    }
+                    "footnoteReference" | "endnoteReference" if in_paragraph => {
+                        let kind = if local == "footnoteReference" {
+                            "footnote"
+                        } else {
+                            "endnote"
+                        };
+                        let id = e
+                            .attributes()
+                            .flatten()
+                            .find(|attr| local_name(attr.key.as_ref()) == "id")
+                            .map(|attr| String::from_utf8_lossy(&attr.value).to_string());
+                        if let Some(label) = id.and_then(|id| notes.reference(kind, id)) {
+                            if in_table_cell {
+                                cell_text.push_str(&label);
+                            } else {
+                                current_text.push_str(&label);
+                                has_text = true;
+                            }
+                        }
+                    }
+                    "sectPr" => {
+                        let props = SectionProps::default();
+                        if in_paragraph {
+                            pending_break = Some(props);
+                        } else {
+                            final_section = Some(props);
+                        }
+                    }
                     _ => {}
                 }
             }
-            Ok(Event::Text(e)) => {
-                if in_run || in_table_cell {
-                    let text = e.decode().unwrap_or_default().to_string();
+            Ok(Event::Text(e)) if in_run || in_table_cell => {
+                let text = e.decode().unwrap_or_default().to_string();
+                if in_instr {
+                    if let Some(field) = fields.last_mut() {
+                        field.instr.push_str(&text);
+                    }
+                } else if fields.iter().any(Field::replaces_result) {
+                    // Replaced once the field ends
+                } else if in_deletion && revisions == Revisions::Accept {
+                    // Deleted text is gone once changes are accepted
+                } else {
+                    let revision = match revisions {
+                        Revisions::Markup if in_insertion => Some(Revision::Inserted),
+                        Revisions::Markup if in_deletion => Some(Revision::Deleted),
+                        _ => None,
+                    };
+                    notes.comments.push_text(&text);
                     if in_table_cell {
-                        cell_text.push_str(&text);
+                        cell_text.push_str(&revision_text(&text, false, false, revision));
                     } else if in_paragraph {
-                        let formatted = format_run_text(&text, is_bold, is_italic);
+                        let formatted = revision_text(&text, is_bold, is_italic, revision);
                         current_text.push_str(&formatted);
+                        if revision != Some(Revision::Deleted) {
+                            plain_text.push_str(&text);
+                        }
+                        has_text |= !text.trim().is_empty();
+                        all_mono &= run_mono || text.trim().is_empty();
                     }
                 }
             }
             Ok(Event::End(e)) => {
                 let local = local_name(e.name().as_ref());
                 match local.as_str() {
+                    "ins" | "moveTo" => in_insertion = false,
+                    "del" | "moveFrom" => in_deletion = false,
                     "p" => {
+                        // Anchors spanning paragraphs read as one line
+                        notes.comments.push_text(" ");
                         if in_table_cell {
                             if !cell_text.is_empty() {
                                 // cell text accumulated separately
                             }
                         } else if in_paragraph {
-                            let para = if let Some(ref style) = current_style {
-                                if let Some(level) = heading_level(style) {
+                            // Empty paragraphs only continue a code block,
+                            // as blank lines between its statements
+                            let code_style = current_style
+                                .as_deref()
+                                .is_some_and(|id| styles.is_code(id));
+                            let is_code = if has_text {
+                                all_mono || code_style
+                            } else {
+                                (mark_mono || code_style)
+                                    && matches!(paragraphs.last(), Some(Paragraph::Code(_)))
+                            };
+                            let para = if images.len() == 1 && !has_text {
+                                let (alt, target) = images.remove(0);
+                                Paragraph::Figure { alt, target }
+                            } else if let Some(role) =
+                                current_style.as_deref().and_then(|id| styles.role(id))
+                            {
+                                match role {
+                                    StyleRole::Heading(level) => {
+                                        Paragraph::Heading(level, current_text.clone())
+                                    }
+                                    StyleRole::BlockQuote => {
+                                        Paragraph::BlockQuote(current_text.clone())
+                                    }
+                                    StyleRole::Code => Paragraph::Code(plain_text.clone()),
+                                    StyleRole::Caption => Paragraph::Caption(current_text.clone()),
+                                    StyleRole::ListItem => Paragraph::ListItem {
+                                        text: current_text.clone(),
+                                        level: list_level,
+                                        num_id: num_id.clone(),
+                                    },
+                                    StyleRole::Paragraph => Paragraph::Text(current_text.clone()),
+                                }
+                            } else if let Some(ref style) = current_style {
+                                if let Some(level) = styles.heading(style) {
                                     Paragraph::Heading(level, current_text.clone())
+                                } else if is_code {
+                                    Paragraph::Code(plain_text.clone())
                                 } else if is_blockquote(style) {
                                     Paragraph::BlockQuote(current_text.clone())
+                                } else if is_caption(style) {
+                                    Paragraph::Caption(current_text.clone())
                                 } else if is_list_item {
-                                    Paragraph::ListItem(current_text.clone())
+                                    Paragraph::ListItem {
+                                        text: current_text.clone(),
+                                        level: list_level,
+                                        num_id: num_id.clone(),
+                                    }
                                 } else {
                                     Paragraph::Text(current_text.clone())
                                 }
                             } else if is_list_item {
-                                Paragraph::ListItem(current_text.clone())
+                                Paragraph::ListItem {
+                                    text: current_text.clone(),
+                                    level: list_level,
+                                    num_id: num_id.clone(),
+                                }
+                            } else if is_code {
+                                Paragraph::Code(plain_text.clone())
                             } else {
                                 Paragraph::Text(current_text.clone())
                             };
+                            if matches!(para, Paragraph::Heading(..)) {
+                                heading_bookmarks.extend(
+                                    paragraph_bookmarks
+                                        .drain(..)
+                                        .map(|name| (name, paragraphs.len())),
+                                );
+                            }
                             paragraphs.push(para);
+                            if bibliography_due {
+                                paragraphs.extend(reference_list(bibliography));
+                                bibliography_due = false;
+                            }
+                        }
+                        if let Some(props) = pending_break.take() {
+                            paragraphs.push(Paragraph::SectionBreak(props));
                         }
                         in_paragraph = false;
                     }
+                    "pPr" => in_paragraph_props = false,
                     "r" => {
                         in_run = false;
                         is_bold = false;
                         is_italic = false;
+                        run_mono = false;
                     }
                     "tc" => {
-                        table_row.push(cell_text.trim().to_string());
+                        let (row, col) = (table_rows.len(), table_row.len());
+                        if cell_merge == Some(VMerge::Continue) {
+                            // Covered by the cell above; extend its merge
+                            table_row.extend(std::iter::repeat_n(String::new(), cell_span));
+                            if let Some(merge) = merges
+                                .iter_mut()
+                                .find(|m| m.col == col && m.row + m.rows == row)
+                            {
+                                merge.rows += 1;
+                            }
+                        } else {
+                            table_row.push(cell_text.trim().to_string());
+                            table_row.extend(std::iter::repeat_n(String::new(), cell_span - 1));
+                            if cell_span > 1 || cell_merge.is_some() {
+                                merges.push(Merge {
+                                    row,
+                                    col,
+                                    rows: 1,
+                                    cols: cell_span,
+                                });
+                            }
+                        }
                         cell_text.clear();
                         in_table_cell = false;
                     }
@@ -193,30 +1429,294 @@ fn parse_document(xml: &str) -> Result<Vec<Paragraph>> {
                     }
                     "tbl" => {
                         if !table_rows.is_empty() {
-                            paragraphs.push(Paragraph::Table(table_rows.clone()));
+                            merges.retain(|m| m.rows > 1 || m.cols > 1);
+                            paragraphs.push(Paragraph::Table {
+                                rows: table_rows.clone(),
+                                merges: std::mem::take(&mut merges),
+                            });
                         }
                         table_rows.clear();
                         in_table = false;
                     }
+                    "hyperlink" => {
+                        if let Some((target, start)) = link.take() {
+                            let text = if in_table_cell {
+                                &mut cell_text
+                            } else {
+                                &mut current_text
+                            };
+                            if text.len() > start && !text[start..].trim().is_empty() {
+                                let label = text.split_off(start);
+                                text.push_str(&format!("[{label}]({target})"));
+                            }
+                        }
+                    }
+                    "instrText" => in_instr = false,
+                    "fldSimple" => {
+                        if let Some(field) = fields.pop() {
+                            finished = field.result.flatten();
+                        }
+                    }
+                    "Fallback" => in_fallback = false,
+                    "drawing" | "pict" if !in_fallback => {
+                        if let Some(alt) = image_alt.take() {
+                            let target = match image_rel.take() {
+                                Some(id) => link_image(&id)?,
+                                None => String::new(),
+                            };
+                            if in_table_cell {
+                                cell_text.push_str(&image_markdown(&alt, &target));
+                            } else if in_paragraph {
+                                current_text.push_str(&image_markdown(&alt, &target));
+                            }
+                            images.push((alt, target));
+                        }
+                    }
+                    "sectPr" => {
+                        // A sectPr inside a paragraph's properties ends a section at
+                        // that paragraph; the body-level one describes the last section.
+                        if let Some(props) = section.take() {
+                            if in_paragraph {
+                                pending_break = Some(props);
+                            } else {
+                                final_section = Some(props);
+                            }
+                        }
+                    }
                     _ => {}
                 }
             }
             Ok(Event::Eof) => break,
             Err(e) => {
-                return Err(Error::Conversion {
+                return Err(Error::Parse {
                     format: "word",
-                    message: format!("Failed to parse document.xml: {e}"),
+                    entry: None,
+                    offset: Some(reader.error_position()),
+                    message: e.to_string(),
                 });
             }
             _ => {}
         }
+
+        match finished.take() {
+            Some(FieldResult::Citation(citation)) => {
+                if in_table_cell {
+                    cell_text.push_str(&citation);
+                } else {
+                    current_text.push_str(&citation);
+                    has_text = true;
+                }
+                cited = true;
+            }
+            Some(FieldResult::Bibliography) => {
+                bibliography_due = true;
+                has_bibliography = true;
+            }
+            None => {}
+        }
     }
 
     // Suppress unused variable warnings
     let _ = in_table;
-    let _ = in_table_row;
 
-    Ok(paragraphs)
+    // Cited sources still get listed when there is no bibliography field
+    if cited && !has_bibliography {
+        paragraphs.push(Paragraph::Heading(2, "References".to_string()));
+        paragraphs.extend(reference_list(bibliography));
+    }
+
+    retarget_bookmark_links(&mut paragraphs, &heading_bookmarks);
+    Ok((paragraphs, final_section))
+}
+
+fn reference_list(bibliography: &Bibliography) -> Vec<Paragraph> {
+    bibliography
+        .references()
+        .into_iter()
+        .map(|text| Paragraph::ListItem {
+            text,
+            level: 0,
+            num_id: None,
+        })
+        .collect()
+}
+
+/// The Markdown link target of a `w:hyperlink`: the URL (or document) its
+/// relationship points to, an in-document bookmark via `w:anchor`, or both.
+fn hyperlink_target(
+    e: &quick_xml::events::BytesStart,
+    rels: &HashMap<String, Relationship>,
+) -> Option<String> {
+    let mut url = None;
+    let mut anchor = None;
+    for attr in e.attributes().flatten() {
+        let value = String::from_utf8_lossy(&attr.value).to_string();
+        match local_name(attr.key.as_ref()).as_str() {
+            "id" => url = rels.get(&value).map(|rel| rel.target.clone()),
+            "anchor" => anchor = Some(value),
+            _ => {}
+        }
+    }
+    match (url, anchor) {
+        (Some(url), Some(anchor)) => Some(format!("{url}#{anchor}")),
+        (Some(url), None) => Some(url),
+        (None, Some(anchor)) => Some(format!("#{anchor}")),
+        (None, None) => None,
+    }
+    .map(|target| target.replace(' ', "%20"))
+}
+
+fn read_bookmark(e: &quick_xml::events::BytesStart, bookmarks: &mut Vec<String>) {
+    let name = e
+        .attributes()
+        .flatten()
+        .find(|attr| local_name(attr.key.as_ref()) == "name")
+        .map(|attr| String::from_utf8_lossy(&attr.value).to_string());
+    // Word adds _GoBack to remember the last edit position
+    if let Some(name) = name.filter(|name| name != "_GoBack") {
+        bookmarks.push(name);
+    }
+}
+
+/// Point links to bookmarks on headings (a table of contents, or
+/// "see section" cross-references) at the anchor the heading gets in
+/// Markdown. Links to other bookmarks keep the bookmark name.
+fn retarget_bookmark_links(paragraphs: &mut [Paragraph], heading_bookmarks: &[(String, usize)]) {
+    if heading_bookmarks.is_empty() {
+        return;
+    }
+    let mut slugger = Slugger::default();
+    let slugs: HashMap<usize, String> = paragraphs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, para)| match para {
+            Paragraph::Heading(_, text) => Some((i, slugger.slug(text))),
+            _ => None,
+        })
+        .collect();
+    let targets: Vec<(String, String)> = heading_bookmarks
+        .iter()
+        .filter_map(|(name, i)| {
+            let slug = slugs.get(i)?;
+            Some((format!("](#{name})"), format!("](#{slug})")))
+        })
+        .collect();
+    let retarget = |text: &mut String| {
+        for (from, to) in &targets {
+            if text.contains(from.as_str()) {
+                *text = text.replace(from.as_str(), to);
+            }
+        }
+    };
+    for para in paragraphs {
+        match para {
+            Paragraph::Heading(_, text)
+            | Paragraph::Text(text)
+            | Paragraph::ListItem { text, .. }
+            | Paragraph::BlockQuote(text)
+            | Paragraph::Caption(text) => retarget(text),
+            Paragraph::Table { rows, .. } => rows.iter_mut().flatten().for_each(retarget),
+            _ => {}
+        }
+    }
+}
+
+/// Pick up alt text from a drawing's `docPr` (or a VML shape/imagedata),
+/// preferring the description over the title.
+fn read_alt_text(e: &quick_xml::events::BytesStart, image_alt: &mut Option<String>) {
+    let Some(alt) = image_alt.as_mut() else {
+        return;
+    };
+    let mut descr = None;
+    let mut title = None;
+    for attr in e.attributes().flatten() {
+        let value = attr
+            .normalized_value(quick_xml::XmlVersion::Implicit1_0)
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default();
+        if value.is_empty() {
+            continue;
+        }
+        match local_name(attr.key.as_ref()).as_str() {
+            "descr" | "alt" => descr = Some(value),
+            "title" => title = Some(value),
+            _ => {}
+        }
+    }
+    if let Some(text) = descr.or(title)
+        && alt.is_empty()
+    {
+        *alt = text;
+    }
+}
+
+/// Pick up the relationship id of a drawing's `a:blip` (`r:embed`, or
+/// `r:link` for linked pictures) or a VML `v:imagedata` (`r:id`).
+fn read_image_rel(e: &quick_xml::events::BytesStart, image_rel: &mut Option<String>) {
+    if image_rel.is_some() {
+        return;
+    }
+    *image_rel = e
+        .attributes()
+        .flatten()
+        .find(|attr| {
+            matches!(
+                local_name(attr.key.as_ref()).as_str(),
+                "embed" | "link" | "id"
+            )
+        })
+        .map(|attr| String::from_utf8_lossy(&attr.value).to_string());
+}
+
+/// A table cell spanning several grid columns (`w:gridSpan`) or rows
+/// (`w:vMerge`), by the grid position of its top-left corner.
+struct Merge {
+    row: usize,
+    col: usize,
+    rows: usize,
+    cols: usize,
+}
+
+impl Merge {
+    /// Spreadsheet-style range such as `A1:B1`, with the header as row 1.
+    fn range(&self) -> String {
+        let cell = |row: usize, col: usize| {
+            let mut letters = String::new();
+            let mut n = col + 1;
+            while n > 0 {
+                letters.insert(0, (b'A' + ((n - 1) % 26) as u8) as char);
+                n = (n - 1) / 26;
+            }
+            format!("{letters}{}", row + 1)
+        };
+        format!(
+            "{}:{}",
+            cell(self.row, self.col),
+            cell(self.row + self.rows - 1, self.col + self.cols - 1)
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VMerge {
+    Restart,
+    Continue,
+}
+
+/// Copy each merged cell's text into every grid cell it covers.
+fn repeat_merged(rows: &[Vec<String>], merges: &[Merge]) -> Vec<Vec<String>> {
+    let mut rows = rows.to_vec();
+    for merge in merges {
+        let Some(text) = rows.get(merge.row).and_then(|r| r.get(merge.col)).cloned() else {
+            continue;
+        };
+        for row in rows.iter_mut().skip(merge.row).take(merge.rows) {
+            for cell in row.iter_mut().skip(merge.col).take(merge.cols) {
+                cell.clone_from(&text);
+            }
+        }
+    }
+    rows
 }
 
 fn write_table(writer: &mut dyn Write, rows: &[Vec<String>]) -> Result<()> {
@@ -258,6 +1758,31 @@ fn write_table(writer: &mut dyn Write, rows: &[Vec<String>]) -> Result<()> {
     Ok(())
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum Revision {
+    Inserted,
+    Deleted,
+}
+
+/// Run text with tracked insertions in bold and deletions struck through.
+fn revision_text(text: &str, bold: bool, italic: bool, revision: Option<Revision>) -> String {
+    if text.trim().is_empty() {
+        return text.to_string();
+    }
+    match revision {
+        Some(Revision::Inserted) => format_run_text(text, true, italic),
+        Some(Revision::Deleted) => format!("~~{}~~", format_run_text(text, bold, italic)),
+        None => format_run_text(text, bold, italic),
+    }
+}
+
+/// Elements such as `w:rPrChange` holding the formatting a tracked change
+/// replaced, which must not apply to the current text.
+fn is_prop_change(name: &[u8]) -> bool {
+    let local = local_name(name);
+    local.ends_with("PrChange") || local == "tblGridChange" || local == "numberingChange"
+}
+
 fn format_run_text(text: &str, bold: bool, italic: bool) -> String {
     if text.is_empty() {
         return String::new();
@@ -275,33 +1800,136 @@ fn is_blockquote(style: &str) -> bool {
     lower == "quote" || lower == "intensequote" || lower == "blockquote"
 }
 
+/// Styles Word, pandoc and LibreOffice use for code and preformatted text,
+/// with or without spaces and a `Char` suffix for the character style.
+fn is_code_style(style: &str) -> bool {
+    let lower: String = style
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let lower = lower.strip_suffix("char").unwrap_or(&lower);
+    matches!(
+        lower,
+        "code"
+            | "codeblock"
+            | "sourcecode"
+            | "htmlcode"
+            | "htmlpreformatted"
+            | "preformatted"
+            | "preformattedtext"
+            | "plaintext"
+            | "verbatim"
+    )
+}
+
+/// Common fixed-width typefaces.
+fn is_monospace_font(font: &str) -> bool {
+    let lower = font.to_ascii_lowercase();
+    const NAMES: [&str; 7] = [
+        "mono",
+        "courier",
+        "consolas",
+        "menlo",
+        "monaco",
+        "lucida console",
+        "inconsolata",
+    ];
+    NAMES.iter().any(|name| lower.contains(name))
+        || lower.starts_with("source code")
+        || lower.ends_with(" code")
+}
+
+/// Names Word gives the built-in heading styles across languages, as they
+/// appear in `styles.xml` names and in style ids (which drop spaces and
+/// some accented letters, e.g. `berschrift1` for "Überschrift 1").
+const HEADING_NAMES: &[&str] = &[
+    "heading",
+    "titre",
+    "überschrift",
+    "berschrift",
+    "titolo",
+    "título",
+    "titulo",
+    "encabezado",
+    "kop",
+    "rubrik",
+    "overskrift",
+    "otsikko",
+    "nagłówek",
+    "nadpis",
+    "címsor",
+    "başlık",
+    "заголовок",
+    "見出し",
+    "标题",
+    "標題",
+    "제목",
+];
+
 fn heading_level(style: &str) -> Option<u8> {
-    let lower = style.to_ascii_lowercase();
-    if let Some(rest) = lower.strip_prefix("heading") {
-        rest.trim()
-            .parse::<u8>()
-            .ok()
-            .filter(|&n| (1..=6).contains(&n))
-    } else if let Some(rest) = lower.strip_prefix("titre") {
-        rest.trim()
+    let lower = style.to_lowercase();
+    HEADING_NAMES.iter().find_map(|name| {
+        lower
+            .strip_prefix(name)?
+            .trim()
             .parse::<u8>()
             .ok()
             .filter(|&n| (1..=6).contains(&n))
-    } else {
-        None
+    })
+}
+
+/// Header or footer parts (`word/header1.xml`, `word/header2.xml`, ...)
+/// in numeric order.
+fn part_names(archive: &zip::ZipArchive<Cursor<&[u8]>>, kind: &str) -> Vec<String> {
+    let prefix = format!("word/{kind}");
+    let mut parts: Vec<(u32, String)> = archive
+        .file_names()
+        .filter_map(|name| {
+            let number = name.strip_prefix(&prefix)?.strip_suffix(".xml")?;
+            Some((number.parse().ok()?, name.to_string()))
+        })
+        .collect();
+    parts.sort();
+    parts.into_iter().map(|(_, name)| name).collect()
+}
+
+/// `word/header1.xml` -> `word/_rels/header1.xml.rels`.
+fn part_rels_name(part: &str) -> String {
+    match part.rsplit_once('/') {
+        Some((dir, file)) => format!("{dir}/_rels/{file}.rels"),
+        None => format!("_rels/{part}.rels"),
     }
 }
 
 fn read_entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
-    let mut file = archive.by_name(name).map_err(|e| Error::Conversion {
+    let mut file = archive.by_name(name).map_err(|_| Error::MissingEntry {
         format: "word",
-        message: format!("Entry not found: {name}: {e}"),
+        entry: name.to_string(),
     })?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
     Ok(content)
 }
 
+/// The value of the attribute with local name `name`.
+fn attr_value(e: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|attr| local_name(attr.key.as_ref()) == name)
+        .map(|attr| String::from_utf8_lossy(&attr.value).to_string())
+}
+
+fn read_bytes(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>> {
+    let mut file = archive.by_name(name).map_err(|_| Error::MissingEntry {
+        format: "word",
+        entry: name.to_string(),
+    })?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
 fn local_name(name: &[u8]) -> String {
     let s = std::str::from_utf8(name).unwrap_or("");
     if let Some(pos) = s.rfind(':') {
@@ -310,3 +1938,707 @@ fn local_name(name: &[u8]) -> String {
         s.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{MediaOptions, Revisions, WordOptions};
+    use rstest::rstest;
+    use std::io::Write;
+
+    fn make_docx(entries: &[(&str, &str)]) -> Vec<u8> {
+        let buf = Vec::new();
+        let cursor = Cursor::new(buf);
+        let mut zip = zip::ZipWriter::new(cursor);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in entries {
+            zip.start_file(name.to_string(), options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn document_xml(body: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+            xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+  <w:body>{body}</w:body>
+</w:document>"#
+        )
+    }
+
+    fn para(text: &str) -> String {
+        format!("<w:p><w:r><w:t>{text}</w:t></w:r></w:p>")
+    }
+
+    fn section_end(text: &str, w: u32, h: u32, orient: &str) -> String {
+        format!(
+            r#"<w:p><w:pPr><w:sectPr><w:pgSz w:w="{w}" w:h="{h}" w:orient="{orient}"/></w:sectPr></w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>"#
+        )
+    }
+
+    fn convert_with(body: &str, options: ConvertOptions) -> String {
+        let docx = make_docx(&[("word/document.xml", &document_xml(body))]);
+        let mut output = Vec::new();
+        WordConverter::new(options)
+            .convert(&docx, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn convert(body: &str) -> String {
+        convert_with(body, ConvertOptions::default())
+    }
+
+    #[rstest]
+    fn test_paragraphs() {
+        let output = convert(&format!("{}{}", para("First"), para("Second")));
+        assert_eq!(output, "First\n\nSecond\n");
+    }
+
+    #[rstest]
+    fn test_section_break_renders_rule() {
+        let body = format!(
+            "{}{}{}",
+            section_end("Portrait part", 12240, 15840, "portrait"),
+            para("Landscape part"),
+            r#"<w:sectPr><w:pgSz w:w="15840" w:h="12240" w:orient="landscape"/></w:sectPr>"#
+        );
+        let output = convert(&body);
+        assert_eq!(output, "Portrait part\n\n---\n\nLandscape part\n");
+    }
+
+    #[rstest]
+    #[case::letter_landscape(15840, 12240, "landscape", "*Page: Letter, landscape*")]
+    #[case::a4_portrait(11906, 16838, "portrait", "*Page: A4, portrait*")]
+    #[case::custom(7200, 10080, "portrait", "*Page: 5.0 × 7.0 in, portrait*")]
+    fn test_section_notes(#[case] w: u32, #[case] h: u32, #[case] orient: &str, #[case] note: &str) {
+        let body = format!(
+            r#"{}{}<w:sectPr><w:pgSz w:w="{w}" w:h="{h}" w:orient="{orient}"/></w:sectPr>"#,
+            section_end("Intro", 12240, 15840, "portrait"),
+            para("Next")
+        );
+        let options = ConvertOptions {
+            word: WordOptions {
+                section_notes: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = convert_with(&body, options);
+        assert!(output.contains(&format!("---\n\n{note}\n")), "{output}");
+    }
+
+    fn figure(descr: &str) -> String {
+        format!(
+            r#"<w:p><w:r><w:drawing><wp:inline xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><wp:docPr id="1" name="Picture 1" descr="{descr}"/></wp:inline></w:drawing></w:r></w:p>"#
+        )
+    }
+
+    fn caption(text: &str) -> String {
+        format!(r#"<w:p><w:pPr><w:pStyle w:val="Caption"/></w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>"#)
+    }
+
+    #[rstest]
+    fn test_figure_alt_text() {
+        let output = convert(&figure("A bar chart"));
+        assert_eq!(output, "*[Image: A bar chart]*\n");
+    }
+
+    const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId5" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/image1.png"/>
+  <Relationship Id="rId6" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="https://example.com/logo.png" TargetMode="External"/>
+</Relationships>"#;
+
+    fn picture(descr: &str, blip: &str) -> String {
+        format!(
+            r#"<w:p><w:r><w:drawing><wp:inline xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><wp:docPr id="1" name="Picture 1" descr="{descr}"/><a:graphic xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><a:graphicData><pic:pic xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:blipFill><a:blip {blip}/></pic:blipFill></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"#
+        )
+    }
+
+    fn convert_with_rels(body: &str, options: ConvertOptions) -> String {
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(body)),
+            ("word/_rels/document.xml.rels", RELS),
+            ("word/media/image1.png", "png bytes"),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::new(options)
+            .convert(&docx, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_images_extracted_to_media_dir() {
+        let dir = std::env::temp_dir().join(format!("mq-conv-word-media-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let options = ConvertOptions {
+            input_name: Some("report.docx".to_string()),
+            media: MediaOptions {
+                extract_dir: Some(dir.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let body = format!(
+            "{}{}",
+            para("Before"),
+            picture("Chart", r#"r:embed="rId5""#)
+        );
+        let output = convert_with_rels(&body, options);
+        let path = dir.join("report-image1.png");
+        assert_eq!(
+            output,
+            format!(
+                "Before\n\n![Chart]({})\n",
+                path.to_string_lossy().replace('\\', "/")
+            )
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"png bytes");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest]
+    fn test_images_embedded_as_data_uri() {
+        let options = ConvertOptions {
+            media: MediaOptions {
+                embed: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = convert_with_rels(&picture("Chart", r#"r:embed="rId5""#), options);
+        assert_eq!(output, "![Chart](data:image/png;base64,cG5nIGJ5dGVz)\n");
+    }
+
+    #[rstest]
+    #[case::linked(r#"r:link="rId6""#, "![Logo](https://example.com/logo.png)\n")]
+    #[case::other_prefix(
+        r#"xmlns:rel="http://schemas.openxmlformats.org/officeDocument/2006/relationships" rel:link="rId6""#,
+        "![Logo](https://example.com/logo.png)\n"
+    )]
+    #[case::not_extracted(r#"r:embed="rId5""#, "*[Image: Logo]*\n")]
+    #[case::unknown_relationship(r#"r:embed="rId9""#, "*[Image: Logo]*\n")]
+    fn test_image_targets_without_media_dir(#[case] blip: &str, #[case] expected: &str) {
+        let output = convert_with_rels(&picture("Logo", blip), ConvertOptions::default());
+        assert_eq!(output, expected);
+    }
+
+    #[rstest]
+    fn test_image_without_alt_or_target() {
+        let output =
+            convert_with_rels(&picture("", r#"r:embed="rId5""#), ConvertOptions::default());
+        assert_eq!(output, "*[Image]*\n");
+    }
+
+    #[rstest]
+    fn test_images_in_table_cells() {
+        let body = format!(
+            "<w:tbl><w:tr><w:tc>{}</w:tc><w:tc>{}</w:tc></w:tr><w:tr><w:tc>{}</w:tc><w:tc>{}</w:tc></w:tr></w:tbl>",
+            para("Brand"),
+            para("Logo"),
+            para("Acme"),
+            picture("Acme logo", r#"r:link="rId6""#)
+        );
+        let output = convert_with_rels(&body, ConvertOptions::default());
+        assert_eq!(
+            output,
+            "| Brand | Logo |\n|---|---|\n| Acme | ![Acme logo](https://example.com/logo.png) |\n"
+        );
+    }
+
+    #[rstest]
+    fn test_hyperlinks() {
+        let body = format!(
+            r#"<w:p><w:r><w:t xml:space="preserve">See </w:t></w:r><w:hyperlink r:id="rId6"><w:r><w:t>the logo</w:t></w:r></w:hyperlink><w:r><w:t xml:space="preserve"> and </w:t></w:r><w:hyperlink w:anchor="_Toc1"><w:r><w:t>Usage</w:t></w:r></w:hyperlink><w:r><w:t xml:space="preserve"> or </w:t></w:r><w:hyperlink w:anchor="figure2"><w:r><w:t>Figure 2</w:t></w:r></w:hyperlink></w:p>{}"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:bookmarkStart w:id="0" w:name="_Toc1"/><w:r><w:t>Getting Started</w:t></w:r><w:bookmarkEnd w:id="0"/></w:p>"#
+        );
+        let output = convert_with_rels(&body, ConvertOptions::default());
+        assert_eq!(
+            output,
+            "See [the logo](https://example.com/logo.png) and [Usage](#getting-started) or [Figure 2](#figure2)\n\n# Getting Started\n"
+        );
+    }
+
+    #[rstest]
+    fn test_hyperlinks_with_other_prefixes() {
+        let body = r#"<w:p xmlns:rel="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wm="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:hyperlink rel:id="rId6"><w:r><w:t>Logo</w:t></w:r></w:hyperlink><w:r><w:t xml:space="preserve"> and </w:t></w:r><w:hyperlink wm:anchor="intro"><w:r><w:t>intro</w:t></w:r></w:hyperlink></w:p><w:p xmlns:wm="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:bookmarkStart wm:id="0" wm:name="intro"/><w:r><w:t>Getting Started</w:t></w:r></w:p>"#;
+        let output = convert_with_rels(body, ConvertOptions::default());
+        assert_eq!(
+            output,
+            "[Logo](https://example.com/logo.png) and [intro](#getting-started)\n\n# Getting Started\n"
+        );
+    }
+
+    #[rstest]
+    #[case::enabled(true, "Acme Corp\n\n---\n\nBody\n\n---\n\nConfidential\n")]
+    #[case::disabled(false, "Body\n")]
+    fn test_headers_and_footers(#[case] headers_footers: bool, #[case] expected: &str) {
+        let part = |root: &str, text: &str| {
+            format!(
+                r#"<w:{root} xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">{}</w:{root}>"#,
+                para(text)
+            )
+        };
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(&para("Body"))),
+            ("word/header1.xml", &part("hdr", "Acme Corp")),
+            // The first-page header repeats the default one
+            ("word/header2.xml", &part("hdr", "Acme Corp")),
+            ("word/header3.xml", &part("hdr", "")),
+            ("word/footer1.xml", &part("ftr", "Confidential")),
+        ]);
+        let options = ConvertOptions {
+            word: WordOptions {
+                headers_footers,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        WordConverter::new(options)
+            .convert(&docx, &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    const REVISED: &str = r#"<w:p><w:r><w:t xml:space="preserve">The </w:t></w:r><w:del w:id="1" w:author="Bob"><w:r><w:delText>old</w:delText></w:r></w:del><w:ins w:id="2" w:author="Bob"><w:r><w:rPr><w:rPrChange w:id="3"><w:rPr><w:i/></w:rPr></w:rPrChange></w:rPr><w:t>new</w:t></w:r></w:ins><w:r><w:t xml:space="preserve"> plan</w:t></w:r></w:p>"#;
+
+    #[rstest]
+    #[case::accept(Revisions::Accept, "The new plan\n")]
+    #[case::markup(Revisions::Markup, "The ~~old~~**new** plan\n")]
+    fn test_tracked_changes(#[case] revisions: Revisions, #[case] expected: &str) {
+        let options = ConvertOptions {
+            word: WordOptions {
+                revisions,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(convert_with(REVISED, options), expected);
+    }
+
+    #[rstest]
+    fn test_comments() {
+        let comments = r#"<w:comments xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:comment w:id="0" w:author="Alice"><w:p><w:r><w:t>Is this right?</w:t></w:r></w:p></w:comment><w:comment w:id="1" w:author=""><w:p><w:r><w:t>General note</w:t></w:r></w:p></w:comment></w:comments>"#;
+        let body = r#"<w:p><w:r><w:t xml:space="preserve">Revenue </w:t></w:r><w:commentRangeStart w:id="0"/><w:r><w:t>grew 50%</w:t></w:r></w:p><w:p><w:r><w:t>last year</w:t></w:r><w:commentRangeEnd w:id="0"/><w:r><w:commentReference w:id="0"/></w:r><w:r><w:commentReference w:id="1"/></w:r></w:p>"#;
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(body)),
+            ("word/comments.xml", comments),
+        ]);
+        let convert = |comments: bool| {
+            let options = ConvertOptions {
+                word: WordOptions {
+                    comments,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut output = Vec::new();
+            WordConverter::new(options)
+                .convert(&docx, &mut output)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(
+            convert(true),
+            "Revenue grew 50%\n\nlast year\n\n## Comments\n\n\
+             - **Alice** on \"grew 50% last year\": Is this right?\n\
+             - General note\n"
+        );
+        assert_eq!(convert(false), "Revenue grew 50%\n\nlast year\n");
+    }
+
+    /// A table whose header spans two columns and whose first column merges
+    /// two rows.
+    fn merged_table() -> String {
+        let cell = |props: &str, text: &str| {
+            format!(
+                r#"<w:tc><w:tcPr>{props}</w:tcPr><w:p><w:r><w:t>{text}</w:t></w:r></w:p></w:tc>"#
+            )
+        };
+        let row = |cells: &[String]| format!("<w:tr>{}</w:tr>", cells.concat());
+        format!(
+            "<w:tbl>{}{}{}</w:tbl>",
+            row(&[
+                cell(r#"<w:gridSpan w:val="2"/>"#, "Region"),
+                cell("", "Total")
+            ]),
+            row(&[
+                cell(r#"<w:vMerge w:val="restart"/>"#, "North"),
+                cell("", "Q1"),
+                cell("", "10"),
+            ]),
+            row(&[cell("<w:vMerge/>", ""), cell("", "Q2"), cell("", "20")]),
+        )
+    }
+
+    #[rstest]
+    #[case::blank(
+        MergedCells::Blank,
+        "| Region |  | Total |\n|---|---|---|\n| North | Q1 | 10 |\n|  | Q2 | 20 |\n"
+    )]
+    #[case::repeat(
+        MergedCells::Repeat,
+        "| Region | Region | Total |\n|---|---|---|\n| North | Q1 | 10 |\n| North | Q2 | 20 |\n"
+    )]
+    #[case::annotate(
+        MergedCells::Annotate,
+        "| Region |  | Total |\n|---|---|---|\n| North | Q1 | 10 |\n|  | Q2 | 20 |\n\n*Merged cells: A1:B1, A2:A3*\n"
+    )]
+    fn test_merged_table_cells(#[case] merged_cells: MergedCells, #[case] expected: &str) {
+        let options = ConvertOptions {
+            word: WordOptions {
+                merged_cells,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(convert_with(&merged_table(), options), expected);
+    }
+
+    #[rstest]
+    fn test_footnotes_and_endnotes() {
+        let notes = |kind: &str| {
+            format!(
+                r#"<w:{kind}s xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:{kind} w:type="separator" w:id="-1"><w:p><w:r><w:separator/></w:r></w:p></w:{kind}><w:{kind} w:id="1"><w:p><w:r><w:{kind}Ref/></w:r><w:r><w:t xml:space="preserve"> First {kind}.</w:t></w:r></w:p><w:p><w:r><w:t>Second paragraph.</w:t></w:r></w:p></w:{kind}></w:{kind}s>"#
+            )
+        };
+        let body = r#"<w:p><w:r><w:t>Claim</w:t></w:r><w:r><w:footnoteReference w:id="1"/></w:r><w:r><w:t xml:space="preserve"> and more</w:t></w:r><w:r><w:endnoteReference w:id="1"/></w:r></w:p><w:p><w:r><w:t>Again</w:t></w:r><w:r><w:footnoteReference w:id="1"/></w:r></w:p>"#;
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(body)),
+            ("word/footnotes.xml", &notes("footnote")),
+            ("word/endnotes.xml", &notes("endnote")),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::default()
+            .convert(&docx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Claim[^1] and more[^2]\n\nAgain[^1]\n\n\
+             [^1]: First footnote. Second paragraph.\n\
+             [^2]: First endnote. Second paragraph.\n"
+        );
+    }
+
+    const NUMBERING: &str = r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:abstractNum w:abstractNumId="0">
+    <w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/></w:lvl>
+    <w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="lowerLetter"/></w:lvl>
+  </w:abstractNum>
+  <w:abstractNum w:abstractNumId="1">
+    <w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/></w:lvl>
+    <w:lvl w:ilvl="1"><w:numFmt w:val="bullet"/></w:lvl>
+  </w:abstractNum>
+  <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+  <w:num w:numId="2"><w:abstractNumId w:val="1"/></w:num>
+</w:numbering>"#;
+
+    fn list_item(num_id: u32, level: u32, text: &str) -> String {
+        format!(
+            r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="{level}"/><w:numId w:val="{num_id}"/></w:numPr></w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>"#
+        )
+    }
+
+    #[rstest]
+    fn test_lists() {
+        let body = [
+            para("Steps:"),
+            list_item(1, 0, "Prepare"),
+            list_item(1, 1, "Gather tools"),
+            list_item(2, 2, "Hammer"),
+            list_item(1, 1, "Clear space"),
+            list_item(1, 0, "Build"),
+            list_item(1, 1, "Frame"),
+            para("Notes:"),
+            list_item(2, 0, "Wear gloves"),
+            list_item(2, 1, "Leather"),
+            list_item(0, 0, "Not a list"),
+            list_item(1, 0, "Finish"),
+        ]
+        .concat();
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(&body)),
+            ("word/numbering.xml", NUMBERING),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::default()
+            .convert(&docx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Steps:\n\n\
+             1. Prepare\n   1. Gather tools\n      - Hammer\n   2. Clear space\n\
+             2. Build\n   1. Frame\n\n\
+             Notes:\n\n\
+             - Wear gloves\n  - Leather\n\n\
+             Not a list\n\n\
+             3. Finish\n"
+        );
+    }
+
+    const SOURCES: &str = r#"<b:Sources xmlns:b="http://schemas.openxmlformats.org/officeDocument/2006/bibliography"><b:Source><b:Tag>Smi20</b:Tag><b:Author><b:Author><b:NameList><b:Person><b:Last>Smith</b:Last><b:First>John</b:First></b:Person></b:NameList></b:Author></b:Author><b:Title>Writing Well</b:Title><b:Year>2020</b:Year><b:Publisher>Acme</b:Publisher></b:Source></b:Sources>"#;
+
+    fn complex_field(instr: &str, result: &str) -> String {
+        format!(
+            r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText xml:space="preserve">{instr}</w:instrText></w:r><w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>{result}</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r>"#
+        )
+    }
+
+    fn convert_with_sources(body: &str) -> String {
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(body)),
+            ("customXml/item1.xml", SOURCES),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::default()
+            .convert(&docx, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_citations_and_bibliography_field() {
+        let body = format!(
+            r#"<w:p><w:r><w:t xml:space="preserve">As shown </w:t></w:r>{}<w:r><w:t>, pages are numbered </w:t></w:r>{}</w:p>{}<w:p>{}</w:p><w:p><w:r><w:t>Bibliography result</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>"#,
+            complex_field(" CITATION Smi20 \\l 1033 ", "(Smith, 2020)"),
+            complex_field(" PAGE ", "7"),
+            styled("Heading1", "References"),
+            r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText> BIBLIOGRAPHY </w:instrText></w:r><w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>Smith, J. 2020.</w:t></w:r>"#,
+        );
+        assert_eq!(
+            convert_with_sources(&body),
+            "As shown [Smith, 2020], pages are numbered 7\n\n\
+             # **References**\n\n\
+             - Smith, J. (2020). *Writing Well*. Acme.\n"
+        );
+    }
+
+    #[rstest]
+    fn test_citations_without_bibliography_field() {
+        let body = r#"<w:p><w:r><w:t xml:space="preserve">See </w:t></w:r><w:fldSimple w:instr=" CITATION Smi20 \p 4 "><w:r><w:t>(Smith, 2020, p. 4)</w:t></w:r></w:fldSimple></w:p>"#;
+        assert_eq!(
+            convert_with_sources(body),
+            "See [Smith, 2020, p. 4]\n\n## References\n\n\
+             - Smith, J. (2020). *Writing Well*. Acme.\n"
+        );
+    }
+
+    #[rstest]
+    #[case::caption_below(format!("{}{}", figure(""), caption("Figure 3: Sales")))]
+    #[case::caption_above(format!("{}{}", caption("Figure 3: Sales"), figure("")))]
+    fn test_caption_attached_to_figure(#[case] body: String) {
+        let output = convert(&body);
+        assert!(output.contains("*[Image: Figure 3: Sales]*"), "{output}");
+        assert!(output.contains("*Figure 3: Sales*"), "{output}");
+    }
+
+    #[rstest]
+    fn test_caption_keeps_existing_alt_text() {
+        let output = convert(&format!("{}{}", figure("Chart"), caption("Figure 1")));
+        assert_eq!(output, "*[Image: Chart]*\n\n*Figure 1*\n");
+    }
+
+    fn styled(style: &str, text: &str) -> String {
+        format!(r#"<w:p><w:pPr><w:pStyle w:val="{style}"/></w:pPr><w:r><w:rPr><w:b/></w:rPr><w:t>{text}</w:t></w:r></w:p>"#)
+    }
+
+    fn with_style_map(json: &str) -> ConvertOptions {
+        ConvertOptions {
+            word: WordOptions {
+                style_map: StyleMap::from_json(json).unwrap(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    fn test_style_map() {
+        let body = format!(
+            "{}{}{}{}{}",
+            styled("CompanyHeading2", "Setup"),
+            styled("CodeSample", "let x = 1;"),
+            styled("CodeSample", "let y = *x;"),
+            styled("Heading1", "Not a heading"),
+            para("Done")
+        );
+        let options = with_style_map(
+            r#"{"companyheading2": "h2", "CodeSample": "code", "Heading1": "paragraph"}"#,
+        );
+        let output = convert_with(&body, options);
+        assert_eq!(
+            output,
+            "## **Setup**\n\n```\nlet x = 1;\nlet y = *x;\n```\n\n**Not a heading**\n\nDone\n"
+        );
+    }
+
+    #[rstest]
+    fn test_style_map_display_name() {
+        let styles = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:style w:type="paragraph" w:styleId="Pullquote1"><w:name w:val="Pull Quote"/></w:style>
+</w:styles>"#;
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(&styled("Pullquote1", "Quoted"))),
+            ("word/styles.xml", styles),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::new(with_style_map(r#"{"Pull Quote": "quote"}"#))
+            .convert(&docx, &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "> **Quoted**\n");
+    }
+
+    #[rstest]
+    fn test_headings_from_styles_xml() {
+        let styles = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:style w:type="paragraph" w:styleId="berschrift1"><w:name w:val="heading 1"/></w:style>
+  <w:style w:type="paragraph" w:styleId="Kapitel"><w:name w:val="Kapitel"/><w:pPr><w:outlineLvl w:val="1"/></w:pPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Abschnitt"><w:name w:val="Abschnitt"/><w:basedOn w:val="Kapitel"/></w:style>
+  <w:style w:type="paragraph" w:styleId="Fliesstext"><w:name w:val="Fliesstext"/><w:basedOn w:val="Kapitel"/><w:pPr><w:outlineLvl w:val="9"/></w:pPr></w:style>
+</w:styles>"#;
+        let body = [
+            ("berschrift1", "Einleitung"),
+            ("Kapitel", "Hintergrund"),
+            ("Abschnitt", "Details"),
+            ("Fliesstext", "Text"),
+        ]
+        .iter()
+        .map(|(style, text)| {
+            format!(
+                r#"<w:p><w:pPr><w:pStyle w:val="{style}"/></w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>"#
+            )
+        })
+        .collect::<String>();
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(&body)),
+            ("word/styles.xml", styles),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::default()
+            .convert(&docx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Einleitung\n\n## Hintergrund\n\n## Details\n\nText\n"
+        );
+    }
+
+    #[rstest]
+    fn test_localized_headings_from_styles_xml() {
+        let styles = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:style w:type="paragraph" w:styleId="1"><w:name w:val="heading 1"/></w:style>
+  <w:style w:type="paragraph" w:styleId="2"><w:name w:val="見出し 2"/></w:style>
+  <w:style w:type="paragraph" w:styleId="Ueberschrift3"><w:name w:val="Überschrift 3"/></w:style>
+  <w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="Plain"/><w:pPr><w:outlineLvl w:val="9"/></w:pPr></w:style>
+</w:styles>"#;
+        let body = [
+            styled("1", "概要"),
+            styled("2", "背景"),
+            styled("Ueberschrift3", "Details"),
+            styled("Heading4", "Text"),
+            styled("Kop2", "Undeclared"),
+        ]
+        .concat();
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(&body)),
+            ("word/styles.xml", styles),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::default()
+            .convert(&docx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# **概要**\n\n## **背景**\n\n### **Details**\n\n**Text**\n\n## **Undeclared**\n"
+        );
+    }
+
+    #[rstest]
+    #[case::english("heading 2", Some(2))]
+    #[case::english_id("Heading2", Some(2))]
+    #[case::french("Titre 1", Some(1))]
+    #[case::german("Überschrift 3", Some(3))]
+    #[case::german_id("berschrift3", Some(3))]
+    #[case::spanish("Título 2", Some(2))]
+    #[case::russian("Заголовок 1", Some(1))]
+    #[case::chinese("标题 4", Some(4))]
+    #[case::too_deep("heading 7", None)]
+    #[case::title("Title", None)]
+    #[case::header("Kopfzeile", None)]
+    fn test_heading_level(#[case] style: &str, #[case] expected: Option<u8>) {
+        assert_eq!(heading_level(style), expected);
+    }
+
+    fn code_run(font: &str, text: &str) -> String {
+        format!(
+            r#"<w:r><w:rPr><w:rFonts w:ascii="{font}" w:hAnsi="{font}"/></w:rPr><w:t xml:space="preserve">{text}</w:t></w:r>"#
+        )
+    }
+
+    #[rstest]
+    #[case::monospace_runs(
+        [
+            para("Example:"),
+            format!("<w:p>{}</w:p>", code_run("Consolas", "fn main() {")),
+            format!("<w:p><w:r><w:tab/></w:r>{}</w:p>", code_run("Consolas", "run();")),
+            r#"<w:p><w:pPr><w:rPr><w:rFonts w:ascii="Consolas"/></w:rPr></w:pPr></w:p>"#.to_string(),
+            format!("<w:p>{}</w:p>", code_run("Courier New", "}")),
+            r#"<w:p><w:pPr><w:rPr><w:rFonts w:ascii="Consolas"/></w:rPr></w:pPr></w:p>"#.to_string(),
+            format!(
+                r#"<w:p><w:r><w:t xml:space="preserve">Call </w:t></w:r>{}</w:p>"#,
+                code_run("Consolas", "main")
+            ),
+        ]
+        .concat(),
+        "Example:\n\n```\nfn main() {\n\trun();\n\n}\n```\n\nCall main\n"
+    )]
+    #[case::code_style(
+        [styled("SourceCode", "let x = 1;"), styled("SourceCode", "let y = x;"), para("Done")].concat(),
+        "```\nlet x = 1;\nlet y = x;\n```\n\nDone\n"
+    )]
+    #[case::character_style(
+        r#"<w:p><w:r><w:rPr><w:rStyle w:val="VerbatimChar"/></w:rPr><w:t>$ cargo build</w:t></w:r><w:r><w:rPr><w:rStyle w:val="VerbatimChar"/></w:rPr><w:br/><w:t>$ cargo test</w:t></w:r></w:p>"#.to_string(),
+        "```\n$ cargo build\n$ cargo test\n```\n"
+    )]
+    fn test_code_blocks(#[case] body: String, #[case] expected: &str) {
+        assert_eq!(convert(&body), expected);
+    }
+
+    #[rstest]
+    fn test_code_style_from_styles_xml() {
+        let styles = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:style w:type="paragraph" w:styleId="Listing"><w:name w:val="Listing"/><w:rPr><w:rFonts w:ascii="Menlo" w:hAnsi="Menlo"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="ListingSmall"><w:name w:val="Listing Small"/><w:basedOn w:val="Listing"/></w:style>
+</w:styles>"#;
+        let body = [styled("Listing", "a = 1"), styled("ListingSmall", "b = 2")].concat();
+        let docx = make_docx(&[
+            ("word/document.xml", &document_xml(&body)),
+            ("word/styles.xml", styles),
+        ]);
+        let mut output = Vec::new();
+        WordConverter::default()
+            .convert(&docx, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "```\na = 1\nb = 2\n```\n"
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_construct(r#"{"Fancy": "sparkles"}"#)]
+    #[case::heading_too_deep(r#"{"Fancy": "h7"}"#)]
+    #[case::not_an_object(r#"["h1"]"#)]
+    fn test_style_map_invalid(#[case] json: &str) {
+        assert!(StyleMap::from_json(json).is_err());
+    }
+}
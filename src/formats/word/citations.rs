@@ -0,0 +1,312 @@
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use super::local_name;
+
+/// A source from the document's bibliography part (`b:Sources`, stored in
+/// `customXml/`), as entered in Word's source manager.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Source {
+    tag: String,
+    /// Author last names, or the corporate author's name.
+    authors: Vec<Author>,
+    year: Option<String>,
+    title: Option<String>,
+    publisher: Option<String>,
+    city: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Author {
+    last: String,
+    first: Option<String>,
+}
+
+/// The sources a document cites, in the order they are stored.
+#[derive(Debug, Default)]
+pub(super) struct Bibliography {
+    sources: Vec<Source>,
+}
+
+/// What a field's result is replaced with.
+pub(super) enum FieldResult {
+    /// A `CITATION` field, rendered as `[Author, Year]`.
+    Citation(String),
+    /// A `BIBLIOGRAPHY` field, rendered as the reference list.
+    Bibliography,
+}
+
+impl Bibliography {
+    /// Parse a `b:Sources` part. Returns `None` for other custom XML parts.
+    pub(super) fn parse(xml: &str) -> Option<Self> {
+        let mut reader = Reader::from_str(xml);
+        let mut path: Vec<String> = Vec::new();
+        let mut sources = Vec::new();
+        let mut source: Option<Source> = None;
+        let mut person: Option<Author> = None;
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) => {
+                    let local = local_name(e.name().as_ref());
+                    if path.is_empty() && local != "Sources" {
+                        return None;
+                    }
+                    match local.as_str() {
+                        "Source" => source = Some(Source::default()),
+                        "Person" if in_author_list(&path) => {
+                            person = Some(Author {
+                                last: String::new(),
+                                first: None,
+                            })
+                        }
+                        _ => {}
+                    }
+                    path.push(local);
+                }
+                Ok(Event::Text(e)) => {
+                    let text = e.decode().unwrap_or_default().trim().to_string();
+                    let Some(source) = source.as_mut().filter(|_| !text.is_empty()) else {
+                        continue;
+                    };
+                    let element = path.last().map(String::as_str).unwrap_or_default();
+                    let parent = path.iter().rev().nth(1).map(String::as_str);
+                    match (parent, element) {
+                        (Some("Person"), "Last") => {
+                            if let Some(person) = person.as_mut() {
+                                person.last = text;
+                            }
+                        }
+                        (Some("Person"), "First") => {
+                            if let Some(person) = person.as_mut() {
+                                person.first = Some(text);
+                            }
+                        }
+                        (Some("Author"), "Corporate")
+                            if in_author_role(&path[..path.len() - 1]) =>
+                        {
+                            source.authors.push(Author {
+                                last: text,
+                                first: None,
+                            });
+                        }
+                        (Some("Source"), "Tag") => source.tag = text,
+                        (Some("Source"), "Year") => source.year = Some(text),
+                        (Some("Source"), "Title") => source.title = Some(text),
+                        (Some("Source"), "Publisher") => source.publisher = Some(text),
+                        (Some("Source"), "City") => source.city = Some(text),
+                        _ => {}
+                    }
+                }
+                Ok(Event::End(e)) => {
+                    match local_name(e.name().as_ref()).as_str() {
+                        "Source" => {
+                            if let Some(source) = source.take().filter(|s| !s.tag.is_empty()) {
+                                sources.push(source);
+                            }
+                        }
+                        "Person" => {
+                            if let (Some(source), Some(person)) = (source.as_mut(), person.take())
+                                && !person.last.is_empty()
+                            {
+                                source.authors.push(person);
+                            }
+                        }
+                        _ => {}
+                    }
+                    path.pop();
+                }
+                Ok(Event::Eof) => break,
+                Err(_) => return None,
+                _ => {}
+            }
+        }
+        Some(Self { sources })
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// What to render in place of the result of a field with instruction
+    /// `instr`, or `None` to keep the result Word stored.
+    pub(super) fn field_result(&self, instr: &str) -> Option<FieldResult> {
+        let mut words = instr.split_whitespace();
+        match words.next()?.to_ascii_uppercase().as_str() {
+            "CITATION" => self.cite(words).map(FieldResult::Citation),
+            "BIBLIOGRAPHY" if !self.is_empty() => Some(FieldResult::Bibliography),
+            _ => None,
+        }
+    }
+
+    /// `CITATION Smi20 \p 12 \m Doe19` -> `[Smith, 2020, p. 12; Doe, 2019]`.
+    /// `None` when a cited source is missing, so Word's own result is kept.
+    fn cite<'a>(&self, mut words: impl Iterator<Item = &'a str>) -> Option<String> {
+        let mut citations: Vec<String> = Vec::new();
+        let mut tag = words.next();
+        while let Some(current) = tag.take() {
+            let source = self.sources.iter().find(|s| s.tag == current)?;
+            let mut citation = format!(
+                "{}, {}",
+                source.short_authors(),
+                source.year.as_deref().unwrap_or("n.d.")
+            );
+            while let Some(word) = words.next() {
+                match word.to_ascii_lowercase().as_str() {
+                    "\\m" => {
+                        tag = words.next();
+                        break;
+                    }
+                    "\\p" => {
+                        if let Some(page) = words.next() {
+                            citation.push_str(&format!(", p. {}", page.trim_matches('"')));
+                        }
+                    }
+                    // Locale and other switches take one argument
+                    "\\l" | "\\s" | "\\v" | "\\f" => {
+                        words.next();
+                    }
+                    _ => {}
+                }
+            }
+            citations.push(citation);
+        }
+        (!citations.is_empty()).then(|| format!("[{}]", citations.join("; ")))
+    }
+
+    /// Reference list entries, sorted by author and year.
+    pub(super) fn references(&self) -> Vec<String> {
+        let mut sources: Vec<&Source> = self.sources.iter().collect();
+        sources.sort_by_key(|source| {
+            (
+                source
+                    .authors
+                    .first()
+                    .map(|a| a.last.to_lowercase())
+                    .or_else(|| source.title.as_ref().map(|t| t.to_lowercase())),
+                source.year.clone(),
+            )
+        });
+        sources.into_iter().map(Source::reference).collect()
+    }
+}
+
+/// Whether `path` is inside the list of a source's authors, rather than
+/// its editors, translators and so on.
+fn in_author_list(path: &[String]) -> bool {
+    path.len() >= 2 && path[path.len() - 1] == "NameList" && in_author_role(&path[..path.len() - 1])
+}
+
+/// Sources nest each contributor role inside `b:Author`, so the authors
+/// themselves are at `Author/Author`.
+fn in_author_role(path: &[String]) -> bool {
+    path.len() >= 2 && path[path.len() - 1] == "Author" && path[path.len() - 2] == "Author"
+}
+
+impl Source {
+    /// `Smith`, `Smith and Doe` or `Smith et al.`; the title when there are
+    /// no authors.
+    fn short_authors(&self) -> String {
+        match self.authors.as_slice() {
+            [] => self.title.clone().unwrap_or_else(|| self.tag.clone()),
+            [only] => only.last.clone(),
+            [first, second] => format!("{} and {}", first.last, second.last),
+            [first, ..] => format!("{} et al.", first.last),
+        }
+    }
+
+    /// `Smith, J., & Doe, A. (2020). *Title*. City: Publisher.`
+    fn reference(&self) -> String {
+        let authors: Vec<String> = self
+            .authors
+            .iter()
+            .map(
+                |author| match author.first.as_deref().and_then(|f| f.chars().next()) {
+                    Some(initial) => format!("{}, {initial}.", author.last),
+                    None => author.last.clone(),
+                },
+            )
+            .collect();
+        let authors = match authors.as_slice() {
+            [] => String::new(),
+            [only] => only.clone(),
+            [rest @ .., last] => format!("{}, & {last}", rest.join(", ")),
+        };
+        let mut parts = Vec::new();
+        if !authors.is_empty() {
+            parts.push(authors);
+        }
+        parts.push(format!("({}).", self.year.as_deref().unwrap_or("n.d.")));
+        if let Some(title) = &self.title {
+            parts.push(format!("*{title}*."));
+        }
+        match (&self.city, &self.publisher) {
+            (Some(city), Some(publisher)) => parts.push(format!("{city}: {publisher}.")),
+            (None, Some(publisher)) => parts.push(format!("{publisher}.")),
+            _ => {}
+        }
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    const SOURCES: &str = r#"<b:Sources xmlns:b="http://schemas.openxmlformats.org/officeDocument/2006/bibliography">
+  <b:Source>
+    <b:Tag>Smi20</b:Tag>
+    <b:SourceType>Book</b:SourceType>
+    <b:Author>
+      <b:Author><b:NameList>
+        <b:Person><b:Last>Smith</b:Last><b:First>John</b:First></b:Person>
+        <b:Person><b:Last>Doe</b:Last><b:First>Alice</b:First></b:Person>
+      </b:NameList></b:Author>
+      <b:Editor><b:NameList><b:Person><b:Last>Editor</b:Last></b:Person></b:NameList></b:Editor>
+    </b:Author>
+    <b:Title>Writing Well</b:Title>
+    <b:Year>2020</b:Year>
+    <b:City>London</b:City>
+    <b:Publisher>Acme</b:Publisher>
+  </b:Source>
+  <b:Source>
+    <b:Tag>Who19</b:Tag>
+    <b:Author><b:Author><b:Corporate>World Health Organization</b:Corporate></b:Author></b:Author>
+    <b:Title>Report</b:Title>
+  </b:Source>
+</b:Sources>"#;
+
+    #[rstest]
+    #[case::single(" CITATION Smi20 \\l 1033 ", Some("[Smith and Doe, 2020]"))]
+    #[case::page_and_multiple(
+        " CITATION Smi20 \\p 12 \\l 1033  \\m Who19",
+        Some("[Smith and Doe, 2020, p. 12; World Health Organization, n.d.]")
+    )]
+    #[case::unknown_source(" CITATION Xyz99 \\l 1033 ", None)]
+    #[case::other_field(" PAGE ", None)]
+    fn test_citation(#[case] instr: &str, #[case] expected: Option<&str>) {
+        let bibliography = Bibliography::parse(SOURCES).unwrap();
+        let result = match bibliography.field_result(instr) {
+            Some(FieldResult::Citation(text)) => Some(text),
+            _ => None,
+        };
+        assert_eq!(result.as_deref(), expected);
+    }
+
+    #[rstest]
+    fn test_references() {
+        assert_eq!(
+            Bibliography::parse(SOURCES).unwrap().references(),
+            vec![
+                "Smith, J., & Doe, A. (2020). *Writing Well*. London: Acme.",
+                "World Health Organization (n.d.). *Report*.",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_other_custom_xml_ignored() {
+        assert!(Bibliography::parse("<root><item/></root>").is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 
 use quick_xml::Reader;
@@ -5,8 +6,18 @@ use quick_xml::events::Event;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
+use crate::options::{ConvertOptions, XmlOptions};
 
-pub struct XmlConverter;
+#[derive(Default)]
+pub struct XmlConverter {
+    options: ConvertOptions,
+}
+
+impl XmlConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for XmlConverter {
     fn format_name(&self) -> &'static str {
@@ -19,8 +30,28 @@ impl Converter for XmlConverter {
             message: e.to_string(),
         })?;
 
+        if is_dtd(text) {
+            return write_dtd_summary(writer, text);
+        }
+
         let root = parse_xml(text)?;
-        write_element(writer, &root, 1)?;
+
+        if is_xsd(&root) {
+            return write_xsd_summary(writer, &root);
+        }
+
+        if let Some(xpath) = &self.options.xml.xpath {
+            let steps = parse_xpath(xpath)?;
+            let matches = select(&root, &steps);
+            if matches.is_empty() {
+                writeln!(writer, "*No nodes match `{xpath}`*")?;
+            } else {
+                write_children(writer, &matches, 0, &self.options.xml)?;
+            }
+            return Ok(());
+        }
+
+        write_element(writer, &root, 1, &self.options.xml)?;
 
         Ok(())
     }
@@ -110,8 +141,10 @@ fn parse_xml(text: &str) -> Result<XmlElement> {
             }
             Ok(Event::Eof) => break,
             Err(e) => {
-                return Err(Error::Conversion {
+                return Err(Error::Parse {
                     format: "xml",
+                    entry: None,
+                    offset: Some(reader.error_position()),
                     message: format!("Invalid XML: {e}"),
                 });
             }
@@ -125,29 +158,43 @@ fn parse_xml(text: &str) -> Result<XmlElement> {
     })
 }
 
-fn write_element(writer: &mut dyn Write, elem: &XmlElement, depth: usize) -> Result<()> {
+fn write_element(
+    writer: &mut dyn Write,
+    elem: &XmlElement,
+    depth: usize,
+    options: &XmlOptions,
+) -> Result<()> {
     let level = depth.min(6);
     let hashes = "#".repeat(level);
-    writeln!(writer, "{hashes} {}", elem.name)?;
+    let title = title_attribute(elem, options);
+    match title {
+        Some((_, value)) => writeln!(writer, "{hashes} {}: {value}", elem.name)?,
+        None => writeln!(writer, "{hashes} {}", elem.name)?,
+    }
     writeln!(writer)?;
 
     // Write attributes as a table
-    if !elem.attributes.is_empty() {
+    let attributes: Vec<&(String, String)> = shown_attributes(elem, options)
+        .filter(|(key, _)| title.is_none_or(|(title, _)| title != key))
+        .collect();
+    if !attributes.is_empty() {
         writeln!(writer, "| Attribute | Value |")?;
         writeln!(writer, "|---|---|")?;
-        for (key, val) in &elem.attributes {
+        for (key, val) in attributes {
             writeln!(writer, "| {} | {} |", escape_pipe(key), escape_pipe(val))?;
         }
         writeln!(writer)?;
     }
 
-    // Separate text nodes and element children
+    // Separate text nodes, flattened leaves and element children
     let mut text_parts: Vec<&str> = Vec::new();
+    let mut leaves: Vec<&XmlElement> = Vec::new();
     let mut child_elements: Vec<&XmlElement> = Vec::new();
 
     for child in &elem.children {
         match child {
             XmlNode::Text(t) => text_parts.push(t),
+            XmlNode::Element(e) if options.flatten_leaves && is_leaf(e, options) => leaves.push(e),
             XmlNode::Element(e) => child_elements.push(e),
         }
     }
@@ -160,30 +207,802 @@ fn write_element(writer: &mut dyn Write, elem: &XmlElement, depth: usize) -> Res
         writeln!(writer)?;
     }
 
-    // Try to group repeated same-name child elements into a table
-    if !child_elements.is_empty() {
-        let mut i = 0;
-        while i < child_elements.len() {
-            // Find a run of same-named elements
-            let name = &child_elements[i].name;
-            let mut end = i + 1;
-            while end < child_elements.len() && child_elements[end].name == *name {
-                end += 1;
+    if !leaves.is_empty() {
+        writeln!(writer, "| Element | Value |")?;
+        writeln!(writer, "|---|---|")?;
+        for leaf in leaves {
+            let name = escape_pipe(&leaf.name);
+            writeln!(writer, "| {name} | {} |", escape_pipe(&element_text(leaf)))?;
+        }
+        writeln!(writer)?;
+    }
+
+    write_children(writer, &child_elements, depth, options)?;
+
+    Ok(())
+}
+
+/// The attributes of `elem` not left out by `--drop-attributes`.
+fn shown_attributes<'a>(
+    elem: &'a XmlElement,
+    options: &XmlOptions,
+) -> impl Iterator<Item = &'a (String, String)> {
+    elem.attributes.iter().filter(|(key, _)| {
+        !options
+            .drop_attributes
+            .iter()
+            .any(|pattern| wildcard_match(pattern, key))
+    })
+}
+
+/// The first of the title attributes `elem` has, as name and value.
+fn title_attribute<'a>(elem: &'a XmlElement, options: &XmlOptions) -> Option<(&'a str, &'a str)> {
+    options.title_attributes.iter().find_map(|name| {
+        shown_attributes(elem, options)
+            .find(|(key, _)| key == name)
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    })
+}
+
+/// Whether `elem` holds nothing but text, with no attributes left to show,
+/// so it fits on one row of its parent's table.
+fn is_leaf(elem: &XmlElement, options: &XmlOptions) -> bool {
+    elem.children.len() <= 1
+        && elem.children.iter().all(|c| matches!(c, XmlNode::Text(_)))
+        && shown_attributes(elem, options).next().is_none()
+}
+
+/// Match an attribute name against a pattern where `*` matches any run of
+/// characters, such as `xsi:*`.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            name.starts_with(prefix)
+                && (prefix.len()..=name.len())
+                    .filter(|&i| name.is_char_boundary(i))
+                    .any(|i| wildcard_match(rest, &name[i..]))
+        }
+    }
+}
+
+/// Write sibling elements one level below `depth`, grouping repeated
+/// same-name elements into a table.
+fn write_children(
+    writer: &mut dyn Write,
+    child_elements: &[&XmlElement],
+    depth: usize,
+    options: &XmlOptions,
+) -> Result<()> {
+    let mut i = 0;
+    while i < child_elements.len() {
+        // Find a run of same-named elements
+        let name = &child_elements[i].name;
+        let mut end = i + 1;
+        while end < child_elements.len() && child_elements[end].name == *name {
+            end += 1;
+        }
+
+        if end - i > 1 && can_table_elements(&child_elements[i..end]) {
+            write_elements_as_table(writer, &child_elements[i..end], depth, options)?;
+            i = end;
+        } else {
+            // Write each element as a subsection
+            while i < end {
+                write_element(writer, child_elements[i], depth + 1, options)?;
+                i += 1;
             }
+        }
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// XPath subset
+// ---------------------------------------------------------------------------
+
+/// One location step of a path such as `//order/item[@status='open']`.
+struct Step {
+    /// `//` before the step: match at any depth below the context node.
+    descendant: bool,
+    /// Element name, or `*` for any element.
+    name: String,
+    predicates: Vec<Predicate>,
+}
+
+enum Predicate {
+    /// `[@attr]` or `[@attr='value']`
+    Attribute(String, Option<String>),
+    /// `[child]` or `[child='text']`
+    Child(String, Option<String>),
+    /// `[n]`, 1-based among the matching siblings
+    Position(usize),
+}
+
+/// Parse the supported XPath subset: child (`/`) and descendant (`//`)
+/// steps, `*` wildcards, and attribute, child-text and position predicates.
+fn parse_xpath(xpath: &str) -> Result<Vec<Step>> {
+    let invalid = |reason: &str| Error::Conversion {
+        format: "xml",
+        message: format!("Unsupported XPath `{xpath}`: {reason}"),
+    };
+
+    let mut steps = Vec::new();
+    let mut rest = xpath.trim();
+    while !rest.is_empty() {
+        let descendant = rest.starts_with("//");
+        rest = rest.trim_start_matches('/');
+
+        // The step runs until the next `/` outside brackets and quotes
+        let mut depth = 0;
+        let mut quote: Option<char> = None;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '\'' | '"') => quote = Some(c),
+                (None, '[') => depth += 1,
+                (None, ']') => depth -= 1,
+                (None, '/') if depth == 0 => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let step = &rest[..end];
+        rest = &rest[end..];
+
+        let (name, mut predicate_src) = match step.find('[') {
+            Some(pos) => (&step[..pos], &step[pos..]),
+            None => (step, ""),
+        };
+        let name = local_name(name.trim().as_bytes());
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || "_-.*".contains(c)) {
+            return Err(invalid("expected an element name or `*`"));
+        }
+
+        let mut predicates = Vec::new();
+        while !predicate_src.is_empty() {
+            let close = predicate_src
+                .find(']')
+                .filter(|_| predicate_src.starts_with('['))
+                .ok_or_else(|| invalid("unbalanced predicate brackets"))?;
+            predicates.push(parse_predicate(predicate_src[1..close].trim()).ok_or_else(|| {
+                invalid("predicates must be [n], [@attr], [@attr='v'], [child] or [child='v']")
+            })?);
+            predicate_src = predicate_src[close + 1..].trim_start();
+        }
+
+        steps.push(Step {
+            descendant,
+            name,
+            predicates,
+        });
+    }
+
+    if steps.is_empty() {
+        return Err(invalid("empty path"));
+    }
+    Ok(steps)
+}
+
+fn parse_predicate(src: &str) -> Option<Predicate> {
+    if let Ok(n) = src.parse::<usize>() {
+        return (n > 0).then_some(Predicate::Position(n));
+    }
+
+    let (target, value) = match src.split_once('=') {
+        Some((target, value)) => {
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('\'')
+                .and_then(|v| v.strip_suffix('\''))
+                .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))?;
+            (target.trim(), Some(unquoted.to_string()))
+        }
+        None => (src, None),
+    };
+
+    match target.strip_prefix('@') {
+        Some(attr) if !attr.is_empty() => Some(Predicate::Attribute(attr.to_string(), value)),
+        None if !target.is_empty() => Some(Predicate::Child(target.to_string(), value)),
+        _ => None,
+    }
+}
+
+/// Evaluate parsed steps against the document, returning matches in document order.
+fn select<'a>(root: &'a XmlElement, steps: &[Step]) -> Vec<&'a XmlElement> {
+    // Each context node is represented by its child elements; the implicit
+    // document node has the root element as its only child.
+    let mut context: Vec<Vec<&XmlElement>> = vec![vec![root]];
+    let mut matched: Vec<&XmlElement> = Vec::new();
 
-            if end - i > 1 && can_table_elements(&child_elements[i..end]) {
-                write_elements_as_table(writer, &child_elements[i..end], depth)?;
-                i = end;
+    for step in steps {
+        // Sibling groups the step is tested against: the context node's
+        // children, plus the children of every descendant for `//`
+        let mut groups: Vec<Vec<&XmlElement>> = Vec::new();
+        for children in context {
+            if step.descendant {
+                let mut descendants = Vec::new();
+                for child in &children {
+                    descendants.push(*child);
+                    collect_descendants(child, &mut descendants);
+                }
+                groups.push(children);
+                groups.extend(descendants.into_iter().map(child_elements));
             } else {
-                // Write each element as a subsection
-                while i < end {
-                    write_element(writer, child_elements[i], depth + 1)?;
-                    i += 1;
+                groups.push(children);
+            }
+        }
+
+        matched = Vec::new();
+        let mut seen: HashSet<*const XmlElement> = HashSet::new();
+        for siblings in groups {
+            let mut candidates: Vec<&XmlElement> = siblings
+                .into_iter()
+                .filter(|e| step.name == "*" || e.name == step.name)
+                .collect();
+            for predicate in &step.predicates {
+                candidates = match predicate {
+                    Predicate::Position(n) => candidates.get(n - 1).copied().into_iter().collect(),
+                    _ => candidates.into_iter().filter(|e| matches_predicate(e, predicate)).collect(),
+                };
+            }
+            for elem in candidates {
+                if seen.insert(elem as *const XmlElement) {
+                    matched.push(elem);
                 }
             }
         }
+
+        context = matched.iter().map(|e| child_elements(e)).collect();
+    }
+
+    // `//` gathers matches group by group; restore document order
+    let mut order = vec![root];
+    collect_descendants(root, &mut order);
+    let position: HashMap<*const XmlElement, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (*e as *const XmlElement, i))
+        .collect();
+    matched.sort_by_key(|m| position.get(&(*m as *const XmlElement)).copied());
+    matched
+}
+
+fn child_elements(elem: &XmlElement) -> Vec<&XmlElement> {
+    elem.children
+        .iter()
+        .filter_map(|c| match c {
+            XmlNode::Element(e) => Some(e),
+            XmlNode::Text(_) => None,
+        })
+        .collect()
+}
+
+fn collect_descendants<'a>(elem: &'a XmlElement, out: &mut Vec<&'a XmlElement>) {
+    for child in child_elements(elem) {
+        out.push(child);
+        collect_descendants(child, out);
+    }
+}
+
+fn matches_predicate(elem: &XmlElement, predicate: &Predicate) -> bool {
+    match predicate {
+        Predicate::Attribute(name, value) => elem.attributes.iter().any(|(k, v)| {
+            (k == name || local_name(k.as_bytes()) == *name)
+                && value.as_ref().is_none_or(|expected| v == expected)
+        }),
+        Predicate::Child(name, value) => child_elements(elem).iter().any(|child| {
+            child.name == *name
+                && value.as_ref().is_none_or(|expected| element_text(child) == *expected)
+        }),
+        Predicate::Position(_) => true,
+    }
+}
+
+fn element_text(elem: &XmlElement) -> String {
+    elem.children
+        .iter()
+        .filter_map(|c| match c {
+            XmlNode::Text(t) => Some(t.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ---------------------------------------------------------------------------
+// Schema summaries (XSD and DTD)
+// ---------------------------------------------------------------------------
+
+const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+fn is_xsd(root: &XmlElement) -> bool {
+    root.name == "schema" && root.attributes.iter().any(|(_, v)| v == XSD_NAMESPACE)
+}
+
+/// A DTD is a sequence of markup declarations rather than a document, so
+/// look at the first construct that is not a prolog, comment or blank.
+fn is_dtd(text: &str) -> bool {
+    let mut rest = text.trim_start_matches('\u{feff}').trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("<?") {
+            rest = after.split_once("?>").map_or("", |(_, r)| r).trim_start();
+        } else if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.split_once("-->").map_or("", |(_, r)| r).trim_start();
+        } else {
+            break;
+        }
+    }
+    ["<!ELEMENT", "<!ATTLIST", "<!ENTITY", "<!NOTATION"]
+        .iter()
+        .any(|decl| rest.starts_with(decl))
+}
+
+fn attr<'a>(elem: &'a XmlElement, name: &str) -> Option<&'a str> {
+    elem.attributes
+        .iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.as_str())
+}
+
+/// Format `minOccurs`/`maxOccurs` as `1`, `0..1`, `1..*` and so on.
+fn occurs(min: &str, max: &str) -> String {
+    let max = if max == "unbounded" { "*" } else { max };
+    if min == max {
+        min.to_string()
+    } else {
+        format!("{min}..{max}")
+    }
+}
+
+#[derive(Default)]
+struct XsdSummary {
+    /// Element, type, occurs, parent
+    elements: Vec<[String; 4]>,
+    /// Attribute, parent, type, use, default
+    attributes: Vec<[String; 5]>,
+    /// Type, kind, base, constraints
+    types: Vec<[String; 4]>,
+}
+
+fn write_xsd_summary(writer: &mut dyn Write, root: &XmlElement) -> Result<()> {
+    writeln!(writer, "# Schema")?;
+    writeln!(writer)?;
+    if let Some(ns) = attr(root, "targetNamespace") {
+        writeln!(writer, "**Target namespace**: `{ns}`")?;
+        writeln!(writer)?;
     }
 
+    let mut summary = XsdSummary::default();
+    for child in child_elements(root) {
+        collect_xsd(child, "", false, &mut summary);
+    }
+
+    write_schema_table(
+        writer,
+        "Elements",
+        &["Element", "Type", "Occurs", "Parent"],
+        &summary.elements,
+    )?;
+    write_schema_table(
+        writer,
+        "Attributes",
+        &["Attribute", "Parent", "Type", "Use", "Default"],
+        &summary.attributes,
+    )?;
+    write_schema_table(
+        writer,
+        "Types",
+        &["Type", "Kind", "Base", "Constraints"],
+        &summary.types,
+    )?;
+
+    Ok(())
+}
+
+fn collect_xsd(elem: &XmlElement, parent: &str, in_choice: bool, summary: &mut XsdSummary) {
+    match elem.name.as_str() {
+        "element" => {
+            let name = attr(elem, "name").or(attr(elem, "ref")).unwrap_or_default();
+            let type_name = match attr(elem, "type") {
+                Some(t) => t.to_string(),
+                None if attr(elem, "ref").is_some() => "(ref)".to_string(),
+                None => anonymous_type(elem),
+            };
+            let mut occurs = occurs(
+                attr(elem, "minOccurs").unwrap_or("1"),
+                attr(elem, "maxOccurs").unwrap_or("1"),
+            );
+            if in_choice {
+                occurs.push_str(" (choice)");
+            }
+            summary
+                .elements
+                .push([name.to_string(), type_name, occurs, parent.to_string()]);
+            for child in child_elements(elem) {
+                collect_xsd(child, name, false, summary);
+            }
+        }
+        "attribute" => {
+            let name = attr(elem, "name").or(attr(elem, "ref")).unwrap_or_default();
+            let type_name = match attr(elem, "type") {
+                Some(t) => t.to_string(),
+                None => anonymous_type(elem),
+            };
+            let default = match (attr(elem, "default"), attr(elem, "fixed")) {
+                (Some(d), _) => d.to_string(),
+                (None, Some(f)) => format!("fixed: {f}"),
+                (None, None) => String::new(),
+            };
+            summary.attributes.push([
+                name.to_string(),
+                parent.to_string(),
+                type_name,
+                attr(elem, "use").unwrap_or("optional").to_string(),
+                default,
+            ]);
+        }
+        "complexType" | "simpleType" if attr(elem, "name").is_some() => {
+            let name = attr(elem, "name").unwrap_or_default();
+            let (kind, base, constraints) = if elem.name == "simpleType" {
+                let (base, constraints) = simple_type_summary(elem);
+                ("simple", base, constraints)
+            } else {
+                ("complex", complex_base(elem), String::new())
+            };
+            summary
+                .types
+                .push([name.to_string(), kind.to_string(), base, constraints]);
+            if kind == "complex" {
+                for child in child_elements(elem) {
+                    collect_xsd(child, name, false, summary);
+                }
+            }
+        }
+        "group" | "attributeGroup" if attr(elem, "name").is_some() => {
+            let name = attr(elem, "name").unwrap_or_default();
+            for child in child_elements(elem) {
+                collect_xsd(child, name, false, summary);
+            }
+        }
+        // Anonymous simple types are summarised by their owning declaration
+        "simpleType" | "annotation" => {}
+        name => {
+            let in_choice = name == "choice" || (in_choice && name != "sequence" && name != "all");
+            for child in child_elements(elem) {
+                collect_xsd(child, parent, in_choice, summary);
+            }
+        }
+    }
+}
+
+/// Describe the inline type of an element or attribute without a `type`.
+fn anonymous_type(elem: &XmlElement) -> String {
+    for child in child_elements(elem) {
+        match child.name.as_str() {
+            "complexType" => {
+                let base = complex_base(child);
+                return if base.is_empty() {
+                    "(complex)".to_string()
+                } else {
+                    format!("(complex, extends {base})")
+                };
+            }
+            "simpleType" => {
+                let (base, constraints) = simple_type_summary(child);
+                return if constraints.is_empty() {
+                    base
+                } else {
+                    format!("{base} ({constraints})")
+                };
+            }
+            _ => {}
+        }
+    }
+    String::new()
+}
+
+fn complex_base(elem: &XmlElement) -> String {
+    child_elements(elem)
+        .into_iter()
+        .filter(|c| c.name == "complexContent" || c.name == "simpleContent")
+        .flat_map(child_elements)
+        .filter(|c| c.name == "extension" || c.name == "restriction")
+        .find_map(|c| attr(c, "base"))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Return the base type and facets (enumerations, patterns, bounds) of a simple type.
+fn simple_type_summary(elem: &XmlElement) -> (String, String) {
+    for child in child_elements(elem) {
+        match child.name.as_str() {
+            "restriction" => {
+                let base = attr(child, "base").unwrap_or_default().to_string();
+                let mut values = Vec::new();
+                let mut facets = Vec::new();
+                for facet in child_elements(child) {
+                    let value = attr(facet, "value").unwrap_or_default();
+                    match facet.name.as_str() {
+                        "enumeration" => values.push(value),
+                        "annotation" | "simpleType" => {}
+                        name => facets.push(format!("{name}: {value}")),
+                    }
+                }
+                if !values.is_empty() {
+                    facets.insert(0, format!("one of: {}", values.join(", ")));
+                }
+                return (base, facets.join("; "));
+            }
+            "list" => {
+                let item = attr(child, "itemType").unwrap_or("(anonymous)");
+                return (format!("list of {item}"), String::new());
+            }
+            "union" => {
+                let members = attr(child, "memberTypes").unwrap_or("(anonymous)");
+                return (format!("union of {members}"), String::new());
+            }
+            _ => {}
+        }
+    }
+    (String::new(), String::new())
+}
+
+fn write_schema_table<const N: usize>(
+    writer: &mut dyn Write,
+    title: &str,
+    headers: &[&str; N],
+    rows: &[[String; N]],
+) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "## {title}")?;
+    writeln!(writer)?;
+    writeln!(writer, "| {} |", headers.join(" | "))?;
+    writeln!(writer, "|{}", "---|".repeat(N))?;
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|c| escape_pipe(c)).collect();
+        writeln!(writer, "| {} |", cells.join(" | "))?;
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Split a DTD into its `<!...>` declarations, skipping comments and
+/// honouring quoted literals that may contain `>`.
+fn dtd_declarations(text: &str) -> Vec<&str> {
+    let mut declarations = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<!") {
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.split_once("-->").map_or("", |(_, r)| r);
+            continue;
+        }
+        let mut quote: Option<char> = None;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '>') => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        declarations.push(rest[2..end].trim());
+        rest = rest.get(end + 1..).unwrap_or("");
+    }
+    declarations
+}
+
+/// Split declaration bodies into words, keeping quoted literals and
+/// parenthesised groups (with their trailing `?`, `*` or `+`) whole.
+fn dtd_tokens(body: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = body.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let literal: String = chars.by_ref().take_while(|&ch| ch != c).collect();
+            tokens.push(format!("\"{literal}\""));
+        } else if c == '(' {
+            let mut depth = 0;
+            let mut group = String::new();
+            for ch in chars.by_ref() {
+                group.push(ch);
+                match ch {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    break;
+                }
+            }
+            while let Some(&m) = chars.peek() {
+                if !matches!(m, '?' | '*' | '+') {
+                    break;
+                }
+                group.push(m);
+                chars.next();
+            }
+            tokens.push(group);
+        } else {
+            let mut word = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() || ch == '(' || ch == '"' || ch == '\'' {
+                    break;
+                }
+                word.push(ch);
+                chars.next();
+            }
+            tokens.push(word);
+        }
+    }
+    tokens
+}
+
+/// A child element referenced from a content model, with its effective
+/// occurrence after applying enclosing groups.
+struct Particle {
+    name: String,
+    optional: bool,
+    repeated: bool,
+}
+
+fn content_particles(model: &str) -> Vec<Particle> {
+    // Each open group collects its particles and whether it is a choice
+    let mut stack: Vec<(Vec<Particle>, bool)> = vec![(Vec::new(), false)];
+    let mut chars = model.chars().peekable();
+    let marker = |chars: &mut std::iter::Peekable<std::str::Chars>| match chars.peek() {
+        Some('?') => {
+            chars.next();
+            (true, false)
+        }
+        Some('*') => {
+            chars.next();
+            (true, true)
+        }
+        Some('+') => {
+            chars.next();
+            (false, true)
+        }
+        _ => (false, false),
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => stack.push((Vec::new(), false)),
+            '|' => {
+                if let Some(group) = stack.last_mut() {
+                    group.1 = true;
+                }
+            }
+            ')' if stack.len() > 1 => {
+                let (mut particles, choice) = stack.pop().unwrap_or_default();
+                let (optional, repeated) = marker(&mut chars);
+                let alternatives = particles.len() > 1;
+                for p in &mut particles {
+                    p.optional |= optional || (choice && alternatives);
+                    p.repeated |= repeated;
+                }
+                if let Some(parent) = stack.last_mut() {
+                    parent.0.extend(particles);
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '#' => {
+                let mut name = c.to_string();
+                while let Some(&ch) = chars.peek() {
+                    if !(ch.is_alphanumeric() || "_-.:".contains(ch)) {
+                        break;
+                    }
+                    name.push(ch);
+                    chars.next();
+                }
+                let (optional, repeated) = marker(&mut chars);
+                if name != "#PCDATA"
+                    && let Some(group) = stack.last_mut()
+                {
+                    group.0.push(Particle {
+                        name,
+                        optional,
+                        repeated,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    stack.into_iter().flat_map(|(particles, _)| particles).collect()
+}
+
+fn write_dtd_summary(writer: &mut dyn Write, text: &str) -> Result<()> {
+    // Element, content, children
+    let mut elements: Vec<[String; 3]> = Vec::new();
+    // Attribute, element, type, default
+    let mut attributes: Vec<[String; 4]> = Vec::new();
+    // Entity, value
+    let mut entities: Vec<[String; 2]> = Vec::new();
+
+    for declaration in dtd_declarations(text) {
+        let tokens = dtd_tokens(declaration);
+        let Some((keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+        match keyword.as_str() {
+            "ELEMENT" if args.len() >= 2 => {
+                let model = args[1..].join(" ");
+                let children: Vec<String> = content_particles(&model)
+                    .into_iter()
+                    .map(|p| {
+                        let occurs = match (p.optional, p.repeated) {
+                            (false, false) => "1",
+                            (true, false) => "0..1",
+                            (false, true) => "1..*",
+                            (true, true) => "0..*",
+                        };
+                        format!("{} ({occurs})", p.name)
+                    })
+                    .collect();
+                elements.push([args[0].clone(), format!("`{model}`"), children.join(", ")]);
+            }
+            "ATTLIST" if !args.is_empty() => {
+                let element = &args[0];
+                let mut rest = &args[1..];
+                while rest.len() >= 3 {
+                    let (name, attr_type) = (&rest[0], &rest[1]);
+                    let (default, used) = if rest[2] == "#FIXED" && rest.len() >= 4 {
+                        (format!("fixed: {}", rest[3].trim_matches('"')), 4)
+                    } else {
+                        (rest[2].trim_matches('"').to_string(), 3)
+                    };
+                    attributes.push([name.clone(), element.clone(), attr_type.clone(), default]);
+                    rest = &rest[used..];
+                }
+            }
+            "ENTITY" if args.len() >= 2 => {
+                let (name, value) = if args[0] == "%" && args.len() >= 3 {
+                    (format!("%{}", args[1]), &args[2..])
+                } else {
+                    (args[0].clone(), &args[1..])
+                };
+                entities.push([name, value.join(" ").trim_matches('"').to_string()]);
+            }
+            _ => {}
+        }
+    }
+
+    writeln!(writer, "# Schema")?;
+    writeln!(writer)?;
+    write_schema_table(
+        writer,
+        "Elements",
+        &["Element", "Content", "Children"],
+        &elements,
+    )?;
+    write_schema_table(
+        writer,
+        "Attributes",
+        &["Attribute", "Element", "Type", "Default"],
+        &attributes,
+    )?;
+    write_schema_table(writer, "Entities", &["Entity", "Value"], &entities)?;
+
     Ok(())
 }
 
@@ -203,6 +1022,7 @@ fn write_elements_as_table(
     writer: &mut dyn Write,
     elements: &[&XmlElement],
     depth: usize,
+    options: &XmlOptions,
 ) -> Result<()> {
     let level = (depth + 1).min(6);
     let hashes = "#".repeat(level);
@@ -214,7 +1034,7 @@ fn write_elements_as_table(
     let mut has_text = false;
 
     for elem in elements {
-        for (key, _) in &elem.attributes {
+        for (key, _) in shown_attributes(elem, options) {
             if !headers.contains(key) {
                 headers.push(key.clone());
             }
@@ -305,7 +1125,7 @@ mod tests {
     use rstest::rstest;
 
     fn convert(input: &str) -> String {
-        let converter = XmlConverter;
+        let converter = XmlConverter::default();
         let mut output = Vec::new();
         converter.convert(input.as_bytes(), &mut output).unwrap();
         String::from_utf8(output).unwrap()
@@ -363,7 +1183,7 @@ mod tests {
 
     #[rstest]
     fn test_empty_xml_error() {
-        let converter = XmlConverter;
+        let converter = XmlConverter::default();
         let mut output = Vec::new();
         let result = converter.convert(b"", &mut output);
         assert!(result.is_err());
@@ -377,4 +1197,181 @@ mod tests {
         assert!(output.contains("## b"));
         assert!(output.contains("| x |"));
     }
+
+    #[rstest]
+    #[case::drop_attributes(
+        XmlOptions {
+            drop_attributes: vec!["xmlns*".to_string(), "xsi:*".to_string()],
+            ..Default::default()
+        },
+        "# order\n\n| Attribute | Value |\n|---|---|\n| id | 7 |\n\n\
+         ## customer\n\n| Attribute | Value |\n|---|---|\n| name | Acme |\n\nAcme Corp\n\n\
+         ## item\n\n| sku | text |\n|---|---|\n| A1 | 2 |\n| B2 | 1 |\n\n\
+         ## note\n\nRush\n\n"
+    )]
+    #[case::title_attribute(
+        XmlOptions {
+            drop_attributes: vec!["xmlns*".to_string(), "xsi:*".to_string()],
+            title_attributes: vec!["name".to_string(), "id".to_string()],
+            ..Default::default()
+        },
+        "# order: 7\n\n\
+         ## customer: Acme\n\nAcme Corp\n\n\
+         ## item\n\n| sku | text |\n|---|---|\n| A1 | 2 |\n| B2 | 1 |\n\n\
+         ## note\n\nRush\n\n"
+    )]
+    #[case::flatten_leaves(
+        XmlOptions {
+            drop_attributes: vec!["xmlns*".to_string(), "xsi:*".to_string()],
+            flatten_leaves: true,
+            ..Default::default()
+        },
+        "# order\n\n| Attribute | Value |\n|---|---|\n| id | 7 |\n\n\
+         | Element | Value |\n|---|---|\n| note | Rush |\n\n\
+         ## customer\n\n| Attribute | Value |\n|---|---|\n| name | Acme |\n\nAcme Corp\n\n\
+         ## item\n\n| sku | text |\n|---|---|\n| A1 | 2 |\n| B2 | 1 |\n\n"
+    )]
+    fn test_attribute_options(#[case] xml: XmlOptions, #[case] expected: &str) {
+        let input = r#"<order xmlns="urn:orders" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Order" id="7"><customer name="Acme">Acme Corp</customer><item sku="A1">2</item><item sku="B2">1</item><note>Rush</note></order>"#;
+        let converter = XmlConverter::new(ConvertOptions {
+            xml,
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("xsi:*", "xsi:type", true)]
+    #[case("xmlns*", "xmlns", true)]
+    #[case("xmlns*", "xmlns:xsi", true)]
+    #[case("*:lang", "xml:lang", true)]
+    #[case("id", "id", true)]
+    #[case("id", "uuid", false)]
+    #[case("xsi:*", "type", false)]
+    fn test_wildcard_match(#[case] pattern: &str, #[case] name: &str, #[case] expected: bool) {
+        assert_eq!(wildcard_match(pattern, name), expected);
+    }
+
+    fn convert_xpath(input: &str, xpath: &str) -> String {
+        let converter = XmlConverter::new(ConvertOptions {
+            xml: crate::options::XmlOptions {
+                xpath: Some(xpath.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    const ORDERS: &str = r#"<export><header>ignored</header><order id="1"><item sku="a">Apple</item><item sku="b">Pear</item></order><order id="2"><item sku="c">Plum</item></order></export>"#;
+
+    #[rstest]
+    #[case::descendant_path(
+        "//order/item",
+        "# item\n\n| sku | text |\n|---|---|\n| a | Apple |\n| b | Pear |\n| c | Plum |\n\n"
+    )]
+    #[case::absolute_path(
+        "/export/header",
+        "# header\n\nignored\n\n"
+    )]
+    #[case::attribute_predicate(
+        "//item[@sku='c']",
+        "# item\n\n| Attribute | Value |\n|---|---|\n| sku | c |\n\nPlum\n\n"
+    )]
+    #[case::position_predicate(
+        "//order[2]/item[1]",
+        "# item\n\n| Attribute | Value |\n|---|---|\n| sku | c |\n\nPlum\n\n"
+    )]
+    #[case::child_text_predicate(
+        "/export/*[item='Plum']",
+        "# order\n\n| Attribute | Value |\n|---|---|\n| id | 2 |\n\n## item\n\n| Attribute | Value |\n|---|---|\n| sku | c |\n\nPlum\n\n"
+    )]
+    #[case::no_match(
+        "//invoice",
+        "*No nodes match `//invoice`*\n"
+    )]
+    fn test_xpath(#[case] xpath: &str, #[case] expected: &str) {
+        assert_eq!(convert_xpath(ORDERS, xpath), expected);
+    }
+
+    #[rstest]
+    fn test_xpath_document_order() {
+        let output = convert_xpath("<a><b n=\"1\"><x><b n=\"2\"/></x></b><b n=\"3\"/></a>", "//b");
+        let first = output.find("| 1 |").unwrap();
+        let second = output.find("| 2 |").unwrap();
+        let third = output.find("| 3 |").unwrap();
+        assert!(first < second && second < third);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::bad_predicate("//item[@]")]
+    #[case::unbalanced("//item[1")]
+    fn test_xpath_invalid(#[case] xpath: &str) {
+        let converter = XmlConverter::new(ConvertOptions {
+            xml: crate::options::XmlOptions {
+                xpath: Some(xpath.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        assert!(converter.convert(ORDERS.as_bytes(), &mut output).is_err());
+    }
+
+    #[rstest]
+    fn test_xsd_summary() {
+        let input = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:orders">
+  <xs:element name="order">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="item" type="xs:string" minOccurs="0" maxOccurs="unbounded"/>
+      </xs:sequence>
+      <xs:attribute name="id" type="xs:int" use="required"/>
+    </xs:complexType>
+  </xs:element>
+  <xs:simpleType name="Status">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="open"/>
+      <xs:enumeration value="closed"/>
+    </xs:restriction>
+  </xs:simpleType>
+</xs:schema>"#;
+        assert_eq!(
+            convert(input),
+            "# Schema\n\n**Target namespace**: `urn:orders`\n\n\
+             ## Elements\n\n| Element | Type | Occurs | Parent |\n|---|---|---|---|\n\
+             | order | (complex) | 1 |  |\n| item | xs:string | 0..* | order |\n\n\
+             ## Attributes\n\n| Attribute | Parent | Type | Use | Default |\n|---|---|---|---|---|\n\
+             | id | order | xs:int | required |  |\n\n\
+             ## Types\n\n| Type | Kind | Base | Constraints |\n|---|---|---|---|\n\
+             | Status | simple | xs:string | one of: open, closed |\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_dtd_summary() {
+        let input = "<!-- note -->\n<!ELEMENT note (to+, (cc|bcc)*, body?)>\n<!ATTLIST note id ID #REQUIRED lang CDATA \"en\">\n<!ENTITY sig \"Jane\">";
+        assert_eq!(
+            convert(input),
+            "# Schema\n\n\
+             ## Elements\n\n| Element | Content | Children |\n|---|---|---|\n\
+             | note | `(to+, (cc\\|bcc)*, body?)` | to (1..*), cc (0..*), bcc (0..*), body (0..1) |\n\n\
+             ## Attributes\n\n| Attribute | Element | Type | Default |\n|---|---|---|---|\n\
+             | id | note | ID | #REQUIRED |\n| lang | note | CDATA | en |\n\n\
+             ## Entities\n\n| Entity | Value |\n|---|---|\n| sig | Jane |\n\n"
+        );
+    }
+
+    #[rstest]
+    #[case::dtd("<?xml version=\"1.0\"?>\n<!ELEMENT a EMPTY>", true)]
+    #[case::document_with_doctype("<!DOCTYPE a>\n<a/>", false)]
+    #[case::document("<a/>", false)]
+    fn test_is_dtd(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(is_dtd(input), expected);
+    }
 }
@@ -2,9 +2,22 @@ use std::io::Write;
 
 use crate::converter::Converter;
 use crate::error::{Error, Result};
-use crate::formats::structured;
+use crate::formats::structured::{self, Value, schema};
+use crate::options::ConvertOptions;
 
-pub struct YamlConverter;
+mod compose;
+mod workflow;
+
+#[derive(Default)]
+pub struct YamlConverter {
+    options: ConvertOptions,
+}
+
+impl YamlConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for YamlConverter {
     fn format_name(&self) -> &'static str {
@@ -12,19 +25,39 @@ impl Converter for YamlConverter {
     }
 
     fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
-        let value: serde_yaml::Value =
-            serde_yaml::from_slice(input).map_err(|e| Error::Conversion {
-                format: "yaml",
-                message: e.to_string(),
-            })?;
-
-        let structured_value = structured::Value::from(value);
-        structured::write_value_as_markdown(writer, &structured_value)?;
-
-        Ok(())
+        let value = parse(input)?;
+        let options = &self.options.structured;
+        let errors = options.schema.as_ref().map(|s| s.validate(&value));
+        // With a schema the report follows the document, so hold it back
+        let mut document = Vec::new();
+        let out: &mut dyn Write = match errors {
+            Some(_) => &mut document,
+            None => writer,
+        };
+        if !options.generic && workflow::is_workflow(&value) {
+            workflow::write(out, &value)?;
+        } else if !options.generic && compose::is_compose(&value) {
+            compose::write(out, value, options)?;
+        } else {
+            structured::write_document(out, value, options)?;
+        }
+        match errors {
+            Some(errors) => schema::write_report(writer, &document, &errors),
+            None => Ok(()),
+        }
     }
 }
 
+pub(crate) fn parse(input: &[u8]) -> Result<Value> {
+    let value: serde_yaml::Value = serde_yaml::from_slice(input).map_err(|e| Error::Parse {
+        format: "yaml",
+        entry: None,
+        offset: e.location().map(|l| l.index() as u64),
+        message: e.to_string(),
+    })?;
+    Ok(Value::from(value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -33,7 +66,7 @@ mod tests {
     use rstest::rstest;
 
     fn convert(input: &str) -> String {
-        let converter = YamlConverter;
+        let converter = YamlConverter::default();
         let mut output = Vec::new();
         converter.convert(input.as_bytes(), &mut output).unwrap();
         String::from_utf8(output).unwrap()
@@ -72,6 +105,139 @@ mod tests {
         assert_eq!(convert(input), expected);
     }
 
+    const COMPOSE: &str = r#"
+name: shop
+services:
+  web:
+    image: nginx:1.25
+    ports: ["80:80", "443:443"]
+    volumes:
+      - ./site:/usr/share/nginx/html:ro
+    depends_on: [api]
+  api:
+    build:
+      context: ./api
+    ports:
+      - target: 8080
+        published: 9000
+        protocol: tcp
+    depends_on:
+      db:
+        condition: service_healthy
+  db:
+    image: postgres:16
+    volumes:
+      - type: volume
+        source: data
+        target: /var/lib/postgresql/data
+volumes:
+  data:
+    driver: local
+"#;
+
+    #[rstest]
+    fn test_compose() {
+        assert_eq!(
+            convert(COMPOSE),
+            "# Services\n\n\
+             | Service | Image | Ports | Volumes | Depends on |\n\
+             |---|---|---|---|---|\n\
+             | web | `nginx:1.25` | 80:80, 443:443 | ./site:/usr/share/nginx/html:ro | api |\n\
+             | api | build `./api` | 9000:8080/tcp |  | db |\n\
+             | db | `postgres:16` |  | data:/var/lib/postgresql/data |  |\n\n\
+             | Key | Value |\n|---|---|\n| name | shop |\n\n\
+             # volumes\n\n## data\n\n| Key | Value |\n|---|---|\n| driver | local |\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_compose_with_structured_options() {
+        let converter = YamlConverter::new(ConvertOptions {
+            structured: crate::options::StructuredOptions {
+                sort_keys: true,
+                front_matter: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(COMPOSE.as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "---\nname: shop\n---\n\n\
+             # Services\n\n\
+             | Service | Image | Ports | Volumes | Depends on |\n\
+             |---|---|---|---|---|\n\
+             | api | build `./api` | 9000:8080/tcp |  | db |\n\
+             | db | `postgres:16` |  | data:/var/lib/postgresql/data |  |\n\
+             | web | `nginx:1.25` | 80:80, 443:443 | ./site:/usr/share/nginx/html:ro | api |\n\n\
+             # volumes\n\n## data\n\n| Key | Value |\n|---|---|\n| driver | local |\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_workflow() {
+        let input = r#"
+name: CI
+on:
+  push:
+    branches: [main]
+  pull_request:
+  schedule:
+    - cron: "0 3 * * 1"
+jobs:
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - run: cargo clippy
+  test:
+    name: Test suite
+    needs: lint
+    runs-on: ${{ matrix.os }}
+    strategy:
+      matrix:
+        os: [ubuntu-latest, macos-latest]
+    steps:
+      - name: Checkout
+        uses: actions/checkout@v4
+      - name: Test
+        run: |
+          cargo build
+          cargo test
+"#;
+        assert_eq!(
+            convert(input),
+            "# CI\n\n\
+             **Triggers:** push (main), pull_request, schedule (cron: 0 3 * * 1)\n\n\
+             ## lint\n\n\
+             - **Runs on:** ubuntu-latest\n\n\
+             1. `actions/checkout@v4`\n\
+             2. `cargo clippy`\n\n\
+             ## test: Test suite\n\n\
+             - **Runs on:** ${{ matrix.os }}\n\
+             - **Needs:** lint\n\
+             - **Matrix:** os: ubuntu-latest, macos-latest\n\n\
+             1. Checkout: `actions/checkout@v4`\n\
+             2. Test\n   ```sh\n   cargo build\n   cargo test\n   ```\n\n"
+        );
+    }
+
+    #[rstest]
+    fn test_generic_yaml() {
+        let converter = YamlConverter::new(ConvertOptions {
+            structured: crate::options::StructuredOptions {
+                generic: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+        converter.convert(COMPOSE.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("| Key | Value |\n|---|---|\n| name | shop |\n\n# services\n"));
+    }
+
     #[rstest]
     fn test_non_string_keys() {
         let output = convert("true: yes\nfalse: no");
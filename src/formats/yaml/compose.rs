@@ -0,0 +1,134 @@
+use std::io::Write;
+
+use crate::error::Result;
+use crate::formats::structured::{self, Value};
+use crate::options::StructuredOptions;
+
+/// Whether `value` looks like a Docker Compose file: a `services` mapping
+/// of mappings, at least one of which names an `image` or a `build`.
+pub(super) fn is_compose(value: &Value) -> bool {
+    let Some(Value::Object(services)) = value.get("services") else {
+        return false;
+    };
+    services
+        .iter()
+        .all(|(_, service)| matches!(service, Value::Object(_)))
+        && services
+            .iter()
+            .any(|(_, service)| service.get("image").or(service.get("build")).is_some())
+}
+
+/// Write a Compose file as a table of its services, with the image, ports,
+/// volumes and dependencies of each, followed by its other top-level
+/// sections such as `networks` and `volumes`. Sorting keys and front matter
+/// apply as they do to any other document.
+pub(super) fn write(
+    writer: &mut dyn Write,
+    mut value: Value,
+    options: &StructuredOptions,
+) -> Result<()> {
+    if options.sort_keys {
+        value.sort_keys();
+    }
+    let Value::Object(mut entries) = value else {
+        return Ok(());
+    };
+    if options.front_matter {
+        entries = structured::write_front_matter(writer, entries)?;
+    }
+    writeln!(writer, "# Services")?;
+    writeln!(writer)?;
+    let headers = ["Service", "Image", "Ports", "Volumes", "Depends on"].map(String::from);
+    let services = entries.iter().find(|(key, _)| key == "services");
+    let rows: Vec<Vec<String>> = match services {
+        Some((_, Value::Object(services))) => services
+            .iter()
+            .map(|(name, service)| {
+                vec![
+                    name.clone(),
+                    image(service),
+                    list(service.get("ports"), port),
+                    list(service.get("volumes"), volume),
+                    depends_on(service),
+                ]
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    structured::write_markdown_table(writer, &headers, &rows)?;
+    writeln!(writer)?;
+
+    let rest: Vec<(String, Value)> = entries
+        .into_iter()
+        .filter(|(key, _)| key != "services")
+        .collect();
+    if !rest.is_empty() {
+        structured::write_value_as_markdown(writer, &Value::Object(rest))?;
+    }
+    Ok(())
+}
+
+/// The image a service runs, or the context it is built from.
+fn image(service: &Value) -> String {
+    if let Some(image) = service.get("image") {
+        return format!("`{}`", image.inline());
+    }
+    match service.get("build") {
+        Some(build @ Value::Object(_)) => match build.get("context") {
+            Some(context) => format!("build `{}`", context.inline()),
+            None => "build".to_string(),
+        },
+        Some(context) => format!("build `{}`", context.inline()),
+        None => String::new(),
+    }
+}
+
+fn list(value: Option<&Value>, item: fn(&Value) -> String) -> String {
+    match value {
+        Some(Value::Array(items)) => items.iter().map(item).collect::<Vec<_>>().join(", "),
+        Some(value) => item(value),
+        None => String::new(),
+    }
+}
+
+/// A port in the short `published:target` form, also for the long syntax.
+fn port(port: &Value) -> String {
+    if !matches!(port, Value::Object(_)) {
+        return port.inline();
+    }
+    let target = port.get("target").map(Value::inline).unwrap_or_default();
+    let mut text = match port.get("published") {
+        Some(published) => format!("{}:{target}", published.inline()),
+        None => target,
+    };
+    if let Some(protocol) = port.get("protocol") {
+        text = format!("{text}/{}", protocol.inline());
+    }
+    text
+}
+
+/// A volume in the short `source:target` form, also for the long syntax.
+fn volume(volume: &Value) -> String {
+    if !matches!(volume, Value::Object(_)) {
+        return volume.inline();
+    }
+    let target = volume.get("target").map(Value::inline).unwrap_or_default();
+    match volume.get("source") {
+        Some(source) => format!("{}:{target}", source.inline()),
+        None => target,
+    }
+}
+
+/// The services a service waits for, from either a list or a mapping of
+/// conditions.
+fn depends_on(service: &Value) -> String {
+    match service.get("depends_on") {
+        Some(Value::Object(entries)) => entries
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(value) => value.inline(),
+        None => String::new(),
+    }
+}
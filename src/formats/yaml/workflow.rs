@@ -0,0 +1,134 @@
+use std::io::Write;
+
+use crate::error::Result;
+use crate::formats::structured::Value;
+
+/// Whether `value` looks like a GitHub Actions workflow: its triggers
+/// under `on` and a `jobs` mapping of mappings.
+pub(super) fn is_workflow(value: &Value) -> bool {
+    let Some(Value::Object(jobs)) = value.get("jobs") else {
+        return false;
+    };
+    triggers(value).is_some() && jobs.iter().all(|(_, job)| matches!(job, Value::Object(_)))
+}
+
+/// The `on` key, which YAML 1.1 parsers read as `true`.
+fn triggers(value: &Value) -> Option<&Value> {
+    value.get("on").or_else(|| value.get("true"))
+}
+
+/// Write a workflow as an outline: its triggers, then each job with what
+/// it runs on, the jobs it needs and its steps as a numbered list.
+pub(super) fn write(writer: &mut dyn Write, value: &Value) -> Result<()> {
+    let name = value.get("name").map(Value::inline);
+    writeln!(writer, "# {}", name.as_deref().unwrap_or("Workflow"))?;
+    writeln!(writer)?;
+    if let Some(on) = triggers(value) {
+        writeln!(writer, "**Triggers:** {}", trigger_list(on))?;
+        writeln!(writer)?;
+    }
+
+    let Some(Value::Object(jobs)) = value.get("jobs") else {
+        return Ok(());
+    };
+    for (id, job) in jobs {
+        match job.get("name") {
+            Some(name) => writeln!(writer, "## {id}: {}", name.inline())?,
+            None => writeln!(writer, "## {id}")?,
+        }
+        writeln!(writer)?;
+
+        let details: Vec<(&str, String)> = [
+            ("Runs on", "runs-on"),
+            ("Needs", "needs"),
+            ("If", "if"),
+            ("Uses", "uses"),
+            ("Matrix", "strategy"),
+            ("Environment", "environment"),
+        ]
+        .into_iter()
+        .filter_map(|(label, key)| {
+            let value = job.get(key)?;
+            let value = match key {
+                "strategy" => value.get("matrix")?,
+                _ => value,
+            };
+            Some((label, value.inline()))
+        })
+        .collect();
+        for (label, value) in &details {
+            writeln!(writer, "- **{label}:** {value}")?;
+        }
+        if !details.is_empty() {
+            writeln!(writer)?;
+        }
+
+        if let Some(Value::Array(steps)) = job.get("steps") {
+            for (n, step) in steps.iter().enumerate() {
+                write_step(writer, n + 1, step)?;
+            }
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// The events a workflow runs on, with the branches or schedules that
+/// narrow them down.
+fn trigger_list(on: &Value) -> String {
+    let Value::Object(events) = on else {
+        return on.inline();
+    };
+    events
+        .iter()
+        .map(|(event, filter)| {
+            let detail = match filter {
+                Value::Object(_) => filter
+                    .get("branches")
+                    .or(filter.get("tags"))
+                    .or(filter.get("types"))
+                    .map(Value::inline),
+                // `schedule` is a list of `cron` entries
+                Value::Array(_) => Some(filter.inline()).filter(|s| !s.is_empty()),
+                _ => None,
+            };
+            match detail {
+                Some(detail) => format!("{event} ({detail})"),
+                None => event.clone(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// One step as a list item: its name with the action it uses or the
+/// command it runs, with multi-line scripts in a code block below.
+fn write_step(writer: &mut dyn Write, n: usize, step: &Value) -> Result<()> {
+    let name = step.get("name").map(Value::inline);
+    let action = step.get("uses").map(Value::inline);
+    let run = step.get("run").map(Value::inline).unwrap_or_default();
+    let script = run.trim_end();
+    let command = action
+        .or_else(|| (!script.contains('\n') && !script.is_empty()).then(|| script.to_string()));
+
+    let item = match (name, command) {
+        (Some(name), Some(command)) => format!("{name}: `{command}`"),
+        (Some(name), None) => name,
+        (None, Some(command)) => format!("`{command}`"),
+        (None, None) => "*Step*".to_string(),
+    };
+    writeln!(writer, "{n}. {item}")?;
+    if script.contains('\n') {
+        let indent = " ".repeat(n.to_string().len() + 2);
+        writeln!(writer, "{indent}```sh")?;
+        for line in script.lines() {
+            if line.is_empty() {
+                writeln!(writer)?;
+            } else {
+                writeln!(writer, "{indent}{line}")?;
+            }
+        }
+        writeln!(writer, "{indent}```")?;
+    }
+    Ok(())
+}
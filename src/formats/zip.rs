@@ -1,9 +1,23 @@
 use std::io::{Cursor, Write};
 
+use super::embedded;
 use crate::converter::Converter;
+use crate::detect::Format;
 use crate::error::{Error, Result};
+use crate::formats::archive::{format_size, is_included, read_entry, write_entry, write_summary};
+use crate::options::{ConvertOptions, ZipFallback};
+use crate::warnings;
 
-pub struct ZipConverter;
+#[derive(Default)]
+pub struct ZipConverter {
+    options: ConvertOptions,
+}
+
+impl ZipConverter {
+    pub fn new(options: ConvertOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Converter for ZipConverter {
     fn format_name(&self) -> &'static str {
@@ -19,7 +33,14 @@ impl Converter for ZipConverter {
 
         let mut total_uncompressed: u64 = 0;
         let mut total_compressed: u64 = 0;
-        let count = archive.len();
+        let count = (0..archive.len())
+            .filter(|&i| {
+                archive
+                    .name_for_index(i)
+                    .is_some_and(|name| is_included(&self.options.archive, name))
+            })
+            .count();
+        let skipped = archive.len() - count;
 
         writeln!(writer, "# Archive")?;
         writeln!(writer)?;
@@ -35,13 +56,19 @@ impl Converter for ZipConverter {
             "|---|------|------|------------|--------|"
         )?;
 
-        for i in 0..count {
+        let mut idx = 0;
+        let mut files: Vec<(String, u64)> = Vec::new();
+        for i in 0..archive.len() {
             let entry = archive.by_index(i).map_err(|e| Error::Conversion {
                 format: "zip",
                 message: e.to_string(),
             })?;
 
             let name = entry.name().to_string();
+            if !is_included(&self.options.archive, &name) {
+                continue;
+            }
+            idx += 1;
             let size = entry.size();
             let compressed = entry.compressed_size();
             let method = format!("{:?}", entry.compression());
@@ -52,17 +79,21 @@ impl Converter for ZipConverter {
             let (size_str, compressed_str) = if entry.is_dir() {
                 ("-".to_string(), "-".to_string())
             } else {
+                files.push((name.clone(), size));
                 (format_size(size), format_size(compressed))
             };
 
             writeln!(
                 writer,
                 "| {idx} | {name} | {size_str} | {compressed_str} | {method} |",
-                idx = i + 1,
             )?;
         }
 
         writeln!(writer)?;
+        if skipped > 0 {
+            writeln!(writer, "*{skipped} entries excluded by filters*")?;
+            writeln!(writer)?;
+        }
         let ratio = if total_uncompressed > 0 {
             format!(
                 "{:.1}%",
@@ -78,22 +109,170 @@ impl Converter for ZipConverter {
             format_size(total_compressed),
         )?;
 
+        if self.options.archive.summary {
+            let files: Vec<(&str, u64)> = files
+                .iter()
+                .map(|(name, size)| (name.as_str(), *size))
+                .collect();
+            write_summary(writer, &files)?;
+        }
+
+        if self.options.archive.depth > 0 {
+            for i in 0..archive.len() {
+                let entry = archive.by_index(i).map_err(|e| Error::Conversion {
+                    format: "zip",
+                    message: e.to_string(),
+                })?;
+                let name = entry.name().to_string();
+                if entry.is_dir() || !is_included(&self.options.archive, &name) {
+                    continue;
+                }
+                let bytes = read_entry(&self.options.archive, entry)?;
+                write_entry(writer, &name, bytes.as_deref(), &self.options)?;
+            }
+        } else if self.options.archive.zip_fallback == ZipFallback::ConvertEntries {
+            for i in 0..archive.len() {
+                let entry = archive.by_index(i).map_err(|e| Error::Conversion {
+                    format: "zip",
+                    message: e.to_string(),
+                })?;
+                let name = entry.name().to_string();
+                if entry.is_dir()
+                    || is_package_part(&name)
+                    || !is_included(&self.options.archive, &name)
+                {
+                    continue;
+                }
+                if let Some(bytes) = read_entry(&self.options.archive, entry)? {
+                    self.write_text_part(writer, &name, &bytes)?;
+                }
+            }
+        }
+
         Ok(())
     }
 }
 
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-
-    if bytes >= GB {
-        format!("{:.1} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{bytes} B")
+impl ZipConverter {
+    /// Write an XML or text entry as a section below the listing. Binary
+    /// entries, and text in formats without a converter, are skipped.
+    fn write_text_part(&self, writer: &mut dyn Write, name: &str, bytes: &[u8]) -> Result<()> {
+        let Some(text) = std::str::from_utf8(bytes)
+            .ok()
+            .filter(|text| !text.contains('\0') && !text.trim().is_empty())
+        else {
+            return Ok(());
+        };
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        let format = match Format::detect(Some(file_name), bytes) {
+            Some(
+                format @ (Format::Xml
+                | Format::Html
+                | Format::Json
+                | Format::Yaml
+                | Format::Toml
+                | Format::Csv
+                | Format::MarkdownDocx),
+            ) => Some(format),
+            Some(_) => return Ok(()),
+            // Package parts often use their own extensions (.fpage, .model, ...)
+            None if text.trim_start().starts_with('<') => Some(Format::Xml),
+            None => None,
+        };
+
+        writeln!(writer)?;
+        writeln!(writer, "## {name}")?;
+        writeln!(writer)?;
+        match format {
+            Some(format) => match embedded::convert_as(format, name, bytes, &self.options, 2) {
+                Ok(markdown) => writeln!(writer, "{markdown}")?,
+                Err(e) => {
+                    warnings::warn(format!("{name}: not converted: {e}"));
+                    writeln!(writer, "*Not converted: {e}*")?
+                }
+            },
+            None => {
+                let fence = "`".repeat(3.max(longest_backtick_run(text) + 1));
+                writeln!(writer, "{fence}")?;
+                writeln!(writer, "{}", text.trim_end())?;
+                writeln!(writer, "{fence}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Open Packaging Convention bookkeeping that says nothing about the
+/// content: the content type map, relationship files and signatures.
+fn is_package_part(name: &str) -> bool {
+    name == "[Content_Types].xml"
+        || name.ends_with(".rels")
+        || name.starts_with("_xmlsignatures/")
+        || name.starts_with("package/services/")
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn xps_like() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in [
+            ("[Content_Types].xml", &b"<Types/>"[..]),
+            ("_rels/.rels", b"<Relationships/>"),
+            (
+                "Documents/1/Pages/1.fpage",
+                b"<FixedPage><Glyphs UnicodeString=\"Hello\"/></FixedPage>",
+            ),
+            ("Documents/1/notes.txt", b"plain ```notes```"),
+            ("Resources/logo.png", b"\x89PNG\r\n\x1a\n\0\0"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            std::io::Write::write_all(&mut zip, content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn convert(fallback: ZipFallback) -> String {
+        let options = ConvertOptions {
+            archive: crate::options::ArchiveOptions {
+                zip_fallback: fallback,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        ZipConverter::new(options)
+            .convert(&xps_like(), &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    fn test_fallback_lists_entries() {
+        let output = convert(ZipFallback::List);
+        assert!(output.contains("| 3 | Documents/1/Pages/1.fpage |"), "{output}");
+        assert!(!output.contains("## "), "{output}");
+    }
+
+    #[cfg(feature = "xml")]
+    #[rstest]
+    fn test_fallback_converts_text_parts() {
+        let output = convert(ZipFallback::ConvertEntries);
+        assert!(output.contains("## Documents/1/Pages/1.fpage\n"), "{output}");
+        assert!(output.contains("Hello"), "{output}");
+        assert!(
+            output.contains("## Documents/1/notes.txt\n\n````\nplain ```notes```\n````\n"),
+            "{output}"
+        );
+        for skipped in ["## [Content_Types].xml", "## _rels/.rels", "## Resources/logo.png"] {
+            assert!(!output.contains(skipped), "unexpected {skipped:?} in\n{output}");
+        }
     }
 }
@@ -0,0 +1,333 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use crate::formats::anchors::Slugger;
+use crate::metadata::without_counts;
+use crate::split;
+
+/// Deepest heading level listed under each document.
+const MAX_LEVEL: usize = 3;
+
+/// Common English words never picked as keywords.
+const STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "also", "among", "another", "because", "been",
+    "before", "being", "below", "between", "both", "cannot", "could", "does", "doing", "down",
+    "during", "each", "either", "even", "every", "from", "further", "have", "having", "here",
+    "into", "itself", "just", "like", "made", "make", "many", "more", "most", "much", "must",
+    "neither", "only", "other", "over", "same", "should", "since", "some", "such", "than", "that",
+    "their", "them", "then", "there", "these", "they", "this", "those", "through", "under",
+    "until", "upon", "very", "were", "what", "when", "where", "which", "while", "will", "with",
+    "within", "without", "would", "your",
+];
+
+/// A browsable index of the documents written by one batch conversion: each
+/// document's headings linked back to its output file and, optionally, a
+/// glossary of its most frequent terms.
+pub struct Index {
+    /// Directory the index is written to, which links are relative to.
+    dir: PathBuf,
+    /// Keywords picked per document; none when zero.
+    keywords: usize,
+    documents: Vec<Document>,
+}
+
+struct Document {
+    title: String,
+    link: String,
+    headings: Vec<Heading>,
+    keywords: Vec<String>,
+}
+
+struct Heading {
+    level: usize,
+    text: String,
+    anchor: String,
+}
+
+impl Index {
+    /// An empty index to be written at `path`, listing up to `keywords`
+    /// terms per document.
+    pub fn new(path: &Path, keywords: usize) -> Self {
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Self {
+            dir: absolute(&dir),
+            keywords,
+            documents: Vec::new(),
+        }
+    }
+
+    /// Add the Markdown written to `output`. A document with a single
+    /// level-1 heading is titled by it; others by their file name. The
+    /// `--counts` appendix is not part of the document.
+    pub fn add(&mut self, output: &Path, markdown: &str) {
+        let markdown = without_counts(markdown);
+        let link = relative_link(&self.dir, &absolute(output));
+        let mut slugger = Slugger::default();
+        let mut headings: Vec<Heading> = outline(markdown)
+            .into_iter()
+            .map(|(level, text)| Heading {
+                level,
+                anchor: slugger.slug(&text),
+                text,
+            })
+            .collect();
+
+        let titles = headings.iter().filter(|h| h.level == 1).count();
+        let title = match headings.iter().position(|h| h.level == 1) {
+            Some(i) if titles == 1 => headings.remove(i).text,
+            _ => output
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| link.clone()),
+        };
+        headings.retain(|h| h.level <= MAX_LEVEL);
+
+        let keywords = if self.keywords > 0 {
+            keywords(markdown, self.keywords)
+        } else {
+            Vec::new()
+        };
+        self.documents.push(Document {
+            title,
+            link,
+            headings,
+            keywords,
+        });
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Index\n\n## Documents\n\n");
+        for doc in &self.documents {
+            out.push_str(&format!("- [{}]({})\n", escape(&doc.title), doc.link));
+            let top = doc.headings.iter().map(|h| h.level).min().unwrap_or(1);
+            for heading in &doc.headings {
+                let indent = "  ".repeat(heading.level - top + 1);
+                out.push_str(&format!(
+                    "{indent}- [{}]({}#{})\n",
+                    escape(&heading.text),
+                    doc.link,
+                    heading.anchor
+                ));
+            }
+        }
+
+        let mut terms: BTreeMap<&str, Vec<&Document>> = BTreeMap::new();
+        for doc in &self.documents {
+            for keyword in &doc.keywords {
+                terms.entry(keyword).or_default().push(doc);
+            }
+        }
+        if !terms.is_empty() {
+            out.push_str("\n## Terms\n\n");
+            for (term, docs) in terms {
+                let links: Vec<String> = docs
+                    .iter()
+                    .map(|doc| format!("[{}]({})", escape(&doc.title), doc.link))
+                    .collect();
+                out.push_str(&format!("- **{term}**: {}\n", links.join(", ")));
+            }
+        }
+        out
+    }
+}
+
+/// Level and plain text of the headings outside front matter and fenced
+/// code blocks.
+fn outline(markdown: &str) -> Vec<(usize, String)> {
+    let mut headings = Vec::new();
+    let mut in_code = false;
+    for line in body(markdown).lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if let Some((level, text)) = split::heading(line) {
+            let text = plain_text(text);
+            if !text.is_empty() {
+                headings.push((level, text));
+            }
+        }
+    }
+    headings
+}
+
+/// The `count` most frequent words of four or more letters in the prose,
+/// leaving out stopwords and words used only once; ties go to the
+/// alphabetically first.
+fn keywords(markdown: &str, count: usize) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut in_code = false;
+    for line in body(markdown).lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let text = plain_text(line);
+        for word in text.split(|c: char| !c.is_alphabetic() && c != '\'') {
+            let word = word.trim_matches('\'').to_lowercase();
+            if word.chars().count() >= 4 && !STOPWORDS.contains(&word.as_str()) {
+                *counts.entry(word).or_default() += 1;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().filter(|(_, n)| *n > 1).collect();
+    ranked.sort_by(|(a, m), (b, n)| n.cmp(m).then_with(|| a.cmp(b)));
+    ranked.truncate(count);
+    ranked.into_iter().map(|(word, _)| word).collect()
+}
+
+/// The document after its YAML front matter, if any.
+fn body(markdown: &str) -> &str {
+    markdown
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.find("\n---\n").map(|end| &rest[end + 5..]))
+        .unwrap_or(markdown)
+}
+
+/// Text with links and images reduced to their text, inline code to its
+/// content, and emphasis markers and HTML comments removed.
+fn plain_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        if c == ']' && rest[1..].starts_with('(') {
+            // Drop the link target
+            rest = rest[2..].find(')').map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        if c == '\\' && rest.len() > 1 {
+            let escaped = rest[1..].chars().next().unwrap_or_default();
+            out.push(escaped);
+            rest = &rest[1 + escaped.len_utf8()..];
+            continue;
+        }
+        if !matches!(c, '[' | ']' | '!' | '*' | '_' | '`') {
+            out.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Link from a file in `dir` to `target`, both absolute, with `..` for each
+/// directory climbed and spaces escaped.
+pub(crate) fn relative_link(dir: &Path, target: &Path) -> String {
+    let dir: Vec<Component> = dir.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    let common = dir.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); dir.len() - common];
+    parts.extend(
+        target[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/").replace(' ', "%20")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_index_links_headings() {
+        let mut index = Index::new(Path::new("/kb/index.md"), 0);
+        index.add(
+            Path::new("/kb/out/report.md"),
+            "---\ntitle: x\n---\n\n# Quarterly Report\n\n## Summary\n\n### [Risks](#risks)\n\n#### Too deep\n\n```\n# not a heading\n```\n\n## Summary\n",
+        );
+        index.add(
+            Path::new("/kb/out/my deck.md"),
+            "# Slide 1\n\nIntro\n\n# Slide 2\n",
+        );
+        assert_eq!(
+            index.to_markdown(),
+            "# Index\n\n\
+             ## Documents\n\n\
+             - [Quarterly Report](out/report.md)\n\
+             \x20 - [Summary](out/report.md#summary)\n\
+             \x20   - [Risks](out/report.md#risks)\n\
+             \x20 - [Summary](out/report.md#summary-1)\n\
+             - [my deck](out/my%20deck.md)\n\
+             \x20 - [Slide 1](out/my%20deck.md#slide-1)\n\
+             \x20 - [Slide 2](out/my%20deck.md#slide-2)\n"
+        );
+    }
+
+    #[rstest]
+    fn test_index_skips_counts() {
+        let mut index = Index::new(Path::new("/kb/index.md"), 0);
+        index.add(
+            Path::new("/kb/notes.md"),
+            "# Notes\n\n## Plans\n\nSome text.\n\n## Counts\n\n| Metric | Value |\n|--------|-------|\n| Words | 4 |\n",
+        );
+        assert_eq!(
+            index.to_markdown(),
+            "# Index\n\n## Documents\n\n- [Notes](notes.md)\n  - [Plans](notes.md#plans)\n"
+        );
+    }
+
+    #[rstest]
+    fn test_index_terms() {
+        let mut index = Index::new(Path::new("/kb/index.md"), 2);
+        index.add(
+            Path::new("/kb/budget.md"),
+            "# Budget\n\nThe budget covers hiring. Hiring starts when the budget is approved.\n\nThe [budget](https://example.com/hiring) has hiring, travel and travel.\n",
+        );
+        index.add(
+            Path::new("/kb/travel.md"),
+            "# Travel\n\nTravel policy: book travel early. Those rules apply to `code code`.\n",
+        );
+        assert_eq!(
+            index.to_markdown(),
+            "# Index\n\n\
+             ## Documents\n\n\
+             - [Budget](budget.md)\n\
+             - [Travel](travel.md)\n\n\
+             ## Terms\n\n\
+             - **budget**: [Budget](budget.md)\n\
+             - **code**: [Travel](travel.md)\n\
+             - **hiring**: [Budget](budget.md)\n\
+             - **travel**: [Travel](travel.md)\n"
+        );
+    }
+
+    #[rstest]
+    #[case::sibling("/kb", "/kb/a.md", "a.md")]
+    #[case::nested("/kb", "/kb/out/sub/a.md", "out/sub/a.md")]
+    #[case::parent("/kb/index", "/kb/out/a.md", "../out/a.md")]
+    fn test_relative_link(#[case] dir: &str, #[case] target: &str, #[case] expected: &str) {
+        assert_eq!(relative_link(Path::new(dir), Path::new(target)), expected);
+    }
+
+    #[rstest]
+    #[case::link("See [the docs](https://example.com) now", "See the docs now")]
+    #[case::image_and_code("![logo](a.png) `x` **bold**", "logo x bold")]
+    #[case::escaped("1\\. Intro \\[draft\\]", "1. Intro [draft]")]
+    #[case::comment("Title <!-- Page 1 -->", "Title")]
+    fn test_plain_text(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(plain_text(text), expected);
+    }
+}
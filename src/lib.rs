@@ -1,4 +1,22 @@
+pub mod batch;
 pub mod converter;
+#[cfg(feature = "remote")]
+pub mod crawl;
+#[cfg(feature = "describe-images")]
+pub mod describe;
 pub mod detect;
 pub mod error;
 pub mod formats;
+pub mod index;
+pub mod manifest;
+pub mod media;
+pub mod metadata;
+pub mod options;
+#[cfg(feature = "redact")]
+pub mod redact;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+pub mod split;
+#[cfg(feature = "remote-transcribe")]
+pub mod transcribe;
+pub mod warnings;
@@ -1,17 +1,54 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{self, BufWriter, IsTerminal, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use miette::IntoDiagnostic;
 
+use mq_conv::batch::{Duplicates, Input, collect_inputs};
+use mq_conv::converter::Converter;
 use mq_conv::detect::Format;
+use mq_conv::error::Error;
+#[cfg(feature = "audio")]
+use mq_conv::formats::audio::album::{self, AlbumTrack};
+#[cfg(feature = "csv")]
+use mq_conv::formats::csv::dataset::{self, DatasetFile};
+#[cfg(feature = "image")]
+use mq_conv::formats::image::contact_sheet::{self, SheetImage};
+use mq_conv::formats::structured::{self, diff, schema::Schema};
+use mq_conv::index::Index;
+use mq_conv::manifest::Outline;
+use mq_conv::metadata::Counts;
+use mq_conv::options::{
+    ArchiveOptions, ConvertOptions, CsvOptions, DescribeOptions, EpubOptions, ExcelOptions,
+    Formulas, HtmlOptions, MediaOptions, MergedCells, MetadataOptions, Notes, NotesStyle,
+    PdfOptions, PowerPointOptions, RedactOptions, Revisions, SlideRanges, SqliteOptions,
+    StructuredOptions, StyleMap, TableSpans, TranscribeOptions, TranscribeProvider, VideoOptions,
+    WordOptions, XmlOptions, ZipFallback,
+};
+#[cfg(feature = "sandbox")]
+use mq_conv::sandbox::{
+    self, DEFAULT_CPU_SECONDS, DEFAULT_MEMORY_MIB, SandboxOptions, SandboxedConverter,
+};
+use mq_conv::split::{self, Split};
+
+// Only shown in --help without the feature, which rejects --sandbox
+#[cfg(not(feature = "sandbox"))]
+const DEFAULT_MEMORY_MIB: u64 = 1024;
+#[cfg(not(feature = "sandbox"))]
+const DEFAULT_CPU_SECONDS: u64 = 60;
 
 #[derive(Parser, Debug)]
 #[command(name = "mq-conv")]
 #[command(version, about = "Convert various file formats to Markdown")]
 struct Args {
-    /// Input file paths (reads from stdin if not provided)
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Input files or directories (reads from stdin if not provided)
     files: Vec<PathBuf>,
 
     /// Force a specific format instead of auto-detecting
@@ -22,9 +59,784 @@ struct Args {
     #[arg(short, long)]
     output_dir: Option<PathBuf>,
 
+    /// Write each section at this heading level to its own file, with an index
+    /// linking them (requires --output-dir)
+    #[arg(long, value_name = "LEVEL", requires = "output_dir")]
+    split_by_heading: Option<HeadingLevelArg>,
+
+    /// Convert byte-identical inputs again instead of noting the duplicate
+    #[arg(long)]
+    keep_duplicates: bool,
+
     /// Target output format when converting from Markdown
     #[arg(long)]
     to: Option<ToArg>,
+
+    /// How to report a failure on stderr
+    #[arg(long, value_name = "MODE", default_value = "text")]
+    errors: ErrorsArg,
+
+    /// Skip slides, sheets or chapters that fail to convert, leaving a
+    /// warning note in their place, instead of failing the whole document
+    #[arg(long)]
+    best_effort: bool,
+
+    /// Neutralize table cells that start like a spreadsheet formula (=, +, -, @)
+    /// or contain HTML, for CSV, Excel and SQLite output read by other tools
+    #[arg(long)]
+    sanitize_cells: bool,
+
+    /// Parse each input in a child process with limited memory and CPU time
+    /// and no network access, for untrusted files (Linux only)
+    #[arg(
+        long,
+        help_heading = "Sandbox",
+        conflicts_with_all = ["crawl", "dataset", "album", "contact_sheet"]
+    )]
+    sandbox: bool,
+
+    /// Memory limit of the sandboxed process, in MiB
+    #[arg(
+        long,
+        value_name = "MIB",
+        default_value_t = DEFAULT_MEMORY_MIB,
+        help_heading = "Sandbox",
+        requires = "sandbox"
+    )]
+    sandbox_memory: u64,
+
+    /// CPU time limit of the sandboxed process, in seconds
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = DEFAULT_CPU_SECONDS,
+        help_heading = "Sandbox",
+        requires = "sandbox"
+    )]
+    sandbox_cpu: u64,
+
+    /// Also install a seccomp filter denying Internet sockets, starting
+    /// programs and other system calls converters never need
+    #[arg(long, help_heading = "Sandbox", requires = "sandbox")]
+    sandbox_seccomp: bool,
+
+    /// Detect the dominant language and record it in the front matter
+    #[arg(long)]
+    detect_language: bool,
+
+    /// Report word, character and estimated token counts: appended to the
+    /// Markdown, or as one JSON line per document on stderr
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "markdown"
+    )]
+    counts: Option<CountsArg>,
+
+    /// Mask email addresses, phone numbers, card numbers and SSNs in the output
+    #[arg(long)]
+    redact: bool,
+
+    /// JSON file choosing the values to mask and adding custom patterns,
+    /// e.g. {"builtins": ["email"], "patterns": [{"pattern": "EMP-\\d{6}"}]};
+    /// implies --redact
+    #[arg(long, value_name = "FILE")]
+    redact_config: Option<PathBuf>,
+
+    /// Write a JSON manifest of the links, media and tables in each output
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// Write per-file conversion timings, sizes, warnings and formats as JSON
+    #[arg(long, value_name = "FILE")]
+    stats: Option<PathBuf>,
+
+    /// Write a Markdown index linking the headings of every output file,
+    /// as an entry point to the converted set (requires --output-dir)
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "output_dir",
+        conflicts_with_all = ["split_by_heading", "split_pdf", "split_sheets"]
+    )]
+    index: Option<PathBuf>,
+
+    /// Also list up to this many frequent terms per document in a glossary
+    /// section of the --index
+    #[arg(long, value_name = "N", requires = "index")]
+    index_keywords: Option<usize>,
+
+    /// Write images and other media to this directory and link them from the output
+    #[arg(long, value_name = "DIR")]
+    extract_media: Option<PathBuf>,
+
+    /// Inline Word and PowerPoint images as base64 data URIs instead of writing them out
+    #[arg(long, conflicts_with = "extract_media")]
+    embed_media: bool,
+
+    /// Describe image files with this vision model endpoint and use the result
+    /// as alt text; images embedded in other documents are not described
+    #[arg(
+        long,
+        value_name = "URL",
+        help_heading = "Image",
+        conflicts_with = "sandbox"
+    )]
+    describe_images: Option<String>,
+
+    /// Model to request from the --describe-images endpoint
+    #[arg(
+        long,
+        value_name = "MODEL",
+        help_heading = "Image",
+        requires = "describe_images"
+    )]
+    describe_model: Option<String>,
+
+    /// Maximum width or height of thumbnails written with --extract-media or
+    /// shown with --contact-sheet
+    #[arg(long, value_name = "PX", help_heading = "Image")]
+    thumbnail_size: Option<u32>,
+
+    /// Show all image inputs as one contact sheet: a table with each image's
+    /// thumbnail, file name, dimensions, size and capture date
+    #[arg(
+        long,
+        help_heading = "Image",
+        requires = "files",
+        conflicts_with_all = ["output_dir", "dataset", "album"]
+    )]
+    contact_sheet: bool,
+
+    /// Only list archive entries matching this glob (repeatable)
+    #[arg(long, value_name = "GLOB", help_heading = "Archive")]
+    include: Vec<String>,
+
+    /// Skip archive entries matching this glob, e.g. node_modules (repeatable)
+    #[arg(long, value_name = "GLOB", help_heading = "Archive")]
+    exclude: Vec<String>,
+
+    /// Convert archive entries after the listing, descending this many
+    /// levels into nested archives
+    #[arg(long, value_name = "N", default_value_t = 0, help_heading = "Archive")]
+    archive_depth: usize,
+
+    /// Add the ten largest files and file counts and sizes per extension
+    #[arg(long, help_heading = "Archive")]
+    archive_summary: bool,
+
+    /// Do not convert archive entries larger than this many bytes [default: 16 MiB]
+    #[arg(long, value_name = "BYTES", help_heading = "Archive")]
+    max_entry_size: Option<u64>,
+
+    /// How to handle zips that are not a known package format (.xps, .vsdx, .3mf, ...)
+    #[arg(long, value_name = "MODE", default_value = "list", help_heading = "Archive")]
+    zip_fallback: ZipFallbackArg,
+
+    /// Annotate section breaks with the page size and orientation
+    #[arg(long, help_heading = "Word")]
+    section_notes: bool,
+
+    /// JSON file mapping custom paragraph styles to Markdown constructs,
+    /// e.g. {"CompanyHeading2": "h2", "CodeSample": "code"}
+    #[arg(long, value_name = "FILE", help_heading = "Word")]
+    style_map: Option<PathBuf>,
+
+    /// How to render table cells merged across columns or rows, in Word
+    /// tables and Excel sheets
+    #[arg(long, value_name = "MODE", default_value = "blank")]
+    merged_cells: MergedCellsArg,
+
+    /// Emit page headers once at the top and footers once at the bottom
+    #[arg(long, help_heading = "Word")]
+    headers_footers: bool,
+
+    /// How to render tracked changes
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value = "accept",
+        help_heading = "Word"
+    )]
+    revisions: RevisionsArg,
+
+    /// Include reviewer comments: after a Word document with their authors and
+    /// anchor text, below each PowerPoint slide with their authors and dates,
+    /// and below each Excel sheet with their cells and authors (.xlsx)
+    #[arg(long)]
+    comments: bool,
+
+    /// Password needed to open encrypted PDFs
+    #[arg(
+        long,
+        value_name = "PASSWORD",
+        env = "MQ_CONV_PDF_PASSWORD",
+        hide_env_values = true,
+        help_heading = "PDF"
+    )]
+    password: Option<String>,
+
+    /// Convert embedded PDF attachments in a recognised format, not just
+    /// list them
+    #[arg(long, help_heading = "PDF")]
+    convert_attachments: bool,
+
+    /// Write each PDF page or outline chapter to its own file, with an index
+    /// linking them (requires --output-dir)
+    #[arg(long, value_name = "UNIT", help_heading = "PDF", requires = "output_dir")]
+    split_pdf: Option<SplitPdfArg>,
+
+    /// Write each worksheet to its own `<workbook>-<sheet>.md` file instead of
+    /// one file per workbook (requires --output-dir)
+    #[arg(long, help_heading = "Excel", requires = "output_dir")]
+    split_sheets: bool,
+
+    /// Document each sheet's data-validation rules and dropdown lists (.xlsx)
+    #[arg(long, help_heading = "Excel")]
+    data_validation: bool,
+
+    /// Append a Mermaid graph of which sheets and tables each sheet's formulas read from
+    #[arg(long, help_heading = "Excel")]
+    formula_graph: bool,
+
+    /// Show formula cells' cached values, their formulas in place of the values,
+    /// or the values with a table of each sheet's formulas
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value = "values",
+        help_heading = "Excel"
+    )]
+    formulas: FormulasArg,
+
+    /// Show numbers and dates as stored instead of as the cells' number formats display them
+    #[arg(long, help_heading = "Excel")]
+    raw_values: bool,
+
+    /// Convert hidden sheets, rows and columns, which are left out by default
+    #[arg(long, help_heading = "Excel")]
+    include_hidden: bool,
+
+    /// Convert only the worksheets with this name, where * matches any
+    /// characters, e.g. 'Summary' or '2024-*' (repeatable)
+    #[arg(long, value_name = "NAME", help_heading = "Excel")]
+    sheet: Vec<String>,
+
+    /// Show at most N rows of each sheet's tables, noting how many there are
+    #[arg(long, value_name = "N", help_heading = "Excel")]
+    max_rows: Option<usize>,
+
+    /// Write .xlsx rows as they are read, each sheet as one table, instead of
+    /// loading whole sheets into memory first
+    #[arg(long, help_heading = "Excel")]
+    stream: bool,
+
+    /// Include speaker notes, leave them out, or convert only the notes as a talk script
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value = "include",
+        help_heading = "PowerPoint"
+    )]
+    notes: NotesArg,
+
+    /// How to render speaker notes
+    #[arg(
+        long,
+        value_name = "STYLE",
+        default_value = "blockquote",
+        help_heading = "PowerPoint"
+    )]
+    notes_style: NotesStyleArg,
+
+    /// Summarize each slide's transition and numbered animation sequence
+    #[arg(long, help_heading = "PowerPoint")]
+    animations: bool,
+
+    /// Write each slide's text top to bottom and left to right, as laid out,
+    /// instead of in the order the shapes were added
+    #[arg(long, help_heading = "PowerPoint")]
+    reading_order: bool,
+
+    /// Only convert these slides, e.g. 1-5,10 or 12- (numbers stay as in the deck)
+    #[arg(long, value_name = "RANGES", help_heading = "PowerPoint")]
+    slides: Option<SlideRanges>,
+
+    /// Show each chapter's word count and reading time below its heading
+    #[arg(long, help_heading = "EPUB")]
+    chapter_stats: bool,
+
+    /// Render each record as a column instead of a row
+    #[arg(long, help_heading = "CSV")]
+    transpose: bool,
+
+    /// Show at most this many table columns
+    #[arg(
+        long,
+        value_name = "N",
+        help_heading = "CSV",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_columns: Option<usize>,
+
+    /// Emit one section per distinct value of the given column
+    #[arg(long, value_name = "COLUMN", help_heading = "CSV")]
+    group_by: Option<String>,
+
+    /// Describe all CSV inputs as one dataset: shared columns and per-file
+    /// row counts
+    #[arg(long, help_heading = "CSV", requires = "files", conflicts_with = "output_dir")]
+    dataset: bool,
+
+    /// With --dataset, add a merged preview of up to N rows from each file
+    #[arg(long, value_name = "N", help_heading = "CSV", requires = "dataset")]
+    dataset_preview: Option<usize>,
+
+    /// How to render tables with colspan/rowspan cells
+    #[arg(long, value_name = "MODE", default_value = "flatten", help_heading = "HTML")]
+    table_spans: TableSpansArg,
+
+    /// Treat the input as a URL and also convert the pages it links to
+    /// (requires the `remote` feature)
+    #[arg(
+        long,
+        help_heading = "HTML",
+        requires = "files",
+        conflicts_with_all = ["dataset", "album", "contact_sheet"]
+    )]
+    crawl: bool,
+
+    /// Stop crawling after this many pages
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 20,
+        help_heading = "HTML",
+        requires = "crawl"
+    )]
+    max_pages: usize,
+
+    /// Only follow links to the start URL's scheme, host and port
+    #[arg(long, help_heading = "HTML", requires = "crawl")]
+    same_origin: bool,
+
+    /// Sort object keys alphabetically
+    #[arg(long, help_heading = "JSON/YAML/TOML")]
+    sort_keys: bool,
+
+    /// Emit top-level scalar keys as YAML front matter and only nested
+    /// structures as the body
+    #[arg(long, help_heading = "JSON/YAML/TOML")]
+    front_matter: bool,
+
+    /// Render GeoJSON, Docker Compose files and GitHub Actions workflows like
+    /// any other document instead of as tailored tables and outlines
+    #[arg(long, help_heading = "JSON/YAML/TOML")]
+    generic: bool,
+
+    /// JSON Schema to check documents against, with a validation report below
+    /// the converted content
+    #[arg(long, value_name = "FILE", help_heading = "JSON/YAML/TOML")]
+    schema: Option<PathBuf>,
+
+    /// Emit only table schemas
+    #[arg(long, help_heading = "SQLite", conflicts_with = "data_only")]
+    schema_only: bool,
+
+    /// Emit only row data, including every row
+    #[arg(long, help_heading = "SQLite")]
+    data_only: bool,
+
+    /// Add null counts, min/max and distinct counts of each table's columns
+    #[arg(long, help_heading = "SQLite", conflicts_with = "schema_only")]
+    column_stats: bool,
+
+    /// Send audio to this speech-to-text endpoint and include the transcript
+    #[arg(
+        long,
+        value_name = "URL",
+        help_heading = "Audio/Video",
+        conflicts_with = "sandbox"
+    )]
+    transcribe_endpoint: Option<String>,
+
+    /// API style of the transcription endpoint
+    #[arg(
+        long,
+        value_name = "PROVIDER",
+        default_value = "openai",
+        help_heading = "Audio/Video"
+    )]
+    transcribe_provider: TranscribeProviderArg,
+
+    /// Model to request from the transcription endpoint
+    #[arg(long, value_name = "MODEL", help_heading = "Audio/Video")]
+    transcribe_model: Option<String>,
+
+    /// Describe all audio inputs as one album: a tracklist with durations and
+    /// the total runtime, then each track's metadata
+    #[arg(
+        long,
+        help_heading = "Audio/Video",
+        requires = "files",
+        conflicts_with_all = ["output_dir", "dataset"]
+    )]
+    album: bool,
+
+    /// Extract this many evenly spaced keyframes (requires --extract-media and ffmpeg)
+    #[arg(
+        long,
+        value_name = "N",
+        help_heading = "Video",
+        requires = "extract_media",
+        conflicts_with = "sandbox_seccomp"
+    )]
+    keyframes: Option<usize>,
+
+    /// Append the subtitles of a video from the .srt or .vtt file of the
+    /// same name next to it, e.g. movie.srt for movie.mp4
+    #[arg(long, help_heading = "Video", conflicts_with = "sandbox")]
+    subtitles: bool,
+
+    /// Only convert the elements matching this path (e.g. //order/item)
+    #[arg(long, value_name = "PATH", help_heading = "XML")]
+    xpath: Option<String>,
+
+    /// Leave out XML attributes with this name, where * matches any characters,
+    /// e.g. 'xmlns*' or 'xsi:*' (repeatable)
+    #[arg(long, value_name = "NAME", help_heading = "XML")]
+    drop_attribute: Vec<String>,
+
+    /// Show this attribute, e.g. name or id, in element headings; the first one
+    /// an element has is used (repeatable)
+    #[arg(long, value_name = "NAME", help_heading = "XML")]
+    title_attribute: Vec<String>,
+
+    /// Write elements holding only text as element/value rows of their parent
+    #[arg(long, help_heading = "XML")]
+    flatten_leaves: bool,
+}
+
+impl Args {
+    fn convert_options(&self) -> miette::Result<ConvertOptions> {
+        let style_map = match &self.style_map {
+            Some(path) => {
+                let json = fs::read_to_string(path).into_diagnostic()?;
+                StyleMap::from_json(&json)
+                    .map_err(|e| miette::miette!("{}: {e}", path.display()))?
+            }
+            None => StyleMap::default(),
+        };
+        let redact = match &self.redact_config {
+            Some(path) => {
+                let json = fs::read_to_string(path).into_diagnostic()?;
+                RedactOptions::from_json(&json)
+                    .map_err(|e| miette::miette!("{}: {e}", path.display()))?
+            }
+            None if self.redact => RedactOptions::builtin(),
+            None => RedactOptions::default(),
+        };
+        let schema = match &self.schema {
+            Some(path) => {
+                let json = fs::read_to_string(path).into_diagnostic()?;
+                Some(
+                    Schema::from_json(&json)
+                        .map_err(|e| miette::miette!("{}: {e}", path.display()))?,
+                )
+            }
+            None => None,
+        };
+        Ok(ConvertOptions {
+            input_name: None,
+            best_effort: self.best_effort,
+            sanitize_cells: self.sanitize_cells,
+            metadata: MetadataOptions {
+                detect_language: self.detect_language,
+                counts: matches!(self.counts, Some(CountsArg::Markdown)),
+            },
+            redact,
+            media: MediaOptions {
+                extract_dir: self.extract_media.clone(),
+                thumbnail_size: self.thumbnail_size,
+                embed: self.embed_media,
+                link_base: None,
+            },
+            describe: DescribeOptions {
+                endpoint: self.describe_images.clone(),
+                model: self.describe_model.clone(),
+            },
+            archive: ArchiveOptions {
+                include: self.include.clone(),
+                exclude: self.exclude.clone(),
+                depth: self.archive_depth,
+                summary: self.archive_summary,
+                max_entry_size: self.max_entry_size,
+                zip_fallback: self.zip_fallback.clone().into(),
+            },
+            word: WordOptions {
+                section_notes: self.section_notes,
+                style_map,
+                merged_cells: self.merged_cells.clone().into(),
+                headers_footers: self.headers_footers,
+                revisions: self.revisions.clone().into(),
+                comments: self.comments,
+            },
+            pdf: PdfOptions {
+                password: self.password.clone(),
+                convert_attachments: self.convert_attachments,
+            },
+            powerpoint: PowerPointOptions {
+                notes: self.notes.clone().into(),
+                notes_style: self.notes_style.clone().into(),
+                animations: self.animations,
+                slides: self.slides.clone(),
+                comments: self.comments,
+                reading_order: self.reading_order,
+            },
+            epub: EpubOptions {
+                chapter_stats: self.chapter_stats,
+            },
+            excel: ExcelOptions {
+                data_validation: self.data_validation,
+                comments: self.comments,
+                formula_graph: self.formula_graph,
+                formulas: self.formulas.clone().into(),
+                merged_cells: self.merged_cells.clone().into(),
+                raw_values: self.raw_values,
+                include_hidden: self.include_hidden,
+                sheets: self.sheet.clone(),
+                max_rows: self.max_rows,
+                stream: self.stream,
+            },
+            csv: CsvOptions {
+                transpose: self.transpose,
+                max_columns: self.max_columns,
+                group_by: self.group_by.clone(),
+            },
+            html: HtmlOptions {
+                table_spans: self.table_spans.clone().into(),
+            },
+            structured: StructuredOptions {
+                sort_keys: self.sort_keys,
+                front_matter: self.front_matter,
+                generic: self.generic,
+                schema,
+            },
+            sqlite: SqliteOptions {
+                schema_only: self.schema_only,
+                data_only: self.data_only,
+                column_stats: self.column_stats,
+            },
+            transcribe: TranscribeOptions {
+                endpoint: self.transcribe_endpoint.clone(),
+                provider: self.transcribe_provider.clone().into(),
+                model: self.transcribe_model.clone(),
+            },
+            video: VideoOptions {
+                keyframes: self.keyframes.unwrap_or(0),
+                subtitles: None,
+            },
+            xml: XmlOptions {
+                xpath: self.xpath.clone(),
+                drop_attributes: self.drop_attribute.clone(),
+                title_attributes: self.title_attribute.clone(),
+                flatten_leaves: self.flatten_leaves,
+            },
+        })
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two JSON, YAML or TOML documents and report added, removed
+    /// and changed keys, or two SQLite databases and report schema changes
+    /// and row counts
+    Diff {
+        /// The original document or database
+        old: PathBuf,
+        /// The document or database to compare against it
+        new: PathBuf,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq, Eq)]
+enum ErrorsArg {
+    /// A readable message
+    Text,
+    /// A JSON object with a stable `code`, the message, and the format,
+    /// container entry and byte offset where known
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq, Eq)]
+enum CountsArg {
+    Markdown,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum HeadingLevelArg {
+    H1,
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+}
+
+impl HeadingLevelArg {
+    fn level(self) -> usize {
+        self as usize + 1
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SplitPdfArg {
+    Pages,
+    Chapters,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum FormulasArg {
+    Values,
+    Inline,
+    Table,
+}
+
+impl From<FormulasArg> for Formulas {
+    fn from(arg: FormulasArg) -> Self {
+        match arg {
+            FormulasArg::Values => Formulas::Values,
+            FormulasArg::Inline => Formulas::Inline,
+            FormulasArg::Table => Formulas::Table,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum NotesArg {
+    Include,
+    Exclude,
+    Only,
+}
+
+impl From<NotesArg> for Notes {
+    fn from(arg: NotesArg) -> Self {
+        match arg {
+            NotesArg::Include => Notes::Include,
+            NotesArg::Exclude => Notes::Exclude,
+            NotesArg::Only => Notes::Only,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum NotesStyleArg {
+    Blockquote,
+    Details,
+    Footnote,
+}
+
+impl From<NotesStyleArg> for NotesStyle {
+    fn from(arg: NotesStyleArg) -> Self {
+        match arg {
+            NotesStyleArg::Blockquote => NotesStyle::Blockquote,
+            NotesStyleArg::Details => NotesStyle::Details,
+            NotesStyleArg::Footnote => NotesStyle::Footnote,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum RevisionsArg {
+    /// Show the document with every change accepted
+    Accept,
+    /// Show insertions in bold and deletions struck through
+    Markup,
+}
+
+impl From<RevisionsArg> for Revisions {
+    fn from(arg: RevisionsArg) -> Self {
+        match arg {
+            RevisionsArg::Accept => Revisions::Accept,
+            RevisionsArg::Markup => Revisions::Markup,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum MergedCellsArg {
+    /// Leave cells covered by a merged cell empty
+    Blank,
+    /// Repeat merged cells in every column and row they cover
+    Repeat,
+    /// Leave covered cells empty and list the merged ranges
+    Annotate,
+}
+
+impl From<MergedCellsArg> for MergedCells {
+    fn from(arg: MergedCellsArg) -> Self {
+        match arg {
+            MergedCellsArg::Blank => MergedCells::Blank,
+            MergedCellsArg::Repeat => MergedCells::Repeat,
+            MergedCellsArg::Annotate => MergedCells::Annotate,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum TableSpansArg {
+    /// Convert spanned cells like any other
+    Flatten,
+    /// Repeat spanned cells in every row and column they cover
+    Expand,
+    /// Keep tables with spanned cells as raw HTML
+    Html,
+}
+
+impl From<TableSpansArg> for TableSpans {
+    fn from(arg: TableSpansArg) -> Self {
+        match arg {
+            TableSpansArg::Flatten => TableSpans::Flatten,
+            TableSpansArg::Expand => TableSpans::Expand,
+            TableSpansArg::Html => TableSpans::Html,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum ZipFallbackArg {
+    /// Only list the entries
+    List,
+    /// Also convert the XML and text parts
+    ConvertEntries,
+}
+
+impl From<ZipFallbackArg> for ZipFallback {
+    fn from(arg: ZipFallbackArg) -> Self {
+        match arg {
+            ZipFallbackArg::List => ZipFallback::List,
+            ZipFallbackArg::ConvertEntries => ZipFallback::ConvertEntries,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum TranscribeProviderArg {
+    #[value(name = "openai")]
+    OpenAi,
+    Deepgram,
+}
+
+impl From<TranscribeProviderArg> for TranscribeProvider {
+    fn from(arg: TranscribeProviderArg) -> Self {
+        match arg {
+            TranscribeProviderArg::OpenAi => TranscribeProvider::OpenAi,
+            TranscribeProviderArg::Deepgram => TranscribeProvider::Deepgram,
+        }
+    }
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -33,6 +845,7 @@ enum FormatArg {
     Pdf,
     Powerpoint,
     Word,
+    Doc,
     Image,
     Zip,
     Epub,
@@ -86,6 +899,7 @@ impl From<FormatArg> for Format {
             FormatArg::Pdf => Format::Pdf,
             FormatArg::Powerpoint => Format::PowerPoint,
             FormatArg::Word => Format::Word,
+            FormatArg::Doc => Format::Doc,
             FormatArg::Image => Format::Image,
             FormatArg::Zip => Format::Zip,
             FormatArg::Epub => Format::Epub,
@@ -120,33 +934,733 @@ fn resolve_output_format(detected: Format, forced_to: Option<&ToArg>) -> miette:
     }
 }
 
-fn convert_one(
+/// The options for converting the file at `path`. With `--subtitles`, they
+/// carry the `.srt` or `.vtt` file of the same name next to it, which the
+/// video converter appends. When the output is written to `out_dir`, links
+/// to extracted media are relative to it.
+fn file_options<'a>(
+    args: &Args,
+    options: &'a ConvertOptions,
+    path: &Path,
+    out_dir: Option<&Path>,
+) -> Cow<'a, ConvertOptions> {
+    let link_base = out_dir.filter(|_| options.media.extract_dir.is_some());
+    if !args.subtitles && link_base.is_none() {
+        return Cow::Borrowed(options);
+    }
+    let mut options = options.clone();
+    if args.subtitles {
+        options.video.subtitles = ["srt", "vtt"]
+            .iter()
+            .map(|ext| path.with_extension(ext))
+            .filter(|sidecar| sidecar != path)
+            .find_map(|sidecar| fs::read(sidecar).ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+    }
+    options.media.link_base = link_base.map(Path::to_path_buf);
+    Cow::Owned(options)
+}
+
+/// Detect the input format and build its converter, which runs in a child
+/// process with `sandbox`.
+fn make_converter(
     input: &[u8],
     filename: Option<&str>,
     forced_format: Option<&FormatArg>,
     forced_to: Option<&ToArg>,
-    writer: &mut dyn Write,
-) -> miette::Result<()> {
+    options: &ConvertOptions,
+    sandbox: bool,
+) -> miette::Result<Box<dyn Converter>> {
     let detected = if let Some(f) = forced_format {
         f.clone().into()
     } else {
         Format::detect(filename, input).ok_or_else(|| {
-            miette::miette!("Could not detect file format. Use --format to specify.")
+            miette::Report::new(Error::DetectionFailed)
+                .wrap_err("Could not detect file format. Use --format to specify.")
         })?
     };
     let format = resolve_output_format(detected, forced_to)?;
+    if options.describe.endpoint.is_some() && format != Format::Image {
+        return Err(miette::miette!(
+            "--describe-images only applies to image files"
+        ));
+    }
+
+    let options = ConvertOptions {
+        input_name: filename.map(str::to_string),
+        ..options.clone()
+    };
+    let converter =
+        mq_conv::formats::get_converter(format, &options).map_err(miette::Report::new)?;
+    if sandbox {
+        return sandboxed(converter, filename, &options);
+    }
+    Ok(converter)
+}
+
+/// Run `converter` in a copy of this process started with the same
+/// arguments, which converts its stdin and exits.
+#[cfg(feature = "sandbox")]
+fn sandboxed(
+    converter: Box<dyn Converter>,
+    filename: Option<&str>,
+    options: &ConvertOptions,
+) -> miette::Result<Box<dyn Converter>> {
+    let program = std::env::current_exe().into_diagnostic()?;
+    Ok(Box::new(SandboxedConverter::new(
+        converter,
+        program,
+        std::env::args_os().skip(1).collect(),
+        filename.map(str::to_string),
+        options.media.link_base.clone(),
+    )))
+}
+
+#[cfg(not(feature = "sandbox"))]
+fn sandboxed(
+    _converter: Box<dyn Converter>,
+    _filename: Option<&str>,
+    _options: &ConvertOptions,
+) -> miette::Result<Box<dyn Converter>> {
+    Err(miette::Report::new(Error::FeatureDisabled(
+        "sandbox".into(),
+    )))
+}
+
+/// The child side of `--sandbox`: convert stdin to stdout under the
+/// sandbox's restrictions. Failures are reported as `--errors json` objects
+/// for the parent to read back.
+#[cfg(feature = "sandbox")]
+fn run_sandbox_child(args: &Args) -> miette::Result<()> {
+    let mut options = args.convert_options()?;
+    options.media.link_base = std::env::var_os(sandbox::LINK_BASE_ENV).map(PathBuf::from);
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input).into_diagnostic()?;
+    let filename = std::env::var(sandbox::INPUT_NAME_ENV).ok();
+
+    // Detection already parses the input, such as a zip's central directory
+    sandbox::restrict(&SandboxOptions {
+        memory_mib: args.sandbox_memory,
+        cpu_seconds: args.sandbox_cpu,
+        seccomp: args.sandbox_seccomp,
+    })
+    .map_err(miette::Report::new)?;
 
-    let converter = mq_conv::formats::get_converter(format).map_err(|e| miette::miette!("{e}"))?;
+    let converter = make_converter(
+        &input,
+        filename.as_deref(),
+        args.format.as_ref(),
+        args.to.as_ref(),
+        &options,
+        false,
+    )?;
+
+    let mut writer = BufWriter::new(io::stdout().lock());
     converter
-        .convert(input, writer)
-        .map_err(|e| miette::miette!("{e}"))?;
+        .convert(&input, &mut writer)
+        .map_err(miette::Report::new)?;
+    writer.flush().into_diagnostic()?;
+    report_warnings(None);
     Ok(())
 }
 
+/// Per-document reports requested on the command line, which need the
+/// converted output rather than just streaming it.
+struct Report {
+    counts_json: bool,
+    manifest: Option<Vec<serde_json::Value>>,
+    stats: Option<Vec<serde_json::Value>>,
+    index: Option<Index>,
+}
+
+impl Report {
+    fn captures_output(&self) -> bool {
+        self.counts_json || self.manifest.is_some() || self.stats.is_some() || self.index.is_some()
+    }
+
+    fn record_stats(
+        &mut self,
+        input: Option<&Path>,
+        format: &str,
+        sizes: (usize, usize),
+        duration: Duration,
+        warnings: usize,
+    ) {
+        if let Some(entries) = &mut self.stats {
+            entries.push(serde_json::json!({
+                "input": input.map(|p| p.display().to_string()),
+                "format": format,
+                "duration_ms": duration.as_secs_f64() * 1000.0,
+                "input_bytes": sizes.0,
+                "output_bytes": sizes.1,
+                "warnings": warnings,
+            }));
+        }
+    }
+
+    fn record(
+        &mut self,
+        input: Option<&Path>,
+        output: Option<&Path>,
+        format: &str,
+        markdown: &str,
+        first_line: usize,
+    ) {
+        let input = input.map(|p| p.display().to_string());
+        if self.counts_json {
+            let counts = Counts::of(markdown);
+            eprintln!(
+                "{}",
+                serde_json::json!({
+                    "file": input,
+                    "words": counts.words,
+                    "characters": counts.characters,
+                    "tokens": counts.tokens,
+                })
+            );
+        }
+        if let Some(entries) = &mut self.manifest {
+            let mut entry = serde_json::json!({
+                "input": input,
+                "output": output.map(|p| p.display().to_string()),
+                "format": format,
+            });
+            if let (Some(entry), serde_json::Value::Object(outline)) = (
+                entry.as_object_mut(),
+                Outline::scan(markdown, first_line).to_json(),
+            ) {
+                entry.extend(outline);
+            }
+            entries.push(entry);
+        }
+        if let (Some(index), Some(output)) = (&mut self.index, output)
+            && output.extension().is_some_and(|ext| ext == "md")
+        {
+            index.add(output, markdown);
+        }
+    }
+
+    fn record_duplicate(&mut self, input: &Path, output: Option<&Path>, original: &Path) {
+        if let Some(entries) = &mut self.manifest {
+            entries.push(serde_json::json!({
+                "input": input.display().to_string(),
+                "output": output.map(|p| p.display().to_string()),
+                "duplicate_of": original.display().to_string(),
+            }));
+        }
+    }
+}
+
+/// Note written in place of a byte-identical input's conversion.
+fn duplicate_note(original: &Path) -> String {
+    format!("*Duplicate of `{}`; not converted again.*\n", original.display())
+}
+
+/// Run a converter into `writer`, recording reports for it. Returns the
+/// number of lines written when output is captured, so callers writing
+/// several documents to one stream can keep line numbers accurate.
+fn run_converter(
+    converter: &dyn Converter,
+    input: &[u8],
+    input_path: Option<&Path>,
+    output_path: Option<&Path>,
+    first_line: usize,
+    report: &mut Report,
+    writer: &mut dyn Write,
+) -> miette::Result<usize> {
+    if !report.captures_output() {
+        converter
+            .convert(input, writer)
+            .map_err(miette::Report::new)?;
+        report_warnings(input_path);
+        return Ok(0);
+    }
+
+    let mut buf = Vec::new();
+    let started = Instant::now();
+    converter
+        .convert(input, &mut buf)
+        .map_err(miette::Report::new)?;
+    let duration = started.elapsed();
+    writer.write_all(&buf).into_diagnostic()?;
+
+    let warnings = report_warnings(input_path);
+    report.record_stats(
+        input_path,
+        converter.format_name(),
+        (input.len(), buf.len()),
+        duration,
+        warnings,
+    );
+
+    let markdown = String::from_utf8_lossy(&buf);
+    report.record(
+        input_path,
+        output_path,
+        converter.format_name(),
+        &markdown,
+        first_line,
+    );
+    Ok(markdown.matches('\n').count())
+}
+
+/// Print the warnings recorded while converting `input` to stderr,
+/// returning how many there were.
+fn report_warnings(input: Option<&Path>) -> usize {
+    let warnings = mq_conv::warnings::take();
+    for warning in &warnings {
+        match input {
+            Some(path) => eprintln!("warning: {}: {warning}", path.display()),
+            None => eprintln!("warning: {warning}"),
+        }
+    }
+    warnings.len()
+}
+
+/// Divides converted Markdown into sections for `write_sections`.
+type Splitter = Box<dyn Fn(&str) -> Split>;
+
+/// Write the sections of `split` to one file each in a directory beside
+/// `out_path`, and an index linking them at `out_path`.
+fn write_sections(out_path: &Path, markdown: &str, mut split: Split) -> miette::Result<()> {
+    if split.sections.is_empty() {
+        return fs::write(out_path, markdown).into_diagnostic();
+    }
+
+    let dir = out_path.with_extension("");
+    fs::create_dir_all(&dir).into_diagnostic()?;
+    let dir_name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let count = split.sections.len();
+    let names: Vec<String> = split
+        .sections
+        .iter()
+        .enumerate()
+        .map(|(i, section)| section.file_name(i, count))
+        .collect();
+    split.link_sections(&names, &dir_name);
+    for (name, section) in names.iter().zip(&split.sections) {
+        fs::write(dir.join(name), &section.markdown).into_diagnostic()?;
+    }
+
+    fs::write(out_path, split::index(&split, &dir_name)).into_diagnostic()
+}
+
+/// Write each sheet of a converted workbook to `<workbook>-<sheet>.md` beside
+/// `out_path`, repeating any front matter in every file. The workbook's named
+/// ranges are not a sheet and are written to `out_path` itself.
+fn write_sheets(out_path: &Path, markdown: &str, mut split: Split) -> miette::Result<()> {
+    if split.sections.is_empty() {
+        return fs::write(out_path, markdown).into_diagnostic();
+    }
+    if let Some(pos) = split
+        .sections
+        .iter()
+        .rposition(|section| section.title == "Named Ranges")
+    {
+        let section = split.sections.remove(pos);
+        let content = format!("{}{}", split.preamble, section.markdown);
+        fs::write(out_path, content).into_diagnostic()?;
+    }
+
+    let workbook = out_path
+        .file_stem()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut written = HashSet::new();
+    let mut names = Vec::new();
+    for section in &split.sections {
+        // Sheet names that differ only in punctuation or case share a slug
+        let base = section.prefixed_file_name(&workbook);
+        let mut name = base.clone();
+        let mut n = 1;
+        while !written.insert(name.clone()) {
+            n += 1;
+            name = format!("{}-{n}.md", base.trim_end_matches(".md"));
+        }
+        names.push(name);
+    }
+    // Links to other sheets point at their files, which sit side by side
+    split.link_sections(&names, "");
+    for (name, section) in names.into_iter().zip(&split.sections) {
+        let content = format!("{}{}", split.preamble, section.markdown);
+        fs::write(out_path.with_file_name(name), content).into_diagnostic()?;
+    }
+    Ok(())
+}
+
+/// Write a Markdown report of the differences between two structured
+/// documents to stdout.
+fn run_diff(old: &Path, new: &Path, forced_format: Option<&FormatArg>) -> miette::Result<()> {
+    let read = |path: &Path| -> miette::Result<(Format, Vec<u8>)> {
+        let input = fs::read(path).map_err(|e| miette::Report::new(Error::Io(e)))?;
+        let filename = path.file_name().map(|n| n.to_string_lossy().into_owned());
+        let format = match forced_format {
+            Some(f) => f.clone().into(),
+            None => Format::detect(filename.as_deref(), &input).ok_or_else(|| {
+                miette::Report::new(Error::DetectionFailed).wrap_err(format!(
+                    "Could not detect the format of {}. Use --format to specify.",
+                    path.display()
+                ))
+            })?,
+        };
+        Ok((format, input))
+    };
+    let (old_format, old_input) = read(old)?;
+    let (new_format, new_input) = read(new)?;
+
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    if old_format == Format::Sqlite || new_format == Format::Sqlite {
+        if old_format != new_format {
+            return Err(miette::miette!(
+                "Cannot compare a SQLite database with another format"
+            ));
+        }
+        run_sqlite_diff(old, new, &mut writer)?;
+        return writer.flush().into_diagnostic();
+    }
+
+    let parse = |path: &Path, format: Format, input: &[u8]| {
+        structured::parse(format, input).map_err(|e| miette::miette!("{}: {e}", path.display()))
+    };
+    let changes = diff::diff(
+        &parse(old, old_format, &old_input)?,
+        &parse(new, new_format, &new_input)?,
+    );
+    diff::write_diff_as_markdown(
+        &mut writer,
+        &old.display().to_string(),
+        &new.display().to_string(),
+        &changes,
+    )
+    .map_err(miette::Report::new)?;
+    writer.flush().into_diagnostic()
+}
+
+/// Write a report of the schema and row-count changes between two SQLite
+/// databases.
+#[cfg(feature = "sqlite")]
+fn run_sqlite_diff(old: &Path, new: &Path, writer: &mut dyn Write) -> miette::Result<()> {
+    use mq_conv::formats::sqlite::diff::{self, Schema};
+
+    let open =
+        |path: &Path| Schema::open(path).map_err(|e| miette::miette!("{}: {e}", path.display()));
+    let changes = diff::diff(&open(old)?, &open(new)?);
+    diff::write_diff_as_markdown(
+        writer,
+        &old.display().to_string(),
+        &new.display().to_string(),
+        &changes,
+    )
+    .map_err(miette::Report::new)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn run_sqlite_diff(_old: &Path, _new: &Path, _writer: &mut dyn Write) -> miette::Result<()> {
+    Err(miette::Report::new(Error::FeatureDisabled("sqlite".into())))
+}
+
+/// Convert the pages crawled from the start URL, each into its own file
+/// under the output directory or as its own section on stdout.
+#[cfg(feature = "remote")]
+fn run_crawl(args: &Args, options: &ConvertOptions, report: &mut Report) -> miette::Result<()> {
+    use mq_conv::crawl::{self, CrawlOptions};
+
+    let [start] = &args.files[..] else {
+        return Err(miette::miette!("--crawl takes a single start URL"));
+    };
+    let crawl_options = CrawlOptions {
+        max_pages: args.max_pages,
+        same_origin: args.same_origin,
+    };
+    let pages =
+        crawl::crawl(&start.to_string_lossy(), &crawl_options).map_err(miette::Report::new)?;
+    report_warnings(None);
+
+    let convert = |page: &crawl::Page,
+                   output: Option<&Path>,
+                   first_line: usize,
+                   report: &mut Report,
+                   writer: &mut dyn Write| {
+        let options = ConvertOptions {
+            input_name: Some(page.url.to_string()),
+            ..options.clone()
+        };
+        let converter =
+            mq_conv::formats::get_converter(Format::Html, &options).map_err(miette::Report::new)?;
+        let input = Path::new(page.url.as_str());
+        run_converter(
+            converter.as_ref(),
+            &page.html,
+            Some(input),
+            output,
+            first_line,
+            report,
+            writer,
+        )
+    };
+
+    if let Some(output_dir) = &args.output_dir {
+        let mut written = HashSet::new();
+        for page in &pages {
+            // Different URLs, such as `/a` and `/a.html`, can map to one file
+            let base = page.file_path();
+            let mut relative = base.clone();
+            let mut n = 1;
+            while !written.insert(relative.clone()) {
+                n += 1;
+                let stem = base.file_stem().unwrap_or_default().to_string_lossy();
+                relative = base.with_file_name(format!("{stem}-{n}.md"));
+            }
+            let out_path = output_dir.join(relative);
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent).into_diagnostic()?;
+            }
+            let file = fs::File::create(&out_path).into_diagnostic()?;
+            let mut writer = BufWriter::new(file);
+            convert(page, Some(&out_path), 1, report, &mut writer)?;
+            writer.flush().into_diagnostic()?;
+        }
+        return Ok(());
+    }
+
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    let mut line = 1;
+    for (i, page) in pages.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer, "\n---\n").into_diagnostic()?;
+            line += 3;
+        }
+        line += convert(page, None, line, report, &mut writer)?;
+    }
+    writer.flush().into_diagnostic()
+}
+
+#[cfg(not(feature = "remote"))]
+fn run_crawl(_args: &Args, _options: &ConvertOptions, _report: &mut Report) -> miette::Result<()> {
+    Err(miette::Report::new(Error::FeatureDisabled("remote".into())))
+}
+
+/// Write one report describing every CSV among `inputs` to stdout.
+#[cfg(feature = "csv")]
+fn run_dataset(args: &Args, inputs: &[Input]) -> miette::Result<()> {
+    let mut files = Vec::new();
+    for entry in inputs {
+        let input = fs::read(&entry.path).map_err(|e| miette::Report::new(Error::Io(e)))?;
+        let filename = entry.path.file_name().map(|n| n.to_string_lossy().into_owned());
+        let format = match &args.format {
+            Some(f) => Some(f.clone().into()),
+            None => Format::detect(filename.as_deref(), &input),
+        };
+        if format != Some(Format::Csv) {
+            continue;
+        }
+        let name = entry.relative.display().to_string();
+        files.push(DatasetFile::read(&name, &input).map_err(miette::Report::new)?);
+    }
+
+    // A single directory argument names the dataset
+    let title = match &args.files[..] {
+        [dir] if dir.is_dir() => dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "Dataset".to_string()),
+        _ => "Dataset".to_string(),
+    };
+
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    dataset::write_dataset_as_markdown(
+        &mut writer,
+        &title,
+        &files,
+        args.dataset_preview,
+        args.sanitize_cells,
+    )
+    .map_err(miette::Report::new)?;
+    writer.flush().into_diagnostic()
+}
+
+#[cfg(not(feature = "csv"))]
+fn run_dataset(_args: &Args, _inputs: &[Input]) -> miette::Result<()> {
+    Err(miette::Report::new(Error::FeatureDisabled("csv".into())))
+}
+
+/// Write one album document describing every audio file among `inputs` to
+/// stdout.
+#[cfg(feature = "audio")]
+fn run_album(args: &Args, inputs: &[Input]) -> miette::Result<()> {
+    let mut tracks = Vec::new();
+    for entry in inputs {
+        let input = fs::read(&entry.path).map_err(|e| miette::Report::new(Error::Io(e)))?;
+        let filename = entry
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned());
+        let format = match &args.format {
+            Some(f) => Some(f.clone().into()),
+            None => Format::detect(filename.as_deref(), &input),
+        };
+        if format != Some(Format::Audio) {
+            continue;
+        }
+        let name = entry.relative.display().to_string();
+        tracks.push(AlbumTrack::read(&name, &input).map_err(miette::Report::new)?);
+    }
+
+    // Without a shared album tag, a single directory argument names the album
+    let title = match &args.files[..] {
+        [dir] if dir.is_dir() => dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "Album".to_string()),
+        _ => "Album".to_string(),
+    };
+
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    album::write_album_as_markdown(&mut writer, &title, &tracks).map_err(miette::Report::new)?;
+    writer.flush().into_diagnostic()
+}
+
+#[cfg(not(feature = "audio"))]
+fn run_album(_args: &Args, _inputs: &[Input]) -> miette::Result<()> {
+    Err(miette::Report::new(Error::FeatureDisabled("audio".into())))
+}
+
+/// Write one contact sheet showing every image among `inputs` to stdout.
+/// Thumbnails are inlined as data URIs unless `--extract-media` is given.
+#[cfg(feature = "image")]
+fn run_contact_sheet(
+    args: &Args,
+    options: &ConvertOptions,
+    inputs: &[Input],
+) -> miette::Result<()> {
+    let mut images = Vec::new();
+    for entry in inputs {
+        let input = fs::read(&entry.path).map_err(|e| miette::Report::new(Error::Io(e)))?;
+        let filename = entry
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned());
+        let format = match &args.format {
+            Some(f) => Some(f.clone().into()),
+            None => Format::detect(filename.as_deref(), &input),
+        };
+        if format != Some(Format::Image) {
+            continue;
+        }
+        let name = entry.relative.display().to_string();
+        images.push(SheetImage::read(&name, &input, &options.media).map_err(miette::Report::new)?);
+    }
+
+    // A single directory argument names the contact sheet
+    let title = match &args.files[..] {
+        [dir] if dir.is_dir() => dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "Contact Sheet".to_string()),
+        _ => "Contact Sheet".to_string(),
+    };
+
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    contact_sheet::write_contact_sheet_as_markdown(&mut writer, &title, &images)
+        .map_err(miette::Report::new)?;
+    writer.flush().into_diagnostic()
+}
+
+#[cfg(not(feature = "image"))]
+fn run_contact_sheet(
+    _args: &Args,
+    _options: &ConvertOptions,
+    _inputs: &[Input],
+) -> miette::Result<()> {
+    Err(miette::Report::new(Error::FeatureDisabled("image".into())))
+}
+
+/// The JSON object written to stderr for a failure with `--errors json`.
+/// Failures outside the converters, such as unreadable input files, have
+/// the code `error`.
+fn error_json(report: &miette::Report) -> serde_json::Value {
+    let mut json = match report.downcast_ref::<Error>() {
+        Some(error) => error.to_json(),
+        None => serde_json::json!({
+            "code": "error",
+            "message": report.to_string(),
+            "format": null,
+            "entry": null,
+            "offset": null,
+        }),
+    };
+    // Keep the CLI's own wording, such as the hint to use --format
+    json["message"] = report.to_string().into();
+    json
+}
+
 fn main() -> miette::Result<()> {
     let args = Args::parse();
+    #[cfg(feature = "sandbox")]
+    if std::env::var_os(sandbox::CHILD_ENV).is_some() {
+        if let Err(report) = run_sandbox_child(&args) {
+            eprintln!("{}", error_json(&report));
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let errors = args.errors.clone();
+    match run(args) {
+        Err(report) if errors == ErrorsArg::Json => {
+            eprintln!("{}", error_json(&report));
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
+
+fn run(args: Args) -> miette::Result<()> {
+    if let Some(Command::Diff { old, new }) = &args.command {
+        return run_diff(old, new, args.format.as_ref());
+    }
+    let options = args.convert_options()?;
+    let mut report = Report {
+        counts_json: args.counts == Some(CountsArg::Json),
+        manifest: args.manifest.as_ref().map(|_| Vec::new()),
+        stats: args.stats.as_ref().map(|_| Vec::new()),
+        index: args
+            .index
+            .as_ref()
+            .map(|path| Index::new(path, args.index_keywords.unwrap_or(0))),
+    };
 
-    if args.files.is_empty() {
+    // A crawl's only input is its start URL
+    let inputs = if args.crawl {
+        Vec::new()
+    } else {
+        collect_inputs(&args.files).map_err(miette::Report::new)?
+    };
+    let mut duplicates = (!args.keep_duplicates).then(Duplicates::default);
+
+    if args.dataset {
+        return run_dataset(&args, &inputs);
+    }
+    if args.album {
+        return run_album(&args, &inputs);
+    }
+    if args.contact_sheet {
+        return run_contact_sheet(&args, &options, &inputs);
+    }
+
+    if args.crawl {
+        run_crawl(&args, &options, &mut report)?;
+    } else if args.files.is_empty() {
         // stdin mode
         if io::stdin().is_terminal() {
             return Err(miette::miette!(
@@ -158,63 +1672,175 @@ fn main() -> miette::Result<()> {
 
         let stdout = io::stdout();
         let mut writer = BufWriter::new(stdout.lock());
-        convert_one(&buf, None, args.format.as_ref(), args.to.as_ref(), &mut writer)?;
+        let converter = make_converter(
+            &buf,
+            None,
+            args.format.as_ref(),
+            args.to.as_ref(),
+            &options,
+            args.sandbox,
+        )?;
+        run_converter(converter.as_ref(), &buf, None, None, 1, &mut report, &mut writer)?;
         writer.flush().into_diagnostic()?;
     } else if let Some(ref output_dir) = args.output_dir {
         // Output each file as individual output file
         fs::create_dir_all(output_dir).into_diagnostic()?;
 
-        for path in &args.files {
-            let input = fs::read(path).into_diagnostic()?;
+        // Output extension of each converted input, which its duplicates share
+        let mut extensions: HashMap<PathBuf, &'static str> = HashMap::new();
+        for entry in &inputs {
+            let path = &entry.path;
+            let input = fs::read(path).map_err(|e| miette::Report::new(Error::Io(e)))?;
             let filename = path.file_name().map(|n| n.to_string_lossy().into_owned());
 
-            let stem = path
-                .file_stem()
-                .map(|s| s.to_string_lossy().into_owned())
-                .unwrap_or_else(|| "output".to_string());
+            if let Some(original) = duplicates.as_mut().and_then(|d| d.check(path, &input)) {
+                let ext = extensions.get(original).copied().unwrap_or("md");
+                let out_path = output_dir.join(entry.relative.with_extension(ext));
+                if let Some(parent) = out_path.parent() {
+                    fs::create_dir_all(parent).into_diagnostic()?;
+                }
+                fs::write(&out_path, duplicate_note(original)).into_diagnostic()?;
+                report.record_duplicate(path, Some(&out_path), original);
+                continue;
+            }
 
-            let detected = if let Some(f) = args.format.as_ref() {
-                f.clone().into()
-            } else {
-                Format::detect(filename.as_deref(), &input).ok_or_else(|| {
-                    miette::miette!("Could not detect file format. Use --format to specify.")
-                })?
-            };
-            let format = resolve_output_format(detected, args.to.as_ref())?;
-
-            let converter =
-                mq_conv::formats::get_converter(format).map_err(|e| miette::miette!("{e}"))?;
+            let out_parent = output_dir.join(&entry.relative).parent().map(Path::to_path_buf);
+            let converter = make_converter(
+                &input,
+                filename.as_deref(),
+                args.format.as_ref(),
+                args.to.as_ref(),
+                &file_options(&args, &options, path, out_parent.as_deref()),
+                args.sandbox,
+            )?;
             let ext = converter.output_extension();
-            let out_path = output_dir.join(format!("{stem}.{ext}"));
+            extensions.insert(path.clone(), ext);
+            let out_path = output_dir.join(entry.relative.with_extension(ext));
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent).into_diagnostic()?;
+            }
+
+            if args.split_sheets && converter.format_name() == "excel" {
+                let mut buf = Vec::new();
+                run_converter(
+                    converter.as_ref(),
+                    &input,
+                    Some(path),
+                    Some(&out_path),
+                    1,
+                    &mut report,
+                    &mut buf,
+                )?;
+                let markdown = String::from_utf8_lossy(&buf);
+                write_sheets(&out_path, &markdown, split::split(&markdown, 1))?;
+                continue;
+            }
+
+            // Page and chapter splitting only apply to PDFs; other inputs fall
+            // back to --split-by-heading, if given
+            let split_pdf = args.split_pdf.filter(|_| converter.format_name() == "pdf");
+            let splitter: Option<Splitter> =
+                match (split_pdf, args.split_by_heading) {
+                    (Some(SplitPdfArg::Pages), _) => Some(Box::new(split::split_pages)),
+                    (Some(SplitPdfArg::Chapters), _) => Some(Box::new(split::split_chapters)),
+                    (None, Some(level)) => Some(Box::new(move |md| split::split(md, level.level()))),
+                    (None, None) => None,
+                };
+            if let Some(splitter) = splitter {
+                if ext != "md" {
+                    return Err(miette::miette!(
+                        "--split-by-heading requires Markdown output, but {} produces .{ext}",
+                        path.display()
+                    ));
+                }
+                let mut buf = Vec::new();
+                run_converter(
+                    converter.as_ref(),
+                    &input,
+                    Some(path),
+                    Some(&out_path),
+                    1,
+                    &mut report,
+                    &mut buf,
+                )?;
+                let markdown = String::from_utf8_lossy(&buf);
+                write_sections(&out_path, &markdown, splitter(&markdown))?;
+                continue;
+            }
 
             let file = fs::File::create(&out_path).into_diagnostic()?;
             let mut writer = BufWriter::new(file);
-            converter
-                .convert(&input, &mut writer)
-                .map_err(|e| miette::miette!("{e}"))?;
+            run_converter(
+                converter.as_ref(),
+                &input,
+                Some(path),
+                Some(&out_path),
+                1,
+                &mut report,
+                &mut writer,
+            )?;
             writer.flush().into_diagnostic()?;
         }
     } else {
         // Output all to stdout
         let stdout = io::stdout();
         let mut writer = BufWriter::new(stdout.lock());
+        let mut line = 1;
 
-        for (i, path) in args.files.iter().enumerate() {
+        for (i, entry) in inputs.iter().enumerate() {
+            let path = &entry.path;
             if i > 0 {
                 writeln!(writer, "\n---\n").into_diagnostic()?;
+                line += 3;
+            }
+            let input = fs::read(path).map_err(|e| miette::Report::new(Error::Io(e)))?;
+            if let Some(original) = duplicates.as_mut().and_then(|d| d.check(path, &input)) {
+                write!(writer, "{}", duplicate_note(original)).into_diagnostic()?;
+                report.record_duplicate(path, None, original);
+                line += 1;
+                continue;
             }
-            let input = fs::read(path).into_diagnostic()?;
             let filename = path.file_name().map(|n| n.to_string_lossy().into_owned());
-            convert_one(
+            let converter = make_converter(
                 &input,
                 filename.as_deref(),
                 args.format.as_ref(),
                 args.to.as_ref(),
+                &file_options(&args, &options, path, None),
+                args.sandbox,
+            )?;
+            line += run_converter(
+                converter.as_ref(),
+                &input,
+                Some(path),
+                None,
+                line,
+                &mut report,
                 &mut writer,
             )?;
         }
         writer.flush().into_diagnostic()?;
     }
 
+    if let (Some(path), Some(entries)) = (&args.manifest, report.manifest) {
+        let manifest = serde_json::json!({ "inputs": entries });
+        let json = serde_json::to_string_pretty(&manifest).into_diagnostic()?;
+        fs::write(path, json + "\n").into_diagnostic()?;
+    }
+
+    if let (Some(path), Some(entries)) = (&args.stats, report.stats) {
+        let stats = serde_json::json!({ "files": entries });
+        let json = serde_json::to_string_pretty(&stats).into_diagnostic()?;
+        fs::write(path, json + "\n").into_diagnostic()?;
+    }
+
+    if let (Some(path), Some(index)) = (&args.index, report.index) {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| miette::miette!("{}: {e}", parent.display()))?;
+        }
+        fs::write(path, index.to_markdown())
+            .map_err(|e| miette::miette!("{}: {e}", path.display()))?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,318 @@
+use serde_json::{Value, json};
+
+/// Links, media references and tables found in converted Markdown, with the
+/// 1-based line each starts on.
+#[derive(Debug, Default, PartialEq)]
+pub struct Outline {
+    pub links: Vec<Link>,
+    pub media: Vec<Media>,
+    pub tables: Vec<Table>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Link {
+    pub text: String,
+    pub url: String,
+    pub line: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Media {
+    pub alt: String,
+    pub path: String,
+    pub line: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Table {
+    pub line: usize,
+    /// Body rows, excluding the header.
+    pub rows: usize,
+    pub columns: usize,
+    /// Text of the nearest heading above the table.
+    pub heading: Option<String>,
+}
+
+impl Outline {
+    /// Scan Markdown, skipping fenced code blocks. `first_line` is the line
+    /// number of the document's first line in the output it was written to.
+    pub fn scan(markdown: &str, first_line: usize) -> Self {
+        let mut outline = Outline::default();
+        let lines: Vec<&str> = markdown.lines().collect();
+        let mut heading: Option<String> = None;
+        let mut in_code = false;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let number = first_line + i;
+            let trimmed = line.trim_start();
+
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code = !in_code;
+                i += 1;
+                continue;
+            }
+            if in_code {
+                i += 1;
+                continue;
+            }
+
+            if trimmed.starts_with('#') {
+                let text = trimmed.trim_start_matches('#');
+                if text.is_empty() || text.starts_with(' ') {
+                    heading = Some(text.trim().to_string());
+                }
+            }
+
+            if trimmed.starts_with('|') && lines.get(i + 1).is_some_and(|l| is_table_rule(l)) {
+                let columns = table_cells(trimmed);
+                let mut end = i + 2;
+                while end < lines.len() && lines[end].trim_start().starts_with('|') {
+                    scan_inline(lines[end], first_line + end, &mut outline);
+                    end += 1;
+                }
+                scan_inline(line, number, &mut outline);
+                outline.tables.push(Table {
+                    line: number,
+                    rows: end - i - 2,
+                    columns,
+                    heading: heading.clone(),
+                });
+                i = end;
+                continue;
+            }
+
+            scan_inline(line, number, &mut outline);
+            i += 1;
+        }
+
+        outline
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "links": self.links.iter().map(|l| json!({
+                "text": l.text,
+                "url": l.url,
+                "line": l.line,
+            })).collect::<Vec<_>>(),
+            "media": self.media.iter().map(|m| json!({
+                "path": m.path,
+                "alt": m.alt,
+                "line": m.line,
+            })).collect::<Vec<_>>(),
+            "tables": self.tables.iter().map(|t| json!({
+                "line": t.line,
+                "rows": t.rows,
+                "columns": t.columns,
+                "heading": t.heading,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn is_table_rule(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with('|')
+        && line.contains('-')
+        && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+fn table_cells(line: &str) -> usize {
+    let inner = line.trim().trim_start_matches('|');
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    let mut cells = 1;
+    let mut escaped = false;
+    for c in inner.chars() {
+        match c {
+            '\\' if !escaped => {
+                escaped = true;
+                continue;
+            }
+            '|' if !escaped => cells += 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    cells
+}
+
+/// Record `[text](url)`, `![alt](path)` and `<scheme://...>` on one line,
+/// including images nested in link text.
+fn scan_inline(text: &str, line: usize, outline: &mut Outline) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '`' => {
+                // Skip inline code spans
+                let end = chars[i + 1..].iter().position(|&c| c == '`');
+                i = end.map_or(chars.len(), |e| i + e + 2);
+            }
+            '<' => {
+                let end = chars[i + 1..].iter().position(|&c| c == '>' || c == ' ');
+                match end {
+                    Some(e) if chars[i + 1 + e] == '>' => {
+                        let url: String = chars[i + 1..i + 1 + e].iter().collect();
+                        if url.contains("://") || url.starts_with("mailto:") {
+                            outline.links.push(Link {
+                                text: url.clone(),
+                                url,
+                                line,
+                            });
+                        }
+                        i += e + 2;
+                    }
+                    _ => i += 1,
+                }
+            }
+            '[' => {
+                let is_image = i > 0 && chars[i - 1] == '!' && (i < 2 || chars[i - 2] != '\\');
+                let Some((label, target, end)) = parse_link(&chars, i) else {
+                    i += 1;
+                    continue;
+                };
+                if is_image {
+                    outline.media.push(Media {
+                        alt: label,
+                        path: target,
+                        line,
+                    });
+                } else {
+                    scan_inline(&label, line, outline);
+                    outline.links.push(Link {
+                        text: label,
+                        url: target,
+                        line,
+                    });
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Parse `[label](target)` starting at the `[`, returning the label, target
+/// and the index just past the closing parenthesis.
+fn parse_link(chars: &[char], start: usize) -> Option<(String, String, usize)> {
+    let mut depth = 0;
+    let mut close = None;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let close = close?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut i = close + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    let label: String = chars[start + 1..close].iter().collect();
+                    let target: String = chars[close + 2..i].iter().collect();
+                    // Drop an optional title: [text](url "title")
+                    let target = target.split_whitespace().next().unwrap_or_default();
+                    return Some((label, target.to_string(), i + 1));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_scan_links_media_and_tables() {
+        let markdown = "# Report\n\
+            \n\
+            See [the docs](https://example.com/docs \"Docs\") or <https://example.org>.\n\
+            \n\
+            [![photo](media/photo.thumb.png)](media/photo.png)\n\
+            \n\
+            ## Data\n\
+            \n\
+            | a | b |\n\
+            |---|---|\n\
+            | 1 | [x](#x) |\n\
+            | 2 | `[not](a link)` |\n\
+            \n\
+            ```\n\
+            [code](ignored)\n\
+            ```\n";
+        let outline = Outline::scan(markdown, 10);
+        assert_eq!(
+            outline,
+            Outline {
+                links: vec![
+                    Link {
+                        text: "the docs".into(),
+                        url: "https://example.com/docs".into(),
+                        line: 12,
+                    },
+                    Link {
+                        text: "https://example.org".into(),
+                        url: "https://example.org".into(),
+                        line: 12,
+                    },
+                    Link {
+                        text: "![photo](media/photo.thumb.png)".into(),
+                        url: "media/photo.png".into(),
+                        line: 14,
+                    },
+                    Link {
+                        text: "x".into(),
+                        url: "#x".into(),
+                        line: 20,
+                    },
+                ],
+                media: vec![Media {
+                    alt: "photo".into(),
+                    path: "media/photo.thumb.png".into(),
+                    line: 14,
+                }],
+                tables: vec![Table {
+                    line: 18,
+                    rows: 2,
+                    columns: 2,
+                    heading: Some("Data".into()),
+                }],
+            }
+        );
+    }
+
+    #[rstest]
+    #[case::plain("| a | b | c |", 3)]
+    #[case::escaped_pipe("| a \\| b | c |", 2)]
+    fn test_table_cells(#[case] line: &str, #[case] expected: usize) {
+        assert_eq!(table_cells(line), expected);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+use crate::index::{absolute, relative_link};
+
+/// Media written during this run, so identical images (a logo repeated on
+/// every slide, or shared by every document in a batch) are stored once.
+#[derive(Default)]
+struct Written {
+    by_content: HashMap<(PathBuf, [u8; 32]), PathBuf>,
+}
+
+static WRITTEN: LazyLock<Mutex<Written>> = LazyLock::new(Default::default);
+
+/// Write extracted media into `dir` under `name`, creating the directory on
+/// first use. Returns the path to reference from the generated Markdown,
+/// relative to `link_base` when the output is written there.
+///
+/// Bytes already written to `dir` are not written again; the existing file
+/// is referenced instead. A name already taken by a file with different
+/// content, whether written now or by an earlier run, gets a numeric suffix
+/// rather than overwriting it.
+pub fn write_media(
+    dir: &Path,
+    name: &str,
+    bytes: &[u8],
+    link_base: Option<&Path>,
+) -> Result<String> {
+    let hash: [u8; 32] = Sha256::digest(bytes).into();
+    let key = (dir.to_path_buf(), hash);
+    let mut written = WRITTEN.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(path) = written.by_content.get(&key)
+        && path.exists()
+    {
+        return Ok(link_target(path, link_base));
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let mut path = dir.join(name);
+    let mut n = 2;
+    while path.exists() && !std::fs::read(&path).is_ok_and(|existing| existing == bytes) {
+        path = dir.join(numbered(name, n));
+        n += 1;
+    }
+    if !path.exists() {
+        std::fs::write(&path, bytes)?;
+    }
+    written.by_content.insert(key, path.clone());
+    Ok(link_target(&path, link_base))
+}
+
+/// `logo.png` -> `logo-2.png`, keeping compound suffixes like `.thumb.png`.
+fn numbered(name: &str, n: usize) -> String {
+    match name.find('.') {
+        Some(i) if i > 0 => format!("{}-{n}{}", &name[..i], &name[i..]),
+        _ => format!("{name}-{n}"),
+    }
+}
+
+/// The stem of the input file name, used to name media extracted from it.
+pub fn input_stem(input_name: Option<&str>, fallback: &str) -> String {
+    input_name
+        .and_then(|name| Path::new(name).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Inline `bytes` as a `data:` URI, with the MIME type guessed from the
+/// extension of `name`.
+#[cfg(any(
+    feature = "word",
+    feature = "powerpoint",
+    feature = "epub",
+    feature = "image"
+))]
+pub fn data_uri(name: &str, bytes: &[u8]) -> String {
+    use base64::Engine;
+
+    let extension = Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "emf" => "image/emf",
+        "wmf" => "image/wmf",
+        _ => "application/octet-stream",
+    };
+    let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+    format!("data:{mime};base64,{data}")
+}
+
+/// Markdown for an image, or a placeholder naming it when there is nothing to
+/// link to (media neither extracted nor embedded, or an unresolved relationship).
+#[cfg(any(feature = "word", feature = "powerpoint"))]
+pub fn image_markdown(alt: &str, target: &str) -> String {
+    let alt = alt.replace('[', "\\[").replace(']', "\\]");
+    match (target.is_empty(), alt.is_empty()) {
+        (false, _) => format!("![{alt}]({target})"),
+        (true, true) => "*[Image]*".to_string(),
+        (true, false) => format!("*[Image: {alt}]*"),
+    }
+}
+
+/// Render a path as a Markdown link target from `base`, using `/`
+/// separators and escaping characters that would end the link.
+fn link_target(path: &Path, base: Option<&Path>) -> String {
+    let link = match base {
+        Some(base) => relative_link(&absolute(base), &absolute(path)),
+        None => path.to_string_lossy().replace('\\', "/"),
+    };
+    link.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_write_media_deduplicates() {
+        let dir = std::env::temp_dir().join(format!("mq-conv-dedup-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let logo = write_media(&dir, "image1.png", b"logo", None).unwrap();
+        let again = write_media(&dir, "image7.png", b"logo", None).unwrap();
+        let other = write_media(&dir, "image1.png", b"photo", None).unwrap();
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(again, logo);
+        assert!(logo.ends_with("/image1.png"));
+        assert!(other.ends_with("/image1-2.png"));
+        assert_eq!(files, vec!["image1-2.png", "image1.png"]);
+    }
+
+    #[rstest]
+    fn test_write_media_keeps_existing_files() {
+        let dir = std::env::temp_dir().join(format!("mq-conv-existing-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("chart.png"), b"mine").unwrap();
+        std::fs::write(dir.join("logo.png"), b"logo").unwrap();
+
+        let chart = write_media(&dir, "chart.png", b"chart", None).unwrap();
+        let logo = write_media(&dir, "logo.png", b"logo", None).unwrap();
+        let mine = std::fs::read(dir.join("chart.png")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(chart.ends_with("/chart-2.png"), "{chart}");
+        assert!(logo.ends_with("/logo.png"), "{logo}");
+        assert_eq!(mine, b"mine");
+    }
+
+    #[rstest]
+    #[case::below("out", "out/media", "media/deck-image1.png")]
+    #[case::beside("out/docs", "out/media", "../media/deck-image1.png")]
+    fn test_write_media_links_from_output_dir(
+        #[case] base: &str,
+        #[case] media: &str,
+        #[case] expected: &str,
+    ) {
+        let root = std::env::temp_dir().join(format!(
+            "mq-conv-link-base-{}-{}",
+            std::process::id(),
+            base.replace('/', "-")
+        ));
+        let (base, dir) = (root.join(base), root.join(media));
+        std::fs::create_dir_all(&base).unwrap();
+        let link = write_media(&dir, "deck-image1.png", b"image", Some(&base)).unwrap();
+        let resolves = base.join(&link).exists();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(link, expected);
+        assert!(resolves);
+    }
+
+    #[rstest]
+    #[case::simple("logo.png", "logo-2.png")]
+    #[case::compound("photo.thumb.png", "photo-2.thumb.png")]
+    #[case::no_extension("README", "README-2")]
+    fn test_numbered(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(numbered(name, 2), expected);
+    }
+}
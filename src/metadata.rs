@@ -0,0 +1,340 @@
+use std::io::Write;
+
+use crate::converter::Converter;
+use crate::error::{Error, Result};
+use crate::options::MetadataOptions;
+
+/// Wraps a Markdown-producing converter and records document-level metadata
+/// derived from its output in YAML front matter.
+pub struct MetadataConverter {
+    inner: Box<dyn Converter>,
+    options: MetadataOptions,
+}
+
+/// Wrap `inner` when any metadata option is enabled and it produces Markdown;
+/// otherwise return it unchanged.
+pub fn wrap(inner: Box<dyn Converter>, options: &MetadataOptions) -> Result<Box<dyn Converter>> {
+    if !(options.detect_language || options.counts) || inner.output_extension() != "md" {
+        return Ok(inner);
+    }
+    #[cfg(not(feature = "language"))]
+    if options.detect_language {
+        return Err(Error::FeatureDisabled("language".into()));
+    }
+    Ok(Box::new(MetadataConverter {
+        inner,
+        options: options.clone(),
+    }))
+}
+
+impl Converter for MetadataConverter {
+    fn format_name(&self) -> &'static str {
+        self.inner.format_name()
+    }
+
+    fn output_extension(&self) -> &'static str {
+        self.inner.output_extension()
+    }
+
+    fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
+        let mut buf = Vec::new();
+        self.inner.convert(input, &mut buf)?;
+        let markdown = String::from_utf8(buf).map_err(|e| Error::Conversion {
+            format: self.inner.format_name(),
+            message: format!("Output is not valid UTF-8: {e}"),
+        })?;
+
+        let mut fields: Vec<(&str, String)> = Vec::new();
+        if self.options.detect_language {
+            fields.extend(language_fields(&markdown));
+        }
+
+        writer.write_all(add_front_matter(&markdown, &fields).as_bytes())?;
+
+        if self.options.counts {
+            let counts = Counts::of(&markdown);
+            if !markdown.is_empty() && !markdown.ends_with("\n\n") {
+                writeln!(writer)?;
+            }
+            write!(writer, "{COUNTS_APPENDIX}")?;
+            writeln!(writer, "| Words | {} |", counts.words)?;
+            writeln!(writer, "| Characters | {} |", counts.characters)?;
+            writeln!(writer, "| Estimated tokens | {} |", counts.tokens)?;
+        }
+        Ok(())
+    }
+}
+
+/// Heading and table header of the appendix written by `--counts`.
+const COUNTS_APPENDIX: &str = "## Counts\n\n| Metric | Value |\n|--------|-------|\n";
+
+/// `markdown` without the appendix written by `--counts`, if it ends with one.
+pub fn without_counts(markdown: &str) -> &str {
+    match markdown.rfind(COUNTS_APPENDIX) {
+        Some(pos)
+            if (pos == 0 || markdown[..pos].ends_with('\n'))
+                && markdown[pos + COUNTS_APPENDIX.len()..]
+                    .lines()
+                    .all(|line| line.starts_with("| ")) =>
+        {
+            &markdown[..pos]
+        }
+        _ => markdown,
+    }
+}
+
+/// Size of a converted document, for budgeting embeddings and context windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    /// Whitespace-separated words, counting each CJK character as a word.
+    /// Link targets and Markdown markup such as `#`, `|` or `---` are not
+    /// words.
+    pub words: usize,
+    /// Characters excluding line breaks.
+    pub characters: usize,
+    /// Rough LLM token estimate: about four characters per token for
+    /// alphabetic scripts and one token per CJK character.
+    pub tokens: usize,
+}
+
+impl Counts {
+    pub fn of(text: &str) -> Self {
+        let characters = text.chars().filter(|c| *c != '\n' && *c != '\r').count();
+        let cjk = text.chars().filter(|c| is_cjk(*c)).count();
+        let other = text.chars().filter(|c| !c.is_whitespace()).count() - cjk;
+        let spaces = text.chars().filter(|c| *c == ' ').count();
+        Self {
+            words: count_words(text),
+            characters,
+            tokens: cjk + (other + spaces).div_ceil(4),
+        }
+    }
+}
+
+/// Words of the prose in `markdown`: link targets are dropped and tokens
+/// without a letter or digit skipped.
+fn count_words(markdown: &str) -> usize {
+    let mut body = markdown;
+    let mut text = String::with_capacity(body.len());
+    while let Some(start) = body.find("](") {
+        text.push_str(&body[..start]);
+        body = &body[start + 2..];
+        body = body.find(')').map_or("", |end| &body[end + 1..]);
+    }
+    text.push_str(body);
+    text.split_whitespace()
+        .filter(|token| token.chars().any(char::is_alphanumeric))
+        .map(token_words)
+        .sum()
+}
+
+/// One word for the token, plus one for each CJK character in it, which are
+/// written without spaces between words.
+fn token_words(token: &str) -> usize {
+    let mut words = 0;
+    let mut in_word = false;
+    for c in token.chars() {
+        if is_cjk(c) {
+            words += 1;
+            in_word = false;
+        } else if !in_word {
+            words += 1;
+            in_word = true;
+        }
+    }
+    words
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul syllables
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FFFF // CJK Extensions B-F
+    )
+}
+
+/// Insert `fields` into the document's leading YAML front matter, creating
+/// the block when the document has none.
+pub fn add_front_matter(markdown: &str, fields: &[(&str, String)]) -> String {
+    if fields.is_empty() {
+        return markdown.to_string();
+    }
+
+    let lines: String = fields
+        .iter()
+        .map(|(key, value)| format!("{key}: {}\n", yaml_scalar(value)))
+        .collect();
+
+    if let Some(rest) = markdown.strip_prefix("---\n")
+        && let Some(end) = rest
+            .find("\n---\n")
+            .map(|i| i + 1)
+            .or_else(|| rest.starts_with("---\n").then_some(0))
+    {
+        return format!("---\n{}{lines}{}", &rest[..end], &rest[end..]);
+    }
+
+    format!("---\n{lines}---\n\n{markdown}")
+}
+
+/// Quote a value when plain YAML would misread it.
+pub(crate) fn yaml_scalar(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value.starts_with(|c: char| c.is_whitespace() || "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !value.ends_with(char::is_whitespace)
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.contains('\n');
+    if plain {
+        value.to_string()
+    } else {
+        format!(
+            "\"{}\"",
+            value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+        )
+    }
+}
+
+fn language_fields(markdown: &str) -> Vec<(&'static str, String)> {
+    #[cfg(feature = "language")]
+    {
+        detect_language(markdown)
+            .map(|lang| {
+                vec![
+                    ("language", lang.code().to_string()),
+                    ("language_name", lang.eng_name().to_string()),
+                ]
+            })
+            .unwrap_or_default()
+    }
+    #[cfg(not(feature = "language"))]
+    {
+        let _ = markdown;
+        Vec::new()
+    }
+}
+
+/// Detect the dominant language of the prose in a Markdown document,
+/// ignoring code, link targets and table rules.
+#[cfg(feature = "language")]
+fn detect_language(markdown: &str) -> Option<whatlang::Lang> {
+    let mut text = String::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code || trimmed.chars().all(|c| "|-: ".contains(c)) {
+            continue;
+        }
+        for word in trimmed.split_whitespace() {
+            if !word.contains("://") && !word.contains("](") {
+                text.push_str(word);
+                text.push(' ');
+            }
+        }
+        text.push('\n');
+    }
+
+    whatlang::detect(&text)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::no_fields("# Title\n", &[], "# Title\n")]
+    #[case::new_block(
+        "# Title\n",
+        &[("language", "eng")],
+        "---\nlanguage: eng\n---\n\n# Title\n"
+    )]
+    #[case::existing_block(
+        "---\ntitle: Book\n---\n\n# Title\n",
+        &[("language", "eng")],
+        "---\ntitle: Book\nlanguage: eng\n---\n\n# Title\n"
+    )]
+    #[case::quoted_value(
+        "text\n",
+        &[("title", "a: b")],
+        "---\ntitle: \"a: b\"\n---\n\ntext\n"
+    )]
+    fn test_add_front_matter(
+        #[case] markdown: &str,
+        #[case] fields: &[(&str, &str)],
+        #[case] expected: &str,
+    ) {
+        let fields: Vec<(&str, String)> = fields.iter().map(|(k, v)| (*k, v.to_string())).collect();
+        assert_eq!(add_front_matter(markdown, &fields), expected);
+    }
+
+    #[rstest]
+    #[case::empty("", Counts { words: 0, characters: 0, tokens: 0 })]
+    #[case::english(
+        "# Hello world\n\nOne two.\n",
+        Counts { words: 4, characters: 21, tokens: 6 }
+    )]
+    #[case::cjk("日本語の文章", Counts { words: 6, characters: 6, tokens: 6 })]
+    #[case::markup(
+        "| A | B |\n|---|---|\n\n---\n\n- See [the docs](https://example.com/a b)\n",
+        Counts { words: 5, characters: 62, tokens: 16 }
+    )]
+    fn test_counts(#[case] text: &str, #[case] expected: Counts) {
+        assert_eq!(Counts::of(text), expected);
+    }
+
+    #[rstest]
+    fn test_counts_appended() {
+        struct Echo;
+        impl Converter for Echo {
+            fn format_name(&self) -> &'static str {
+                "echo"
+            }
+            fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
+                writer.write_all(input)?;
+                Ok(())
+            }
+        }
+
+        let options = MetadataOptions {
+            counts: true,
+            ..Default::default()
+        };
+        let converter = wrap(Box::new(Echo), &options).unwrap();
+        let mut output = Vec::new();
+        converter.convert(b"# Title\n\nSome text.\n", &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Title\n\nSome text.\n\n## Counts\n\n| Metric | Value |\n|--------|-------|\n\
+             | Words | 3 |\n| Characters | 17 |\n| Estimated tokens | 5 |\n"
+        );
+    }
+
+    #[cfg(feature = "language")]
+    #[rstest]
+    #[case::english(
+        "# Report\n\nThe committee reviewed the annual budget and agreed to fund the new library building next spring.\n",
+        Some("eng")
+    )]
+    #[case::german(
+        "# Bericht\n\nDer Ausschuss hat den Jahreshaushalt geprüft und beschlossen, das neue Bibliotheksgebäude im nächsten Frühjahr zu finanzieren.\n",
+        Some("deu")
+    )]
+    #[case::code_only("```\nfn main() {}\n```\n", None)]
+    fn test_detect_language(#[case] markdown: &str, #[case] expected: Option<&str>) {
+        assert_eq!(detect_language(markdown).map(|l| l.code()), expected);
+    }
+}
@@ -0,0 +1,599 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::formats::structured::schema::Schema;
+
+/// Options that tune how converters render their output.
+///
+/// Options are grouped by the format they apply to; converters that do not
+/// take any options ignore them entirely.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// File name of the input being converted, used to name extracted media.
+    pub input_name: Option<String>,
+    /// Convert what can be converted when part of a document, such as one
+    /// slide or sheet, is corrupt: the failed part is replaced by a warning
+    /// note instead of failing the whole conversion.
+    pub best_effort: bool,
+    /// Neutralize table cells that read as spreadsheet formulas or contain
+    /// HTML, in the CSV, Excel and SQLite tables.
+    pub sanitize_cells: bool,
+    pub metadata: MetadataOptions,
+    pub redact: RedactOptions,
+    pub media: MediaOptions,
+    pub describe: DescribeOptions,
+    pub archive: ArchiveOptions,
+    pub word: WordOptions,
+    pub excel: ExcelOptions,
+    pub pdf: PdfOptions,
+    pub powerpoint: PowerPointOptions,
+    pub epub: EpubOptions,
+    pub csv: CsvOptions,
+    pub html: HtmlOptions,
+    pub structured: StructuredOptions,
+    pub sqlite: SqliteOptions,
+    pub transcribe: TranscribeOptions,
+    pub video: VideoOptions,
+    pub xml: XmlOptions,
+}
+
+/// Document-level metadata added to Markdown output as YAML front matter.
+#[derive(Debug, Clone, Default)]
+pub struct MetadataOptions {
+    /// Detect the dominant language of the converted text.
+    pub detect_language: bool,
+    /// Append word, character and estimated token counts.
+    pub counts: bool,
+}
+
+/// Sensitive values masked in Markdown output. Nothing is masked unless a
+/// built-in kind or a pattern is listed.
+#[derive(Debug, Clone, Default)]
+pub struct RedactOptions {
+    pub builtins: Vec<Sensitive>,
+    pub patterns: Vec<RedactPattern>,
+}
+
+impl RedactOptions {
+    /// Every built-in kind and no custom patterns.
+    pub fn builtin() -> Self {
+        Self {
+            builtins: Sensitive::ALL.to_vec(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Parse a JSON object such as
+    /// `{"builtins": ["email", "ssn"], "patterns": [{"pattern": "EMP-\\d{6}"}]}`.
+    /// Every built-in kind is masked when `builtins` is omitted.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let invalid = |message: &str| format!("Invalid redaction config: {message}");
+        let config: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| invalid(&e.to_string()))?;
+        let config = config
+            .as_object()
+            .ok_or_else(|| invalid("expected an object"))?;
+        if let Some(key) = config.keys().find(|k| *k != "builtins" && *k != "patterns") {
+            return Err(invalid(&format!("unknown field \"{key}\"")));
+        }
+        let builtins = match config.get("builtins") {
+            Some(names) => names
+                .as_array()
+                .ok_or_else(|| invalid("\"builtins\" must be an array"))?
+                .iter()
+                .map(|name| {
+                    name.as_str()
+                        .ok_or_else(|| invalid("\"builtins\" must list names"))?
+                        .parse()
+                        .map_err(|e: String| invalid(&e))
+                })
+                .collect::<Result<_, _>>()?,
+            None => Sensitive::ALL.to_vec(),
+        };
+        let patterns = match config.get("patterns") {
+            Some(patterns) => patterns
+                .as_array()
+                .ok_or_else(|| invalid("\"patterns\" must be an array"))?
+                .iter()
+                .map(|entry| {
+                    let field = |key| entry.get(key).and_then(|v| v.as_str()).map(String::from);
+                    Ok(RedactPattern {
+                        pattern: field("pattern")
+                            .ok_or_else(|| invalid("each pattern needs a \"pattern\" string"))?,
+                        replacement: field("replacement"),
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self { builtins, patterns })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.builtins.is_empty() || !self.patterns.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensitive {
+    Email,
+    /// Phone numbers written with separators, e.g. `+1 555-123-4567`.
+    Phone,
+    /// Card numbers of 13 to 19 digits passing the Luhn check.
+    CreditCard,
+    /// US Social Security numbers written as `123-45-6789`.
+    Ssn,
+}
+
+impl Sensitive {
+    pub const ALL: [Self; 4] = [Self::Email, Self::Phone, Self::CreditCard, Self::Ssn];
+}
+
+impl FromStr for Sensitive {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "email" => Ok(Self::Email),
+            "phone" => Ok(Self::Phone),
+            "credit-card" | "card" => Ok(Self::CreditCard),
+            "ssn" => Ok(Self::Ssn),
+            other => Err(format!(
+                "Unknown sensitive value \"{other}\" (expected email, phone, credit-card or ssn)"
+            )),
+        }
+    }
+}
+
+/// A custom regular expression whose matches are replaced, `[REDACTED]` by
+/// default. The replacement may refer to capture groups as `$1` or `$name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactPattern {
+    pub pattern: String,
+    pub replacement: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MediaOptions {
+    /// Directory to write images and other media into; Markdown output
+    /// links to the written files.
+    pub extract_dir: Option<PathBuf>,
+    /// Maximum width or height of generated thumbnails, in pixels.
+    /// Defaults to [`DEFAULT_THUMBNAIL_SIZE`].
+    pub thumbnail_size: Option<u32>,
+    /// Inline images as base64 `data:` URIs when no directory is set.
+    pub embed: bool,
+    /// Directory the generated file is written to. Links to written media
+    /// are relative to it; without one they are the paths media was written
+    /// to.
+    pub link_base: Option<PathBuf>,
+}
+
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
+/// Options for describing images with a remote vision model. Nothing is
+/// uploaded unless an endpoint is configured.
+#[derive(Debug, Clone, Default)]
+pub struct DescribeOptions {
+    /// OpenAI-compatible chat completions endpoint URL.
+    pub endpoint: Option<String>,
+    /// Model name to request.
+    pub model: Option<String>,
+}
+
+/// Options shared by the zip and tar converters.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveOptions {
+    /// Only list entries matching at least one of these globs.
+    pub include: Vec<String>,
+    /// Skip entries matching any of these globs, e.g. `node_modules`.
+    pub exclude: Vec<String>,
+    /// Convert the entries after the listing, descending this many levels
+    /// into nested archives. 0 only lists entries.
+    pub depth: usize,
+    /// Follow the listing with the largest files and a breakdown of file
+    /// counts and sizes by extension.
+    pub summary: bool,
+    /// Entries larger than this many bytes are listed but not converted.
+    /// Defaults to [`DEFAULT_MAX_ENTRY_SIZE`].
+    pub max_entry_size: Option<u64>,
+    /// What to do with a zip that is not a recognised package format, such
+    /// as `.xps`, `.vsdx` or `.3mf`, when entries are not being converted.
+    pub zip_fallback: ZipFallback,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZipFallback {
+    /// Only list the entries.
+    #[default]
+    List,
+    /// Also convert the XML and text parts, skipping package plumbing
+    /// such as `[Content_Types].xml` and relationship files.
+    ConvertEntries,
+}
+
+pub const DEFAULT_MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct WordOptions {
+    /// Annotate section breaks with the page size and orientation of the
+    /// section that follows them.
+    pub section_notes: bool,
+    /// Markdown constructs for custom paragraph styles, consulted before the
+    /// built-in style names.
+    pub style_map: StyleMap,
+    /// How to render table cells merged across columns or rows.
+    pub merged_cells: MergedCells,
+    /// Emit the document's distinct headers once above the body and its
+    /// footers once below it.
+    pub headers_footers: bool,
+    /// How tracked insertions and deletions are rendered.
+    pub revisions: Revisions,
+    /// Append a section listing each comment with its author and the text
+    /// it is anchored to.
+    pub comments: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Revisions {
+    /// Show the document as if every tracked change were accepted.
+    #[default]
+    Accept,
+    /// Show insertions in bold and deletions struck through.
+    Markup,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergedCells {
+    /// Leave the grid cells a merged cell covers empty, so later cells
+    /// stay in their columns.
+    #[default]
+    Blank,
+    /// Repeat a merged cell's content in every grid cell it covers.
+    Repeat,
+    /// Leave covered cells empty and list the merged ranges below the table
+    /// or sheet.
+    Annotate,
+}
+
+/// Maps paragraph style ids or display names, matched case-insensitively,
+/// to the Markdown construct they should render as.
+#[derive(Debug, Clone, Default)]
+pub struct StyleMap(HashMap<String, StyleRole>);
+
+impl StyleMap {
+    /// Parse a JSON object such as `{"CompanyHeading2": "h2", "CodeSample": "code"}`.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let entries: HashMap<String, String> =
+            serde_json::from_str(json).map_err(|e| format!("Invalid style map: {e}"))?;
+        let mut map = Self::default();
+        for (style, role) in entries {
+            let role = role
+                .parse()
+                .map_err(|e| format!("Invalid style map entry for \"{style}\": {e}"))?;
+            map.insert(&style, role);
+        }
+        Ok(map)
+    }
+
+    pub fn insert(&mut self, style: &str, role: StyleRole) {
+        self.0.insert(style.to_lowercase(), role);
+    }
+
+    pub fn get(&self, style: &str) -> Option<StyleRole> {
+        self.0.get(&style.to_lowercase()).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleRole {
+    /// A heading of the given level (1-6).
+    Heading(u8),
+    BlockQuote,
+    /// A line of a fenced code block; consecutive lines share one block.
+    Code,
+    Caption,
+    ListItem,
+    /// A plain paragraph, e.g. to stop a style being treated as a heading.
+    Paragraph,
+}
+
+impl FromStr for StyleRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        let level = lower
+            .strip_prefix("heading")
+            .or_else(|| lower.strip_prefix('h'))
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| (1..=6).contains(n));
+        match (lower.as_str(), level) {
+            (_, Some(level)) => Ok(Self::Heading(level)),
+            ("quote" | "blockquote", _) => Ok(Self::BlockQuote),
+            ("code", _) => Ok(Self::Code),
+            ("caption", _) => Ok(Self::Caption),
+            ("list" | "list-item", _) => Ok(Self::ListItem),
+            ("paragraph" | "text", _) => Ok(Self::Paragraph),
+            _ => Err(format!(
+                "unknown construct \"{s}\"; expected h1-h6, quote, code, caption, list or paragraph"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PdfOptions {
+    /// User (open) password used to decrypt protected documents.
+    pub password: Option<String>,
+    /// Convert embedded attachments in a recognised format below the
+    /// attachment list, instead of only listing them.
+    pub convert_attachments: bool,
+}
+
+impl std::fmt::Debug for PdfOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PdfOptions")
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("convert_attachments", &self.convert_attachments)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PowerPointOptions {
+    /// Whether speaker notes are converted along with the slides.
+    pub notes: Notes,
+    /// How speaker notes are rendered.
+    pub notes_style: NotesStyle,
+    /// Summarize each slide's transition and animation sequence.
+    pub animations: bool,
+    /// Only convert these slides; the others are skipped without being
+    /// parsed, and the converted ones keep their numbers in the deck.
+    pub slides: Option<SlideRanges>,
+    /// List each slide's reviewer comments, with their authors, dates and
+    /// replies, below it.
+    pub comments: bool,
+    /// Write shapes in the order they are seen, top to bottom and left to
+    /// right, rather than the order they are stored in.
+    pub reading_order: bool,
+}
+
+/// 1-based slide numbers and ranges such as `1-5,10` or `12-` (to the end).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlideRanges(Vec<(usize, Option<usize>)>);
+
+impl SlideRanges {
+    pub fn contains(&self, slide: usize) -> bool {
+        self.0
+            .iter()
+            .any(|&(start, end)| slide >= start && end.is_none_or(|end| slide <= end))
+    }
+
+    /// The lowest slide number selected.
+    pub fn first(&self) -> usize {
+        self.0.iter().map(|(start, _)| *start).min().unwrap_or(1)
+    }
+}
+
+impl FromStr for SlideRanges {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |n: &str| match n.trim().parse::<usize>() {
+            Ok(0) => Err("slide numbers start at 1".to_string()),
+            Ok(n) => Ok(n),
+            Err(_) => Err(format!("invalid slide number \"{}\"", n.trim())),
+        };
+        let mut ranges = Vec::new();
+        for part in s.split(',') {
+            let range = match part.split_once('-') {
+                Some((start, end)) if end.trim().is_empty() => (number(start)?, None),
+                Some((start, end)) => {
+                    let (start, end) = (number(start)?, number(end)?);
+                    if end < start {
+                        return Err(format!("range {start}-{end} ends before it starts"));
+                    }
+                    (start, Some(end))
+                }
+                None => {
+                    let n = number(part)?;
+                    (n, Some(n))
+                }
+            };
+            ranges.push(range);
+        }
+        Ok(Self(ranges))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EpubOptions {
+    /// Show each chapter's word count and reading time below its heading.
+    pub chapter_stats: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Notes {
+    /// Each slide's notes follow its content.
+    #[default]
+    Include,
+    /// Leave speaker notes out.
+    Exclude,
+    /// Only each slide's title and notes, as plain paragraphs, e.g. for a
+    /// talk script.
+    Only,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotesStyle {
+    /// A `> **Notes**:` blockquote after the slide content.
+    #[default]
+    Blockquote,
+    /// A collapsible `<details>` block after the slide content.
+    Details,
+    /// A footnote reference on the slide, with the notes collected at the
+    /// end of the document.
+    Footnote,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExcelOptions {
+    /// Document each sheet's data-validation rules (dropdown lists, number
+    /// and date limits, custom formulas) below its content. `.xlsx` only.
+    pub data_validation: bool,
+    /// List each sheet's cell comments and notes, with their cell and
+    /// author, below its content. `.xlsx` only.
+    pub comments: bool,
+    /// Append a Mermaid graph of the sheets and tables each sheet's
+    /// formulas read from.
+    pub formula_graph: bool,
+    /// Whether formula cells show their cached values, their formulas, or
+    /// both.
+    pub formulas: Formulas,
+    /// How to render cells merged across columns or rows.
+    pub merged_cells: MergedCells,
+    /// Show numbers and dates as stored instead of applying the cells'
+    /// number formats: dates as serial numbers and percentages, currency
+    /// and fixed decimals unformatted.
+    pub raw_values: bool,
+    /// Convert hidden sheets, rows and columns too. They are left out by
+    /// default, as they usually hold scratch data or lookup tables.
+    pub include_hidden: bool,
+    /// Convert only the sheets whose names match one of these patterns,
+    /// where `*` matches any characters and case is ignored. Empty converts
+    /// every sheet.
+    pub sheets: Vec<String>,
+    /// Show at most this many rows of each table, noting how many there
+    /// are.
+    pub max_rows: Option<usize>,
+    /// Write the rows of `.xlsx` sheets as they are read, each sheet as a
+    /// single table, instead of loading whole sheets to split them into
+    /// tables and text. Sheets of other workbooks, and sheets converted
+    /// with inline or tabled formulas or repeated merged cells, are loaded
+    /// as usual.
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Formulas {
+    /// Show the value each formula last computed, as Excel displays it.
+    #[default]
+    Values,
+    /// Show the formula in place of its value.
+    Inline,
+    /// Keep the values and list each sheet's formulas, with their cells
+    /// and values, in a table below it.
+    Table,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CsvOptions {
+    /// Render each record as a column instead of a row, which suits
+    /// exports with few records but many fields.
+    pub transpose: bool,
+    /// Show at most this many columns, noting how many were left out.
+    pub max_columns: Option<usize>,
+    /// Emit one section per distinct value of this column, each with a
+    /// table of its rows.
+    pub group_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HtmlOptions {
+    /// How to render tables whose cells use `colspan` or `rowspan`.
+    pub table_spans: TableSpans,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableSpans {
+    /// Convert spanned cells like any other, which can shift later cells
+    /// into the wrong columns.
+    #[default]
+    Flatten,
+    /// Repeat a spanned cell's content in every row and column it covers.
+    Expand,
+    /// Keep tables with spanned cells as raw HTML.
+    Html,
+}
+
+/// Options shared by the JSON, YAML and TOML converters.
+#[derive(Debug, Clone, Default)]
+pub struct StructuredOptions {
+    /// Sort object keys alphabetically so output is stable regardless of
+    /// source key order.
+    pub sort_keys: bool,
+    /// Emit top-level primitive entries as YAML front matter, leaving only
+    /// nested structures in the body.
+    pub front_matter: bool,
+    /// Render GeoJSON feature collections, Docker Compose files and GitHub
+    /// Actions workflows as plain trees instead of as a features table, a
+    /// services table and a jobs outline.
+    pub generic: bool,
+    /// A JSON Schema to check documents against, reporting the result in a
+    /// section below the converted content.
+    pub schema: Option<Schema>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SqliteOptions {
+    /// Emit only the table schemas, without row counts or data.
+    pub schema_only: bool,
+    /// Emit only row data, including every row rather than a preview.
+    pub data_only: bool,
+    /// Profile each table's columns: null counts, smallest and largest
+    /// values and, for columns with few of them, distinct counts.
+    pub column_stats: bool,
+}
+
+/// Options for sending audio to a remote speech-to-text service. Nothing is
+/// uploaded unless an endpoint is configured.
+#[derive(Debug, Clone, Default)]
+pub struct TranscribeOptions {
+    /// Transcription endpoint URL, e.g.
+    /// `https://api.openai.com/v1/audio/transcriptions`.
+    pub endpoint: Option<String>,
+    /// API style of the endpoint.
+    pub provider: TranscribeProvider,
+    /// Model name to request, if the provider supports choosing one.
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TranscribeProvider {
+    /// OpenAI-compatible multipart upload returning `verbose_json`.
+    #[default]
+    OpenAi,
+    /// Deepgram-compatible raw upload returning utterances.
+    Deepgram,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VideoOptions {
+    /// Number of evenly spaced keyframes to extract into the media
+    /// directory. Requires the `ffmpeg` feature and an `ffmpeg` binary.
+    pub keyframes: usize,
+    /// The text of an SRT or WebVTT subtitle file for the video, written
+    /// as a timestamped transcript after its metadata.
+    pub subtitles: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct XmlOptions {
+    /// Only convert the elements selected by this path, e.g. `//order/item`.
+    pub xpath: Option<String>,
+    /// Attributes to leave out, by name, where `*` matches any run of
+    /// characters, e.g. `xmlns*` or `xsi:*`.
+    pub drop_attributes: Vec<String>,
+    /// Attributes, such as `name` or `id`, shown in an element's heading
+    /// instead of its attribute table. The first one an element has is used.
+    pub title_attributes: Vec<String>,
+    /// Write child elements holding only text as rows of an element/value
+    /// table under their parent instead of as headings of their own.
+    pub flatten_leaves: bool,
+}
@@ -0,0 +1,301 @@
+use std::io::Write;
+
+use regex::{Captures, Regex};
+
+use crate::converter::Converter;
+use crate::error::{Error, Result};
+use crate::options::{RedactOptions, Sensitive};
+
+/// Wraps a Markdown-producing converter and masks sensitive values in its
+/// output.
+pub struct RedactConverter {
+    inner: Box<dyn Converter>,
+    redactor: Redactor,
+}
+
+/// Wrap `inner` when redaction is enabled and it produces Markdown;
+/// otherwise return it unchanged.
+pub fn wrap(inner: Box<dyn Converter>, options: &RedactOptions) -> Result<Box<dyn Converter>> {
+    if !options.is_enabled() || inner.output_extension() != "md" {
+        return Ok(inner);
+    }
+    Ok(Box::new(RedactConverter {
+        inner,
+        redactor: Redactor::new(options)?,
+    }))
+}
+
+impl Converter for RedactConverter {
+    fn format_name(&self) -> &'static str {
+        self.inner.format_name()
+    }
+
+    fn output_extension(&self) -> &'static str {
+        self.inner.output_extension()
+    }
+
+    fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
+        let mut buf = Vec::new();
+        self.inner.convert(input, &mut buf)?;
+        let markdown = String::from_utf8(buf).map_err(|e| Error::Conversion {
+            format: self.inner.format_name(),
+            message: format!("Output is not valid UTF-8: {e}"),
+        })?;
+        writer.write_all(self.redactor.redact(&markdown).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Compiled redaction rules, applied in order: custom patterns first so
+/// their replacements win, then card numbers before the shorter digit
+/// groups of SSNs and phone numbers, then email addresses.
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    regex: Regex,
+    replacement: String,
+    /// Set for built-in kinds, whose matches are validated before masking
+    /// and whose replacement is literal.
+    kind: Option<Sensitive>,
+}
+
+impl Redactor {
+    pub fn new(options: &RedactOptions) -> Result<Self> {
+        let mut rules = Vec::new();
+        for pattern in &options.patterns {
+            let regex = Regex::new(&pattern.pattern).map_err(|e| Error::Conversion {
+                format: "redact",
+                message: format!("Invalid pattern \"{}\": {e}", pattern.pattern),
+            })?;
+            rules.push(Rule {
+                regex,
+                replacement: pattern
+                    .replacement
+                    .clone()
+                    .unwrap_or_else(|| "[REDACTED]".to_string()),
+                kind: None,
+            });
+        }
+        for kind in [
+            Sensitive::CreditCard,
+            Sensitive::Ssn,
+            Sensitive::Phone,
+            Sensitive::Email,
+        ] {
+            if options.builtins.contains(&kind) {
+                let (pattern, replacement) = builtin(kind);
+                rules.push(Rule {
+                    regex: Regex::new(pattern).expect("built-in pattern is valid"),
+                    replacement: replacement.to_string(),
+                    kind: Some(kind),
+                });
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for rule in &self.rules {
+            let redacted = match rule.kind {
+                Some(kind) => rule.regex.replace_all(&text, |caps: &Captures| {
+                    if is_valid(kind, &caps[0]) {
+                        rule.replacement.clone()
+                    } else {
+                        caps[0].to_string()
+                    }
+                }),
+                None => rule.regex.replace_all(&text, rule.replacement.as_str()),
+            };
+            text = redacted.into_owned();
+        }
+        text
+    }
+}
+
+fn builtin(kind: Sensitive) -> (&'static str, &'static str) {
+    match kind {
+        // Backslashes are allowed so Markdown-escaped underscores stay part
+        // of the address
+        Sensitive::Email => (
+            r"\b[A-Za-z0-9._%+\\-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+            "[EMAIL]",
+        ),
+        // Separators are required so plain runs of digits such as ids and
+        // amounts are left alone
+        Sensitive::Phone => (
+            r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)[ .-]?|\b\d{2,4}[ .-])\d{3,4}[ .-]\d{3,4}\b",
+            "[PHONE]",
+        ),
+        Sensitive::CreditCard => (r"\b(?:\d[ -]?){12,18}\d\b", "[CARD]"),
+        Sensitive::Ssn => (r"\b\d{3}-\d{2}-\d{4}\b", "[SSN]"),
+    }
+}
+
+/// Reject matches that have the shape of a value but cannot be one, such
+/// as digit groups failing the card checksum.
+fn is_valid(kind: Sensitive, value: &str) -> bool {
+    let digits: Vec<u32> = value.chars().filter_map(|c| c.to_digit(10)).collect();
+    match kind {
+        Sensitive::Email => true,
+        // Thousands written with spaces, e.g. "12 345 678", are too short,
+        // and groups of a longer number too long for a national number
+        Sensitive::Phone if value.starts_with('+') => (10..=15).contains(&digits.len()),
+        Sensitive::Phone => (10..=11).contains(&digits.len()),
+        Sensitive::CreditCard => luhn(&digits),
+        // Area 000, 666 and 900-999, group 00 and serial 0000 are never issued
+        Sensitive::Ssn => {
+            let area = digits[..3].iter().fold(0, |n, d| n * 10 + d);
+            area != 0
+                && area != 666
+                && area < 900
+                && digits[3..5] != [0, 0]
+                && digits[5..] != [0, 0, 0, 0]
+        }
+    }
+}
+
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::RedactPattern;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    struct Echo;
+
+    impl Converter for Echo {
+        fn format_name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
+            writer.write_all(input)?;
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[case::email("Mail jane.doe@example.co.uk today", "Mail [EMAIL] today")]
+    #[case::escaped_email(r"Mail jane\_doe@example.com", "Mail [EMAIL]")]
+    #[case::mailto_link(
+        "[jane@example.com](mailto:jane@example.com)",
+        "[[EMAIL]](mailto:[EMAIL])"
+    )]
+    #[case::phone("Call +1 555-123-4567 or (555) 123-4567.", "Call [PHONE] or [PHONE].")]
+    #[case::card("| Visa | 4111 1111 1111 1111 |", "| Visa | [CARD] |")]
+    #[case::card_without_separators("Card 4111111111111111", "Card [CARD]")]
+    #[case::card_failing_luhn("Order 4111 1111 1111 1112", "Order 4111 1111 1111 1112")]
+    #[case::ssn("SSN: 123-45-6789", "SSN: [SSN]")]
+    #[case::never_issued_ssn("Ref 000-12-3456 and 666-12-3456", "Ref 000-12-3456 and 666-12-3456")]
+    #[case::dates_and_amounts(
+        "2024-10-18, 12 345 678 units, v1.2.3, 192.168.100.200",
+        "2024-10-18, 12 345 678 units, v1.2.3, 192.168.100.200"
+    )]
+    fn test_builtins(#[case] input: &str, #[case] expected: &str) {
+        let redactor = Redactor::new(&RedactOptions::builtin()).unwrap();
+        assert_eq!(redactor.redact(input), expected);
+    }
+
+    #[rstest]
+    fn test_only_selected_builtins() {
+        let options = RedactOptions {
+            builtins: vec![Sensitive::Email],
+            patterns: Vec::new(),
+        };
+        let redactor = Redactor::new(&options).unwrap();
+        assert_eq!(
+            redactor.redact("jane@example.com, 123-45-6789"),
+            "[EMAIL], 123-45-6789"
+        );
+    }
+
+    #[rstest]
+    #[case::default_replacement(None, "Badge [REDACTED], id 7")]
+    #[case::capture_groups(Some("EMP-${1}xxxx"), "Badge EMP-12xxxx, id 7")]
+    fn test_custom_patterns(#[case] replacement: Option<&str>, #[case] expected: &str) {
+        let options = RedactOptions {
+            builtins: Vec::new(),
+            patterns: vec![RedactPattern {
+                pattern: r"EMP-(\d{2})\d{4}".to_string(),
+                replacement: replacement.map(String::from),
+            }],
+        };
+        let redactor = Redactor::new(&options).unwrap();
+        assert_eq!(redactor.redact("Badge EMP-123456, id 7"), expected);
+    }
+
+    #[rstest]
+    fn test_invalid_pattern() {
+        let options = RedactOptions {
+            builtins: Vec::new(),
+            patterns: vec![RedactPattern {
+                pattern: "(unclosed".to_string(),
+                replacement: None,
+            }],
+        };
+        assert!(matches!(
+            Redactor::new(&options),
+            Err(Error::Conversion {
+                format: "redact",
+                ..
+            })
+        ));
+    }
+
+    #[rstest]
+    fn test_wrap() {
+        let converter = wrap(Box::new(Echo), &RedactOptions::builtin()).unwrap();
+        let mut output = Vec::new();
+        converter
+            .convert(b"# Contact\n\njane@example.com\n", &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "# Contact\n\n[EMAIL]\n");
+    }
+
+    #[rstest]
+    #[case::builtins_by_default(r#"{}"#, Ok(RedactOptions::builtin()))]
+    #[case::selected(
+        r#"{"builtins": ["email", "credit-card"], "patterns": [{"pattern": "x+", "replacement": "y"}]}"#,
+        Ok(RedactOptions {
+            builtins: vec![Sensitive::Email, Sensitive::CreditCard],
+            patterns: vec![RedactPattern { pattern: "x+".to_string(), replacement: Some("y".to_string()) }],
+        })
+    )]
+    #[case::unknown_builtin(r#"{"builtins": ["iban"]}"#, Err(()))]
+    #[case::unknown_field(r#"{"builtin": ["email"]}"#, Err(()))]
+    #[case::missing_pattern(r#"{"patterns": [{"replacement": "y"}]}"#, Err(()))]
+    fn test_options_from_json(
+        #[case] json: &str,
+        #[case] expected: std::result::Result<RedactOptions, ()>,
+    ) {
+        let parsed = RedactOptions::from_json(json);
+        match expected {
+            Ok(expected) => {
+                let parsed = parsed.unwrap();
+                assert_eq!(parsed.builtins, expected.builtins);
+                assert_eq!(parsed.patterns, expected.patterns);
+            }
+            Err(()) => assert!(parsed.is_err()),
+        }
+    }
+}
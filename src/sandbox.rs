@@ -0,0 +1,468 @@
+//! Converting untrusted input in a restricted child process.
+//!
+//! With `--sandbox` the CLI re-runs itself with the same arguments and
+//! [`CHILD_ENV`] set. The child reads the input from stdin, calls
+//! [`restrict`] before parsing it, and writes the Markdown to stdout, so a
+//! parser bug can at worst take down the child.
+
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::converter::Converter;
+use crate::error::{Error, Result};
+use crate::warnings;
+
+/// Set in the environment of the child process.
+pub const CHILD_ENV: &str = "MQ_CONV_SANDBOX_CHILD";
+/// File name of the input, for format detection in the child.
+pub const INPUT_NAME_ENV: &str = "MQ_CONV_SANDBOX_INPUT_NAME";
+/// Directory the output is written to, which media links are relative to.
+pub const LINK_BASE_ENV: &str = "MQ_CONV_SANDBOX_LINK_BASE";
+
+/// Default for `--sandbox-memory`, in MiB.
+pub const DEFAULT_MEMORY_MIB: u64 = 1024;
+/// Default for `--sandbox-cpu`, in seconds.
+pub const DEFAULT_CPU_SECONDS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct SandboxOptions {
+    /// Address space limit of the child, in MiB.
+    pub memory_mib: u64,
+    /// CPU time limit of the child, in seconds.
+    pub cpu_seconds: u64,
+    /// Also install a seccomp filter that denies sockets other than Unix
+    /// sockets, starting programs and other calls a converter never needs.
+    pub seccomp: bool,
+}
+
+impl Default for SandboxOptions {
+    fn default() -> Self {
+        Self {
+            memory_mib: DEFAULT_MEMORY_MIB,
+            cpu_seconds: DEFAULT_CPU_SECONDS,
+            seccomp: false,
+        }
+    }
+}
+
+/// Runs a conversion in a child process instead of in-process. The wrapped
+/// converter only supplies the format name and output extension.
+pub struct SandboxedConverter {
+    inner: Box<dyn Converter>,
+    program: PathBuf,
+    args: Vec<OsString>,
+    input_name: Option<String>,
+    link_base: Option<PathBuf>,
+}
+
+impl SandboxedConverter {
+    /// Convert by running `program` with `args`, which have to make it
+    /// choose the same converter as `inner` for `input_name`. Media links
+    /// are made relative to `link_base`, as they are in-process.
+    pub fn new(
+        inner: Box<dyn Converter>,
+        program: PathBuf,
+        args: Vec<OsString>,
+        input_name: Option<String>,
+        link_base: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            inner,
+            program,
+            args,
+            input_name,
+            link_base,
+        }
+    }
+}
+
+impl Converter for SandboxedConverter {
+    fn format_name(&self) -> &'static str {
+        self.inner.format_name()
+    }
+
+    fn output_extension(&self) -> &'static str {
+        self.inner.output_extension()
+    }
+
+    fn convert(&self, input: &[u8], writer: &mut dyn Write) -> Result<()> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .env(CHILD_ENV, "1")
+            .env_remove(INPUT_NAME_ENV)
+            .env_remove(LINK_BASE_ENV)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(name) = &self.input_name {
+            command.env(INPUT_NAME_ENV, name);
+        }
+        if let Some(dir) = &self.link_base {
+            command.env(LINK_BASE_ENV, dir);
+        }
+        let mut child = command.spawn()?;
+
+        // Feed stdin from another thread so a child that writes a lot
+        // before reading everything cannot deadlock us
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let output = std::thread::scope(|scope| {
+            scope.spawn(move || {
+                // A child that exits early closes the pipe; its exit status
+                // says why
+                let _ = stdin.write_all(input);
+            });
+            child.wait_with_output()
+        })?;
+
+        let failure = read_stderr(&String::from_utf8_lossy(&output.stderr));
+        if output.status.success() {
+            writer.write_all(&output.stdout)?;
+            return Ok(());
+        }
+        let message = match failure {
+            // Drop the child's own prefix, which this error adds back
+            Some(message) => {
+                let prefix = format!("Conversion error ({}): ", self.format_name());
+                message
+                    .strip_prefix(&prefix)
+                    .unwrap_or(&message)
+                    .to_string()
+            }
+            None => match exit_signal(&output.status) {
+                Some(signal) => format!("sandboxed conversion was killed by signal {signal}"),
+                None => format!("sandboxed conversion failed with {}", output.status),
+            },
+        };
+        Err(Error::Conversion {
+            format: self.format_name(),
+            message,
+        })
+    }
+}
+
+/// Pass the child's warnings on, returning the message of the error it
+/// reported, if any.
+fn read_stderr(stderr: &str) -> Option<String> {
+    let mut error = None;
+    let mut other = Vec::new();
+    for line in stderr.lines() {
+        if let Some(warning) = line.strip_prefix("warning: ") {
+            warnings::warn(warning);
+        } else if let Ok(serde_json::Value::Object(json)) = serde_json::from_str(line)
+            && let Some(message) = json.get("message").and_then(|m| m.as_str())
+        {
+            error = Some(message.to_string());
+        } else if !line.trim().is_empty() {
+            other.push(line);
+        }
+    }
+    // Anything else, such as a panic message, is better than nothing
+    error.or_else(|| (!other.is_empty()).then(|| other.join("\n")))
+}
+
+#[cfg(unix)]
+fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Restrict the current process before it parses untrusted input: limit
+/// its memory and CPU time, disable core dumps and cut it off from the
+/// network, optionally with a seccomp filter on top.
+///
+/// The network is isolated with a new network namespace. Where that is not
+/// allowed, such as in many containers, the seccomp filter is required and
+/// does the job instead by denying Internet sockets.
+#[cfg(target_os = "linux")]
+pub fn restrict(options: &SandboxOptions) -> Result<()> {
+    let limits = [
+        (libc::RLIMIT_CORE, 0),
+        (
+            libc::RLIMIT_AS,
+            options.memory_mib.saturating_mul(1024 * 1024),
+        ),
+        (libc::RLIMIT_CPU, options.cpu_seconds),
+    ];
+    for (resource, value) in limits {
+        let limit = libc::rlimit {
+            rlim_cur: value,
+            rlim_max: value,
+        };
+        // SAFETY: `limit` is a valid rlimit for the duration of the call
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(error(format!(
+                "could not set resource limit: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+    }
+
+    // SAFETY: unshare only changes the namespaces of the calling process
+    let isolated = unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } == 0;
+    if !isolated && !options.seccomp {
+        return Err(error(format!(
+            "could not isolate the network ({}); pass --sandbox-seccomp to deny it with a seccomp filter instead",
+            std::io::Error::last_os_error()
+        )));
+    }
+    if options.seccomp {
+        seccomp::install()?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn restrict(_options: &SandboxOptions) -> Result<()> {
+    Err(error("sandboxing is only supported on Linux".to_string()))
+}
+
+#[cfg(target_os = "linux")]
+mod seccomp {
+    use super::error;
+    use crate::error::Result;
+
+    // Not exported by libc
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc000_00b7;
+    /// Set in the numbers of x32 calls, which share x86_64's `AUDIT_ARCH`.
+    #[cfg(target_arch = "x86_64")]
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    // Offsets into `struct seccomp_data`
+    const NR: u32 = 0;
+    const ARCH: u32 = 4;
+    const FIRST_ARG: u32 = 16;
+
+    const ALLOW: u32 = libc::SECCOMP_RET_ALLOW;
+    const DENY: u32 = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+
+    /// Calls denied outright: starting programs, inspecting or changing
+    /// other processes, namespaces and mounts, and kernel interfaces with a
+    /// history of privilege escalation.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    const DENIED: &[libc::c_long] = &[
+        libc::SYS_execve,
+        libc::SYS_execveat,
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_unshare,
+        libc::SYS_setns,
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+        libc::SYS_userfaultfd,
+        libc::SYS_io_uring_setup,
+        libc::SYS_keyctl,
+        libc::SYS_add_key,
+        libc::SYS_request_key,
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_kexec_load,
+        libc::SYS_reboot,
+        libc::SYS_swapon,
+    ];
+
+    fn statement(code: u32, k: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    fn jump(k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter {
+            code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn jump_at_least(k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter {
+            code: (libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K) as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    /// The filter program: kill the process for calls made under another
+    /// architecture's numbering, including x32's on x86_64, deny the calls
+    /// in [`DENIED`], allow `socket` only for Unix sockets and allow
+    /// everything else.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn program() -> Vec<libc::sock_filter> {
+        let load = |offset| statement(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset);
+        let ret = |action| statement(libc::BPF_RET | libc::BPF_K, action);
+
+        let mut program = vec![
+            load(ARCH),
+            jump(AUDIT_ARCH, 1, 0),
+            ret(libc::SECCOMP_RET_KILL_PROCESS),
+            load(NR),
+        ];
+        // x32 numbers would otherwise slip past the checks below
+        #[cfg(target_arch = "x86_64")]
+        program.extend([
+            jump_at_least(X32_SYSCALL_BIT, 0, 1),
+            ret(libc::SECCOMP_RET_KILL_PROCESS),
+        ]);
+        for &nr in DENIED {
+            program.push(jump(nr as u32, 0, 1));
+            program.push(ret(DENY));
+        }
+        program.extend([
+            jump(libc::SYS_socket as u32, 0, 4),
+            load(FIRST_ARG),
+            jump(libc::AF_UNIX as u32, 0, 1),
+            ret(ALLOW),
+            ret(DENY),
+            ret(ALLOW),
+        ]);
+        program
+    }
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    pub(super) fn install() -> Result<()> {
+        let program = program();
+        let fprog = libc::sock_fprog {
+            len: program.len() as u16,
+            filter: program.as_ptr() as *mut libc::sock_filter,
+        };
+        // prctl reads every argument as an unsigned long, so none may be
+        // passed as a narrower integer
+        let (zero, one): (libc::c_ulong, libc::c_ulong) = (0, 1);
+        let mode = libc::SECCOMP_MODE_FILTER as libc::c_ulong;
+        // SAFETY: `fprog` points at `program`, which outlives both calls;
+        // no_new_privs is required to install a filter without privileges
+        let installed = unsafe {
+            libc::prctl(libc::PR_SET_NO_NEW_PRIVS, one, zero, zero, zero) == 0
+                && libc::prctl(libc::PR_SET_SECCOMP, mode, &fprog, zero, zero) == 0
+        };
+        if !installed {
+            return Err(error(format!(
+                "could not install the seccomp filter: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    pub(super) fn install() -> Result<()> {
+        Err(error(
+            "seccomp filtering is not supported on this architecture".to_string(),
+        ))
+    }
+
+    #[cfg(all(test, any(target_arch = "x86_64", target_arch = "aarch64")))]
+    mod tests {
+        use super::*;
+        use pretty_assertions::assert_eq;
+        use rstest::rstest;
+
+        /// Run the filter program against a call, as the kernel would.
+        fn run(arch: u32, nr: u32, first_arg: u32) -> u32 {
+            let program = program();
+            let mut acc = 0;
+            let mut pc = 0;
+            loop {
+                let insn = &program[pc];
+                pc += 1;
+                match insn.code as u32 {
+                    c if c == libc::BPF_LD | libc::BPF_W | libc::BPF_ABS => {
+                        acc = match insn.k {
+                            NR => nr,
+                            ARCH => arch,
+                            FIRST_ARG => first_arg,
+                            k => panic!("unexpected offset {k}"),
+                        }
+                    }
+                    c if c == libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K => {
+                        pc += if acc == insn.k { insn.jt } else { insn.jf } as usize;
+                    }
+                    c if c == libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K => {
+                        pc += if acc >= insn.k { insn.jt } else { insn.jf } as usize;
+                    }
+                    c if c == libc::BPF_RET | libc::BPF_K => return insn.k,
+                    c => panic!("unexpected instruction {c:#x}"),
+                }
+            }
+        }
+
+        #[rstest]
+        #[case::read(libc::SYS_read, 0, ALLOW)]
+        #[case::execve(libc::SYS_execve, 0, DENY)]
+        #[case::swapon(libc::SYS_swapon, 0, DENY)]
+        #[case::unix_socket(libc::SYS_socket, libc::AF_UNIX as u32, ALLOW)]
+        #[case::inet_socket(libc::SYS_socket, libc::AF_INET as u32, DENY)]
+        #[case::inet6_socket(libc::SYS_socket, libc::AF_INET6 as u32, DENY)]
+        fn test_filter(#[case] nr: libc::c_long, #[case] first_arg: u32, #[case] expected: u32) {
+            assert_eq!(run(AUDIT_ARCH, nr as u32, first_arg), expected);
+        }
+
+        #[rstest]
+        fn test_filter_kills_other_architectures() {
+            assert_eq!(
+                run(0x4000_0003, libc::SYS_read as u32, 0),
+                libc::SECCOMP_RET_KILL_PROCESS
+            );
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        #[rstest]
+        // x32 execve is 520; socket shares the x86_64 number
+        #[case::execve(520, 0)]
+        #[case::inet_socket(libc::SYS_socket as u32, libc::AF_INET as u32)]
+        fn test_filter_kills_x32_calls(#[case] nr: u32, #[case] first_arg: u32) {
+            assert_eq!(
+                run(AUDIT_ARCH, X32_SYSCALL_BIT | nr, first_arg),
+                libc::SECCOMP_RET_KILL_PROCESS
+            );
+        }
+    }
+}
+
+fn error(message: String) -> Error {
+    Error::Conversion {
+        format: "sandbox",
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::error_json(
+        "warning: Slide 2 skipped: bad\n{\"code\":\"parse_error\",\"message\":\"Conversion error (xml): At byte 3: bad\"}\n",
+        Some("Conversion error (xml): At byte 3: bad")
+    )]
+    #[case::panic(
+        "thread 'main' panicked at src/formats/pdf.rs:10:5:\noverflow\n",
+        Some("thread 'main' panicked at src/formats/pdf.rs:10:5:\noverflow")
+    )]
+    #[case::only_warnings("warning: Image missing\n", None)]
+    fn test_read_stderr(#[case] stderr: &str, #[case] expected: Option<&str>) {
+        assert_eq!(read_stderr(stderr).as_deref(), expected);
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use crate::formats::anchors::Slugger;
+
+/// A converted document divided at headings of one level.
+#[derive(Debug, PartialEq)]
+pub struct Split {
+    /// Content before the first heading, including any front matter.
+    pub preamble: String,
+    pub sections: Vec<Section>,
+}
+
+/// One heading and everything up to the next heading of the same or a
+/// higher level.
+#[derive(Debug, PartialEq)]
+pub struct Section {
+    pub title: String,
+    pub markdown: String,
+}
+
+impl Section {
+    /// File name for the section at `index` (0-based) of `count`, numbered so
+    /// the files sort in document order.
+    pub fn file_name(&self, index: usize, count: usize) -> String {
+        let width = count.to_string().len().max(2);
+        format!("{:0width$}-{}.md", index + 1, slug(&self.title))
+    }
+
+    /// File name `<prefix>-<title>.md` for a section written as a standalone
+    /// file beside the document's own output.
+    pub fn prefixed_file_name(&self, prefix: &str) -> String {
+        format!("{prefix}-{}.md", slug(&self.title))
+    }
+}
+
+impl Split {
+    /// Point the `#anchor` links of the document at the section file the
+    /// heading they name ended up in, given the file name of each section.
+    /// Links within one section keep their bare fragment, and `dir` is the
+    /// directory of the section files as seen from the preamble.
+    pub fn link_sections(&mut self, file_names: &[String], dir: &str) {
+        // Anchors are slugged over the whole document, so repeated headings
+        // get the same numbered suffixes as before the split
+        let mut slugger = Slugger::default();
+        let mut anchors: HashMap<String, usize> = HashMap::new();
+        for (index, text) in std::iter::once(&self.preamble)
+            .chain(self.sections.iter().map(|section| &section.markdown))
+            .enumerate()
+        {
+            for line in outside_code(text) {
+                if let Some((_, title)) = heading(line) {
+                    anchors.entry(slugger.slug(title)).or_insert(index);
+                }
+            }
+        }
+
+        let dir = dir.replace(' ', "%20");
+        let target = |from: usize, anchor: &str| {
+            let to = *anchors.get(anchor)?;
+            let file = file_names.get(to.checked_sub(1)?)?;
+            match from {
+                _ if from == to => None,
+                0 if !dir.is_empty() => Some(format!("{dir}/{file}#{anchor}")),
+                _ => Some(format!("{file}#{anchor}")),
+            }
+        };
+        self.preamble = rewrite_fragments(&self.preamble, |anchor| target(0, anchor));
+        for (index, section) in self.sections.iter_mut().enumerate() {
+            section.markdown =
+                rewrite_fragments(&section.markdown, |anchor| target(index + 1, anchor));
+        }
+    }
+}
+
+/// The lines of `text` outside fenced code blocks.
+fn outside_code(text: &str) -> impl Iterator<Item = &str> {
+    let mut in_code = false;
+    text.lines().filter(move |line| {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            return false;
+        }
+        !in_code
+    })
+}
+
+/// Replace the targets of `](#anchor)` links outside code blocks with what
+/// `target` returns for their anchor, if anything.
+fn rewrite_fragments(text: &str, target: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_code = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        }
+        if in_code {
+            out.push_str(line);
+            continue;
+        }
+        let mut rest = line;
+        while let Some(start) = rest.find("](#") {
+            let (before, link) = rest.split_at(start + 2);
+            out.push_str(before);
+            let end = link
+                .find(|c: char| c == ')' || c.is_whitespace())
+                .unwrap_or(link.len());
+            match target(&link[1..end]) {
+                Some(resolved) => out.push_str(&resolved),
+                None => out.push_str(&link[..end]),
+            }
+            rest = &link[end..];
+        }
+        out.push_str(rest);
+    }
+    out
+}
+
+/// Split `markdown` at ATX headings of `level` (1-6) or higher, ignoring
+/// headings inside fenced code blocks.
+pub fn split(markdown: &str, level: usize) -> Split {
+    split_by(markdown, |line| {
+        heading(line)
+            .filter(|(depth, _)| *depth <= level)
+            .map(|(_, title)| title.to_string())
+    })
+}
+
+/// Split converted PDF output at its page markers (`## Page N`, or
+/// `<!-- Page N -->` when the outline provides the headings), dropping the
+/// rules between pages.
+pub fn split_pages(markdown: &str) -> Split {
+    let mut result = split_by(markdown, |line| {
+        let line = line.trim_end();
+        page_comment(line)
+            .or_else(|| line.strip_prefix("## ").filter(|title| is_page_title(title)))
+            .map(str::to_string)
+    });
+    strip_page_breaks(&mut result);
+    result
+}
+
+/// Split converted PDF output at its top-level outline entries, which are
+/// rendered as level-2 headings below the document title. Without an outline
+/// this gives one section per page.
+pub fn split_chapters(markdown: &str) -> Split {
+    let mut result = split_by(markdown, |line| {
+        heading(line)
+            .filter(|(depth, _)| *depth == 2)
+            .map(|(_, title)| title.to_string())
+    });
+    strip_page_breaks(&mut result);
+    result
+}
+
+/// Start a new section at every line outside code blocks for which
+/// `boundary` returns a title.
+fn split_by(markdown: &str, boundary: impl Fn(&str) -> Option<String>) -> Split {
+    let mut result = Split {
+        preamble: String::new(),
+        sections: Vec::new(),
+    };
+    let mut in_code = false;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        }
+        if !in_code && let Some(title) = boundary(line) {
+            result.sections.push(Section {
+                title,
+                markdown: String::new(),
+            });
+        }
+        match result.sections.last_mut() {
+            Some(section) => section.markdown.push_str(line),
+            None => result.preamble.push_str(line),
+        }
+    }
+
+    for section in &mut result.sections {
+        let trimmed = section.markdown.trim_end().len();
+        section.markdown.truncate(trimmed);
+        section.markdown.push('\n');
+    }
+    result
+}
+
+/// Remove the page break rules and markers left at the end of each section
+/// when the next one starts a new page.
+fn strip_page_breaks(split: &mut Split) {
+    for section in &mut split.sections {
+        let mut lines: Vec<&str> = section.markdown.lines().collect();
+        while let Some(last) = lines.last() {
+            let last = last.trim();
+            if last.is_empty() || last == "---" || page_comment(last).is_some() {
+                lines.pop();
+            } else {
+                break;
+            }
+        }
+        section.markdown = lines.join("\n") + "\n";
+    }
+}
+
+/// The `Page N` in a `<!-- Page N -->` marker.
+fn page_comment(line: &str) -> Option<&str> {
+    line.strip_prefix("<!-- ")
+        .and_then(|rest| rest.strip_suffix(" -->"))
+        .filter(|marker| is_page_title(marker))
+}
+
+fn is_page_title(text: &str) -> bool {
+    text.strip_prefix("Page ")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The index page: the preamble followed by a numbered list linking each
+/// section's file inside `dir`.
+pub fn index(split: &Split, dir: &str) -> String {
+    let mut out = split.preamble.trim_end().to_string();
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str("## Contents\n\n");
+    let count = split.sections.len();
+    for (i, section) in split.sections.iter().enumerate() {
+        let target = format!("{dir}/{}", section.file_name(i, count)).replace(' ', "%20");
+        out.push_str(&format!("{}. [{}]({target})\n", i + 1, section.title));
+    }
+    out
+}
+
+/// Depth and text of an ATX heading line.
+pub(crate) fn heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_end();
+    let depth = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&depth) {
+        return None;
+    }
+    let rest = &line[depth..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    // A closing run of `#` only counts when separated by a space
+    let text = rest.trim();
+    let stripped = text.trim_end_matches('#');
+    if stripped.is_empty() || stripped.ends_with(' ') {
+        Some((depth, stripped.trim_end()))
+    } else {
+        Some((depth, text))
+    }
+}
+
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_split_at_level() {
+        let markdown = "---\ntitle: Book\n---\n\nIntro.\n\n\
+            # Sheet: Sales\n\n| a |\n|---|\n\n## Totals\n\n\
+            ```\n# not a heading\n```\n\n\
+            # Sheet: Costs\n\nNone.\n";
+        let result = split(markdown, 1);
+        assert_eq!(result.preamble, "---\ntitle: Book\n---\n\nIntro.\n\n");
+        assert_eq!(
+            result.sections,
+            vec![
+                Section {
+                    title: "Sheet: Sales".into(),
+                    markdown: "# Sheet: Sales\n\n| a |\n|---|\n\n## Totals\n\n```\n# not a heading\n```\n".into(),
+                },
+                Section {
+                    title: "Sheet: Costs".into(),
+                    markdown: "# Sheet: Costs\n\nNone.\n".into(),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_split_pages() {
+        let markdown = "# Report\n\n<!-- Page 1 -->\n\n## Intro\n\nHello.\n\n\n---\n\n\
+            <!-- Page 2 -->\n\nMore.\n\n## Page 2 of the plan\n\nDone.\n";
+        let result = split_pages(markdown);
+        assert_eq!(result.preamble, "# Report\n\n");
+        assert_eq!(
+            result.sections,
+            vec![
+                Section {
+                    title: "Page 1".into(),
+                    markdown: "<!-- Page 1 -->\n\n## Intro\n\nHello.\n".into(),
+                },
+                Section {
+                    title: "Page 2".into(),
+                    markdown: "<!-- Page 2 -->\n\nMore.\n\n## Page 2 of the plan\n\nDone.\n".into(),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_split_chapters() {
+        let markdown = "# Report\n\n<!-- Page 1 -->\n\n## Intro\n\nHello.\n\n### Detail\n\nMore.\n\n\n---\n\n\
+            <!-- Page 2 -->\n\n## Results\n\nDone.\n";
+        let result = split_chapters(markdown);
+        assert_eq!(result.preamble, "# Report\n\n<!-- Page 1 -->\n\n");
+        assert_eq!(
+            result.sections,
+            vec![
+                Section {
+                    title: "Intro".into(),
+                    markdown: "## Intro\n\nHello.\n\n### Detail\n\nMore.\n".into(),
+                },
+                Section {
+                    title: "Results".into(),
+                    markdown: "## Results\n\nDone.\n".into(),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_index() {
+        let result = split("Intro.\n\n# Slide 1\n\nA\n\n# Q&A!\n\nB\n", 1);
+        assert_eq!(
+            index(&result, "deck"),
+            "Intro.\n\n## Contents\n\n1. [Slide 1](deck/01-slide-1.md)\n2. [Q&A!](deck/02-q-a.md)\n"
+        );
+    }
+
+    #[rstest]
+    fn test_link_sections() {
+        let mut result = split(
+            "See [two](#two).\n\n# One\n\n[Two](#two), [here](#one) and [gone](#gone).\n\n\
+             ```\n[two](#two)\n```\n\n# Two\n\n## Notes\n\n# Notes\n\n[back](#one \"One\") [notes](#notes-1)\n",
+            1,
+        );
+        let names: Vec<String> = (0..result.sections.len())
+            .map(|i| result.sections[i].file_name(i, result.sections.len()))
+            .collect();
+        result.link_sections(&names, "doc");
+        assert_eq!(result.preamble, "See [two](doc/02-two.md#two).\n\n");
+        assert_eq!(
+            result.sections[0].markdown,
+            "# One\n\n[Two](02-two.md#two), [here](#one) and [gone](#gone).\n\n```\n[two](#two)\n```\n"
+        );
+        assert_eq!(
+            result.sections[2].markdown,
+            "# Notes\n\n[back](01-one.md#one \"One\") [notes](#notes-1)\n"
+        );
+    }
+
+    #[rstest]
+    fn test_prefixed_file_name() {
+        let result = split("# Q1 Sales\n\nA\n\n# Notes & Todo\n\nB\n", 1);
+        let names: Vec<String> = result
+            .sections
+            .iter()
+            .map(|section| section.prefixed_file_name("budget"))
+            .collect();
+        assert_eq!(names, ["budget-q1-sales.md", "budget-notes-todo.md"]);
+    }
+
+    #[rstest]
+    #[case::plain("# Title", Some((1, "Title")))]
+    #[case::closing_hashes("## Title ##", Some((2, "Title")))]
+    #[case::trailing_hash("# C#", Some((1, "C#")))]
+    #[case::no_space("#hashtag", None)]
+    #[case::too_deep("####### Seven", None)]
+    fn test_heading(#[case] line: &str, #[case] expected: Option<(usize, &str)>) {
+        assert_eq!(heading(line), expected);
+    }
+
+    #[rstest]
+    #[case::ascii("Chapter 1: The Start", "chapter-1-the-start")]
+    #[case::unicode("Überblick", "überblick")]
+    #[case::symbols("***", "section")]
+    fn test_slug(#[case] title: &str, #[case] expected: &str) {
+        assert_eq!(slug(title), expected);
+    }
+}
@@ -0,0 +1,283 @@
+use std::io::Write;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::options::{TranscribeOptions, TranscribeProvider};
+
+/// Environment variable holding the API key sent to the transcription endpoint.
+pub const API_KEY_ENV: &str = "MQ_CONV_TRANSCRIBE_API_KEY";
+
+/// Default model requested from OpenAI-compatible endpoints.
+const DEFAULT_OPENAI_MODEL: &str = "whisper-1";
+
+/// Uploads can be long recordings, so allow generous time for a response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// A span of transcribed speech.
+#[derive(Debug, PartialEq)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Send `input` to the configured endpoint and write the returned transcript
+/// as a `## Transcript` section with one timestamped paragraph per segment.
+pub fn write_transcript(
+    options: &TranscribeOptions,
+    input: &[u8],
+    file_name: &str,
+    format: &'static str,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let Some(endpoint) = &options.endpoint else {
+        return Ok(());
+    };
+
+    let segments =
+        transcribe(options, endpoint, input, file_name).map_err(|message| Error::Conversion {
+            format,
+            message: format!("Transcription failed: {message}"),
+        })?;
+
+    writeln!(writer, "## Transcript")?;
+    writeln!(writer)?;
+    if segments.is_empty() {
+        writeln!(writer, "*No speech detected*")?;
+        return Ok(());
+    }
+    for segment in &segments {
+        writeln!(
+            writer,
+            "**[{} – {}]** {}",
+            format_time(segment.start),
+            format_time(segment.end),
+            segment.text.trim()
+        )?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+fn transcribe(
+    options: &TranscribeOptions,
+    endpoint: &str,
+    input: &[u8],
+    file_name: &str,
+) -> std::result::Result<Vec<Segment>, String> {
+    let api_key = std::env::var(API_KEY_ENV).ok();
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .into();
+
+    let request = agent.post(endpoint);
+    let response = match options.provider {
+        TranscribeProvider::OpenAi => {
+            let model = options.model.as_deref().unwrap_or(DEFAULT_OPENAI_MODEL);
+            let boundary = format!("mq-conv-{:x}", std::process::id());
+            let body = multipart_body(&boundary, model, file_name, input);
+            let request = request.content_type(format!("multipart/form-data; boundary={boundary}"));
+            match &api_key {
+                Some(key) => request.header("Authorization", format!("Bearer {key}")),
+                None => request,
+            }
+            .send(&body[..])
+        }
+        TranscribeProvider::Deepgram => {
+            let request = request
+                .query("utterances", "true")
+                .query("punctuate", "true")
+                .content_type("application/octet-stream");
+            let request = match &options.model {
+                Some(model) => request.query("model", model),
+                None => request,
+            };
+            match &api_key {
+                Some(key) => request.header("Authorization", format!("Token {key}")),
+                None => request,
+            }
+            .send(input)
+        }
+    };
+
+    let mut response = response.map_err(|e| e.to_string())?;
+    let status = response.status();
+    let body = response
+        .body_mut()
+        .with_config()
+        .limit(64 * 1024 * 1024)
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{endpoint} returned {status}: {}", body.trim()));
+    }
+
+    let json: Value = serde_json::from_str(&body).map_err(|e| format!("Invalid response: {e}"))?;
+    Ok(match options.provider {
+        TranscribeProvider::OpenAi => parse_openai(&json),
+        TranscribeProvider::Deepgram => parse_deepgram(&json),
+    })
+}
+
+/// Build the `multipart/form-data` body for an OpenAI-style upload, asking
+/// for segment timestamps.
+fn multipart_body(boundary: &str, model: &str, file_name: &str, input: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(input.len() + 512);
+    for (name, value) in [
+        ("model", model),
+        ("response_format", "verbose_json"),
+        ("timestamp_granularities[]", "segment"),
+    ] {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    let file_name = file_name.replace('"', "");
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(input);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    body
+}
+
+/// `{"text": ..., "segments": [{"start", "end", "text"}]}`; responses without
+/// segments become a single untimed segment.
+fn parse_openai(json: &Value) -> Vec<Segment> {
+    let segments: Vec<Segment> = json["segments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| segment(s, "text"))
+        .collect();
+    if !segments.is_empty() {
+        return segments;
+    }
+    whole_text(json["text"].as_str(), json["duration"].as_f64())
+}
+
+/// `{"results": {"utterances": [{"start", "end", "transcript"}]}}`, falling
+/// back to the first channel's best alternative.
+fn parse_deepgram(json: &Value) -> Vec<Segment> {
+    let segments: Vec<Segment> = json["results"]["utterances"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| segment(s, "transcript"))
+        .collect();
+    if !segments.is_empty() {
+        return segments;
+    }
+    whole_text(
+        json["results"]["channels"][0]["alternatives"][0]["transcript"].as_str(),
+        json["metadata"]["duration"].as_f64(),
+    )
+}
+
+fn segment(value: &Value, text_key: &str) -> Option<Segment> {
+    let text = value[text_key].as_str()?.trim();
+    (!text.is_empty()).then(|| Segment {
+        start: value["start"].as_f64().unwrap_or(0.0),
+        end: value["end"].as_f64().unwrap_or(0.0),
+        text: text.to_string(),
+    })
+}
+
+fn whole_text(text: Option<&str>, duration: Option<f64>) -> Vec<Segment> {
+    text.map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| Segment {
+            start: 0.0,
+            end: duration.unwrap_or(0.0),
+            text: t.to_string(),
+        })
+        .into_iter()
+        .collect()
+}
+
+fn format_time(seconds: f64) -> String {
+    let total_secs = seconds.max(0.0) as u64;
+    let hours = total_secs / 3600;
+    let mins = (total_secs % 3600) / 60;
+    let secs = total_secs % 60;
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}")
+    } else {
+        format!("{mins}:{secs:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn seg(start: f64, end: f64, text: &str) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    #[rstest]
+    #[case::segments(
+        r#"{"text": "Hello there. Bye.", "segments": [
+            {"start": 0.0, "end": 2.5, "text": " Hello there."},
+            {"start": 2.5, "end": 4.0, "text": " Bye."}
+        ]}"#,
+        vec![seg(0.0, 2.5, "Hello there."), seg(2.5, 4.0, "Bye.")]
+    )]
+    #[case::text_only(r#"{"text": "Hello", "duration": 3.0}"#, vec![seg(0.0, 3.0, "Hello")])]
+    #[case::empty(r#"{"text": ""}"#, vec![])]
+    fn test_parse_openai(#[case] json: &str, #[case] expected: Vec<Segment>) {
+        assert_eq!(parse_openai(&serde_json::from_str(json).unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case::utterances(
+        r#"{"results": {"utterances": [{"start": 1.0, "end": 65.0, "transcript": "Hi"}]}}"#,
+        vec![seg(1.0, 65.0, "Hi")]
+    )]
+    #[case::channel_fallback(
+        r#"{"metadata": {"duration": 9.5}, "results": {"channels": [{"alternatives": [{"transcript": "Hi all"}]}]}}"#,
+        vec![seg(0.0, 9.5, "Hi all")]
+    )]
+    fn test_parse_deepgram(#[case] json: &str, #[case] expected: Vec<Segment>) {
+        assert_eq!(
+            parse_deepgram(&serde_json::from_str(json).unwrap()),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_multipart_body() {
+        let body = multipart_body("b", "whisper-1", "talk.mp3", b"DATA");
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with(
+            "--b\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n"
+        ));
+        assert!(body.contains("name=\"file\"; filename=\"talk.mp3\"\r\n"));
+        assert!(body.ends_with("\r\n\r\nDATA\r\n--b--\r\n"));
+    }
+
+    #[rstest]
+    #[case::seconds(5.9, "0:05")]
+    #[case::hours(3725.0, "1:02:05")]
+    fn test_format_time(#[case] seconds: f64, #[case] expected: &str) {
+        assert_eq!(format_time(seconds), expected);
+    }
+}
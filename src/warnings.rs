@@ -0,0 +1,27 @@
+use std::sync::Mutex;
+
+use crate::error::Error;
+
+/// Problems converters worked around without failing, e.g. an archive entry
+/// that could not be converted, waiting to be reported by the caller.
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Record a non-fatal problem with the current conversion.
+pub fn warn(message: impl Into<String>) {
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(message.into());
+}
+
+/// Record that `section` of a document was skipped because of `error`, and
+/// return the note that takes its place in the output under `--best-effort`.
+pub fn skipped_section(section: &str, error: &Error) -> String {
+    warn(format!("{section} skipped: {error}"));
+    format!("> **Conversion warning**: {section} could not be converted: {error}")
+}
+
+/// Take the warnings recorded since the last call.
+pub fn take() -> Vec<String> {
+    std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()))
+}